@@ -0,0 +1,222 @@
+package main
+
+import (
+	"context"
+	"fmt"
+	"log/slog"
+	"os"
+	"strings"
+	"time"
+
+	"github.com/spf13/cobra"
+	"github.com/weiihann/statoor/harness"
+	"github.com/weiihann/statoor/workload"
+)
+
+type bisectConfig struct {
+	workloadPath string
+	clients      []string
+	dbDir        string
+	harnessesDir string
+	skipBuild    bool
+	maxAccounts  int
+	output       string
+	selector     workload.Selector
+}
+
+func newBisectCmd(logger *slog.Logger) *cobra.Command {
+	var (
+		cfg   bisectConfig
+		flags selectorFlags
+	)
+
+	cmd := &cobra.Command{
+		Use:   "bisect",
+		Short: "Narrow a state root divergence to a few accounts",
+		Long: `Run a workload whose roots differ between clients, then repeatedly
+split its accounts in two by address hash and keep a half whose roots still
+differ. The smallest diverging workload is written to --output.`,
+		RunE: func(cmd *cobra.Command, _ []string) error {
+			var err error
+
+			cfg.selector, err = flags.selector()
+			if err != nil {
+				return err
+			}
+
+			return runBisect(cmd.Context(), logger, cfg)
+		},
+	}
+
+	cmd.Flags().StringVar(&cfg.workloadPath, "workload", "",
+		"Workload whose roots diverge")
+	cmd.Flags().StringSliceVar(&cfg.clients, "clients", nil,
+		"Clients to compare, at least two (e.g. reth,ethrex)")
+	cmd.Flags().StringVar(&cfg.dbDir, "db-dir", "",
+		"Base directory for client databases")
+	cmd.Flags().StringVar(&cfg.harnessesDir, "harnesses-dir", "",
+		"Path to harnesses directory (default: ./harnesses)")
+	cmd.Flags().BoolVar(&cfg.skipBuild, "skip-build", false,
+		"Skip building harness binaries")
+	cmd.Flags().IntVar(&cfg.maxAccounts, "max-accounts", 1,
+		"Stop once the diverging workload has at most this many accounts")
+	cmd.Flags().StringVar(&cfg.output, "output", "bisect.jsonl",
+		"Where to write the smallest diverging workload")
+	flags.register(cmd)
+
+	return cmd
+}
+
+func runBisect(ctx context.Context, logger *slog.Logger, cfg bisectConfig) error {
+	if cfg.workloadPath == "" {
+		return fmt.Errorf("a workload must be specified via --workload")
+	}
+
+	if len(cfg.clients) < 2 {
+		return fmt.Errorf("at least two clients must be specified via --clients")
+	}
+
+	dbDir := cfg.dbDir
+	if dbDir == "" {
+		dbDir = "tmp"
+	}
+
+	if err := harness.CheckDBPaths(dbDir, cfg.clients); err != nil {
+		return err
+	}
+
+	ops, err := scanWorkloadOps(cfg.workloadPath)
+	if err != nil {
+		return err
+	}
+
+	binaries, err := buildHarnesses(ctx, logger, cfg.harnessesDir, cfg.clients, cfg.skipBuild)
+	if err != nil {
+		return err
+	}
+
+	if err := checkCapabilities(ctx, logger, binaries, plainJSONL, ops); err != nil {
+		return err
+	}
+
+	if err := os.MkdirAll(dbDir, 0o755); err != nil {
+		return fmt.Errorf("create db dir: %w", err)
+	}
+
+	probe := func(s workload.Selector) (int, bool, error) {
+		return probeSelection(ctx, logger, cfg, dbDir, binaries, s)
+	}
+
+	b, err := workload.Bisect(cfg.selector, cfg.maxAccounts, probe)
+	if err != nil {
+		return fmt.Errorf("bisect after %d runs: %w", b.Probes, err)
+	}
+
+	// Leave the smallest diverging workload behind for inspection.
+	summary, err := writeSelection(cfg.workloadPath, cfg.output, b.Selector)
+	if err != nil {
+		return err
+	}
+
+	logger.InfoContext(ctx, "bisection complete",
+		slog.Int("runs", b.Probes),
+		slog.Int("accounts", b.Accounts),
+		slog.String("filter", strings.Join(b.Selector.Args(), " ")),
+		slog.String("workload", cfg.output),
+	)
+
+	for _, address := range summary.Addresses {
+		fmt.Println(address)
+	}
+
+	return nil
+}
+
+// probeSelection runs every client on the workload restricted to s and
+// reports how many accounts it kept and whether the roots differ.
+func probeSelection(
+	ctx context.Context,
+	logger *slog.Logger,
+	cfg bisectConfig,
+	dbDir string,
+	binaries map[string]string,
+	s workload.Selector,
+) (int, bool, error) {
+	tmp, err := os.CreateTemp("", "statoor-bisect-*.jsonl")
+	if err != nil {
+		return 0, false, fmt.Errorf("create temp file: %w", err)
+	}
+
+	tmp.Close()
+	defer os.Remove(tmp.Name())
+
+	summary, err := writeSelection(cfg.workloadPath, tmp.Name(), s)
+	if err != nil {
+		return 0, false, err
+	}
+
+	accounts := len(summary.Addresses)
+	if accounts == 0 {
+		return 0, false, nil
+	}
+
+	roots := make(map[string]string, len(cfg.clients))
+
+	for _, client := range cfg.clients {
+		cmdCfg := harness.WrapCommand(client, binaries[client])
+		runner := harness.NewRunner(
+			client, cmdCfg.Binary, cmdCfg.ExtraArgs, cmdCfg.Env, logger,
+		)
+
+		result, err := runner.Run(ctx, harness.RunConfig{
+			WorkloadPath: tmp.Name(),
+			DBDir:        dbDir,
+			Timeout:      30 * time.Minute,
+		})
+		if err != nil {
+			return accounts, false, fmt.Errorf("run %s: %w", client, err)
+		}
+
+		roots[client] = strings.ToLower(result.StateRoot)
+	}
+
+	diverged := false
+
+	for _, client := range cfg.clients[1:] {
+		if roots[client] != roots[cfg.clients[0]] {
+			diverged = true
+		}
+	}
+
+	logger.InfoContext(ctx, "bisection step",
+		slog.String("filter", strings.Join(s.Args(), " ")),
+		slog.Int("accounts", accounts),
+		slog.Bool("diverged", diverged),
+		slog.Any("roots", roots),
+	)
+
+	return accounts, diverged, nil
+}
+
+// writeSelection filters the workload at input to a new file at output.
+func writeSelection(input, output string, s workload.Selector) (workload.FilterSummary, error) {
+	in, err := os.Open(input)
+	if err != nil {
+		return workload.FilterSummary{}, fmt.Errorf("open workload %s: %w", input, err)
+	}
+	defer in.Close()
+
+	out, err := os.Create(output)
+	if err != nil {
+		return workload.FilterSummary{}, fmt.Errorf("create %s: %w", output, err)
+	}
+
+	summary, err := workload.Filter(in, out, s)
+	if err != nil {
+		out.Close()
+
+		return summary, fmt.Errorf("filter %s: %w", input, err)
+	}
+
+	return summary, out.Close()
+}
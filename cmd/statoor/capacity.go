@@ -0,0 +1,309 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"log/slog"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"time"
+
+	"github.com/spf13/cobra"
+	"github.com/weiihann/statoor/harness"
+	"github.com/weiihann/statoor/workload"
+)
+
+// scalePlaceholder is replaced by the candidate scale in --generator.
+const scalePlaceholder = "{scale}"
+
+type capacityConfig struct {
+	client         string
+	generator      string
+	budgetMemoryGB float64
+	budgetTimeMin  float64
+	startScale     int
+	maxScale       int
+	precision      float64
+	dbDir          string
+	harnessesDir   string
+	skipBuild      bool
+	outDir         string
+}
+
+// capacityProbe is one run of a capacity search.
+type capacityProbe struct {
+	Scale int `json:"scale"`
+	// Outcome is "fit", "over_memory", or the FailureKind of a failed
+	// run, such as "timeout".
+	Outcome         string `json:"outcome"`
+	PeakMemoryBytes uint64 `json:"peak_memory_bytes,omitempty"`
+	ElapsedMs       int64  `json:"elapsed_ms,omitempty"`
+	Error           string `json:"error,omitempty"`
+	// Result is the file holding the run's result JSON.
+	Result string `json:"result,omitempty"`
+}
+
+// capacityReport is the capacity.json written to the output directory.
+type capacityReport struct {
+	Client            string          `json:"client"`
+	Generator         string          `json:"generator"`
+	BudgetMemoryBytes uint64          `json:"budget_memory_bytes,omitempty"`
+	BudgetTimeMs      int64           `json:"budget_time_ms,omitempty"`
+	LargestScale      int             `json:"largest_scale"`
+	ExceededScale     int             `json:"exceeded_scale,omitempty"`
+	Probes            []capacityProbe `json:"probes"`
+}
+
+func newCapacityCmd(logger *slog.Logger) *cobra.Command {
+	var cfg capacityConfig
+
+	cmd := &cobra.Command{
+		Use:   "capacity",
+		Short: "Find the largest workload a client handles within a budget",
+		Long: `Generate workloads of growing scale and run each through one harness,
+doubling the scale until a run exceeds the memory or time budget, then
+bisecting between the last run that fit and the first that did not. A run
+that times out or fails counts as not fitting. Every run's result is kept in
+--out-dir next to capacity.json, the search trace.`,
+		RunE: func(cmd *cobra.Command, _ []string) error {
+			return runCapacity(cmd.Context(), logger, cfg)
+		},
+	}
+
+	flags := cmd.Flags()
+	flags.StringVar(&cfg.client, "harness", "",
+		"Client whose harness to run (e.g. reth)")
+	flags.StringVar(&cfg.generator, "generator", "",
+		"Shell command printing the workload at a scale, which replaces {scale}")
+	flags.Float64Var(&cfg.budgetMemoryGB, "budget-memory-gb", 0,
+		"Largest peak memory of a fitting run in GiB (0 = unbounded)")
+	flags.Float64Var(&cfg.budgetTimeMin, "budget-time-min", 0,
+		"Longest a fitting run may take in minutes; longer runs are killed (0 = unbounded)")
+	flags.IntVar(&cfg.startScale, "start-scale", 1,
+		"Scale of the first run")
+	flags.IntVar(&cfg.maxScale, "max-scale", 0,
+		"Largest scale to try (0 = unbounded)")
+	flags.Float64Var(&cfg.precision, "precision", 0.05,
+		"Stop bisecting once the bounds are within this fraction of the largest fitting scale")
+	flags.StringVar(&cfg.dbDir, "db-dir", "",
+		"Base directory for client databases")
+	flags.StringVar(&cfg.harnessesDir, "harnesses-dir", "",
+		"Path to harnesses directory (default: ./harnesses)")
+	flags.BoolVar(&cfg.skipBuild, "skip-build", false,
+		"Skip building harness binaries")
+	flags.StringVar(&cfg.outDir, "out-dir", "capacity",
+		"Where to write each run's result and capacity.json")
+
+	return cmd
+}
+
+func runCapacity(ctx context.Context, logger *slog.Logger, cfg capacityConfig) error {
+	if cfg.client == "" {
+		return fmt.Errorf("a harness must be specified via --harness")
+	}
+
+	if !strings.Contains(cfg.generator, scalePlaceholder) {
+		return fmt.Errorf("--generator must contain %s", scalePlaceholder)
+	}
+
+	if cfg.budgetMemoryGB <= 0 && cfg.budgetTimeMin <= 0 {
+		return fmt.Errorf("a budget must be set via --budget-memory-gb or --budget-time-min")
+	}
+
+	dbDir := cfg.dbDir
+	if dbDir == "" {
+		dbDir = "tmp"
+	}
+
+	if err := harness.CheckDBPaths(dbDir, []string{cfg.client}); err != nil {
+		return err
+	}
+
+	binaries, err := buildHarnesses(ctx, logger, cfg.harnessesDir, []string{cfg.client}, cfg.skipBuild)
+	if err != nil {
+		return err
+	}
+
+	for _, dir := range []string{dbDir, cfg.outDir} {
+		if err := os.MkdirAll(dir, 0o755); err != nil {
+			return fmt.Errorf("create %s: %w", dir, err)
+		}
+	}
+
+	report := capacityReport{
+		Client:            cfg.client,
+		Generator:         cfg.generator,
+		BudgetMemoryBytes: uint64(cfg.budgetMemoryGB * (1 << 30)),
+		BudgetTimeMs:      int64(cfg.budgetTimeMin * float64(time.Minute/time.Millisecond)),
+	}
+
+	probe := func(scale int) (bool, error) {
+		p, err := probeScale(ctx, logger, cfg, report, dbDir, binaries, scale)
+		if err != nil {
+			return false, err
+		}
+
+		report.Probes = append(report.Probes, p)
+
+		return p.Outcome == "fit", nil
+	}
+
+	c, err := workload.SearchCapacity(cfg.startScale, cfg.maxScale, cfg.precision, probe)
+	report.LargestScale, report.ExceededScale = c.Largest, c.Exceeded
+
+	// The trace of a search cut short is still worth keeping.
+	if werr := writeCapacityReport(cfg.outDir, report); werr != nil {
+		return errors.Join(err, werr)
+	}
+
+	if err != nil {
+		return fmt.Errorf("capacity search after %d runs: %w", c.Probes, err)
+	}
+
+	printCapacity(report)
+
+	return nil
+}
+
+// probeScale generates the workload at scale, runs the harness on it and
+// classifies the run against the budget. Only failures of the search
+// itself, such as a failing generator, are returned as errors.
+func probeScale(
+	ctx context.Context,
+	logger *slog.Logger,
+	cfg capacityConfig,
+	report capacityReport,
+	dbDir string,
+	binaries map[string]string,
+	scale int,
+) (capacityProbe, error) {
+	p := capacityProbe{Scale: scale}
+	workloadPath := filepath.Join(cfg.outDir, fmt.Sprintf("scale-%d.jsonl", scale))
+
+	if err := generateAtScale(ctx, cfg.generator, scale, workloadPath); err != nil {
+		return p, err
+	}
+
+	// Workloads near a memory budget are large; only the results are kept.
+	defer os.Remove(workloadPath)
+
+	ops, err := scanWorkloadOps(workloadPath)
+	if err != nil {
+		return p, err
+	}
+
+	if err := checkCapabilities(ctx, logger, binaries, plainJSONL, ops); err != nil {
+		return p, err
+	}
+
+	cmdCfg := harness.WrapCommand(cfg.client, binaries[cfg.client])
+	runner := harness.NewRunner(
+		cfg.client, cmdCfg.Binary, cmdCfg.ExtraArgs, cmdCfg.Env, logger,
+	)
+
+	result, err := runner.Run(ctx, harness.RunConfig{
+		WorkloadPath: workloadPath,
+		DBDir:        dbDir,
+		Timeout:      time.Duration(report.BudgetTimeMs) * time.Millisecond,
+	})
+
+	var runErr *harness.RunError
+
+	switch {
+	case errors.As(err, &runErr) && runErr.Kind != harness.FailureCanceled:
+		p.Outcome, p.Error = string(runErr.Kind), runErr.Error()
+	case err != nil:
+		return p, fmt.Errorf("run %s at scale %d: %w", cfg.client, scale, err)
+	default:
+		p.PeakMemoryBytes, p.ElapsedMs = result.PeakMemoryBytes, result.ElapsedMs
+
+		p.Outcome = "fit"
+		if report.BudgetMemoryBytes > 0 && result.PeakMemoryBytes > report.BudgetMemoryBytes {
+			p.Outcome = "over_memory"
+		}
+
+		p.Result = filepath.Join(cfg.outDir, fmt.Sprintf("scale-%d.json", scale))
+		if err := writeJSONFile(p.Result, result); err != nil {
+			return p, err
+		}
+	}
+
+	logger.InfoContext(ctx, "capacity step",
+		slog.Int("scale", scale),
+		slog.String("outcome", p.Outcome),
+		slog.Uint64("peak_memory_bytes", p.PeakMemoryBytes),
+		slog.Int64("elapsed_ms", p.ElapsedMs),
+	)
+
+	return p, nil
+}
+
+// generateAtScale runs the generator through the shell with the scale
+// substituted, writing its stdout to path.
+func generateAtScale(ctx context.Context, generator string, scale int, path string) error {
+	out, err := os.Create(path)
+	if err != nil {
+		return fmt.Errorf("create %s: %w", path, err)
+	}
+
+	command := strings.ReplaceAll(generator, scalePlaceholder, strconv.Itoa(scale))
+	gen := exec.CommandContext(ctx, "sh", "-c", command)
+	gen.Stdout = out
+	gen.Stderr = os.Stderr
+
+	if err := gen.Run(); err != nil {
+		out.Close()
+
+		return fmt.Errorf("generator at scale %d: %w", scale, err)
+	}
+
+	return out.Close()
+}
+
+func writeCapacityReport(outDir string, report capacityReport) error {
+	return writeJSONFile(filepath.Join(outDir, "capacity.json"), report)
+}
+
+func writeJSONFile(path string, v any) error {
+	data, err := json.MarshalIndent(v, "", "  ")
+	if err != nil {
+		return fmt.Errorf("encode %s: %w", path, err)
+	}
+
+	if err := os.WriteFile(path, append(data, '\n'), 0o644); err != nil {
+		return fmt.Errorf("write %s: %w", path, err)
+	}
+
+	return nil
+}
+
+// printCapacity prints the search trace in the order the runs were made,
+// then the answer.
+func printCapacity(report capacityReport) {
+	fmt.Printf("%-12s %-12s %14s %12s\n", "Scale", "Outcome", "Peak memory", "Elapsed ms")
+
+	for _, p := range report.Probes {
+		fmt.Printf("%-12d %-12s %14d %12d\n", p.Scale, p.Outcome, p.PeakMemoryBytes, p.ElapsedMs)
+	}
+
+	fmt.Println()
+
+	if report.LargestScale == 0 {
+		fmt.Printf("No scale fit the budget; scale %d already exceeded it.\n", report.ExceededScale)
+
+		return
+	}
+
+	fmt.Printf("Largest scale within budget: %d", report.LargestScale)
+
+	if report.ExceededScale != 0 {
+		fmt.Printf(" (scale %d exceeded it)", report.ExceededScale)
+	}
+
+	fmt.Println()
+}
@@ -0,0 +1,110 @@
+package main
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+
+	"github.com/spf13/cobra"
+	"github.com/weiihann/statoor/statediff"
+)
+
+// errStatesDiffer fails diff-state when the dumps differ, so it exits
+// nonzero.
+var errStatesDiffer = errors.New("states differ")
+
+func newDiffStateCmd() *cobra.Command {
+	var (
+		maxDiffs int
+		output   string
+	)
+
+	cmd := &cobra.Command{
+		Use:   "diff-state <a.jsonl> <b.jsonl>",
+		Short: "Compare two state dumps account by account",
+		Long: `Merge-join two state dumps, one JSON account per line sorted by key,
+and report accounts present in only one of them, differing balance, nonce or
+code_hash, and differing storage slots. Both dumps are streamed, so their size
+is not bounded by memory. Exits nonzero when the states differ.`,
+		Args: cobra.ExactArgs(2),
+		RunE: func(_ *cobra.Command, args []string) error {
+			if maxDiffs < 0 {
+				return fmt.Errorf("--max-diffs must not be negative, got %d", maxDiffs)
+			}
+
+			return diffState(args[0], args[1], maxDiffs, output)
+		},
+	}
+
+	cmd.Flags().IntVar(&maxDiffs, "max-diffs", 100,
+		"Differences to list (0 = all); counts always cover every one")
+	cmd.Flags().StringVar(&output, "output", "state-diff.json",
+		"Where to write the JSON diff document (- for stdout, without the table)")
+
+	return cmd
+}
+
+func diffState(pathA, pathB string, maxDiffs int, output string) error {
+	a, err := os.Open(pathA)
+	if err != nil {
+		return fmt.Errorf("open dump %s: %w", pathA, err)
+	}
+	defer a.Close()
+
+	b, err := os.Open(pathB)
+	if err != nil {
+		return fmt.Errorf("open dump %s: %w", pathB, err)
+	}
+	defer b.Close()
+
+	rep, err := statediff.Run(pathA, a, pathB, b, maxDiffs)
+	if err != nil {
+		return fmt.Errorf("compare %s and %s: %w", pathA, pathB, err)
+	}
+
+	if output == "-" {
+		if err := writeDiff(os.Stdout, rep); err != nil {
+			return err
+		}
+	} else {
+		if err := writeDiffFile(output, rep); err != nil {
+			return err
+		}
+
+		rep.WriteTable(os.Stdout)
+	}
+
+	if rep.Differ() {
+		return errStatesDiffer
+	}
+
+	return nil
+}
+
+func writeDiffFile(path string, rep statediff.Report) error {
+	f, err := os.Create(path)
+	if err != nil {
+		return fmt.Errorf("create %s: %w", path, err)
+	}
+
+	if err := writeDiff(f, rep); err != nil {
+		f.Close()
+
+		return err
+	}
+
+	return f.Close()
+}
+
+func writeDiff(w io.Writer, rep statediff.Report) error {
+	enc := json.NewEncoder(w)
+	enc.SetIndent("", "  ")
+
+	if err := enc.Encode(rep); err != nil {
+		return fmt.Errorf("write diff: %w", err)
+	}
+
+	return nil
+}
@@ -0,0 +1,205 @@
+package main
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"log/slog"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"github.com/spf13/cobra"
+	"github.com/weiihann/statoor/harness"
+	"github.com/weiihann/statoor/harnesses/common/examples"
+	"github.com/weiihann/statoor/workload"
+)
+
+// errExampleRoot fails examples run when the harness ends at another
+// root than the example's, so it exits nonzero.
+var errExampleRoot = errors.New("state root differs from the example's")
+
+func newExamplesCmd(logger *slog.Logger) *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "examples",
+		Short: "List, print and run the embedded example workloads",
+	}
+
+	cmd.AddCommand(newExamplesListCmd())
+	cmd.AddCommand(newExamplesEmitCmd())
+	cmd.AddCommand(newExamplesRunCmd(logger))
+
+	return cmd
+}
+
+func newExamplesListCmd() *cobra.Command {
+	return &cobra.Command{
+		Use:   "list",
+		Short: "List the example workloads",
+		Args:  cobra.NoArgs,
+		RunE: func(cmd *cobra.Command, _ []string) error {
+			return listExamples(cmd.OutOrStdout())
+		},
+	}
+}
+
+func newExamplesEmitCmd() *cobra.Command {
+	var comments bool
+
+	cmd := &cobra.Command{
+		Use:   "emit <name>",
+		Short: "Print an example workload",
+		Long: `Print the named example as a workload any harness supporting its
+operations reads. With --comments, the explanatory comment lines are kept; the
+output is then for reading, not for a harness.`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			example, err := examples.Get(args[0])
+			if err != nil {
+				return err
+			}
+
+			out := example.Workload()
+			if comments {
+				out = example.Source
+			}
+
+			_, err = cmd.OutOrStdout().Write(out)
+
+			return err
+		},
+	}
+
+	cmd.Flags().BoolVar(&comments, "comments", false,
+		"Keep the comment lines explaining the example")
+
+	return cmd
+}
+
+func newExamplesRunCmd(logger *slog.Logger) *cobra.Command {
+	var (
+		harnessCmd string
+		dbDir      string
+		timeout    time.Duration
+	)
+
+	cmd := &cobra.Command{
+		Use:   "run <name>",
+		Short: "Run an example workload through a harness",
+		Long: `Run the named example through the harness binary --harness names,
+with any arguments it needs after it, against a fresh database, then print the
+result and check the root against the example's. Exits nonzero when the roots
+differ or the harness lacks an operation the example uses.`,
+		Args: cobra.ExactArgs(1),
+		RunE: func(cmd *cobra.Command, args []string) error {
+			command := strings.Fields(harnessCmd)
+			if len(command) == 0 {
+				return errors.New("--harness is required")
+			}
+
+			return runExample(cmd.Context(), logger, cmd.OutOrStdout(),
+				args[0], command, dbDir, timeout)
+		},
+	}
+
+	cmd.Flags().StringVar(&harnessCmd, "harness", "",
+		"Harness command, e.g. ./harnesses/reth/target/release/reth-harness")
+	cmd.Flags().StringVar(&dbDir, "db-dir", "",
+		"Directory for the harness database (default: a temporary directory)")
+	cmd.Flags().DurationVar(&timeout, "timeout", 5*time.Minute,
+		"Longest the harness may run")
+
+	return cmd
+}
+
+func listExamples(w io.Writer) error {
+	all, err := examples.All()
+	if err != nil {
+		return err
+	}
+
+	width := 0
+	for _, example := range all {
+		width = max(width, len(example.Name))
+	}
+
+	for _, example := range all {
+		if _, err := fmt.Fprintf(w, "%-*s  %s\n", width, example.Name, example.Summary); err != nil {
+			return err
+		}
+	}
+
+	return nil
+}
+
+func runExample(
+	ctx context.Context,
+	logger *slog.Logger,
+	w io.Writer,
+	name string,
+	command []string,
+	dbDir string,
+	timeout time.Duration,
+) error {
+	example, err := examples.Get(name)
+	if err != nil {
+		return err
+	}
+
+	body := example.Workload()
+
+	ops, err := workload.ScanOps(bytes.NewReader(body))
+	if err != nil {
+		return fmt.Errorf("scan example %s: %w", name, err)
+	}
+
+	client := filepath.Base(command[0])
+	runner := harness.NewRunner(client, command[0], command[1:], nil, logger)
+
+	caps := map[string]harness.Capabilities{client: runner.Capabilities(ctx)}
+	if err := harness.CheckOps(ops, caps); err != nil {
+		return err
+	}
+
+	tmp, err := os.MkdirTemp("", "statoor-example-")
+	if err != nil {
+		return fmt.Errorf("create temp dir: %w", err)
+	}
+	defer os.RemoveAll(tmp)
+
+	if dbDir == "" {
+		dbDir = tmp
+	}
+
+	workloadPath := filepath.Join(tmp, name+".jsonl")
+	if err := os.WriteFile(workloadPath, body, 0o644); err != nil {
+		return fmt.Errorf("write example %s: %w", name, err)
+	}
+
+	result, err := runner.Run(ctx, harness.RunConfig{
+		WorkloadPath: workloadPath,
+		DBDir:        dbDir,
+		Timeout:      timeout,
+	})
+	if err != nil {
+		return err
+	}
+
+	enc := json.NewEncoder(w)
+	enc.SetIndent("", "  ")
+
+	if err := enc.Encode(result); err != nil {
+		return fmt.Errorf("write result: %w", err)
+	}
+
+	if !strings.EqualFold(result.StateRoot, example.ExpectedRoot) {
+		return fmt.Errorf("%w: %s computed %s, expected %s",
+			errExampleRoot, client, result.StateRoot, example.ExpectedRoot)
+	}
+
+	return nil
+}
@@ -0,0 +1,164 @@
+package main
+
+import (
+	"fmt"
+	"io"
+	"os"
+	"strings"
+
+	"github.com/spf13/cobra"
+	"github.com/weiihann/statoor/workload"
+)
+
+func newWorkloadCmd() *cobra.Command {
+	cmd := &cobra.Command{
+		Use:   "workload",
+		Short: "Inspect and transform workload files",
+	}
+
+	cmd.AddCommand(newFilterCmd())
+	cmd.AddCommand(newValidateCmd())
+
+	return cmd
+}
+
+func newFilterCmd() *cobra.Command {
+	var (
+		input  string
+		output string
+		flags  selectorFlags
+	)
+
+	cmd := &cobra.Command{
+		Use:   "filter",
+		Short: "Keep only the operations of selected accounts",
+		Long: `Copy the operations of the accounts matching an address prefix and
+hash bucket, in their original order, ending in compute_root. The output is a
+valid workload for any harness.`,
+		RunE: func(_ *cobra.Command, _ []string) error {
+			selector, err := flags.selector()
+			if err != nil {
+				return err
+			}
+
+			return filterWorkload(input, output, selector)
+		},
+	}
+
+	cmd.Flags().StringVar(&input, "input", "-",
+		"Workload to filter (- for stdin)")
+	cmd.Flags().StringVar(&output, "output", "-",
+		"Where to write the filtered workload (- for stdout)")
+	flags.register(cmd)
+
+	return cmd
+}
+
+func newValidateCmd() *cobra.Command {
+	var input string
+
+	cmd := &cobra.Command{
+		Use:   "validate",
+		Short: "Check a workload against its trailer",
+		Long: `Decode every line of a workload and, if it has a trailer line, check
+that the lines before it match the operation counts it declares. A mismatch
+means lines were lost or duplicated since the workload was generated.`,
+		RunE: func(cmd *cobra.Command, _ []string) error {
+			return validateWorkload(cmd.OutOrStdout(), input)
+		},
+	}
+
+	cmd.Flags().StringVar(&input, "input", "-",
+		"Workload to validate (- for stdin)")
+
+	return cmd
+}
+
+func validateWorkload(w io.Writer, input string) error {
+	var r io.Reader = os.Stdin
+
+	if input != "-" {
+		f, err := os.Open(input)
+		if err != nil {
+			return fmt.Errorf("open workload %s: %w", input, err)
+		}
+		defer f.Close()
+
+		r = f
+	}
+
+	check, err := workload.VerifyTrailer(r)
+	if err != nil {
+		return fmt.Errorf("validate %s: %w", input, err)
+	}
+
+	switch {
+	case !check.Found:
+		fmt.Fprintln(w, "valid, no trailer to check")
+	case !check.Match():
+		return fmt.Errorf("%s does not match its trailer: %s", input, check)
+	default:
+		fmt.Fprintln(w, "valid, trailer matches")
+	}
+
+	return nil
+}
+
+// selectorFlags are the flags that build a workload.Selector.
+type selectorFlags struct {
+	prefix  string
+	modulus uint64
+	bucket  uint64
+}
+
+func (f *selectorFlags) register(cmd *cobra.Command) {
+	cmd.Flags().StringVar(&f.prefix, "address-prefix", "",
+		"Keep addresses starting with these hex digits")
+	cmd.Flags().Uint64Var(&f.modulus, "modulus", 0,
+		"Partition addresses by hash into this many buckets (0 = off)")
+	cmd.Flags().Uint64Var(&f.bucket, "bucket", 0,
+		"Hash bucket to keep, below --modulus")
+}
+
+func (f *selectorFlags) selector() (workload.Selector, error) {
+	selector := workload.Selector{
+		Prefix:  strings.ToLower(strings.TrimPrefix(f.prefix, "0x")),
+		Modulus: f.modulus,
+		Bucket:  f.bucket,
+	}
+
+	return selector, selector.Validate()
+}
+
+func filterWorkload(input, output string, selector workload.Selector) error {
+	var r io.Reader = os.Stdin
+
+	if input != "-" {
+		f, err := os.Open(input)
+		if err != nil {
+			return fmt.Errorf("open workload %s: %w", input, err)
+		}
+		defer f.Close()
+
+		r = f
+	}
+
+	if output == "-" {
+		_, err := workload.Filter(r, os.Stdout, selector)
+
+		return err
+	}
+
+	f, err := os.Create(output)
+	if err != nil {
+		return fmt.Errorf("create %s: %w", output, err)
+	}
+
+	if _, err := workload.Filter(r, f, selector); err != nil {
+		f.Close()
+
+		return fmt.Errorf("filter %s: %w", input, err)
+	}
+
+	return f.Close()
+}
@@ -4,10 +4,16 @@ package main
 
 import (
 	"context"
+	"errors"
 	"fmt"
+	"io"
 	"log/slog"
+	"maps"
 	"os"
 	"path/filepath"
+	"slices"
+	"strconv"
+	"strings"
 	"time"
 
 	"github.com/spf13/cobra"
@@ -39,6 +45,12 @@ layer and comparing performance metrics.`,
 	}
 
 	root.AddCommand(newRunCmd(logger))
+	root.AddCommand(newWorkloadCmd())
+	root.AddCommand(newBisectCmd(logger))
+	root.AddCommand(newCapacityCmd(logger))
+	root.AddCommand(newDiffStateCmd())
+	root.AddCommand(newVerifyDBCmd(logger))
+	root.AddCommand(newExamplesCmd(logger))
 
 	return root
 }
@@ -52,12 +64,38 @@ func newRunCmd(logger *slog.Logger) *cobra.Command {
 		distribution string
 		seed         int64
 		codeSize     int
+		tenants      int
+		blockOps     int
+		gasCosts     string
 		clients      []string
 		dbDir        string
 		workloadPath string
 		harnessesDir string
 		skipBuild    bool
 		outputJSON   bool
+		latencyMs    int
+		replayEvents string
+		flushCommit  bool
+		maxOps       []string
+		durationSecs int
+		probe        string
+		readCode     int
+		recompute    int
+		retries      int
+		healthCheck  bool
+		preload      bool
+		preloadLimit int
+		trailer      bool
+		strict       bool
+		keepZero     bool
+		sorted       bool
+		sortBufferMB int
+		compareOrder bool
+		readBenchSec int
+		iterateSecs  int
+		verifySecs   int
+		allowModel   bool
+		allowSim     bool
 	)
 
 	cmd := &cobra.Command{
@@ -74,12 +112,38 @@ Ethereum client harnesses, comparing state roots and performance.`,
 				distribution: distribution,
 				seed:         seed,
 				codeSize:     codeSize,
+				tenants:      tenants,
+				blockOps:     blockOps,
+				gasCosts:     gasCosts,
 				clients:      clients,
 				dbDir:        dbDir,
 				workloadPath: workloadPath,
 				harnessesDir: harnessesDir,
 				skipBuild:    skipBuild,
 				outputJSON:   outputJSON,
+				latencyMs:    latencyMs,
+				replayEvents: replayEvents,
+				flushCommit:  flushCommit,
+				maxOps:       maxOps,
+				durationSecs: durationSecs,
+				probe:        probe,
+				readCode:     readCode,
+				recompute:    recompute,
+				retries:      retries,
+				healthCheck:  healthCheck,
+				preload:      preload,
+				preloadLimit: preloadLimit,
+				trailer:      trailer,
+				strict:       strict,
+				keepZero:     keepZero,
+				sorted:       sorted,
+				sortBufferMB: sortBufferMB,
+				compareOrder: compareOrder,
+				readBenchSec: readBenchSec,
+				iterateSecs:  iterateSecs,
+				verifySecs:   verifySecs,
+				allowModel:   allowModel,
+				allowSim:     allowSim,
 			})
 		},
 	}
@@ -99,6 +163,12 @@ Ethereum client harnesses, comparing state roots and performance.`,
 		"Random seed (0 = use current time)")
 	flags.IntVar(&codeSize, "code-size", 1024,
 		"Average contract code size in bytes")
+	flags.IntVar(&tenants, "tenants", 0,
+		"Split accounts between N tenants with disjoint address ranges, tagging each op")
+	flags.IntVar(&blockOps, "block-ops", 0,
+		"End a block with commit_block every N operations, stamped with its approx_gas (0 = one batch)")
+	flags.StringVar(&gasCosts, "gas-costs", "",
+		"Override approx_gas prices, e.g. storage_set=22100,code_byte=0")
 	flags.StringSliceVar(&clients, "clients", nil,
 		"Clients to benchmark (e.g. geth,reth,erigon)")
 	flags.StringVar(&dbDir, "db-dir", "",
@@ -111,6 +181,52 @@ Ethereum client harnesses, comparing state roots and performance.`,
 		"Skip building harness binaries")
 	flags.BoolVar(&outputJSON, "json", false,
 		"Output results as JSON instead of table")
+	flags.IntVar(&latencyMs, "inject-commit-latency-ms", 0,
+		"Simulated latency slept around each commit (reth, ethrex only)")
+	flags.StringVar(&replayEvents, "replay-events", "",
+		"Pace workload delivery to match a harness --events-out log")
+	flags.BoolVar(&flushCommit, "flush-after-commit", false,
+		"Make ethrex fsync its database after commit, timed separately")
+	flags.StringSliceVar(&maxOps, "max-ops", nil,
+		"Stop after N operations and compute the root, as N or per client as client=N (reth, ethrex only)")
+	flags.IntVar(&durationSecs, "duration-secs", 0,
+		"Apply operations for N seconds, then compute the root (reth, ethrex only)")
+	flags.StringVar(&probe, "durability-probe", "",
+		"Re-open each committed DB in a child process: soft or hard (reth, ethrex only)")
+	flags.IntVar(&readCode, "read-code", 0,
+		"After the run, time N code reads by hash, warm and cold (reth, ethrex only)")
+	flags.IntVar(&recompute, "recompute-root", 0,
+		"After the run, recompute the unchanged root once cold and N more times warm (reth, ethrex only)")
+	flags.IntVar(&readBenchSec, "read-bench-timeout-secs", 0,
+		"Stop the --read-code passes after N seconds, reporting them truncated (0 = no limit)")
+	flags.IntVar(&iterateSecs, "iterate-timeout-secs", 0,
+		"Stop listing the codes --read-code samples from after N seconds (0 = no limit)")
+	flags.IntVar(&verifySecs, "verify-timeout-secs", 0,
+		"Stop the --durability-probe after N seconds, reporting it truncated (0 = no limit)")
+	flags.IntVar(&retries, "retries", 0,
+		"Retry a harness run up to N times after environmental failures")
+	flags.BoolVar(&healthCheck, "health-check", false,
+		"Run each harness's --health-check before the benchmark (reth, ethrex only)")
+	flags.BoolVar(&preload, "preload-workload", false,
+		"Read the whole workload into memory before timing (reth, ethrex only)")
+	flags.IntVar(&preloadLimit, "preload-limit-mb", 0,
+		"Largest workload --preload-workload accepts, in MiB (0 = harness default)")
+	flags.BoolVar(&trailer, "trailer", false,
+		"End the generated workload with a trailer line declaring its operation counts")
+	flags.BoolVar(&strict, "strict", false,
+		"Fail a run whose workload trailer disagrees with the lines read (reth, ethrex only)")
+	flags.BoolVar(&keepZero, "keep-zero-slots", false,
+		"Store slots set to zero instead of deleting them (reth, ethrex only)")
+	flags.BoolVar(&sorted, "sorted-by-hashed-key", false,
+		"Generate the workload ordered by keccak(address), then keccak(slot)")
+	flags.IntVar(&sortBufferMB, "sort-buffer-mb", 0,
+		"Memory a sorted workload may take before spilling to a temp dir, in MiB (0 = 256)")
+	flags.BoolVar(&compareOrder, "compare-ordering", false,
+		"Run each client on the generated workload both unsorted and sorted by hashed key")
+	flags.BoolVar(&allowModel, "allow-model-mismatch", false,
+		"Compare results whose measurement models differ, flagging the differences")
+	flags.BoolVar(&allowSim, "allow-simulated", false,
+		"Compare simulated results with real ones, marking the simulated rows")
 
 	return cmd
 }
@@ -123,12 +239,38 @@ type runConfig struct {
 	distribution string
 	seed         int64
 	codeSize     int
+	tenants      int
+	blockOps     int
+	gasCosts     string
 	clients      []string
 	dbDir        string
 	workloadPath string
 	harnessesDir string
 	skipBuild    bool
 	outputJSON   bool
+	latencyMs    int
+	replayEvents string
+	flushCommit  bool
+	maxOps       []string
+	durationSecs int
+	probe        string
+	readCode     int
+	recompute    int
+	retries      int
+	healthCheck  bool
+	preload      bool
+	preloadLimit int
+	trailer      bool
+	strict       bool
+	keepZero     bool
+	sorted       bool
+	sortBufferMB int
+	compareOrder bool
+	readBenchSec int
+	iterateSecs  int
+	verifySecs   int
+	allowModel   bool
+	allowSim     bool
 }
 
 func runBenchmark(
@@ -142,6 +284,38 @@ func runBenchmark(
 		)
 	}
 
+	if cfg.retries < 0 {
+		return fmt.Errorf("--retries must not be negative, got %d", cfg.retries)
+	}
+
+	if err := checkOrdering(cfg); err != nil {
+		return err
+	}
+
+	// Both orderings must come from one seed to be the same workload.
+	if cfg.compareOrder && cfg.seed == 0 {
+		cfg.seed = time.Now().UnixNano()
+	}
+
+	harnessArgs, err := harnessArgsFor(cfg)
+	if err != nil {
+		return err
+	}
+
+	maxOps, err := maxOpsFor(cfg)
+	if err != nil {
+		return err
+	}
+
+	dbDir := cfg.dbDir
+	if dbDir == "" {
+		dbDir = "tmp"
+	}
+
+	if err := harness.CheckDBPaths(dbDir, cfg.clients); err != nil {
+		return err
+	}
+
 	logger.InfoContext(ctx, "starting benchmark",
 		slog.Int("accounts", cfg.accounts),
 		slog.Int("contracts", cfg.contracts),
@@ -152,51 +326,81 @@ func runBenchmark(
 		slog.Any("clients", cfg.clients),
 	)
 
-	harnessesDir := cfg.harnessesDir
-	if harnessesDir == "" {
-		harnessesDir = "harnesses"
-	}
+	// Step 1: Generate workload (or use pre-generated file).
+	workloadPath := cfg.workloadPath
 
-	var err error
+	var workloadOps []string
 
-	harnessesDir, err = filepath.Abs(harnessesDir)
-	if err != nil {
-		return fmt.Errorf("resolve harnesses dir: %w", err)
-	}
+	encoding := plainJSONL
 
-	// Step 1: Generate workload (or use pre-generated file).
-	workloadPath := cfg.workloadPath
 	if workloadPath == "" {
-		workloadPath, err = generateWorkload(ctx, logger, cfg)
+		workloadPath, workloadOps, err = generateWorkload(ctx, logger, cfg)
 		if err != nil {
 			return fmt.Errorf("generate workload: %w", err)
 		}
 
 		defer os.Remove(workloadPath)
+	} else {
+		encoding, err = workloadEncodingOf(workloadPath)
+		if err != nil {
+			return err
+		}
+
+		// The harnesses check the operations of a compressed or binary
+		// workload as they decode it.
+		if encoding == plainJSONL {
+			workloadOps, err = scanWorkloadOps(workloadPath)
+			if err != nil {
+				return err
+			}
+		}
 	}
 
-	// Step 2: Build harness binaries (unless --skip-build).
-	binaries := make(map[string]string, len(cfg.clients))
+	var replay *harness.ReplaySchedule
+	if cfg.replayEvents != "" {
+		replay, err = harness.LoadReplaySchedule(cfg.replayEvents)
+		if err != nil {
+			return fmt.Errorf("load replay events: %w", err)
+		}
+	}
 
-	for _, client := range cfg.clients {
-		binPath := harness.ResolveBinary(harnessesDir, client)
+	workloads := []orderedWorkload{{path: workloadPath}}
+	if cfg.sorted {
+		workloads[0].ordering = harness.OrderingHashedKey
+	}
 
-		if !cfg.skipBuild {
-			binPath, err = harness.Build(ctx, logger, harnessesDir, client)
-			if err != nil {
-				return fmt.Errorf("build %s: %w", client, err)
-			}
+	if cfg.compareOrder {
+		sortedCfg := cfg
+		sortedCfg.sorted = true
+
+		sortedPath, _, err := generateWorkload(ctx, logger, sortedCfg)
+		if err != nil {
+			return fmt.Errorf("generate sorted workload: %w", err)
 		}
 
-		binaries[client] = binPath
+		defer os.Remove(sortedPath)
+
+		workloads = append(workloads, orderedWorkload{path: sortedPath, ordering: harness.OrderingHashedKey})
 	}
 
-	// Step 3: Prepare DB directory.
-	dbDir := cfg.dbDir
-	if dbDir == "" {
-		dbDir = "tmp"
+	// Step 2: Build harness binaries (unless --skip-build).
+	binaries, err := buildHarnesses(ctx, logger, cfg.harnessesDir, cfg.clients, cfg.skipBuild)
+	if err != nil {
+		return err
+	}
+
+	// Fail fast if any harness build cannot handle the workload.
+	if err := checkCapabilities(ctx, logger, binaries, encoding, workloadOps); err != nil {
+		return err
 	}
 
+	if cfg.healthCheck {
+		if err := runHealthChecks(ctx, logger, cfg.clients, binaries); err != nil {
+			return err
+		}
+	}
+
+	// Step 3: Prepare DB directory.
 	if err = os.MkdirAll(dbDir, 0o755); err != nil {
 		return fmt.Errorf("create db dir: %w", err)
 	}
@@ -211,17 +415,40 @@ func runBenchmark(
 		runner := harness.NewRunner(
 			client, cmdCfg.Binary, cmdCfg.ExtraArgs, cmdCfg.Env, logger,
 		)
-		result, runErr := runner.Run(ctx, harness.RunConfig{
-			WorkloadPath: workloadPath,
-			DBDir:        dbDir,
-			Timeout:      30 * time.Minute,
-		})
-
-		if runErr != nil {
-			return fmt.Errorf("run %s: %w", client, runErr)
+		clientArgs := harnessArgs
+		if cfg.flushCommit && client == "ethrex" {
+			clientArgs = append(slices.Clone(harnessArgs), "--flush-after-commit")
 		}
 
-		results = append(results, *result)
+		if n := maxOps[client]; n > 0 {
+			clientArgs = append(slices.Clone(clientArgs), "--max-ops", strconv.Itoa(n))
+		}
+
+		policy := harness.RetryPolicy{Retries: cfg.retries, BaseDelay: retryBaseDelay}
+
+		for _, wl := range workloads {
+			result, attempts, runErr := harness.Retry(ctx, policy,
+				func(ctx context.Context) (*harness.Result, error) {
+					return runner.Run(ctx, harness.RunConfig{
+						WorkloadPath: wl.path,
+						DBDir:        dbDir,
+						Timeout:      30 * time.Minute,
+						HarnessArgs:  clientArgs,
+						Replay:       replay,
+					})
+				})
+
+			if runErr != nil {
+				return fmt.Errorf("run %s: %w", client, runErr)
+			}
+
+			if len(attempts) > 1 {
+				result.Attempts = attempts
+			}
+
+			result.Ordering = wl.ordering
+			results = append(results, *result)
+		}
 	}
 
 	// Step 5: Generate report.
@@ -230,7 +457,8 @@ func runBenchmark(
 			return fmt.Errorf("generate JSON report: %w", err)
 		}
 	} else {
-		if err := report.Generate(os.Stdout, results); err != nil {
+		opts := report.Options{AllowModelMismatch: cfg.allowModel, AllowSimulated: cfg.allowSim}
+		if err := report.GenerateWith(os.Stdout, results, opts); err != nil {
 			return fmt.Errorf("generate report: %w", err)
 		}
 	}
@@ -240,29 +468,324 @@ func runBenchmark(
 	return nil
 }
 
+// orderedWorkload is a workload file and the ordering its results are
+// tagged with.
+type orderedWorkload struct {
+	path     string
+	ordering string
+}
+
+// checkOrdering rejects ordering flags that cannot apply to cfg. Only a
+// generated workload can be sorted, and --compare-ordering already runs
+// the sorted one.
+func checkOrdering(cfg runConfig) error {
+	if cfg.sortBufferMB < 0 {
+		return fmt.Errorf("--sort-buffer-mb must not be negative, got %d", cfg.sortBufferMB)
+	}
+
+	if cfg.workloadPath != "" && (cfg.sorted || cfg.compareOrder) {
+		return errors.New("--sorted-by-hashed-key and --compare-ordering apply to a generated workload, not --workload")
+	}
+
+	if cfg.sorted && cfg.compareOrder {
+		return errors.New("--compare-ordering runs the sorted workload itself; drop --sorted-by-hashed-key")
+	}
+
+	if cfg.replayEvents != "" && cfg.compareOrder {
+		return errors.New("--replay-events paces one ordering; it cannot be used with --compare-ordering")
+	}
+
+	return nil
+}
+
+// buildHarnesses builds the binary of every client, or only resolves
+// its path when skipBuild is set, and returns the paths by client.
+func buildHarnesses(
+	ctx context.Context,
+	logger *slog.Logger,
+	harnessesDir string,
+	clients []string,
+	skipBuild bool,
+) (map[string]string, error) {
+	if harnessesDir == "" {
+		harnessesDir = "harnesses"
+	}
+
+	harnessesDir, err := filepath.Abs(harnessesDir)
+	if err != nil {
+		return nil, fmt.Errorf("resolve harnesses dir: %w", err)
+	}
+
+	binaries := make(map[string]string, len(clients))
+
+	for _, client := range clients {
+		binPath := harness.ResolveBinary(harnessesDir, client)
+
+		if !skipBuild {
+			binPath, err = harness.Build(ctx, logger, harnessesDir, client)
+			if err != nil {
+				return nil, fmt.Errorf("build %s: %w", client, err)
+			}
+		}
+
+		binaries[client] = binPath
+	}
+
+	return binaries, nil
+}
+
+// checkCapabilities fails when a harness cannot handle every operation
+// in ops.
+func checkCapabilities(
+	ctx context.Context,
+	logger *slog.Logger,
+	binaries map[string]string,
+	encoding workloadEncoding,
+	ops []string,
+) error {
+	caps := make(map[string]harness.Capabilities, len(binaries))
+
+	for client, binPath := range binaries {
+		cmdCfg := harness.WrapCommand(client, binPath)
+		runner := harness.NewRunner(
+			client, cmdCfg.Binary, cmdCfg.ExtraArgs, cmdCfg.Env, logger,
+		)
+		caps[client] = runner.Capabilities(ctx)
+	}
+
+	if encoding.compression != "" {
+		if err := harness.CheckCompression(encoding.compression, caps); err != nil {
+			return err
+		}
+	}
+
+	if encoding.format != "" {
+		if err := harness.CheckFormat(encoding.format, caps); err != nil {
+			return err
+		}
+	}
+
+	return harness.CheckOps(ops, caps)
+}
+
+// retryBaseDelay is the wait before the first retry of a failed run;
+// each further retry waits twice as long.
+const retryBaseDelay = 2 * time.Second
+
+// rustClients lists the harnesses built from harnesses/, which share
+// the flags statoor-common defines.
+var rustClients = []string{"reth", "ethrex"}
+
+// healthCheckClients lists the harnesses that support --health-check.
+var healthCheckClients = rustClients
+
+// runHealthChecks runs --health-check for every client that supports it
+// and fails on the first unhealthy harness. Missing optional host
+// capabilities are only logged.
+func runHealthChecks(
+	ctx context.Context,
+	logger *slog.Logger,
+	clients []string,
+	binaries map[string]string,
+) error {
+	for _, client := range clients {
+		if !slices.Contains(healthCheckClients, client) {
+			logger.WarnContext(ctx, "harness has no health check, skipping",
+				slog.String("client", client))
+
+			continue
+		}
+
+		cmdCfg := harness.WrapCommand(client, binaries[client])
+		runner := harness.NewRunner(
+			client, cmdCfg.Binary, cmdCfg.ExtraArgs, cmdCfg.Env, logger,
+		)
+
+		report, err := runner.HealthCheck(ctx)
+		if err != nil {
+			return err
+		}
+
+		logger.InfoContext(ctx, "harness healthy",
+			slog.String("client", client),
+			slog.Any("missing_capabilities", report.MissingCapabilities),
+		)
+	}
+
+	return nil
+}
+
+// harnessArgsFor returns the harness flags implied by cfg, rejecting
+// flags that some of the selected clients do not support.
+func harnessArgsFor(cfg runConfig) ([]string, error) {
+	if err := checkHarnessArgs(cfg); err != nil {
+		return nil, err
+	}
+
+	// Each flag is passed when set, to harnesses that all list it; a flag
+	// without a value is a switch.
+	flags := []struct {
+		flag    string
+		value   string
+		set     bool
+		clients []string
+	}{
+		{"--inject-commit-latency-ms", strconv.Itoa(cfg.latencyMs), cfg.latencyMs > 0, rustClients},
+		{"--duration-secs", strconv.Itoa(cfg.durationSecs), cfg.durationSecs > 0, rustClients},
+		{"--durability-probe", cfg.probe, cfg.probe != "", rustClients},
+		{"--verify-timeout-secs", strconv.Itoa(cfg.verifySecs), cfg.verifySecs > 0, rustClients},
+		{"--read-code", strconv.Itoa(cfg.readCode), cfg.readCode > 0, rustClients},
+		{"--read-bench-timeout-secs", strconv.Itoa(cfg.readBenchSec), cfg.readBenchSec > 0, rustClients},
+		{"--iterate-timeout-secs", strconv.Itoa(cfg.iterateSecs), cfg.iterateSecs > 0, rustClients},
+		{"--recompute-root", strconv.Itoa(cfg.recompute), cfg.recompute > 0, rustClients},
+		{"--preload-workload", "", cfg.preload, rustClients},
+		{"--preload-limit-mb", strconv.Itoa(cfg.preloadLimit), cfg.preloadLimit > 0, rustClients},
+		{"--strict", "", cfg.strict, rustClients},
+		{"--keep-zero-slots", "", cfg.keepZero, rustClients},
+	}
+
+	var args []string
+
+	for _, f := range flags {
+		if !f.set {
+			continue
+		}
+
+		for _, client := range cfg.clients {
+			if !slices.Contains(f.clients, client) {
+				return nil, fmt.Errorf("%s is not supported by %s", f.flag, client)
+			}
+		}
+
+		args = append(args, f.flag)
+		if f.value != "" {
+			args = append(args, f.value)
+		}
+	}
+
+	return args, nil
+}
+
+// maxOpsFor returns the --max-ops of each client. A bare N applies to
+// every client, and client=N overrides it for one, so a single workload
+// can drive a smoke run of one client and a full run of another; 0 lifts
+// the limit.
+func maxOpsFor(cfg runConfig) (map[string]int, error) {
+	limits := make(map[string]int, len(cfg.clients))
+	overrides := make(map[string]int)
+
+	for _, value := range cfg.maxOps {
+		client, count, perClient := strings.Cut(value, "=")
+		if !perClient {
+			count = client
+		}
+
+		n, err := strconv.Atoi(count)
+		if err != nil || n < 0 {
+			return nil, fmt.Errorf("--max-ops must be a count or client=count, got %q", value)
+		}
+
+		if !perClient {
+			for _, client := range cfg.clients {
+				limits[client] = n
+			}
+
+			continue
+		}
+
+		if !slices.Contains(cfg.clients, client) {
+			return nil, fmt.Errorf("--max-ops names %s, which --clients does not", client)
+		}
+
+		overrides[client] = n
+	}
+
+	maps.Copy(limits, overrides)
+
+	for _, client := range cfg.clients {
+		if limits[client] > 0 && !slices.Contains(rustClients, client) {
+			return nil, fmt.Errorf("--max-ops is not supported by %s", client)
+		}
+	}
+
+	return limits, nil
+}
+
+// checkHarnessArgs rejects harness flag values that are out of range or
+// given without the flag they depend on.
+func checkHarnessArgs(cfg runConfig) error {
+	for _, count := range []struct {
+		flag  string
+		value int
+	}{
+		{"--duration-secs", cfg.durationSecs},
+		{"--verify-timeout-secs", cfg.verifySecs},
+		{"--read-code", cfg.readCode},
+		{"--read-bench-timeout-secs", cfg.readBenchSec},
+		{"--iterate-timeout-secs", cfg.iterateSecs},
+		{"--recompute-root", cfg.recompute},
+		{"--preload-limit-mb", cfg.preloadLimit},
+	} {
+		if count.value < 0 {
+			return fmt.Errorf("%s must not be negative, got %d", count.flag, count.value)
+		}
+	}
+
+	if cfg.probe != "" && cfg.probe != "soft" && cfg.probe != "hard" {
+		return fmt.Errorf("--durability-probe must be soft or hard, got %q", cfg.probe)
+	}
+
+	for _, dependent := range []struct {
+		flag, requires string
+		set, given     bool
+	}{
+		{"--verify-timeout-secs", "--durability-probe", cfg.verifySecs > 0, cfg.probe != ""},
+		{"--read-bench-timeout-secs", "--read-code", cfg.readBenchSec > 0, cfg.readCode > 0},
+		{"--iterate-timeout-secs", "--read-code", cfg.iterateSecs > 0, cfg.readCode > 0},
+		{"--preload-limit-mb", "--preload-workload", cfg.preloadLimit > 0, cfg.preload},
+	} {
+		if dependent.set && !dependent.given {
+			return fmt.Errorf("%s requires %s", dependent.flag, dependent.requires)
+		}
+	}
+
+	return nil
+}
+
 func generateWorkload(
 	ctx context.Context,
 	logger *slog.Logger,
 	cfg runConfig,
-) (string, error) {
+) (string, []string, error) {
 	seed := cfg.seed
 	if seed == 0 {
 		seed = time.Now().UnixNano()
 	}
 
+	costs, err := workload.ParseGasCosts(cfg.gasCosts)
+	if err != nil {
+		return "", nil, fmt.Errorf("--gas-costs: %w", err)
+	}
+
 	gen := workload.NewGenerator(workload.Config{
-		NumAccounts:  cfg.accounts,
-		NumContracts: cfg.contracts,
-		MaxSlots:     cfg.maxSlots,
-		MinSlots:     cfg.minSlots,
-		Distribution: cfg.distribution,
-		Seed:         seed,
-		CodeSize:     cfg.codeSize,
+		NumAccounts:       cfg.accounts,
+		NumContracts:      cfg.contracts,
+		MaxSlots:          cfg.maxSlots,
+		MinSlots:          cfg.minSlots,
+		Distribution:      cfg.distribution,
+		Seed:              seed,
+		CodeSize:          cfg.codeSize,
+		Tenants:           cfg.tenants,
+		BlockOps:          cfg.blockOps,
+		GasCosts:          &costs,
+		Trailer:           cfg.trailer,
+		SortedByHashedKey: cfg.sorted,
+		SortBufferBytes:   cfg.sortBufferMB << 20,
 	})
 
 	tmpFile, err := os.CreateTemp("", "statoor-workload-*.jsonl")
 	if err != nil {
-		return "", fmt.Errorf("create temp file: %w", err)
+		return "", nil, fmt.Errorf("create temp file: %w", err)
 	}
 
 	summary, err := gen.Generate(tmpFile)
@@ -270,11 +793,11 @@ func generateWorkload(
 		tmpFile.Close()
 		os.Remove(tmpFile.Name())
 
-		return "", fmt.Errorf("generate: %w", err)
+		return "", nil, fmt.Errorf("generate: %w", err)
 	}
 
 	if err := tmpFile.Close(); err != nil {
-		return "", fmt.Errorf("close workload file: %w", err)
+		return "", nil, fmt.Errorf("close workload file: %w", err)
 	}
 
 	logger.InfoContext(ctx, "workload generated",
@@ -283,7 +806,85 @@ func generateWorkload(
 		slog.Int("accounts", summary.AccountsCreated),
 		slog.Int("contracts", summary.ContractsCreated),
 		slog.Int("storage_slots", summary.StorageSlots),
+		slog.Int("blocks", summary.Blocks),
+		slog.Uint64("approx_gas", summary.ApproxGas),
+		slog.Bool("sorted_by_hashed_key", cfg.sorted),
+		slog.Int("sort_runs", summary.SortRuns),
 	)
 
-	return tmpFile.Name(), nil
+	return tmpFile.Name(), gen.Ops(), nil
+}
+
+// errEncodedWorkload rejects a compressed or CBOR workload where JSONL
+// lines are needed.
+var errEncodedWorkload = errors.New(
+	"not plain JSONL; decompress it, or convert it with statoor-convert --to jsonl")
+
+func scanWorkloadOps(path string) ([]string, error) {
+	// Only a run passes encoded workloads on; the rest splits lines.
+	encoding, err := workloadEncodingOf(path)
+	if err != nil {
+		return nil, err
+	}
+
+	if encoding != plainJSONL {
+		return nil, fmt.Errorf("scan workload %s: %w", path, errEncodedWorkload)
+	}
+
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, fmt.Errorf("open workload %s: %w", path, err)
+	}
+	defer f.Close()
+
+	ops, err := workload.ScanOps(f)
+	if err != nil {
+		return nil, fmt.Errorf("scan workload %s: %w", path, err)
+	}
+
+	return ops, nil
+}
+
+// workloadEncoding is how a workload file is stored, as the harnesses
+// tell it apart by its first bytes.
+type workloadEncoding struct {
+	// compression is "gzip", "zstd", or "" for none.
+	compression string
+	// format is "jsonl" or "cbor", or "" when compressed, since only the
+	// harness sees the decompressed bytes.
+	format string
+}
+
+var plainJSONL = workloadEncoding{format: "jsonl"}
+
+func workloadEncodingOf(path string) (workloadEncoding, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return workloadEncoding{}, fmt.Errorf("open workload %s: %w", path, err)
+	}
+	defer f.Close()
+
+	codec, err := workload.Compression(f)
+	if err != nil {
+		return workloadEncoding{}, fmt.Errorf("read workload %s: %w", path, err)
+	}
+
+	if codec != "" {
+		return workloadEncoding{compression: codec}, nil
+	}
+
+	if _, err := f.Seek(0, io.SeekStart); err != nil {
+		return workloadEncoding{}, fmt.Errorf("read workload %s: %w", path, err)
+	}
+
+	cbor, err := workload.IsCBOR(f)
+	if err != nil {
+		return workloadEncoding{}, fmt.Errorf("read workload %s: %w", path, err)
+	}
+
+	if cbor {
+		return workloadEncoding{format: "cbor"}, nil
+	}
+
+	return plainJSONL, nil
 }
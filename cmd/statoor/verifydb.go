@@ -0,0 +1,193 @@
+package main
+
+import (
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"log/slog"
+	"os"
+	"time"
+
+	"github.com/spf13/cobra"
+	"github.com/weiihann/statoor/harness"
+)
+
+// errDBsDiffer fails verify-db when the databases disagree, so it exits
+// nonzero.
+var errDBsDiffer = errors.New("databases differ")
+
+type verifyDBConfig struct {
+	dbs          map[string]*string
+	keys         string
+	samples      int
+	seed         int64
+	harnessesDir string
+	skipBuild    bool
+	output       string
+}
+
+func newVerifyDBCmd(logger *slog.Logger) *cobra.Command {
+	cfg := verifyDBConfig{dbs: make(map[string]*string, len(harness.VerifyClients))}
+
+	cmd := &cobra.Command{
+		Use:   "verify-db",
+		Short: "Check that kept databases of several clients hold the same state",
+		Long: `Open the database each client left behind read-only and compare their
+state roots, reth's recomputed from its hashed tables and ethrex's read from the
+stored root node. With --keys, also read a sample of accounts and slots named in
+that JSONL file, such as the workload the databases were built from, from every
+database and report the ones that differ. Nothing is rerun. The JSON verdict goes
+to --output; the command exits nonzero when the databases differ.`,
+		RunE: func(cmd *cobra.Command, _ []string) error {
+			if cfg.samples < 0 {
+				return fmt.Errorf("--samples must not be negative, got %d", cfg.samples)
+			}
+
+			return verifyDB(cmd.Context(), logger, cfg)
+		},
+	}
+
+	for _, client := range harness.VerifyClients {
+		cfg.dbs[client] = cmd.Flags().String(client, "",
+			fmt.Sprintf("Database directory a %s run kept", client))
+	}
+
+	cmd.Flags().StringVar(&cfg.keys, "keys", "",
+		"JSONL file to sample read keys from, e.g. the workload (default: roots only)")
+	cmd.Flags().IntVar(&cfg.samples, "samples", 1000,
+		"Accounts and slots to sample from --keys")
+	cmd.Flags().Int64Var(&cfg.seed, "seed", 1,
+		"Seed of the key sample")
+	cmd.Flags().StringVar(&cfg.harnessesDir, "harnesses-dir", "",
+		"Path to harnesses directory (default: ./harnesses)")
+	cmd.Flags().BoolVar(&cfg.skipBuild, "skip-build", false,
+		"Skip building harness binaries")
+	cmd.Flags().StringVar(&cfg.output, "output", "-",
+		"Where to write the JSON verdict (- for stdout)")
+
+	return cmd
+}
+
+func verifyDB(ctx context.Context, logger *slog.Logger, cfg verifyDBConfig) error {
+	start := time.Now()
+
+	var clients []string
+
+	for _, client := range harness.VerifyClients {
+		if *cfg.dbs[client] != "" {
+			clients = append(clients, client)
+		}
+	}
+
+	if len(clients) == 0 {
+		return fmt.Errorf("name at least one database, with --%s", harness.VerifyClients[0])
+	}
+
+	var queries []harness.Query
+
+	if cfg.keys != "" {
+		f, err := os.Open(cfg.keys)
+		if err != nil {
+			return fmt.Errorf("open keys %s: %w", cfg.keys, err)
+		}
+
+		queries, err = harness.SampleQueries(f, cfg.samples, cfg.seed)
+		f.Close()
+
+		if err != nil {
+			return fmt.Errorf("sample keys from %s: %w", cfg.keys, err)
+		}
+	}
+
+	binaries, err := buildHarnesses(ctx, logger, cfg.harnessesDir, clients, cfg.skipBuild)
+	if err != nil {
+		return err
+	}
+
+	verdict := harness.DBVerdict{Samples: len(queries)}
+	responses := make(map[string][]harness.QueryResponse, len(clients))
+
+	for _, client := range clients {
+		dbDir := *cfg.dbs[client]
+		cmdCfg := harness.WrapCommand(client, binaries[client])
+		runner := harness.NewRunner(client, cmdCfg.Binary, cmdCfg.ExtraArgs, cmdCfg.Env, logger)
+		check := harness.DBCheck{Client: client, DB: dbDir}
+
+		mark := time.Now()
+
+		check.StateRoot, err = runner.StoredRoot(ctx, dbDir)
+		if err != nil {
+			return err
+		}
+
+		check.RootTimeMs = time.Since(mark).Milliseconds()
+
+		if len(queries) > 0 {
+			mark = time.Now()
+
+			responses[client], err = runner.Query(ctx, dbDir, queries)
+			if err != nil {
+				return err
+			}
+
+			check.QueryTimeMs = time.Since(mark).Milliseconds()
+		}
+
+		logger.InfoContext(ctx, "read database",
+			slog.String("client", client),
+			slog.String("state_root", check.StateRoot),
+		)
+
+		verdict.Clients = append(verdict.Clients, check)
+	}
+
+	verdict.RootsMatch = harness.CompareRoots(verdict.Clients)
+	if len(responses) > 0 {
+		verdict.Mismatches = harness.CompareResponses(queries, responses)
+	}
+
+	verdict.ElapsedMs = time.Since(start).Milliseconds()
+
+	if err := writeVerdict(cfg.output, verdict); err != nil {
+		return err
+	}
+
+	if !verdict.Match() {
+		return fmt.Errorf("%w: %d of %d samples differ, roots match: %t", errDBsDiffer,
+			len(verdict.Mismatches), verdict.Samples, verdict.RootsMatch)
+	}
+
+	return nil
+}
+
+func writeVerdict(path string, verdict harness.DBVerdict) error {
+	if path == "-" {
+		return encodeVerdict(os.Stdout, verdict)
+	}
+
+	f, err := os.Create(path)
+	if err != nil {
+		return fmt.Errorf("create %s: %w", path, err)
+	}
+
+	if err := encodeVerdict(f, verdict); err != nil {
+		f.Close()
+
+		return err
+	}
+
+	return f.Close()
+}
+
+func encodeVerdict(w io.Writer, verdict harness.DBVerdict) error {
+	enc := json.NewEncoder(w)
+	enc.SetIndent("", "  ")
+
+	if err := enc.Encode(verdict); err != nil {
+		return fmt.Errorf("write verdict: %w", err)
+	}
+
+	return nil
+}
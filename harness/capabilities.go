@@ -0,0 +1,134 @@
+package harness
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"log/slog"
+	"os"
+	"os/exec"
+	"slices"
+	"strings"
+	"time"
+)
+
+// capabilitiesTimeout bounds how long a harness may take to report its
+// capabilities.
+const capabilitiesTimeout = 30 * time.Second
+
+// legacyOps are the operations every harness build supports, assumed
+// for harnesses that do not implement --capabilities.
+var legacyOps = []string{"create_account", "set_code", "set_storage", "compute_root"}
+
+// legacyFormats are the workload encodings of harnesses that do not list
+// theirs.
+var legacyFormats = []string{"jsonl"}
+
+// Capabilities describes the workload protocol a harness build speaks.
+type Capabilities struct {
+	ProtocolVersion int      `json:"protocol_version"`
+	Ops             []string `json:"ops"`
+	Formats         []string `json:"formats"`
+	// Compression lists the codecs the harness decompresses on stdin.
+	Compression []string `json:"compression"`
+}
+
+// Capabilities asks the harness for its supported operations. Harnesses
+// without --capabilities are assumed to support the original operation
+// set only.
+func (r *Runner) Capabilities(ctx context.Context) Capabilities {
+	ctx, cancel := context.WithTimeout(ctx, capabilitiesTimeout)
+	defer cancel()
+
+	// Stdin stays empty, so an older build that ignores the flag
+	// fails on the missing workload instead of waiting for input.
+	args := append(slices.Clone(r.ExtraArgs), "--capabilities")
+	cmd := exec.CommandContext(ctx, r.BinaryPath, args...)
+
+	if len(r.Env) > 0 {
+		cmd.Env = append(os.Environ(), r.Env...)
+	}
+
+	out, err := cmd.Output()
+	if err == nil {
+		var caps Capabilities
+		if err = json.Unmarshal(out, &caps); err == nil {
+			if len(caps.Formats) == 0 {
+				caps.Formats = legacyFormats
+			}
+
+			return caps
+		}
+	}
+
+	r.Logger.Debug("no capabilities reported, assuming legacy operations",
+		slog.String("error", err.Error()),
+	)
+
+	return Capabilities{Ops: legacyOps, Formats: legacyFormats}
+}
+
+// CheckFormat fails if any harness cannot read a workload in format,
+// naming those harnesses.
+func CheckFormat(format string, caps map[string]Capabilities) error {
+	return checkListed(format, caps, func(c Capabilities) []string { return c.Formats })
+}
+
+// CheckCompression fails if any harness cannot decompress a workload
+// compressed with codec, naming those harnesses.
+func CheckCompression(codec string, caps map[string]Capabilities) error {
+	return checkListed(codec, caps, func(c Capabilities) []string { return c.Compression })
+}
+
+func checkListed(name string, caps map[string]Capabilities, list func(Capabilities) []string) error {
+	var unsupported []string
+
+	for client, c := range caps {
+		if !slices.Contains(list(c), name) {
+			unsupported = append(unsupported, client)
+		}
+	}
+
+	if len(unsupported) > 0 {
+		slices.Sort(unsupported)
+
+		return fmt.Errorf("%s workload unsupported by %s", name, strings.Join(unsupported, ", "))
+	}
+
+	return nil
+}
+
+// CheckOps fails if any harness lacks an operation the workload uses,
+// naming the unsupported operations per harness.
+func CheckOps(ops []string, caps map[string]Capabilities) error {
+	clients := make([]string, 0, len(caps))
+	for client := range caps {
+		clients = append(clients, client)
+	}
+
+	slices.Sort(clients)
+
+	var problems []string
+
+	for _, client := range clients {
+		var missing []string
+
+		for _, op := range ops {
+			if !slices.Contains(caps[client].Ops, op) {
+				missing = append(missing, op)
+			}
+		}
+
+		if len(missing) > 0 {
+			problems = append(problems,
+				fmt.Sprintf("%s (%s)", client, strings.Join(missing, ", ")))
+		}
+	}
+
+	if len(problems) > 0 {
+		return fmt.Errorf("workload uses operations unsupported by %s",
+			strings.Join(problems, "; "))
+	}
+
+	return nil
+}
@@ -0,0 +1,65 @@
+package harness
+
+import (
+	"slices"
+	"strings"
+	"testing"
+)
+
+func TestCheckOpsNamesUnsupportedPerHarness(t *testing.T) {
+	ops := []string{"compute_root", "create_account", "delete_account", "set_storage"}
+	caps := map[string]Capabilities{
+		"reth": {ProtocolVersion: 1, Ops: append(slices.Clone(legacyOps), "delete_account")},
+		"geth": {Ops: legacyOps},
+	}
+
+	err := CheckOps(ops, caps)
+	if err == nil {
+		t.Fatal("expected error for mismatched harness")
+	}
+
+	if !strings.Contains(err.Error(), "geth (delete_account)") {
+		t.Errorf("err = %q, want geth named with delete_account", err)
+	}
+
+	if strings.Contains(err.Error(), "reth") {
+		t.Errorf("err = %q, reth supports every op", err)
+	}
+}
+
+func TestCheckOpsAcceptsSupportedWorkload(t *testing.T) {
+	caps := map[string]Capabilities{"reth": {Ops: legacyOps}}
+	if err := CheckOps(legacyOps, caps); err != nil {
+		t.Errorf("CheckOps failed: %v", err)
+	}
+}
+
+func TestCheckFormatNamesHarnessesWithoutIt(t *testing.T) {
+	caps := map[string]Capabilities{
+		"reth":   {Formats: []string{"jsonl", "cbor"}},
+		"geth":   {Formats: legacyFormats},
+		"besu":   {Formats: legacyFormats},
+		"ethrex": {Formats: []string{"jsonl", "cbor"}},
+	}
+
+	if err := CheckFormat("jsonl", caps); err != nil {
+		t.Errorf("jsonl: %v", err)
+	}
+
+	err := CheckFormat("cbor", caps)
+	if err == nil || err.Error() != "cbor workload unsupported by besu, geth" {
+		t.Errorf("err = %v, want besu and geth named", err)
+	}
+}
+
+func TestCheckCompressionNeedsTheCodec(t *testing.T) {
+	caps := map[string]Capabilities{
+		"reth": {Compression: []string{"gzip", "zstd"}},
+		"geth": {},
+	}
+
+	err := CheckCompression("zstd", caps)
+	if err == nil || err.Error() != "zstd workload unsupported by geth" {
+		t.Errorf("err = %v, want geth named", err)
+	}
+}
@@ -4,20 +4,42 @@ import (
 	"bytes"
 	"context"
 	"encoding/json"
+	"errors"
 	"fmt"
 	"io"
 	"log/slog"
 	"os"
 	"os/exec"
 	"path/filepath"
+	"syscall"
 	"time"
 )
 
+// exitDiskFull is the exit code harnesses use when the write phase
+// stopped because the database filesystem ran out of space.
+const exitDiskFull = 4
+
+// exitRootMismatch is the exit code harnesses use when the root they
+// computed differs from the workload's expected_root. The result they
+// printed is complete, so the run is not a failure.
+const exitRootMismatch = 3
+
+// ErrDiskFull reports a run that stopped early because the database
+// filesystem filled up or dropped below the requested reserve.
+var ErrDiskFull = errors.New("disk full")
+
 // RunConfig holds parameters for a single harness execution.
 type RunConfig struct {
 	WorkloadPath string
 	DBDir        string
 	Timeout      time.Duration
+
+	// HarnessArgs are extra flags appended after --db.
+	HarnessArgs []string
+
+	// Replay, when set, paces workload delivery to match the timeline
+	// recorded in a previous run's event log.
+	Replay *ReplaySchedule
 }
 
 // Runner launches and manages a single harness binary.
@@ -55,7 +77,13 @@ func (r *Runner) Run(ctx context.Context, cfg RunConfig) (*Result, error) {
 		defer cancel()
 	}
 
-	dbDir := filepath.Join(cfg.DBDir, r.Name)
+	dbDir := DBPath(cfg.DBDir, r.Name)
+
+	// Cleaning the directory would delete a running harness's lock and
+	// database from under it.
+	if err := checkDBLock(dbDir); err != nil {
+		return nil, &RunError{Kind: FailureDBOpen, Err: err}
+	}
 
 	if err := os.RemoveAll(dbDir); err != nil {
 		return nil, fmt.Errorf("clean db dir %s: %w", dbDir, err)
@@ -65,9 +93,10 @@ func (r *Runner) Run(ctx context.Context, cfg RunConfig) (*Result, error) {
 		return nil, fmt.Errorf("create db dir %s: %w", dbDir, err)
 	}
 
-	args := make([]string, 0, len(r.ExtraArgs)+2)
+	args := make([]string, 0, len(r.ExtraArgs)+len(cfg.HarnessArgs)+2)
 	args = append(args, r.ExtraArgs...)
 	args = append(args, "--db", dbDir)
+	args = append(args, cfg.HarnessArgs...)
 
 	cmd := exec.CommandContext(ctx, r.BinaryPath, args...)
 
@@ -83,6 +112,17 @@ func (r *Runner) Run(ctx context.Context, cfg RunConfig) (*Result, error) {
 
 	cmd.Stdin = workloadFile
 
+	if cfg.Replay != nil {
+		pr, pw := io.Pipe()
+		defer pr.Close()
+
+		go func() {
+			pw.CloseWithError(cfg.Replay.Pace(ctx, pw, workloadFile))
+		}()
+
+		cmd.Stdin = pr
+	}
+
 	var stdout, stderr bytes.Buffer
 	cmd.Stdout = &stdout
 	cmd.Stderr = &stderr
@@ -94,11 +134,16 @@ func (r *Runner) Run(ctx context.Context, cfg RunConfig) (*Result, error) {
 
 	wallStart := time.Now()
 
-	if err := cmd.Run(); err != nil {
-		return nil, fmt.Errorf(
+	if err := cmd.Run(); err != nil && !rootMismatch(err) {
+		kind := classifyExit(ctx, err, stderr.String())
+		if kind == FailureDiskFull {
+			return nil, &RunError{Kind: kind, Err: diskFullError(r.Name, &stdout)}
+		}
+
+		return nil, &RunError{Kind: kind, Err: fmt.Errorf(
 			"harness %s failed: %w\nstderr: %s",
 			r.Name, err, stderr.String(),
-		)
+		)}
 	}
 
 	wallElapsed := time.Since(wallStart)
@@ -109,30 +154,107 @@ func (r *Runner) Run(ctx context.Context, cfg RunConfig) (*Result, error) {
 
 	result, err := parseResult(r.Name, &stdout)
 	if err != nil {
-		return nil, fmt.Errorf(
+		return nil, &RunError{Kind: FailureParse, Err: fmt.Errorf(
 			"parse %s output: %w\nstdout: %s",
 			r.Name, err, stdout.String(),
-		)
+		)}
 	}
 
-	dbSize, err := dirSize(dbDir)
+	usage, err := measureDB(dbDir)
 	if err != nil {
 		r.Logger.Warn("failed to measure db size",
 			slog.String("error", err.Error()),
 		)
 	}
 
-	result.DBSizeBytes = dbSize
+	result.ResultEmitMs = emitTime(stderr.Bytes())
+	result.DBSizeBytes = usage.apparent
+	result.DBApparentSizeBytes = usage.apparent
+	result.DBAllocatedSizeBytes = usage.allocated
 
 	return result, nil
 }
 
+// emitTime returns the result_emit_ms of the last emit line the harness
+// wrote to stderr, the one of the result parseResult keeps, or nil when
+// it wrote none.
+func emitTime(stderr []byte) *int64 {
+	var last *int64
+
+	for line := range bytes.Lines(stderr) {
+		if !bytes.HasPrefix(line, []byte(`{"result_emit_ms":`)) {
+			continue
+		}
+
+		var emit struct {
+			ResultEmitMs int64 `json:"result_emit_ms"`
+		}
+		if json.Unmarshal(line, &emit) == nil {
+			last = &emit.ResultEmitMs
+		}
+	}
+
+	return last
+}
+
+// rootMismatch reports whether err is the exit of a harness that ran to
+// completion but found a root other than the workload's expected_root.
+func rootMismatch(err error) bool {
+	var exitErr *exec.ExitError
+
+	return errors.As(err, &exitErr) && exitErr.ExitCode() == exitRootMismatch
+}
+
+func diskFullError(client string, stdout io.Reader) error {
+	var report DiskFullReport
+	if err := decodeLast(stdout, &report); err != nil {
+		return fmt.Errorf("harness %s: %w (no partial result: %v)", client, ErrDiskFull, err)
+	}
+
+	return fmt.Errorf(
+		"harness %s: %w during %s (%s) after writing %d bytes",
+		client, ErrDiskFull, report.Phase, report.Reason, report.DBBytesWritten,
+	)
+}
+
 func parseResult(client string, r io.Reader) (*Result, error) {
 	var result Result
-	if err := json.NewDecoder(r).Decode(&result); err != nil {
+	if err := decodeLast(r, &result); err != nil {
 		return nil, fmt.Errorf("decode JSON: %w", err)
 	}
 
+	if result.Error != "" {
+		return nil, fmt.Errorf("harness rejected its result: %s", result.Error)
+	}
+
+	// A stream that stopped at an intermediate root lost the run's line.
+	if result.Final != nil && !*result.Final {
+		return nil, errors.New("result stream ended before the final line of the run")
+	}
+
+	if result.ResultSidecar != "" {
+		sidecar := result.ResultSidecar
+		data, err := os.ReadFile(sidecar)
+		if err != nil {
+			return nil, fmt.Errorf("read result sidecar: %w", err)
+		}
+		result = Result{}
+		if err := json.Unmarshal(data, &result); err != nil {
+			return nil, fmt.Errorf("decode result sidecar %s: %w", sidecar, err)
+		}
+		result.ResultSidecar = sidecar
+	}
+
+	if result.BlocksSidecar != "" && len(result.Blocks) == 0 {
+		data, err := os.ReadFile(result.BlocksSidecar)
+		if err != nil {
+			return nil, fmt.Errorf("read blocks sidecar: %w", err)
+		}
+		if err := json.Unmarshal(data, &result.Blocks); err != nil {
+			return nil, fmt.Errorf("decode blocks sidecar %s: %w", result.BlocksSidecar, err)
+		}
+	}
+
 	if result.Client == "" {
 		result.Client = client
 	}
@@ -140,19 +262,61 @@ func parseResult(client string, r io.Reader) (*Result, error) {
 	return &result, nil
 }
 
-func dirSize(path string) (uint64, error) {
-	var size uint64
+// decodeLast decodes the last of the JSON values in r into v. A workload
+// with a compute_root before its end makes the harness print a result
+// for each root; the last is the run's.
+func decodeLast(r io.Reader, v any) error {
+	dec := json.NewDecoder(r)
+
+	var last json.RawMessage
+	for {
+		var value json.RawMessage
+
+		err := dec.Decode(&value)
+		if errors.Is(err, io.EOF) && last != nil {
+			break
+		}
+
+		if err != nil {
+			return err
+		}
+
+		last = value
+	}
+
+	return json.Unmarshal(last, v)
+}
+
+// dbUsage is the space the files below a database directory take: their
+// summed lengths, and the disk blocks allocated to them.
+type dbUsage struct {
+	apparent  uint64
+	allocated uint64
+}
+
+func measureDB(path string) (dbUsage, error) {
+	var usage dbUsage
 
 	err := filepath.Walk(path, func(_ string, info os.FileInfo, err error) error {
 		if err != nil {
 			return err
 		}
-		if !info.IsDir() {
-			size += uint64(info.Size())
+		if info.IsDir() {
+			return nil
+		}
+
+		usage.apparent += uint64(info.Size())
+
+		// st_blocks counts 512-byte units whatever the filesystem's
+		// block size.
+		if stat, ok := info.Sys().(*syscall.Stat_t); ok {
+			usage.allocated += uint64(stat.Blocks) * 512
+		} else {
+			usage.allocated += uint64(info.Size())
 		}
 
 		return nil
 	})
 
-	return size, err
+	return usage, err
 }
@@ -2,6 +2,10 @@ package harness
 
 import (
 	"bytes"
+	"errors"
+	"os"
+	"os/exec"
+	"path/filepath"
 	"strings"
 	"testing"
 )
@@ -61,6 +65,45 @@ func TestParseResultFillsClient(t *testing.T) {
 	}
 }
 
+func TestParseResultTakesTheLastRoot(t *testing.T) {
+	input := `{"state_root": "0x01", "accounts_created": 2}
+{"state_root": "0x02", "accounts_created": 4}
+`
+
+	result, err := parseResult("ethrex", strings.NewReader(input))
+	if err != nil {
+		t.Fatalf("parseResult failed: %v", err)
+	}
+
+	if result.StateRoot != "0x02" || result.AccountsCreated != 4 {
+		t.Errorf("result = %+v, want the second line", result)
+	}
+}
+
+func TestParseResultNeedsTheFinalMultiRootLine(t *testing.T) {
+	done := `{"state_root": "0x01", "root_index": 0, "final": false}
+{"state_root": "0x02", "root_index": 1, "final": true}
+`
+
+	result, err := parseResult("reth", strings.NewReader(done))
+	if err != nil {
+		t.Fatalf("parseResult failed: %v", err)
+	}
+
+	if result.RootIndex == nil || *result.RootIndex != 1 {
+		t.Errorf("root_index = %v, want 1", result.RootIndex)
+	}
+
+	for _, cut := range []string{
+		`{"state_root": "0x01", "root_index": 0, "final": false}`,
+		`{"state_root": "0x01", "final": false}`,
+	} {
+		if _, err := parseResult("reth", strings.NewReader(cut)); err == nil {
+			t.Errorf("expected an error for a stream without its final line: %s", cut)
+		}
+	}
+}
+
 func TestParseResultInvalidJSON(t *testing.T) {
 	input := `not json at all`
 	_, err := parseResult("test", strings.NewReader(input))
@@ -68,3 +111,136 @@ func TestParseResultInvalidJSON(t *testing.T) {
 		t.Error("expected error for invalid JSON")
 	}
 }
+
+func TestParseResultRejectedByHarness(t *testing.T) {
+	input := `{"client":"ethrex","error":"result field avg_bytes_per_op is null or non-finite"}`
+	_, err := parseResult("ethrex", strings.NewReader(input))
+	if err == nil || !strings.Contains(err.Error(), "avg_bytes_per_op") {
+		t.Errorf("err = %v, want the harness's reason", err)
+	}
+}
+
+func TestParseResultLoadsSidecar(t *testing.T) {
+	sidecar := filepath.Join(t.TempDir(), "reth.result.json")
+	full := `{"client":"reth","state_root":"0xabc","storage_slots":7,"artifacts":["trie.svg"]}`
+	if err := os.WriteFile(sidecar, []byte(full), 0o644); err != nil {
+		t.Fatal(err)
+	}
+
+	input := `{"client":"reth","state_root":"0xabc","result_sidecar":"` + sidecar + `"}`
+	result, err := parseResult("reth", strings.NewReader(input))
+	if err != nil {
+		t.Fatalf("parseResult failed: %v", err)
+	}
+	if result.StorageSlots != 7 || len(result.Artifacts) != 1 {
+		t.Errorf("result = %+v, want the sidecar's fields", result)
+	}
+	if result.ResultSidecar != sidecar {
+		t.Errorf("result_sidecar = %q, want %q", result.ResultSidecar, sidecar)
+	}
+}
+
+func TestParseResultLoadsBlocksSidecar(t *testing.T) {
+	sidecar := filepath.Join(t.TempDir(), "db.blocks.json")
+	blocks := `[{"state_root":"0x1","trie_time_ms":3,"db_write_time_ms":1,"ops":40},` +
+		`{"state_root":"0x2","trie_time_ms":5,"db_write_time_ms":2,"ops":60}]`
+	if err := os.WriteFile(sidecar, []byte(blocks), 0o644); err != nil {
+		t.Fatal(err)
+	}
+
+	input := `{"client":"reth","state_root":"0x2","rolling_window":1,` +
+		`"rolling_stats":[{"first_block":0,"blocks":1,"trie_time_ms":{"median":3,"p95":3},` +
+		`"db_write_time_ms":{"median":1,"p95":1},"ops":{"median":40,"p95":40}}],` +
+		`"blocks_sidecar":"` + sidecar + `"}`
+	result, err := parseResult("reth", strings.NewReader(input))
+	if err != nil {
+		t.Fatalf("parseResult failed: %v", err)
+	}
+	if len(result.Blocks) != 2 || result.Blocks[1].Ops != 60 {
+		t.Errorf("blocks = %+v, want the sidecar's two blocks", result.Blocks)
+	}
+	if len(result.RollingStats) != 1 || result.RollingStats[0].TrieTimeMs.Median != 3 {
+		t.Errorf("rolling_stats = %+v, want one window", result.RollingStats)
+	}
+}
+
+func TestEmitTimeTakesTheLastResult(t *testing.T) {
+	stderr := []byte(`{"phase":"reading","ops_applied":3}
+{"result_emit_ms":2,"result_bytes":400}
+reth-harness: state root does not match expected_root
+{"result_emit_ms":7,"result_bytes":410}
+`)
+
+	got := emitTime(stderr)
+	if got == nil || *got != 7 {
+		t.Errorf("emitTime = %v, want 7", got)
+	}
+
+	if got := emitTime([]byte("harness: starting\n")); got != nil {
+		t.Errorf("emitTime without an emit line = %d, want nil", *got)
+	}
+}
+
+func TestRootMismatchExitIsNotAFailure(t *testing.T) {
+	mismatch := exec.Command("sh", "-c", "exit 3").Run()
+	if !rootMismatch(mismatch) {
+		t.Errorf("exit 3 = %v, want a root mismatch", mismatch)
+	}
+
+	for _, err := range []error{exec.Command("false").Run(), errors.New("exit status 3")} {
+		if rootMismatch(err) {
+			t.Errorf("%v is not a root mismatch", err)
+		}
+	}
+}
+
+func TestDiskFullError(t *testing.T) {
+	input := `{"client":"reth","disk_full":true,"reason":"enospc","phase":"db_write","db_bytes_written":4096}`
+	err := diskFullError("reth", strings.NewReader(input))
+	if !errors.Is(err, ErrDiskFull) {
+		t.Fatalf("err = %v, want ErrDiskFull", err)
+	}
+	if !strings.Contains(err.Error(), "4096 bytes") {
+		t.Errorf("err = %q, want bytes written", err)
+	}
+}
+
+func TestMeasureDBSeparatesSparseFiles(t *testing.T) {
+	dir := t.TempDir()
+	if err := os.WriteFile(filepath.Join(dir, "dense"), bytes.Repeat([]byte{1}, 8192), 0o644); err != nil {
+		t.Fatal(err)
+	}
+
+	// A preallocated data file: 64 MiB long with no blocks written.
+	sparse, err := os.Create(filepath.Join(dir, "mdbx.dat"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	if err := sparse.Truncate(64 << 20); err != nil {
+		t.Fatal(err)
+	}
+	sparse.Close()
+
+	usage, err := measureDB(dir)
+	if err != nil {
+		t.Fatalf("measure: %v", err)
+	}
+
+	if usage.apparent != 64<<20+8192 {
+		t.Errorf("apparent = %d, want %d", usage.apparent, 64<<20+8192)
+	}
+	if usage.allocated < 8192 || usage.allocated >= 1<<20 {
+		t.Errorf("allocated = %d, want the dense file's blocks only", usage.allocated)
+	}
+
+	result := Result{DBSizeBytes: usage.apparent, DBApparentSizeBytes: usage.apparent, DBAllocatedSizeBytes: usage.allocated}
+	if !result.SparseDB() || result.ComparableDBSize() != usage.allocated {
+		t.Errorf("sparse = %v, comparable = %d", result.SparseDB(), result.ComparableDBSize())
+	}
+
+	logical := uint64(4096)
+	result.DBLogicalSizeBytes = &logical
+	if result.ComparableDBSize() != logical {
+		t.Errorf("comparable = %d, want the logical size", result.ComparableDBSize())
+	}
+}
@@ -0,0 +1,78 @@
+package harness
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"os"
+	"os/exec"
+	"slices"
+	"strings"
+	"time"
+)
+
+// healthCheckTimeout bounds how long a harness's --health-check may take.
+const healthCheckTimeout = 2 * time.Minute
+
+// HealthReport is what a harness's --health-check prints. Status is
+// "pass" or "fail". MissingCapabilities names optional host facilities
+// (e.g. "proc_io", "cgroup_memory_peak") whose metrics results will lack.
+type HealthReport struct {
+	Client              string        `json:"client"`
+	Status              string        `json:"status"`
+	Checks              []HealthCheck `json:"checks"`
+	MissingCapabilities []string      `json:"missing_capabilities,omitempty"`
+}
+
+// HealthCheck is one step of a health check: "run", "root",
+// "read_back" or "metrics".
+type HealthCheck struct {
+	Name  string `json:"name"`
+	OK    bool   `json:"ok"`
+	Error string `json:"error,omitempty"`
+}
+
+// Err names the failed checks, or returns nil when every check passed.
+func (h HealthReport) Err() error {
+	var failed []string
+
+	for _, c := range h.Checks {
+		if !c.OK {
+			failed = append(failed, fmt.Sprintf("%s: %s", c.Name, c.Error))
+		}
+	}
+
+	if len(failed) == 0 && h.Status == "pass" {
+		return nil
+	}
+
+	return fmt.Errorf("%s health check failed: %s", h.Client, strings.Join(failed, "; "))
+}
+
+// HealthCheck runs the harness's --health-check. A failing harness
+// still prints its report and exits nonzero; the report is returned
+// then too, with the failures in the error.
+func (r *Runner) HealthCheck(ctx context.Context) (*HealthReport, error) {
+	ctx, cancel := context.WithTimeout(ctx, healthCheckTimeout)
+	defer cancel()
+
+	args := append(slices.Clone(r.ExtraArgs), "--health-check")
+	cmd := exec.CommandContext(ctx, r.BinaryPath, args...)
+
+	if len(r.Env) > 0 {
+		cmd.Env = append(os.Environ(), r.Env...)
+	}
+
+	out, runErr := cmd.Output()
+
+	var report HealthReport
+	if err := json.Unmarshal(out, &report); err != nil {
+		if runErr != nil {
+			return nil, fmt.Errorf("health check %s: %w", r.Name, runErr)
+		}
+
+		return nil, fmt.Errorf("decode %s health report: %w", r.Name, err)
+	}
+
+	return &report, report.Err()
+}
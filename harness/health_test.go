@@ -0,0 +1,30 @@
+package harness
+
+import (
+	"encoding/json"
+	"strings"
+	"testing"
+)
+
+func TestHealthReportErrNamesFailedChecks(t *testing.T) {
+	var report HealthReport
+
+	line := `{"client":"reth","status":"fail","checks":[` +
+		`{"name":"run","ok":true},{"name":"root","ok":false,"error":"root 0x00"}` +
+		`],"missing_capabilities":["proc_io"]}`
+	if err := json.Unmarshal([]byte(line), &report); err != nil {
+		t.Fatalf("decode report: %v", err)
+	}
+
+	err := report.Err()
+	if err == nil || !strings.Contains(err.Error(), "reth health check failed: root: root 0x00") {
+		t.Errorf("Err() = %v, want the failed root check", err)
+	}
+
+	report.Status = "pass"
+	report.Checks = report.Checks[:1]
+
+	if err := report.Err(); err != nil {
+		t.Errorf("Err() = %v for a passing report", err)
+	}
+}
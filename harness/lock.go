@@ -0,0 +1,69 @@
+package harness
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"syscall"
+)
+
+// LockFile is the advisory lock the Rust harnesses hold in their
+// database directory while they run.
+const LockFile = "statoor.lock"
+
+// lockHolder is the content of a LockFile.
+type lockHolder struct {
+	PID    int    `json:"pid"`
+	Client string `json:"client"`
+}
+
+// DBPath returns the database directory Run uses for client below base.
+func DBPath(base, client string) string {
+	return filepath.Join(base, client)
+}
+
+// CheckDBPaths rejects client lists whose database directories below
+// base would coincide, e.g. because a client is listed twice.
+func CheckDBPaths(base string, clients []string) error {
+	owners := make(map[string]string, len(clients))
+
+	for _, client := range clients {
+		path, err := filepath.Abs(DBPath(base, client))
+		if err != nil {
+			return fmt.Errorf("resolve db dir for %s: %w", client, err)
+		}
+
+		if owner, taken := owners[path]; taken {
+			return fmt.Errorf("clients %s and %s would share db dir %s", owner, client, path)
+		}
+
+		owners[path] = client
+	}
+
+	return nil
+}
+
+// checkDBLock returns an error when dir holds a LockFile of a process
+// that is still alive. Missing, unreadable and stale locks pass.
+func checkDBLock(dir string) error {
+	data, err := os.ReadFile(filepath.Join(dir, LockFile))
+	if err != nil {
+		return nil
+	}
+
+	var holder lockHolder
+	if err := json.Unmarshal(data, &holder); err != nil || holder.PID <= 0 {
+		return nil
+	}
+
+	// Signal 0 only checks that the process exists; EPERM means it does
+	// but belongs to another user.
+	err = syscall.Kill(holder.PID, 0)
+	if err != nil && !errors.Is(err, syscall.EPERM) {
+		return nil
+	}
+
+	return fmt.Errorf("db dir %s is locked by %s (pid %d)", dir, holder.Client, holder.PID)
+}
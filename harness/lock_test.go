@@ -0,0 +1,45 @@
+package harness
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestCheckDBPathsRejectsSharedDirs(t *testing.T) {
+	if err := CheckDBPaths("tmp", []string{"reth", "ethrex"}); err != nil {
+		t.Errorf("distinct clients rejected: %v", err)
+	}
+
+	err := CheckDBPaths("tmp", []string{"reth", "ethrex", "reth"})
+	if err == nil || !strings.Contains(err.Error(), "would share db dir") {
+		t.Errorf("duplicate client not rejected, err = %v", err)
+	}
+}
+
+func TestCheckDBLock(t *testing.T) {
+	dir := t.TempDir()
+	if err := checkDBLock(dir); err != nil {
+		t.Errorf("unlocked dir rejected: %v", err)
+	}
+
+	writeLock := func(pid int) {
+		body := fmt.Sprintf(`{"pid":%d,"client":"reth"}`, pid)
+		if err := os.WriteFile(filepath.Join(dir, LockFile), []byte(body), 0o644); err != nil {
+			t.Fatal(err)
+		}
+	}
+
+	writeLock(os.Getpid())
+	if err := checkDBLock(dir); err == nil {
+		t.Error("live lock not reported")
+	}
+
+	// Above the kernel's PID limit, so never alive.
+	writeLock(1 << 30)
+	if err := checkDBLock(dir); err != nil {
+		t.Errorf("stale lock rejected: %v", err)
+	}
+}
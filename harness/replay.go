@@ -0,0 +1,110 @@
+package harness
+
+import (
+	"bufio"
+	"context"
+	"encoding/json"
+	"fmt"
+	"io"
+	"os"
+	"time"
+)
+
+// replaySchemaVersion is the event log schema this package understands.
+const replaySchemaVersion = 1
+
+// replayEvent is one line of a harness --events-out log.
+type replayEvent struct {
+	V     int    `json:"v"`
+	Seq   uint64 `json:"seq"`
+	TUs   int64  `json:"t_us"`
+	Event string `json:"event"`
+}
+
+// ReplaySchedule holds the delivery offset of each workload line as
+// recorded in a previous run's event log.
+type ReplaySchedule struct {
+	offsets map[uint64]time.Duration
+}
+
+// LoadReplaySchedule parses an NDJSON event log written by a harness
+// run with --events-out.
+func LoadReplaySchedule(path string) (*ReplaySchedule, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, fmt.Errorf("open events %s: %w", path, err)
+	}
+	defer f.Close()
+
+	return parseReplaySchedule(f)
+}
+
+func parseReplaySchedule(r io.Reader) (*ReplaySchedule, error) {
+	sched := &ReplaySchedule{offsets: make(map[uint64]time.Duration)}
+
+	scanner := bufio.NewScanner(r)
+	lineNum := 0
+
+	for scanner.Scan() {
+		lineNum++
+
+		var ev replayEvent
+		if err := json.Unmarshal(scanner.Bytes(), &ev); err != nil {
+			return nil, fmt.Errorf("events line %d: %w", lineNum, err)
+		}
+
+		if ev.V != replaySchemaVersion {
+			return nil, fmt.Errorf(
+				"events line %d: unsupported schema version %d", lineNum, ev.V,
+			)
+		}
+
+		if ev.Event == "op" {
+			sched.offsets[ev.Seq] = time.Duration(ev.TUs) * time.Microsecond
+		}
+	}
+
+	if err := scanner.Err(); err != nil {
+		return nil, fmt.Errorf("read events: %w", err)
+	}
+
+	return sched, nil
+}
+
+// Pace copies workload lines from r to w, holding back each line until
+// its recorded offset has elapsed since the copy started. Lines without
+// a recorded event are delivered immediately.
+func (s *ReplaySchedule) Pace(ctx context.Context, w io.Writer, r io.Reader) error {
+	reader := bufio.NewReader(r)
+	start := time.Now()
+
+	for seq := uint64(1); ; seq++ {
+		line, err := reader.ReadBytes('\n')
+		if len(line) > 0 {
+			if offset, ok := s.offsets[seq]; ok {
+				if wait := offset - time.Since(start); wait > 0 {
+					timer := time.NewTimer(wait)
+					select {
+					case <-ctx.Done():
+						timer.Stop()
+
+						return ctx.Err()
+					case <-timer.C:
+					}
+				}
+			}
+
+			if _, werr := w.Write(line); werr != nil {
+				return fmt.Errorf("write workload line %d: %w", seq, werr)
+			}
+		}
+
+		if err == io.EOF {
+			return nil
+		}
+
+		if err != nil {
+			return fmt.Errorf("read workload: %w", err)
+		}
+	}
+}
@@ -0,0 +1,54 @@
+package harness
+
+import (
+	"bytes"
+	"context"
+	"strings"
+	"testing"
+	"time"
+)
+
+func TestParseReplaySchedule(t *testing.T) {
+	input := `{"v":1,"seq":1,"t_us":0,"event":"op","op":"create_account"}
+{"v":1,"seq":2,"t_us":1500,"event":"op","op":"compute_root"}
+`
+
+	sched, err := parseReplaySchedule(strings.NewReader(input))
+	if err != nil {
+		t.Fatalf("parseReplaySchedule failed: %v", err)
+	}
+
+	if got := sched.offsets[2]; got != 1500*time.Microsecond {
+		t.Errorf("offset of seq 2 = %v, want 1.5ms", got)
+	}
+}
+
+func TestParseReplayScheduleRejectsUnknownVersion(t *testing.T) {
+	input := `{"v":2,"seq":1,"t_us":0,"event":"op"}`
+
+	if _, err := parseReplaySchedule(strings.NewReader(input)); err == nil {
+		t.Error("expected error for unknown schema version")
+	}
+}
+
+func TestPaceDelaysLines(t *testing.T) {
+	sched := &ReplaySchedule{offsets: map[uint64]time.Duration{
+		2: 20 * time.Millisecond,
+	}}
+	workload := "{\"op\":\"create_account\"}\n{\"op\":\"compute_root\"}\n"
+
+	var out bytes.Buffer
+
+	start := time.Now()
+	if err := sched.Pace(context.Background(), &out, strings.NewReader(workload)); err != nil {
+		t.Fatalf("Pace failed: %v", err)
+	}
+
+	if out.String() != workload {
+		t.Errorf("paced output = %q, want %q", out.String(), workload)
+	}
+
+	if elapsed := time.Since(start); elapsed < 20*time.Millisecond {
+		t.Errorf("Pace returned after %v, want at least 20ms", elapsed)
+	}
+}
@@ -1,6 +1,8 @@
 // Package harness manages execution of per-client state benchmark binaries.
 package harness
 
+import "slices"
+
 // Result holds the structured output from a harness execution.
 type Result struct {
 	Client           string `json:"client"`
@@ -13,4 +15,555 @@ type Result struct {
 	DBWriteTimeMs    int64  `json:"db_write_time_ms"`
 	PeakMemoryBytes  uint64 `json:"peak_memory_bytes"`
 	DBSizeBytes      uint64 `json:"db_size_bytes"`
+
+	// DBApparentSizeBytes sums the file lengths below the database
+	// directory, as DBSizeBytes does, and DBAllocatedSizeBytes the disk
+	// blocks those files occupy, which is less for sparse or
+	// preallocated files such as an MDBX data file grown to its geometry.
+	// DBLogicalSizeBytes is the space the backend reports in use: MDBX's
+	// used pages, RocksDB's SST files. Compare clients by ComparableDBSize.
+	DBApparentSizeBytes  uint64  `json:"db_apparent_size_bytes,omitempty"`
+	DBAllocatedSizeBytes uint64  `json:"db_allocated_size_bytes,omitempty"`
+	DBLogicalSizeBytes   *uint64 `json:"db_logical_size_bytes,omitempty"`
+
+	// SleepMs is the time the harness spent in sleep lines, which
+	// ElapsedMs leaves out.
+	SleepMs int64 `json:"sleep_ms,omitempty"`
+
+	// Blocks lists the blocks of a workload split by commit_block, in
+	// order. TrieTimeMs and DBWriteTimeMs are then their sums, and
+	// StateRoot is the last block's root.
+	Blocks []Block `json:"blocks,omitempty"`
+
+	// RollingStats holds the median and p95 of each block metric over
+	// every RollingWindow consecutive blocks, and BlockPercentiles their
+	// percentiles over all blocks. Past one window the harness writes
+	// Blocks to BlocksSidecar instead, and parseResult loads them back.
+	// BlockSampleEvery is set when a run had too many blocks to keep:
+	// Blocks and both stats then cover every that many blocks and the last.
+	RollingWindow    int               `json:"rolling_window,omitempty"`
+	BlockSampleEvery uint64            `json:"block_sample_every,omitempty"`
+	RollingStats     []WindowStats     `json:"rolling_stats,omitempty"`
+	BlockPercentiles *BlockPercentiles `json:"block_percentiles,omitempty"`
+	BlocksSidecar    string            `json:"blocks_sidecar,omitempty"`
+
+	// RootMatch is set when the workload's compute_root carried an
+	// expected_root: whether StateRoot equals it.
+	RootMatch *bool `json:"root_match,omitempty"`
+
+	// Final marks the run's own result line, after one line per
+	// intermediate compute_root. RootIndex numbers the lines of a harness
+	// run with --multi-root.
+	RootIndex *int64 `json:"root_index,omitempty"`
+	Final     *bool  `json:"final,omitempty"`
+
+	// Warnings lists tolerated input irregularities reported by the
+	// harness, such as a stripped byte order mark.
+	Warnings []string `json:"warnings,omitempty"`
+
+	// CodeUpdates counts set_code operations on an address that already
+	// had code; EmptyCodeSets counts set_code with zero-length code.
+	// Neither is included in ContractsCreated.
+	CodeUpdates   int `json:"code_updates,omitempty"`
+	EmptyCodeSets int `json:"empty_code_sets,omitempty"`
+
+	// DelegationsCreated counts set_delegation operations, which deploy
+	// an EIP-7702 designation rather than a contract. CodeBytes is the
+	// code deployed in all, designations included.
+	DelegationsCreated int   `json:"delegations_created,omitempty"`
+	CodeBytes          int64 `json:"code_bytes,omitempty"`
+
+	// AccountsDeleted counts delete_account operations. AccountsCreated
+	// still includes the accounts deleted afterwards.
+	AccountsDeleted int `json:"accounts_deleted,omitempty"`
+
+	// Selfdestructs counts selfdestruct operations, which are not
+	// included in AccountsDeleted.
+	Selfdestructs int `json:"selfdestructs,omitempty"`
+
+	// StorageClears counts clear_storage operations.
+	StorageClears int `json:"storage_clears,omitempty"`
+
+	// BalanceUpdates counts update_balance operations and Transfers
+	// transfer operations; a transfer is not also two balance updates.
+	BalanceUpdates int `json:"balance_updates,omitempty"`
+	Transfers      int `json:"transfers,omitempty"`
+
+	// NonceIncrements totals the nonce bumps of increment_nonce
+	// operations, each adding its count.
+	NonceIncrements int `json:"nonce_increments,omitempty"`
+
+	// AccountsTouched counts touch_account operations. EmptyAccountsPruned
+	// counts the empty accounts a harness run with --prune-empty-accounts
+	// removed at commit instead of storing.
+	AccountsTouched     int `json:"accounts_touched,omitempty"`
+	EmptyAccountsPruned int `json:"empty_accounts_pruned,omitempty"`
+
+	// Simulated is set when the harness injected artificial latency,
+	// so the timings do not reflect real hardware.
+	Simulated              bool   `json:"simulated,omitempty"`
+	InjectedLatencyTotalMs *int64 `json:"injected_latency_total_ms,omitempty"`
+	DBWriteTimeNetMs       *int64 `json:"db_write_time_net_ms,omitempty"`
+
+	// Durability states what the DB write time covers: "durable" when
+	// data was fsynced before the write phase ended, "os_buffered" when
+	// the commit returned with data only handed to the OS.
+	// PostCommitFlushMs is the separately timed fsync after commit.
+	Durability        string `json:"durability,omitempty"`
+	PostCommitFlushMs *int64 `json:"post_commit_flush_ms,omitempty"`
+
+	// DurabilityProbe is the outcome of --durability-probe: whether a
+	// child process reopening the committed database found the same root.
+	DurabilityProbe *DurabilityProbe `json:"durability_probe,omitempty"`
+
+	// CodeRead is the outcome of --read-code: code fetched by hash
+	// through the client's read API once the state was committed.
+	CodeRead *CodeRead `json:"code_read,omitempty"`
+
+	// RecomputeTimesMs times each --recompute-root computation of the
+	// root over the unchanged committed state, the cold one first.
+	// RecomputeColdWarmRatio is the cold time over the median warm one.
+	// RecomputePageCacheKept says why the cold one ran without dropping
+	// the OS page cache.
+	RecomputeTimesMs       []int64  `json:"recompute_times_ms,omitempty"`
+	RecomputeColdWarmRatio *float64 `json:"recompute_cold_warm_ratio,omitempty"`
+	RecomputePageCacheKept string   `json:"recompute_page_cache_kept,omitempty"`
+
+	// Attempts lists every try of this run, failed ones with their kind,
+	// when the orchestrator had to retry it. The other fields come from
+	// the first successful attempt.
+	Attempts []Attempt `json:"attempts,omitempty"`
+
+	// TruncatedAtOp is set when the harness stopped after --max-ops
+	// operations; counters and the root cover only that prefix.
+	TruncatedAtOp *int64 `json:"truncated_at_op,omitempty"`
+
+	// TimeBoxed is set when the harness ran for --duration-secs.
+	// OpsCompleted is how many operations it applied by then, and the
+	// counters and root cover only those. DeadlineReached is false when
+	// the workload ended first.
+	TimeBoxed       bool   `json:"time_boxed,omitempty"`
+	OpsCompleted    *int64 `json:"ops_completed,omitempty"`
+	DeadlineReached bool   `json:"deadline_reached,omitempty"`
+
+	// WorkloadFingerprint hashes the operation types and addresses the
+	// harness applied, in order, so results from differently encoded
+	// copies of one workload still match. FingerprintOps is how many
+	// operations it covers.
+	WorkloadFingerprint string `json:"workload_fingerprint,omitempty"`
+	FingerprintOps      int64  `json:"fingerprint_ops,omitempty"`
+
+	// Ordering is OrderingHashedKey when the orchestrator generated the
+	// workload sorted by hashed key, and empty for generation order. The
+	// harness does not report it.
+	Ordering string `json:"ordering,omitempty"`
+
+	// WorkloadName is the name the workload's header line gave it.
+	WorkloadName string `json:"workload_name,omitempty"`
+
+	// Reads counts the workload's read_account and read_storage lookups
+	// of the state as of the last commit_block, timed apart from writes.
+	// ReadMisses are reads of keys that do not exist.
+	Reads      int64 `json:"reads,omitempty"`
+	ReadTimeMs int64 `json:"read_time_ms,omitempty"`
+	ReadMisses int64 `json:"read_misses,omitempty"`
+
+	// ProofsGenerated counts the workload's get_proof operations, each a
+	// Merkle proof against the last committed root. ProofBytesTotal sums
+	// their encoded nodes; ProofExclusions are proofs of absent accounts.
+	ProofsGenerated int64 `json:"proofs_generated,omitempty"`
+	ProofTimeMs     int64 `json:"proof_time_ms,omitempty"`
+	ProofBytesTotal int64 `json:"proof_bytes_total,omitempty"`
+	ProofExclusions int64 `json:"proof_exclusions,omitempty"`
+
+	// ZeroAddressTouches, PrecompileTouches and AllOnesAddressTouches
+	// count the operations' references to the zero address, the
+	// precompiles 0x01 to 0x0a and 0xff...ff.
+	ZeroAddressTouches    int64 `json:"zero_address_touches,omitempty"`
+	PrecompileTouches     int64 `json:"precompile_touches,omitempty"`
+	AllOnesAddressTouches int64 `json:"all_ones_address_touches,omitempty"`
+
+	// Preloaded is set when the harness read and decoded the whole
+	// workload before timing anything, with --preload-workload. PreloadMs
+	// and PreloadRSSGrowthBytes are what that took, outside ElapsedMs.
+	Preloaded             bool    `json:"preloaded,omitempty"`
+	PreloadMs             float64 `json:"preload_ms,omitempty"`
+	PreloadBytes          uint64  `json:"preload_bytes,omitempty"`
+	PreloadOps            int64   `json:"preload_ops,omitempty"`
+	PreloadRSSGrowthBytes *uint64 `json:"preload_rss_growth_bytes,omitempty"`
+
+	// WorkloadCompression is the codec the harness decompressed stdin
+	// with. WorkloadBytesCompressed and WorkloadBytesRaw are the bytes
+	// read from stdin and the bytes decompressed from them.
+	WorkloadCompression     string `json:"workload_compression,omitempty"`
+	WorkloadBytesCompressed uint64 `json:"workload_bytes_compressed,omitempty"`
+	WorkloadBytesRaw        uint64 `json:"workload_bytes_raw,omitempty"`
+
+	// ResultEmitMs is how long the harness took to write the result line
+	// to stdout. The write comes after ElapsedMs is taken, so the harness
+	// reports it on stderr and the runner copies it here.
+	ResultEmitMs *int64 `json:"result_emit_ms,omitempty"`
+
+	// TrailerMatch reports whether the lines the harness read agree with
+	// the counts of the workload's trailer line, and is nil without one.
+	// TrailerDeltas and TrailerLinesDelta are read minus declared, for
+	// the operations and the line total that differ.
+	TrailerMatch      *bool            `json:"trailer_match,omitempty"`
+	TrailerDeltas     map[string]int64 `json:"trailer_deltas,omitempty"`
+	TrailerLinesDelta *int64           `json:"trailer_lines_delta,omitempty"`
+
+	// CanariesChecked counts the workload's canary lines, and
+	// CanariesFailed those whose leaf hash the harness encoded
+	// differently; CanaryFailedLines lists the first of them.
+	CanariesChecked   int64   `json:"canaries_checked,omitempty"`
+	CanariesFailed    int64   `json:"canaries_failed,omitempty"`
+	CanaryFailedLines []int64 `json:"canary_failed_lines,omitempty"`
+
+	// AssertionsChecked counts the fields the workload's assert_account
+	// and assert_storage lines checked, and AssertionFailures those that
+	// differed, which only a harness run with --assertions warn survives.
+	AssertionsChecked int64 `json:"assertions_checked,omitempty"`
+	AssertionFailures int64 `json:"assertion_failures,omitempty"`
+
+	// InputBytesByOp is the workload bytes each op type accounted for,
+	// with every line attributed whole to its op and line terminators
+	// excluded; AvgBytesPerOp is the mean line length.
+	InputBytesByOp map[string]uint64 `json:"input_bytes_by_op,omitempty"`
+	AvgBytesPerOp  *float64          `json:"avg_bytes_per_op,omitempty"`
+
+	// PerTenant counts the operations tagged with each tenant in a
+	// multi-tenant workload.
+	PerTenant map[uint32]uint64 `json:"per_tenant,omitempty"`
+
+	// UniqueStorageSlots is the number of distinct (address, slot) pairs
+	// written, reported by harnesses run with --unique-slots.
+	// StorageSlots counts every write.
+	UniqueStorageSlots *int `json:"unique_storage_slots,omitempty"`
+
+	// JSONDecodeMsEst and HexDecodeMsEst estimate the time spent parsing
+	// line JSON and converting hex field values. They are extrapolated
+	// from timing one operation in DecodeSampleEvery.
+	JSONDecodeMsEst   *int64 `json:"json_decode_ms_est,omitempty"`
+	HexDecodeMsEst    *int64 `json:"hex_decode_ms_est,omitempty"`
+	DecodeSampleEvery int    `json:"decode_sample_every,omitempty"`
+
+	// InputConsumptionOpsPerSec is operations per second of time the
+	// harness spent waiting for input, ApplyOpsPerSec per second of time
+	// spent decoding and applying them. When input consumption is the
+	// lower rate, the workload source was the bottleneck.
+	InputConsumptionOpsPerSec *int64 `json:"input_consumption_ops_per_sec,omitempty"`
+	ApplyOpsPerSec            *int64 `json:"apply_ops_per_sec,omitempty"`
+
+	// ApplyThroughputMinOpsPerSec is the slowest apply rate of any
+	// window of 4096 operations. A window 100x below the running average
+	// also adds a warning.
+	ApplyThroughputMinOpsPerSec *int64 `json:"apply_throughput_min_ops_per_sec,omitempty"`
+
+	// BackgroundCPUMs maps a phase to the CPU other threads used during
+	// it, reported only when that clearly exceeds the main thread's CPU
+	// (Linux only). Such phases come with a warning in Warnings.
+	BackgroundCPUMs map[string]int64 `json:"background_cpu_ms,omitempty"`
+
+	// SystemMetrics holds the process metrics the harness could read at
+	// each phase boundary, keyed by boundary then metric name.
+	SystemMetrics map[string]map[string]uint64 `json:"system_metrics,omitempty"`
+
+	// TableSizeBytes is the on-disk size of each table, reported by the
+	// reth harness with --full-tables.
+	TableSizeBytes map[string]uint64 `json:"table_size_bytes,omitempty"`
+
+	// RootAlgorithm names the state root implementation the reth harness
+	// used ("serial", "parallel" or "both"). RootThreads is the parallel
+	// worker count. ParallelTrieTimeMs is the parallel time when both ran.
+	RootAlgorithm      string `json:"root_algorithm,omitempty"`
+	RootThreads        *int   `json:"root_threads,omitempty"`
+	ParallelTrieTimeMs *int64 `json:"parallel_trie_time_ms,omitempty"`
+
+	// StorageTrieTimeMs and AccountTrieTimeMs split TrieTimeMs into its
+	// storage-trie and account-trie stages (ethrex with --two-stage-trie).
+	StorageTrieTimeMs *int64 `json:"storage_trie_time_ms,omitempty"`
+	AccountTrieTimeMs *int64 `json:"account_trie_time_ms,omitempty"`
+
+	// Phases maps "trie" and "db_write" to when they ran (ethrex). They
+	// overlap in a --pipelined run, which also reports Pipeline; there
+	// TrieTimeMs and DBWriteTimeMs are each side's busy time.
+	Phases   map[string]PhaseInterval `json:"phases,omitempty"`
+	Pipeline *Pipeline                `json:"pipeline,omitempty"`
+
+	// MemoryBudgetTriggeredAtOp is the operation after which the ethrex
+	// --max-trie-nodes-in-memory budget was first exceeded.
+	// MemoryBudgetStrategy says what the harness did: "spill_to_rocksdb"
+	// writes the staged updates to RocksDB and reopens the tries there,
+	// each time the budget is exceeded again. MemoryBudgetSpills counts
+	// the times.
+	MemoryBudgetTriggeredAtOp *int64 `json:"memory_budget_triggered_at_op,omitempty"`
+	MemoryBudgetStrategy      string `json:"memory_budget_strategy,omitempty"`
+	MemoryBudgetSpills        *int64 `json:"memory_budget_spills,omitempty"`
+
+	// FillCurve splits the trie time of a chunked trie phase (ethrex with
+	// --pipelined) by the fraction
+	// of the accounts applied, deciles unless --fill-curve-points says
+	// otherwise.
+	FillCurve []FillPoint `json:"fill_curve,omitempty"`
+
+	// SkippedPhases lists the phases an ethrex --load-updates run did not
+	// perform ("parse", "trie"); their counters and times are zero.
+	// UpdatesSource names the file the node set came from.
+	SkippedPhases []string       `json:"skipped_phases,omitempty"`
+	UpdatesSource *UpdatesSource `json:"updates_source,omitempty"`
+
+	// MeasurementModel describes what the timed phases of the run
+	// included (reth, ethrex).
+	MeasurementModel *MeasurementModel `json:"measurement_model,omitempty"`
+
+	// Artifacts lists files the harness wrote next to its result, such
+	// as the per-phase flamegraphs of --profile-out.
+	Artifacts []string `json:"artifacts,omitempty"`
+
+	// ResultSidecar is set when the full result was too large to print:
+	// stdout then carries only the required fields, and parseResult
+	// loads the rest from this file.
+	ResultSidecar string `json:"result_sidecar,omitempty"`
+
+	// Error is set instead of the metrics when the harness's result
+	// failed its own check (a non-finite metric or a missing field).
+	Error string `json:"error,omitempty"`
+
+	// Invocation holds every harness flag with its effective value,
+	// defaults included; flags left unset are omitted (reth, ethrex).
+	Invocation map[string]any `json:"invocation,omitempty"`
+
+	// Environment records host settings that skew timings (transparent
+	// huge pages, swap, major faults) at the start and end of the run.
+	Environment *Environment `json:"environment,omitempty"`
+}
+
+// DurabilityProbe reports a crash-consistency check. Status is "pass",
+// "fail" or "skipped"; Mode is "soft" (reopen in place) or "hard" (also
+// reopen a lock-free copy). Details explain anything but a pass.
+type DurabilityProbe struct {
+	Status  string   `json:"status"`
+	Mode    string   `json:"mode"`
+	Details []string `json:"details,omitempty"`
+	// Truncated is set when --verify-timeout-secs ran out before every
+	// check finished.
+	Truncated bool `json:"truncated,omitempty"`
+}
+
+// CodeRead reports --read-code. Codes is how many distinct codes the
+// sample was drawn from. Cold repeats the sample after the page cache was
+// dropped; ColdSkipped says why it is missing, usually that the harness
+// was not root. CodesTruncated is set when --iterate-timeout-secs cut
+// the listing of the codes short, and Truncated when
+// --read-bench-timeout-secs cut the passes short.
+type CodeRead struct {
+	Codes          int64         `json:"codes"`
+	CodesTruncated bool          `json:"codes_truncated,omitempty"`
+	Warm           CodeReadPass  `json:"warm"`
+	Cold           *CodeReadPass `json:"cold,omitempty"`
+	ColdSkipped    string        `json:"cold_skipped,omitempty"`
+	Truncated      bool          `json:"truncated,omitempty"`
+}
+
+// CodeReadPass times one pass over the sampled hashes. The percentiles
+// are of single reads, in microseconds.
+type CodeReadPass struct {
+	Reads          int64 `json:"reads"`
+	Misses         int64 `json:"misses"`
+	BytesRead      int64 `json:"bytes_read"`
+	CodeReadTimeMs int64 `json:"code_read_time_ms"`
+	P50Us          int64 `json:"p50_us"`
+	P90Us          int64 `json:"p90_us"`
+	P99Us          int64 `json:"p99_us"`
+	MaxUs          int64 `json:"max_us"`
+	// Truncated is set when the pass stopped before reading the whole
+	// sample; Reads counts the reads it made.
+	Truncated bool `json:"truncated,omitempty"`
+}
+
+// UpdatesSource identifies the --save-updates file a loaded run read:
+// its path and the keccak hash of its contents.
+type UpdatesSource struct {
+	Path   string `json:"path"`
+	Keccak string `json:"keccak"`
+}
+
+// MeasurementModel is what a harness measured, as derived from its
+// configuration. Phases lists the timed phases that ran, and Included
+// what each of them included, both in a vocabulary shared by the
+// harnesses. CodePaths names the client machinery behind them, which
+// differs between clients.
+type MeasurementModel struct {
+	Phases    []string                     `json:"phases"`
+	Included  map[string]map[string]string `json:"included,omitempty"`
+	CodePaths map[string]string            `json:"code_paths,omitempty"`
+}
+
+// Differences lists the keys in which m and o disagree about what was
+// measured, sorted: "phases" and "included.<phase>.<aspect>". Code paths
+// are not compared.
+func (m *MeasurementModel) Differences(o *MeasurementModel) []string {
+	var keys []string
+
+	if !slices.Equal(m.Phases, o.Phases) {
+		keys = append(keys, "phases")
+	}
+
+	seen := make(map[string]bool)
+
+	for _, included := range []map[string]map[string]string{m.Included, o.Included} {
+		for phase, aspects := range included {
+			for aspect := range aspects {
+				key := "included." + phase + "." + aspect
+				if seen[key] {
+					continue
+				}
+
+				seen[key] = true
+
+				if m.Included[phase][aspect] != o.Included[phase][aspect] {
+					keys = append(keys, key)
+				}
+			}
+		}
+	}
+
+	slices.Sort(keys)
+
+	return keys
+}
+
+// OrderingHashedKey marks a result run on a workload the generator
+// sorted by keccak(address) and keccak(slot).
+const OrderingHashedKey = "hashed-key"
+
+// Block is one committed block: the state root after it and the time
+// its trie update and DB write took. ApproxGas is the generator's cost
+// estimate from the block's commit_block, when it had one; Ops counts
+// the operations the block applied. BlockNumber is the block tag of its
+// lines in a workload exported from a chain.
+type Block struct {
+	StateRoot     string  `json:"state_root"`
+	TrieTimeMs    int64   `json:"trie_time_ms"`
+	DBWriteTimeMs int64   `json:"db_write_time_ms"`
+	ApproxGas     *uint64 `json:"approx_gas,omitempty"`
+	BlockNumber   *uint64 `json:"block_number,omitempty"`
+	Ops           int64   `json:"ops,omitempty"`
+}
+
+// WindowStats summarizes Blocks consecutive blocks starting at index
+// FirstBlock; the last window of a run may be shorter.
+type WindowStats struct {
+	FirstBlock    int    `json:"first_block"`
+	Blocks        int    `json:"blocks"`
+	TrieTimeMs    Spread `json:"trie_time_ms"`
+	DBWriteTimeMs Spread `json:"db_write_time_ms"`
+	Ops           Spread `json:"ops"`
+}
+
+// Spread is the median and p95 of one metric over a window.
+type Spread struct {
+	Median int64 `json:"median"`
+	P95    int64 `json:"p95"`
+}
+
+// BlockPercentiles holds the Percentiles of each block metric.
+type BlockPercentiles struct {
+	TrieTimeMs    Percentiles `json:"trie_time_ms"`
+	DBWriteTimeMs Percentiles `json:"db_write_time_ms"`
+	Ops           Percentiles `json:"ops"`
+}
+
+// Percentiles of one metric over every block of a run.
+type Percentiles struct {
+	P50 int64 `json:"p50"`
+	P90 int64 `json:"p90"`
+	P95 int64 `json:"p95"`
+	P99 int64 `json:"p99"`
+	Max int64 `json:"max"`
+}
+
+// PhaseInterval is a phase's start and end in ms since the run started.
+type PhaseInterval struct {
+	StartMs int64 `json:"start_ms"`
+	EndMs   int64 `json:"end_ms"`
+}
+
+// Pipeline describes an ethrex --pipelined run: the chunk count and
+// size, the writer queue's depth, how long the trie side waited on a
+// full queue, and the CPU of the main (trie) thread and of all others
+// (writer and RocksDB threads, Linux only).
+type Pipeline struct {
+	Chunks        int64  `json:"chunks"`
+	ChunkAccounts int64  `json:"chunk_accounts"`
+	QueueDepth    int    `json:"queue_depth"`
+	TrieBlockedMs int64  `json:"trie_blocked_ms"`
+	TrieCPUMs     *int64 `json:"trie_cpu_ms,omitempty"`
+	DBWriteCPUMs  *int64 `json:"db_write_cpu_ms,omitempty"`
+}
+
+// FillPoint is the trie time of the Accounts applied since the previous
+// point, ending at Fraction of all accounts.
+type FillPoint struct {
+	Fraction float64 `json:"fraction"`
+	Accounts int64   `json:"accounts"`
+	TrieMs   int64   `json:"trie_ms"`
+}
+
+// Environment holds EnvSnapshots taken at the start and end of a run.
+type Environment struct {
+	Start EnvSnapshot `json:"start"`
+	End   EnvSnapshot `json:"end"`
+}
+
+// EnvSnapshot is one reading of the host settings and counters.
+type EnvSnapshot struct {
+	THPEnabled     string  `json:"thp_enabled,omitempty"`
+	SwapTotalBytes *uint64 `json:"swap_total_bytes,omitempty"`
+	SwapUsedBytes  *uint64 `json:"swap_used_bytes,omitempty"`
+	MajorFaults    *uint64 `json:"major_faults,omitempty"`
+}
+
+// SwapGrowth returns how many bytes swap usage grew during the run, or
+// zero when it did not grow or was not reported.
+func (e *Environment) SwapGrowth() uint64 {
+	if e == nil || e.Start.SwapUsedBytes == nil || e.End.SwapUsedBytes == nil {
+		return 0
+	}
+
+	if *e.End.SwapUsedBytes <= *e.Start.SwapUsedBytes {
+		return 0
+	}
+
+	return *e.End.SwapUsedBytes - *e.Start.SwapUsedBytes
+}
+
+// ComparableDBSize returns the database size to compare clients by: the
+// logical size when the harness reported one, else the allocated size,
+// else the apparent size of results that predate both.
+func (r *Result) ComparableDBSize() uint64 {
+	switch {
+	case r.DBLogicalSizeBytes != nil:
+		return *r.DBLogicalSizeBytes
+	case r.DBAllocatedSizeBytes > 0:
+		return r.DBAllocatedSizeBytes
+	default:
+		return r.DBSizeBytes
+	}
+}
+
+// SparseDB reports whether less than half the apparent size of the
+// database files is allocated on disk, so DBSizeBytes overstates it.
+func (r *Result) SparseDB() bool {
+	return r.DBAllocatedSizeBytes > 0 && r.DBAllocatedSizeBytes < r.DBApparentSizeBytes/2
+}
+
+// DiskFullReport is the partial result printed by a harness that
+// exited with exitDiskFull.
+type DiskFullReport struct {
+	Reason           string  `json:"reason"`
+	Phase            string  `json:"phase"`
+	DBBytesWritten   uint64  `json:"db_bytes_written"`
+	FreeBytesAtStart *uint64 `json:"free_bytes_at_start,omitempty"`
+	Error            string  `json:"error,omitempty"`
 }
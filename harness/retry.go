@@ -0,0 +1,147 @@
+package harness
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os/exec"
+	"strings"
+	"time"
+)
+
+// FailureKind classifies why a harness run failed.
+type FailureKind string
+
+// Failure kinds. Only environmental failures are retryable; anything
+// that a rerun would reproduce, such as undecodable output, is not.
+// Root mismatches are never retried: both runs succeeded.
+const (
+	FailureDBOpen      FailureKind = "db_open"
+	FailureTimeout     FailureKind = "timeout"
+	FailureInterrupted FailureKind = "interrupted"
+	FailureDiskFull    FailureKind = "disk_full"
+	FailureParse       FailureKind = "parse"
+	FailureCanceled    FailureKind = "canceled"
+	FailureHarness     FailureKind = "harness"
+)
+
+// Retryable reports whether a failure of this kind may succeed on a
+// later attempt.
+func (k FailureKind) Retryable() bool {
+	switch k {
+	case FailureDBOpen, FailureTimeout, FailureInterrupted, FailureDiskFull:
+		return true
+	default:
+		return false
+	}
+}
+
+// dbOpenMarkers are the fatal messages harnesses print when they cannot
+// open their database, e.g. because a previous run still holds a lock.
+var dbOpenMarkers = []string{
+	"open mdbx", "open rocksdb", "open pebble", "open store", "open state trie",
+	"lock database",
+}
+
+// classifyExit picks the FailureKind of a harness process that exited
+// unsuccessfully.
+func classifyExit(ctx context.Context, err error, stderr string) FailureKind {
+	switch {
+	case errors.Is(ctx.Err(), context.DeadlineExceeded):
+		return FailureTimeout
+	case errors.Is(ctx.Err(), context.Canceled):
+		return FailureCanceled
+	}
+
+	var exitErr *exec.ExitError
+	if errors.As(err, &exitErr) {
+		switch {
+		case exitErr.ExitCode() == exitDiskFull:
+			return FailureDiskFull
+		case exitErr.ExitCode() == -1:
+			// Killed by a signal, e.g. the OOM killer or an operator.
+			return FailureInterrupted
+		}
+	}
+
+	for _, marker := range dbOpenMarkers {
+		if strings.Contains(stderr, marker) {
+			return FailureDBOpen
+		}
+	}
+
+	return FailureHarness
+}
+
+// RunError is a failed harness run together with its FailureKind.
+type RunError struct {
+	Kind FailureKind
+	Err  error
+}
+
+func (e *RunError) Error() string { return e.Err.Error() }
+
+func (e *RunError) Unwrap() error { return e.Err }
+
+// KindOf returns the FailureKind of err, or FailureHarness when err does
+// not carry one.
+func KindOf(err error) FailureKind {
+	var runErr *RunError
+	if errors.As(err, &runErr) {
+		return runErr.Kind
+	}
+
+	return FailureHarness
+}
+
+// Attempt records one try of a run. Kind and Error are empty for the
+// successful attempt, whose output is the Result that carries it.
+type Attempt struct {
+	Index int         `json:"attempt"`
+	Kind  FailureKind `json:"failure_kind,omitempty"`
+	Error string      `json:"error,omitempty"`
+}
+
+// RetryPolicy bounds how often and how fast a failed run is retried.
+// The wait before retry n (counting from 1) is BaseDelay * 2^(n-1).
+type RetryPolicy struct {
+	Retries   int
+	BaseDelay time.Duration
+}
+
+// Retry calls run until it succeeds, fails with a non-retryable kind, or
+// the policy's retries are used up. It returns the first successful
+// result and every attempt made, in order.
+func Retry(
+	ctx context.Context,
+	policy RetryPolicy,
+	run func(context.Context) (*Result, error),
+) (*Result, []Attempt, error) {
+	var attempts []Attempt
+
+	delay := policy.BaseDelay
+
+	for index := 1; ; index++ {
+		result, err := run(ctx)
+		if err == nil {
+			attempts = append(attempts, Attempt{Index: index})
+
+			return result, attempts, nil
+		}
+
+		kind := KindOf(err)
+		attempts = append(attempts, Attempt{Index: index, Kind: kind, Error: err.Error()})
+
+		if !kind.Retryable() || index > policy.Retries {
+			return nil, attempts, fmt.Errorf("attempt %d of %d: %w", index, policy.Retries+1, err)
+		}
+
+		select {
+		case <-ctx.Done():
+			return nil, attempts, fmt.Errorf("retry after %s: %w", kind, ctx.Err())
+		case <-time.After(delay):
+		}
+
+		delay *= 2
+	}
+}
@@ -0,0 +1,94 @@
+package harness
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os/exec"
+	"testing"
+)
+
+func TestRetryStopsAtFirstSuccess(t *testing.T) {
+	calls := 0
+	run := func(context.Context) (*Result, error) {
+		calls++
+		if calls == 1 {
+			return nil, &RunError{Kind: FailureDBOpen, Err: errors.New("open mdbx: busy")}
+		}
+
+		return &Result{Client: "reth", StateRoot: fmt.Sprintf("0x%d", calls)}, nil
+	}
+
+	result, attempts, err := Retry(context.Background(), RetryPolicy{Retries: 3}, run)
+	if err != nil {
+		t.Fatalf("Retry failed: %v", err)
+	}
+	if result.StateRoot != "0x2" || calls != 2 {
+		t.Errorf("root = %s after %d calls, want 0x2 after 2", result.StateRoot, calls)
+	}
+	if len(attempts) != 2 || attempts[0].Kind != FailureDBOpen || attempts[1].Kind != "" {
+		t.Errorf("attempts = %+v, want a db_open failure then a success", attempts)
+	}
+	if attempts[1].Index != 2 {
+		t.Errorf("success index = %d, want 2", attempts[1].Index)
+	}
+}
+
+func TestRetryNeverRetriesParseErrors(t *testing.T) {
+	calls := 0
+	run := func(context.Context) (*Result, error) {
+		calls++
+
+		return nil, &RunError{Kind: FailureParse, Err: errors.New("decode JSON")}
+	}
+
+	_, attempts, err := Retry(context.Background(), RetryPolicy{Retries: 3}, run)
+	if err == nil || calls != 1 || len(attempts) != 1 {
+		t.Errorf("err = %v after %d calls, want one failed attempt", err, calls)
+	}
+}
+
+func TestRetryGivesUpAfterPolicy(t *testing.T) {
+	calls := 0
+	run := func(context.Context) (*Result, error) {
+		calls++
+
+		return nil, &RunError{Kind: FailureTimeout, Err: context.DeadlineExceeded}
+	}
+
+	_, attempts, err := Retry(context.Background(), RetryPolicy{Retries: 2}, run)
+	if !errors.Is(err, context.DeadlineExceeded) {
+		t.Errorf("err = %v, want the last attempt's error", err)
+	}
+	if calls != 3 || len(attempts) != 3 {
+		t.Errorf("%d calls, %d attempts, want 3 each", calls, len(attempts))
+	}
+}
+
+func TestClassifyExit(t *testing.T) {
+	timedOut, cancel := context.WithTimeout(context.Background(), 0)
+	defer cancel()
+	<-timedOut.Done()
+
+	failed := exec.Command("false").Run()
+
+	tests := []struct {
+		name   string
+		ctx    context.Context
+		err    error
+		stderr string
+		want   FailureKind
+	}{
+		{"timeout", timedOut, failed, "", FailureTimeout},
+		{"db open", context.Background(), failed, "fatal: open rocksdb: lock held", FailureDBOpen},
+		{"other", context.Background(), failed, "fatal: unknown operation: x", FailureHarness},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			if got := classifyExit(tt.ctx, tt.err, tt.stderr); got != tt.want {
+				t.Errorf("classifyExit = %s, want %s", got, tt.want)
+			}
+		})
+	}
+}
@@ -0,0 +1,291 @@
+package harness
+
+import (
+	"bufio"
+	"bytes"
+	"context"
+	"encoding/json"
+	"fmt"
+	"io"
+	"math/big"
+	mrand "math/rand"
+	"os"
+	"os/exec"
+	"slices"
+	"strings"
+)
+
+// VerifyClients lists the harnesses that can open a kept database on
+// their own, with --probe-root-of and --query-db.
+var VerifyClients = []string{"reth", "ethrex"}
+
+// Query is a read a harness answers with --query-db, in the format of
+// its --stay-alive queries.
+type Query struct {
+	Op      string `json:"op"`
+	Address string `json:"address"`
+	Slot    string `json:"slot,omitempty"`
+}
+
+// QueryResponse is a harness's answer to one Query: Result on success,
+// Error otherwise.
+type QueryResponse struct {
+	Op     string          `json:"op"`
+	Result json.RawMessage `json:"result,omitempty"`
+	Error  string          `json:"error,omitempty"`
+}
+
+// StoredRoot returns the state root of the database in dbDir, as the
+// harness reads it back with --probe-root-of: reth recomputes it from
+// its hashed tables, ethrex hashes the stored root node.
+func (r *Runner) StoredRoot(ctx context.Context, dbDir string) (string, error) {
+	if err := checkDBLock(dbDir); err != nil {
+		return "", err
+	}
+
+	out, err := r.command(ctx, "--probe-root-of", dbDir).Output()
+	if err != nil {
+		return "", fmt.Errorf("read %s root: %w", r.Name, exitDetail(err))
+	}
+
+	return strings.TrimSpace(string(out)), nil
+}
+
+// Query answers queries against the database in dbDir with
+// --query-db, one response per query in the same order.
+func (r *Runner) Query(ctx context.Context, dbDir string, queries []Query) ([]QueryResponse, error) {
+	if err := checkDBLock(dbDir); err != nil {
+		return nil, err
+	}
+
+	var stdin bytes.Buffer
+
+	enc := json.NewEncoder(&stdin)
+	for _, q := range queries {
+		if err := enc.Encode(q); err != nil {
+			return nil, fmt.Errorf("encode query: %w", err)
+		}
+	}
+
+	cmd := r.command(ctx, "--query-db", dbDir)
+	cmd.Stdin = &stdin
+
+	out, err := cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("query %s: %w", r.Name, exitDetail(err))
+	}
+
+	responses := make([]QueryResponse, 0, len(queries))
+
+	scanner := bufio.NewScanner(bytes.NewReader(out))
+	scanner.Buffer(nil, 1<<20)
+
+	for scanner.Scan() {
+		var resp QueryResponse
+		if err := json.Unmarshal(scanner.Bytes(), &resp); err != nil {
+			return nil, fmt.Errorf("decode %s response %d: %w", r.Name, len(responses)+1, err)
+		}
+
+		responses = append(responses, resp)
+	}
+
+	if len(responses) != len(queries) {
+		return nil, fmt.Errorf("%s answered %d of %d queries", r.Name, len(responses), len(queries))
+	}
+
+	return responses, nil
+}
+
+func (r *Runner) command(ctx context.Context, args ...string) *exec.Cmd {
+	cmd := exec.CommandContext(ctx, r.BinaryPath, append(slices.Clone(r.ExtraArgs), args...)...)
+
+	if len(r.Env) > 0 {
+		cmd.Env = append(os.Environ(), r.Env...)
+	}
+
+	return cmd
+}
+
+// exitDetail adds the harness's stderr to the error of a failed run.
+func exitDetail(err error) error {
+	if exitErr, ok := err.(*exec.ExitError); ok && len(exitErr.Stderr) > 0 {
+		return fmt.Errorf("%w: %s", err, strings.TrimSpace(string(exitErr.Stderr)))
+	}
+
+	return err
+}
+
+// SampleQueries draws up to k queries from the JSONL lines in r, using
+// seed. A line naming an address and a slot, such as a set_storage or a
+// read_storage, yields a read_storage; one naming only an address
+// yields a read_account. A workload is therefore a keys file as it
+// stands. Lines without an address are skipped. Slots are normalized to
+// 32-byte hex, since harnesses only take that form in queries.
+func SampleQueries(r io.Reader, k int, seed int64) ([]Query, error) {
+	rng := mrand.New(mrand.NewSource(seed))
+	dec := json.NewDecoder(r)
+	sample := make([]Query, 0, k)
+
+	for seen, line := 0, 1; ; line++ {
+		var key struct {
+			Address string `json:"address"`
+			Slot    string `json:"slot"`
+		}
+
+		err := dec.Decode(&key)
+		if err == io.EOF {
+			return sample, nil
+		}
+
+		if err != nil {
+			return nil, fmt.Errorf("decode key line %d: %w", line, err)
+		}
+
+		if key.Address == "" {
+			continue
+		}
+
+		q := Query{Op: "read_account", Address: strings.ToLower(key.Address)}
+
+		if key.Slot != "" {
+			slot, err := fullSlot(key.Slot)
+			if err != nil {
+				return nil, fmt.Errorf("key line %d: %w", line, err)
+			}
+
+			q.Op, q.Slot = "read_storage", slot
+		}
+
+		// Reservoir sampling keeps each key with probability k/seen.
+		seen++
+
+		switch {
+		case len(sample) < k:
+			sample = append(sample, q)
+		case k > 0:
+			if i := rng.Intn(seen); i < k {
+				sample[i] = q
+			}
+		}
+	}
+}
+
+// fullSlot writes a slot, hex with a 0x prefix or decimal, as 32 bytes
+// of 0x-prefixed hex.
+func fullSlot(s string) (string, error) {
+	n, ok := new(big.Int), false
+	if digits, hex := strings.CutPrefix(s, "0x"); hex {
+		_, ok = n.SetString(digits, 16)
+	} else {
+		_, ok = n.SetString(s, 10)
+	}
+
+	if !ok || n.Sign() < 0 || n.BitLen() > 256 {
+		return "", fmt.Errorf("invalid slot %q", s)
+	}
+
+	return fmt.Sprintf("0x%064x", n), nil
+}
+
+// DBVerdict is the outcome of checking databases built from the same
+// workload by different clients.
+type DBVerdict struct {
+	Clients []DBCheck `json:"clients"`
+	// RootsMatch is set when every client read back the same root.
+	RootsMatch bool `json:"roots_match"`
+	// Samples is how many queries every client answered.
+	Samples    int             `json:"samples"`
+	Mismatches []QueryMismatch `json:"mismatches,omitempty"`
+	ElapsedMs  int64           `json:"elapsed_ms"`
+}
+
+// DBCheck is what one client's database yielded.
+type DBCheck struct {
+	Client      string `json:"client"`
+	DB          string `json:"db"`
+	StateRoot   string `json:"state_root"`
+	RootTimeMs  int64  `json:"root_time_ms"`
+	QueryTimeMs int64  `json:"query_time_ms,omitempty"`
+}
+
+// QueryMismatch is a sampled query the clients answered differently,
+// with each client's result or error.
+type QueryMismatch struct {
+	Query   Query                      `json:"query"`
+	Results map[string]json.RawMessage `json:"results,omitempty"`
+	Errors  map[string]string          `json:"errors,omitempty"`
+}
+
+// Match reports whether the roots agree and no sample differs.
+func (v DBVerdict) Match() bool {
+	return v.RootsMatch && len(v.Mismatches) == 0
+}
+
+// CompareRoots reports whether every check read back the same root,
+// ignoring case.
+func CompareRoots(checks []DBCheck) bool {
+	for _, c := range checks {
+		if !strings.EqualFold(c.StateRoot, checks[0].StateRoot) {
+			return false
+		}
+	}
+
+	return true
+}
+
+// CompareResponses lists the queries whose responses differ between
+// clients. An error never matches, since it leaves the state unknown.
+func CompareResponses(queries []Query, responses map[string][]QueryResponse) []QueryMismatch {
+	clients := make([]string, 0, len(responses))
+	for client := range responses {
+		clients = append(clients, client)
+	}
+
+	slices.Sort(clients)
+
+	var mismatches []QueryMismatch
+
+	for i, q := range queries {
+		m := QueryMismatch{Query: q, Results: make(map[string]json.RawMessage)}
+		differ := false
+
+		for _, client := range clients {
+			resp := responses[client][i]
+			if resp.Error != "" {
+				if m.Errors == nil {
+					m.Errors = make(map[string]string)
+				}
+
+				m.Errors[client] = resp.Error
+				differ = true
+
+				continue
+			}
+
+			m.Results[client] = resp.Result
+			if first := responses[clients[0]][i]; !sameJSON(first.Result, resp.Result) {
+				differ = true
+			}
+		}
+
+		if differ {
+			mismatches = append(mismatches, m)
+		}
+	}
+
+	return mismatches
+}
+
+// sameJSON compares two JSON values regardless of whitespace and key
+// order.
+func sameJSON(a, b json.RawMessage) bool {
+	var va, vb any
+	if json.Unmarshal(a, &va) != nil || json.Unmarshal(b, &vb) != nil {
+		return bytes.Equal(a, b)
+	}
+
+	ja, _ := json.Marshal(va)
+	jb, _ := json.Marshal(vb)
+
+	return bytes.Equal(ja, jb)
+}
@@ -0,0 +1,91 @@
+package harness
+
+import (
+	"encoding/json"
+	"strings"
+	"testing"
+)
+
+func TestSampleQueriesReadsWorkloadKeys(t *testing.T) {
+	input := strings.Join([]string{
+		`{"op":"create_account","address":"0xAB00000000000000000000000000000000000001","balance":"0x1","nonce":0}`,
+		`{"op":"set_storage","address":"0xab00000000000000000000000000000000000001","slot":"0x01","value":"0x02"}`,
+		`{"op":"set_storage","address":"0xab00000000000000000000000000000000000001","slot":"16","value":"0x02"}`,
+		`{"op":"commit_block"}`,
+		`{"op":"compute_root"}`,
+	}, "\n")
+
+	queries, err := SampleQueries(strings.NewReader(input), 10, 1)
+	if err != nil {
+		t.Fatalf("sample: %v", err)
+	}
+
+	address := "0xab00000000000000000000000000000000000001"
+	want := []Query{
+		{Op: "read_account", Address: address},
+		{Op: "read_storage", Address: address, Slot: "0x" + strings.Repeat("0", 63) + "1"},
+		{Op: "read_storage", Address: address, Slot: "0x" + strings.Repeat("0", 62) + "10"},
+	}
+
+	if len(queries) != len(want) {
+		t.Fatalf("queries = %+v, want %+v", queries, want)
+	}
+
+	for i := range want {
+		if queries[i] != want[i] {
+			t.Errorf("query %d = %+v, want %+v", i, queries[i], want[i])
+		}
+	}
+
+	few, err := SampleQueries(strings.NewReader(input), 2, 1)
+	if err != nil || len(few) != 2 {
+		t.Errorf("sample of 2 = %+v, %v", few, err)
+	}
+}
+
+func TestSampleQueriesRejectsBadSlots(t *testing.T) {
+	input := `{"op":"set_storage","address":"0x01","slot":"0xzz"}`
+
+	_, err := SampleQueries(strings.NewReader(input), 1, 1)
+	if err == nil || !strings.Contains(err.Error(), `key line 1: invalid slot "0xzz"`) {
+		t.Errorf("err = %v, want the bad slot named", err)
+	}
+}
+
+func TestCompareResponsesListsDifferingSamples(t *testing.T) {
+	queries := []Query{
+		{Op: "read_account", Address: "0x01"},
+		{Op: "read_storage", Address: "0x01", Slot: "0x02"},
+		{Op: "read_account", Address: "0x03"},
+	}
+	responses := map[string][]QueryResponse{
+		"reth": {
+			{Op: "read_account", Result: json.RawMessage(`{"nonce":1,"balance":"0x1"}`)},
+			{Op: "read_storage", Result: json.RawMessage(`"0x5"`)},
+			{Op: "read_account", Result: json.RawMessage(`null`)},
+		},
+		"ethrex": {
+			{Op: "read_account", Result: json.RawMessage(`{"balance":"0x1", "nonce":1}`)},
+			{Op: "read_storage", Result: json.RawMessage(`"0x6"`)},
+			{Op: "read_account", Error: "read account: missing node"},
+		},
+	}
+
+	mismatches := CompareResponses(queries, responses)
+	if len(mismatches) != 2 {
+		t.Fatalf("mismatches = %+v, want the slot and the failed read", mismatches)
+	}
+
+	if got := string(mismatches[0].Results["ethrex"]); got != `"0x6"` {
+		t.Errorf("ethrex slot = %s, want \"0x6\"", got)
+	}
+
+	if mismatches[1].Errors["ethrex"] == "" || mismatches[1].Query.Address != "0x03" {
+		t.Errorf("mismatch = %+v, want ethrex's error on 0x03", mismatches[1])
+	}
+
+	checks := []DBCheck{{Client: "reth", StateRoot: "0xAB"}, {Client: "ethrex", StateRoot: "0xab"}}
+	if !CompareRoots(checks) {
+		t.Error("roots differing only in case must match")
+	}
+}
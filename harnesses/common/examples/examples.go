@@ -0,0 +1,106 @@
+// Package examples embeds the example workloads of the shared harness
+// crate, the same files its self-tests run, so the statoor CLI can list,
+// print and run them.
+//
+// An example is a JSONL workload with "#" comment lines. The first
+// comment is its summary and the one starting "# expected_root: " names
+// the root the workload ends at.
+package examples
+
+import (
+	"bytes"
+	"embed"
+	"fmt"
+	"io/fs"
+	"slices"
+	"strings"
+)
+
+const (
+	comment         = "#"
+	expectedRootTag = "# expected_root: "
+)
+
+//go:embed *.jsonl
+var files embed.FS
+
+// Example is one embedded example workload.
+type Example struct {
+	Name         string
+	Summary      string
+	ExpectedRoot string
+	// Source is the file as written, comments included.
+	Source []byte
+}
+
+// Workload returns the example without its comment lines, as a harness
+// reads it.
+func (e Example) Workload() []byte {
+	var out bytes.Buffer
+
+	for line := range bytes.Lines(e.Source) {
+		if !bytes.HasPrefix(line, []byte(comment)) {
+			out.Write(line)
+		}
+	}
+
+	return out.Bytes()
+}
+
+// All returns every embedded example, sorted by name.
+func All() ([]Example, error) {
+	paths, err := fs.Glob(files, "*.jsonl")
+	if err != nil {
+		return nil, fmt.Errorf("list examples: %w", err)
+	}
+
+	slices.Sort(paths)
+
+	all := make([]Example, 0, len(paths))
+
+	for _, path := range paths {
+		source, err := files.ReadFile(path)
+		if err != nil {
+			return nil, fmt.Errorf("read example %s: %w", path, err)
+		}
+
+		all = append(all, parse(strings.TrimSuffix(path, ".jsonl"), source))
+	}
+
+	return all, nil
+}
+
+// Get returns the example called name.
+func Get(name string) (Example, error) {
+	all, err := All()
+	if err != nil {
+		return Example{}, err
+	}
+
+	for _, example := range all {
+		if example.Name == name {
+			return example, nil
+		}
+	}
+
+	names := make([]string, 0, len(all))
+	for _, example := range all {
+		names = append(names, example.Name)
+	}
+
+	return Example{}, fmt.Errorf("unknown example %q; have %s", name, strings.Join(names, ", "))
+}
+
+func parse(name string, source []byte) Example {
+	example := Example{Name: name, Source: source}
+
+	for i, line := range strings.Split(string(source), "\n") {
+		if root, ok := strings.CutPrefix(line, expectedRootTag); ok {
+			example.ExpectedRoot = strings.TrimSpace(root)
+		} else if i == 0 && strings.HasPrefix(line, comment) {
+			example.Summary = strings.TrimSpace(strings.TrimPrefix(line, comment))
+		}
+	}
+
+	return example
+}
@@ -0,0 +1,55 @@
+package examples
+
+import (
+	"bytes"
+	"strings"
+	"testing"
+
+	"github.com/weiihann/statoor/workload"
+)
+
+func TestExamplesAreWorkloads(t *testing.T) {
+	all, err := All()
+	if err != nil {
+		t.Fatalf("All failed: %v", err)
+	}
+
+	if len(all) < 4 {
+		t.Fatalf("got %d examples, want at least 4", len(all))
+	}
+
+	for _, example := range all {
+		if example.Summary == "" || len(example.ExpectedRoot) != 66 {
+			t.Errorf("%s: summary %q, expected root %q", example.Name, example.Summary, example.ExpectedRoot)
+		}
+
+		body := example.Workload()
+		if bytes.Contains(body, []byte(comment)) {
+			t.Errorf("%s: workload keeps a comment:\n%s", example.Name, body)
+		}
+
+		if _, err := workload.ScanOps(bytes.NewReader(body)); err != nil {
+			t.Errorf("%s: %v", example.Name, err)
+		}
+
+		lines := strings.Split(strings.TrimSpace(string(body)), "\n")
+		if last := lines[len(lines)-1]; last != `{"op":"compute_root"}` {
+			t.Errorf("%s: last line = %s, want compute_root", example.Name, last)
+		}
+	}
+}
+
+func TestGetNamesTheKnownExamples(t *testing.T) {
+	example, err := Get("multi_block")
+	if err != nil {
+		t.Fatalf("Get failed: %v", err)
+	}
+
+	if !bytes.Contains(example.Workload(), []byte(`"op":"commit_block"`)) {
+		t.Errorf("multi_block has no commit_block:\n%s", example.Workload())
+	}
+
+	if _, err := Get("nope"); err == nil || !strings.Contains(err.Error(), "basic_accounts") {
+		t.Errorf("Get(nope) = %v, want an error listing the examples", err)
+	}
+}
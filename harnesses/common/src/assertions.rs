@@ -0,0 +1,92 @@
+//! `assert_account` and `assert_storage` lines, which turn a hand-written
+//! workload into a differential test. Each names values the workload
+//! must have produced by then; the harness reads them back as the
+//! workload has written them, pending writes over the last committed
+//! state, and a mismatch fails the run. With `--assertions warn` the
+//! harness only prints it and goes on, and the result counts the
+//! failures.
+
+use std::fmt::LowerHex;
+
+use serde::Serialize;
+
+use crate::hexfmt;
+
+/// The assertions checked so far.
+#[derive(Debug, Default)]
+pub struct Assertions {
+    checked: u64,
+    failed: u64,
+}
+
+/// The result fields describing the assertions.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AssertionReport {
+    /// Fields checked; an `assert_account` with a balance and a nonce
+    /// checks two.
+    pub assertions_checked: u64,
+    pub assertion_failures: u64,
+}
+
+impl Assertions {
+    /// Compares the `actual` value of `field` of `address`, as written in
+    /// the workload, with the `expected` value the assertion on `line`
+    /// names.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the line, the address and both values
+    /// when they differ.
+    pub fn check<T: PartialEq + LowerHex>(
+        &mut self,
+        line: u64,
+        address: &str,
+        field: &str,
+        expected: T,
+        actual: T,
+    ) -> Result<(), String> {
+        self.checked = self.checked.saturating_add(1);
+        if expected == actual {
+            return Ok(());
+        }
+        self.failed = self.failed.saturating_add(1);
+        Err(format!(
+            "line {line}: {field} of {address} is {}, expected {}",
+            hexfmt::quantity(actual),
+            hexfmt::quantity(expected)
+        ))
+    }
+
+    /// The report for the result, or `None` for a workload without
+    /// assertions.
+    #[must_use]
+    pub fn report(&self) -> Option<AssertionReport> {
+        (self.checked > 0).then_some(AssertionReport {
+            assertions_checked: self.checked,
+            assertion_failures: self.failed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_checks_and_names_mismatches() {
+        let mut assertions = Assertions::default();
+        assert_eq!(assertions.report(), None);
+        assert_eq!(assertions.check(3, "0x01", "nonce", 1_u64, 1), Ok(()));
+        assert_eq!(
+            assertions.check(4, "0x01", "slot 0x02", 0xff_u64, 0x10),
+            Err("line 4: slot 0x02 of 0x01 is 0x10, expected 0xff".to_string())
+        );
+        assert_eq!(
+            assertions.report(),
+            Some(AssertionReport {
+                assertions_checked: 2,
+                assertion_failures: 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Converts a workload between JSONL and CBOR, stdin to stdout:
+//!
+//! ```text
+//! statoor-convert --to cbor [--raw-bytes] < workload.jsonl > workload.cbor
+//! statoor-convert --to jsonl < workload.cbor > workload.jsonl
+//! ```
+//!
+//! Every line goes through [`Entry`], so the output holds exactly the
+//! operations a harness would apply, and an invalid line fails the
+//! conversion instead of being copied. `--canaries N` follows each of the
+//! first `N` `set_storage` lines with a `canary` for its value, hashed
+//! with [`canary::leaf_hash`].
+
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+use statoor_common::canary;
+use statoor_common::cbor::{self, CborReader, MAGIC};
+use statoor_common::hexfmt;
+use statoor_common::input::LineReader;
+use statoor_common::protocol::{Entry, Op};
+use statoor_common::quantity;
+
+const USAGE: &str =
+    "usage: statoor-convert --to cbor|jsonl [--raw-bytes] [--canaries N] < input > output";
+
+fn main() {
+    let mut to = None;
+    let mut raw_bytes = false;
+    let mut canaries = Canaries(0);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => to = args.next(),
+            "--raw-bytes" => raw_bytes = true,
+            "--canaries" => match args.next().map(|n| n.parse()) {
+                Some(Ok(n)) => canaries = Canaries(n),
+                _ => fatal(&format!("--canaries needs a count\n{USAGE}")),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => fatal(&format!("unexpected argument {arg:?}\n{USAGE}")),
+        }
+    }
+
+    let stdin = io::stdin().lock();
+    let mut out = BufWriter::new(io::stdout().lock());
+    let converted = match to.as_deref() {
+        Some("cbor") => to_cbor(stdin, raw_bytes, &mut canaries, &mut out),
+        Some("jsonl") if !raw_bytes => to_jsonl(stdin, &mut canaries, &mut out),
+        Some("jsonl") => Err("--raw-bytes only applies to --to cbor".to_string()),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = converted.and_then(|()| out.flush().map_err(|e| format!("write: {e}"))) {
+        fatal(&e);
+    }
+}
+
+/// How many more `set_storage` lines get a canary.
+struct Canaries(u64);
+
+impl Canaries {
+    /// The entries to write for `entry`: itself, then its canary if it
+    /// is a `set_storage` and canaries are left.
+    fn follow(&mut self, entry: Entry) -> Result<Vec<Entry>, String> {
+        let canary = match &entry.op {
+            Op::SetStorage {
+                address,
+                slot,
+                value,
+            } if self.0 > 0 => {
+                self.0 -= 1;
+                let bytes = quantity::parse(value).map_err(|e| e.to_string())?;
+                Some(Entry {
+                    op: Op::Canary {
+                        address: address.clone(),
+                        slot: slot.clone(),
+                        value: value.clone(),
+                        expected_leaf_hash: hexfmt::fixed(canary::leaf_hash(&bytes)),
+                    },
+                    tenant: entry.tenant,
+                    block: entry.block,
+                })
+            }
+            _ => None,
+        };
+        Ok([entry].into_iter().chain(canary).collect())
+    }
+}
+
+fn to_cbor(
+    input: impl BufRead,
+    raw_bytes: bool,
+    canaries: &mut Canaries,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let mut lines = LineReader::new(input);
+    let mut record = Vec::new();
+    out.write_all(&MAGIC).map_err(|e| format!("write: {e}"))?;
+    while let Some(line) = lines.next_line().map_err(|e| e.to_string())? {
+        let entry = serde_json::from_str::<Entry>(line);
+        let number = lines.line_number();
+        let at = |e| format!("line {number}: {e}");
+        let entry = entry.map_err(|e| at(e.to_string()))?;
+        for entry in canaries.follow(entry).map_err(at)? {
+            record.clear();
+            cbor::encode(&entry, raw_bytes, &mut record);
+            out.write_all(&record).map_err(|e| format!("write: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn to_jsonl(
+    input: impl BufRead,
+    canaries: &mut Canaries,
+    out: &mut impl Write,
+) -> Result<(), String> {
+    let mut records = CborReader::new(input).map_err(|e| format!("read: {e}"))?;
+    while let Some((value, _)) = records.next_value().map_err(|e| e.to_string())? {
+        let at = |e| format!("record {}: {e}", records.record());
+        let entry: Entry = serde_json::from_value(value).map_err(|e| at(e.to_string()))?;
+        for entry in canaries.follow(entry).map_err(at)? {
+            let line = serde_json::to_string(&entry).map_err(|e| at(e.to_string()))?;
+            writeln!(out, "{line}").map_err(|e| format!("write: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn fatal(msg: &str) -> ! {
+    eprintln!("statoor-convert: {msg}");
+    process::exit(1);
+}
@@ -0,0 +1,493 @@
+//! Per-block results of a workload split by `commit_block`. Each block
+//! updates the trie on top of the previous block's root, and the result
+//! lists the blocks in order; its own `trie_time_ms` and
+//! `db_write_time_ms` are the sums over the blocks.
+//!
+//! A replay of thousands of blocks makes that list too long to read, so
+//! the result also carries [`BlockStats`]: the median and p95 of each
+//! metric over every window of `--rolling-window` blocks, which shows
+//! drift as the state grows, and percentiles over all blocks. Past one
+//! window, the list itself moves to a sidecar unless `--emit-per-block`
+//! keeps it inline.
+//!
+//! Lines may also carry the number of the chain block they came from
+//! instead of the workload marking blocks with `commit_block`:
+//! [`BlockTags`] tells the harness when a line starts a later block, so
+//! it commits the lines before it as one.
+//!
+//! However long the run, [`BlockReports`] keeps at most
+//! [`MAX_BLOCK_REPORTS`] blocks: past that it thins them to every other
+//! block, then every fourth and so on, and the stats say which blocks
+//! they sample. The timing sums still cover every block.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/// Blocks per window of the rolling statistics, unless `--rolling-window`
+/// says otherwise.
+pub const DEFAULT_ROLLING_WINDOW: usize = 50;
+
+/// Blocks a run keeps before [`BlockReports`] starts sampling them.
+pub const MAX_BLOCK_REPORTS: usize = 65_536;
+
+/// One committed block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BlockReport {
+    /// The state root after the block.
+    pub state_root: String,
+    pub trie_time_ms: u128,
+    /// Includes any injected commit latency.
+    pub db_write_time_ms: u128,
+    /// The `approx_gas` of the block's `commit_block`, if it had one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approx_gas: Option<u64>,
+    /// The `block` its lines were tagged with, if they were.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Operations applied in the block.
+    pub ops: u64,
+}
+
+/// The blocks of a run, bounded at [`MAX_BLOCK_REPORTS`]. Blocks are kept
+/// every `stride` from the first, and the stride doubles, dropping every
+/// other kept block, whenever they reach the bound. The latest block is
+/// always kept as well, so a harness can still stamp it.
+#[derive(Debug)]
+pub struct BlockReports {
+    kept: Vec<BlockReport>,
+    cap: usize,
+    stride: u64,
+    committed: u64,
+    trie_time_ms: u128,
+    db_write_time_ms: u128,
+}
+
+impl Default for BlockReports {
+    fn default() -> Self {
+        Self::with_cap(MAX_BLOCK_REPORTS)
+    }
+}
+
+impl BlockReports {
+    /// Keeps at most `cap` blocks, and never fewer than two.
+    #[must_use]
+    pub fn with_cap(cap: usize) -> Self {
+        Self {
+            kept: Vec::new(),
+            cap: cap.max(2),
+            stride: 1,
+            committed: 0,
+            trie_time_ms: 0,
+            db_write_time_ms: 0,
+        }
+    }
+
+    /// Records the next committed block.
+    pub fn push(&mut self, block: BlockReport) {
+        self.trie_time_ms = self.trie_time_ms.saturating_add(block.trie_time_ms);
+        self.db_write_time_ms = self.db_write_time_ms.saturating_add(block.db_write_time_ms);
+        // The previous block was only kept for being the latest.
+        if self.committed > 0 && !(self.committed - 1).is_multiple_of(self.stride) {
+            self.kept.pop();
+        }
+        self.kept.push(block);
+        self.committed = self.committed.saturating_add(1);
+        if self.kept.len() > self.cap {
+            let latest = self.kept.pop();
+            let mut index = 0;
+            self.kept.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.kept.extend(latest);
+            self.stride = self.stride.saturating_mul(2);
+        }
+    }
+
+    /// Whether no block was committed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.committed == 0
+    }
+
+    /// The latest block.
+    pub fn last_mut(&mut self) -> Option<&mut BlockReport> {
+        self.kept.last_mut()
+    }
+
+    /// The blocks kept so far, in order.
+    #[must_use]
+    pub fn kept(&self) -> &[BlockReport] {
+        &self.kept
+    }
+
+    /// Kept blocks before the latest are this many blocks apart.
+    #[must_use]
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// The trie and DB write times summed over every block committed,
+    /// kept or not.
+    #[must_use]
+    pub fn totals(&self) -> (u128, u128) {
+        (self.trie_time_ms, self.db_write_time_ms)
+    }
+}
+
+impl FromIterator<BlockReport> for BlockReports {
+    fn from_iter<I: IntoIterator<Item = BlockReport>>(blocks: I) -> Self {
+        let mut reports = Self::default();
+        for block in blocks {
+            reports.push(block);
+        }
+        reports
+    }
+}
+
+/// The block tag of the lines read so far.
+#[derive(Debug, Default)]
+pub struct BlockTags {
+    current: Option<u64>,
+}
+
+impl BlockTags {
+    /// Takes in the `block` of the next line. Returns the number of the
+    /// block it ends, when the line is of a later block than the lines
+    /// before it. Untagged lines belong to the current block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for a block number lower than the current one.
+    pub fn advance(&mut self, block: Option<u64>) -> Result<Option<u64>, String> {
+        let Some(block) = block else {
+            return Ok(None);
+        };
+        match self.current.replace(block) {
+            Some(current) if block < current => {
+                self.current = Some(current);
+                Err(format!(
+                    "block {block} follows block {current}; block numbers must not decrease"
+                ))
+            }
+            Some(current) if block > current => Ok(Some(current)),
+            _ => Ok(None),
+        }
+    }
+
+    /// The block of the lines since the last one that ended a block.
+    #[must_use]
+    pub fn current(&self) -> Option<u64> {
+        self.current
+    }
+}
+
+/// Median and p95 of one metric over a window of blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Spread {
+    pub median: u128,
+    pub p95: u128,
+}
+
+/// One window of consecutive blocks; the last may be shorter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WindowStats {
+    /// Index of the window's first block, counting from 0.
+    pub first_block: usize,
+    pub blocks: usize,
+    pub trie_time_ms: Spread,
+    pub db_write_time_ms: Spread,
+    pub ops: Spread,
+}
+
+/// Percentiles of one metric over every block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Percentiles {
+    pub p50: u128,
+    pub p90: u128,
+    pub p95: u128,
+    pub p99: u128,
+    pub max: u128,
+}
+
+/// [`Percentiles`] of each block metric.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct BlockPercentiles {
+    pub trie_time_ms: Percentiles,
+    pub db_write_time_ms: Percentiles,
+    pub ops: Percentiles,
+}
+
+/// The statistics of a multi-block run, flattened into the result.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BlockStats {
+    pub rolling_window: usize,
+    /// Set once the run outgrew [`MAX_BLOCK_REPORTS`]: the stats and the
+    /// list then cover every this many blocks, plus the last.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_sample_every: Option<u64>,
+    pub rolling_stats: Vec<WindowStats>,
+    pub block_percentiles: BlockPercentiles,
+    /// Where the per-block list went instead of the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_sidecar: Option<String>,
+}
+
+/// Where the blocks of the database at `db` are spilled:
+/// `<db>.blocks.json`, beside the database directory.
+#[must_use]
+pub fn sidecar_path(db: &Path) -> PathBuf {
+    let mut name = db.file_name().unwrap_or_default().to_os_string();
+    name.push(".blocks.json");
+    db.with_file_name(name)
+}
+
+/// Computes the [`BlockStats`] of the kept `reports` over windows of
+/// `window` of them and returns the blocks the result lists. Those are all of them
+/// when `emit_per_block` is set or they fit in one window; otherwise
+/// none, and they are written to `sidecar` as a JSON array. Returns no
+/// stats for a run without blocks.
+///
+/// # Errors
+///
+/// Returns an error if the sidecar cannot be written.
+pub fn summarize(
+    reports: BlockReports,
+    window: usize,
+    emit_per_block: bool,
+    sidecar: &Path,
+) -> Result<(Vec<BlockReport>, Option<BlockStats>), String> {
+    let stride = reports.stride;
+    let latest = reports.committed.saturating_sub(1);
+    let reports = reports.kept;
+    if reports.is_empty() {
+        return Ok((reports, None));
+    }
+    // Every kept block but the latest sits on the stride.
+    let block_index = |position: usize| {
+        if position + 1 == reports.len() {
+            latest
+        } else {
+            (position as u64).saturating_mul(stride)
+        }
+    };
+    let window = window.max(1);
+    let metrics = |blocks: &[BlockReport]| {
+        let mut trie: Vec<u128> = blocks.iter().map(|b| b.trie_time_ms).collect();
+        let mut write: Vec<u128> = blocks.iter().map(|b| b.db_write_time_ms).collect();
+        let mut ops: Vec<u128> = blocks.iter().map(|b| u128::from(b.ops)).collect();
+        trie.sort_unstable();
+        write.sort_unstable();
+        ops.sort_unstable();
+        [trie, write, ops]
+    };
+    let rolling_stats = reports
+        .chunks(window)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let [trie, write, ops] = metrics(chunk);
+            let spread = |sorted: &[u128]| Spread {
+                median: nearest_rank(sorted, 50),
+                p95: nearest_rank(sorted, 95),
+            };
+            WindowStats {
+                first_block: usize::try_from(block_index(i * window)).unwrap_or(usize::MAX),
+                blocks: chunk.len(),
+                trie_time_ms: spread(&trie),
+                db_write_time_ms: spread(&write),
+                ops: spread(&ops),
+            }
+        })
+        .collect();
+    let [trie, write, ops] = metrics(&reports);
+    let percentiles = |sorted: &[u128]| Percentiles {
+        p50: nearest_rank(sorted, 50),
+        p90: nearest_rank(sorted, 90),
+        p95: nearest_rank(sorted, 95),
+        p99: nearest_rank(sorted, 99),
+        max: nearest_rank(sorted, 100),
+    };
+    let mut stats = BlockStats {
+        rolling_window: window,
+        block_sample_every: (stride > 1).then_some(stride),
+        rolling_stats,
+        block_percentiles: BlockPercentiles {
+            trie_time_ms: percentiles(&trie),
+            db_write_time_ms: percentiles(&write),
+            ops: percentiles(&ops),
+        },
+        blocks_sidecar: None,
+    };
+    if emit_per_block || reports.len() <= window {
+        return Ok((reports, Some(stats)));
+    }
+    let json = serde_json::to_vec(&reports).map_err(|e| format!("encode blocks: {e}"))?;
+    std::fs::write(sidecar, json)
+        .map_err(|e| format!("write blocks sidecar {}: {e}", sidecar.display()))?;
+    stats.blocks_sidecar = Some(sidecar.display().to_string());
+    Ok((Vec::new(), Some(stats)))
+}
+
+/// The smallest value at least `p`% of `sorted` are at or below.
+fn nearest_rank(sorted: &[u128], p: usize) -> u128 {
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied().unwrap_or(0)
+}
+
+/// Sums the trie and DB write times of `blocks`.
+#[must_use]
+pub fn totals(blocks: &[BlockReport]) -> (u128, u128) {
+    blocks.iter().fold((0, 0), |(trie, write), block| {
+        (trie + block.trie_time_ms, write + block.db_write_time_ms)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totals_sum_every_block() {
+        let block = |trie_time_ms, db_write_time_ms| BlockReport {
+            state_root: String::new(),
+            trie_time_ms,
+            db_write_time_ms,
+            approx_gas: None,
+            block_number: None,
+            ops: 0,
+        };
+        assert_eq!(totals(&[]), (0, 0));
+        assert_eq!(totals(&[block(3, 5), block(0, 7), block(11, 0)]), (14, 12));
+        let json = serde_json::to_value(block(1, 2)).unwrap_or_default();
+        assert_eq!(
+            json,
+            serde_json::json!({"state_root": "", "trie_time_ms": 1, "db_write_time_ms": 2, "ops": 0})
+        );
+        let stamped = BlockReport {
+            approx_gas: Some(21_000),
+            ..block(1, 2)
+        };
+        let json = serde_json::to_value(stamped).unwrap_or_default();
+        assert_eq!(json["approx_gas"], 21_000);
+    }
+
+    #[test]
+    fn tags_end_a_block_when_the_number_rises() {
+        let mut tags = BlockTags::default();
+        assert_eq!(tags.advance(None), Ok(None));
+        assert_eq!(tags.advance(Some(7)), Ok(None));
+        assert_eq!(tags.advance(Some(7)), Ok(None));
+        assert_eq!(tags.advance(None), Ok(None));
+        // Skipped numbers are empty blocks, not committed.
+        assert_eq!(tags.advance(Some(9)), Ok(Some(7)));
+        let Err(e) = tags.advance(Some(8)) else {
+            panic!("a lower block number must be rejected");
+        };
+        assert!(e.contains("block 8 follows block 9"), "{e}");
+        assert_eq!(tags.current(), Some(9));
+    }
+
+    fn blocks(n: u64) -> BlockReports {
+        (1..=n)
+            .map(|i| BlockReport {
+                state_root: format!("0x{i:x}"),
+                trie_time_ms: u128::from(i),
+                db_write_time_ms: u128::from(2 * i),
+                approx_gas: None,
+                block_number: None,
+                ops: 10 * i,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rolling_windows_cover_every_block() {
+        let sidecar = std::env::temp_dir().join("statoor-blocks-unused.blocks.json");
+        let Ok((inline, Some(stats))) = summarize(blocks(5), 2, true, &sidecar) else {
+            panic!("blocks must summarize");
+        };
+        assert_eq!(inline.len(), 5);
+        let windows: Vec<(usize, usize, Spread)> = stats
+            .rolling_stats
+            .iter()
+            .map(|w| (w.first_block, w.blocks, w.trie_time_ms))
+            .collect();
+        assert_eq!(
+            windows,
+            [
+                (0, 2, Spread { median: 1, p95: 2 }),
+                (2, 2, Spread { median: 3, p95: 4 }),
+                (4, 1, Spread { median: 5, p95: 5 }),
+            ]
+        );
+        assert_eq!(
+            stats.rolling_stats[1].ops,
+            Spread {
+                median: 30,
+                p95: 40
+            }
+        );
+        let all = stats.block_percentiles.db_write_time_ms;
+        assert_eq!((all.p50, all.p90, all.max), (6, 10, 10));
+        assert!(stats.blocks_sidecar.is_none());
+        assert!(!sidecar.exists());
+
+        let Ok((none, None)) = summarize(BlockReports::default(), 2, false, &sidecar) else {
+            panic!("a run without blocks has no stats");
+        };
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn long_runs_sample_their_blocks() {
+        let mut reports = BlockReports::with_cap(4);
+        for block in blocks(1_000).kept().iter().cloned() {
+            reports.push(block);
+            assert!(reports.kept().len() <= 4);
+        }
+        assert_eq!(reports.stride(), 512);
+        assert_eq!(reports.totals(), (500_500, 1_001_000));
+        let ops: Vec<u64> = reports.kept().iter().map(|block| block.ops).collect();
+        assert_eq!(ops, [10, 5_130, 10_000], "blocks 0, 512 and the latest");
+
+        let sidecar = std::env::temp_dir().join("statoor-blocks-unused.blocks.json");
+        let Ok((inline, Some(stats))) = summarize(reports, 2, true, &sidecar) else {
+            panic!("blocks must summarize");
+        };
+        assert_eq!(inline.len(), 3);
+        assert_eq!(stats.block_sample_every, Some(512));
+        let windows: Vec<(usize, usize)> = stats
+            .rolling_stats
+            .iter()
+            .map(|w| (w.first_block, w.blocks))
+            .collect();
+        assert_eq!(windows, [(0, 2), (999, 1)]);
+        assert_eq!(blocks(3).stride(), 1);
+    }
+
+    #[test]
+    fn long_runs_spill_their_blocks() {
+        let db = std::env::temp_dir().join(format!("statoor-blocks-{}", std::process::id()));
+        let sidecar = sidecar_path(&db);
+        let Ok((inline, Some(stats))) = summarize(blocks(3), 2, false, &sidecar) else {
+            panic!("blocks must summarize");
+        };
+        let spilled = std::fs::read(&sidecar).unwrap_or_default();
+        let _ = std::fs::remove_file(&sidecar);
+        assert!(inline.is_empty());
+        assert_eq!(
+            stats.blocks_sidecar.as_deref(),
+            Some(sidecar.display().to_string().as_str())
+        );
+        let spilled: serde_json::Value = serde_json::from_slice(&spilled).unwrap_or_default();
+        assert_eq!(spilled.as_array().map(Vec::len), Some(3));
+        assert_eq!(spilled[2]["ops"], 30);
+
+        let Ok((kept, _)) = summarize(blocks(2), 2, false, &sidecar) else {
+            panic!("blocks must summarize");
+        };
+        assert_eq!(kept.len(), 2, "one window of blocks stays inline");
+        assert!(!sidecar.exists());
+    }
+}
@@ -0,0 +1,53 @@
+//! Time budgets of the optional phases after the root. On a big state
+//! the code read bench (`--read-bench-timeout-secs`), the iteration
+//! listing the codes it samples (`--iterate-timeout-secs`) and the
+//! durability probe (`--verify-timeout-secs`) can outlast the build. A
+//! phase past its budget stops at the next step it checks, reports what
+//! it measured with `truncated: true`, and the phases after it still run,
+//! so the result is printed either way.
+
+use std::time::{Duration, Instant};
+
+/// The deadline of one phase, or none.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Budget {
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    /// A budget of `secs` seconds from now, or an unlimited one.
+    #[must_use]
+    pub fn start(secs: Option<u64>) -> Self {
+        Self {
+            deadline: secs.map(|secs| Instant::now() + Duration::from_secs(secs)),
+        }
+    }
+
+    /// A budget that ends at `deadline`.
+    #[must_use]
+    pub fn until(deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Reports whether the phase must stop.
+    #[must_use]
+    pub fn exhausted(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_passed_deadline_is_exhausted() {
+        assert!(!Budget::default().exhausted());
+        assert!(!Budget::start(None).exhausted());
+        assert!(!Budget::start(Some(3600)).exhausted());
+        assert!(Budget::until(Instant::now()).exhausted());
+    }
+}
@@ -0,0 +1,127 @@
+//! Canary lines. Harnesses that decode a value the same wrong way, say
+//! from a converter that packed it little-endian, agree on a wrong root,
+//! so comparing their roots cannot catch it. A generator may add `canary`
+//! lines naming a storage value and `expected_leaf_hash`, the keccak of
+//! its RLP encoding as the storage trie holds it; each harness hashes its
+//! own client's encoding of the value and counts the canaries that differ.
+//!
+//! [`leaf_hash`] is the reference encoding generators compute the hash
+//! with.
+
+use serde::Serialize;
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::rootcheck;
+
+/// Most failed canary lines the result lists.
+const MAX_FAILED_LINES: usize = 16;
+
+/// The hash a canary for the storage value `value`, big-endian, expects:
+/// keccak256 of the RLP string of its bytes without leading zeroes.
+#[must_use]
+pub fn leaf_hash(value: &[u8; 32]) -> [u8; 32] {
+    let start = value.iter().position(|&b| b != 0).unwrap_or(value.len());
+    let bytes = &value[start..];
+    let mut hasher = Keccak::v256();
+    match bytes {
+        // A single byte below 0x80 is its own encoding.
+        [b] if *b < 0x80 => hasher.update(bytes),
+        _ => {
+            // At most 32 bytes, so the length fits the short form.
+            #[allow(clippy::cast_possible_truncation)]
+            hasher.update(&[0x80 + bytes.len() as u8]);
+            hasher.update(bytes);
+        }
+    }
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+/// The canaries checked so far.
+#[derive(Debug, Default)]
+pub struct Canaries {
+    checked: u64,
+    failed: u64,
+    failed_lines: Vec<u64>,
+}
+
+/// The result fields describing the canary checks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CanaryReport {
+    pub canaries_checked: u64,
+    pub canaries_failed: u64,
+    /// Workload lines of the first failed canaries.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub canary_failed_lines: Vec<u64>,
+}
+
+impl Canaries {
+    /// Compares `actual`, the hash of the harness's encoding, with the
+    /// `expected` hash of the canary on `line`, and reports whether they
+    /// match. Hex case and the `0x` prefix do not matter.
+    pub fn check(&mut self, line: u64, expected: &str, actual: &str) -> bool {
+        self.checked = self.checked.saturating_add(1);
+        let matched = rootcheck::matches(expected, actual);
+        if !matched {
+            self.failed = self.failed.saturating_add(1);
+            if self.failed_lines.len() < MAX_FAILED_LINES {
+                self.failed_lines.push(line);
+            }
+        }
+        matched
+    }
+
+    /// The report for the result, or `None` for a workload without
+    /// canaries.
+    #[must_use]
+    pub fn report(&self) -> Option<CanaryReport> {
+        (self.checked > 0).then(|| CanaryReport {
+            canaries_checked: self.checked,
+            canaries_failed: self.failed,
+            canary_failed_lines: self.failed_lines.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hexfmt;
+
+    fn value(bytes: &[u8]) -> [u8; 32] {
+        let mut value = [0; 32];
+        value[32 - bytes.len()..].copy_from_slice(bytes);
+        value
+    }
+
+    #[test]
+    fn hashes_the_rlp_of_the_trimmed_value() {
+        // keccak256(0x2a) and keccak256(0x820100).
+        assert_eq!(
+            hexfmt::fixed(leaf_hash(&value(&[0x2a]))),
+            "0x04994f67dc55b09e814ab7ffc8df3686b4afb2bb53e60eae97ef043fe03fb829"
+        );
+        assert_eq!(
+            hexfmt::fixed(leaf_hash(&value(&[0x01, 0x00]))),
+            "0xe9855fcb82ca8786997b5c93adeb8d260e46222415fe16ffb237cd973e396cc1"
+        );
+    }
+
+    #[test]
+    fn counts_failed_canaries() {
+        let mut canaries = Canaries::default();
+        assert_eq!(canaries.report(), None);
+        let hash = hexfmt::fixed(leaf_hash(&value(&[0x80])));
+        assert!(canaries.check(3, &hash.to_uppercase()[2..], &hash));
+        assert!(!canaries.check(4, &hash, &hexfmt::fixed(leaf_hash(&value(&[0x01])))));
+        assert_eq!(
+            canaries.report(),
+            Some(CanaryReport {
+                canaries_checked: 2,
+                canaries_failed: 1,
+                canary_failed_lines: vec![4],
+            })
+        );
+    }
+}
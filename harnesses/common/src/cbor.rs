@@ -0,0 +1,460 @@
+//! The binary workload format: the lines of a JSONL workload as a CBOR
+//! sequence (RFC 8949, RFC 8742) of maps with the same fields, after the
+//! self-describe tag `0xd9d9f7` that tells it apart from JSON at the
+//! first byte. Decoding skips JSON's text scanning, which dominates the
+//! read time of very large workloads.
+//!
+//! Hex fields may be CBOR byte strings instead of text; they decode to
+//! the lowercase `0x` form, so an operation reads the same either way.
+//! Only what a workload line needs is supported: unsigned integers,
+//! byte and text strings, arrays, maps with text keys, booleans and
+//! null, all of definite length.
+
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::hexfmt;
+use crate::protocol::Entry;
+
+/// The self-describe tag that starts a CBOR workload.
+pub const MAGIC: [u8; 3] = [0xd9, 0xd9, 0xf7];
+
+/// Fields whose `0x` hex the encoder may write as byte strings.
+const HEX_FIELDS: &[&str] = &[
+    "address",
+    "balance",
+    "code",
+    "slot",
+    "value",
+    "from",
+    "to",
+    "seed",
+    "expected_root",
+    "expected_leaf_hash",
+];
+
+/// Deepest nesting a record may have; a line nests two levels at most.
+const MAX_DEPTH: u8 = 8;
+
+/// How a harness reads its workload, from `--workload-format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkloadFormat {
+    /// CBOR when the input starts with [`MAGIC`], JSONL otherwise.
+    #[default]
+    Auto,
+    Jsonl,
+    Cbor,
+}
+
+impl FromStr for WorkloadFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "jsonl" => Ok(Self::Jsonl),
+            "cbor" => Ok(Self::Cbor),
+            _ => Err(format!(
+                "unknown workload format {s:?}; expected auto, jsonl or cbor"
+            )),
+        }
+    }
+}
+
+impl WorkloadFormat {
+    /// Reports whether `reader` is to be read as CBOR, peeking at its
+    /// first bytes for `Auto`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the underlying reader.
+    pub fn is_cbor(self, reader: &mut impl BufRead) -> io::Result<bool> {
+        match self {
+            Self::Auto => Ok(reader.fill_buf()?.starts_with(&MAGIC)),
+            Self::Jsonl => Ok(false),
+            Self::Cbor => Ok(true),
+        }
+    }
+}
+
+/// Errors produced while reading a CBOR workload.
+#[derive(Debug)]
+pub enum CborError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// A record is not a workload line in the supported CBOR subset.
+    Malformed {
+        /// One-based record number.
+        record: u64,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Malformed { record, reason } => write!(f, "record {record}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for CborError {}
+
+/// Appends `entry` to `out` as one CBOR record, with the fields in
+/// [`HEX_FIELDS`] as byte strings when `raw_bytes` is set and they hold
+/// whole bytes of hex.
+pub fn encode(entry: &Entry, raw_bytes: bool, out: &mut Vec<u8>) {
+    // An entry always serializes, to an object of strings and integers.
+    let Ok(Value::Object(fields)) = serde_json::to_value(entry) else {
+        return;
+    };
+    head(out, 5, fields.len() as u64);
+    for (key, value) in &fields {
+        text(out, key);
+        match value
+            .as_str()
+            .and_then(|s| raw_hex(s).filter(|_| raw_bytes))
+        {
+            Some(bytes) if HEX_FIELDS.contains(&key.as_str()) => {
+                head(out, 2, bytes.len() as u64);
+                out.extend_from_slice(&bytes);
+            }
+            _ => encode_value(value, out),
+        }
+    }
+}
+
+fn encode_value(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => out.push(0xf6),
+        Value::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        // Workload numbers are unsigned; anything else keeps its text.
+        Value::Number(n) => match n.as_u64() {
+            Some(n) => head(out, 0, n),
+            None => text(out, &n.to_string()),
+        },
+        Value::String(s) => text(out, s),
+        Value::Array(items) => {
+            head(out, 4, items.len() as u64);
+            for item in items {
+                encode_value(item, out);
+            }
+        }
+        Value::Object(fields) => {
+            head(out, 5, fields.len() as u64);
+            for (key, value) in fields {
+                text(out, key);
+                encode_value(value, out);
+            }
+        }
+    }
+}
+
+/// Writes the head of an item of `major` type, with `arg` in the fewest
+/// bytes.
+// Each arm casts only values its range bounds to the target width.
+#[allow(clippy::cast_possible_truncation)]
+fn head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..0x18 => out.push(major | arg as u8),
+        0x18..0x100 => out.extend_from_slice(&[major | 0x18, arg as u8]),
+        0x100..0x1_0000 => {
+            out.push(major | 0x19);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..0x1_0000_0000 => {
+            out.push(major | 0x1a);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 0x1b);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+fn text(out: &mut Vec<u8>, s: &str) {
+    head(out, 3, s.len() as u64);
+    out.extend_from_slice(s.as_bytes());
+}
+
+/// The bytes of `0x`-prefixed hex with an even number of digits.
+fn raw_hex(s: &str) -> Option<Vec<u8>> {
+    let digits = s.strip_prefix("0x")?;
+    if !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Reads the records of a CBOR workload one at a time.
+pub struct CborReader<R> {
+    inner: R,
+    record: u64,
+    /// Bytes read of the record being decoded.
+    read: usize,
+}
+
+impl<R: BufRead> CborReader<R> {
+    /// Creates a reader over `inner`, skipping [`MAGIC`] if it starts
+    /// with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the underlying reader.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        if inner.fill_buf()?.starts_with(&MAGIC) {
+            inner.consume(MAGIC.len());
+        }
+        Ok(Self {
+            inner,
+            record: 0,
+            read: 0,
+        })
+    }
+
+    /// Returns the next record decoded to the JSON value of its line,
+    /// with its length in bytes, or `None` at the end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reader fails, or the record is truncated
+    /// or outside the supported subset.
+    pub fn next_value(&mut self) -> Result<Option<(Value, usize)>, CborError> {
+        if self.at_end().map_err(CborError::Io)? {
+            return Ok(None);
+        }
+        self.record += 1;
+        self.read = 0;
+        match self.value(0) {
+            Ok(value) => Ok(Some((value, self.read))),
+            Err(Failure::Io(e)) => Err(CborError::Io(e)),
+            Err(Failure::Malformed(reason)) => Err(CborError::Malformed {
+                record: self.record,
+                reason,
+            }),
+        }
+    }
+
+    /// Reports whether the input has ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the underlying reader.
+    pub fn at_end(&mut self) -> io::Result<bool> {
+        Ok(self.inner.fill_buf()?.is_empty())
+    }
+
+    /// Returns the one-based number of the record last returned.
+    #[must_use]
+    pub fn record(&self) -> u64 {
+        self.record
+    }
+
+    /// Returns the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    fn value(&mut self, depth: u8) -> Result<Value, Failure> {
+        if depth > MAX_DEPTH {
+            return Err(malformed("nested too deeply"));
+        }
+        let (major, arg) = self.head()?;
+        match major {
+            0 => Ok(Value::from(arg)),
+            2 => Ok(Value::String(hexfmt::fixed(self.bytes(arg)?))),
+            3 => String::from_utf8(self.bytes(arg)?)
+                .map(Value::String)
+                .map_err(|_| malformed("text is not valid UTF-8")),
+            4 => (0..arg)
+                .map(|_| self.value(depth + 1))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            5 => {
+                let mut fields = Map::new();
+                for _ in 0..arg {
+                    let Value::String(key) = self.value(depth + 1)? else {
+                        return Err(malformed("map keys must be text"));
+                    };
+                    fields.insert(key, self.value(depth + 1)?);
+                }
+                Ok(Value::Object(fields))
+            }
+            7 => match arg {
+                20 => Ok(Value::Bool(false)),
+                21 => Ok(Value::Bool(true)),
+                22 => Ok(Value::Null),
+                _ => Err(malformed(&format!("unsupported simple value {arg}"))),
+            },
+            _ => Err(malformed(&format!("unsupported major type {major}"))),
+        }
+    }
+
+    fn head(&mut self) -> Result<(u8, u64), Failure> {
+        let [initial] = self.array::<1>()?;
+        let arg = match initial & 0x1f {
+            info @ 0..24 => u64::from(info),
+            24 => u64::from(u8::from_be_bytes(self.array()?)),
+            25 => u64::from(u16::from_be_bytes(self.array()?)),
+            26 => u64::from(u32::from_be_bytes(self.array()?)),
+            27 => u64::from_be_bytes(self.array()?),
+            _ => return Err(malformed("indefinite lengths are not supported")),
+        };
+        Ok((initial >> 5, arg))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Failure> {
+        let mut buf = [0; N];
+        self.inner.read_exact(&mut buf).map_err(truncated)?;
+        self.read += N;
+        Ok(buf)
+    }
+
+    fn bytes(&mut self, len: u64) -> Result<Vec<u8>, Failure> {
+        // Read through `take` so a corrupt length allocates only what
+        // the input actually holds.
+        let mut buf = Vec::new();
+        (&mut self.inner)
+            .take(len)
+            .read_to_end(&mut buf)
+            .map_err(Failure::Io)?;
+        if buf.len() as u64 != len {
+            return Err(malformed("truncated record"));
+        }
+        self.read += buf.len();
+        Ok(buf)
+    }
+}
+
+enum Failure {
+    Io(io::Error),
+    Malformed(String),
+}
+
+fn malformed(reason: &str) -> Failure {
+    Failure::Malformed(reason.to_string())
+}
+
+fn truncated(e: io::Error) -> Failure {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        malformed("truncated record")
+    } else {
+        Failure::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{BLOCK_FIXTURES, FIXTURES};
+
+    fn entries() -> Vec<Entry> {
+        FIXTURES
+            .iter()
+            .chain(BLOCK_FIXTURES)
+            .flat_map(|fixture| fixture.workload.lines())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    fn transcode(entries: &[Entry], raw_bytes: bool) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        for entry in entries {
+            encode(entry, raw_bytes, &mut out);
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Vec<Entry> {
+        let Ok(mut reader) = CborReader::new(bytes) else {
+            panic!("open CBOR reader");
+        };
+        let mut entries = Vec::new();
+        loop {
+            match reader.next_value() {
+                Ok(Some((value, _))) => match serde_json::from_value(value) {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => panic!("record {}: {e}", reader.record()),
+                },
+                Ok(None) => return entries,
+                Err(e) => panic!("{e}"),
+            }
+        }
+    }
+
+    #[test]
+    fn every_fixture_line_round_trips() {
+        let entries = entries();
+        assert!(entries.len() > 50);
+        assert_eq!(decode(&transcode(&entries, false)), entries);
+        let raw = transcode(&entries, true);
+        assert!(raw.len() < transcode(&entries, false).len());
+        assert_eq!(decode(&raw), entries);
+    }
+
+    #[test]
+    fn encodes_lengths_in_the_shortest_head() {
+        let mut out = Vec::new();
+        for arg in [23, 24, 0xff, 0x100, 0x1_0000, 0x1_0000_0000] {
+            head(&mut out, 0, arg);
+        }
+        assert_eq!(
+            out,
+            [
+                0x17, 0x18, 0x18, 0x18, 0xff, 0x19, 0x01, 0x00, 0x1a, 0x00, 0x01, 0x00, 0x00, 0x1b,
+                0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn names_the_malformed_record() {
+        let mut bytes = transcode(&entries()[..2], false);
+        // A third record announcing a map of one entry that never comes.
+        bytes.push(0xa1);
+        let Ok(mut reader) = CborReader::new(&bytes[..]) else {
+            panic!("open CBOR reader");
+        };
+        assert!(matches!(reader.next_value(), Ok(Some(_))));
+        assert!(matches!(reader.next_value(), Ok(Some(_))));
+        let Err(e) = reader.next_value() else {
+            panic!("a truncated record must fail");
+        };
+        assert_eq!(e.to_string(), "record 3: truncated record");
+
+        let Err(e) = CborReader::new(&[0xbf][..])
+            .map_err(CborError::Io)
+            .and_then(|mut reader| reader.next_value())
+        else {
+            panic!("an indefinite map must fail");
+        };
+        assert_eq!(
+            e.to_string(),
+            "record 1: indefinite lengths are not supported"
+        );
+    }
+
+    #[test]
+    fn detects_the_format_from_the_magic() {
+        let Ok(cbor) = WorkloadFormat::Auto.is_cbor(&mut &MAGIC[..]) else {
+            panic!("peek CBOR input");
+        };
+        let Ok(jsonl) = WorkloadFormat::Auto.is_cbor(&mut &b"{\"op\":\"compute_root\"}"[..]) else {
+            panic!("peek JSONL input");
+        };
+        assert!(cbor && !jsonl);
+        assert_eq!("cbor".parse(), Ok(WorkloadFormat::Cbor));
+        assert!("bincode".parse::<WorkloadFormat>().is_err());
+    }
+}
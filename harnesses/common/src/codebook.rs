@@ -0,0 +1,111 @@
+//! `define_code` and `set_code_ref` operations: a workload that deploys
+//! the same bytecode to many accounts defines it once under a short id
+//! and then attaches it by id, instead of repeating the hex on every
+//! line. Each harness keeps the decoded, hashed code per id here, so the
+//! code is hashed once however often it is attached.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Errors produced by a `define_code` or `set_code_ref`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CodeBookError {
+    /// A `define_code` reused an id.
+    Redefined(String),
+    /// A `set_code_ref` named an id no `define_code` registered.
+    Undefined(String),
+}
+
+impl fmt::Display for CodeBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Redefined(id) => write!(f, "code id {id:?} is already defined"),
+            Self::Undefined(id) => write!(f, "code id {id:?} is not defined by a define_code"),
+        }
+    }
+}
+
+impl std::error::Error for CodeBookError {}
+
+/// The codes a workload has defined, by id.
+pub struct CodeBook<C> {
+    codes: HashMap<String, C>,
+}
+
+impl<C> Default for CodeBook<C> {
+    fn default() -> Self {
+        Self {
+            codes: HashMap::new(),
+        }
+    }
+}
+
+impl<C> CodeBook<C> {
+    /// Registers `code` under `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `id` is already defined; an id always names
+    /// the same code.
+    pub fn define(&mut self, id: String, code: C) -> Result<(), CodeBookError> {
+        if self.codes.contains_key(&id) {
+            return Err(CodeBookError::Redefined(id));
+        }
+        self.codes.insert(id, code);
+        Ok(())
+    }
+
+    /// Returns the code defined under `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming `id` if it is not defined.
+    pub fn get_mut(&mut self, id: &str) -> Result<&mut C, CodeBookError> {
+        self.codes
+            .get_mut(id)
+            .ok_or_else(|| CodeBookError::Undefined(id.to_string()))
+    }
+
+    /// Number of codes defined.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// Reports whether no code is defined.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_defined_codes_by_id() {
+        let mut book = CodeBook::default();
+        assert!(book.is_empty());
+        assert_eq!(book.define("erc20".to_string(), vec![0x60, 0x01]), Ok(()));
+        assert_eq!(book.get_mut("erc20").map(|code| code.len()), Ok(2));
+        assert_eq!(book.len(), 1);
+    }
+
+    #[test]
+    fn names_the_offending_id() {
+        let mut book = CodeBook::default();
+        assert_eq!(book.define("erc20".to_string(), 1), Ok(()));
+        let Err(e) = book.define("erc20".to_string(), 2) else {
+            panic!("redefinition must be rejected");
+        };
+        assert_eq!(e.to_string(), r#"code id "erc20" is already defined"#);
+        let Err(e) = book.get_mut("erc721") else {
+            panic!("undefined id must be rejected");
+        };
+        assert_eq!(
+            e.to_string(),
+            r#"code id "erc721" is not defined by a define_code"#
+        );
+    }
+}
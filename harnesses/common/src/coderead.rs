@@ -0,0 +1,248 @@
+//! `--read-code N`: once the state is committed, the harness fetches N
+//! code blobs by hash through its client's read API and times each
+//! fetch. The hashes are drawn from the codes in the database with a
+//! fixed seed, so every client and both passes read the same sequence.
+//! The first pass runs on whatever the write phase left cached; the cold
+//! pass reopens the database after dropping the OS page cache, which
+//! needs root, and is skipped with the reason when that is refused.
+//! Both passes share the `--read-bench-timeout-secs` budget; once it runs
+//! out a pass stops before its next read and the report is `truncated`.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::budget::Budget;
+
+/// Why there is no cold pass when the warm one used up the budget.
+pub const OUT_OF_BUDGET: &str = "the read bench budget ran out";
+
+/// Seed of the sample, fixed so runs are comparable.
+const SEED: u64 = 0x5eed_c0de;
+
+/// The `code_read` object of a result.
+#[derive(Debug, Serialize)]
+pub struct CodeReadReport {
+    /// Distinct codes the sample was drawn from.
+    pub codes: u64,
+    /// Set when `--iterate-timeout-secs` stopped the listing of the codes
+    /// early, so the sample is drawn from the first `codes` only.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub codes_truncated: bool,
+    /// Reads with the caches the write phase left behind.
+    pub warm: CodeReadPass,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cold: Option<CodeReadPass>,
+    /// Why there is no cold pass.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cold_skipped: Option<String>,
+    /// Set when the read bench budget ran out before both passes read
+    /// the whole sample.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl CodeReadReport {
+    /// Gathers the passes over a sample of `codes` codes; `cold` is the
+    /// pass or why it was skipped.
+    #[must_use]
+    pub fn new(
+        codes: u64,
+        codes_truncated: bool,
+        warm: CodeReadPass,
+        cold: Result<CodeReadPass, String>,
+    ) -> Self {
+        let truncated = warm.truncated
+            || match &cold {
+                Ok(pass) => pass.truncated,
+                Err(reason) => reason == OUT_OF_BUDGET,
+            };
+        let (cold, cold_skipped) = match cold {
+            Ok(pass) => (Some(pass), None),
+            Err(reason) => (None, Some(reason)),
+        };
+        Self {
+            codes,
+            codes_truncated,
+            warm,
+            cold,
+            cold_skipped,
+            truncated,
+        }
+    }
+}
+
+/// Timings of one pass over the sample.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct CodeReadPass {
+    pub reads: u64,
+    /// Hashes the client found no code for. Every hash was written, so
+    /// any miss is a bug in the harness or the client.
+    pub misses: u64,
+    pub bytes_read: u64,
+    pub code_read_time_ms: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+    /// Set when the budget ran out before the pass read the whole
+    /// sample; `reads` counts those it did.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// Draws `n` hashes from `hashes`, with replacement so `n` may exceed
+/// the number of codes. Duplicates are removed and the rest sorted
+/// first, so the sample does not depend on the order they were listed
+/// in. Returns nothing when there are no codes.
+#[must_use]
+pub fn sample<H: Copy + Ord>(mut hashes: Vec<H>, n: u64) -> Vec<H> {
+    hashes.sort_unstable();
+    hashes.dedup();
+    if hashes.is_empty() {
+        return Vec::new();
+    }
+    let len = hashes.len() as u64;
+    let mut state = SEED;
+    (0..n)
+        .map(|_| {
+            // xorshift64: uniform enough for picking keys, and stable
+            // across platforms and library versions.
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            #[allow(clippy::cast_possible_truncation)]
+            hashes[(state % len) as usize]
+        })
+        .collect()
+}
+
+/// Reads every hash of `sample` with `read`, which returns the length of
+/// the code it found or `None` for a miss, until `budget` runs out.
+///
+/// # Errors
+///
+/// Returns the first error `read` reports.
+pub fn measure<H>(
+    sample: &[H],
+    budget: &Budget,
+    mut read: impl FnMut(&H) -> Result<Option<usize>, String>,
+) -> Result<CodeReadPass, String> {
+    let mut latencies = Vec::with_capacity(sample.len());
+    let mut misses = 0;
+    let mut bytes_read = 0;
+    let mut truncated = false;
+    for hash in sample {
+        if budget.exhausted() {
+            truncated = true;
+            break;
+        }
+        let start = Instant::now();
+        let found = read(hash)?;
+        latencies.push(start.elapsed());
+        match found {
+            Some(len) => bytes_read += len as u64,
+            None => misses += 1,
+        }
+    }
+    let total: Duration = latencies.iter().sum();
+    latencies.sort_unstable();
+    let micros = |p: usize| {
+        // Nearest rank: the smallest latency at least p% of reads took.
+        let rank = (latencies.len() * p).div_ceil(100).max(1);
+        latencies
+            .get(rank - 1)
+            .map_or(0, |d| u64::try_from(d.as_micros()).unwrap_or(u64::MAX))
+    };
+    Ok(CodeReadPass {
+        reads: latencies.len() as u64,
+        misses,
+        bytes_read,
+        code_read_time_ms: u64::try_from(total.as_millis()).unwrap_or(u64::MAX),
+        p50_us: micros(50),
+        p90_us: micros(90),
+        p99_us: micros(99),
+        max_us: micros(100),
+        truncated,
+    })
+}
+
+/// Runs the cold pass with `pass` once the page cache is dropped, unless
+/// `budget` has run out.
+///
+/// # Errors
+///
+/// Returns why the pass was skipped.
+pub fn cold_pass(
+    budget: &Budget,
+    pass: impl FnOnce() -> CodeReadPass,
+) -> Result<CodeReadPass, String> {
+    if budget.exhausted() {
+        return Err(OUT_OF_BUDGET.to_string());
+    }
+    drop_page_cache()?;
+    Ok(pass())
+}
+
+/// Flushes dirty pages and drops the OS page cache, so the next reads
+/// come from disk.
+///
+/// # Errors
+///
+/// Returns why the cache could not be dropped, usually that the process
+/// is not root.
+pub fn drop_page_cache() -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("dropping the page cache is only supported on Linux".to_string());
+    }
+    // SAFETY: sync takes no arguments and cannot fail.
+    unsafe { libc::sync() };
+    std::fs::write("/proc/sys/vm/drop_caches", "1\n").map_err(|e| format!("drop page cache: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_is_deterministic_and_covers_the_codes() {
+        let hashes: Vec<u64> = (0..10).collect();
+        let mut shuffled = hashes.clone();
+        shuffled.reverse();
+        shuffled.extend_from_slice(&hashes);
+        let picked = sample(hashes, 1000);
+        assert_eq!(picked.len(), 1000);
+        assert_eq!(picked, sample(shuffled, 1000));
+        assert!((0..10).all(|hash| picked.contains(&hash)));
+        assert!(sample(Vec::<u64>::new(), 5).is_empty());
+    }
+
+    #[test]
+    fn measure_counts_misses_and_bytes() {
+        let codes = [Some(3), None, Some(5), Some(0)];
+        let Ok(pass) = measure(&[0, 1, 2, 3, 1], &Budget::default(), |&i| Ok(codes[i])) else {
+            panic!("reads must succeed");
+        };
+        assert_eq!((pass.reads, pass.misses, pass.bytes_read), (5, 2, 8));
+        assert!(pass.p50_us <= pass.p90_us && pass.p99_us <= pass.max_us);
+        assert!(!pass.truncated);
+        let failed = measure(&[0], &Budget::default(), |_| Err("closed".to_string()));
+        assert_eq!(failed, Err("closed".to_string()));
+    }
+
+    #[test]
+    fn an_exhausted_budget_truncates_the_bench() {
+        let spent = Budget::until(Instant::now());
+        let Ok(warm) = measure(&[1, 2, 3], &spent, |_| Ok(Some(1))) else {
+            panic!("reads must succeed");
+        };
+        assert_eq!((warm.reads, warm.truncated), (0, true));
+        let cold = cold_pass(&spent, || panic!("the cold pass must not run"));
+        let report = CodeReadReport::new(3, false, warm, cold);
+        assert!(report.truncated);
+        assert_eq!(report.cold_skipped.as_deref(), Some(OUT_OF_BUDGET));
+        let json = serde_json::to_value(&report).unwrap_or_default();
+        assert_eq!(json["truncated"], true);
+        assert!(json.get("codes_truncated").is_none());
+    }
+}
@@ -0,0 +1,246 @@
+//! Compressed workloads on stdin. Large workloads are kept compressed,
+//! and decompressing them inside the harness spares the pipe a `zstd -d`
+//! process, whose scheduling against the harness skews the timings.
+//!
+//! The decoders come with each harness's client dependencies, so this
+//! module only picks the codec, from `--compression` or the magic bytes,
+//! and counts the bytes on both sides of the decoder.
+
+use std::cell::Cell;
+use std::io::{self, BufRead, BufReader, Read};
+use std::rc::Rc;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// First bytes of a gzip member.
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// First bytes of a zstd frame.
+pub const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Size of the buffer the line reader reads decompressed bytes from.
+const DECODED_BUFFER_BYTES: usize = 256 * 1024;
+
+/// Codec of the workload on stdin, from `--compression`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    /// Whichever codec the magic bytes name, or none.
+    #[default]
+    Auto,
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!(
+                "unknown compression {s:?}; expected auto, none, gzip or zstd"
+            )),
+        }
+    }
+}
+
+impl Compression {
+    /// Resolves `Auto` by peeking at the first bytes of `reader`; any
+    /// other value is returned as it is.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the underlying reader.
+    pub fn detect(self, reader: &mut impl BufRead) -> io::Result<Self> {
+        if self != Self::Auto {
+            return Ok(self);
+        }
+        let head = reader.fill_buf()?;
+        Ok(if head.starts_with(&GZIP_MAGIC) {
+            Self::Gzip
+        } else if head.starts_with(&ZSTD_MAGIC) {
+            Self::Zstd
+        } else {
+            Self::None
+        })
+    }
+}
+
+/// Bytes that went through a [`Counted`] reader, still readable once the
+/// reader is gone.
+#[derive(Clone, Debug, Default)]
+pub struct ByteCount(Rc<Cell<u64>>);
+
+impl ByteCount {
+    #[must_use]
+    pub fn get(&self) -> u64 {
+        self.0.get()
+    }
+
+    fn add(&self, n: usize) {
+        self.0.set(self.0.get() + n as u64);
+    }
+}
+
+/// A reader counting the bytes read or consumed through it.
+pub struct Counted<R> {
+    inner: R,
+    count: ByteCount,
+}
+
+impl<R> Counted<R> {
+    pub fn new(inner: R, count: ByteCount) -> Self {
+        Self { inner, count }
+    }
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.add(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Counted<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count.add(amt);
+        self.inner.consume(amt);
+    }
+}
+
+/// Sizes of a compressed workload, in the result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct CompressionReport {
+    pub workload_compression: Compression,
+    /// Bytes read from stdin.
+    pub workload_bytes_compressed: u64,
+    /// Bytes the decoder produced, which the operations were read from.
+    pub workload_bytes_raw: u64,
+}
+
+/// The counters of a workload being decompressed.
+pub struct Decompressed {
+    compression: Compression,
+    compressed: ByteCount,
+    raw: ByteCount,
+}
+
+impl Decompressed {
+    /// The sizes read so far; after the run, the whole workload's.
+    #[must_use]
+    pub fn report(&self) -> CompressionReport {
+        CompressionReport {
+            workload_compression: self.compression,
+            workload_bytes_compressed: self.compressed.get(),
+            workload_bytes_raw: self.raw.get(),
+        }
+    }
+}
+
+/// Wraps `reader` in the decoder `decoder` builds for its codec, and
+/// returns it with the counters to report once it is read. An
+/// uncompressed `reader` is returned as it is, without counters.
+///
+/// # Errors
+///
+/// Returns an error if the start of `reader` cannot be read, or the
+/// error of `decoder`.
+pub fn open<'a, R: BufRead + 'a>(
+    reader: R,
+    compression: Compression,
+    decoder: impl FnOnce(Compression, Counted<R>) -> io::Result<Box<dyn Read + 'a>>,
+) -> io::Result<(Box<dyn BufRead + 'a>, Option<Decompressed>)> {
+    let compressed = ByteCount::default();
+    let mut reader = Counted::new(reader, compressed.clone());
+    let compression = compression.detect(&mut reader)?;
+    if compression == Compression::None {
+        return Ok((Box::new(reader), None));
+    }
+    let raw = ByteCount::default();
+    let decoded = BufReader::with_capacity(DECODED_BUFFER_BYTES, decoder(compression, reader)?);
+    Ok((
+        Box::new(Counted::new(decoded, raw.clone())),
+        Some(Decompressed {
+            compression,
+            compressed,
+            raw,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for a real decoder: drops the magic and repeats every
+    /// byte twice, so the raw size differs from the compressed one.
+    fn doubling<'a>(
+        _: Compression,
+        mut input: impl BufRead + 'a,
+    ) -> io::Result<Box<dyn Read + 'a>> {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let doubled: Vec<u8> = bytes[GZIP_MAGIC.len()..]
+            .iter()
+            .flat_map(|&b| [b, b])
+            .collect();
+        Ok(Box::new(io::Cursor::new(doubled)))
+    }
+
+    #[test]
+    fn detects_the_codec_from_the_magic() {
+        let detect = |input: &[u8]| Compression::Auto.detect(&mut &input[..]).ok();
+        assert_eq!(detect(&[0x1f, 0x8b, 8, 0]), Some(Compression::Gzip));
+        assert_eq!(
+            detect(&[0x28, 0xb5, 0x2f, 0xfd, 0]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(detect(br#"{"op":"compute_root"}"#), Some(Compression::None));
+        assert_eq!(detect(b""), Some(Compression::None));
+        let forced = Compression::Zstd.detect(&mut &b"{}"[..]).ok();
+        assert_eq!(forced, Some(Compression::Zstd));
+    }
+
+    #[test]
+    fn counts_both_sides_of_the_decoder() {
+        let input = [&GZIP_MAGIC[..], b"ab\n"].concat();
+        let Ok((mut reader, Some(decompressed))) = open(&input[..], Compression::Auto, doubling)
+        else {
+            panic!("a gzip magic must select the decoder");
+        };
+        let mut all = String::new();
+        assert!(reader.read_to_string(&mut all).is_ok());
+        assert_eq!(all, "aabb\n\n");
+        drop(reader);
+        assert_eq!(
+            decompressed.report(),
+            CompressionReport {
+                workload_compression: Compression::Gzip,
+                workload_bytes_compressed: 5,
+                workload_bytes_raw: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn passes_plain_input_through() {
+        let input = b"{\"op\":\"compute_root\"}\n";
+        let Ok((mut reader, None)) = open(&input[..], Compression::Auto, doubling) else {
+            panic!("plain input must not be decoded");
+        };
+        let mut all = Vec::new();
+        assert!(reader.read_to_end(&mut all).is_ok());
+        assert_eq!(all, input);
+    }
+}
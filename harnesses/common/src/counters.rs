@@ -0,0 +1,553 @@
+//! Counting rules shared by the harnesses, so that identical workloads
+//! report identical numbers regardless of client.
+
+use std::collections::{BTreeMap, HashSet};
+use std::hash::Hash;
+
+use serde::Serialize;
+
+use crate::delegation;
+
+/// Every workload count a harness reports. The main loops record each
+/// applied operation here, so both harnesses count the same way.
+///
+/// Counts saturate instead of wrapping. Unique storage slots are only
+/// tracked when asked for, since remembering every `(address, slot)`
+/// pair costs memory that shows up in the measured peak.
+pub struct Counters<A, S> {
+    code: CodeCounters<A>,
+    ops_applied: u64,
+    accounts_created: u64,
+    accounts_deleted: u64,
+    selfdestructs: u64,
+    storage_clears: u64,
+    balance_updates: u64,
+    transfers: u64,
+    nonce_increments: u64,
+    accounts_touched: u64,
+    empty_accounts_pruned: u64,
+    storage_slots: u64,
+    seen_slots: Option<HashSet<(A, S)>>,
+    per_tenant: BTreeMap<u32, u64>,
+}
+
+/// The counts as serialized into the result.
+#[derive(Serialize)]
+pub struct CounterTotals {
+    pub accounts_created: u64,
+    pub accounts_deleted: u64,
+    pub selfdestructs: u64,
+    pub storage_clears: u64,
+    /// `update_balance` operations; a `transfer` is counted apart.
+    pub balance_updates: u64,
+    pub transfers: u64,
+    /// Nonce bumps of `increment_nonce`, each line adding its `count`.
+    pub nonce_increments: u64,
+    pub accounts_touched: u64,
+    /// Empty accounts removed at commit under `--prune-empty-accounts`.
+    pub empty_accounts_pruned: u64,
+    pub contracts_created: u64,
+    pub code_updates: u64,
+    pub empty_code_sets: u64,
+    /// `set_delegation` operations, none of which counts as a contract.
+    pub delegations_created: u64,
+    /// Bytes of code deployed, designations included; code deployed to
+    /// several addresses counts once per address.
+    pub code_bytes: u64,
+    /// Storage writes, including the `storage` of `create_account`; a
+    /// slot written twice counts twice.
+    pub storage_slots: u64,
+    /// Distinct `(address, slot)` pairs written, when tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique_storage_slots: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub per_tenant: BTreeMap<u32, u64>,
+}
+
+impl<A, S> Default for Counters<A, S> {
+    fn default() -> Self {
+        Self {
+            code: CodeCounters::default(),
+            ops_applied: 0,
+            accounts_created: 0,
+            accounts_deleted: 0,
+            selfdestructs: 0,
+            storage_clears: 0,
+            balance_updates: 0,
+            transfers: 0,
+            nonce_increments: 0,
+            accounts_touched: 0,
+            empty_accounts_pruned: 0,
+            storage_slots: 0,
+            seen_slots: None,
+            per_tenant: BTreeMap::new(),
+        }
+    }
+}
+
+impl<A: Hash + Eq, S: Hash + Eq> Counters<A, S> {
+    /// Counters that also track unique storage slots.
+    #[must_use]
+    pub fn with_unique_slots() -> Self {
+        Self {
+            seen_slots: Some(HashSet::new()),
+            ..Self::default()
+        }
+    }
+
+    /// Records the tenant tag of a decoded operation.
+    pub fn record_tenant(&mut self, tenant: Option<u32>) {
+        if let Some(tenant) = tenant {
+            let ops = self.per_tenant.entry(tenant).or_default();
+            *ops = ops.saturating_add(1);
+        }
+    }
+
+    /// Records a fully applied operation and returns how many have been
+    /// applied so far.
+    pub fn record_applied(&mut self) -> u64 {
+        self.ops_applied = self.ops_applied.saturating_add(1);
+        self.ops_applied
+    }
+
+    /// Records a `create_account`. Its storage is recorded separately,
+    /// one [`Self::record_storage_write`] per slot.
+    pub fn record_account_created(&mut self) {
+        self.accounts_created = self.accounts_created.saturating_add(1);
+    }
+
+    /// Records a `delete_account` of `address`. Code deployed to it
+    /// after a re-create counts as a new contract again.
+    pub fn record_account_deleted(&mut self, address: &A) {
+        self.accounts_deleted = self.accounts_deleted.saturating_add(1);
+        self.code.with_code.remove(address);
+    }
+
+    /// Records a `selfdestruct` of `address`. Like a deletion, it frees
+    /// the address for a new contract.
+    pub fn record_selfdestruct(&mut self, address: &A) {
+        self.selfdestructs = self.selfdestructs.saturating_add(1);
+        self.code.with_code.remove(address);
+    }
+
+    /// Records a `clear_storage`. The account keeps its code, so it stays
+    /// a contract.
+    pub fn record_storage_cleared(&mut self) {
+        self.storage_clears = self.storage_clears.saturating_add(1);
+    }
+
+    /// Records an `update_balance`.
+    pub fn record_balance_update(&mut self) {
+        self.balance_updates = self.balance_updates.saturating_add(1);
+    }
+
+    /// Records a `transfer`.
+    pub fn record_transfer(&mut self) {
+        self.transfers = self.transfers.saturating_add(1);
+    }
+
+    /// Records an `increment_nonce` of `count` bumps.
+    pub fn record_nonce_increment(&mut self, count: u64) {
+        self.nonce_increments = self.nonce_increments.saturating_add(count);
+    }
+
+    /// Records a `touch_account`.
+    pub fn record_account_touched(&mut self) {
+        self.accounts_touched = self.accounts_touched.saturating_add(1);
+    }
+
+    /// Records `count` empty accounts pruned by a commit.
+    pub fn record_empty_pruned(&mut self, count: u64) {
+        self.empty_accounts_pruned = self.empty_accounts_pruned.saturating_add(count);
+    }
+
+    /// Records a write of `slot` in the storage of `address`.
+    pub fn record_storage_write(&mut self, address: A, slot: S) {
+        self.storage_slots = self.storage_slots.saturating_add(1);
+        if let Some(seen) = &mut self.seen_slots {
+            seen.insert((address, slot));
+        }
+    }
+
+    /// Records a `set_code` of `code_len` bytes on `address`.
+    pub fn record_set_code(&mut self, address: A, code_len: usize) {
+        self.code.record_set_code(address, code_len);
+    }
+
+    /// Records a `set_delegation` on `address`.
+    pub fn record_delegation(&mut self, address: &A) {
+        self.code.record_delegation(address);
+    }
+
+    /// Operations applied so far.
+    #[must_use]
+    pub fn ops_applied(&self) -> u64 {
+        self.ops_applied
+    }
+
+    /// Accounts created so far.
+    #[must_use]
+    pub fn accounts_created(&self) -> u64 {
+        self.accounts_created
+    }
+
+    /// Contracts created so far.
+    #[must_use]
+    pub fn contracts_created(&self) -> u64 {
+        self.code.contracts_created
+    }
+
+    /// Storage writes so far.
+    #[must_use]
+    pub fn storage_slots(&self) -> u64 {
+        self.storage_slots
+    }
+
+    /// A warning naming the counts that reached `u64::MAX`, or `None` if
+    /// none did. They stopped there instead of wrapping, so each is only
+    /// a lower bound.
+    #[must_use]
+    pub fn saturation_warning(&self) -> Option<String> {
+        let counts = [
+            ("ops_applied", self.ops_applied),
+            ("accounts_created", self.accounts_created),
+            ("accounts_deleted", self.accounts_deleted),
+            ("selfdestructs", self.selfdestructs),
+            ("storage_clears", self.storage_clears),
+            ("balance_updates", self.balance_updates),
+            ("transfers", self.transfers),
+            ("nonce_increments", self.nonce_increments),
+            ("accounts_touched", self.accounts_touched),
+            ("empty_accounts_pruned", self.empty_accounts_pruned),
+            ("contracts_created", self.code.contracts_created),
+            ("code_updates", self.code.code_updates),
+            ("empty_code_sets", self.code.empty_code_sets),
+            ("delegations_created", self.code.delegations_created),
+            ("code_bytes", self.code.code_bytes),
+            ("storage_slots", self.storage_slots),
+            (
+                "per_tenant",
+                self.per_tenant.values().copied().max().unwrap_or(0),
+            ),
+        ];
+        let saturated: Vec<&str> = counts
+            .iter()
+            .filter(|(_, count)| *count == u64::MAX)
+            .map(|(name, _)| *name)
+            .collect();
+        (!saturated.is_empty())
+            .then(|| format!("counts saturated at u64::MAX: {}", saturated.join(", ")))
+    }
+
+    /// The counts for the result.
+    #[must_use]
+    pub fn totals(&self) -> CounterTotals {
+        CounterTotals {
+            accounts_created: self.accounts_created,
+            accounts_deleted: self.accounts_deleted,
+            selfdestructs: self.selfdestructs,
+            storage_clears: self.storage_clears,
+            balance_updates: self.balance_updates,
+            transfers: self.transfers,
+            nonce_increments: self.nonce_increments,
+            accounts_touched: self.accounts_touched,
+            empty_accounts_pruned: self.empty_accounts_pruned,
+            contracts_created: self.code.contracts_created,
+            code_updates: self.code.code_updates,
+            empty_code_sets: self.code.empty_code_sets,
+            delegations_created: self.code.delegations_created,
+            code_bytes: self.code.code_bytes,
+            storage_slots: self.storage_slots,
+            unique_storage_slots: self.seen_slots.as_ref().map(|seen| seen.len() as u64),
+            per_tenant: self.per_tenant.clone(),
+        }
+    }
+}
+
+/// Classifies `set_code` operations by their effect on an address.
+///
+/// An address counts as a created contract the first time it receives
+/// non-empty code; later `set_code` operations on it are code updates.
+/// Zero-length code is counted separately and never creates a contract,
+/// and neither does a delegation designation.
+pub struct CodeCounters<A> {
+    with_code: HashSet<A>,
+    pub contracts_created: u64,
+    pub code_updates: u64,
+    pub empty_code_sets: u64,
+    pub delegations_created: u64,
+    pub code_bytes: u64,
+}
+
+impl<A> Default for CodeCounters<A> {
+    fn default() -> Self {
+        Self {
+            with_code: HashSet::new(),
+            contracts_created: 0,
+            code_updates: 0,
+            empty_code_sets: 0,
+            delegations_created: 0,
+            code_bytes: 0,
+        }
+    }
+}
+
+impl<A: Hash + Eq> CodeCounters<A> {
+    /// Records a `set_code` of `code_len` bytes on `address`.
+    pub fn record_set_code(&mut self, address: A, code_len: usize) {
+        let count = if code_len == 0 {
+            &mut self.empty_code_sets
+        } else if self.with_code.insert(address) {
+            &mut self.contracts_created
+        } else {
+            &mut self.code_updates
+        };
+        *count = count.saturating_add(1);
+        self.code_bytes = self.code_bytes.saturating_add(code_len as u64);
+    }
+
+    /// Records a `set_delegation` on `address`. The designation replaces
+    /// the address's code, so code deployed to it afterwards is a new
+    /// contract again.
+    pub fn record_delegation(&mut self, address: &A) {
+        self.with_code.remove(address);
+        self.delegations_created = self.delegations_created.saturating_add(1);
+        self.code_bytes = self.code_bytes.saturating_add(delegation::LEN as u64);
+    }
+}
+
+/// Input bytes consumed per operation type. Each workload line is
+/// attributed whole to its `op`, excluding the line terminator; the
+/// workload format has no batched operations to split.
+#[derive(Clone, Default)]
+pub struct InputBytes {
+    by_op: BTreeMap<String, u64>,
+    lines: u64,
+    total: u64,
+}
+
+impl InputBytes {
+    /// Records a decoded line of `len` bytes carrying `op`.
+    pub fn record(&mut self, op: &str, len: usize) {
+        let len = len as u64;
+        if let Some(bytes) = self.by_op.get_mut(op) {
+            *bytes = bytes.saturating_add(len);
+        } else {
+            self.by_op.insert(op.to_string(), len);
+        }
+        self.lines = self.lines.saturating_add(1);
+        self.total = self.total.saturating_add(len);
+    }
+
+    /// Returns the bytes per operation type and the mean line length, or
+    /// `None` for the mean when no line was recorded.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn into_parts(self) -> (BTreeMap<String, u64>, Option<f64>) {
+        let avg = (self.lines > 0).then(|| self.total as f64 / self.lines as f64);
+        (self.by_op, avg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinguishes_creation_update_and_empty_code() {
+        let mut counters = CodeCounters::default();
+        counters.record_set_code(1, 10);
+        counters.record_set_code(1, 10);
+        counters.record_set_code(1, 20);
+        counters.record_set_code(2, 0);
+        counters.record_set_code(2, 5);
+
+        assert_eq!(counters.contracts_created, 2);
+        assert_eq!(counters.code_updates, 2);
+        assert_eq!(counters.empty_code_sets, 1);
+        assert_eq!(counters.code_bytes, 45);
+    }
+
+    #[test]
+    fn counts_delegations_apart_from_contracts() {
+        let mut counters = CodeCounters::default();
+        counters.record_set_code(1, 10);
+        counters.record_delegation(&1);
+        counters.record_delegation(&2);
+        counters.record_set_code(1, 10);
+
+        assert_eq!(counters.delegations_created, 2);
+        assert_eq!(counters.contracts_created, 2);
+        assert_eq!(counters.code_updates, 0);
+        assert_eq!(counters.code_bytes, 10 + 23 + 23 + 10);
+    }
+
+    #[test]
+    fn counts_every_operation_kind() {
+        let mut counters = Counters::default();
+        counters.record_account_created();
+        counters.record_storage_write(1, 7);
+        counters.record_storage_write(1, 7);
+        counters.record_set_code(1, 3);
+        counters.record_account_deleted(&1);
+        counters.record_set_code(1, 3);
+        counters.record_selfdestruct(&1);
+        counters.record_set_code(1, 3);
+        counters.record_storage_cleared();
+        counters.record_set_code(1, 3);
+        counters.record_balance_update();
+        counters.record_transfer();
+        counters.record_transfer();
+        counters.record_nonce_increment(1);
+        counters.record_nonce_increment(3);
+        counters.record_account_touched();
+        counters.record_empty_pruned(2);
+        counters.record_tenant(Some(2));
+        counters.record_tenant(None);
+        assert_eq!(counters.record_applied(), 1);
+        assert_eq!(counters.record_applied(), 2);
+
+        let totals = counters.totals();
+        assert_eq!(totals.accounts_created, 1);
+        assert_eq!(totals.accounts_deleted, 1);
+        assert_eq!((totals.selfdestructs, totals.storage_clears), (1, 1));
+        assert_eq!((totals.balance_updates, totals.transfers), (1, 2));
+        assert_eq!(totals.nonce_increments, 4);
+        assert_eq!(
+            (totals.accounts_touched, totals.empty_accounts_pruned),
+            (1, 2)
+        );
+        assert_eq!(totals.contracts_created, 3);
+        assert_eq!(totals.code_updates, 1);
+        assert_eq!(totals.storage_slots, 2);
+        assert_eq!(totals.unique_storage_slots, None);
+        assert_eq!(totals.per_tenant, BTreeMap::from([(2, 1)]));
+        let json = serde_json::to_string(&totals).unwrap_or_default();
+        assert!(!json.contains("unique_storage_slots"));
+    }
+
+    #[test]
+    fn tracks_unique_slots_on_request() {
+        let mut counters = Counters::with_unique_slots();
+        for (address, slot) in [(1, 7), (1, 7), (1, 8), (2, 7)] {
+            counters.record_storage_write(address, slot);
+        }
+        assert_eq!(counters.storage_slots(), 4);
+        assert_eq!(counters.totals().unique_storage_slots, Some(3));
+    }
+
+    #[test]
+    fn counts_saturate() {
+        let mut counters: Counters<u8, u8> = Counters {
+            storage_slots: u64::MAX,
+            ..Counters::default()
+        };
+        counters.record_storage_write(0, 0);
+        assert_eq!(counters.storage_slots(), u64::MAX);
+        assert_eq!(
+            counters.saturation_warning().as_deref(),
+            Some("counts saturated at u64::MAX: storage_slots")
+        );
+        assert_eq!(Counters::<u8, u8>::default().saturation_warning(), None);
+    }
+
+    /// A run long enough to exhaust every count: each keeps its maximum
+    /// instead of wrapping or panicking.
+    #[test]
+    fn huge_runs_saturate_every_count() {
+        let mut counters: Counters<u8, u8> = Counters::default();
+        for _ in 0..3 {
+            counters.record_nonce_increment(u64::MAX);
+            counters.record_empty_pruned(u64::MAX);
+        }
+        let mut counters = Counters {
+            ops_applied: u64::MAX - 1,
+            accounts_created: u64::MAX,
+            accounts_deleted: u64::MAX,
+            selfdestructs: u64::MAX,
+            storage_clears: u64::MAX,
+            balance_updates: u64::MAX,
+            transfers: u64::MAX,
+            accounts_touched: u64::MAX,
+            storage_slots: u64::MAX,
+            per_tenant: BTreeMap::from([(1, u64::MAX)]),
+            code: CodeCounters {
+                contracts_created: u64::MAX,
+                code_updates: u64::MAX,
+                empty_code_sets: u64::MAX,
+                delegations_created: u64::MAX,
+                code_bytes: u64::MAX,
+                ..CodeCounters::default()
+            },
+            ..counters
+        };
+        for _ in 0..3 {
+            counters.record_applied();
+            counters.record_account_created();
+            counters.record_account_deleted(&1);
+            counters.record_selfdestruct(&1);
+            counters.record_storage_cleared();
+            counters.record_balance_update();
+            counters.record_transfer();
+            counters.record_account_touched();
+            counters.record_storage_write(1, 1);
+            counters.record_set_code(1, 0);
+            counters.record_set_code(1, 1);
+            counters.record_set_code(1, 1);
+            counters.record_delegation(&1);
+            counters.record_tenant(Some(1));
+        }
+        let totals = counters.totals();
+        assert_eq!(counters.ops_applied(), u64::MAX);
+        for count in [
+            totals.accounts_created,
+            totals.accounts_deleted,
+            totals.selfdestructs,
+            totals.storage_clears,
+            totals.balance_updates,
+            totals.transfers,
+            totals.nonce_increments,
+            totals.accounts_touched,
+            totals.empty_accounts_pruned,
+            totals.contracts_created,
+            totals.code_updates,
+            totals.empty_code_sets,
+            totals.delegations_created,
+            totals.code_bytes,
+            totals.storage_slots,
+            totals.per_tenant[&1],
+        ] {
+            assert_eq!(count, u64::MAX);
+        }
+        let Some(warning) = counters.saturation_warning() else {
+            panic!("saturated counts must warn");
+        };
+        assert!(warning.contains("ops_applied"), "{warning}");
+        assert!(warning.contains("per_tenant"), "{warning}");
+
+        let mut input = InputBytes {
+            total: u64::MAX,
+            lines: u64::MAX,
+            ..InputBytes::default()
+        };
+        input.record("set_storage", usize::MAX);
+        input.record("set_storage", usize::MAX);
+        let (by_op, avg) = input.into_parts();
+        assert_eq!(by_op.get("set_storage"), Some(&u64::MAX));
+        assert_eq!(avg, Some(1.0));
+    }
+
+    #[test]
+    fn attributes_line_bytes_to_ops() {
+        let mut input = InputBytes::default();
+        input.record("set_storage", 100);
+        input.record("set_storage", 110);
+        input.record("compute_root", 20);
+
+        let (by_op, avg) = input.into_parts();
+        assert_eq!(by_op.get("set_storage"), Some(&210));
+        assert_eq!(by_op.get("compute_root"), Some(&20));
+        assert_eq!(avg, Some(230.0 / 3.0));
+        assert_eq!(InputBytes::default().into_parts().1, None);
+    }
+}
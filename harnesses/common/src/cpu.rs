@@ -0,0 +1,108 @@
+//! Per-phase CPU accounting, to flag background threads (compaction,
+//! thread pools) whose work spills across the phase boundaries the
+//! harnesses time. Linux-only; elsewhere nothing is sampled.
+
+use std::collections::BTreeMap;
+
+use crate::sysmetrics::stat_cpu_ms;
+
+/// Background CPU beyond the main thread's own CPU, in milliseconds,
+/// above which a phase is reported.
+pub const BACKGROUND_CPU_THRESHOLD_MS: u64 = 100;
+
+/// CPU time consumed so far by the main thread and by the whole process.
+#[derive(Clone, Copy)]
+pub struct CpuSample {
+    main_ms: u64,
+    total_ms: u64,
+}
+
+impl CpuSample {
+    /// Samples the current CPU times. The process total comes from
+    /// `/proc/self/stat`, which unlike summing `/proc/self/task/*/stat`
+    /// still counts threads that already exited.
+    #[must_use]
+    pub fn now() -> Option<Self> {
+        let pid = std::process::id();
+        Some(Self {
+            main_ms: stat_cpu_ms(&format!("/proc/self/task/{pid}/stat"))?,
+            total_ms: stat_cpu_ms("/proc/self/stat")?,
+        })
+    }
+
+    /// Splits the CPU used between `start` and this sample into the main
+    /// thread's and every other thread's, in milliseconds.
+    #[must_use]
+    pub fn since(self, start: Self) -> (u64, u64) {
+        let main_ms = self.main_ms.saturating_sub(start.main_ms);
+        let other_ms = self
+            .total_ms
+            .saturating_sub(start.total_ms)
+            .saturating_sub(main_ms);
+        (main_ms, other_ms)
+    }
+}
+
+/// Collects the background CPU attributed to each phase.
+#[derive(Default)]
+pub struct BackgroundCpu {
+    per_phase: BTreeMap<&'static str, u64>,
+    warnings: Vec<String>,
+}
+
+impl BackgroundCpu {
+    /// Attributes the CPU spent between two samples to `phase`, keeping
+    /// it only when other threads used noticeably more CPU than the main
+    /// thread did.
+    pub fn record(
+        &mut self,
+        phase: &'static str,
+        start: Option<CpuSample>,
+        end: Option<CpuSample>,
+    ) {
+        let (Some(start), Some(end)) = (start, end) else {
+            return;
+        };
+        let (main_ms, background_ms) = end.since(start);
+        if background_ms > main_ms + BACKGROUND_CPU_THRESHOLD_MS {
+            self.per_phase.insert(phase, background_ms);
+            self.warnings.push(format!(
+                "{phase}: background threads used {background_ms}ms CPU vs {main_ms}ms on the main thread; phase timings may overlap"
+            ));
+        }
+    }
+
+    /// Returns the reported per-phase background CPU in milliseconds and
+    /// the matching warnings.
+    #[must_use]
+    pub fn into_parts(self) -> (BTreeMap<&'static str, u64>, Vec<String>) {
+        (self.per_phase, self.warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_only_phases_dominated_by_background_threads() {
+        let sample = |main_ms, total_ms| Some(CpuSample { main_ms, total_ms });
+        let mut cpu = BackgroundCpu::default();
+        cpu.record("trie", sample(0, 0), sample(500, 550));
+        cpu.record("db_write", sample(500, 550), sample(600, 1200));
+        cpu.record("skipped", None, sample(0, 0));
+
+        let (per_phase, warnings) = cpu.into_parts();
+        assert_eq!(
+            per_phase.into_iter().collect::<Vec<_>>(),
+            [("db_write", 550)]
+        );
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn samples_own_process() {
+        assert!(CpuSample::now().is_some_and(|s| s.total_ms >= s.main_ms));
+    }
+}
@@ -0,0 +1,33 @@
+//! EIP-7702 delegation designations. Since Prague, an EOA that delegates
+//! to a contract carries the 23 bytes `0xef0100 || target` as its code.
+//! A `set_delegation` line names only the target; the harnesses build the
+//! designation here and store it as any other code, under its hash.
+
+/// The bytes a designation starts with: the `0xef` no contract may begin
+/// with since EIP-3541, then the designation version.
+pub const PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+/// Length of a designation: the prefix and a 20-byte address.
+pub const LEN: usize = PREFIX.len() + 20;
+
+/// The code of an account delegating to `target`.
+#[must_use]
+pub fn designation(target: [u8; 20]) -> [u8; LEN] {
+    let mut code = [0; LEN];
+    code[..PREFIX.len()].copy_from_slice(&PREFIX);
+    code[PREFIX.len()..].copy_from_slice(&target);
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_the_target() {
+        let code = designation([0xaa; 20]);
+        assert_eq!(code.len(), 23);
+        assert_eq!(&code[..3], &[0xef, 0x01, 0x00]);
+        assert!(code[3..].iter().all(|&b| b == 0xaa));
+    }
+}
@@ -0,0 +1,123 @@
+//! Derived addresses for `create_accounts`, which creates `count`
+//! accounts in one line instead of one `create_account` line each.
+//! Account `i`, counting from zero, gets the last 20 bytes of
+//! `keccak256(seed || i)` with `i` as a big-endian `u64`. Each harness
+//! hashes with its own client's keccak; the seed and preimages come from
+//! here, so the harnesses derive the same addresses.
+
+/// Parses a `create_accounts` seed: `0x`-prefixed hex with an even
+/// number of digits, taken as raw bytes.
+///
+/// # Errors
+///
+/// Returns an error naming `s` if it is not such a hex string.
+pub fn parse_seed(s: &str) -> Result<Vec<u8>, String> {
+    let invalid = |reason| format!("invalid create_accounts seed {s:?}: {reason}");
+    let digits = s
+        .strip_prefix("0x")
+        .ok_or_else(|| invalid("missing 0x prefix"))?;
+    if !digits.len().is_multiple_of(2) {
+        return Err(invalid("odd number of hex digits"));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            digits
+                .get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| invalid("not a hex digit"))
+        })
+        .collect()
+}
+
+/// The addresses of a `create_accounts` line, derived one at a time so
+/// none are held beyond the one being created.
+pub struct DerivedAddresses<H> {
+    /// The seed followed by the 8 bytes of the current index.
+    preimage: Vec<u8>,
+    next: u64,
+    count: u64,
+    keccak: H,
+}
+
+/// Derives the `count` addresses of `seed` with `keccak`.
+#[must_use]
+pub fn addresses<H>(seed: &[u8], count: u64, keccak: H) -> DerivedAddresses<H>
+where
+    H: FnMut(&[u8]) -> [u8; 32],
+{
+    let mut preimage = Vec::with_capacity(seed.len() + 8);
+    preimage.extend_from_slice(seed);
+    preimage.extend_from_slice(&[0; 8]);
+    DerivedAddresses {
+        preimage,
+        next: 0,
+        count,
+        keccak,
+    }
+}
+
+impl<H> Iterator for DerivedAddresses<H>
+where
+    H: FnMut(&[u8]) -> [u8; 32],
+{
+    type Item = [u8; 20];
+
+    fn next(&mut self) -> Option<[u8; 20]> {
+        if self.next == self.count {
+            return None;
+        }
+        let index_at = self.preimage.len() - 8;
+        self.preimage[index_at..].copy_from_slice(&self.next.to_be_bytes());
+        self.next += 1;
+        let hash = (self.keccak)(&self.preimage);
+        let mut address = [0; 20];
+        address.copy_from_slice(&hash[12..]);
+        Some(address)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = usize::try_from(self.count - self.next).unwrap_or(usize::MAX);
+        (left, Some(left))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for keccak by right-aligning the preimage, so its last
+    /// 20 bytes end up in the address.
+    fn identity(preimage: &[u8]) -> [u8; 32] {
+        let mut hash = [0; 32];
+        hash[32 - preimage.len()..].copy_from_slice(preimage);
+        hash
+    }
+
+    #[test]
+    fn appends_the_big_endian_index_to_the_seed() {
+        let Ok(seed) = parse_seed("0x5eed") else {
+            panic!("0x5eed is a valid seed");
+        };
+        let derived: Vec<[u8; 20]> = addresses(&seed, 3, identity).collect();
+        assert_eq!(derived.len(), 3);
+        for (i, address) in (0u8..).zip(&derived) {
+            let mut want = [0; 20];
+            want[10..12].copy_from_slice(&[0x5e, 0xed]);
+            want[19] = i;
+            assert_eq!(*address, want);
+        }
+        assert_eq!(addresses(&seed, 0, identity).next(), None);
+    }
+
+    #[test]
+    fn rejects_seeds_that_are_not_whole_bytes() {
+        assert_eq!(parse_seed("0x"), Ok(Vec::new()));
+        for seed in ["5eed", "0x5ee", "0x5eeg"] {
+            let Err(e) = parse_seed(seed) else {
+                panic!("{seed} must be rejected");
+            };
+            assert!(e.contains(&format!("{seed:?}")), "{e}");
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Disk space measurement and out-of-space handling for the write phase.
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/// Exit code used when a run stops because the disk is (nearly) full.
+pub const EXIT_DISK_FULL: i32 = 4;
+
+/// How often, in written entries, the write phase re-checks free space
+/// when a reserve is configured.
+pub const FREE_SPACE_CHECK_INTERVAL: u64 = 65_536;
+
+/// Durability level of a run whose committed data was fsynced before the
+/// write phase ended.
+pub const DURABLE: &str = "durable";
+
+/// Durability level of a run whose commit returned once the data reached
+/// the OS; for `RocksDB`, the WAL was written but not synced.
+pub const OS_BUFFERED: &str = "os_buffered";
+
+/// Partial result emitted instead of a `BenchResult` when the write
+/// phase runs out of space.
+#[derive(Serialize)]
+pub struct DiskFullReport {
+    pub client: &'static str,
+    pub disk_full: bool,
+    /// `"enospc"` when the backend failed, `"reserve"` when the run
+    /// stopped early to keep `--reserve-free-gb` free.
+    pub reason: &'static str,
+    pub phase: &'static str,
+    pub db_bytes_written: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_bytes_at_start: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Tracks free space on the database filesystem during the write phase.
+pub struct SpaceGuard {
+    db_path: PathBuf,
+    reserve_bytes: u64,
+    free_bytes_at_start: Option<u64>,
+}
+
+impl SpaceGuard {
+    /// Records the free space below `db_path` and the reserve to keep,
+    /// in GiB; a zero reserve disables early stopping.
+    #[must_use]
+    pub fn new(db_path: &Path, reserve_gb: u64) -> Self {
+        Self {
+            db_path: db_path.to_path_buf(),
+            reserve_bytes: reserve_gb.saturating_mul(1 << 30),
+            free_bytes_at_start: free_bytes(db_path),
+        }
+    }
+
+    /// Reports whether free space has dropped below the reserve. Only
+    /// checks every [`FREE_SPACE_CHECK_INTERVAL`] written entries.
+    #[must_use]
+    pub fn reserve_exhausted(&self, written: u64) -> bool {
+        if self.reserve_bytes == 0 || !written.is_multiple_of(FREE_SPACE_CHECK_INTERVAL) {
+            return false;
+        }
+        free_bytes(&self.db_path).is_some_and(|free| free < self.reserve_bytes)
+    }
+
+    /// Builds the partial result for a write phase stopped for `reason`.
+    #[must_use]
+    pub fn report(
+        &self,
+        client: &'static str,
+        reason: &'static str,
+        error: Option<String>,
+    ) -> DiskFullReport {
+        DiskFullReport {
+            client,
+            disk_full: true,
+            reason,
+            phase: "db_write",
+            db_bytes_written: dir_size(&self.db_path),
+            free_bytes_at_start: self.free_bytes_at_start,
+            error,
+        }
+    }
+}
+
+/// Reports whether a backend error means the filesystem is out of space.
+///
+/// Backends wrap the OS error in their own types, so this matches the
+/// errno text and number rather than a concrete error type.
+pub fn is_disk_full(err: &dyn Display) -> bool {
+    let msg = err.to_string();
+    msg.contains("No space left on device") || msg.contains("ENOSPC") || msg.contains("os error 28")
+}
+
+/// Returns the bytes available to unprivileged users on the filesystem
+/// holding `path`, or `None` where that cannot be determined.
+#[cfg(unix)]
+#[must_use]
+pub fn free_bytes(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is a
+    // properly sized, writable statvfs struct.
+    if unsafe { libc::statvfs(c_path.as_ptr(), &raw mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::useless_conversion)]
+    Some(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+/// Returns `None`: free space is not measured on this platform.
+#[cfg(not(unix))]
+#[must_use]
+pub fn free_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Returns the sum of file lengths below `path`, ignoring unreadable
+/// entries.
+#[must_use]
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Returns the sum of the lengths of the `RocksDB` table files (`*.sst`)
+/// in `path`: the data the database holds once flushed, without its WAL,
+/// manifest or logs.
+#[must_use]
+pub fn sst_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "sst"))
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Fsyncs every file below `path` and then the directories themselves,
+/// so whatever a backend has handed to the OS is durable on return.
+///
+/// # Errors
+///
+/// Returns the first error from opening or syncing an entry.
+pub fn sync_tree(path: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            sync_tree(&entry.path())?;
+        } else if file_type.is_file() {
+            std::fs::File::open(entry.path())?.sync_all()?;
+        }
+    }
+    std::fs::File::open(path)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_out_of_space_errors() {
+        let io = std::io::Error::from_raw_os_error(28);
+        assert!(is_disk_full(&io));
+        assert!(is_disk_full(&"IO error: No space left on device"));
+        assert!(!is_disk_full(&"commit failed: MDBX_BAD_TXN"));
+    }
+
+    #[test]
+    fn zero_reserve_never_triggers() {
+        let guard = SpaceGuard::new(&std::env::temp_dir(), 0);
+        assert!(!guard.reserve_exhausted(0));
+        let huge = SpaceGuard::new(&std::env::temp_dir(), u64::MAX);
+        assert!(huge.reserve_exhausted(0));
+        assert!(!huge.reserve_exhausted(1));
+    }
+
+    #[test]
+    fn measures_directory_size_recursively() {
+        let dir = std::env::temp_dir().join(format!("statoor-disk-test-{}", std::process::id()));
+        let nested = dir.join("nested");
+        assert!(std::fs::create_dir_all(&nested).is_ok());
+        assert!(std::fs::write(dir.join("a"), [0u8; 10]).is_ok());
+        assert!(std::fs::write(nested.join("b"), [0u8; 5]).is_ok());
+
+        assert_eq!(dir_size(&dir), 15);
+        assert_eq!(sst_size(&dir), 0);
+        assert!(std::fs::write(dir.join("000012.sst"), [0u8; 7]).is_ok());
+        assert!(std::fs::write(dir.join("000013.log"), [0u8; 3]).is_ok());
+        assert_eq!(sst_size(&dir), 7);
+        assert!(free_bytes(&dir).is_some_and(|free| free > 0));
+        assert!(sync_tree(&dir).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
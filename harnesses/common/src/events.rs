@@ -0,0 +1,85 @@
+//! NDJSON event log of applied operations.
+//!
+//! Each line records the sequence number of an operation (its one-based
+//! workload line) and when it was applied relative to the start of the
+//! run. The orchestrator replays workloads from this log with the
+//! original pacing, so the schema is versioned and must stay stable.
+
+use std::io::{self, Write};
+use std::time::Instant;
+
+use serde::Serialize;
+
+/// Version stamped into every event line.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct EventLine<'a> {
+    v: u32,
+    seq: u64,
+    t_us: u64,
+    event: &'a str,
+    op: &'a str,
+}
+
+/// Writes one event line per applied operation.
+pub struct EventLog<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl<W: Write> EventLog<W> {
+    /// Creates a log whose timestamps are relative to `start`.
+    pub fn new(out: W, start: Instant) -> Self {
+        Self { out, start }
+    }
+
+    /// Records that the operation on workload line `seq` was applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the underlying sink fails.
+    pub fn op(&mut self, seq: u64, op: &str) -> io::Result<()> {
+        let event = EventLine {
+            v: SCHEMA_VERSION,
+            seq,
+            t_us: u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX),
+            event: "op",
+            op,
+        };
+        serde_json::to_writer(&mut self.out, &event)?;
+        self.out.write_all(b"\n")
+    }
+
+    /// Flushes buffered events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if flushing the underlying sink fails.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_versioned_line_per_op() {
+        let mut log = EventLog::new(Vec::new(), Instant::now());
+        assert!(log.op(1, "create_account").is_ok());
+        assert!(log.op(2, "compute_root").is_ok());
+
+        let text = String::from_utf8(log.out).unwrap_or_default();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["v"], SCHEMA_VERSION);
+        assert_eq!(lines[0]["seq"], 1);
+        assert_eq!(lines[1]["op"], "compute_root");
+        assert!(lines[1]["t_us"].as_u64() >= lines[0]["t_us"].as_u64());
+    }
+}
@@ -0,0 +1,145 @@
+//! Example workloads for new users, embedded from `examples/` so the
+//! `statoor examples` command and the self-tests read the same files.
+//!
+//! An example is a workload with `#` comment lines explaining it. The
+//! first comment is its summary and the second names the root it must
+//! end at, `# expected_root: 0x...`. Harnesses do not read comments, so
+//! [`Example::workload`] drops them before a run.
+
+use crate::fixtures::Outcome;
+
+/// The comment line prefix of an example.
+const COMMENT: &str = "#";
+
+/// The comment naming the root an example ends at.
+const EXPECTED_ROOT: &str = "# expected_root: ";
+
+/// An embedded example workload.
+pub struct Example {
+    pub name: &'static str,
+    /// The file as written, comments included.
+    pub source: &'static str,
+}
+
+/// The embedded examples, from a single batch of accounts to state
+/// built and torn down over several blocks.
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "basic_accounts",
+        source: include_str!("../examples/basic_accounts.jsonl"),
+    },
+    Example {
+        name: "contract_with_storage",
+        source: include_str!("../examples/contract_with_storage.jsonl"),
+    },
+    Example {
+        name: "multi_block",
+        source: include_str!("../examples/multi_block.jsonl"),
+    },
+    Example {
+        name: "deletion_churn",
+        source: include_str!("../examples/deletion_churn.jsonl"),
+    },
+];
+
+impl Example {
+    /// The first comment line, without the `#`.
+    #[must_use]
+    pub fn summary(&self) -> &'static str {
+        self.source
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix(COMMENT))
+            .map_or("", str::trim)
+    }
+
+    /// The root the example ends at, or `""` if its comments do not
+    /// name one.
+    #[must_use]
+    pub fn expected_root(&self) -> &'static str {
+        self.source
+            .lines()
+            .find_map(|line| line.strip_prefix(EXPECTED_ROOT))
+            .map_or("", str::trim)
+    }
+
+    /// The workload a harness reads: every line but the comments.
+    #[must_use]
+    pub fn workload(&self) -> String {
+        self.source
+            .lines()
+            .filter(|line| !line.starts_with(COMMENT))
+            .flat_map(|line| [line, "\n"])
+            .collect()
+    }
+
+    /// Pairs the example with the root a harness computed for it.
+    #[must_use]
+    pub fn outcome(&self, actual_root: String) -> Outcome {
+        Outcome {
+            name: self.name,
+            expected_root: self.expected_root(),
+            actual_root,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::LineReader;
+    use crate::protocol::Entry;
+
+    #[test]
+    fn examples_are_valid_workloads() {
+        for example in EXAMPLES {
+            assert!(!example.summary().is_empty(), "{}", example.name);
+            assert_eq!(example.expected_root().len(), 66, "{}", example.name);
+            let workload = example.workload();
+            let mut lines = LineReader::new(workload.as_bytes());
+            let mut last = None;
+            while let Ok(Some(line)) = lines.next_line() {
+                let parsed = serde_json::from_str::<Entry>(line).map(|_| line.to_owned());
+                let number = lines.line_number();
+                last =
+                    Some(parsed.unwrap_or_else(|e| panic!("{} line {number}: {e}", example.name)));
+            }
+            assert_eq!(
+                last.as_deref(),
+                Some(r#"{"op":"compute_root"}"#),
+                "{}",
+                example.name
+            );
+        }
+    }
+
+    #[test]
+    fn every_example_file_is_embedded() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            panic!("{dir} must be readable");
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
+                let name = path.file_stem().and_then(|stem| stem.to_str());
+                assert!(
+                    EXAMPLES.iter().any(|example| Some(example.name) == name),
+                    "{} is not in EXAMPLES",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn workload_drops_the_comments() {
+        let example = Example {
+            name: "tiny",
+            source: "# One line.\n# expected_root: 0xab\n{\"op\":\"compute_root\"}\n",
+        };
+        assert_eq!(example.summary(), "One line.");
+        assert_eq!(example.expected_root(), "0xab");
+        assert_eq!(example.workload(), "{\"op\":\"compute_root\"}\n");
+    }
+}
@@ -0,0 +1,129 @@
+//! The `fill_curve` of a chunked trie phase. Applying the first accounts
+//! of a workload to a near-empty trie is cheaper than applying the last
+//! ones to a full trie, and one aggregate trie time hides that. The curve
+//! splits the trie time by the fraction of the accounts applied, deciles
+//! by default, so one run traces how the cost grows with the trie.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// The trie time of one fraction of the accounts.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct FillPoint {
+    /// Share of all accounts applied once this point's were, up to 1.
+    pub fraction: f64,
+    /// Accounts applied since the previous point.
+    pub accounts: u64,
+    pub trie_ms: u64,
+}
+
+/// Collects the points of a curve chunk by chunk. A point ends with the
+/// first chunk that reaches its fraction, so with chunks larger than a
+/// fraction a point may cover more than one, and the curve has fewer
+/// points than asked for.
+#[derive(Debug)]
+pub struct FillCurve {
+    total: u64,
+    points: u64,
+    /// The fraction the next point ends at, counted in points.
+    next: u64,
+    applied: u64,
+    accounts: u64,
+    busy: Duration,
+    curve: Vec<FillPoint>,
+}
+
+impl FillCurve {
+    /// A curve of `points` equal fractions of `total` accounts.
+    #[must_use]
+    pub fn new(total: u64, points: u64) -> Self {
+        Self {
+            total,
+            points: points.max(1),
+            next: 1,
+            applied: 0,
+            accounts: 0,
+            busy: Duration::ZERO,
+            curve: Vec::new(),
+        }
+    }
+
+    /// Records a chunk of `accounts` whose trie work took `busy`.
+    pub fn record(&mut self, accounts: u64, busy: Duration) {
+        self.applied = self.applied.saturating_add(accounts);
+        self.accounts = self.accounts.saturating_add(accounts);
+        self.busy += busy;
+        if self.total == 0 || self.next > self.points || self.applied < self.boundary(self.next) {
+            return;
+        }
+        while self.next <= self.points && self.applied >= self.boundary(self.next) {
+            self.next += 1;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let fraction = self.applied as f64 / self.total as f64;
+        self.curve.push(FillPoint {
+            fraction,
+            accounts: self.accounts,
+            trie_ms: u64::try_from(self.busy.as_millis()).unwrap_or(u64::MAX),
+        });
+        self.accounts = 0;
+        self.busy = Duration::ZERO;
+    }
+
+    /// The points recorded so far.
+    #[must_use]
+    pub fn into_points(self) -> Vec<FillPoint> {
+        self.curve
+    }
+
+    /// Accounts applied by the end of point `point`, rounded up.
+    fn boundary(&self, point: u64) -> u64 {
+        let accounts =
+            (u128::from(self.total) * u128::from(point)).div_ceil(u128::from(self.points));
+        u64::try_from(accounts).unwrap_or(u64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_trie_time_by_fraction() {
+        let mut curve = FillCurve::new(100, 4);
+        for ms in [1, 2, 3, 4] {
+            curve.record(25, Duration::from_millis(ms));
+        }
+        let points: Vec<_> = curve
+            .into_points()
+            .iter()
+            .map(|point| (point.fraction, point.accounts, point.trie_ms))
+            .collect();
+        assert_eq!(
+            points,
+            [(0.25, 25, 1), (0.5, 25, 2), (0.75, 25, 3), (1.0, 25, 4)]
+        );
+    }
+
+    #[test]
+    fn a_point_ends_with_the_chunk_reaching_its_fraction() {
+        let mut curve = FillCurve::new(10, 10);
+        curve.record(4, Duration::from_millis(8));
+        curve.record(1, Duration::from_millis(2));
+        curve.record(5, Duration::from_millis(10));
+        let points: Vec<_> = curve
+            .into_points()
+            .iter()
+            .map(|point| (point.fraction, point.accounts, point.trie_ms))
+            .collect();
+        assert_eq!(points, [(0.4, 4, 8), (0.5, 1, 2), (1.0, 5, 10)]);
+    }
+
+    #[test]
+    fn no_accounts_make_no_points() {
+        let mut curve = FillCurve::new(0, 10);
+        curve.record(0, Duration::from_millis(1));
+        assert!(curve.into_points().is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+//! The workload fingerprint: a short hash over the ordered sequence of
+//! operation types and the addresses they touch, folded in as the apply
+//! loop decodes each line. It identifies what a workload does rather than
+//! how it is written, so two results can be checked for coming from the
+//! same workload even when their files differ byte for byte: in field
+//! order, whitespace, hex case or `tenant` tags.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::protocol::Op;
+
+/// Bytes of the SHA-256 kept in the reported fingerprint.
+const FINGERPRINT_BYTES: usize = 8;
+
+/// Hashes the operations of a run in order.
+#[derive(Default)]
+pub struct Fingerprint {
+    hasher: Sha256,
+    ops: u64,
+}
+
+/// The result fields describing a fingerprint.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FingerprintReport {
+    /// The first bytes of the SHA-256, as lowercase hex.
+    pub workload_fingerprint: String,
+    /// Operations the fingerprint covers.
+    pub fingerprint_ops: u64,
+}
+
+impl Fingerprint {
+    /// Folds in `op`: its tag and, for operations on accounts, each
+    /// address as lowercase hex without `0x`. `compute_root` ends the
+    /// workload and is not included, so a truncated run covers the same
+    /// operations as a workload cut to its length; nor is a `trailer`,
+    /// which only describes the lines before it, a `checkpoint`, which
+    /// only reports on them, a `sleep`, which only waits, or a `header`, `canary` or assertion, which
+    /// describe the workload.
+    pub fn record(&mut self, op: &Op) {
+        if matches!(
+            op,
+            Op::ComputeRoot { .. }
+                | Op::Trailer { .. }
+                | Op::Checkpoint { .. }
+                | Op::Sleep { .. }
+                | Op::Header { .. }
+                | Op::Canary { .. }
+                | Op::AssertAccount { .. }
+                | Op::AssertStorage { .. }
+        ) {
+            return;
+        }
+        self.ops = self.ops.saturating_add(1);
+        self.hasher.update(op.name().as_bytes());
+        for address in op.addresses() {
+            let digits = address
+                .strip_prefix("0x")
+                .or_else(|| address.strip_prefix("0X"))
+                .unwrap_or(address);
+            self.hasher.update([b' ']);
+            self.hasher.update(digits.to_ascii_lowercase().as_bytes());
+        }
+        self.hasher.update([b'\n']);
+    }
+
+    /// The fingerprint of the operations recorded so far.
+    #[must_use]
+    pub fn report(&self) -> FingerprintReport {
+        let digest = self.hasher.clone().finalize();
+        FingerprintReport {
+            workload_fingerprint: crate::hexfmt::fixed(&digest[..FINGERPRINT_BYTES]),
+            fingerprint_ops: self.ops,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{BLOCK_FIXTURES, FIXTURES};
+    use crate::protocol::Entry;
+
+    fn fingerprint(workload: &str) -> FingerprintReport {
+        let mut fingerprint = Fingerprint::default();
+        for line in workload.lines().filter(|line| !line.trim().is_empty()) {
+            let entry = serde_json::from_str::<Entry>(line).ok();
+            if let Some(Entry { op, .. }) = entry {
+                fingerprint.record(&op);
+            }
+        }
+        fingerprint.report()
+    }
+
+    /// Re-encodes a workload the way another writer might: fields in
+    /// reverse order, uppercase hex digits, padded with whitespace and
+    /// tagged with a tenant.
+    fn reencode(workload: &str) -> String {
+        let mut out = String::new();
+        for line in workload.lines() {
+            let Ok(serde_json::Value::Object(mut fields)) = serde_json::from_str(line) else {
+                continue;
+            };
+            fields.entry("tenant").or_insert_with(|| 1.into());
+            let mut encoded: Vec<String> = fields
+                .iter()
+                .map(|(key, value)| match value.as_str() {
+                    Some(address) if key == "address" => {
+                        format!("{key:?} : \"0x{}\"", address[2..].to_uppercase())
+                    }
+                    _ => format!("{key:?} : {value}"),
+                })
+                .collect();
+            encoded.reverse();
+            out.push_str("  { ");
+            out.push_str(&encoded.join(" , "));
+            out.push_str(" }\r\n");
+        }
+        out
+    }
+
+    #[test]
+    fn encoding_does_not_change_the_fingerprint() {
+        for fixture in FIXTURES.iter().chain(BLOCK_FIXTURES) {
+            let reencoded = reencode(fixture.workload);
+            assert_ne!(reencoded, fixture.workload, "{}", fixture.name);
+            assert_eq!(
+                fingerprint(&reencoded),
+                fingerprint(fixture.workload),
+                "{}",
+                fixture.name
+            );
+        }
+    }
+
+    #[test]
+    fn different_operations_differ() {
+        let by_name = |name: &str| {
+            let fixture = FIXTURES.iter().find(|fixture| fixture.name == name);
+            fingerprint(fixture.map_or("", |fixture| fixture.workload))
+        };
+        let empty = by_name("empty_trie");
+        assert_eq!(empty.fingerprint_ops, 0);
+        assert_eq!(empty.workload_fingerprint.len(), 2 + 2 * FINGERPRINT_BYTES);
+
+        let single = by_name("single_account");
+        assert_eq!(single.fingerprint_ops, 1);
+        assert_ne!(single, empty);
+        assert_ne!(by_name("deleted_account"), by_name("deleted_and_recreated"));
+        // A selfdestruct is not a delete_account, though the state is the same.
+        let deleted = by_name("deleted_account");
+        let selfdestructed = FIXTURES
+            .iter()
+            .find(|fixture| fixture.name == "deleted_account")
+            .map(|fixture| fixture.workload.replace("delete_account", "selfdestruct"))
+            .unwrap_or_default();
+        assert_ne!(fingerprint(&selfdestructed), deleted);
+        // Only the operations count, not the values they carry.
+        let fixture = FIXTURES
+            .iter()
+            .find(|fixture| fixture.name == "single_account");
+        let rebalanced = fixture.map(|fixture| {
+            fixture
+                .workload
+                .replace("\"balance\":\"0x", "\"balance\":\"0x1")
+        });
+        assert_eq!(fingerprint(&rebalanced.unwrap_or_default()), single);
+    }
+}
@@ -0,0 +1,644 @@
+//! Embedded known-good workloads for harness self-tests.
+//!
+//! Expected roots were computed with an independent Merkle Patricia trie
+//! implementation. Every harness must reproduce them exactly.
+
+use std::fmt::Write;
+
+/// A small workload paired with its canonical state root.
+pub struct Fixture {
+    pub name: &'static str,
+    pub workload: &'static str,
+    pub expected_root: &'static str,
+}
+
+/// The standard self-test fixtures.
+pub const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "empty_trie",
+        workload: "{\"op\":\"compute_root\"}\n",
+        expected_root: "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+    },
+    Fixture {
+        name: "single_account",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xc7c7d71c0335625b327dc9f669c77579386edbdfcf1e977d95bf8656c25f5a7a",
+    },
+    // single_account as written by Windows tools, with trailing blank lines.
+    Fixture {
+        name: "crlf_and_blank_lines",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\r\n",
+            r#"{"op":"compute_root"}"#,
+            "\r\n\r\n  \n",
+        ),
+        expected_root: "0xc7c7d71c0335625b327dc9f669c77579386edbdfcf1e977d95bf8656c25f5a7a",
+    },
+    Fixture {
+        name: "account_with_storage",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // account_with_storage tagged with tenants, which must not touch state.
+    Fixture {
+        name: "tenant_tags",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1,"tenant":0}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500","tenant":0}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01","tenant":0}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a","tenant":1}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // account_with_storage with its slots inline; the root must not change.
+    Fixture {
+        name: "inline_storage",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1,"storage":{"0x0000000000000000000000000000000000000000000000000000000000000000":"0x01","0x0000000000000000000000000000000000000000000000000000000000000001":"0x2a"}}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // inline_storage with one inline slot overwritten and the other
+    // written by a later set_storage; the merged state, and so the root,
+    // is account_with_storage's.
+    Fixture {
+        name: "inline_and_set_storage",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1,"storage":{"0x0000000000000000000000000000000000000000000000000000000000000001":"0x07"}}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // account_with_storage's slots written by a set_storage_batch between
+    // plain set_storage lines, each overriding the write before it. The
+    // empty batch leaves the second address without an account.
+    Fixture {
+        name: "storage_batch",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x05"}"#,
+            "\n",
+            r#"{"op":"set_storage_batch","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","entries":[["0x0000000000000000000000000000000000000000000000000000000000000000","0x01"],["0x0000000000000000000000000000000000000000000000000000000000000001","0x07"]]}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"set_storage_batch","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","entries":[]}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // The "dogs" vector from the Ethereum Foundation trie tests, with
+    // keys and values right-aligned into storage slots of one contract.
+    Fixture {
+        name: "ef_dogs_storage",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba","balance":"0x0","nonce":0}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba","code":"0x00"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba","slot":"0x0000000000000000000000000000000000000000000000000000000000646f65","value":"0x7265696e64656572"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba","slot":"0x0000000000000000000000000000000000000000000000000000000000646f67","value":"0x7075707079"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba","slot":"0x0000000000000000000000000000000000000000646f67676c6573776f727468","value":"0x636174"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x59cf412ed8c9e2795a575c9dcad746596e5cc9283743cbbff44b78ec677bc914",
+    },
+    Fixture {
+        name: "multiple_accounts",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000001","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000002","balance":"0x02","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000003","balance":"0x03","nonce":7}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000004","balance":"0x3635c9adc5dea00000","nonce":255}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x368942a9fb854b17354383f69c78344f8e447bde7a77a5a7af8d48d60df40a23",
+    },
+    // Shrunk from a fuzzed workload of zero-padded address variants that
+    // stalled the ethrex accumulator.
+    Fixture {
+        name: "zero_padded_addresses",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000001","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000100","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000010000","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000001000000","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0100000000000000000000000000000000000000","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000000","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x0000000000000000000000000000000000000100","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x6738003dab912c84159d511ac751f0e11f109b8824458055acd3c72885ef7e9c",
+    },
+    // account_with_storage plus a slot set and then zeroed, and a zero
+    // written to a slot never set: zero deletes, so the root must not
+    // change.
+    Fixture {
+        name: "zeroed_slot",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000002","value":"0x05"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000002","value":"0x0"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000003","value":"0x00"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // A contract deleted after it was created must leave single_account's
+    // root, as if it had never existed.
+    Fixture {
+        name: "deleted_account",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"delete_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xc7c7d71c0335625b327dc9f669c77579386edbdfcf1e977d95bf8656c25f5a7a",
+    },
+    // A re-created account starts without the storage it had before its
+    // deletion: only slot 1 remains.
+    Fixture {
+        name: "deleted_and_recreated",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"delete_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xa11dd9906e6375115e1c2b1aab74dac6ae34560eee61a8941c72aa366d208bb2",
+    },
+    // Slot 0, written before the contract self-destructs, must not leak
+    // into the contract created again at its address: the root is
+    // deleted_and_recreated's.
+    Fixture {
+        name: "selfdestructed_contract",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x07"}"#,
+            "\n",
+            r#"{"op":"selfdestruct","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xa11dd9906e6375115e1c2b1aab74dac6ae34560eee61a8941c72aa366d208bb2",
+    },
+    // The zero address, precompiles and the all-ones address are plain
+    // accounts to the state trie, with balances, code and storage.
+    Fixture {
+        name: "reserved_addresses",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000000","balance":"0x01","nonce":0,"storage":{"0x0000000000000000000000000000000000000000000000000000000000000001":"0x2a"}}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x0000000000000000000000000000000000000000","code":"0x6000"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000001","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000005","balance":"0x05","nonce":0}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x0000000000000000000000000000000000000005","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x0000000000000000000000000000000000000005","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x000000000000000000000000000000000000000a","balance":"0x0a","nonce":1}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0xffffffffffffffffffffffffffffffffffffffff","balance":"0x3635c9adc5dea00000","nonce":255,"storage":{"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff":"0x01"}}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0xffffffffffffffffffffffffffffffffffffffff","code":"0x00"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xd421af19522b6eeb065c6f4a42481d3ea00137d0ea65e681aa752ac4026ff69b",
+    },
+    // selfdestructed_contract with clear_storage instead of selfdestruct
+    // and re-create: the slots written before the wipe are gone, while
+    // the balance and code survive it.
+    Fixture {
+        name: "cleared_storage",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x07"}"#,
+            "\n",
+            r#"{"op":"clear_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xa56319cbc853da5802a23d1b25e420b2af528060ed926ccb593c85c4a7cb2595",
+    },
+    // Three accounts from one create_accounts line, the first of them,
+    // keccak256(0x5eed || 0)[12..], then given a slot.
+    Fixture {
+        name: "derived_accounts",
+        workload: concat!(
+            r#"{"op":"create_accounts","seed":"0x5eed","count":3,"balance":"0x1"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0xa7281267ff939b0a90c3635145bf0292f35f1e1a","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x9b5797abb2edb1009a73d44d767ad6d270d8dfe9207f2615d02a109c36f88d85",
+    },
+    // An EOA delegated to a contract: its code is the designation
+    // 0xef0100 || target, with the root of a set_code of those bytes.
+    Fixture {
+        name: "delegated_account",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_delegation","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","target":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x69c698bc57dcd5558621e8b1c0d731c0eccb3ffe4ee48c1570d185bdc5eae06c",
+    },
+];
+
+/// Fixtures split into blocks by `commit_block`, or by a `compute_root`
+/// with more operations after it. Each expected root is that of the same
+/// operations in a single batch.
+pub const BLOCK_FIXTURES: &[Fixture] = &[
+    // account_with_storage built over two blocks, with a slot of the
+    // first block zeroed in the second.
+    Fixture {
+        name: "two_blocks",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000002","value":"0x05"}"#,
+            "\n",
+            r#"{"op":"commit_block"}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000002","value":"0x0"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xb03075f20699d5e53dc16a5bc881ff65ecd572477b98083c6d5a067433c8a88b",
+    },
+    // A contract from the first block deleted in the second, leaving
+    // single_account's root. The stream ends without compute_root.
+    Fixture {
+        name: "deleted_across_blocks",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"commit_block"}"#,
+            "\n",
+            r#"{"op":"delete_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"commit_block"}"#,
+            "\n",
+        ),
+        expected_root: "0xc7c7d71c0335625b327dc9f669c77579386edbdfcf1e977d95bf8656c25f5a7a",
+    },
+    // deleted_and_recreated with the first storage in an earlier block:
+    // the re-created account must not see it.
+    Fixture {
+        name: "recreated_across_blocks",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"commit_block"}"#,
+            "\n",
+            r#"{"op":"delete_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xa11dd9906e6375115e1c2b1aab74dac6ae34560eee61a8941c72aa366d208bb2",
+    },
+    // multiple_accounts with a root computed halfway, after which the
+    // rest is layered over it.
+    Fixture {
+        name: "two_roots",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000001","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000002","balance":"0x02","nonce":0}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000003","balance":"0x03","nonce":7}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x1000000000000000000000000000000000000004","balance":"0x3635c9adc5dea00000","nonce":255}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x368942a9fb854b17354383f69c78344f8e447bde7a77a5a7af8d48d60df40a23",
+    },
+    // reserved_addresses with the code and storage of precompile 0x05,
+    // and the accounts after it, written in a second block.
+    Fixture {
+        name: "reserved_across_blocks",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000000","balance":"0x01","nonce":0,"storage":{"0x0000000000000000000000000000000000000000000000000000000000000001":"0x2a"}}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x0000000000000000000000000000000000000000","code":"0x6000"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000001","balance":"0x01","nonce":0}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x0000000000000000000000000000000000000005","balance":"0x05","nonce":0}"#,
+            "\n",
+            r#"{"op":"commit_block"}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x0000000000000000000000000000000000000005","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x0000000000000000000000000000000000000005","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0x000000000000000000000000000000000000000a","balance":"0x0a","nonce":1}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0xffffffffffffffffffffffffffffffffffffffff","balance":"0x3635c9adc5dea00000","nonce":255,"storage":{"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff":"0x01"}}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0xffffffffffffffffffffffffffffffffffffffff","code":"0x00"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0xd421af19522b6eeb065c6f4a42481d3ea00137d0ea65e681aa752ac4026ff69b",
+    },
+    // cleared_storage with the first slot in an earlier block, next to
+    // an account whose storage the wipe must leave alone.
+    Fixture {
+        name: "cleared_across_blocks",
+        workload: concat!(
+            r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            "\n",
+            r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000000","value":"0x01"}"#,
+            "\n",
+            r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1,"storage":{"0x0000000000000000000000000000000000000000000000000000000000000000":"0x01"}}"#,
+            "\n",
+            r#"{"op":"commit_block"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x07"}"#,
+            "\n",
+            r#"{"op":"clear_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            "\n",
+            r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+            "\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+        ),
+        expected_root: "0x72b397bcca6b0798f41eeb6d7f9f26f5cf67aa20501949d5521ca5ce672bd8e5",
+    },
+];
+
+/// The root a harness computed for a fixture.
+pub struct Outcome {
+    pub name: &'static str,
+    pub expected_root: &'static str,
+    pub actual_root: String,
+}
+
+impl Outcome {
+    /// Pairs a fixture with the root a harness computed for it.
+    #[must_use]
+    pub fn new(fixture: &Fixture, actual_root: String) -> Self {
+        Self {
+            name: fixture.name,
+            expected_root: fixture.expected_root,
+            actual_root,
+        }
+    }
+
+    /// Reports whether the computed root matches, ignoring hex case.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.actual_root.eq_ignore_ascii_case(self.expected_root)
+    }
+}
+
+/// Renders outcomes as a plain-text pass/fail table.
+#[must_use]
+pub fn render_table(outcomes: &[Outcome]) -> String {
+    let width = outcomes.iter().map(|o| o.name.len()).max().unwrap_or(0);
+    let mut table = String::new();
+    for outcome in outcomes {
+        let status = if outcome.passed() { "PASS" } else { "FAIL" };
+        let _ = writeln!(
+            table,
+            "{status}  {:<width$}  {}",
+            outcome.name, outcome.actual_root
+        );
+        if !outcome.passed() {
+            let _ = writeln!(
+                table,
+                "      {:<width$}  expected {}",
+                "", outcome.expected_root
+            );
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::LineReader;
+
+    #[test]
+    fn fixtures_are_well_formed() {
+        let last_line = |fixture: &Fixture| {
+            fixture
+                .workload
+                .lines()
+                .map(str::trim)
+                .rfind(|line| !line.is_empty())
+                .unwrap_or_default()
+        };
+        for fixture in FIXTURES {
+            assert_eq!(
+                last_line(fixture),
+                r#"{"op":"compute_root"}"#,
+                "{}",
+                fixture.name
+            );
+            assert!(
+                !fixture.workload.contains("commit_block"),
+                "{}",
+                fixture.name
+            );
+        }
+        for fixture in BLOCK_FIXTURES {
+            assert!(
+                [r#"{"op":"compute_root"}"#, r#"{"op":"commit_block"}"#]
+                    .contains(&last_line(fixture)),
+                "{}",
+                fixture.name
+            );
+            // A compute_root before the last line ends a block too.
+            let roots = fixture.workload.matches("compute_root").count();
+            assert!(
+                fixture.workload.contains("commit_block") || roots > 1,
+                "{}",
+                fixture.name
+            );
+        }
+        for fixture in FIXTURES.iter().chain(BLOCK_FIXTURES) {
+            assert_eq!(fixture.expected_root.len(), 66, "{}", fixture.name);
+        }
+    }
+
+    #[test]
+    fn crlf_fixture_reads_like_its_lf_original() {
+        let read = |name: &str| {
+            let workload = FIXTURES
+                .iter()
+                .find(|f| f.name == name)
+                .map_or("", |f| f.workload);
+            let mut reader = LineReader::new(workload.as_bytes());
+            let mut lines = Vec::new();
+            while let Ok(Some(line)) = reader.next_line() {
+                lines.push(line.to_owned());
+            }
+            lines
+        };
+        assert_eq!(read("crlf_and_blank_lines"), read("single_account"));
+    }
+
+    #[test]
+    fn table_marks_failures() {
+        let outcomes = [
+            Outcome::new(&FIXTURES[0], FIXTURES[0].expected_root.to_uppercase()),
+            Outcome::new(&FIXTURES[1], "0x00".to_string()),
+        ];
+        let table = render_table(&outcomes);
+        assert!(table.contains("PASS  empty_trie"));
+        assert!(table.contains("FAIL  single_account"));
+        assert!(table.contains(FIXTURES[1].expected_root));
+    }
+}
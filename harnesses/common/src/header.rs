@@ -0,0 +1,121 @@
+//! Workload headers. A workload may open with a `header` line naming it,
+//! the protocol version it was written for, how many operations follow
+//! and the root it must end at, so a file run against a harness that
+//! reads it differently fails at the first line instead of producing a
+//! result that looks valid. Workloads without a header read as before.
+
+use crate::protocol::{Op, PROTOCOL_VERSION};
+
+/// Most entries a header's `total_ops` pre-sizes a collection for. A
+/// larger hint is cut to this, so a wrong header cannot reserve the
+/// machine's memory before the first operation is applied.
+pub const MAX_PRESIZED_OPS: u64 = 1 << 26;
+
+/// What the workload's header declared, if it had one.
+#[derive(Debug, Default)]
+pub struct WorkloadHeader {
+    /// Whether a line has been read, after which a header is misplaced.
+    started: bool,
+    pub name: Option<String>,
+    pub expected_root: Option<String>,
+}
+
+impl WorkloadHeader {
+    /// Takes in the line decoded as `op`. For a header, returns how many
+    /// entries to reserve for the operations it announces: `total_ops`,
+    /// capped by `max_ops` and [`MAX_PRESIZED_OPS`], or 0 without one.
+    /// Any other line returns `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for a header after the first line, or of a
+    /// protocol version other than [`PROTOCOL_VERSION`].
+    pub fn record(&mut self, op: &Op, max_ops: Option<u64>) -> Result<Option<usize>, String> {
+        let started = std::mem::replace(&mut self.started, true);
+        let Op::Header {
+            version,
+            name,
+            total_ops,
+            expected_root,
+        } = op
+        else {
+            return Ok(None);
+        };
+        if started {
+            return Err("header must be the workload's first line".to_string());
+        }
+        if *version != PROTOCOL_VERSION {
+            return Err(format!(
+                "workload header version {version} is not supported; this harness reads version {PROTOCOL_VERSION}"
+            ));
+        }
+        self.name.clone_from(name);
+        self.expected_root.clone_from(expected_root);
+        let ops = total_ops
+            .unwrap_or(0)
+            .min(max_ops.unwrap_or(u64::MAX))
+            .min(MAX_PRESIZED_OPS);
+        Ok(Some(usize::try_from(ops).unwrap_or(usize::MAX)))
+    }
+
+    /// The root the workload must end at, when it was read to its end: a
+    /// run cut short by `--max-ops` or a time box has a different one.
+    #[must_use]
+    pub fn expected_root(&mut self, complete: bool) -> Option<String> {
+        self.expected_root.take().filter(|_| complete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(version: u32, total_ops: Option<u64>) -> Op {
+        Op::Header {
+            version,
+            name: Some("10m-erc20".to_string()),
+            total_ops,
+            expected_root: Some("0xab".to_string()),
+        }
+    }
+
+    #[test]
+    fn takes_in_a_leading_header() {
+        let mut header_state = WorkloadHeader::default();
+        let presize = header_state.record(&header(1, Some(10_500_000)), None);
+        assert_eq!(presize, Ok(Some(10_500_000)));
+        let root = Op::ComputeRoot {
+            expected_root: None,
+        };
+        assert_eq!(header_state.record(&root, None), Ok(None));
+        assert_eq!(header_state.name.as_deref(), Some("10m-erc20"));
+        assert_eq!(header_state.expected_root(false), None);
+
+        let mut capped = WorkloadHeader::default();
+        assert_eq!(
+            capped.record(&header(1, Some(u64::MAX)), Some(5)),
+            Ok(Some(5))
+        );
+        assert_eq!(capped.expected_root(true).as_deref(), Some("0xab"));
+        assert_eq!(
+            WorkloadHeader::default().record(&header(1, None), None),
+            Ok(Some(0))
+        );
+    }
+
+    #[test]
+    fn rejects_late_headers_and_unknown_versions() {
+        let Err(e) = WorkloadHeader::default().record(&header(2, None), None) else {
+            panic!("a newer version must be rejected");
+        };
+        assert!(e.contains("version 2 is not supported"), "{e}");
+
+        let mut late = WorkloadHeader::default();
+        let block = Op::CommitBlock { approx_gas: None };
+        assert_eq!(late.record(&block, None), Ok(None));
+        let Err(e) = late.record(&header(1, None), None) else {
+            panic!("a header after the first line must be rejected");
+        };
+        assert!(e.contains("first line"), "{e}");
+    }
+}
@@ -0,0 +1,247 @@
+//! `--health-check`: a one-command validation of a harness on a new
+//! machine. The harness re-executes itself on a tiny embedded workload
+//! in a temporary database, checks the root it reports, reads the root
+//! back through the durability probe and checks that the procfs metrics
+//! results rely on are readable, then prints a JSON report.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::budget::Budget;
+use crate::cpu::CpuSample;
+use crate::fixtures::Fixture;
+use crate::output;
+use crate::probe;
+use crate::sysmetrics::SystemMetrics;
+
+/// The workload every health check runs: an account, one of its storage
+/// slots and the root.
+pub const HEALTH_FIXTURE: Fixture = Fixture {
+    name: "health_check",
+    workload: concat!(
+        r#"{"op":"create_account","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+        "\n",
+        r#"{"op":"set_storage","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b","slot":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x2a"}"#,
+        "\n",
+        r#"{"op":"compute_root"}"#,
+        "\n",
+    ),
+    expected_root: "0x8e069d709f50d49f676831450d29026d534cff6f7598997b71ae6f35e8185475",
+};
+
+/// Procfs metrics that must be nonzero at the end of a Linux run.
+const END_METRICS: &[&str] = &["vm_hwm_bytes", "vm_rss_bytes", "threads"];
+
+/// Outcome of `--health-check`.
+#[derive(Serialize)]
+pub struct HealthReport {
+    pub client: String,
+    /// `pass` when every check passed, `fail` otherwise.
+    pub status: &'static str,
+    pub checks: Vec<HealthCheck>,
+    /// Optional facilities this host lacks. Results leave out what they
+    /// would have measured, but they do not fail the check.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_capabilities: Vec<&'static str>,
+}
+
+/// One step of the health check.
+#[derive(Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    fn new(client: &str) -> Self {
+        Self {
+            client: client.to_string(),
+            status: "pass",
+            checks: Vec::new(),
+            missing_capabilities: missing_capabilities(),
+        }
+    }
+
+    fn check(&mut self, name: &'static str, outcome: Result<(), String>) {
+        if outcome.is_err() {
+            self.status = "fail";
+        }
+        self.checks.push(HealthCheck {
+            name,
+            ok: outcome.is_ok(),
+            error: outcome.err(),
+        });
+    }
+
+    /// Reports whether every check passed.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.status == "pass"
+    }
+}
+
+/// Runs the health check of the current harness binary, which must
+/// accept `--db` and a workload on stdin and print its result line.
+#[must_use]
+pub fn run(client: &str) -> HealthReport {
+    let db = std::env::temp_dir().join(format!("statoor-{client}-health-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&db);
+    let mut report = HealthReport::new(client);
+
+    match run_child(&db) {
+        Ok(result) => {
+            report.check("run", Ok(()));
+            let root = result["state_root"].as_str().unwrap_or_default();
+            report.check(
+                "root",
+                if root == HEALTH_FIXTURE.expected_root {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "root {root}, expected {}",
+                        HEALTH_FIXTURE.expected_root
+                    ))
+                },
+            );
+            let probe = probe::run(&db, HEALTH_FIXTURE.expected_root, false, &Budget::default());
+            report.check(
+                "read_back",
+                if probe.status == "pass" {
+                    Ok(())
+                } else {
+                    Err(probe.details.join("; "))
+                },
+            );
+            report.check("metrics", check_metrics(&result));
+        }
+        Err(e) => report.check("run", Err(e)),
+    }
+
+    let _ = std::fs::remove_dir_all(&db);
+    let _ = std::fs::remove_file(output::sidecar_path(&db));
+    report
+}
+
+/// Re-executes the current binary on [`HEALTH_FIXTURE`] and returns the
+/// result it prints.
+fn run_child(db: &Path) -> Result<Value, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("locate harness binary: {e}"))?;
+    let mut child = Command::new(exe)
+        .arg("--db")
+        .arg(db)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("start harness: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(HEALTH_FIXTURE.workload.as_bytes())
+            .map_err(|e| format!("write workload: {e}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("wait for harness: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "harness exited with {}: {}",
+            output.status,
+            stderr.lines().last().unwrap_or_default()
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("decode result: {e}"))
+}
+
+/// Checks that a result carries the procfs metrics a Linux run must
+/// report. Elsewhere they are not read, and nothing is expected.
+fn check_metrics(result: &Value) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Ok(());
+    }
+    let nonzero = |value: &Value| value.as_u64().is_some_and(|v| v > 0);
+    let mut missing = Vec::new();
+    if !nonzero(&result["peak_memory_bytes"]) {
+        missing.push("peak_memory_bytes".to_string());
+    }
+    for metric in END_METRICS {
+        if !nonzero(&result["system_metrics"]["end"][*metric]) {
+            missing.push(format!("system_metrics.end.{metric}"));
+        }
+    }
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("missing or zero: {}", missing.join(", ")))
+    }
+}
+
+/// Names the optional facilities this host does not provide:
+/// `thread_cpu` (per-thread CPU times, for background CPU per phase),
+/// `proc_io` (I/O byte counters), `cgroup_memory_peak` (cgroup v2
+/// `memory.peak`) and `perf_counters` (unprivileged perf events, for
+/// external profilers).
+fn missing_capabilities() -> Vec<&'static str> {
+    let metrics = SystemMetrics::capture();
+    let perf_paranoid = std::fs::read_to_string("/proc/sys/kernel/perf_event_paranoid")
+        .ok()
+        .and_then(|s| s.trim().parse::<i32>().ok());
+    [
+        ("thread_cpu", CpuSample::now().is_some()),
+        ("proc_io", metrics.io_read_bytes.is_some()),
+        (
+            "cgroup_memory_peak",
+            metrics.cgroup_memory_peak_bytes.is_some(),
+        ),
+        (
+            "perf_counters",
+            perf_paranoid.is_some_and(|level| level <= 2),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, present)| (!present).then_some(name))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_failed_check_fails_the_report() {
+        let mut report = HealthReport::new("reth");
+        report.check("run", Ok(()));
+        assert!(report.passed());
+        report.check("root", Err("root 0x00".to_string()));
+        assert!(!report.passed());
+        let json = serde_json::to_value(&report).unwrap_or_default();
+        assert_eq!(json["status"], "fail");
+        assert_eq!(
+            json["checks"][0],
+            serde_json::json!({"name": "run", "ok": true})
+        );
+        assert_eq!(json["checks"][1]["error"], "root 0x00");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn metrics_must_be_nonzero_on_linux() {
+        let mut result = serde_json::json!({
+            "peak_memory_bytes": 1,
+            "system_metrics": {"end": {"vm_hwm_bytes": 1, "vm_rss_bytes": 1, "threads": 2}},
+        });
+        assert_eq!(check_metrics(&result), Ok(()));
+        result["system_metrics"]["end"]["threads"] = Value::from(0);
+        result["peak_memory_bytes"] = Value::Null;
+        assert_eq!(
+            check_metrics(&result),
+            Err("missing or zero: peak_memory_bytes, system_metrics.end.threads".to_string())
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! The one hex format every statoor output uses: `0x`-prefixed and
+//! lowercase, full width for hashes, addresses and byte strings, minimal
+//! for quantities. Results, dumps and responses from different harnesses
+//! can then be compared as plain strings.
+
+use std::fmt::{LowerHex, Write};
+
+/// Formats fixed-width data (a hash, an address, raw bytes) with two
+/// digits per byte, leading zeroes kept.
+#[must_use]
+pub fn fixed(bytes: impl AsRef<[u8]>) -> String {
+    let bytes = bytes.as_ref();
+    let mut out = String::with_capacity(2 + 2 * bytes.len());
+    out.push_str("0x");
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+/// Formats a quantity (a balance, a storage value) without leading
+/// zeroes; zero is `0x0`.
+#[must_use]
+pub fn quantity(value: impl LowerHex) -> String {
+    format!("0x{value:x}")
+}
+
+/// Reports whether `s` is [`fixed`] output for `len` bytes.
+#[must_use]
+pub fn is_fixed(s: &str, len: usize) -> bool {
+    s.strip_prefix("0x")
+        .is_some_and(|digits| digits.len() == 2 * len && digits.bytes().all(is_lower_hex))
+}
+
+/// Reports whether `s` is [`quantity`] output.
+#[must_use]
+pub fn is_quantity(s: &str) -> bool {
+    s.strip_prefix("0x").is_some_and(|digits| {
+        !digits.is_empty()
+            && (digits == "0" || !digits.starts_with('0'))
+            && digits.bytes().all(is_lower_hex)
+    })
+}
+
+fn is_lower_hex(b: u8) -> bool {
+    b.is_ascii_digit() || (b'a'..=b'f').contains(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_keeps_width_and_lowercases() {
+        let address = [0x00, 0xAB, 0x01];
+        assert_eq!(fixed(address), "0x00ab01");
+        assert_eq!(fixed([]), "0x");
+        assert!(is_fixed(&fixed([0xFF; 32]), 32));
+        assert!(!is_fixed("0x00AB01", 3));
+        assert!(!is_fixed("00ab01", 3));
+        assert!(!is_fixed("0x00ab", 3));
+    }
+
+    #[test]
+    fn quantity_is_minimal() {
+        assert_eq!(quantity(0_u64), "0x0");
+        assert_eq!(quantity(42_u64), "0x2a");
+        assert_eq!(quantity(u128::MAX), format!("0x{}", "f".repeat(32)));
+        assert!(is_quantity("0x0") && is_quantity("0x2a"));
+        assert!(!is_quantity("0x02a") && !is_quantity("0x2A") && !is_quantity("0x"));
+    }
+}
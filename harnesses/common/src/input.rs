@@ -0,0 +1,257 @@
+//! Workload input layer.
+//!
+//! Reads the workload as raw bytes and performs explicit UTF-8 handling,
+//! so that a leading byte order mark or a line with invalid UTF-8 yields
+//! a precise error instead of the generic one from `BufRead::lines`.
+//! Line endings and blank lines are normalized here, so every harness
+//! accepts `\r\n` workloads and trailing blank lines alike.
+
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// The UTF-8 encoded byte order mark.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Number of bytes shown in the hex snippet of an invalid line.
+const SNIPPET_LEN: usize = 16;
+
+/// Errors produced while reading workload lines.
+#[derive(Debug)]
+pub enum InputError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// A line is not valid UTF-8.
+    InvalidUtf8 {
+        /// One-based line number.
+        line: u64,
+        /// Byte offset of the first invalid byte within the line.
+        offset: usize,
+        /// Hex dump of the bytes starting at `offset`.
+        snippet: String,
+    },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::InvalidUtf8 {
+                line,
+                offset,
+                snippet,
+            } => write!(f, "line {line}: invalid UTF-8 at byte {offset}: {snippet}"),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Reads newline-terminated lines from a byte stream.
+pub struct LineReader<R> {
+    inner: R,
+    buf: Vec<u8>,
+    line_number: u64,
+    bom_stripped: bool,
+    blank_lines: u64,
+}
+
+impl<R: BufRead> LineReader<R> {
+    /// Creates a reader over `inner`.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+            line_number: 0,
+            bom_stripped: false,
+            blank_lines: 0,
+        }
+    }
+
+    /// Returns the next non-blank line with surrounding ASCII whitespace,
+    /// including a `\r` before the `\n`, removed, or `None` at EOF.
+    /// Blank and whitespace-only lines are skipped and counted.
+    ///
+    /// A UTF-8 byte order mark at the very start of the input is removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying reader fails or the line is
+    /// not valid UTF-8.
+    pub fn next_line(&mut self) -> Result<Option<&str>, InputError> {
+        let start = loop {
+            self.buf.clear();
+            if self.inner.read_until(b'\n', &mut self.buf)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+
+            if self.buf.last() == Some(&b'\n') {
+                self.buf.pop();
+            }
+
+            let mut start = 0;
+            if self.line_number == 1 && self.buf.starts_with(UTF8_BOM) {
+                self.bom_stripped = true;
+                start = UTF8_BOM.len();
+            }
+
+            if self.buf[start..].trim_ascii().is_empty() {
+                self.blank_lines += 1;
+                continue;
+            }
+            break start;
+        };
+
+        match std::str::from_utf8(&self.buf[start..]) {
+            Ok(line) => Ok(Some(line.trim_ascii())),
+            Err(e) => {
+                let offset = start + e.valid_up_to();
+                let end = (offset + SNIPPET_LEN).min(self.buf.len());
+                Err(InputError::InvalidUtf8 {
+                    line: self.line_number,
+                    offset,
+                    snippet: hex_snippet(&self.buf[offset..end]),
+                })
+            }
+        }
+    }
+
+    /// Skips blank lines and reports whether the input has ended, without
+    /// consuming the next line. Blocks until more input arrives or the
+    /// stream closes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying reader fails.
+    pub fn at_end(&mut self) -> Result<bool, InputError> {
+        loop {
+            let Some(&byte) = self.inner.fill_buf()?.first() else {
+                return Ok(true);
+            };
+            if !byte.is_ascii_whitespace() {
+                return Ok(false);
+            }
+            self.inner.consume(1);
+            if byte == b'\n' {
+                self.line_number += 1;
+                self.blank_lines += 1;
+            }
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the one-based number of the line last returned.
+    pub fn line_number(&self) -> u64 {
+        self.line_number
+    }
+
+    /// Reports whether a leading byte order mark was removed.
+    pub fn bom_stripped(&self) -> bool {
+        self.bom_stripped
+    }
+
+    /// Returns the number of blank or whitespace-only lines skipped.
+    pub fn blank_lines(&self) -> u64 {
+        self.blank_lines
+    }
+
+    /// Describes the input irregularities that were tolerated, for the
+    /// result's `warnings`.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.bom_stripped {
+            warnings.push("stripped UTF-8 byte order mark from input".to_string());
+        }
+        if self.blank_lines > 0 {
+            warnings.push(format!("skipped {} blank input lines", self.blank_lines));
+        }
+        warnings
+    }
+}
+
+fn hex_snippet(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(input: &[u8]) -> Result<Vec<String>, InputError> {
+        let mut reader = LineReader::new(input);
+        let mut lines = Vec::new();
+        while let Some(line) = reader.next_line()? {
+            lines.push(line.to_owned());
+        }
+        Ok(lines)
+    }
+
+    #[test]
+    fn reads_lines_with_and_without_final_newline() {
+        let lines = read_all(b"a\nb\nc").unwrap_or_default();
+        assert_eq!(lines, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn normalizes_crlf_and_skips_blank_lines() {
+        let mut reader = LineReader::new(&b"a\r\n  b \r\n\r\n \t\nc\n\n\n"[..]);
+        let mut lines = Vec::new();
+        while let Ok(Some(line)) = reader.next_line() {
+            lines.push(line.to_owned());
+        }
+        assert_eq!(lines, ["a", "b", "c"]);
+        assert_eq!(reader.blank_lines(), 4);
+        assert_eq!(reader.line_number(), 7);
+        assert_eq!(reader.warnings(), ["skipped 4 blank input lines"]);
+    }
+
+    #[test]
+    fn at_end_skips_blank_lines_only() {
+        let mut reader = LineReader::new(&b"a\n \r\n\n  b\n\n"[..]);
+        assert_eq!(reader.next_line().ok().flatten(), Some("a"));
+        assert!(matches!(reader.at_end(), Ok(false)));
+        assert_eq!(reader.next_line().ok().flatten(), Some("b"));
+        assert_eq!(reader.line_number(), 4);
+        assert!(matches!(reader.at_end(), Ok(true)));
+        assert_eq!(reader.blank_lines(), 3);
+    }
+
+    #[test]
+    fn strips_leading_bom() {
+        let mut reader = LineReader::new(&b"\xEF\xBB\xBF{\"op\":\"compute_root\"}\n"[..]);
+        let line = reader.next_line().ok().flatten().map(str::to_owned);
+        assert_eq!(line.as_deref(), Some("{\"op\":\"compute_root\"}"));
+        assert!(reader.bom_stripped());
+    }
+
+    #[test]
+    fn reports_invalid_utf8_with_line_and_snippet() {
+        let err = read_all(b"ok\nab\xff\xfecd\n").err();
+        match err {
+            Some(InputError::InvalidUtf8 {
+                line,
+                offset,
+                snippet,
+            }) => {
+                assert_eq!(line, 2);
+                assert_eq!(offset, 2);
+                assert_eq!(snippet, "ff fe 63 64");
+            }
+            other => panic!("expected InvalidUtf8, got {other:?}"),
+        }
+    }
+}
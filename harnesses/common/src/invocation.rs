@@ -0,0 +1,48 @@
+//! The resolved command line a result was produced with. It is
+//! serialized from the parsed CLI struct rather than taken from argv, so
+//! flags left at their defaults are recorded with the value in effect.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Serializes `cli` to an object of every flag and its effective value.
+/// Options that were not given are left out rather than written as
+/// `null`, which the result check would reject.
+///
+/// # Errors
+///
+/// Returns why `cli` does not serialize, e.g. a non-UTF-8 path.
+pub fn resolved(cli: &impl Serialize) -> Result<Value, String> {
+    let mut value = serde_json::to_value(cli).map_err(|e| format!("encode invocation: {e}"))?;
+    if let Value::Object(fields) = &mut value {
+        fields.retain(|_, field| !field.is_null());
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Cli {
+        db: Option<String>,
+        max_ops: Option<u64>,
+        reserve_free_gb: u64,
+        force: bool,
+    }
+
+    #[test]
+    fn keeps_defaults_and_drops_unset_options() {
+        let cli = Cli {
+            db: Some("/tmp/db".to_string()),
+            max_ops: None,
+            reserve_free_gb: 0,
+            force: false,
+        };
+        assert_eq!(
+            resolved(&cli),
+            Ok(serde_json::json!({"db": "/tmp/db", "reserve_free_gb": 0, "force": false}))
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Client-agnostic building blocks shared by the statoor Rust harnesses.
+//!
+//! Everything here is independent of any client library so that each
+//! harness can depend on it without pulling in another client's tree.
+
+pub mod assertions;
+pub mod blocks;
+pub mod budget;
+pub mod canary;
+pub mod cbor;
+pub mod codebook;
+pub mod coderead;
+pub mod compression;
+pub mod counters;
+pub mod cpu;
+pub mod delegation;
+pub mod derive;
+pub mod disk;
+pub mod events;
+pub mod examples;
+pub mod fillcurve;
+pub mod fingerprint;
+pub mod fixtures;
+pub mod header;
+pub mod health;
+pub mod hexfmt;
+pub mod input;
+pub mod invocation;
+pub mod lock;
+pub mod metrics;
+pub mod model;
+pub mod multiroot;
+pub mod output;
+pub mod preload;
+pub mod probe;
+pub mod profile;
+pub mod proofs;
+pub mod protocol;
+pub mod quantity;
+pub mod query;
+pub mod reads;
+pub mod recompute;
+pub mod reserved;
+pub mod rootcheck;
+pub mod sampling;
+pub mod sleep;
+pub mod status;
+pub mod sysmetrics;
+pub mod timebox;
+pub mod trailer;
+pub mod watchdog;
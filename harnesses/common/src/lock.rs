@@ -0,0 +1,136 @@
+//! Advisory lock on a database directory. Two harnesses pointed at the
+//! same `--db` would otherwise only notice once their engines' own locks
+//! collide, with client-specific errors long into the run.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the lock file created in the database directory.
+pub const LOCK_FILE: &str = "statoor.lock";
+
+/// Contents of the lock file: who holds it.
+#[derive(Serialize, Deserialize)]
+struct Holder {
+    pid: u32,
+    client: String,
+}
+
+/// A held lock. Dropping it removes the lock file; a process that exits
+/// without unwinding leaves a stale file behind, which the next
+/// [`DbLock::acquire`] takes over once its PID is gone.
+pub struct DbLock {
+    path: PathBuf,
+}
+
+impl DbLock {
+    /// Creates the database directory if needed and takes its lock on
+    /// behalf of `client`. A lock left by a dead process is taken over,
+    /// and with `force` so is a live one.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the holder when another live process
+    /// holds the lock, or the I/O error creating the lock file.
+    pub fn acquire(db: &Path, client: &str, force: bool) -> Result<Self, String> {
+        std::fs::create_dir_all(db).map_err(|e| format!("create {}: {e}", db.display()))?;
+        let path = db.join(LOCK_FILE);
+        let own = Holder {
+            pid: std::process::id(),
+            client: client.to_string(),
+        };
+        let body = serde_json::to_string(&own).map_err(|e| format!("encode lock: {e}"))?;
+
+        let created = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(body.as_bytes()));
+        match created {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let holder = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|held| serde_json::from_str::<Holder>(&held).ok());
+                if let Some(holder) = holder
+                    && !force
+                    && holder.pid != own.pid
+                    && is_alive(holder.pid)
+                {
+                    return Err(format!(
+                        "lock database {}: held by {} (pid {}); pass --force to override",
+                        db.display(),
+                        holder.client,
+                        holder.pid
+                    ));
+                }
+                std::fs::write(&path, body)
+                    .map_err(|e| format!("lock database {}: {e}", db.display()))?;
+            }
+            Err(e) => return Err(format!("lock database {}: {e}", db.display())),
+        }
+        Ok(Self { path })
+    }
+}
+
+impl Drop for DbLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Reports whether a process with this PID exists.
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 performs only the existence and permission checks.
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    // EPERM: the process exists but belongs to another user.
+    io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Assumes the holder is alive where liveness cannot be checked.
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_live_holders_and_takes_over_stale_ones() {
+        let db = std::env::temp_dir().join(format!("statoor-lock-test-{}", std::process::id()));
+        let path = db.join(LOCK_FILE);
+        let write_holder = |pid: u32| {
+            let holder = format!(r#"{{"pid":{pid},"client":"other"}}"#);
+            assert!(std::fs::create_dir_all(&db).is_ok());
+            assert!(std::fs::write(&path, holder).is_ok());
+        };
+
+        // PID 1 is always alive.
+        write_holder(1);
+        let Err(e) = DbLock::acquire(&db, "reth", false) else {
+            panic!("a live holder must be refused");
+        };
+        assert!(e.contains("held by other (pid 1)"));
+        assert!(DbLock::acquire(&db, "reth", true).is_ok());
+        assert!(!path.exists(), "dropping the lock removes the file");
+
+        // Above the kernel's PID limit, so never alive.
+        write_holder(u32::MAX >> 1);
+        let lock = DbLock::acquire(&db, "reth", false);
+        assert!(lock.is_ok());
+        let held = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(held.contains(r#""client":"reth""#));
+        drop(lock);
+        let _ = std::fs::remove_dir_all(&db);
+    }
+}
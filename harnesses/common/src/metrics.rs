@@ -0,0 +1,101 @@
+//! NDJSON metric lines of `checkpoint` operations.
+//!
+//! A `checkpoint` only observes: the harness writes where the run stands
+//! and reads on, with no flush and no root. The lines go to stderr, or to
+//! the `--metrics-out` file, so the result on stdout is unchanged.
+
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::counters::Counters;
+use crate::sysmetrics::SystemMetrics;
+
+#[derive(Serialize)]
+struct MetricsLine<'a> {
+    checkpoint: &'a str,
+    /// Workload line of the `checkpoint`.
+    line: u64,
+    ops_applied: u64,
+    elapsed_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rss_bytes: Option<u64>,
+    accounts_created: u64,
+    contracts_created: u64,
+    storage_slots: u64,
+}
+
+/// Writes one metric line per `checkpoint`.
+pub struct MetricsLog<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl<W: Write> MetricsLog<W> {
+    /// Creates a log whose elapsed times are relative to `start`.
+    pub fn new(out: W, start: Instant) -> Self {
+        Self { out, start }
+    }
+
+    /// Records the `checkpoint` labeled `label` on workload line `line`,
+    /// with the counts so far and the current resident set size. Each
+    /// line is flushed, so it can be followed while the run goes on.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the underlying sink fails.
+    pub fn record<A: Hash + Eq, S: Hash + Eq>(
+        &mut self,
+        label: &str,
+        line: u64,
+        counters: &Counters<A, S>,
+    ) -> io::Result<()> {
+        let metrics = MetricsLine {
+            checkpoint: label,
+            line,
+            ops_applied: counters.ops_applied(),
+            elapsed_ms: u64::try_from(self.start.elapsed().as_millis()).unwrap_or(u64::MAX),
+            rss_bytes: SystemMetrics::capture().vm_rss_bytes,
+            accounts_created: counters.accounts_created(),
+            contracts_created: counters.contracts_created(),
+            storage_slots: counters.storage_slots(),
+        };
+        serde_json::to_writer(&mut self.out, &metrics)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_one_line_per_checkpoint() {
+        let mut counters: Counters<u8, u8> = Counters::default();
+        let mut log = MetricsLog::new(Vec::new(), Instant::now());
+        counters.record_account_created();
+        counters.record_applied();
+        assert!(log.record("after-accounts", 2, &counters).is_ok());
+        counters.record_set_code(1, 3);
+        counters.record_storage_write(1, 0);
+        counters.record_applied();
+        assert!(log.record("after-storage", 4, &counters).is_ok());
+
+        let text = String::from_utf8(log.out).unwrap_or_default();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["checkpoint"], "after-accounts");
+        assert_eq!(lines[0]["line"], 2);
+        assert_eq!(lines[0]["ops_applied"], 1);
+        assert_eq!(lines[0]["storage_slots"], 0);
+        assert_eq!(lines[1]["ops_applied"], 2);
+        assert_eq!(lines[1]["contracts_created"], 1);
+        assert_eq!(lines[1]["storage_slots"], 1);
+    }
+}
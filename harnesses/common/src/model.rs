@@ -0,0 +1,114 @@
+//! The `measurement_model` of a result: which timed phases ran, what each
+//! of them included, and which client code paths produced them. Flags
+//! such as `--pipelined`, `--load-updates` or `commit_block` change what
+//! a bare `trie_time_ms` measures, so each harness describes its run
+//! here, from its configuration, and the orchestrator refuses to compare
+//! results whose `phases` or `included` differ.
+//!
+//! `included` uses the same keys and values on every client, so two
+//! clients run the same way describe their phases identically.
+//! `code_paths` names client-specific machinery, which is expected to
+//! differ between clients, and is reported without being compared.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Reading and applying the workload, up to the first flush.
+pub const APPLY: &str = "apply";
+/// Computing the state root, `trie_time_ms`.
+pub const TRIE: &str = "trie";
+/// Writing state to the database, `db_write_time_ms`.
+pub const DB_WRITE: &str = "db_write";
+
+/// What one run measured.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MeasurementModel {
+    /// Timed phases that ran, in the order above.
+    pub phases: Vec<&'static str>,
+    /// What each phase included, by phase and aspect.
+    pub included: BTreeMap<&'static str, BTreeMap<&'static str, &'static str>>,
+    /// Client code paths exercised, by name.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub code_paths: BTreeMap<&'static str, String>,
+}
+
+impl MeasurementModel {
+    /// The model of a run of all three phases that the Rust harnesses
+    /// describe the same way: whether the workload was `preloaded`, and
+    /// whether `commit_block` split it into blocks, each with its own
+    /// flush and incremental root. Trie time overlaps no other phase.
+    #[must_use]
+    pub fn standard(preloaded: bool, block_mode: bool) -> Self {
+        let mut model = Self {
+            phases: vec![APPLY, TRIE, DB_WRITE],
+            ..Self::default()
+        };
+        model.include(
+            APPLY,
+            "input",
+            if preloaded { "preloaded" } else { "stream" },
+        );
+        model.include(
+            TRIE,
+            "roots",
+            if block_mode { "per_block" } else { "batch" },
+        );
+        model.include(TRIE, "overlaps", "none");
+        model.include(
+            DB_WRITE,
+            "flushes",
+            if block_mode { "per_block" } else { "once" },
+        );
+        model
+    }
+
+    /// Records what `phase` included as to `aspect`, replacing any value
+    /// recorded before.
+    pub fn include(&mut self, phase: &'static str, aspect: &'static str, value: &'static str) {
+        self.included
+            .entry(phase)
+            .or_default()
+            .insert(aspect, value);
+    }
+
+    /// Records that `phase` did not run, with what it included.
+    pub fn skip(&mut self, phase: &str) {
+        self.phases.retain(|ran| *ran != phase);
+        self.included.remove(phase);
+    }
+
+    /// Records the code path `name` the run took.
+    pub fn code_path(&mut self, name: &'static str, value: impl Into<String>) {
+        self.code_paths.insert(name, value.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_the_configured_run() {
+        let mut model = MeasurementModel::standard(false, true);
+        model.include(TRIE, "overlaps", DB_WRITE);
+        model.skip(APPLY);
+        model.code_path("root", "serial");
+
+        assert_eq!(
+            serde_json::to_value(&model).unwrap_or_default(),
+            serde_json::json!({
+                "phases": ["trie", "db_write"],
+                "included": {
+                    "trie": {"roots": "per_block", "overlaps": "db_write"},
+                    "db_write": {"flushes": "per_block"},
+                },
+                "code_paths": {"root": "serial"},
+            })
+        );
+        assert_eq!(
+            MeasurementModel::standard(true, false).included[APPLY]["input"],
+            "preloaded"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Numbered result lines. A `compute_root` with more lines after it
+//! prints a result and reads on, so one process can report several roots.
+//! Every result line carries `final`, true only on the run's own line, so
+//! a reader of the stream can tell an intermediate root from the end of
+//! the run without waiting for EOF, and a stream cut after a checkpoint
+//! from a complete one. With `--multi-root`, every line also carries its
+//! `root_index`, counted from 0.
+
+use serde::Serialize;
+
+/// Numbers the result lines of a run.
+#[derive(Debug, Default)]
+pub struct RootLines {
+    enabled: bool,
+    emitted: u64,
+}
+
+/// A result with the fields that number it.
+#[derive(Serialize)]
+pub struct RootLine<'a, T> {
+    #[serde(flatten)]
+    result: &'a T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_index: Option<u64>,
+    #[serde(rename = "final")]
+    is_final: bool,
+}
+
+impl RootLines {
+    /// Numbers lines when `enabled`; otherwise they are only marked
+    /// `final` or not.
+    #[must_use]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            emitted: 0,
+        }
+    }
+
+    /// Wraps `result` as the next line, the run's last when `is_final`.
+    pub fn line<'a, T>(&mut self, result: &'a T, is_final: bool) -> RootLine<'a, T> {
+        if !self.enabled {
+            return RootLine {
+                result,
+                root_index: None,
+                is_final,
+            };
+        }
+        let root_index = self.emitted;
+        self.emitted = self.emitted.saturating_add(1);
+        RootLine {
+            result,
+            root_index: Some(root_index),
+            is_final,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_every_line_and_numbers_them_when_enabled() {
+        let result = serde_json::json!({"state_root": "0xab"});
+        let encode =
+            |line: &RootLine<'_, serde_json::Value>| serde_json::to_value(line).unwrap_or_default();
+
+        let mut plain = RootLines::new(false);
+        assert_eq!(
+            encode(&plain.line(&result, false)),
+            serde_json::json!({"state_root": "0xab", "final": false})
+        );
+        assert_eq!(
+            encode(&plain.line(&result, true)),
+            serde_json::json!({"state_root": "0xab", "final": true})
+        );
+
+        let mut lines = RootLines::new(true);
+        assert_eq!(
+            encode(&lines.line(&result, false)),
+            serde_json::json!({"state_root": "0xab", "root_index": 0, "final": false})
+        );
+        assert_eq!(
+            encode(&lines.line(&result, true)),
+            serde_json::json!({"state_root": "0xab", "root_index": 1, "final": true})
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! The final result line. It is serialized to a buffer and checked
+//! before anything reaches stdout: `serde_json` writes non-finite floats
+//! as `null`, so a NaN in a derived metric would otherwise leave as a
+//! valid-looking line that breaks consumers much later.
+//!
+//! The write itself can block on a slow reader of stdout. It happens after
+//! `elapsed_ms` is taken, and its time goes to stderr as [`Emitted`],
+//! since the line cannot carry the time it took to write.
+
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Fields every result must carry; the orchestrator's report reads them.
+pub const REQUIRED_FIELDS: &[&str] = &[
+    "client",
+    "state_root",
+    "accounts_created",
+    "contracts_created",
+    "storage_slots",
+    "elapsed_ms",
+    "trie_time_ms",
+    "db_write_time_ms",
+    "peak_memory_bytes",
+];
+
+/// Results larger than this are written to a sidecar file, and stdout
+/// carries only the required fields and the sidecar's path.
+pub const MAX_INLINE_RESULT_BYTES: usize = 1 << 20;
+
+/// Where a result spilled for the database at `db` goes:
+/// `<db>.result.json`, beside the database directory.
+#[must_use]
+pub fn sidecar_path(db: &Path) -> PathBuf {
+    let mut name = db.file_name().unwrap_or_default().to_os_string();
+    name.push(".result.json");
+    db.with_file_name(name)
+}
+
+/// How long writing a checked result took, from the buffered line to the
+/// flushed stdout, any sidecar included.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Emitted {
+    pub result_emit_ms: u64,
+    /// Bytes written to stdout, the newline included.
+    pub result_bytes: usize,
+}
+
+impl fmt::Display for Emitted {
+    /// Writes the one JSON line the orchestrator picks out of stderr.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"{{"result_emit_ms":{},"result_bytes":{}}}"#,
+            self.result_emit_ms, self.result_bytes
+        )
+    }
+}
+
+/// Serializes `result`, checks it and writes it to `out` as one line,
+/// spilling it to `sidecar` when it exceeds [`MAX_INLINE_RESULT_BYTES`].
+/// When the check fails, `out` gets `{"client":..,"error":..}` instead.
+/// `out` is flushed, so a reader that went away fails the call here.
+///
+/// # Errors
+///
+/// Returns why the result failed the check or could not be written.
+pub fn emit<T: Serialize>(
+    out: &mut impl Write,
+    client: &str,
+    result: &T,
+    sidecar: &Path,
+) -> Result<Emitted, String> {
+    let checked = serde_json::to_vec(result)
+        .map_err(|e| format!("encode result: {e}"))
+        .and_then(|line| check(&line).map(|value| (line, value)));
+    let (mut line, value) = match checked {
+        Ok(checked) => checked,
+        Err(e) => {
+            let error = serde_json::json!({"client": client, "error": e});
+            let _ = writeln!(out, "{error}").and_then(|()| out.flush());
+            return Err(e);
+        }
+    };
+
+    let start = Instant::now();
+    if line.len() > MAX_INLINE_RESULT_BYTES {
+        std::fs::write(sidecar, &line)
+            .map_err(|e| format!("write result sidecar {}: {e}", sidecar.display()))?;
+        let mut summary: Map<String, Value> = REQUIRED_FIELDS
+            .iter()
+            .filter_map(|field| Some((field.to_string(), value.get(field)?.clone())))
+            .collect();
+        summary.insert(
+            "result_sidecar".to_string(),
+            Value::String(sidecar.display().to_string()),
+        );
+        line = serde_json::to_vec(&summary).map_err(|e| format!("encode result: {e}"))?;
+    }
+
+    line.push(b'\n');
+    out.write_all(&line)
+        .and_then(|()| out.flush())
+        .map_err(|e| format!("write result: {e}"))?;
+    Ok(Emitted {
+        result_emit_ms: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+        result_bytes: line.len(),
+    })
+}
+
+/// Parses a serialized result back and rejects it unless it is an object
+/// with every required field and no `null` anywhere. Results leave unset
+/// fields out, so a `null` can only come from a non-finite float.
+fn check(line: &[u8]) -> Result<Value, String> {
+    let value: Value =
+        serde_json::from_slice(line).map_err(|e| format!("result does not parse: {e}"))?;
+    let Some(fields) = value.as_object() else {
+        return Err("result is not a JSON object".to_string());
+    };
+    if let Some(missing) = REQUIRED_FIELDS.iter().find(|f| !fields.contains_key(**f)) {
+        return Err(format!("result lacks required field {missing}"));
+    }
+    if let Some(path) = find_null(&value, "") {
+        return Err(format!("result field {path} is null or non-finite"));
+    }
+    Ok(value)
+}
+
+/// Returns the path of the first `null` below `value`.
+fn find_null(value: &Value, path: &str) -> Option<String> {
+    match value {
+        Value::Null => Some(path.to_string()),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .find_map(|(i, item)| find_null(item, &format!("{path}[{i}]"))),
+        Value::Object(fields) => fields.iter().find_map(|(key, item)| {
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            find_null(item, &path)
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::InputBytes;
+
+    fn result(avg_bytes_per_op: Option<f64>, padding: usize) -> Value {
+        let mut result = serde_json::json!({"padding": "x".repeat(padding)});
+        for field in REQUIRED_FIELDS {
+            result[*field] = Value::from(1);
+        }
+        if let Some(avg) = avg_bytes_per_op {
+            // Value::from maps non-finite floats to null, as serialization does.
+            result["input"] = serde_json::json!({"avg_bytes_per_op": avg});
+        }
+        result
+    }
+
+    #[test]
+    fn nan_metric_becomes_an_error_object() {
+        let mut input = InputBytes::default();
+        input.record("set_storage", 10);
+        let (_, avg) = input.into_parts();
+        let sidecar = std::env::temp_dir().join("statoor-output-unused.result.json");
+
+        let mut out = Vec::new();
+        assert!(emit(&mut out, "reth", &result(avg, 0), &sidecar).is_ok());
+        assert!(String::from_utf8_lossy(&out).contains(r#""avg_bytes_per_op":10.0"#));
+
+        let mut out = Vec::new();
+        let nan = avg.map(|avg| avg * f64::NAN);
+        let Err(e) = emit(&mut out, "reth", &result(nan, 0), &sidecar) else {
+            panic!("a NaN metric must fail the check");
+        };
+        assert_eq!(
+            e,
+            "result field input.avg_bytes_per_op is null or non-finite"
+        );
+        let error: Value = serde_json::from_slice(&out).unwrap_or_default();
+        assert_eq!(error["client"], "reth");
+        assert_eq!(error["error"], e);
+        assert!(!sidecar.exists());
+    }
+
+    #[test]
+    fn rejects_missing_required_fields() {
+        let mut out = Vec::new();
+        let sidecar = std::env::temp_dir().join("statoor-output-unused.result.json");
+        let e = emit(
+            &mut out,
+            "ethrex",
+            &serde_json::json!({"client": "ethrex"}),
+            &sidecar,
+        );
+        assert_eq!(e, Err("result lacks required field state_root".to_string()));
+    }
+
+    #[test]
+    fn a_closed_reader_fails_the_write() {
+        let mut fds = [0; 2];
+        // SAFETY: `fds` has room for the two descriptors pipe writes.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        // SAFETY: both descriptors were just opened and are owned here.
+        let (reader, mut writer) = unsafe {
+            use std::os::fd::FromRawFd;
+            (
+                std::fs::File::from_raw_fd(fds[0]),
+                std::fs::File::from_raw_fd(fds[1]),
+            )
+        };
+        drop(reader);
+        // Rust ignores SIGPIPE, so the write returns EPIPE instead of
+        // killing the process.
+        let sidecar = std::env::temp_dir().join("statoor-output-unused.result.json");
+        let Err(e) = emit(&mut writer, "reth", &result(None, 0), &sidecar) else {
+            panic!("a write to a closed pipe must fail");
+        };
+        assert!(e.starts_with("write result: "), "{e}");
+        assert!(e.contains("Broken pipe"), "{e}");
+    }
+
+    #[test]
+    fn reports_the_bytes_written() {
+        let mut out = Vec::new();
+        let sidecar = std::env::temp_dir().join("statoor-output-unused.result.json");
+        let Ok(emitted) = emit(&mut out, "reth", &result(None, 0), &sidecar) else {
+            panic!("a valid result must be written");
+        };
+        assert_eq!(emitted.result_bytes, out.len());
+        let line: Value = serde_json::from_str(&emitted.to_string()).unwrap_or_default();
+        assert_eq!(line["result_bytes"], out.len());
+        assert!(line["result_emit_ms"].is_u64());
+    }
+
+    #[test]
+    fn large_results_spill_to_a_sidecar() {
+        let db = std::env::temp_dir().join(format!("statoor-output-{}", std::process::id()));
+        let sidecar = sidecar_path(&db);
+        let full = result(None, MAX_INLINE_RESULT_BYTES);
+
+        let mut out = Vec::new();
+        assert!(emit(&mut out, "reth", &full, &sidecar).is_ok());
+        let inline: Value = serde_json::from_slice(&out).unwrap_or_default();
+        assert_eq!(inline["elapsed_ms"], 1);
+        assert!(inline.get("padding").is_none());
+        assert_eq!(
+            inline["result_sidecar"],
+            sidecar.display().to_string().as_str()
+        );
+
+        let spilled = std::fs::read(&sidecar).unwrap_or_default();
+        let _ = std::fs::remove_file(&sidecar);
+        assert_eq!(serde_json::from_slice::<Value>(&spilled).ok(), Some(full));
+    }
+}
@@ -0,0 +1,390 @@
+//! `--preload-workload`: read and decode the whole workload before the
+//! measured phases, so the apply loop does no stdin I/O or JSON parsing.
+//! On the smallest workloads those reads are a large share of the
+//! timings. [`Input`] hides which mode a run uses from the apply loop,
+//! and whether the workload is JSONL or CBOR.
+
+use std::fmt;
+use std::io::BufRead;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::cbor::{CborError, CborReader, WorkloadFormat};
+use crate::input::{InputError, LineReader};
+use crate::protocol::Entry;
+use crate::sampling::DecodeSampler;
+use crate::sysmetrics::SystemMetrics;
+
+/// Default for `--preload-limit-mb`.
+pub const DEFAULT_PRELOAD_LIMIT_MB: u64 = 1024;
+
+/// Errors produced while reading or decoding operations.
+#[derive(Debug)]
+pub enum LoadError {
+    /// A line could not be read.
+    Read(InputError),
+    /// A line is not a valid operation.
+    Decode(serde_json::Error),
+    /// A CBOR record could not be read.
+    Cbor(CborError),
+    /// The workload is larger than `--preload-limit-mb`.
+    TooLarge { limit_mb: u64 },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(e) => write!(f, "read stdin: {e}"),
+            Self::Decode(e) => write!(f, "decode operation: {e}"),
+            Self::Cbor(e) => write!(f, "read CBOR workload: {e}"),
+            Self::TooLarge { limit_mb } => write!(
+                f,
+                "workload exceeds --preload-limit-mb {limit_mb}; raise the limit \
+                 or drop --preload-workload to stream it"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// How the preload went, flattened into the result. A streaming run has
+/// none of these fields, so `preloaded` tells the two modes apart.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PreloadReport {
+    pub preloaded: bool,
+    pub preload_ms: f64,
+    /// Raw workload bytes read, blank lines included.
+    pub preload_bytes: u64,
+    pub preload_ops: u64,
+    /// Growth of the resident set across the preload, where the platform
+    /// reports it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preload_rss_growth_bytes: Option<u64>,
+}
+
+/// A decoded workload held in memory.
+pub struct Preloaded {
+    /// One-based line number, encoded length and operation of each line.
+    entries: std::vec::IntoIter<(u64, usize, Entry)>,
+    line_number: u64,
+    warnings: Vec<String>,
+}
+
+impl Preloaded {
+    /// Reads and decodes all of `reader` as JSONL, refusing more than
+    /// `limit_mb` mebibytes of input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a line cannot be read or decoded, or the input
+    /// is larger than the limit.
+    pub fn load<R: BufRead>(reader: R, limit_mb: u64) -> Result<(Self, PreloadReport), LoadError> {
+        Self::load_as(reader, WorkloadFormat::Jsonl, limit_mb)
+    }
+
+    /// Reads and decodes all of `reader` in `format`, refusing more than
+    /// `limit_mb` mebibytes of input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a line or record cannot be read or decoded, or
+    /// the input is larger than the limit.
+    pub fn load_as<R: BufRead>(
+        mut reader: R,
+        format: WorkloadFormat,
+        limit_mb: u64,
+    ) -> Result<(Self, PreloadReport), LoadError> {
+        let start = Instant::now();
+        let rss_before = SystemMetrics::capture().vm_rss_bytes;
+        let cbor = format
+            .is_cbor(&mut reader)
+            .map_err(|e| LoadError::Read(e.into()))?;
+
+        // One byte past the limit tells an input of exactly the limit
+        // apart from a larger one.
+        let limit = limit_mb.saturating_mul(1024 * 1024).saturating_add(1);
+        let mut limited = reader.take(limit);
+        let mut entries = Vec::new();
+        let warnings = if cbor {
+            let mut input = CborReader::new(&mut limited).map_err(|e| LoadError::Read(e.into()))?;
+            loop {
+                let next = input.next_value();
+                // A record cut short by the limit fails to decode; report
+                // the limit instead.
+                if input.get_ref().limit() == 0 {
+                    return Err(LoadError::TooLarge { limit_mb });
+                }
+                let Some((value, len)) = next.map_err(LoadError::Cbor)? else {
+                    break;
+                };
+                let entry = serde_json::from_value(value).map_err(LoadError::Decode)?;
+                entries.push((input.record(), len, entry));
+            }
+            Vec::new()
+        } else {
+            let mut input = LineReader::new(&mut limited);
+            loop {
+                let next = input
+                    .next_line()
+                    .map(|line| line.map(|line| (serde_json::from_str(line), line.len())));
+                // A line cut short by the limit fails to decode; report the
+                // limit instead.
+                if input.get_ref().limit() == 0 {
+                    return Err(LoadError::TooLarge { limit_mb });
+                }
+                let Some((entry, len)) = next.map_err(LoadError::Read)? else {
+                    break;
+                };
+                entries.push((input.line_number(), len, entry.map_err(LoadError::Decode)?));
+            }
+            input.warnings()
+        };
+
+        let rss_after = SystemMetrics::capture().vm_rss_bytes;
+        let report = PreloadReport {
+            preloaded: true,
+            preload_ms: start.elapsed().as_secs_f64() * 1000.0,
+            preload_bytes: limit - limited.limit(),
+            preload_ops: entries.len() as u64,
+            preload_rss_growth_bytes: rss_before
+                .zip(rss_after)
+                .map(|(before, after)| after.saturating_sub(before)),
+        };
+        let preloaded = Self {
+            entries: entries.into_iter(),
+            line_number: 0,
+            warnings,
+        };
+        Ok((preloaded, report))
+    }
+}
+
+/// The workload as the apply loop sees it: streamed from a reader or
+/// preloaded into memory.
+pub enum Input<R> {
+    Stream(LineReader<R>),
+    Cbor(CborReader<R>),
+    Preloaded(Preloaded),
+}
+
+impl<R: BufRead> Input<R> {
+    /// Streams `reader` as JSONL, or preloads it when `preload_limit_mb`
+    /// is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`Preloaded::load`].
+    pub fn open(
+        reader: R,
+        preload_limit_mb: Option<u64>,
+    ) -> Result<(Self, Option<PreloadReport>), LoadError> {
+        Self::open_as(reader, WorkloadFormat::Jsonl, preload_limit_mb)
+    }
+
+    /// Streams `reader` in `format`, or preloads it when
+    /// `preload_limit_mb` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the start of the input cannot be read, or the
+    /// error of [`Preloaded::load_as`].
+    pub fn open_as(
+        mut reader: R,
+        format: WorkloadFormat,
+        preload_limit_mb: Option<u64>,
+    ) -> Result<(Self, Option<PreloadReport>), LoadError> {
+        if let Some(limit_mb) = preload_limit_mb {
+            let (preloaded, report) = Preloaded::load_as(reader, format, limit_mb)?;
+            return Ok((Self::Preloaded(preloaded), Some(report)));
+        }
+        let input = if format
+            .is_cbor(&mut reader)
+            .map_err(|e| LoadError::Read(e.into()))?
+        {
+            Self::Cbor(CborReader::new(reader).map_err(|e| LoadError::Read(e.into()))?)
+        } else {
+            Self::Stream(LineReader::new(reader))
+        };
+        Ok((input, None))
+    }
+
+    /// Returns the next operation and the length of its line, or `None`
+    /// at the end. A preloaded operation still passes through `decode`
+    /// so that the sampler counts it, but costs it nothing to decode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the next line cannot be read or decoded.
+    pub fn next_entry(
+        &mut self,
+        decode: &mut DecodeSampler,
+    ) -> Result<Option<(Entry, usize)>, LoadError> {
+        match self {
+            Self::Stream(input) => {
+                let Some(line) = input.next_line().map_err(LoadError::Read)? else {
+                    return Ok(None);
+                };
+                let entry = decode
+                    .json(|| serde_json::from_str(line))
+                    .map_err(LoadError::Decode)?;
+                Ok(Some((entry, line.len())))
+            }
+            Self::Cbor(input) => {
+                let Some((value, len)) = input.next_value().map_err(LoadError::Cbor)? else {
+                    return Ok(None);
+                };
+                let entry = decode
+                    .json(|| serde_json::from_value(value))
+                    .map_err(LoadError::Decode)?;
+                Ok(Some((entry, len)))
+            }
+            Self::Preloaded(preloaded) => {
+                Ok(decode
+                    .json(|| preloaded.entries.next())
+                    .map(|(line_number, len, entry)| {
+                        preloaded.line_number = line_number;
+                        (entry, len)
+                    }))
+            }
+        }
+    }
+
+    /// Reports whether the input has ended; see [`LineReader::at_end`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying reader fails.
+    pub fn at_end(&mut self) -> Result<bool, InputError> {
+        match self {
+            Self::Stream(input) => input.at_end(),
+            Self::Cbor(input) => Ok(input.at_end()?),
+            Self::Preloaded(preloaded) => Ok(preloaded.entries.as_slice().is_empty()),
+        }
+    }
+
+    /// Returns the one-based number of the line last returned.
+    #[must_use]
+    pub fn line_number(&self) -> u64 {
+        match self {
+            Self::Stream(input) => input.line_number(),
+            Self::Cbor(input) => input.record(),
+            Self::Preloaded(preloaded) => preloaded.line_number,
+        }
+    }
+
+    /// Describes the input irregularities that were tolerated, for the
+    /// result's `warnings`.
+    #[must_use]
+    pub fn warnings(&self) -> Vec<String> {
+        match self {
+            Self::Stream(input) => input.warnings(),
+            Self::Cbor(_) => Vec::new(),
+            Self::Preloaded(preloaded) => preloaded.warnings.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Op;
+
+    const WORKLOAD: &[u8] =
+        b"{\"op\":\"create_account\",\"address\":\"0x01\",\"balance\":\"0x1\"}\n\
+        \n\
+        {\"op\":\"compute_root\"}\n";
+
+    fn drain(mut input: Input<&[u8]>) -> Vec<(u64, String, usize)> {
+        let mut decode = DecodeSampler::default();
+        let mut seen = Vec::new();
+        while let Ok(Some((entry, len))) = input.next_entry(&mut decode) {
+            seen.push((input.line_number(), entry.op.name().to_string(), len));
+        }
+        assert!(matches!(input.at_end(), Ok(true)));
+        seen
+    }
+
+    #[test]
+    fn preloaded_and_streamed_inputs_agree() {
+        let Ok((streamed, None)) = Input::open(WORKLOAD, None) else {
+            panic!("open streamed input");
+        };
+        let Ok((preloaded, Some(report))) = Input::open(WORKLOAD, Some(1)) else {
+            panic!("open preloaded input");
+        };
+        assert_eq!(preloaded.warnings(), ["skipped 1 blank input lines"]);
+        assert_eq!(drain(preloaded), drain(streamed));
+        assert!(report.preloaded);
+        assert_eq!(report.preload_ops, 2);
+        assert_eq!(report.preload_bytes, WORKLOAD.len() as u64);
+    }
+
+    #[test]
+    fn cbor_inputs_agree_with_jsonl() {
+        let mut cbor = crate::cbor::MAGIC.to_vec();
+        for line in WORKLOAD
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+        {
+            let Ok(entry) = serde_json::from_slice::<Entry>(line) else {
+                panic!("decode workload line");
+            };
+            crate::cbor::encode(&entry, true, &mut cbor);
+        }
+        let Ok((jsonl, _)) = Input::open(WORKLOAD, None) else {
+            panic!("open JSONL input");
+        };
+        let names = |seen: Vec<(u64, String, usize)>| -> Vec<String> {
+            seen.into_iter().map(|(_, name, _)| name).collect()
+        };
+        let want = names(drain(jsonl));
+        for preload in [None, Some(1)] {
+            let Ok((input, _)) = Input::open_as(&cbor[..], WorkloadFormat::Auto, preload) else {
+                panic!("open CBOR input");
+            };
+            let seen = drain(input);
+            assert_eq!(
+                seen.iter().map(|(line, ..)| *line).collect::<Vec<_>>(),
+                [1, 2]
+            );
+            assert_eq!(names(seen), want);
+        }
+    }
+
+    #[test]
+    fn preloaded_input_reports_remaining_operations() {
+        let Ok((mut input, _)) = Input::open(WORKLOAD, Some(1)) else {
+            panic!("open preloaded input");
+        };
+        let mut decode = DecodeSampler::default();
+        let first = input.next_entry(&mut decode).ok().flatten();
+        assert!(matches!(
+            first,
+            Some((
+                Entry {
+                    op: Op::CreateAccount { .. },
+                    ..
+                },
+                _
+            ))
+        ));
+        assert!(matches!(input.at_end(), Ok(false)));
+    }
+
+    #[test]
+    fn refuses_input_past_the_limit() {
+        let line = b"{\"op\":\"compute_root\"}\n";
+        let workload = line.repeat(1024 * 1024 / line.len() + 1);
+        let err = Preloaded::load(&workload[..], 1).err();
+        assert!(matches!(err, Some(LoadError::TooLarge { limit_mb: 1 })));
+        assert!(Preloaded::load(&line.repeat(10)[..], 1).is_ok());
+    }
+
+    #[test]
+    fn preload_rejects_undecodable_lines() {
+        let err = Preloaded::load(&b"{\"op\":\"compute_root\"}\nnope\n"[..], 1).err();
+        assert!(matches!(err, Some(LoadError::Decode(_))));
+    }
+}
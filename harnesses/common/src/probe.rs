@@ -0,0 +1,257 @@
+//! Crash-consistency probe. After the write phase a child process
+//! reopens what was committed and recomputes the state root from it,
+//! optionally on a copy of the database directory taken without its lock
+//! files, which approximates the snapshot a crash would leave behind.
+//! A child still running when the `--verify-timeout-secs` budget runs
+//! out is killed and the probe reported `truncated`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::budget::Budget;
+
+/// Hidden flag the harness re-executes itself with. The child opens the
+/// given database directory, prints its state root and exits.
+pub const PROBE_FLAG: &str = "--probe-root-of";
+
+/// Lock files of the supported engines and of [`crate::lock`], which a
+/// crash snapshot would not carry over and the copy must not inherit.
+const LOCK_FILES: &[&str] = &["LOCK", "mdbx.lck", "lock.mdb", crate::lock::LOCK_FILE];
+
+/// How often a running child is checked against the budget.
+const POLL: Duration = Duration::from_millis(10);
+
+/// Outcome of `--durability-probe`, reported as `durability_probe`.
+#[derive(Serialize)]
+pub struct ProbeReport {
+    /// `pass`, `fail` or `skipped`.
+    pub status: &'static str,
+    /// `soft` reopens the database in place, `hard` also checks a copy.
+    pub mode: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+    /// Set when the budget ran out before every check finished.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// Runs the probe against `db`, which must no longer be open in this
+/// process, and compares the roots the children find with
+/// `expected_root`, within `budget`.
+#[must_use]
+pub fn run(db: &Path, expected_root: &str, hard: bool, budget: &Budget) -> ProbeReport {
+    let mode = if hard { "hard" } else { "soft" };
+    let mut report = ProbeReport {
+        status: "pass",
+        mode,
+        details: Vec::new(),
+        truncated: false,
+    };
+    report.probe("in place", db, expected_root, budget);
+    if hard && budget.exhausted() {
+        report.out_of_budget("copy");
+    } else if hard {
+        let copy = copy_path(db);
+        match copy_db(db, &copy) {
+            Ok(()) => report.probe("copy", &copy, expected_root, budget),
+            Err(e) => {
+                if report.status == "pass" {
+                    report.status = "skipped";
+                }
+                report.details.push(format!("copy: {e}"));
+            }
+        }
+        let _ = std::fs::remove_dir_all(&copy);
+    }
+    report
+}
+
+impl ProbeReport {
+    fn probe(&mut self, what: &str, db: &Path, expected_root: &str, budget: &Budget) {
+        match probe_child(db, budget) {
+            Ok(Some(root)) => self.check(what, Ok(root), expected_root),
+            Ok(None) => self.out_of_budget(what),
+            Err(e) => self.check(what, Err(e), expected_root),
+        }
+    }
+
+    /// Records the check `what` as cut off by the budget. Like a copy
+    /// that could not be made, it leaves a passing probe `skipped`.
+    fn out_of_budget(&mut self, what: &str) {
+        if self.status == "pass" {
+            self.status = "skipped";
+        }
+        self.truncated = true;
+        self.details
+            .push(format!("{what}: not finished within the verify budget"));
+    }
+
+    fn check(&mut self, what: &str, found: Result<String, String>, expected_root: &str) {
+        match found {
+            Ok(root) if root.eq_ignore_ascii_case(expected_root) => {}
+            Ok(root) => {
+                self.status = "fail";
+                self.details
+                    .push(format!("{what}: root {root}, expected {expected_root}"));
+            }
+            Err(e) => {
+                self.status = "fail";
+                self.details.push(format!("{what}: {e}"));
+            }
+        }
+    }
+}
+
+/// Re-executes the current binary with [`PROBE_FLAG`] and returns the
+/// root it prints, or `None` if `budget` ran out first.
+fn probe_child(db: &Path, budget: &Budget) -> Result<Option<String>, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("locate harness binary: {e}"))?;
+    let child = Command::new(exe)
+        .arg(PROBE_FLAG)
+        .arg(db)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("spawn probe: {e}"))?;
+    let Some(output) = wait_within(child, budget).map_err(|e| format!("wait for probe: {e}"))?
+    else {
+        return Ok(None);
+    };
+    if !output.status.success() {
+        return Err(format!(
+            "probe exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Waits for `child` to exit and collects its output, or kills it once
+/// `budget` runs out and returns `None`. The probe prints one line, so
+/// its pipes cannot fill while it runs.
+fn wait_within(mut child: Child, budget: &Budget) -> io::Result<Option<Output>> {
+    while child.try_wait()?.is_none() {
+        if budget.exhausted() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(POLL);
+    }
+    child.wait_with_output().map(Some)
+}
+
+fn copy_path(db: &Path) -> PathBuf {
+    let mut name = db.file_name().unwrap_or_default().to_os_string();
+    name.push(".probe-copy");
+    db.with_file_name(name)
+}
+
+/// Copies a database directory tree, leaving out engine lock files.
+/// `std::fs::copy` reflinks or uses `copy_file_range` where the
+/// filesystem supports it.
+///
+/// # Errors
+///
+/// Returns the first error creating, reading or copying an entry.
+pub fn copy_db(src: &Path, dst: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if LOCK_FILES.iter().any(|lock| name == *lock) {
+            continue;
+        }
+        let target = dst.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_db(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_skips_lock_files() {
+        let dir = std::env::temp_dir().join(format!("statoor-probe-test-{}", std::process::id()));
+        let src = dir.join("db");
+        let nested = src.join("nested");
+        assert!(std::fs::create_dir_all(&nested).is_ok());
+        for (path, body) in [
+            (src.join("mdbx.dat"), "data"),
+            (src.join("mdbx.lck"), "lock"),
+            (nested.join("000001.sst"), "sst"),
+            (nested.join("LOCK"), ""),
+        ] {
+            assert!(std::fs::write(path, body).is_ok());
+        }
+
+        let copy = copy_path(&src);
+        assert_eq!(copy, dir.join("db.probe-copy"));
+        assert!(copy_db(&src, &copy).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(copy.join("mdbx.dat"))
+                .ok()
+                .as_deref(),
+            Some("data")
+        );
+        assert!(copy.join("nested/000001.sst").exists());
+        assert!(!copy.join("mdbx.lck").exists() && !copy.join("nested/LOCK").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn mismatched_root_fails() {
+        let mut report = ProbeReport {
+            status: "pass",
+            mode: "soft",
+            details: Vec::new(),
+            truncated: false,
+        };
+        report.check("in place", Ok("0xAB".to_string()), "0xab");
+        assert_eq!(report.status, "pass");
+        report.check("copy", Ok("0xcd".to_string()), "0xab");
+        assert_eq!(report.status, "fail");
+        assert_eq!(report.details, ["copy: root 0xcd, expected 0xab"]);
+    }
+
+    #[test]
+    fn a_child_past_the_budget_is_killed() {
+        let Ok(slow) = Command::new("sleep").arg("5").spawn() else {
+            panic!("sleep must spawn");
+        };
+        let budget = Budget::until(std::time::Instant::now() + Duration::from_millis(50));
+        assert!(matches!(wait_within(slow, &budget), Ok(None)));
+
+        let Ok(quick) = Command::new("true").spawn() else {
+            panic!("true must spawn");
+        };
+        let finished = wait_within(quick, &Budget::default());
+        assert!(finished.is_ok_and(|output| output.is_some_and(|o| o.status.success())));
+
+        let mut report = ProbeReport {
+            status: "pass",
+            mode: "hard",
+            details: Vec::new(),
+            truncated: false,
+        };
+        report.out_of_budget("copy");
+        assert_eq!((report.status, report.truncated), ("skipped", true));
+        assert_eq!(
+            report.details,
+            ["copy: not finished within the verify budget"]
+        );
+    }
+}
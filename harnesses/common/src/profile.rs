@@ -0,0 +1,116 @@
+//! Opt-in in-process sampling profiler for the timed phases. It exists
+//! only in builds with the `profiling` feature, which adds the
+//! `--profile-out` and `--profile-frequency` flags through
+//! [`ProfileArgs`]. Without the feature [`Profiler`] is an empty type
+//! whose methods compile to nothing.
+
+#[cfg(feature = "profiling")]
+use std::path::PathBuf;
+
+/// Profiling flags, flattened into each harness's CLI.
+#[cfg(feature = "profiling")]
+#[derive(clap::Args, serde::Serialize)]
+pub struct ProfileArgs {
+    /// Sample the trie and DB write phases separately and write one
+    /// flamegraph SVG per phase to this directory.
+    #[arg(long)]
+    pub profile_out: Option<PathBuf>,
+
+    /// Samples per second while profiling, which bounds the overhead.
+    #[arg(
+        long,
+        default_value_t = 99,
+        value_parser = clap::value_parser!(i32).range(1..=10_000)
+    )]
+    pub profile_frequency: i32,
+}
+
+/// Samples one phase at a time and writes `<phase>.svg` for each.
+/// Failures to profile never stop a run; they become warnings.
+#[derive(Default)]
+pub struct Profiler {
+    #[cfg(feature = "profiling")]
+    inner: Option<Inner>,
+}
+
+#[cfg(feature = "profiling")]
+struct Inner {
+    dir: PathBuf,
+    frequency: i32,
+    active: Option<(&'static str, pprof::ProfilerGuard<'static>)>,
+    artifacts: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Profiler {
+    /// A profiler writing to `--profile-out`, or a disabled one when the
+    /// flag is absent.
+    #[cfg(feature = "profiling")]
+    #[must_use]
+    pub fn new(args: &ProfileArgs) -> Self {
+        Self {
+            inner: args.profile_out.clone().map(|dir| Inner {
+                dir,
+                frequency: args.profile_frequency,
+                active: None,
+                artifacts: Vec::new(),
+                warnings: Vec::new(),
+            }),
+        }
+    }
+
+    /// Ends the current phase and starts sampling `phase`.
+    pub fn phase(&mut self, phase: &'static str) {
+        self.stop();
+        #[cfg(feature = "profiling")]
+        if let Some(inner) = &mut self.inner {
+            let guard = pprof::ProfilerGuardBuilder::default()
+                .frequency(inner.frequency)
+                .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+                .build();
+            match guard {
+                Ok(guard) => inner.active = Some((phase, guard)),
+                Err(e) => inner.warnings.push(format!("profile {phase}: {e}")),
+            }
+        }
+        #[cfg(not(feature = "profiling"))]
+        let _ = phase;
+    }
+
+    /// Ends the current phase, writing its flamegraph.
+    #[cfg_attr(not(feature = "profiling"), allow(clippy::unused_self))]
+    pub fn stop(&mut self) {
+        #[cfg(feature = "profiling")]
+        if let Some(inner) = &mut self.inner
+            && let Some((phase, guard)) = inner.active.take()
+            && let Err(e) = inner.write(phase, &guard)
+        {
+            inner.warnings.push(format!("profile {phase}: {e}"));
+        }
+    }
+
+    /// Ends the current phase and returns the paths of the files written
+    /// and any warnings.
+    #[must_use]
+    pub fn into_parts(mut self) -> (Vec<String>, Vec<String>) {
+        self.stop();
+        #[cfg(feature = "profiling")]
+        if let Some(inner) = self.inner {
+            return (inner.artifacts, inner.warnings);
+        }
+        (Vec::new(), Vec::new())
+    }
+}
+
+#[cfg(feature = "profiling")]
+impl Inner {
+    fn write(&mut self, phase: &str, guard: &pprof::ProfilerGuard<'static>) -> Result<(), String> {
+        let report = guard.report().build().map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let path = self.dir.join(format!("{phase}.svg"));
+        let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+        report.flamegraph(file).map_err(|e| e.to_string())?;
+        self.artifacts.push(path.display().to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! `get_proof` operations: Merkle proofs of an account and some of its
+//! slots against the last committed root, generated by the client's own
+//! proof code and timed apart from the write path, as stateless clients
+//! need them for witnesses. Like a read, a proof sees the state as of the
+//! last `commit_block`; an account that is not there gets a proof of its
+//! absence, which counts like any other.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Running totals of the proofs of a workload.
+#[derive(Default)]
+pub struct ProofStats {
+    proofs: u64,
+    absent: u64,
+    bytes: u64,
+    time: Duration,
+}
+
+/// What one `get_proof` produced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Proof {
+    /// Encoded nodes of the account proof and every storage proof.
+    pub bytes: u64,
+    /// Whether the account exists, so the account proof includes it.
+    pub found: bool,
+}
+
+/// The result fields of a workload with proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ProofReport {
+    pub proofs_generated: u64,
+    pub proof_time_ms: u64,
+    pub proof_bytes_total: u64,
+    /// Proofs of accounts that do not exist.
+    pub proof_exclusions: u64,
+}
+
+impl ProofStats {
+    /// Times the generation of one proof. Errors of `prove` are passed
+    /// through uncounted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `prove`.
+    pub fn time<E>(&mut self, prove: impl FnOnce() -> Result<Proof, E>) -> Result<(), E> {
+        let start = Instant::now();
+        let proof = prove()?;
+        self.time = self.time.saturating_add(start.elapsed());
+        self.proofs = self.proofs.saturating_add(1);
+        self.bytes = self.bytes.saturating_add(proof.bytes);
+        if !proof.found {
+            self.absent = self.absent.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    /// The result fields, or `None` when the workload had no proofs.
+    #[must_use]
+    pub fn report(&self) -> Option<ProofReport> {
+        (self.proofs > 0).then(|| ProofReport {
+            proofs_generated: self.proofs,
+            proof_time_ms: u64::try_from(self.time.as_millis()).unwrap_or(u64::MAX),
+            proof_bytes_total: self.bytes,
+            proof_exclusions: self.absent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn totals_bytes_and_exclusions() {
+        let mut stats = ProofStats::default();
+        assert_eq!(stats.report(), None);
+        let proof = |bytes, found| move || Ok::<_, String>(Proof { bytes, found });
+        assert_eq!(stats.time(proof(532, true)), Ok(()));
+        assert_eq!(stats.time(proof(210, false)), Ok(()));
+        assert_eq!(
+            stats.time(|| Err::<Proof, _>("no root".to_string())),
+            Err("no root".to_string())
+        );
+        let json = serde_json::to_value(stats.report()).unwrap_or_default();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "proofs_generated": 2,
+                "proof_time_ms": 0,
+                "proof_bytes_total": 742,
+                "proof_exclusions": 1,
+            })
+        );
+    }
+}
@@ -0,0 +1,1554 @@
+//! The workload protocol: one JSON object per line, tagged by its `op`
+//! field. This is the single definition every Rust harness decodes, and
+//! the published JSON Schema is generated from it.
+//!
+//! Lines decode through a flat [`Line`] struct rather than an internally
+//! tagged enum, which would buffer every line before picking a variant;
+//! the conversion to [`Op`] then checks the tag.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema, schema_for};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Operation names a harness accepts in the `op` field.
+pub const OP_NAMES: &[&str] = &[
+    "header",
+    "create_account",
+    "create_accounts",
+    "set_code",
+    "define_code",
+    "set_code_ref",
+    "set_delegation",
+    "set_storage",
+    "set_storage_batch",
+    "delete_account",
+    "selfdestruct",
+    "clear_storage",
+    "update_balance",
+    "transfer",
+    "increment_nonce",
+    "touch_account",
+    "read_account",
+    "read_storage",
+    "get_proof",
+    "assert_account",
+    "assert_storage",
+    "commit_block",
+    "checkpoint",
+    "sleep",
+    "trailer",
+    "canary",
+    "compute_root",
+];
+
+/// Version of the workload protocol the harnesses speak, reported by
+/// `--capabilities`.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Most slots one inline `storage` map or `set_storage_batch` may carry.
+/// Larger accounts must be split over several lines so a single line
+/// stays bounded.
+pub const MAX_INLINE_STORAGE_SLOTS: usize = 1 << 20;
+
+/// One workload operation. Values stay strings as written, `0x`-prefixed
+/// hex or, for quantities and slots, decimal; each harness parses them
+/// into its client's types with [`crate::quantity`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Line", into = "Line")]
+pub enum Op {
+    /// Describes the workload, as its first line: the protocol `version`
+    /// it was written for, a `name` for the result, how many operations
+    /// follow, and the root its final `compute_root` must produce. Not an
+    /// applied operation.
+    Header {
+        version: u32,
+        name: Option<String>,
+        total_ops: Option<u64>,
+        expected_root: Option<String>,
+    },
+    /// Creates an account. `storage` holds `(slot, value)` pairs in input
+    /// order, applied as if each were a `set_storage` line.
+    CreateAccount {
+        address: String,
+        balance: String,
+        nonce: u64,
+        storage: Vec<(String, String)>,
+    },
+    /// Creates `count` accounts with the same balance and nonce at
+    /// addresses derived from `seed`, as [`crate::derive`] describes.
+    CreateAccounts {
+        seed: String,
+        count: u64,
+        balance: String,
+        nonce: u64,
+    },
+    /// Deploys bytecode to an address.
+    SetCode { address: String, code: String },
+    /// Registers bytecode under a short id for `set_code_ref` lines to
+    /// attach. Changes no state by itself.
+    DefineCode { id: String, code: String },
+    /// Deploys the bytecode of a `define_code` to an address, as a
+    /// `set_code` with that code would.
+    SetCodeRef { address: String, code_id: String },
+    /// Delegates an address to `target` under EIP-7702: deploys the
+    /// designation `0xef0100 || target` as its code.
+    SetDelegation { address: String, target: String },
+    /// Sets one storage slot of an address.
+    SetStorage {
+        address: String,
+        slot: String,
+        value: String,
+    },
+    /// Sets several storage slots of an address, `(slot, value)` pairs in
+    /// input order, as that many `set_storage` lines would. An empty
+    /// `entries` changes nothing.
+    SetStorageBatch {
+        address: String,
+        entries: Vec<(String, String)>,
+    },
+    /// Deletes an account with its code and storage, as if it had never
+    /// been created. A later `create_account` starts it empty again.
+    DeleteAccount { address: String },
+    /// Self-destructs a contract with pre-Cancun semantics: its code,
+    /// balance and storage are removed, including slots written earlier
+    /// in the workload. The state is then that of `delete_account`.
+    Selfdestruct { address: String },
+    /// Wipes every storage slot of an existing account, including slots
+    /// written earlier in the workload, and keeps its balance, nonce and
+    /// code, as a contract re-deployed in place. An account that is in
+    /// neither the workload nor the state is an error.
+    ClearStorage { address: String },
+    /// Adds `value` to the balance of an address, or subtracts it. An
+    /// address without an account is treated as an empty one.
+    UpdateBalance {
+        address: String,
+        value: String,
+        sign: Sign,
+    },
+    /// Moves `value` between two balances: an `update_balance` that
+    /// subtracts from `from`, then one that adds to `to`.
+    Transfer {
+        from: String,
+        to: String,
+        value: String,
+    },
+    /// Raises the nonce of an existing account by `count`, keeping its
+    /// balance, code and storage. An account the workload has not
+    /// written is looked up in the state; one that is not there either is
+    /// an error rather than an empty account.
+    IncrementNonce { address: String, count: u64 },
+    /// Touches an address as any call or zero-value transfer to it would,
+    /// leaving an existing account unchanged and creating a missing one
+    /// empty. With `--prune-empty-accounts`, a touched account that is
+    /// empty is removed at the next commit, as EIP-158 requires.
+    TouchAccount { address: String },
+    /// Looks up an account in the committed state without changing it.
+    ReadAccount { address: String },
+    /// Looks up one storage slot in the committed state.
+    ReadStorage { address: String, slot: String },
+    /// Generates a Merkle proof of an account and of `slots` of its
+    /// storage against the last committed root.
+    GetProof { address: String, slots: Vec<String> },
+    /// Checks the `balance` and `nonce` of an address, whichever are
+    /// given, against what the workload has written so far: its pending
+    /// writes over the last committed state. A missing account has both
+    /// zero. Not an applied operation.
+    AssertAccount {
+        address: String,
+        balance: Option<String>,
+        nonce: Option<u64>,
+    },
+    /// Checks that `slot` of an address holds `expected`, read the way
+    /// `assert_account` reads an account. Not an applied operation.
+    AssertStorage {
+        address: String,
+        slot: String,
+        expected: String,
+    },
+    /// Ends a block: flush the writes since the previous block and
+    /// compute the state root on top of its root, then keep reading.
+    /// `approx_gas`, the generator's estimate of what the block would
+    /// cost on chain, is copied into the block's report untouched.
+    CommitBlock { approx_gas: Option<u64> },
+    /// Reports the counts, memory and time so far under `label`, without
+    /// writing or hashing anything; not an applied operation.
+    Checkpoint { label: String },
+    /// Pauses the run for `ms` milliseconds, to let the machine cool down
+    /// between phases. The pause is left out of the reported times; not
+    /// an applied operation.
+    Sleep { ms: u64 },
+    /// Declares how many lines of each operation come before it, and
+    /// `lines` in all, for the harness to check against what it read.
+    /// Written just before the final `compute_root`; not an applied
+    /// operation.
+    Trailer {
+        counts: BTreeMap<String, u64>,
+        lines: u64,
+    },
+    /// Checks the harness's encoding of one storage value: the keccak of
+    /// the RLP of `value`, as the storage trie of `address` holds it at
+    /// `slot`, must be `expected_leaf_hash`. Not an applied operation.
+    Canary {
+        address: String,
+        slot: String,
+        value: String,
+        expected_leaf_hash: String,
+    },
+    /// Ends the workload: flush writes and compute the state root. With
+    /// `expected_root`, the harness also reports whether they match.
+    ComputeRoot { expected_root: Option<String> },
+}
+
+/// Direction of an `update_balance`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sign {
+    Add,
+    Sub,
+}
+
+impl Sign {
+    /// The value of the `sign` field.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Sub => "sub",
+        }
+    }
+}
+
+impl Op {
+    /// The operation's `op` tag, one of [`OP_NAMES`].
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Header { .. } => "header",
+            Self::CreateAccount { .. } => "create_account",
+            Self::CreateAccounts { .. } => "create_accounts",
+            Self::SetCode { .. } => "set_code",
+            Self::DefineCode { .. } => "define_code",
+            Self::SetCodeRef { .. } => "set_code_ref",
+            Self::SetDelegation { .. } => "set_delegation",
+            Self::SetStorage { .. } => "set_storage",
+            Self::SetStorageBatch { .. } => "set_storage_batch",
+            Self::DeleteAccount { .. } => "delete_account",
+            Self::Selfdestruct { .. } => "selfdestruct",
+            Self::ClearStorage { .. } => "clear_storage",
+            Self::UpdateBalance { .. } => "update_balance",
+            Self::Transfer { .. } => "transfer",
+            Self::IncrementNonce { .. } => "increment_nonce",
+            Self::TouchAccount { .. } => "touch_account",
+            Self::ReadAccount { .. } => "read_account",
+            Self::ReadStorage { .. } => "read_storage",
+            Self::GetProof { .. } => "get_proof",
+            Self::AssertAccount { .. } => "assert_account",
+            Self::AssertStorage { .. } => "assert_storage",
+            Self::CommitBlock { .. } => "commit_block",
+            Self::Checkpoint { .. } => "checkpoint",
+            Self::Sleep { .. } => "sleep",
+            Self::Trailer { .. } => "trailer",
+            Self::Canary { .. } => "canary",
+            Self::ComputeRoot { .. } => "compute_root",
+        }
+    }
+
+    /// The accounts the operation acts on, in field order and as written
+    /// in the workload.
+    pub fn addresses(&self) -> impl Iterator<Item = &str> {
+        let (first, second) = match self {
+            Self::CreateAccount { address, .. }
+            | Self::SetCode { address, .. }
+            | Self::SetCodeRef { address, .. }
+            | Self::SetDelegation { address, .. }
+            | Self::SetStorage { address, .. }
+            | Self::SetStorageBatch { address, .. }
+            | Self::DeleteAccount { address }
+            | Self::Selfdestruct { address }
+            | Self::ClearStorage { address }
+            | Self::UpdateBalance { address, .. }
+            | Self::IncrementNonce { address, .. }
+            | Self::TouchAccount { address }
+            | Self::ReadAccount { address }
+            | Self::ReadStorage { address, .. }
+            | Self::GetProof { address, .. }
+            | Self::AssertAccount { address, .. }
+            | Self::AssertStorage { address, .. } => (Some(address), None),
+            Self::Transfer { from, to, .. } => (Some(from), Some(to)),
+            Self::Header { .. }
+            | Self::CreateAccounts { .. }
+            | Self::DefineCode { .. }
+            | Self::CommitBlock { .. }
+            | Self::Checkpoint { .. }
+            | Self::Sleep { .. }
+            | Self::Trailer { .. }
+            | Self::Canary { .. }
+            | Self::ComputeRoot { .. } => (None, None),
+        };
+        [first, second].into_iter().flatten().map(String::as_str)
+    }
+}
+
+/// One decoded line: the operation plus the tenant a multi-tenant
+/// workload tags it with and the block a workload exported from a chain
+/// puts it in. The tenant never affects state; harnesses only count it.
+/// A line of a later block than the one before it commits that block
+/// first, as a `commit_block` would.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Line", into = "Line")]
+pub struct Entry {
+    pub op: Op,
+    pub tenant: Option<u32>,
+    pub block: Option<u64>,
+}
+
+/// The wire form of a line. Fields an operation does not use may be
+/// omitted and are left out when encoding.
+#[derive(Default, Serialize, Deserialize)]
+struct Line {
+    op: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    address: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    balance: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nonce: Option<u64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    code: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    code_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    target: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    slot: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    slots: Vec<String>,
+    #[serde(default, skip_serializing_if = "BatchEntries::is_empty")]
+    entries: BatchEntries,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    value: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    expected: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    sign: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    count: Option<u64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    seed: String,
+    #[serde(default, skip_serializing_if = "InlineStorage::is_empty")]
+    storage: InlineStorage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    approx_gas: Option<u64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    counts: Option<BTreeMap<String, u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lines: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected_root: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    total_ops: Option<u64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    expected_leaf_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tenant: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block: Option<u64>,
+}
+
+impl TryFrom<Line> for Entry {
+    type Error = String;
+
+    fn try_from(mut line: Line) -> Result<Self, Self::Error> {
+        let tenant = line.tenant.take();
+        let block = line.block.take();
+        Ok(Self {
+            op: Op::try_from(line)?,
+            tenant,
+            block,
+        })
+    }
+}
+
+impl From<Entry> for Line {
+    fn from(entry: Entry) -> Self {
+        Self {
+            tenant: entry.tenant,
+            block: entry.block,
+            ..Self::from(entry.op)
+        }
+    }
+}
+
+impl TryFrom<Line> for Op {
+    type Error = String;
+
+    #[allow(clippy::too_many_lines)]
+    fn try_from(line: Line) -> Result<Self, Self::Error> {
+        Ok(match line.op.as_str() {
+            "header" => Self::Header {
+                version: line
+                    .version
+                    .ok_or_else(|| "header version is required".to_string())?,
+                name: line.name,
+                total_ops: line.total_ops,
+                expected_root: line.expected_root,
+            },
+            "create_account" => Self::CreateAccount {
+                address: line.address,
+                balance: line.balance,
+                nonce: line.nonce.unwrap_or(0),
+                storage: line.storage.0,
+            },
+            "create_accounts" => Self::CreateAccounts {
+                seed: line.seed,
+                count: match line.count {
+                    None => return Err("create_accounts count is required".to_string()),
+                    Some(0) => return Err("create_accounts count must be positive".to_string()),
+                    Some(count) => count,
+                },
+                balance: line.balance,
+                nonce: line.nonce.unwrap_or(0),
+            },
+            "set_code" => Self::SetCode {
+                address: line.address,
+                code: line.code,
+            },
+            "define_code" => Self::DefineCode {
+                id: line.id,
+                code: line.code,
+            },
+            "set_code_ref" => Self::SetCodeRef {
+                address: line.address,
+                code_id: line.code_id,
+            },
+            "set_delegation" => Self::SetDelegation {
+                address: line.address,
+                target: line.target,
+            },
+            "set_storage" => Self::SetStorage {
+                address: line.address,
+                slot: line.slot,
+                value: line.value,
+            },
+            "set_storage_batch" => Self::SetStorageBatch {
+                address: line.address,
+                entries: line.entries.0,
+            },
+            "delete_account" => Self::DeleteAccount {
+                address: line.address,
+            },
+            "selfdestruct" => Self::Selfdestruct {
+                address: line.address,
+            },
+            "clear_storage" => Self::ClearStorage {
+                address: line.address,
+            },
+            "update_balance" => Self::UpdateBalance {
+                address: line.address,
+                value: line.value,
+                sign: match line.sign.as_str() {
+                    "add" => Sign::Add,
+                    "sub" => Sign::Sub,
+                    other => {
+                        return Err(format!(
+                            "update_balance sign must be add or sub, got {other:?}"
+                        ));
+                    }
+                },
+            },
+            "transfer" => Self::Transfer {
+                from: line.from,
+                to: line.to,
+                value: line.value,
+            },
+            "increment_nonce" => Self::IncrementNonce {
+                address: line.address,
+                count: match line.count.unwrap_or(1) {
+                    0 => return Err("increment_nonce count must be positive".to_string()),
+                    count => count,
+                },
+            },
+            "touch_account" => Self::TouchAccount {
+                address: line.address,
+            },
+            "read_account" => Self::ReadAccount {
+                address: line.address,
+            },
+            "read_storage" => Self::ReadStorage {
+                address: line.address,
+                slot: line.slot,
+            },
+            "get_proof" => Self::GetProof {
+                address: line.address,
+                slots: line.slots,
+            },
+            "assert_account" if line.balance.is_empty() && line.nonce.is_none() => {
+                return Err("assert_account needs a balance or a nonce to check".to_string());
+            }
+            "assert_account" => Self::AssertAccount {
+                address: line.address,
+                balance: (!line.balance.is_empty()).then_some(line.balance),
+                nonce: line.nonce,
+            },
+            "assert_storage" if line.expected.is_empty() => {
+                return Err("assert_storage expected is required".to_string());
+            }
+            "assert_storage" => Self::AssertStorage {
+                address: line.address,
+                slot: line.slot,
+                expected: line.expected,
+            },
+            "commit_block" => Self::CommitBlock {
+                approx_gas: line.approx_gas,
+            },
+            "checkpoint" => Self::Checkpoint { label: line.label },
+            "sleep" => Self::Sleep {
+                ms: line.ms.ok_or_else(|| "sleep ms is required".to_string())?,
+            },
+            "trailer" => Self::Trailer {
+                counts: line.counts.unwrap_or_default(),
+                lines: line
+                    .lines
+                    .ok_or_else(|| "trailer lines is required".to_string())?,
+            },
+            "canary" if line.expected_leaf_hash.is_empty() => {
+                return Err("canary expected_leaf_hash is required".to_string());
+            }
+            "canary" => Self::Canary {
+                address: line.address,
+                slot: line.slot,
+                value: line.value,
+                expected_leaf_hash: line.expected_leaf_hash,
+            },
+            "compute_root" => Self::ComputeRoot {
+                expected_root: line.expected_root,
+            },
+            other => return Err(format!("unknown operation: {other}")),
+        })
+    }
+}
+
+impl From<Op> for Line {
+    #[allow(clippy::too_many_lines)]
+    fn from(op: Op) -> Self {
+        let name = op.name().to_string();
+        match op {
+            Op::Header {
+                version,
+                name: workload_name,
+                total_ops,
+                expected_root,
+            } => Self {
+                op: name,
+                expected_root,
+                version: Some(version),
+                name: workload_name,
+                total_ops,
+                ..Self::default()
+            },
+            Op::CreateAccount {
+                address,
+                balance,
+                nonce,
+                storage,
+            } => Self {
+                op: name,
+                address,
+                balance,
+                nonce: Some(nonce),
+                storage: InlineStorage(storage),
+                ..Self::default()
+            },
+            Op::CreateAccounts {
+                seed,
+                count,
+                balance,
+                nonce,
+            } => Self {
+                op: name,
+                balance,
+                nonce: Some(nonce),
+                count: Some(count),
+                seed,
+                ..Self::default()
+            },
+            Op::SetCode { address, code } => Self {
+                op: name,
+                address,
+                code,
+                ..Self::default()
+            },
+            Op::DefineCode { id, code } => Self {
+                op: name,
+                id,
+                code,
+                ..Self::default()
+            },
+            Op::SetCodeRef { address, code_id } => Self {
+                op: name,
+                address,
+                code_id,
+                ..Self::default()
+            },
+            Op::SetDelegation { address, target } => Self {
+                op: name,
+                address,
+                target,
+                ..Self::default()
+            },
+            Op::SetStorage {
+                address,
+                slot,
+                value,
+            } => Self {
+                op: name,
+                address,
+                slot,
+                value,
+                ..Self::default()
+            },
+            Op::SetStorageBatch { address, entries } => Self {
+                op: name,
+                address,
+                entries: BatchEntries(entries),
+                ..Self::default()
+            },
+            Op::DeleteAccount { address }
+            | Op::Selfdestruct { address }
+            | Op::ClearStorage { address }
+            | Op::TouchAccount { address }
+            | Op::ReadAccount { address } => Self {
+                op: name,
+                address,
+                ..Self::default()
+            },
+            Op::UpdateBalance {
+                address,
+                value,
+                sign,
+            } => Self {
+                op: name,
+                address,
+                value,
+                sign: sign.as_str().to_string(),
+                ..Self::default()
+            },
+            Op::Transfer { from, to, value } => Self {
+                op: name,
+                from,
+                to,
+                value,
+                ..Self::default()
+            },
+            Op::IncrementNonce { address, count } => Self {
+                op: name,
+                address,
+                count: (count != 1).then_some(count),
+                ..Self::default()
+            },
+            Op::ReadStorage { address, slot } => Self {
+                op: name,
+                address,
+                slot,
+                ..Self::default()
+            },
+            Op::GetProof { address, slots } => Self {
+                op: name,
+                address,
+                slots,
+                ..Self::default()
+            },
+            Op::AssertAccount {
+                address,
+                balance,
+                nonce,
+            } => Self {
+                op: name,
+                address,
+                balance: balance.unwrap_or_default(),
+                nonce,
+                ..Self::default()
+            },
+            Op::AssertStorage {
+                address,
+                slot,
+                expected,
+            } => Self {
+                op: name,
+                address,
+                slot,
+                expected,
+                ..Self::default()
+            },
+            Op::CommitBlock { approx_gas } => Self {
+                op: name,
+                approx_gas,
+                ..Self::default()
+            },
+            Op::Checkpoint { label } => Self {
+                op: name,
+                label,
+                ..Self::default()
+            },
+            Op::Sleep { ms } => Self {
+                op: name,
+                ms: Some(ms),
+                ..Self::default()
+            },
+            Op::Trailer { counts, lines } => Self {
+                op: name,
+                counts: Some(counts),
+                lines: Some(lines),
+                ..Self::default()
+            },
+            Op::Canary {
+                address,
+                slot,
+                value,
+                expected_leaf_hash,
+            } => Self {
+                op: name,
+                address,
+                slot,
+                value,
+                expected_leaf_hash,
+                ..Self::default()
+            },
+            Op::ComputeRoot { expected_root } => Self {
+                op: name,
+                expected_root,
+                ..Self::default()
+            },
+        }
+    }
+}
+
+/// Inline account storage as `(slot, value)` pairs, kept in input order
+/// and bounded by [`MAX_INLINE_STORAGE_SLOTS`].
+#[derive(Default)]
+struct InlineStorage(Vec<(String, String)>);
+
+impl InlineStorage {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for InlineStorage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (slot, value) in &self.0 {
+            map.serialize_entry(slot, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for InlineStorage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(InlineStorageVisitor)
+    }
+}
+
+struct InlineStorageVisitor;
+
+impl<'de> Visitor<'de> for InlineStorageVisitor {
+    type Value = InlineStorage;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of storage slot to value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut slots = Vec::with_capacity(map.size_hint().unwrap_or(0).min(1024));
+        while let Some(entry) = map.next_entry::<String, String>()? {
+            if slots.len() == MAX_INLINE_STORAGE_SLOTS {
+                return Err(serde::de::Error::custom(format_args!(
+                    "inline storage exceeds {MAX_INLINE_STORAGE_SLOTS} slots; split the account into set_storage operations"
+                )));
+            }
+            slots.push(entry);
+        }
+        Ok(InlineStorage(slots))
+    }
+}
+
+/// The `(slot, value)` pairs of a `set_storage_batch`, written as
+/// two-element arrays and bounded like [`InlineStorage`].
+#[derive(Default)]
+struct BatchEntries(Vec<(String, String)>);
+
+impl BatchEntries {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for BatchEntries {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BatchEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(BatchEntriesVisitor)
+    }
+}
+
+struct BatchEntriesVisitor;
+
+impl<'de> Visitor<'de> for BatchEntriesVisitor {
+    type Value = BatchEntries;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of [slot, value] pairs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(1024));
+        while let Some(entry) = seq.next_element::<(String, String)>()? {
+            if entries.len() == MAX_INLINE_STORAGE_SLOTS {
+                return Err(serde::de::Error::custom(format_args!(
+                    "set_storage_batch exceeds {MAX_INLINE_STORAGE_SLOTS} entries; split it into several lines"
+                )));
+            }
+            entries.push(entry);
+        }
+        Ok(BatchEntries(entries))
+    }
+}
+
+// Written out by hand rather than derived; keep the properties in step
+// with the fields of `Line`, which `schema_covers_every_field` checks.
+impl JsonSchema for Op {
+    fn schema_name() -> Cow<'static, str> {
+        "Operation".into()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "One workload line. Fields an operation does not use may be omitted.",
+            "type": "object",
+            "required": ["op"],
+            "properties": {
+                "op": {
+                    "description": "Operation name.",
+                    "type": "string",
+                    "enum": OP_NAMES,
+                },
+                "address": {
+                    "description": "20-byte account address, 0x-prefixed hex.",
+                    "type": "string",
+                },
+                "balance": {
+                    "description": "Account balance, 0x-prefixed hex or decimal (create_account, create_accounts, assert_account).",
+                    "type": "string",
+                },
+                "nonce": {
+                    "description": "Account nonce (create_account, create_accounts, assert_account).",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 0,
+                },
+                "code": {
+                    "description": "Contract bytecode, 0x-prefixed hex (set_code, define_code).",
+                    "type": "string",
+                },
+                "id": {
+                    "description": "Name the bytecode is registered under (define_code).",
+                    "type": "string",
+                },
+                "code_id": {
+                    "description": "Id of the define_code whose bytecode to deploy (set_code_ref).",
+                    "type": "string",
+                },
+                "target": {
+                    "description": "20-byte address the account delegates to, 0x-prefixed hex (set_delegation).",
+                    "type": "string",
+                },
+                "slot": {
+                    "description": "32-byte storage slot, 0x-prefixed hex, or a decimal slot index (set_storage, read_storage, assert_storage, canary).",
+                    "type": "string",
+                },
+                "slots": {
+                    "description": "32-byte storage slots to prove, each as slot is written (get_proof).",
+                    "type": "array",
+                    "items": { "type": "string" },
+                },
+                "entries": {
+                    "description": "Slot and value pairs, each written as slot and value are (set_storage_batch).",
+                    "type": "array",
+                    "items": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "minItems": 2,
+                        "maxItems": 2,
+                    },
+                    "maxItems": MAX_INLINE_STORAGE_SLOTS,
+                },
+                "value": {
+                    "description": "Storage value (set_storage, canary) or balance delta (update_balance, transfer), 0x-prefixed hex or decimal.",
+                    "type": "string",
+                },
+                "expected": {
+                    "description": "Storage value the slot must hold, 0x-prefixed hex or decimal (assert_storage).",
+                    "type": "string",
+                },
+                "sign": {
+                    "description": "Whether update_balance adds value to the balance or subtracts it.",
+                    "type": "string",
+                    "enum": ["add", "sub"],
+                },
+                "from": {
+                    "description": "20-byte address the transfer debits, 0x-prefixed hex (transfer).",
+                    "type": "string",
+                },
+                "to": {
+                    "description": "20-byte address the transfer credits, 0x-prefixed hex (transfer).",
+                    "type": "string",
+                },
+                "count": {
+                    "description": "How many times increment_nonce raises the nonce, 1 when omitted, or how many accounts create_accounts creates.",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 1,
+                },
+                "seed": {
+                    "description": "Bytes the create_accounts addresses are derived from, 0x-prefixed hex: account i gets the last 20 bytes of keccak256(seed || i as a big-endian uint64).",
+                    "type": "string",
+                },
+                "storage": {
+                    "description": "Slot-to-value map applied with the account (create_account).",
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "maxProperties": MAX_INLINE_STORAGE_SLOTS,
+                },
+                "approx_gas": {
+                    "description": "Approximate gas of the block's operations, as estimated by the generator and reported with the block (commit_block).",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 0,
+                },
+                "label": {
+                    "description": "Name the checkpoint's metric line is reported under (checkpoint).",
+                    "type": "string",
+                },
+                "ms": {
+                    "description": "Milliseconds to pause for, left out of elapsed_ms and reported as sleep_ms (sleep).",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 0,
+                },
+                "counts": {
+                    "description": "Lines of each operation before the trailer; an operation left out has none (trailer).",
+                    "type": "object",
+                    "additionalProperties": { "type": "integer", "format": "uint64", "minimum": 0 },
+                },
+                "lines": {
+                    "description": "Operation lines before the trailer, blank lines excluded (trailer).",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 0,
+                },
+                "expected_root": {
+                    "description": "32-byte state root the workload must produce, hex with or without 0x, any case (compute_root, header).",
+                    "type": "string",
+                },
+                "version": {
+                    "description": "Protocol version the workload was written for; a harness rejects a newer one (header).",
+                    "type": "integer",
+                    "format": "uint32",
+                    "minimum": 1,
+                },
+                "name": {
+                    "description": "Name of the workload, reported as the result's workload_name (header).",
+                    "type": "string",
+                },
+                "total_ops": {
+                    "description": "Operations after the header, a hint the harness pre-sizes its collections by (header).",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 0,
+                },
+                "expected_leaf_hash": {
+                    "description": "keccak256 of the RLP encoding of value as the storage trie holds it, 32 bytes of hex (canary).",
+                    "type": "string",
+                },
+                "tenant": {
+                    "description": "Tenant the operation belongs to in a multi-tenant workload. Counted per tenant, never applied to state.",
+                    "type": "integer",
+                    "format": "uint32",
+                    "minimum": 0,
+                },
+                "block": {
+                    "description": "Block the operation belongs to in a workload exported from a chain. A line of a later block than the previous line's commits that block first; an earlier one fails the run.",
+                    "type": "integer",
+                    "format": "uint64",
+                    "minimum": 0,
+                },
+            },
+        })
+    }
+}
+
+/// Renders the JSON Schema of a workload line, as printed by
+/// `--print-schema`.
+#[must_use]
+pub fn schema_json() -> String {
+    let schema = schema_for!(Op);
+    serde_json::to_string_pretty(&schema).unwrap_or_default() + "\n"
+}
+
+/// Renders the `--capabilities` output the orchestrator checks a
+/// workload against before starting a run.
+#[must_use]
+pub fn capabilities_json() -> String {
+    serde_json::json!({
+        "protocol_version": PROTOCOL_VERSION,
+        "ops": OP_NAMES,
+        "formats": ["jsonl", "cbor"],
+        "compression": ["gzip", "zstd"],
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "0x095e7baea6a6c7c4c2dfeb977efac326af552d87";
+
+    /// One example of every variant with its canonical encoding.
+    #[allow(clippy::too_many_lines)]
+    fn golden() -> Vec<(Op, &'static str)> {
+        vec![
+            (
+                Op::Header {
+                    version: 1,
+                    name: Some("10m-erc20".to_string()),
+                    total_ops: Some(10_500_000),
+                    expected_root: Some("0xAB".to_string()),
+                },
+                r#"{"op":"header","expected_root":"0xAB","version":1,"name":"10m-erc20","total_ops":10500000}"#,
+            ),
+            (
+                Op::Header {
+                    version: 1,
+                    name: None,
+                    total_ops: None,
+                    expected_root: None,
+                },
+                r#"{"op":"header","version":1}"#,
+            ),
+            (
+                Op::CreateAccount {
+                    address: ADDRESS.to_string(),
+                    balance: "0x0de0b6b3a7640000".to_string(),
+                    nonce: 1,
+                    storage: Vec::new(),
+                },
+                r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            ),
+            (
+                Op::CreateAccount {
+                    address: ADDRESS.to_string(),
+                    balance: "0x0".to_string(),
+                    nonce: 0,
+                    storage: vec![
+                        ("0x01".to_string(), "0x2a".to_string()),
+                        ("0x00".to_string(), "0x01".to_string()),
+                    ],
+                },
+                r#"{"op":"create_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0","nonce":0,"storage":{"0x01":"0x2a","0x00":"0x01"}}"#,
+            ),
+            (
+                Op::CreateAccounts {
+                    seed: "0x5eed".to_string(),
+                    count: 1_000_000,
+                    balance: "0x1".to_string(),
+                    nonce: 0,
+                },
+                r#"{"op":"create_accounts","balance":"0x1","nonce":0,"count":1000000,"seed":"0x5eed"}"#,
+            ),
+            (
+                Op::SetCode {
+                    address: ADDRESS.to_string(),
+                    code: "0x600160005500".to_string(),
+                },
+                r#"{"op":"set_code","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code":"0x600160005500"}"#,
+            ),
+            (
+                Op::DefineCode {
+                    id: "erc20".to_string(),
+                    code: "0x600160005500".to_string(),
+                },
+                r#"{"op":"define_code","code":"0x600160005500","id":"erc20"}"#,
+            ),
+            (
+                Op::SetCodeRef {
+                    address: ADDRESS.to_string(),
+                    code_id: "erc20".to_string(),
+                },
+                r#"{"op":"set_code_ref","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","code_id":"erc20"}"#,
+            ),
+            (
+                Op::SetDelegation {
+                    address: ADDRESS.to_string(),
+                    target: "0x00000000000000000000000000000000000000aa".to_string(),
+                },
+                r#"{"op":"set_delegation","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","target":"0x00000000000000000000000000000000000000aa"}"#,
+            ),
+            (
+                Op::SetStorage {
+                    address: ADDRESS.to_string(),
+                    slot: "0x01".to_string(),
+                    value: "0x2a".to_string(),
+                },
+                r#"{"op":"set_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x01","value":"0x2a"}"#,
+            ),
+            (
+                Op::SetStorageBatch {
+                    address: ADDRESS.to_string(),
+                    entries: vec![
+                        ("0x01".to_string(), "0xff".to_string()),
+                        ("0x02".to_string(), "0x10".to_string()),
+                    ],
+                },
+                r#"{"op":"set_storage_batch","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","entries":[["0x01","0xff"],["0x02","0x10"]]}"#,
+            ),
+            (
+                Op::SetStorageBatch {
+                    address: ADDRESS.to_string(),
+                    entries: Vec::new(),
+                },
+                r#"{"op":"set_storage_batch","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            ),
+            (
+                Op::DeleteAccount {
+                    address: ADDRESS.to_string(),
+                },
+                r#"{"op":"delete_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            ),
+            (
+                Op::Selfdestruct {
+                    address: ADDRESS.to_string(),
+                },
+                r#"{"op":"selfdestruct","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            ),
+            (
+                Op::ClearStorage {
+                    address: ADDRESS.to_string(),
+                },
+                r#"{"op":"clear_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            ),
+            (
+                Op::UpdateBalance {
+                    address: ADDRESS.to_string(),
+                    value: "0x10".to_string(),
+                    sign: Sign::Sub,
+                },
+                r#"{"op":"update_balance","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","value":"0x10","sign":"sub"}"#,
+            ),
+            (
+                Op::Transfer {
+                    from: ADDRESS.to_string(),
+                    to: "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b".to_string(),
+                    value: "0x10".to_string(),
+                },
+                r#"{"op":"transfer","value":"0x10","from":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","to":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b"}"#,
+            ),
+            (
+                Op::IncrementNonce {
+                    address: ADDRESS.to_string(),
+                    count: 3,
+                },
+                r#"{"op":"increment_nonce","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","count":3}"#,
+            ),
+            (
+                Op::TouchAccount {
+                    address: ADDRESS.to_string(),
+                },
+                r#"{"op":"touch_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            ),
+            (
+                Op::ReadAccount {
+                    address: ADDRESS.to_string(),
+                },
+                r#"{"op":"read_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87"}"#,
+            ),
+            (
+                Op::ReadStorage {
+                    address: ADDRESS.to_string(),
+                    slot: "0x01".to_string(),
+                },
+                r#"{"op":"read_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x01"}"#,
+            ),
+            (
+                Op::GetProof {
+                    address: ADDRESS.to_string(),
+                    slots: vec!["0x01".to_string(), "2".to_string()],
+                },
+                r#"{"op":"get_proof","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slots":["0x01","2"]}"#,
+            ),
+            (
+                Op::AssertAccount {
+                    address: ADDRESS.to_string(),
+                    balance: Some("0x0de0b6b3a7640000".to_string()),
+                    nonce: Some(1),
+                },
+                r#"{"op":"assert_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","balance":"0x0de0b6b3a7640000","nonce":1}"#,
+            ),
+            (
+                Op::AssertAccount {
+                    address: ADDRESS.to_string(),
+                    balance: None,
+                    nonce: Some(0),
+                },
+                r#"{"op":"assert_account","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","nonce":0}"#,
+            ),
+            (
+                Op::AssertStorage {
+                    address: ADDRESS.to_string(),
+                    slot: "0x01".to_string(),
+                    expected: "0xff".to_string(),
+                },
+                r#"{"op":"assert_storage","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x01","expected":"0xff"}"#,
+            ),
+            (
+                Op::CommitBlock { approx_gas: None },
+                r#"{"op":"commit_block"}"#,
+            ),
+            (
+                Op::CommitBlock {
+                    approx_gas: Some(30_000_000),
+                },
+                r#"{"op":"commit_block","approx_gas":30000000}"#,
+            ),
+            (
+                Op::Checkpoint {
+                    label: "after-accounts".to_string(),
+                },
+                r#"{"op":"checkpoint","label":"after-accounts"}"#,
+            ),
+            (Op::Sleep { ms: 5000 }, r#"{"op":"sleep","ms":5000}"#),
+            (
+                Op::Trailer {
+                    counts: BTreeMap::from([
+                        ("create_account".to_string(), 2),
+                        ("set_storage".to_string(), 5),
+                    ]),
+                    lines: 7,
+                },
+                r#"{"op":"trailer","counts":{"create_account":2,"set_storage":5},"lines":7}"#,
+            ),
+            (
+                Op::Canary {
+                    address: ADDRESS.to_string(),
+                    slot: "0x01".to_string(),
+                    value: "0x2a".to_string(),
+                    expected_leaf_hash:
+                        "0x04994f67dc55b09e814ab7ffc8df3686b4afb2bb53e60eae97ef043fe03fb829"
+                            .to_string(),
+                },
+                r#"{"op":"canary","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slot":"0x01","value":"0x2a","expected_leaf_hash":"0x04994f67dc55b09e814ab7ffc8df3686b4afb2bb53e60eae97ef043fe03fb829"}"#,
+            ),
+            (
+                Op::ComputeRoot {
+                    expected_root: None,
+                },
+                r#"{"op":"compute_root"}"#,
+            ),
+            (
+                Op::ComputeRoot {
+                    expected_root: Some("0xAB".to_string()),
+                },
+                r#"{"op":"compute_root","expected_root":"0xAB"}"#,
+            ),
+        ]
+    }
+
+    #[test]
+    fn golden_lines_round_trip() {
+        let examples = golden();
+        for name in OP_NAMES {
+            assert!(
+                examples.iter().any(|(op, _)| op.name() == *name),
+                "no golden line for {name}"
+            );
+        }
+        for (op, line) in examples {
+            assert_eq!(serde_json::to_string(&op).ok().as_deref(), Some(line));
+            assert_eq!(serde_json::from_str::<Op>(line).ok(), Some(op));
+        }
+    }
+
+    #[test]
+    fn schema_matches_snapshot() {
+        // Regenerate with `--print-schema` when the format changes on purpose.
+        assert_eq!(
+            schema_json(),
+            include_str!("../schema/operation.schema.json")
+        );
+    }
+
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn schema_covers_every_field() {
+        let line = r#"{"op":"set_storage","address":"a","balance":"b","nonce":1,"code":"c","id":"m","code_id":"n","target":"w","slot":"d","slots":["s"],"entries":[["t","u"]],"value":"e","expected":"v","sign":"i","from":"j","to":"k","count":3,"seed":"o","storage":{"f":"g"},"approx_gas":4,"label":"p","ms":10,"counts":{"l":5},"lines":6,"expected_root":"h","version":7,"name":"q","total_ops":8,"expected_leaf_hash":"r","tenant":2,"block":9}"#;
+        let Ok(line) = serde_json::from_str::<Line>(line) else {
+            panic!("full line must decode");
+        };
+        let Line {
+            op,
+            address,
+            balance,
+            nonce,
+            code,
+            id,
+            code_id,
+            target,
+            slot,
+            slots,
+            entries,
+            value,
+            expected,
+            sign,
+            from,
+            to,
+            count,
+            seed,
+            storage,
+            approx_gas,
+            label,
+            ms,
+            counts,
+            lines,
+            expected_root,
+            version,
+            name,
+            total_ops,
+            expected_leaf_hash,
+            tenant,
+            block,
+        } = line;
+        // Destructured without `..`, so a new field fails to compile here
+        // until the schema below is updated too.
+        let decoded = [
+            op,
+            address,
+            balance,
+            code,
+            id,
+            code_id,
+            target,
+            slot,
+            value,
+            expected,
+            sign,
+            from,
+            to,
+            seed,
+            label,
+            expected_leaf_hash,
+        ];
+        assert!(decoded.iter().all(|field| !field.is_empty()));
+        assert_eq!((nonce, count), (Some(1), Some(3)));
+        assert_eq!(storage.0, [("f".to_string(), "g".to_string())]);
+        assert_eq!(slots, ["s"]);
+        assert_eq!(entries.0, [("t".to_string(), "u".to_string())]);
+        assert_eq!((approx_gas, ms), (Some(4), Some(10)));
+        assert_eq!(counts, Some(BTreeMap::from([("l".to_string(), 5)])));
+        assert_eq!(lines, Some(6));
+        assert_eq!(expected_root.as_deref(), Some("h"));
+        assert_eq!(
+            (version, name.as_deref(), total_ops),
+            (Some(7), Some("q"), Some(8))
+        );
+        assert_eq!((tenant, block), (Some(2), Some(9)));
+
+        let schema = schema_for!(Op);
+        let mut properties = schema
+            .get("properties")
+            .and_then(serde_json::Value::as_object)
+            .map(|props| props.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        properties.sort();
+        assert_eq!(
+            properties,
+            [
+                "address",
+                "approx_gas",
+                "balance",
+                "block",
+                "code",
+                "code_id",
+                "count",
+                "counts",
+                "entries",
+                "expected",
+                "expected_leaf_hash",
+                "expected_root",
+                "from",
+                "id",
+                "label",
+                "lines",
+                "ms",
+                "name",
+                "nonce",
+                "op",
+                "seed",
+                "sign",
+                "slot",
+                "slots",
+                "storage",
+                "target",
+                "tenant",
+                "to",
+                "total_ops",
+                "value",
+                "version"
+            ]
+        );
+    }
+
+    #[test]
+    fn capabilities_list_every_op() {
+        assert_eq!(
+            capabilities_json(),
+            r#"{"compression":["gzip","zstd"],"formats":["jsonl","cbor"],"ops":["header","create_account","create_accounts","set_code","define_code","set_code_ref","set_delegation","set_storage","set_storage_batch","delete_account","selfdestruct","clear_storage","update_balance","transfer","increment_nonce","touch_account","read_account","read_storage","get_proof","assert_account","assert_storage","commit_block","checkpoint","sleep","trailer","canary","compute_root"],"protocol_version":1}"#
+        );
+    }
+
+    #[test]
+    fn entries_keep_the_tenant_tag() {
+        let line = r#"{"op":"compute_root","tenant":3}"#;
+        let entry = serde_json::from_str::<Entry>(line).ok();
+        assert_eq!(
+            entry,
+            Some(Entry {
+                op: Op::ComputeRoot {
+                    expected_root: None
+                },
+                tenant: Some(3),
+                block: None,
+            })
+        );
+        assert_eq!(serde_json::to_string(&entry).ok().as_deref(), Some(line));
+
+        let untagged = serde_json::from_str::<Entry>(r#"{"op":"compute_root"}"#).ok();
+        assert_eq!(untagged.and_then(|entry| entry.tenant), None);
+    }
+
+    #[test]
+    fn decodes_sparse_lines() {
+        let op = serde_json::from_str::<Op>(r#"{"op":"create_account","address":"0x01"}"#).ok();
+        assert_eq!(
+            op,
+            Some(Op::CreateAccount {
+                address: "0x01".to_string(),
+                balance: String::new(),
+                nonce: 0,
+                storage: Vec::new(),
+            })
+        );
+        // A single nonce bump is the default and left out when encoding.
+        let line = r#"{"op":"increment_nonce","address":"0x01"}"#;
+        let op = serde_json::from_str::<Op>(line).ok();
+        assert_eq!(
+            op,
+            Some(Op::IncrementNonce {
+                address: "0x01".to_string(),
+                count: 1,
+            })
+        );
+        assert_eq!(serde_json::to_string(&op).ok().as_deref(), Some(line));
+    }
+
+    #[test]
+    fn rejects_unknown_ops() {
+        let Err(e) = serde_json::from_str::<Op>(r#"{"op":"self_destruct"}"#) else {
+            panic!("unknown op must be rejected");
+        };
+        assert!(e.to_string().contains("unknown operation: self_destruct"));
+    }
+
+    #[test]
+    fn rejects_a_zero_nonce_count() {
+        let line = r#"{"op":"increment_nonce","address":"0x01","count":0}"#;
+        let Err(e) = serde_json::from_str::<Op>(line) else {
+            panic!("count 0 must be rejected");
+        };
+        assert!(e.to_string().contains("count must be positive"));
+        let Err(e) = serde_json::from_str::<Op>(r#"{"op":"create_accounts","seed":"0x01"}"#) else {
+            panic!("create_accounts without count must be rejected");
+        };
+        assert!(e.to_string().contains("create_accounts count is required"));
+    }
+
+    #[test]
+    fn rejects_oversized_inline_storage() {
+        let slots: Vec<String> = (0..=MAX_INLINE_STORAGE_SLOTS)
+            .map(|slot| format!(r#""{slot:x}":"1""#))
+            .collect();
+        let line = format!(
+            r#"{{"op":"create_account","storage":{{{}}}}}"#,
+            slots.join(",")
+        );
+        let Err(e) = serde_json::from_str::<Op>(&line) else {
+            panic!("oversized map must be rejected");
+        };
+        assert!(e.to_string().contains("split the account"));
+    }
+
+    #[test]
+    fn batch_entries_must_be_pairs() {
+        let line = r#"{"op":"set_storage_batch","address":"0x01","entries":[["0x01"]]}"#;
+        assert!(serde_json::from_str::<Op>(line).is_err());
+
+        let entries: Vec<String> = (0..=MAX_INLINE_STORAGE_SLOTS)
+            .map(|slot| format!(r#"["{slot:x}","1"]"#))
+            .collect();
+        let line = format!(
+            r#"{{"op":"set_storage_batch","entries":[{}]}}"#,
+            entries.join(",")
+        );
+        let Err(e) = serde_json::from_str::<Op>(&line) else {
+            panic!("oversized batch must be rejected");
+        };
+        assert!(e.to_string().contains("split it into several lines"));
+    }
+
+    #[test]
+    fn assertions_must_check_something() {
+        let Err(e) = serde_json::from_str::<Op>(r#"{"op":"assert_account","address":"0x01"}"#)
+        else {
+            panic!("assert_account without fields must be rejected");
+        };
+        assert!(e.to_string().contains("a balance or a nonce"));
+        let line = r#"{"op":"assert_storage","address":"0x01","slot":"0x01"}"#;
+        let Err(e) = serde_json::from_str::<Op>(line) else {
+            panic!("assert_storage without expected must be rejected");
+        };
+        assert!(e.to_string().contains("expected is required"));
+    }
+}
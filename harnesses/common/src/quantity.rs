@@ -0,0 +1,133 @@
+//! Workload quantities: balances, values and storage slots, written
+//! either as `0x`-prefixed hex or as plain decimal, such as wei amounts
+//! copied from a CSV. The prefix decides, so `"10"` is ten and `"0x10"`
+//! sixteen, and a string mixing the two is an error rather than a guess.
+
+use std::fmt;
+
+/// Why a string is not a quantity.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QuantityError {
+    input: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for QuantityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid quantity {:?}: {}", self.input, self.reason)
+    }
+}
+
+impl std::error::Error for QuantityError {}
+
+/// Parses a 256-bit quantity into its big-endian bytes. An empty string,
+/// like a bare `0x`, is zero. Hex may have any number of digits up to 64,
+/// odd counts included; decimal must fit in 256 bits.
+///
+/// # Errors
+///
+/// Returns an error naming `s` if it has a digit its base does not
+/// allow, or does not fit in 256 bits.
+pub fn parse(s: &str) -> Result<[u8; 32], QuantityError> {
+    let error = |reason| QuantityError {
+        input: s.to_string(),
+        reason,
+    };
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(digits) => parse_hex(digits).map_err(error),
+        None => parse_decimal(s).map_err(error),
+    }
+}
+
+fn parse_hex(digits: &str) -> Result<[u8; 32], &'static str> {
+    if digits.len() > 64 {
+        return Err("more than 64 hex digits");
+    }
+    let mut bytes = [0u8; 32];
+    // Fill from the least significant digit, two per byte.
+    for (i, digit) in digits.bytes().rev().enumerate() {
+        let nibble = match digit {
+            b'0'..=b'9' => digit - b'0',
+            b'a'..=b'f' => digit - b'a' + 10,
+            b'A'..=b'F' => digit - b'A' + 10,
+            _ => return Err("not a hex digit after 0x"),
+        };
+        bytes[31 - i / 2] |= nibble << (4 * (i % 2));
+    }
+    Ok(bytes)
+}
+
+fn parse_decimal(digits: &str) -> Result<[u8; 32], &'static str> {
+    let mut bytes = [0u8; 32];
+    for digit in digits.bytes() {
+        if !digit.is_ascii_digit() {
+            return Err("not a decimal digit; prefix hex with 0x");
+        }
+        // bytes = bytes * 10 + digit, from the least significant byte.
+        let mut carry = u16::from(digit - b'0');
+        for byte in bytes.iter_mut().rev() {
+            let [low, high] = (u16::from(*byte) * 10 + carry).to_le_bytes();
+            *byte = low;
+            carry = u16::from(high);
+        }
+        if carry != 0 {
+            return Err("exceeds 256 bits");
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn low_u64(bytes: [u8; 32]) -> u64 {
+        bytes[24..]
+            .iter()
+            .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+    }
+
+    #[test]
+    fn reads_hex_and_decimal() {
+        let parsed = |s| parse(s).map(low_u64);
+        assert_eq!(parsed("0x10"), Ok(16));
+        assert_eq!(parsed("0X0de0b6b3a7640000"), Ok(1_000_000_000_000_000_000));
+        assert_eq!(parsed("1000000000000000000"), Ok(1_000_000_000_000_000_000));
+        assert_eq!(parsed("10"), Ok(10));
+        assert_eq!(parsed("0xabc"), Ok(0xabc));
+        assert_eq!(parsed("0x"), Ok(0));
+        assert_eq!(parsed(""), Ok(0));
+        assert_eq!(parsed("007"), Ok(7));
+    }
+
+    #[test]
+    fn reads_the_full_width() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(parse(max), Ok([0xff; 32]));
+        assert_eq!(parse(&format!("0x{}", "f".repeat(64))), Ok([0xff; 32]));
+
+        let Err(e) = parse(&max.replace("935", "936")) else {
+            panic!("2^256 must be rejected");
+        };
+        assert!(e.to_string().contains("exceeds 256 bits"));
+        assert!(parse(&format!("0x1{}", "0".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn rejects_mixed_strings_by_name() {
+        let Err(e) = parse("0x12g") else {
+            panic!("0x12g must be rejected");
+        };
+        assert_eq!(
+            e.to_string(),
+            r#"invalid quantity "0x12g": not a hex digit after 0x"#
+        );
+        let Err(e) = parse("12a3") else {
+            panic!("12a3 must be rejected");
+        };
+        assert!(e.to_string().contains(r#""12a3""#));
+        assert!(e.to_string().contains("prefix hex with 0x"));
+        assert!(parse("-1").is_err());
+        assert!(parse("1e18").is_err());
+    }
+}
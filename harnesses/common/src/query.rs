@@ -0,0 +1,175 @@
+//! Follow-up queries for `--stay-alive`. Once a harness has printed its
+//! result, it keeps reading stdin and answers each line with one JSON
+//! response line until EOF. Only reads are accepted; the state is final
+//! after `compute_root`, so workload operations are rejected.
+
+use std::io::{BufRead, Write};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::input::LineReader;
+use crate::protocol::OP_NAMES;
+
+/// Query operation names, in the order they are documented.
+pub const QUERY_NAMES: &[&str] = &["read_account", "read_storage", "get_proof", "dump_state"];
+
+/// A decoded query line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    /// The account at `address`.
+    ReadAccount { address: String },
+    /// One storage slot of `address`.
+    ReadStorage { address: String, slot: String },
+    /// A Merkle proof of the account and of the given slots.
+    GetProof { address: String, slots: Vec<String> },
+    /// Every account with its storage.
+    DumpState,
+}
+
+impl Query {
+    /// The query's `op` tag, one of [`QUERY_NAMES`].
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ReadAccount { .. } => "read_account",
+            Self::ReadStorage { .. } => "read_storage",
+            Self::GetProof { .. } => "get_proof",
+            Self::DumpState => "dump_state",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct QueryLine {
+    op: String,
+    #[serde(default)]
+    address: String,
+    #[serde(default)]
+    slot: String,
+    #[serde(default)]
+    slots: Vec<String>,
+}
+
+/// Decodes one query line.
+///
+/// # Errors
+///
+/// Returns why the line is not a query: undecodable JSON, a missing
+/// field, a workload operation, or an unknown `op`.
+pub fn parse_query(line: &str) -> Result<Query, String> {
+    let line: QueryLine = serde_json::from_str(line).map_err(|e| format!("decode query: {e}"))?;
+    let address = || {
+        if line.address.is_empty() {
+            Err(format!("{} requires an address", line.op))
+        } else {
+            Ok(line.address.clone())
+        }
+    };
+    match line.op.as_str() {
+        "read_account" => Ok(Query::ReadAccount {
+            address: address()?,
+        }),
+        "read_storage" if line.slot.is_empty() => Err("read_storage requires a slot".to_string()),
+        "read_storage" => Ok(Query::ReadStorage {
+            address: address()?,
+            slot: line.slot.clone(),
+        }),
+        "get_proof" => Ok(Query::GetProof {
+            address: address()?,
+            slots: line.slots.clone(),
+        }),
+        "dump_state" => Ok(Query::DumpState),
+        op if OP_NAMES.contains(&op) => Err(format!(
+            "{op} rejected: the state is final after compute_root"
+        )),
+        op => Err(format!("unknown query {op:?}")),
+    }
+}
+
+/// Answers every query line of `reader` on `out` until EOF. A response
+/// is `{"op":..,"result":..}`, or `{"op":..,"error":..}` when `answer`
+/// fails or the line is not a query (without `op` if it has none).
+///
+/// # Errors
+///
+/// Returns an error only when reading input or writing a response fails.
+pub fn serve(
+    reader: impl BufRead,
+    out: &mut impl Write,
+    mut answer: impl FnMut(&Query) -> Result<Value, String>,
+) -> Result<(), String> {
+    let mut input = LineReader::new(reader);
+    while let Some(line) = input.next_line().map_err(|e| format!("read query: {e}"))? {
+        let response = match parse_query(line) {
+            Ok(query) => match answer(&query) {
+                Ok(result) => serde_json::json!({"op": query.name(), "result": result}),
+                Err(e) => serde_json::json!({"op": query.name(), "error": e}),
+            },
+            Err(e) => serde_json::json!({"error": e}),
+        };
+        writeln!(out, "{response}")
+            .and_then(|()| out.flush())
+            .map_err(|e| format!("write response: {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_queries_and_rejects_mutations() {
+        assert_eq!(
+            parse_query(r#"{"op":"read_storage","address":"0x01","slot":"0x02"}"#),
+            Ok(Query::ReadStorage {
+                address: "0x01".to_string(),
+                slot: "0x02".to_string()
+            })
+        );
+        assert_eq!(
+            parse_query(r#"{"op":"get_proof","address":"0x01"}"#).map(|q| q.name()),
+            Ok("get_proof")
+        );
+        assert_eq!(
+            parse_query(r#"{"op":"set_storage","address":"0x01","slot":"0x02","value":"0x03"}"#),
+            Err("set_storage rejected: the state is final after compute_root".to_string())
+        );
+        assert_eq!(
+            parse_query(r#"{"op":"read_account"}"#),
+            Err("read_account requires an address".to_string())
+        );
+        assert!(parse_query(r#"{"op":"delete_everything"}"#).is_err());
+    }
+
+    #[test]
+    fn answers_one_line_per_query() {
+        let input = concat!(
+            r#"{"op":"read_account","address":"0x01"}"#,
+            "\n\n",
+            r#"{"op":"compute_root"}"#,
+            "\n",
+            r#"{"op":"dump_state"}"#,
+            "\n",
+        );
+        let mut out = Vec::new();
+        let served = serve(input.as_bytes(), &mut out, |query| match query {
+            Query::ReadAccount { address } => Ok(Value::String(address.clone())),
+            _ => Err("unsupported".to_string()),
+        });
+        assert_eq!(served, Ok(()));
+        let lines: Vec<&str> = std::str::from_utf8(&out)
+            .unwrap_or_default()
+            .lines()
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"op":"read_account","result":"0x01"}"#,
+                r#"{"error":"compute_root rejected: the state is final after compute_root"}"#,
+                r#"{"error":"unsupported","op":"dump_state"}"#,
+            ]
+        );
+    }
+}
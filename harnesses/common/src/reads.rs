@@ -0,0 +1,77 @@
+//! `read_account` and `read_storage` operations: lookups against the
+//! committed state, timed apart from the write path. A read sees the
+//! state as of the last `commit_block`, since writes since then are
+//! still buffered in the harness rather than in the client's state
+//! layer. A key that is not there is a miss, never an error.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Running totals of the reads of a workload.
+#[derive(Default)]
+pub struct ReadStats {
+    reads: u64,
+    misses: u64,
+    time: Duration,
+}
+
+/// The result fields of a workload with reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ReadReport {
+    pub reads: u64,
+    pub read_time_ms: u64,
+    /// Reads of an account or slot that does not exist.
+    pub read_misses: u64,
+}
+
+impl ReadStats {
+    /// Times one lookup. `read` reports whether it found the key; its
+    /// errors are passed through uncounted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `read`.
+    pub fn time<E>(&mut self, read: impl FnOnce() -> Result<bool, E>) -> Result<(), E> {
+        let start = Instant::now();
+        let found = read()?;
+        self.time = self.time.saturating_add(start.elapsed());
+        self.reads = self.reads.saturating_add(1);
+        if !found {
+            self.misses = self.misses.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    /// The result fields, or `None` when the workload had no reads.
+    #[must_use]
+    pub fn report(&self) -> Option<ReadReport> {
+        (self.reads > 0).then(|| ReadReport {
+            reads: self.reads,
+            read_time_ms: u64::try_from(self.time.as_millis()).unwrap_or(u64::MAX),
+            read_misses: self.misses,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_misses_and_skips_errors() {
+        let mut stats = ReadStats::default();
+        assert_eq!(stats.report(), None);
+        assert_eq!(stats.time(|| Ok::<_, String>(true)), Ok(()));
+        assert_eq!(stats.time(|| Ok::<_, String>(false)), Ok(()));
+        assert_eq!(
+            stats.time(|| Err("closed".to_string())),
+            Err("closed".to_string())
+        );
+        let json = serde_json::to_value(stats.report()).unwrap_or_default();
+        assert_eq!(
+            json,
+            serde_json::json!({"reads": 2, "read_time_ms": 0, "read_misses": 1})
+        );
+    }
+}
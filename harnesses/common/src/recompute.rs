@@ -0,0 +1,126 @@
+//! `--recompute-root K`: once the run has committed its state, the
+//! harness computes the root again K + 1 times without changing anything,
+//! to separate the cost of the trie work from that of reading the state
+//! it covers. The first computation runs on a freshly opened database,
+//! after dropping the OS page cache when the process may, so it is cold;
+//! the K after it run on the same handle and are warm. Every computation
+//! must return the root of the run. The times are reported one by one, so
+//! an outlier such as a compaction running underneath stays visible.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::coderead;
+
+/// The `recompute_*` fields of a result.
+#[derive(Debug, Serialize)]
+pub struct RecomputeReport {
+    /// Time of each computation, the cold one first.
+    pub recompute_times_ms: Vec<u64>,
+    /// The cold time over the median warm one, or `None` when the warm
+    /// computations took no measurable time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recompute_cold_warm_ratio: Option<f64>,
+    /// Why the page cache was kept before the cold computation, which
+    /// then only starts with the client's own caches empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recompute_page_cache_kept: Option<String>,
+}
+
+/// Drops the page cache, then times `open`, which opens the database and
+/// computes the cold root, and `passes` more calls of `recompute` on what
+/// it opened. Each returns the root as `hexfmt::fixed` formats it.
+///
+/// # Errors
+///
+/// Returns the first computation whose root is not `expected`.
+pub fn measure<T>(
+    passes: u64,
+    expected: &str,
+    open: impl FnOnce() -> (T, String),
+    mut recompute: impl FnMut(&T) -> String,
+) -> Result<RecomputeReport, String> {
+    let page_cache_kept = coderead::drop_page_cache().err();
+    let mut times = Vec::new();
+    let start = Instant::now();
+    let (db, root) = open();
+    times.push(start.elapsed());
+    check(0, &root, expected)?;
+    for pass in 1..=passes {
+        let start = Instant::now();
+        let root = recompute(&db);
+        times.push(start.elapsed());
+        check(pass, &root, expected)?;
+    }
+    Ok(RecomputeReport {
+        recompute_times_ms: times
+            .iter()
+            .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+            .collect(),
+        recompute_cold_warm_ratio: cold_warm_ratio(&times),
+        recompute_page_cache_kept: page_cache_kept,
+    })
+}
+
+fn check(pass: u64, root: &str, expected: &str) -> Result<(), String> {
+    if root == expected {
+        return Ok(());
+    }
+    Err(format!(
+        "recomputation {pass} returned root {root}, not the run's {expected}"
+    ))
+}
+
+/// The first time over the median of the rest, the lower one of the two
+/// middle times when their number is even.
+fn cold_warm_ratio(times: &[Duration]) -> Option<f64> {
+    let (cold, warm) = times.split_first()?;
+    let mut warm = warm.to_vec();
+    warm.sort_unstable();
+    let median = warm.get(warm.len().saturating_sub(1) / 2)?;
+    (!median.is_zero()).then(|| cold.as_secs_f64() / median.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_computation_must_return_the_root() {
+        let report = measure(
+            3,
+            "0xaa",
+            || ((), "0xaa".to_string()),
+            |()| "0xaa".to_string(),
+        );
+        let Ok(report) = report else {
+            panic!("identical roots were refused");
+        };
+        assert_eq!(report.recompute_times_ms.len(), 4);
+
+        let mut roots = ["0xaa", "0xaa", "0xbb"].into_iter();
+        let err = measure(
+            3,
+            "0xaa",
+            || ((), "0xaa".to_string()),
+            |()| roots.next().unwrap_or_default().to_string(),
+        );
+        let Err(err) = err else {
+            panic!("a diverging root was accepted");
+        };
+        assert_eq!(
+            err,
+            "recomputation 3 returned root 0xbb, not the run's 0xaa"
+        );
+    }
+
+    #[test]
+    fn ratio_is_over_the_median_warm_time() {
+        let ms = Duration::from_millis;
+        assert_eq!(cold_warm_ratio(&[ms(40), ms(10), ms(90), ms(5)]), Some(4.0));
+        assert_eq!(cold_warm_ratio(&[ms(40), ms(10), ms(20)]), Some(4.0));
+        assert_eq!(cold_warm_ratio(&[ms(40), Duration::ZERO]), None);
+        assert_eq!(cold_warm_ratio(&[ms(40)]), None);
+    }
+}
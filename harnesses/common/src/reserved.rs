@@ -0,0 +1,140 @@
+//! Reserved addresses: the zero address, the precompiles `0x01` to
+//! `0x0a` and the all-ones address. The state trie stores them like any
+//! other account, which the `reserved_addresses` fixtures pin down, but a
+//! generated workload that touches them has often mixed up an index and
+//! an address. Harnesses count every touch and, with
+//! `--reject-reserved-addresses`, refuse the first one.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::protocol::Op;
+
+/// The last precompile of the Cancun fork.
+const LAST_PRECOMPILE: u8 = 0x0a;
+
+/// The kind of a reserved address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reserved {
+    Zero,
+    Precompile,
+    AllOnes,
+}
+
+impl Reserved {
+    /// Classifies a workload address, `0x`-prefixed or not and in either
+    /// case, by its value: `0x01` is the first precompile however many
+    /// zeros pad it. A malformed address is not reserved; the harness
+    /// rejects it when it decodes the operation.
+    #[must_use]
+    pub fn of(address: &str) -> Option<Self> {
+        let digits = address
+            .strip_prefix("0x")
+            .or_else(|| address.strip_prefix("0X"))
+            .unwrap_or(address);
+        if digits.len() > 40 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let significant = digits.trim_start_matches('0');
+        if significant.is_empty() {
+            return Some(Self::Zero);
+        }
+        if significant.len() == 40 && significant.bytes().all(|b| b.eq_ignore_ascii_case(&b'f')) {
+            return Some(Self::AllOnes);
+        }
+        u8::from_str_radix(significant, 16)
+            .is_ok_and(|n| n <= LAST_PRECOMPILE)
+            .then_some(Self::Precompile)
+    }
+}
+
+impl fmt::Display for Reserved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Zero => "the zero address",
+            Self::Precompile => "a precompile address",
+            Self::AllOnes => "the all-ones address",
+        })
+    }
+}
+
+/// Running counts of the reserved addresses a workload touches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ReservedTouches {
+    pub zero_address_touches: u64,
+    pub precompile_touches: u64,
+    pub all_ones_address_touches: u64,
+}
+
+impl ReservedTouches {
+    /// Counts each reserved address `op` touches; a `transfer` between
+    /// two of them counts twice. Returns the kind of the first.
+    pub fn record(&mut self, op: &Op) -> Option<Reserved> {
+        let mut first = None;
+        for kind in op.addresses().filter_map(Reserved::of) {
+            let count = match kind {
+                Reserved::Zero => &mut self.zero_address_touches,
+                Reserved::Precompile => &mut self.precompile_touches,
+                Reserved::AllOnes => &mut self.all_ones_address_touches,
+            };
+            *count = count.saturating_add(1);
+            first = first.or(Some(kind));
+        }
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::FIXTURES;
+    use crate::protocol::Entry;
+
+    #[test]
+    fn classifies_reserved_addresses() {
+        let zero = format!("0x{}", "0".repeat(40));
+        let all_ones = format!("0x{}", "F".repeat(40));
+        assert_eq!(Reserved::of(&zero), Some(Reserved::Zero));
+        assert_eq!(Reserved::of("0x"), Some(Reserved::Zero));
+        assert_eq!(Reserved::of("0x1"), Some(Reserved::Precompile));
+        assert_eq!(Reserved::of("0X0A"), Some(Reserved::Precompile));
+        assert_eq!(Reserved::of(&all_ones), Some(Reserved::AllOnes));
+        assert_eq!(Reserved::of("0x0b"), None);
+        assert_eq!(Reserved::of("0x100"), None);
+        assert_eq!(Reserved::of(&all_ones[..41]), None);
+        assert_eq!(Reserved::of("0xzz"), None);
+        assert_eq!(Reserved::of(&format!("{zero}0")), None);
+    }
+
+    #[test]
+    fn counts_the_touches_of_the_fixture() {
+        let workload = FIXTURES
+            .iter()
+            .find(|fixture| fixture.name == "reserved_addresses")
+            .map_or("", |fixture| fixture.workload);
+        let mut touches = ReservedTouches::default();
+        for line in workload.lines() {
+            if let Ok(Entry { op, .. }) = serde_json::from_str::<Entry>(line) {
+                touches.record(&op);
+            }
+        }
+        assert_eq!(
+            touches,
+            ReservedTouches {
+                zero_address_touches: 2,
+                precompile_touches: 5,
+                all_ones_address_touches: 2,
+            }
+        );
+
+        let transfer = Op::Transfer {
+            from: "0x0b".to_string(),
+            to: "0x00".to_string(),
+            value: "0x1".to_string(),
+        };
+        let mut touches = ReservedTouches::default();
+        assert_eq!(touches.record(&transfer), Some(Reserved::Zero));
+        assert_eq!(touches.zero_address_touches, 1);
+    }
+}
@@ -0,0 +1,38 @@
+//! `expected_root` on `compute_root`: a workload may carry the root it
+//! must produce. The harness then reports `root_match` and, after
+//! printing its result, exits with [`EXIT_ROOT_MISMATCH`] if the roots
+//! differ, so a CI script can catch a regression without parsing output.
+
+/// Exit code of a run whose root differs from the workload's
+/// `expected_root`. Its result line is complete.
+pub const EXIT_ROOT_MISMATCH: i32 = 3;
+
+/// Reports whether `root` is `expected`, ignoring the case of the hex
+/// digits and whether either has a `0x` prefix.
+#[must_use]
+pub fn matches(expected: &str, root: &str) -> bool {
+    let digits = |s: &str| {
+        let s = s.trim();
+        s.strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s)
+            .to_ascii_lowercase()
+    };
+    digits(expected) == digits(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_case_and_prefix() {
+        let root = "0x8e069d709f50d49f676831450d29026d534cff6f7598997b71ae6f35e8185475";
+        assert!(matches(root, root));
+        assert!(matches(&root.to_uppercase(), root));
+        assert!(matches(&root[2..], root));
+        assert!(matches(&format!("0X{}", &root[2..].to_uppercase()), root));
+        assert!(!matches(&root[..65], root));
+        assert!(!matches("", root));
+    }
+}
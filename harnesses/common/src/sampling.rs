@@ -0,0 +1,107 @@
+//! Sampled timing of workload decoding. Timing every operation would
+//! cost more than some of the decoding it measures, so only every
+//! [`SAMPLE_EVERY`]th operation is timed and the totals are extrapolated.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// One operation in this many is timed.
+pub const SAMPLE_EVERY: u64 = 64;
+
+/// Splits decode time between JSON parsing of a line and the hex
+/// conversion of its field values.
+#[derive(Default)]
+pub struct DecodeSampler {
+    ops: u64,
+    sampled: u64,
+    active: bool,
+    json: Duration,
+    hex: Duration,
+}
+
+/// Extrapolated decode times, reported in the harness result.
+#[derive(Serialize)]
+pub struct DecodeEstimates {
+    pub json_decode_ms_est: u64,
+    pub hex_decode_ms_est: u64,
+    /// The sampling factor behind both estimates.
+    pub decode_sample_every: u64,
+}
+
+impl DecodeSampler {
+    /// Runs the JSON decode of one line. This starts a new operation and
+    /// decides whether it is sampled.
+    pub fn json<T>(&mut self, decode: impl FnOnce() -> T) -> T {
+        self.active = self.ops.is_multiple_of(SAMPLE_EVERY);
+        self.ops = self.ops.saturating_add(1);
+        if !self.active {
+            return decode();
+        }
+        self.sampled = self.sampled.saturating_add(1);
+        let start = Instant::now();
+        let value = decode();
+        self.json = self.json.saturating_add(start.elapsed());
+        value
+    }
+
+    /// Runs one hex conversion of the current operation's fields.
+    pub fn hex<T>(&mut self, parse: impl FnOnce() -> T) -> T {
+        if !self.active {
+            return parse();
+        }
+        let start = Instant::now();
+        let value = parse();
+        self.hex = self.hex.saturating_add(start.elapsed());
+        value
+    }
+
+    /// Scales the sampled times up to all operations seen.
+    #[must_use]
+    pub fn estimates(&self) -> DecodeEstimates {
+        let scale = |sampled: Duration| {
+            let ms = sampled.as_secs_f64() * 1000.0;
+            #[allow(clippy::cast_precision_loss)]
+            let factor = self.ops as f64 / self.sampled.max(1) as f64;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let estimate = (ms * factor).round() as u64;
+            estimate
+        };
+        DecodeEstimates {
+            json_decode_ms_est: scale(self.json),
+            hex_decode_ms_est: scale(self.hex),
+            decode_sample_every: SAMPLE_EVERY,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_one_op_in_sample_every() {
+        let mut sampler = DecodeSampler::default();
+        let mut hex_calls = 0;
+        for _ in 0..SAMPLE_EVERY * 3 {
+            sampler.json(|| ());
+            sampler.hex(|| hex_calls += 1);
+        }
+        // Unsampled closures still run; only the timing is skipped.
+        assert_eq!(hex_calls, SAMPLE_EVERY * 3);
+        assert_eq!(sampler.sampled, 3);
+
+        sampler.json = Duration::from_millis(2);
+        sampler.hex = Duration::from_millis(1);
+        let estimates = sampler.estimates();
+        assert_eq!(estimates.json_decode_ms_est, 2 * SAMPLE_EVERY);
+        assert_eq!(estimates.hex_decode_ms_est, SAMPLE_EVERY);
+    }
+
+    #[test]
+    fn empty_input_estimates_zero() {
+        let estimates = DecodeSampler::default().estimates();
+        assert_eq!(estimates.json_decode_ms_est, 0);
+        assert_eq!(estimates.decode_sample_every, SAMPLE_EVERY);
+    }
+}
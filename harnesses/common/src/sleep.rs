@@ -0,0 +1,56 @@
+//! `sleep` lines. A workload can pause between phases, say between its
+//! accounts and its storage, so a laptop cools down and does not throttle
+//! the phase after. The pause changes no state and is timed by neither
+//! the trie nor the DB writes; the harness subtracts it from `elapsed_ms`
+//! and reports it as `sleep_ms`.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The time a run spent in `sleep` lines.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sleeps {
+    slept: Duration,
+}
+
+impl Sleeps {
+    /// Sleeps for `ms` milliseconds and returns the time actually slept,
+    /// which is also added to the total.
+    pub fn sleep(&mut self, ms: u64) -> Duration {
+        let start = Instant::now();
+        thread::sleep(Duration::from_millis(ms));
+        let slept = start.elapsed();
+        self.slept = self.slept.saturating_add(slept);
+        slept
+    }
+
+    /// The time since `start`, less the time slept.
+    #[must_use]
+    pub fn elapsed_since(&self, start: Instant) -> Duration {
+        start.elapsed().saturating_sub(self.slept)
+    }
+
+    /// The `sleep_ms` of the result, or `None` for a run without `sleep`
+    /// lines.
+    #[must_use]
+    pub fn sleep_ms(&self) -> Option<u64> {
+        (!self.slept.is_zero()).then(|| u64::try_from(self.slept.as_millis()).unwrap_or(u64::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slept_time_is_left_out_of_elapsed() {
+        let start = Instant::now();
+        let mut sleeps = Sleeps::default();
+        assert_eq!(sleeps.sleep_ms(), None);
+
+        let slept = sleeps.sleep(20);
+        assert!(slept >= Duration::from_millis(20));
+        assert!(sleeps.sleep_ms().is_some_and(|ms| ms >= 20));
+        assert!(sleeps.elapsed_since(start) <= start.elapsed().saturating_sub(slept));
+    }
+}
@@ -0,0 +1,296 @@
+//! On-demand status dumps. SIGUSR2 makes a running harness print one
+//! JSON line to stderr saying how far it has got, without stopping it.
+//!
+//! The read loop alternates between waiting for input and applying it,
+//! so the time spent on each gives two rates: how fast the workload
+//! source delivered operations and how fast the harness consumed them.
+
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::sysmetrics::SystemMetrics;
+
+/// How often the reporter thread checks for a pending request.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set by the signal handler, cleared by the reporter thread.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Which part of the run the harness is in.
+#[derive(Clone, Copy)]
+#[repr(u8)]
+pub enum Phase {
+    Reading = 0,
+    Trie = 1,
+    DbWrite = 2,
+    Done = 3,
+}
+
+impl Phase {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Trie,
+            2 => Self::DbWrite,
+            3 => Self::Done,
+            _ => Self::Reading,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Reading => "reading",
+            Self::Trie => "trie",
+            Self::DbWrite => "db_write",
+            Self::Done => "done",
+        }
+    }
+}
+
+/// Sizes of the structures a harness accumulates before committing.
+#[derive(Clone, Copy, Default)]
+pub struct Pending {
+    pub accounts: u64,
+    pub slots: u64,
+    pub code: u64,
+    /// Approximate memory held by the pending structures.
+    pub bytes: u64,
+}
+
+/// Run progress shared between the main thread and the reporter. The
+/// main thread only does relaxed atomic stores, so updating it per
+/// operation is cheap.
+#[derive(Default)]
+pub struct Progress {
+    ops_read: AtomicU64,
+    ops_applied: AtomicU64,
+    pending_accounts: AtomicU64,
+    pending_slots: AtomicU64,
+    pending_code: AtomicU64,
+    pending_bytes: AtomicU64,
+    read_nanos: AtomicU64,
+    apply_nanos: AtomicU64,
+    phase: AtomicU8,
+}
+
+/// Operations per second of time spent reading input and of time spent
+/// decoding and applying it. Whichever is lower limited the read loop.
+#[derive(Clone, Copy, Serialize)]
+pub struct OpRates {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_consumption_ops_per_sec: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apply_ops_per_sec: Option<u64>,
+}
+
+impl Progress {
+    /// Counts a workload line read from the input after waiting
+    /// `waited` for it.
+    pub fn read_op(&self, waited: Duration) {
+        bump(&self.ops_read, 1);
+        bump(&self.read_nanos, nanos(waited));
+    }
+
+    /// Counts an operation applied to the pending state, which took
+    /// `took` including its decoding.
+    pub fn applied_op(&self, took: Duration) {
+        bump(&self.ops_applied, 1);
+        bump(&self.apply_nanos, nanos(took));
+    }
+
+    /// Rates over the operations counted so far.
+    #[must_use]
+    pub fn rates(&self) -> OpRates {
+        let rate = |ops: &AtomicU64, nanos: &AtomicU64| {
+            let nanos = nanos.load(Ordering::Relaxed);
+            #[allow(
+                clippy::cast_precision_loss,
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss
+            )]
+            let rate = (nanos > 0)
+                .then(|| (ops.load(Ordering::Relaxed) as f64 * 1e9 / nanos as f64).round() as u64);
+            rate
+        };
+        OpRates {
+            input_consumption_ops_per_sec: rate(&self.ops_read, &self.read_nanos),
+            apply_ops_per_sec: rate(&self.ops_applied, &self.apply_nanos),
+        }
+    }
+
+    /// Records the current size of the pending structures.
+    pub fn set_pending(&self, pending: Pending) {
+        self.pending_accounts
+            .store(pending.accounts, Ordering::Relaxed);
+        self.pending_slots.store(pending.slots, Ordering::Relaxed);
+        self.pending_code.store(pending.code, Ordering::Relaxed);
+        self.pending_bytes.store(pending.bytes, Ordering::Relaxed);
+    }
+
+    /// Records the phase the run has entered.
+    pub fn set_phase(&self, phase: Phase) {
+        self.phase.store(phase as u8, Ordering::Relaxed);
+    }
+
+    fn status_line(&self) -> StatusLine {
+        StatusLine {
+            event: "status",
+            phase: Phase::from_u8(self.phase.load(Ordering::Relaxed)).as_str(),
+            ops_read: self.ops_read.load(Ordering::Relaxed),
+            ops_applied: self.ops_applied.load(Ordering::Relaxed),
+            pending_accounts: self.pending_accounts.load(Ordering::Relaxed),
+            pending_slots: self.pending_slots.load(Ordering::Relaxed),
+            pending_code: self.pending_code.load(Ordering::Relaxed),
+            pending_bytes: self.pending_bytes.load(Ordering::Relaxed),
+            rates: self.rates(),
+            rss_bytes: SystemMetrics::capture().vm_rss_bytes,
+        }
+    }
+}
+
+/// Adds `by` to `counter`, stopping at `u64::MAX` rather than wrapping.
+/// The main thread is the only writer, so a load and a store lose no
+/// update.
+fn bump(counter: &AtomicU64, by: u64) {
+    let value = counter.load(Ordering::Relaxed).saturating_add(by);
+    counter.store(value, Ordering::Relaxed);
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[derive(Serialize)]
+struct StatusLine {
+    event: &'static str,
+    phase: &'static str,
+    ops_read: u64,
+    ops_applied: u64,
+    pending_accounts: u64,
+    pending_slots: u64,
+    pending_code: u64,
+    pending_bytes: u64,
+    #[serde(flatten)]
+    rates: OpRates,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rss_bytes: Option<u64>,
+}
+
+/// Installs the SIGUSR2 handler and starts a thread that writes a status
+/// line to `out` each time the signal arrives. The thread runs until the
+/// process exits.
+///
+/// # Errors
+///
+/// Returns an error if the reporter thread cannot be spawned.
+pub fn spawn_reporter<W: Write + Send + 'static>(
+    progress: Arc<Progress>,
+    mut out: W,
+) -> io::Result<()> {
+    install_handler();
+    std::thread::Builder::new()
+        .name("status".to_string())
+        .spawn(move || {
+            loop {
+                std::thread::sleep(POLL_INTERVAL);
+                if !REQUESTED.swap(false, Ordering::Relaxed) {
+                    continue;
+                }
+                if let Ok(mut line) = serde_json::to_vec(&progress.status_line()) {
+                    line.push(b'\n');
+                    // Reporting is best effort; a closed stderr must not
+                    // disturb the run.
+                    let _ = out.write_all(&line).and_then(|()| out.flush());
+                }
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn install_handler() {
+    extern "C" fn request_status(_: libc::c_int) {
+        REQUESTED.store(true, Ordering::Relaxed);
+    }
+
+    // SAFETY: the handler only performs an atomic store, which is
+    // async-signal-safe.
+    unsafe {
+        libc::signal(
+            libc::SIGUSR2,
+            request_status as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn install_handler() {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if let Ok(mut inner) = self.0.lock() {
+                inner.extend_from_slice(buf);
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn signal_dumps_parseable_status() {
+        let progress = Arc::new(Progress::default());
+        progress.read_op(Duration::from_millis(1));
+        progress.read_op(Duration::from_millis(3));
+        progress.applied_op(Duration::from_millis(1));
+        progress.set_pending(Pending {
+            accounts: 1,
+            slots: 0,
+            code: 0,
+            bytes: 64,
+        });
+        progress.set_phase(Phase::DbWrite);
+
+        let out = SharedBuf::default();
+        assert!(spawn_reporter(Arc::clone(&progress), out.clone()).is_ok());
+        // SAFETY: raising a signal whose handler was just installed.
+        unsafe {
+            libc::raise(libc::SIGUSR2);
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let line = loop {
+            let text = out
+                .0
+                .lock()
+                .map(|buf| String::from_utf8_lossy(&buf).into_owned())
+                .unwrap_or_default();
+            if text.ends_with('\n') || Instant::now() > deadline {
+                break text;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        let status: serde_json::Value = serde_json::from_str(&line).unwrap_or_default();
+        assert_eq!(status["ops_read"], 2);
+        assert_eq!(status["ops_applied"], 1);
+        assert_eq!(status["phase"], "db_write");
+        assert_eq!(status["pending_bytes"], 64);
+        assert_eq!(status["input_consumption_ops_per_sec"], 500);
+        assert_eq!(status["apply_ops_per_sec"], 1000);
+    }
+}
@@ -0,0 +1,271 @@
+//! Process and system metrics read from procfs and cgroupfs.
+//!
+//! Every figure is optional: on platforms without these files, or when a
+//! file is missing or unreadable, the field stays `None` and is left out
+//! of the serialized result. The parsers take file contents so they can
+//! be tested against canned input on any platform.
+
+use serde::Serialize;
+
+/// A snapshot of process metrics at one point of a run.
+#[derive(Clone, Default, Serialize)]
+pub struct SystemMetrics {
+    /// Peak virtual memory size (`VmPeak`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_peak_bytes: Option<u64>,
+    /// Peak resident set size (`VmHWM`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_hwm_bytes: Option<u64>,
+    /// Current resident set size (`VmRSS`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vm_rss_bytes: Option<u64>,
+    /// Current number of threads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads: Option<u64>,
+    /// Bytes this process caused to be fetched from storage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_read_bytes: Option<u64>,
+    /// Bytes this process caused to be sent to storage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_write_bytes: Option<u64>,
+    /// Peak memory usage of the process's cgroup (cgroup v2 `memory.peak`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup_memory_peak_bytes: Option<u64>,
+}
+
+impl SystemMetrics {
+    /// Captures whatever metrics this platform provides.
+    #[must_use]
+    pub fn capture() -> Self {
+        capture_impl()
+    }
+
+    fn from_procfs(status: Option<&str>, io: Option<&str>, cgroup_peak: Option<&str>) -> Self {
+        Self {
+            vm_peak_bytes: status
+                .and_then(|s| status_kb(s, "VmPeak:"))
+                .map(|kb| kb * 1024),
+            vm_hwm_bytes: status
+                .and_then(|s| status_kb(s, "VmHWM:"))
+                .map(|kb| kb * 1024),
+            vm_rss_bytes: status
+                .and_then(|s| status_kb(s, "VmRSS:"))
+                .map(|kb| kb * 1024),
+            threads: status.and_then(|s| status_kb(s, "Threads:")),
+            io_read_bytes: io.and_then(|s| io_field(s, "read_bytes:")),
+            io_write_bytes: io.and_then(|s| io_field(s, "write_bytes:")),
+            cgroup_memory_peak_bytes: cgroup_peak.and_then(|s| s.trim().parse().ok()),
+        }
+    }
+}
+
+/// Machine settings and counters that skew timings between otherwise
+/// identical hosts.
+#[derive(Clone, Default, Serialize)]
+pub struct EnvSnapshot {
+    /// Active transparent huge page mode, e.g. `always` or `madvise`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thp_enabled: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swap_total_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swap_used_bytes: Option<u64>,
+    /// Major page faults of this process so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub major_faults: Option<u64>,
+}
+
+impl EnvSnapshot {
+    /// Captures the current settings and counters.
+    #[must_use]
+    pub fn capture() -> Self {
+        let read = |path: &str| std::fs::read_to_string(path).ok();
+        let meminfo = read("/proc/meminfo");
+        let mut snapshot = Self::from_procfs(
+            read("/sys/kernel/mm/transparent_hugepage/enabled").as_deref(),
+            meminfo.as_deref(),
+        );
+        snapshot.major_faults = major_faults();
+        snapshot
+    }
+
+    fn from_procfs(thp: Option<&str>, meminfo: Option<&str>) -> Self {
+        let swap_total = meminfo.and_then(|m| status_kb(m, "SwapTotal:"));
+        let swap_free = meminfo.and_then(|m| status_kb(m, "SwapFree:"));
+        Self {
+            thp_enabled: thp.and_then(thp_mode),
+            swap_total_bytes: swap_total.map(|kb| kb * 1024),
+            swap_used_bytes: swap_total
+                .zip(swap_free)
+                .map(|(total, free)| total.saturating_sub(free) * 1024),
+            major_faults: None,
+        }
+    }
+}
+
+/// Environment at the start and end of a run.
+#[derive(Serialize)]
+pub struct Environment {
+    pub start: EnvSnapshot,
+    pub end: EnvSnapshot,
+}
+
+impl Environment {
+    /// Warns when swap usage grew during the run, which distorts memory
+    /// and timing comparisons.
+    #[must_use]
+    pub fn swap_warning(&self) -> Option<String> {
+        let (Some(before), Some(after)) = (self.start.swap_used_bytes, self.end.swap_used_bytes)
+        else {
+            return None;
+        };
+        (after > before).then(|| {
+            format!(
+                "swap usage grew by {} bytes during the run; memory and timing figures may be skewed",
+                after - before
+            )
+        })
+    }
+}
+
+/// Extracts the bracketed active mode from a THP sysfs setting such as
+/// `always [madvise] never`.
+fn thp_mode(setting: &str) -> Option<String> {
+    let start = setting.find('[')? + 1;
+    let end = start + setting[start..].find(']')?;
+    Some(setting[start..end].to_string())
+}
+
+#[cfg(unix)]
+fn major_faults() -> Option<u64> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `usage` is a properly sized, writable rusage struct.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, &raw mut usage) } != 0 {
+        return None;
+    }
+    u64::try_from(usage.ru_majflt).ok()
+}
+
+#[cfg(not(unix))]
+fn major_faults() -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn capture_impl() -> SystemMetrics {
+    let read = |path: &str| std::fs::read_to_string(path).ok();
+    let status = read("/proc/self/status");
+    let io = read("/proc/self/io");
+    let cgroup_peak = read("/proc/self/cgroup")
+        .as_deref()
+        .and_then(cgroup_v2_path)
+        .and_then(|path| read(&format!("/sys/fs/cgroup{path}/memory.peak")));
+    SystemMetrics::from_procfs(status.as_deref(), io.as_deref(), cgroup_peak.as_deref())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn capture_impl() -> SystemMetrics {
+    SystemMetrics::default()
+}
+
+/// Reads the CPU time (utime + stime) from a `/proc/.../stat` file, in
+/// milliseconds.
+pub(crate) fn stat_cpu_ms(path: &str) -> Option<u64> {
+    let ticks = stat_cpu_ticks(&std::fs::read_to_string(path).ok()?)?;
+    Some(ticks * 1000 / clock_ticks_per_sec()?)
+}
+
+/// Parses utime + stime, in clock ticks, from a stat line. The command
+/// name may contain spaces, so fields are counted from the closing
+/// parenthesis; utime and stime are fields 14 and 15.
+fn stat_cpu_ticks(stat: &str) -> Option<u64> {
+    let mut fields = stat.get(stat.rfind(')')? + 2..)?.split(' ').skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(unix)]
+fn clock_ticks_per_sec() -> Option<u64> {
+    // SAFETY: sysconf has no preconditions.
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    u64::try_from(ticks).ok().filter(|&t| t > 0)
+}
+
+#[cfg(not(unix))]
+fn clock_ticks_per_sec() -> Option<u64> {
+    None
+}
+
+/// Reads a `Key:   value [kB]` line from `/proc/self/status`.
+fn status_kb(status: &str, key: &str) -> Option<u64> {
+    status.lines().find_map(|line| {
+        let rest = line.strip_prefix(key)?;
+        rest.trim().trim_end_matches("kB").trim().parse().ok()
+    })
+}
+
+/// Reads a `key: value` line from `/proc/self/io`.
+fn io_field(io: &str, key: &str) -> Option<u64> {
+    io.lines()
+        .find_map(|line| line.strip_prefix(key)?.trim().parse().ok())
+}
+
+/// Extracts the unified (v2) hierarchy path from `/proc/self/cgroup`.
+fn cgroup_v2_path(cgroup: &str) -> Option<&str> {
+    cgroup.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "Name:\treth-harness\nVmPeak:\t  204800 kB\nVmHWM:\t   51200 kB\nVmRSS:\t   40960 kB\nThreads:\t7\n";
+    const IO: &str =
+        "rchar: 100\nwchar: 200\nread_bytes: 4096\nwrite_bytes: 8192\ncancelled_write_bytes: 0\n";
+
+    #[test]
+    fn parses_canned_procfs() {
+        let metrics = SystemMetrics::from_procfs(Some(STATUS), Some(IO), Some("1048576\n"));
+        assert_eq!(metrics.vm_peak_bytes, Some(204_800 * 1024));
+        assert_eq!(metrics.vm_hwm_bytes, Some(51_200 * 1024));
+        assert_eq!(metrics.vm_rss_bytes, Some(40_960 * 1024));
+        assert_eq!(metrics.threads, Some(7));
+        assert_eq!(metrics.io_read_bytes, Some(4096));
+        assert_eq!(metrics.io_write_bytes, Some(8192));
+        assert_eq!(metrics.cgroup_memory_peak_bytes, Some(1_048_576));
+    }
+
+    #[test]
+    fn missing_files_leave_fields_empty() {
+        let metrics = SystemMetrics::from_procfs(None, Some("garbage"), None);
+        assert!(metrics.vm_peak_bytes.is_none() && metrics.io_read_bytes.is_none());
+        assert_eq!(serde_json::to_string(&metrics).unwrap_or_default(), "{}");
+    }
+
+    #[test]
+    fn parses_environment_and_warns_on_swap_growth() {
+        let meminfo = "MemTotal: 1000 kB\nSwapTotal:  2048 kB\nSwapFree:   1024 kB\n";
+        let start = EnvSnapshot::from_procfs(Some("always [madvise] never\n"), Some(meminfo));
+        assert_eq!(start.thp_enabled.as_deref(), Some("madvise"));
+        assert_eq!(start.swap_total_bytes, Some(2048 * 1024));
+        assert_eq!(start.swap_used_bytes, Some(1024 * 1024));
+
+        let end = EnvSnapshot {
+            swap_used_bytes: Some(1024 * 1024 + 4096),
+            ..start.clone()
+        };
+        let env = Environment { start, end };
+        assert!(env.swap_warning().is_some_and(|w| w.contains("4096 bytes")));
+    }
+
+    #[test]
+    fn parses_stat_and_cgroup_lines() {
+        let stat = "42 (tokio runtime) S 1 42 42 0 -1 4194560 100 0 0 0 250 50 0 0 20 0 7 0";
+        assert_eq!(stat_cpu_ticks(stat), Some(300));
+        assert_eq!(
+            cgroup_v2_path("12:cpu:/legacy\n0::/user.slice/bench.scope\n"),
+            Some("/user.slice/bench.scope")
+        );
+    }
+}
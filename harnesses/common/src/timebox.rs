@@ -0,0 +1,93 @@
+//! `--duration-secs`: a time-boxed run applies operations until a
+//! deadline, then computes the root of the prefix it consumed, as a
+//! `--max-ops` run does. The deadline is checked after each applied
+//! operation and never during one, so harnesses fed the same workload
+//! consume prefixes of it that differ only in length, and are compared
+//! by how many operations they completed.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// The deadline of a time-boxed run.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeBox {
+    deadline: Instant,
+    reached: bool,
+}
+
+/// The result fields of a time-boxed run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct TimeBoxReport {
+    /// Always `true`, so results can be told apart by the field alone.
+    pub time_boxed: bool,
+    /// Operations applied before the run stopped; the root and counters
+    /// cover exactly these.
+    pub ops_completed: u64,
+    /// `false` when the workload ended before the deadline.
+    pub deadline_reached: bool,
+}
+
+impl TimeBox {
+    /// Starts a time box of `secs` seconds at `start`.
+    #[must_use]
+    pub fn new(start: Instant, secs: u64) -> Self {
+        Self {
+            deadline: start + Duration::from_secs(secs),
+            reached: false,
+        }
+    }
+
+    /// Records an operation applied by `now` and reports whether the run
+    /// must stop before the next one.
+    pub fn applied(&mut self, now: Instant) -> bool {
+        self.reached = now >= self.deadline;
+        self.reached
+    }
+
+    /// Moves the deadline back by `paused`, time a `sleep` line spent
+    /// that the box does not count.
+    pub fn extend(&mut self, paused: Duration) {
+        self.deadline += paused;
+    }
+
+    /// Reports whether the run stopped at the deadline.
+    #[must_use]
+    pub fn reached(&self) -> bool {
+        self.reached
+    }
+
+    /// The result fields of a run that applied `ops_completed` operations.
+    #[must_use]
+    pub fn report(&self, ops_completed: u64) -> TimeBoxReport {
+        TimeBoxReport {
+            time_boxed: true,
+            ops_completed,
+            deadline_reached: self.reached,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_at_the_first_operation_past_the_deadline() {
+        let start = Instant::now();
+        let mut time_box = TimeBox::new(start, 2);
+        assert!(!time_box.applied(start + Duration::from_millis(1999)));
+        assert!(!time_box.reached());
+        assert!(time_box.applied(start + Duration::from_secs(2)));
+        let json = serde_json::to_value(time_box.report(7)).unwrap_or_default();
+        assert_eq!(
+            json,
+            serde_json::json!({"time_boxed": true, "ops_completed": 7, "deadline_reached": true})
+        );
+        assert!(!TimeBox::new(start, 2).report(3).deadline_reached);
+
+        let mut paused = TimeBox::new(start, 2);
+        paused.extend(Duration::from_secs(1));
+        assert!(!paused.applied(start + Duration::from_millis(2999)));
+    }
+}
@@ -0,0 +1,151 @@
+//! Workload trailers. A generator may write a `trailer` line just before
+//! the final `compute_root`, declaring how many lines of each operation
+//! it emitted. The harness tallies the lines it decodes and compares, so
+//! a pipe that lost or duplicated lines in transit shows up in the result
+//! rather than passing as a slightly different workload.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::protocol::Op;
+
+/// Lines decoded so far, by operation.
+#[derive(Default)]
+pub struct OpTally {
+    counts: BTreeMap<&'static str, u64>,
+    lines: u64,
+}
+
+/// The result fields describing a trailer check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TrailerReport {
+    pub trailer_match: bool,
+    /// Per operation, lines decoded minus lines declared, for the
+    /// operations where the two differ.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub trailer_deltas: BTreeMap<String, i64>,
+    /// Lines decoded minus the trailer's `lines`, when they differ.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trailer_lines_delta: Option<i64>,
+}
+
+impl OpTally {
+    /// Counts the line carrying `op`. Trailers are not counted, so a
+    /// trailer declares only the lines before it.
+    pub fn record(&mut self, op: &Op) {
+        if matches!(op, Op::Trailer { .. }) {
+            return;
+        }
+        let count = self.counts.entry(op.name()).or_default();
+        *count = count.saturating_add(1);
+        self.lines = self.lines.saturating_add(1);
+    }
+
+    /// Compares the lines counted so far with a trailer declaring
+    /// `counts` per operation and `lines` in all. An operation missing
+    /// from `counts` is declared absent.
+    #[must_use]
+    pub fn check(&self, counts: &BTreeMap<String, u64>, lines: u64) -> TrailerReport {
+        let mut trailer_deltas = BTreeMap::new();
+        for (&op, &seen) in &self.counts {
+            let declared = counts.get(op).copied().unwrap_or(0);
+            if seen != declared {
+                trailer_deltas.insert(op.to_string(), delta(seen, declared));
+            }
+        }
+        for (op, &declared) in counts {
+            if declared > 0 && !self.counts.contains_key(op.as_str()) {
+                trailer_deltas.insert(op.clone(), delta(0, declared));
+            }
+        }
+        let trailer_lines_delta = (self.lines != lines).then(|| delta(self.lines, lines));
+        TrailerReport {
+            trailer_match: trailer_deltas.is_empty() && trailer_lines_delta.is_none(),
+            trailer_deltas,
+            trailer_lines_delta,
+        }
+    }
+}
+
+fn delta(seen: u64, declared: u64) -> i64 {
+    let signed = |n| i64::try_from(n).unwrap_or(i64::MAX);
+    signed(seen).saturating_sub(signed(declared))
+}
+
+impl fmt::Display for TrailerReport {
+    /// Lists the deltas, such as `set_storage -3, lines -3`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = self.trailer_lines_delta.map(|delta| ("lines", delta));
+        let deltas = self
+            .trailer_deltas
+            .iter()
+            .map(|(op, &delta)| (op.as_str(), delta))
+            .chain(lines);
+        for (i, (name, delta)) in deltas.enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(f, "{separator}{name} {delta:+}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(ops: &[Op]) -> OpTally {
+        let mut tally = OpTally::default();
+        for op in ops {
+            tally.record(op);
+        }
+        tally
+    }
+
+    fn set_code() -> Op {
+        Op::SetCode {
+            address: "0x01".to_string(),
+            code: "0x00".to_string(),
+        }
+    }
+
+    fn commit_block() -> Op {
+        Op::CommitBlock { approx_gas: None }
+    }
+
+    #[test]
+    fn matches_the_lines_it_declares() {
+        let tally = tally(&[set_code(), set_code(), commit_block()]);
+        let counts = BTreeMap::from([
+            ("set_code".to_string(), 2),
+            ("commit_block".to_string(), 1),
+            ("transfer".to_string(), 0),
+        ]);
+        let report = tally.check(&counts, 3);
+        assert!(report.trailer_match);
+        let json = serde_json::to_string(&report).unwrap_or_default();
+        assert_eq!(json, r#"{"trailer_match":true}"#);
+    }
+
+    #[test]
+    fn reports_lost_and_duplicated_lines() {
+        let trailer = Op::Trailer {
+            counts: BTreeMap::new(),
+            lines: 0,
+        };
+        let tally = tally(&[set_code(), set_code(), set_code(), trailer]);
+        let counts = BTreeMap::from([("set_code".to_string(), 2), ("commit_block".to_string(), 2)]);
+        let report = tally.check(&counts, 4);
+        assert!(!report.trailer_match);
+        assert_eq!(
+            report.trailer_deltas,
+            BTreeMap::from([
+                ("commit_block".to_string(), -2),
+                ("set_code".to_string(), 1)
+            ])
+        );
+        assert_eq!(report.trailer_lines_delta, Some(-1));
+        assert_eq!(report.to_string(), "commit_block -2, set_code +1, lines -1");
+    }
+}
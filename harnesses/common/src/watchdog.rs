@@ -0,0 +1,115 @@
+//! Apply-throughput watchdog. A workload of crafted keys can push a
+//! harness's accumulator into long probe sequences, and the run then
+//! looks hung. The read loop times each operation anyway, so the
+//! watchdog compares the apply rate of each window of operations with
+//! the running average and warns once when it collapses.
+
+use std::time::Duration;
+
+/// Operations per watchdog window.
+pub const WINDOW_OPS: u64 = 4096;
+
+/// A window this many times slower than the running average warns.
+pub const SLOWDOWN_FACTOR: u64 = 100;
+
+/// Tracks the apply rate window by window.
+#[derive(Clone, Default)]
+pub struct ApplyWatchdog {
+    ops: u64,
+    nanos: u64,
+    window_ops: u64,
+    window_nanos: u64,
+    min_ops_per_sec: Option<u64>,
+    warning: Option<String>,
+}
+
+impl ApplyWatchdog {
+    /// Records an operation that took `took` to decode and apply.
+    /// Returns a warning the first time a window's rate falls
+    /// [`SLOWDOWN_FACTOR`] times below the average of the windows before.
+    pub fn applied(&mut self, took: Duration) -> Option<String> {
+        self.window_ops = self.window_ops.saturating_add(1);
+        self.window_nanos = self
+            .window_nanos
+            .saturating_add(u64::try_from(took.as_nanos()).unwrap_or(u64::MAX));
+        if self.window_ops < WINDOW_OPS {
+            return None;
+        }
+
+        let rate = ops_per_sec(self.window_ops, self.window_nanos)?;
+        let average = ops_per_sec(self.ops, self.nanos);
+        self.ops = self.ops.saturating_add(self.window_ops);
+        self.nanos = self.nanos.saturating_add(self.window_nanos);
+        self.window_ops = 0;
+        self.window_nanos = 0;
+        self.min_ops_per_sec = Some(self.min_ops_per_sec.map_or(rate, |min| min.min(rate)));
+
+        let average = average.filter(|&avg| rate.saturating_mul(SLOWDOWN_FACTOR) < avg)?;
+        if self.warning.is_some() {
+            return None;
+        }
+        let warning = format!(
+            "apply throughput fell to {rate} ops/s after {} operations, over \
+             {SLOWDOWN_FACTOR}x below its running average of {average} ops/s",
+            self.ops
+        );
+        self.warning = Some(warning.clone());
+        Some(warning)
+    }
+
+    /// Returns the slowest rate of any complete window, `None` before
+    /// the first completes, and the warning if one was raised.
+    #[must_use]
+    pub fn into_parts(self) -> (Option<u64>, Option<String>) {
+        (self.min_ops_per_sec, self.warning)
+    }
+}
+
+fn ops_per_sec(ops: u64, nanos: u64) -> Option<u64> {
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let rate = (nanos > 0).then(|| (ops as f64 * 1e9 / nanos as f64).round() as u64);
+    rate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(watchdog: &mut ApplyWatchdog, per_op: Duration) -> Vec<String> {
+        (0..WINDOW_OPS)
+            .filter_map(|_| watchdog.applied(per_op))
+            .collect()
+    }
+
+    #[test]
+    fn warns_once_when_a_window_collapses() {
+        let mut watchdog = ApplyWatchdog::default();
+        for _ in 0..3 {
+            assert!(window(&mut watchdog, Duration::from_micros(1)).is_empty());
+        }
+        assert_eq!(watchdog.min_ops_per_sec, Some(1_000_000));
+
+        // 50x slower is tolerated.
+        assert!(window(&mut watchdog, Duration::from_micros(50)).is_empty());
+        assert_eq!(watchdog.min_ops_per_sec, Some(20_000));
+
+        let warnings = window(&mut watchdog, Duration::from_millis(10));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("apply throughput fell to 100 ops/s"));
+        assert!(window(&mut watchdog, Duration::from_millis(20)).is_empty());
+        let (min, warning) = watchdog.into_parts();
+        assert_eq!(min, Some(50));
+        assert_eq!(warning.as_ref(), warnings.first());
+    }
+
+    #[test]
+    fn partial_windows_report_no_minimum() {
+        let mut watchdog = ApplyWatchdog::default();
+        assert_eq!(watchdog.applied(Duration::from_secs(1)), None);
+        assert_eq!(watchdog.into_parts(), (None, None));
+    }
+}
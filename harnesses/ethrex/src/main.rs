@@ -1,69 +1,1261 @@
 /// Ethrex harness reads a JSONL workload from stdin, applies state
 /// operations using ethrex's native state/trie layer, and outputs
 /// benchmark results as JSON to stdout.
-use std::collections::HashMap;
-use std::io::{self, BufRead};
+use std::collections::{BTreeMap, HashMap, HashSet, hash_map};
+use std::fs::File;
+use std::hash::RandomState;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::path::Path;
 use std::process;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
-use clap::Parser;
-use ethrex_common::types::{AccountInfo, AccountUpdate, Code};
+use clap::{Parser, ValueEnum};
+use ethrex_common::types::{AccountInfo, AccountState, AccountUpdate, Code};
+use ethrex_common::utils::keccak;
 use ethrex_common::{Address, H256, U256};
+use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_storage::api::StorageBackend;
 use ethrex_storage::api::tables::{ACCOUNT_CODES, ACCOUNT_TRIE_NODES, STORAGE_TRIE_NODES};
 use ethrex_storage::backend::rocksdb::RocksDBBackend;
 use ethrex_storage::{AccountUpdatesList, Store, apply_prefix};
-use ethrex_trie::EMPTY_TRIE_HASH;
-use serde::{Deserialize, Serialize};
+use ethrex_trie::{EMPTY_TRIE_HASH, Nibbles};
+use serde::Serialize;
+use serde_json::Value;
+use statoor_common::assertions::{AssertionReport, Assertions};
+use statoor_common::blocks::{
+    self, BlockReport, BlockReports, BlockStats, BlockTags, DEFAULT_ROLLING_WINDOW,
+};
+use statoor_common::budget::Budget;
+use statoor_common::canary::{Canaries, CanaryReport};
+use statoor_common::cbor::WorkloadFormat;
+use statoor_common::codebook::CodeBook;
+use statoor_common::coderead::{self, CodeReadPass, CodeReadReport};
+use statoor_common::compression::{self, Compression, CompressionReport};
+use statoor_common::counters::{CounterTotals, Counters, InputBytes};
+use statoor_common::cpu::{BackgroundCpu, CpuSample};
+use statoor_common::delegation;
+use statoor_common::derive;
+use statoor_common::disk::{
+    DURABLE, DiskFullReport, EXIT_DISK_FULL, OS_BUFFERED, SpaceGuard, is_disk_full, sst_size,
+    sync_tree,
+};
+use statoor_common::events::EventLog;
+use statoor_common::examples::EXAMPLES;
+use statoor_common::fillcurve::{FillCurve, FillPoint};
+use statoor_common::fingerprint::{Fingerprint, FingerprintReport};
+use statoor_common::fixtures::{BLOCK_FIXTURES, FIXTURES, Outcome, render_table};
+use statoor_common::header::WorkloadHeader;
+use statoor_common::health::{self, HEALTH_FIXTURE};
+use statoor_common::hexfmt;
+use statoor_common::invocation;
+use statoor_common::lock::DbLock;
+use statoor_common::metrics::MetricsLog;
+use statoor_common::model::{APPLY, DB_WRITE, MeasurementModel, TRIE};
+use statoor_common::multiroot::RootLines;
+use statoor_common::output;
+use statoor_common::preload::{DEFAULT_PRELOAD_LIMIT_MB, Input, PreloadReport};
+use statoor_common::probe::{self, ProbeReport};
+#[cfg(feature = "profiling")]
+use statoor_common::profile::ProfileArgs;
+use statoor_common::profile::Profiler;
+use statoor_common::proofs::{self, ProofReport, ProofStats};
+use statoor_common::protocol::{Entry, Op, Sign, capabilities_json, schema_json};
+use statoor_common::quantity;
+use statoor_common::query::{self, Query};
+use statoor_common::reads::{ReadReport, ReadStats};
+use statoor_common::recompute::{self, RecomputeReport};
+use statoor_common::reserved::ReservedTouches;
+use statoor_common::rootcheck::{self, EXIT_ROOT_MISMATCH};
+use statoor_common::sampling::{DecodeEstimates, DecodeSampler};
+use statoor_common::sleep::Sleeps;
+use statoor_common::status::{OpRates, Pending, Phase, Progress, spawn_reporter};
+use statoor_common::sysmetrics::{EnvSnapshot, Environment, SystemMetrics};
+use statoor_common::timebox::{TimeBox, TimeBoxReport};
+use statoor_common::trailer::{OpTally, TrailerReport};
+use statoor_common::watchdog::ApplyWatchdog;
 
-#[derive(Parser)]
+#[derive(Parser, Serialize)]
 #[command(about = "Ethrex state benchmark harness")]
 struct Cli {
     /// Database directory path
+    #[arg(
+        long,
+        required_unless_present_any = [
+            "self_test", "health_check", "print_schema", "capabilities", "probe_root_of",
+            "query_db"
+        ]
+    )]
+    db: Option<String>,
+
+    /// Run the embedded fixtures in temporary databases, print a
+    /// pass/fail table and exit. Only the flags that leave the root as it
+    /// is, such as --pipelined, apply to them
+    #[arg(long)]
+    self_test: bool,
+
+    /// Run a three-operation workload end to end in a temporary
+    /// database, print a JSON health report and exit nonzero on failure
+    #[arg(long)]
+    health_check: bool,
+
+    /// Print the JSON Schema of a workload line and exit
+    #[arg(long)]
+    print_schema: bool,
+
+    /// Print the supported protocol version and operations as JSON and
+    /// exit
+    #[arg(long)]
+    capabilities: bool,
+
+    /// Write an NDJSON event per applied operation to this file, usable
+    /// by the orchestrator's --replay-events
+    #[arg(long)]
+    events_out: Option<String>,
+
+    /// Write the metric line of each checkpoint operation to this file
+    /// instead of stderr
+    #[arg(long)]
+    metrics_out: Option<String>,
+
+    /// Artificial latency in milliseconds slept around each commit, to
+    /// simulate slower storage. Results are marked as simulated.
+    #[arg(long, default_value_t = 0)]
+    inject_commit_latency_ms: u64,
+
+    /// Store a slot set to zero as a zero-valued leaf instead of deleting
+    /// it. The root then diverges from mainnet semantics
+    #[arg(long)]
+    keep_zero_slots: bool,
+
+    /// Set the balance to zero when an `update_balance` or `transfer`
+    /// subtracts more than it holds, instead of failing the run
+    #[arg(long)]
+    clamp_balance_underflow: bool,
+
+    /// Remove accounts left with zero nonce, zero balance and no code
+    /// from the state at each commit, as EIP-158 does, instead of
+    /// storing them as empty leaves
+    #[arg(long)]
+    prune_empty_accounts: bool,
+
+    /// Fail on the first operation touching the zero address, a
+    /// precompile or the all-ones address, which are counted either way
+    #[arg(long)]
+    reject_reserved_addresses: bool,
+
+    /// Fail on a `set_delegation` whose target is in neither the workload
+    /// nor the state, which is more often a generator bug than intended
+    #[arg(long)]
+    require_delegation_targets: bool,
+
+    /// Read and decode the whole workload before opening the store, so
+    /// the measured phases do no stdin I/O or JSON parsing
+    #[arg(long)]
+    preload_workload: bool,
+
+    /// Workload encoding: `jsonl`, `cbor`, or `auto` to tell them apart
+    /// by the CBOR self-describe tag
+    #[arg(long, default_value = "auto")]
+    workload_format: WorkloadFormat,
+
+    /// Codec stdin is compressed with: `gzip`, `zstd`, `none`, or `auto`
+    /// to go by its magic bytes
+    #[arg(long, default_value = "auto")]
+    compression: Compression,
+
+    /// Refuse to preload a workload larger than this many MiB
+    #[arg(long, default_value_t = DEFAULT_PRELOAD_LIMIT_MB)]
+    preload_limit_mb: u64,
+
+    /// Fail the run when a `trailer` line declares other operation
+    /// counts than were read, or a `canary` expects another leaf hash,
+    /// instead of only reporting the deltas
+    #[arg(long)]
+    strict: bool,
+
+    /// What a failed `assert_account` or `assert_storage` does: `fail`
+    /// ends the run, `warn` prints it to stderr and goes on
+    #[arg(long, value_enum, default_value_t = AssertionMode::Fail)]
+    assertions: AssertionMode,
+
+    /// Blocks per window of the `rolling_stats` of a `commit_block`
+    /// workload
+    #[arg(long, default_value_t = DEFAULT_ROLLING_WINDOW)]
+    rolling_window: usize,
+
+    /// List every block in the result even past one rolling window,
+    /// instead of in a `<db>.blocks.json` sidecar
+    #[arg(long)]
+    emit_per_block: bool,
+
+    /// Stop the write phase with a partial result once free space on the
+    /// database filesystem drops below this many GiB. 0 disables the check.
+    #[arg(long, default_value_t = 0)]
+    reserve_free_gb: u64,
+
+    /// After the commit, fsync the database files so the WAL is durable,
+    /// timed separately as `post_commit_flush_ms`
+    #[arg(long)]
+    flush_after_commit: bool,
+
+    /// Stop after this many applied operations and compute the root over
+    /// that prefix, for quick smoke runs of huge workloads
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_ops: Option<u64>,
+
+    /// Stop applying operations this many seconds after starting to
+    /// read them, and compute the root over the prefix applied
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    duration_secs: Option<u64>,
+
+    /// After the run, reopen the database in a child process and check
+    /// that its persisted root node hashes to the reported root. `hard`
+    /// also checks a copy taken without lock files, approximating a
+    /// crash snapshot
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "soft")]
+    durability_probe: Option<ProbeMode>,
+
+    /// After the run, fetch this many code blobs by hash through ethrex's
+    /// Store, sampled from the codes written, and report their latencies.
+    /// A second pass reopens the Store after dropping the page cache when
+    /// the process may do so
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    read_code: Option<u64>,
+
+    /// Stop the `--read-code` passes after this many seconds, reporting
+    /// the reads made so far as `truncated`
+    #[arg(long, requires = "read_code", value_parser = clap::value_parser!(u64).range(1..))]
+    read_bench_timeout_secs: Option<u64>,
+
+    /// Stop listing the codes `--read-code` samples from after this many
+    /// seconds, and sample from those listed so far
+    #[arg(long, requires = "read_code", value_parser = clap::value_parser!(u64).range(1..))]
+    iterate_timeout_secs: Option<u64>,
+
+    /// After the run, compute the root again through a freshly opened
+    /// Store, then this many more times through the same one, without
+    /// changing any state, and report the time of each. Every computation
+    /// must return the run's root
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    recompute_root: Option<u64>,
+
+    /// Kill a `--durability-probe` child still running after this many
+    /// seconds, reporting the probe as `truncated`
+    #[arg(long, requires = "durability_probe", value_parser = clap::value_parser!(u64).range(1..))]
+    verify_timeout_secs: Option<u64>,
+
+    /// Child side of --durability-probe and `statoor verify-db`: print
+    /// the persisted state root of this database and exit
+    #[arg(long, hide = true)]
+    probe_root_of: Option<String>,
+
+    /// Answer `read_account` and `read_storage` lines from stdin against
+    /// the tries persisted in this database, without running a workload,
+    /// one JSON response per line until EOF
+    #[arg(long)]
+    query_db: Option<String>,
+
+    /// Compute every account's storage root first, timed as
+    /// `storage_trie_time_ms`, then build the account trie from the
+    /// precomputed roots, timed as `account_trie_time_ms`
+    #[arg(long)]
+    two_stage_trie: bool,
+
+    /// Overlap the trie and DB write phases: compute the trie in chunks
+    /// of accounts and persist each finished chunk on a writer thread
+    /// while the next one is computed
+    #[arg(long, conflicts_with_all = ["two_stage_trie", "save_updates", "load_updates"])]
+    pipelined: bool,
+
+    /// Accounts per chunk of a --pipelined trie phase
+    #[arg(
+        long,
+        default_value_t = 4096,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pipeline_chunk_accounts: u64,
+
+    /// Points of the fill_curve a chunked trie phase reports, each the
+    /// trie time of an equal fraction of the accounts; 10 for deciles
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    fill_curve_points: u64,
+
+    /// Once the accounts and storage writes held in memory, a bound on
+    /// the open tries' leaves, exceed this many, write the staged updates
+    /// to RocksDB and reopen the tries there, so that memory stays bounded
+    /// however large the state grows
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = [
+            "two_stage_trie",
+            "pipelined",
+            "save_updates",
+            "load_updates",
+            "stay_alive",
+        ]
+    )]
+    max_trie_nodes_in_memory: Option<u64>,
+
+    /// After the trie phase, save the computed node set and code to this
+    /// file for later runs with --load-updates
+    #[arg(long)]
+    save_updates: Option<String>,
+
+    /// Skip reading stdin and computing the trie: load the node set saved
+    /// by --save-updates from this file and run only the DB write phase
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "save_updates",
+            "two_stage_trie",
+            "max_ops",
+            "duration_secs",
+            "events_out",
+            "metrics_out"
+        ]
+    )]
+    load_updates: Option<String>,
+
+    /// Start even if another live statoor process holds the lock file in
+    /// the database directory
+    #[arg(long)]
+    force: bool,
+
+    /// Also report `unique_storage_slots`, the distinct (address, slot)
+    /// pairs written. Remembering them adds to the peak memory
     #[arg(long)]
-    db: String,
-}
-
-#[derive(Deserialize)]
-struct Operation {
-    op: String,
-    #[serde(default)]
-    address: String,
-    #[serde(default)]
-    balance: String,
-    #[serde(default)]
-    nonce: u64,
-    #[serde(default)]
-    code: String,
-    #[serde(default)]
-    slot: String,
-    #[serde(default)]
-    value: String,
+    unique_slots: bool,
+
+    /// After printing the result, keep answering `read_account`,
+    /// `read_storage` and `dump_state` lines from stdin until EOF, one
+    /// JSON response per line
+    #[arg(long, conflicts_with = "load_updates")]
+    stay_alive: bool,
+
+    /// Number every result line with its `root_index`, for workloads
+    /// with several `compute_root` lines. Every line carries `final`
+    /// either way
+    #[arg(long)]
+    multi_root: bool,
+
+    #[cfg(feature = "profiling")]
+    #[command(flatten)]
+    #[serde(flatten)]
+    profile: ProfileArgs,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum AssertionMode {
+    Fail,
+    Warn,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ProbeMode {
+    /// Reopen the database in place
+    Soft,
+    /// Also reopen a lock-free copy of the database directory
+    Hard,
 }
 
 #[derive(Serialize)]
 struct BenchResult {
     client: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    simulated: bool,
     state_root: String,
-    accounts_created: usize,
-    contracts_created: usize,
-    storage_slots: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_match: Option<bool>,
+    #[serde(flatten)]
+    counters: CounterTotals,
     elapsed_ms: u128,
+    /// Time spent in `sleep` lines, left out of `elapsed_ms`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sleep_ms: Option<u64>,
     trie_time_ms: u128,
     db_write_time_ms: u128,
+    /// Blocks committed with `commit_block`, in order. The trie and DB
+    /// write times above are their sums.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    blocks: Vec<BlockReport>,
+    #[serde(flatten)]
+    block_stats: Option<BlockStats>,
     peak_memory_bytes: u64,
+    /// Bytes in `RocksDB`'s table files, without its WAL and logs; absent
+    /// while everything written is still in the memtable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    db_logical_size_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    injected_latency_total_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    db_write_time_net_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    post_commit_flush_ms: Option<u128>,
+    durability: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated_at_op: Option<u64>,
+    #[serde(flatten)]
+    time_box: Option<TimeBoxReport>,
+    #[serde(flatten)]
+    fingerprint: Option<FingerprintReport>,
+    /// The `name` of the workload's header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workload_name: Option<String>,
+    #[serde(flatten)]
+    reads: Option<ReadReport>,
+    #[serde(flatten)]
+    proofs: Option<ProofReport>,
+    #[serde(flatten)]
+    reserved: Option<ReservedTouches>,
+    #[serde(flatten)]
+    preload: Option<PreloadReport>,
+    #[serde(flatten)]
+    compression: Option<CompressionReport>,
+    #[serde(flatten)]
+    trailer: Option<TrailerReport>,
+    #[serde(flatten)]
+    canaries: Option<CanaryReport>,
+    #[serde(flatten)]
+    assertions: Option<AssertionReport>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    input_bytes_by_op: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_bytes_per_op: Option<f64>,
+    #[serde(flatten)]
+    decode_estimates: Option<DecodeEstimates>,
+    #[serde(flatten)]
+    op_rates: Option<OpRates>,
+    /// Slowest apply rate of any window of operations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apply_throughput_min_ops_per_sec: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    background_cpu_ms: BTreeMap<&'static str, u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    system_metrics: BTreeMap<&'static str, SystemMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<Environment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    durability_probe: Option<ProbeReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_read: Option<CodeReadReport>,
+    #[serde(flatten)]
+    recompute: Option<RecomputeReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_trie_time_ms: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_trie_time_ms: Option<u128>,
+    /// When the trie and DB write phases ran. They overlap in a
+    /// `--pipelined` run.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    phases: BTreeMap<&'static str, PhaseInterval>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pipeline: Option<PipelineReport>,
+    /// Trie time by fraction of the accounts applied, when the trie went
+    /// chunk by chunk.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fill_curve: Vec<FillPoint>,
+    /// The operation after which --max-trie-nodes-in-memory was exceeded,
+    /// and what the harness did about it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_budget_triggered_at_op: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_budget_strategy: Option<&'static str>,
+    /// How many times the tries were written out and reopened.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_budget_spills: Option<u64>,
+    /// Phases a `--load-updates` run did not perform. Their counters and
+    /// times are reported as zero.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_phases: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updates_source: Option<UpdatesSource>,
+    /// Files written alongside the result, such as `--profile-out`
+    /// flamegraphs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<String>,
+    /// What each timed phase of this run included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    measurement_model: Option<MeasurementModel>,
+    /// Every flag of this run with its effective value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invocation: Option<Value>,
+}
+
+/// The file a `--load-updates` run took its node set from.
+#[derive(Serialize)]
+struct UpdatesSource {
+    path: String,
+    /// Keccak-256 of the whole file.
+    keccak: String,
+}
+
+/// A phase's start and end, in milliseconds since the run started.
+#[derive(Clone, Copy, Serialize)]
+struct PhaseInterval {
+    start_ms: u128,
+    end_ms: u128,
+}
+
+impl PhaseInterval {
+    fn new(run_start: Instant, start: Instant, end: Instant) -> Self {
+        Self {
+            start_ms: start.duration_since(run_start).as_millis(),
+            end_ms: end.duration_since(run_start).as_millis(),
+        }
+    }
+}
+
+/// How a `--pipelined` run split its work. `trie_time_ms` and
+/// `db_write_time_ms` are then each side's busy time, and their sum
+/// exceeds the wall time by however much the two overlapped.
+#[derive(Serialize)]
+struct PipelineReport {
+    chunks: u64,
+    chunk_accounts: u64,
+    queue_depth: usize,
+    /// Time the trie side waited for room in the queue.
+    trie_blocked_ms: u128,
+    /// CPU of the main thread, which computes the trie (Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trie_cpu_ms: Option<u64>,
+    /// CPU of every other thread: the writer and RocksDB's own threads
+    /// (Linux only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    db_write_cpu_ms: Option<u64>,
+}
+
+/// `memory_budget_strategy` of a run that exceeded
+/// --max-trie-nodes-in-memory: the staged updates were written to
+/// RocksDB and the tries reopened there, so only the nodes later updates
+/// touch are held in memory.
+const SPILL_TO_ROCKSDB: &str = "spill_to_rocksdb";
+
+/// Finished chunks that may wait for the writer before the trie side of
+/// a `--pipelined` run blocks.
+const PIPELINE_QUEUE_DEPTH: usize = 2;
+
+/// Node set the DB write phase persists: computed from the workload's
+/// account updates, or loaded from a `--save-updates` file. The blocks
+/// of a `commit_block` workload arrive already persisted.
+enum TrieInput<'a> {
+    Compute {
+        store: &'a Store,
+        state_trie: &'a mut ethrex_trie::Trie,
+        account_updates: &'a [AccountUpdate],
+        /// Persist the trie chunk by chunk, overlapping the phases.
+        chunked: bool,
+    },
+    Loaded(AccountUpdatesList),
+    Committed(PhaseTimes),
+}
+
+/// State carried across the blocks of a `commit_block` workload, and
+/// across the spills of a run over --max-trie-nodes-in-memory. The
+/// in-memory store keeps none of the nodes written to RocksDB, so the
+/// tries stay open from block to block, until a spill reopens them over
+/// RocksDB itself.
+#[derive(Default)]
+struct Blocks {
+    /// Storage tries by account hash.
+    storage_tries: HashMap<H256, ethrex_trie::Trie>,
+    /// Every account as of the last block, merged the way the read loop
+    /// merges updates, for queries and for `set_code` on an account of
+    /// an earlier block. No longer kept once the tries have spilled.
+    committed: HashMap<Address, AccountUpdate, RandomState>,
+    /// Addresses deleted since the last block.
+    deleted: HashSet<Address, RandomState>,
+    /// Hashes of the code an earlier block already persisted.
+    written_code: HashSet<H256>,
+    state_root: H256,
+    injected_ms: Option<u128>,
+    reports: BlockReports,
+    /// Operations applied before the last block was committed.
+    ops_committed: u64,
+    /// Accounts and storage writes the open tries took in since they
+    /// were last opened, a bound on their leaves.
+    resident_leaves: u64,
+    /// Storage writes applied when the tries were last written to.
+    slots_applied: u64,
+    /// The database the tries were reopened over by the first spill.
+    spilled_to: Option<Arc<dyn StorageBackend>>,
+    spills: u64,
+    /// Trie and DB write time of the flushes since the last block, which
+    /// the next block report includes.
+    flushed_trie_ms: u128,
+    flushed_db_write_ms: u128,
+    /// Accounts a spill left empty, which `--prune-empty-accounts` checks
+    /// again when the block, or the run, ends.
+    unpruned: HashSet<Address, RandomState>,
+}
+
+impl Blocks {
+    /// Applies one block's merged updates on top of the previous block
+    /// and persists the changed nodes in one transaction. With
+    /// `--prune-empty-accounts`, the accounts the block left empty are
+    /// removed first and counted in `counters`.
+    #[allow(clippy::too_many_arguments)]
+    fn commit(
+        &mut self,
+        store: &Store,
+        state_trie: &mut ethrex_trie::Trie,
+        updates: HashMap<Address, AccountUpdate, RandomState>,
+        db_backend: &Arc<dyn StorageBackend>,
+        guard: &SpaceGuard,
+        cli: &Cli,
+        counters: &mut Counters<Address, H256>,
+    ) {
+        let updates = self.flush(
+            store,
+            state_trie,
+            updates,
+            db_backend,
+            guard,
+            cli,
+            counters,
+            cli.prune_empty_accounts,
+        );
+        let ops_applied = counters.ops_applied();
+        self.reports.push(BlockReport {
+            state_root: hexfmt::fixed(self.state_root),
+            trie_time_ms: std::mem::take(&mut self.flushed_trie_ms),
+            db_write_time_ms: std::mem::take(&mut self.flushed_db_write_ms),
+            approx_gas: None,
+            block_number: None,
+            ops: ops_applied - self.ops_committed,
+        });
+        self.ops_committed = ops_applied;
+        if self.spilled_to.is_none() {
+            for update in updates {
+                self.merge(update);
+            }
+        }
+        self.deleted.clear();
+    }
+
+    /// Writes the staged updates out once the open tries hold more than
+    /// `budget` leaves, then reopens the tries over RocksDB, so memory no
+    /// longer grows with the state. Returns whether it spilled.
+    #[allow(clippy::too_many_arguments)]
+    fn spill_over(
+        &mut self,
+        budget: u64,
+        store: &Store,
+        state_trie: &mut ethrex_trie::Trie,
+        updates: &mut HashMap<Address, AccountUpdate, RandomState>,
+        db_backend: &Arc<dyn StorageBackend>,
+        guard: &SpaceGuard,
+        cli: &Cli,
+        counters: &mut Counters<Address, H256>,
+    ) -> bool {
+        let staged = updates.len() as u64 + counters.storage_slots() - self.slots_applied;
+        if self.resident_leaves + staged <= budget {
+            return false;
+        }
+        // A block's empty accounts are pruned when it ends, by which time
+        // one of them may hold a balance again.
+        if cli.prune_empty_accounts {
+            let empty: Vec<Address> = updates
+                .values()
+                .filter(|update| !update.removed && self.leaves_empty(state_trie, update))
+                .map(|update| update.address)
+                .collect();
+            self.unpruned.extend(empty);
+        }
+        self.flush(
+            store,
+            state_trie,
+            std::mem::take(updates),
+            db_backend,
+            guard,
+            cli,
+            counters,
+            false,
+        );
+        self.deleted.clear();
+        self.storage_tries.clear();
+        self.committed.clear();
+        self.resident_leaves = 0;
+        *state_trie = SpilledNodes::accounts(db_backend).open(self.state_root);
+        self.spilled_to = Some(Arc::clone(db_backend));
+        self.spills += 1;
+        true
+    }
+
+    /// Applies merged updates on top of the open tries and persists the
+    /// changed nodes in one transaction, adding the times to those of the
+    /// block. With `prune`, the accounts left empty are removed first and
+    /// counted in `counters`. Returns the updates applied.
+    #[allow(clippy::too_many_arguments)]
+    fn flush(
+        &mut self,
+        store: &Store,
+        state_trie: &mut ethrex_trie::Trie,
+        mut updates: HashMap<Address, AccountUpdate, RandomState>,
+        db_backend: &Arc<dyn StorageBackend>,
+        guard: &SpaceGuard,
+        cli: &Cli,
+        counters: &mut Counters<Address, H256>,
+        prune: bool,
+    ) -> Vec<AccountUpdate> {
+        if prune {
+            for address in std::mem::take(&mut self.unpruned) {
+                updates
+                    .entry(address)
+                    .or_insert_with(|| AccountUpdate::new(address));
+            }
+            counters.record_empty_pruned(self.prune_empty(state_trie, &mut updates));
+        }
+        let latency_ms = cli.inject_commit_latency_ms;
+        let updates: Vec<AccountUpdate> = updates.into_values().collect();
+        self.resident_leaves +=
+            updates.len() as u64 + counters.storage_slots() - self.slots_applied;
+        self.slots_applied = counters.storage_slots();
+        let trie_start = Instant::now();
+        let list = self.apply(store, state_trie, &updates, cli.keep_zero_slots);
+        self.flushed_trie_ms += trie_start.elapsed().as_millis();
+        let db_start = Instant::now();
+        if let Some(ms) = write_updates_to_db(db_backend, &list, guard, latency_ms) {
+            self.injected_ms = Some(self.injected_ms.unwrap_or(0).saturating_add(ms));
+        }
+        self.flushed_db_write_ms += db_start.elapsed().as_millis();
+        self.state_root = list.state_trie_hash;
+        updates
+    }
+
+    /// Updates the open tries with one block's updates and collects the
+    /// nodes they changed. An account deleted in the block, or whose
+    /// storage it cleared, starts over with an empty storage trie; any
+    /// other keeps what earlier blocks wrote, and a zero value deletes
+    /// its slot unless `keep_zero` is set.
+    fn apply(
+        &mut self,
+        store: &Store,
+        state_trie: &mut ethrex_trie::Trie,
+        updates: &[AccountUpdate],
+        keep_zero: bool,
+    ) -> AccountUpdatesList {
+        let mut storage_updates = Vec::new();
+        let mut code_updates = Vec::new();
+        for update in updates {
+            let account_hash = keccak(update.address);
+            let key = account_hash.as_bytes().to_vec();
+            let wiped = update.removed || self.deleted.contains(&update.address);
+            if wiped || update.removed_storage {
+                self.storage_tries.remove(&account_hash);
+            }
+            if update.removed {
+                if let Err(e) = state_trie.remove(&key) {
+                    fatal(&format!("remove account: {e}"));
+                }
+                continue;
+            }
+
+            let mut account = if wiped {
+                AccountState::default()
+            } else {
+                match state_trie.get(&key) {
+                    Ok(Some(rlp)) => match AccountState::decode(&rlp) {
+                        Ok(account) => account,
+                        Err(e) => fatal(&format!("decode account: {e}")),
+                    },
+                    Ok(None) => AccountState::default(),
+                    Err(e) => fatal(&format!("read account: {e}")),
+                }
+            };
+            if update.removed_storage {
+                account.storage_root = *EMPTY_TRIE_HASH;
+            }
+            if let Some(info) = &update.info {
+                account.nonce = info.nonce;
+                account.balance = info.balance;
+                account.code_hash = info.code_hash;
+                if let Some(code) = &update.code
+                    && self.written_code.insert(info.code_hash)
+                {
+                    code_updates.push((info.code_hash, code.clone()));
+                }
+            }
+
+            if !update.added_storage.is_empty() {
+                let storage_trie = match self.storage_tries.entry(account_hash) {
+                    hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    hash_map::Entry::Vacant(entry) => {
+                        if let Some(backend) = &self.spilled_to {
+                            entry.insert(
+                                SpilledNodes::storage(backend, account_hash)
+                                    .open(account.storage_root),
+                            )
+                        } else {
+                            match store.open_storage_trie(
+                                account_hash,
+                                *EMPTY_TRIE_HASH,
+                                *EMPTY_TRIE_HASH,
+                            ) {
+                                Ok(trie) => entry.insert(trie),
+                                Err(e) => fatal(&format!("open storage trie: {e}")),
+                            }
+                        }
+                    }
+                };
+                for (slot, value) in &update.added_storage {
+                    let slot_key = keccak(slot).as_bytes().to_vec();
+                    let outcome = if value.is_zero() && !keep_zero {
+                        storage_trie.remove(&slot_key).map(drop)
+                    } else {
+                        storage_trie.insert(slot_key, value.encode_to_vec())
+                    };
+                    if let Err(e) = outcome {
+                        fatal(&format!("update storage slot: {e}"));
+                    }
+                }
+                let (storage_root, nodes) = storage_trie.collect_changes_since_last_hash();
+                account.storage_root = storage_root;
+                storage_updates.push((account_hash, nodes));
+            }
+
+            if let Err(e) = state_trie.insert(key, account.encode_to_vec()) {
+                fatal(&format!("insert account: {e}"));
+            }
+        }
+
+        let (state_trie_hash, state_updates) = state_trie.collect_changes_since_last_hash();
+        AccountUpdatesList {
+            state_trie_hash,
+            state_updates,
+            storage_updates,
+            code_updates,
+        }
+    }
+
+    /// Turns every update that leaves its account empty into a deletion,
+    /// returning how many did. An update without info carries storage
+    /// only, so the account is as the state trie has it.
+    fn prune_empty(
+        &mut self,
+        state_trie: &ethrex_trie::Trie,
+        updates: &mut HashMap<Address, AccountUpdate, RandomState>,
+    ) -> u64 {
+        let mut pruned = 0;
+        for update in updates.values_mut().filter(|update| !update.removed) {
+            if self.leaves_empty(state_trie, update) {
+                *update = AccountUpdate {
+                    removed: true,
+                    ..AccountUpdate::new(update.address)
+                };
+                self.deleted.insert(update.address);
+                pruned += 1;
+            }
+        }
+        pruned
+    }
+
+    /// Whether `update` leaves its account empty.
+    fn leaves_empty(&self, state_trie: &ethrex_trie::Trie, update: &AccountUpdate) -> bool {
+        match &update.info {
+            Some(info) => is_empty_account(info),
+            None => self
+                .trie_info(state_trie, update.address)
+                .is_none_or(|info| is_empty_account(&info)),
+        }
+    }
+
+    /// Folds a committed update into the state as of the last block.
+    fn merge(&mut self, update: AccountUpdate) {
+        let fresh = update.removed || self.deleted.contains(&update.address);
+        if let Some(account) = self.committed.get_mut(&update.address).filter(|_| !fresh) {
+            if update.info.is_some() {
+                account.info = update.info;
+            }
+            if update.code.is_some() {
+                account.code = update.code;
+            }
+            if update.removed_storage {
+                account.added_storage.clear();
+            }
+            account.added_storage.extend(update.added_storage);
+        } else {
+            self.committed.insert(update.address, update);
+        }
+    }
+
+    /// The info `address` had after the last block, unless it has been
+    /// deleted since. Once the tries have spilled, it is read from the
+    /// state trie.
+    fn committed_info(
+        &self,
+        state_trie: &ethrex_trie::Trie,
+        address: Address,
+    ) -> Option<AccountInfo> {
+        if self.spilled_to.is_some() {
+            return self.trie_info(state_trie, address);
+        }
+        if self.deleted.contains(&address) {
+            return None;
+        }
+        self.committed.get(&address)?.info.clone()
+    }
+
+    /// The info of `address` in the open state trie, which holds the
+    /// state as of the last block, unless it has been deleted since.
+    fn trie_info(&self, state_trie: &ethrex_trie::Trie, address: Address) -> Option<AccountInfo> {
+        self.trie_account(state_trie, address)
+            .map(|account| AccountInfo {
+                code_hash: account.code_hash,
+                balance: account.balance,
+                nonce: account.nonce,
+            })
+    }
+
+    fn trie_account(
+        &self,
+        state_trie: &ethrex_trie::Trie,
+        address: Address,
+    ) -> Option<AccountState> {
+        if self.deleted.contains(&address) {
+            return None;
+        }
+        let key = keccak(address).as_bytes().to_vec();
+        match state_trie.get(&key) {
+            Ok(Some(rlp)) => match AccountState::decode(&rlp) {
+                Ok(account) => Some(account),
+                Err(e) => fatal(&format!("decode account: {e}")),
+            },
+            Ok(None) => None,
+            Err(e) => fatal(&format!("read account: {e}")),
+        }
+    }
+
+    /// Runs `read` on the storage trie of `address` as of the last block:
+    /// the open one, or once the tries have spilled, one opened over
+    /// RocksDB at the account's storage root.
+    fn with_storage_trie<R>(
+        &self,
+        state_trie: &ethrex_trie::Trie,
+        address: Address,
+        read: impl FnOnce(Option<&ethrex_trie::Trie>) -> R,
+    ) -> R {
+        let account_hash = keccak(address);
+        if let Some(storage_trie) = self.storage_tries.get(&account_hash) {
+            return read(Some(storage_trie));
+        }
+        let spilled = self.spilled_to.as_ref().and_then(|backend| {
+            let account = self.trie_account(state_trie, address)?;
+            Some(SpilledNodes::storage(backend, account_hash).open(account.storage_root))
+        });
+        read(spilled.as_ref())
+    }
+
+    fn phase_times(&self) -> PhaseTimes {
+        let (trie_ms, db_write_ms) = self.reports.totals();
+        PhaseTimes {
+            state_root: self.state_root,
+            trie_ms: trie_ms + self.flushed_trie_ms,
+            db_write_ms: db_write_ms + self.flushed_db_write_ms,
+            injected_ms: self.injected_ms,
+            stage_ms: None,
+            phases: BTreeMap::new(),
+            pipeline: None,
+            fill_curve: Vec::new(),
+        }
+    }
+}
+
+/// Reads the nodes of one trie from the tables `write_updates_to_db`
+/// fills, for tries reopened after a spill. Writing is left to
+/// `write_updates_to_db`, so `put_batch` keeps nothing.
+struct SpilledNodes {
+    backend: Arc<dyn StorageBackend>,
+    /// The account hash a storage trie's keys are prefixed with.
+    account_hash: Option<H256>,
+}
+
+impl SpilledNodes {
+    fn accounts(backend: &Arc<dyn StorageBackend>) -> Self {
+        SpilledNodes {
+            backend: Arc::clone(backend),
+            account_hash: None,
+        }
+    }
+
+    fn storage(backend: &Arc<dyn StorageBackend>, account_hash: H256) -> Self {
+        SpilledNodes {
+            backend: Arc::clone(backend),
+            account_hash: Some(account_hash),
+        }
+    }
+
+    fn open(self, root: H256) -> ethrex_trie::Trie {
+        ethrex_trie::Trie::open(Box::new(self), root)
+    }
+}
+
+impl ethrex_trie::TrieDB for SpilledNodes {
+    fn get(&self, key: Nibbles) -> Result<Option<Vec<u8>>, ethrex_trie::TrieError> {
+        let (table, key) = match self.account_hash {
+            Some(account_hash) => (
+                STORAGE_TRIE_NODES,
+                apply_prefix(Some(account_hash), key).into_vec(),
+            ),
+            None => (ACCOUNT_TRIE_NODES, key.as_ref().to_vec()),
+        };
+        let db_error = |e| ethrex_trie::TrieError::DbError(anyhow::anyhow!("{e}"));
+        let read = self.backend.begin_read().map_err(db_error)?;
+        // A node removed from the trie is written as an empty value.
+        Ok(read
+            .get(table, &key)
+            .map_err(db_error)?
+            .filter(|node| !node.is_empty()))
+    }
+
+    fn put_batch(&self, _: Vec<(Nibbles, Vec<u8>)>) -> Result<(), ethrex_trie::TrieError> {
+        Ok(())
+    }
+}
+
+/// Names the set flags a `commit_block` workload ignores, as a warning:
+/// every block goes through the tries in one pass and is persisted in
+/// one transaction.
+fn ignored_in_block_mode(cli: &Cli) -> Option<String> {
+    let ignored: Vec<&str> = [
+        ("--two-stage-trie", cli.two_stage_trie),
+        ("--pipelined", cli.pipelined),
+        ("--save-updates", cli.save_updates.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(flag, set)| set.then_some(flag))
+    .collect();
+    (!ignored.is_empty()).then(|| {
+        format!(
+            "{} ignored: commit_block workloads apply each block in one pass",
+            ignored.join(", ")
+        )
+    })
 }
 
 fn main() {
     let cli = Cli::parse();
+    if cli.capabilities {
+        println!("{}", capabilities_json());
+        return;
+    }
+    if cli.print_schema {
+        print!("{}", schema_json());
+        return;
+    }
+    if cli.self_test {
+        self_test(&cli);
+    }
+    if cli.health_check {
+        health_check();
+    }
+    if let Some(path) = cli.probe_root_of.as_deref() {
+        println!("{}", hexfmt::fixed(persisted_root(path)));
+        return;
+    }
+    if let Some(path) = cli.query_db.as_deref() {
+        serve_persisted_queries(path);
+        return;
+    }
+
+    let Some(db_path) = cli.db.as_deref() else {
+        fatal("--db is required");
+    };
+    // Held until the result is emitted; dropping it removes the lock file.
+    let _lock = match DbLock::acquire(Path::new(db_path), "ethrex", cli.force) {
+        Ok(lock) => lock,
+        Err(e) => fatal(&e),
+    };
+    let progress = Arc::new(Progress::default());
+    if let Err(e) = spawn_reporter(Arc::clone(&progress), io::stderr()) {
+        fatal(&format!("start status reporter: {e}"));
+    }
+    let mut state = None;
+    let mut checkpoint_mismatch = false;
+    let mut roots = RootLines::new(cli.multi_root);
+    let mut result = match cli.load_updates.as_deref() {
+        Some(path) => run_loaded(&cli, db_path, path, &progress),
+        None => {
+            let (workload, decompressed) =
+                match compression::open(io::stdin().lock(), cli.compression, decoder) {
+                    Ok(opened) => opened,
+                    Err(e) => fatal(&format!("open workload: {e}")),
+                };
+            let (mut result, updates) =
+                run_with_state(&cli, db_path, workload, &progress, &mut |checkpoint| {
+                    checkpoint_mismatch |= checkpoint.root_match == Some(false);
+                    emit_result(&roots.line(checkpoint, false), db_path);
+                });
+            state = cli.stay_alive.then_some(updates);
+            result.compression = decompressed.map(|d| d.report());
+            result
+        }
+    };
+    // `run` has closed the database, so the child can take its lock and
+    // sees only what was committed.
+    if let Some(mode) = cli.durability_probe {
+        result.durability_probe = Some(probe::run(
+            Path::new(db_path),
+            &result.state_root,
+            mode == ProbeMode::Hard,
+            &Budget::start(cli.verify_timeout_secs),
+        ));
+    }
+    if let Some(n) = cli.read_code {
+        result.code_read = Some(read_code(
+            db_path,
+            n,
+            cli.iterate_timeout_secs,
+            cli.read_bench_timeout_secs,
+        ));
+    }
+    if let Some(passes) = cli.recompute_root {
+        result.recompute = Some(recompute_root(db_path, passes, &result.state_root));
+    }
+    result.invocation = match invocation::resolved(&cli) {
+        Ok(invocation) => Some(invocation),
+        Err(e) => fatal(&e),
+    };
+    emit_result(&roots.line(&result, true), db_path);
+    if let Some(updates) = state {
+        serve_queries(&updates);
+    }
+    if result.root_match == Some(false) || checkpoint_mismatch {
+        eprintln!("ethrex-harness: state root does not match expected_root");
+        process::exit(EXIT_ROOT_MISMATCH);
+    }
+}
+
+/// Returns the state root persisted in the database at `db_path`: the hash
+/// of the stored account trie root node, or the empty root when there is
+/// none.
+fn persisted_root(db_path: &str) -> H256 {
+    let backend = match RocksDBBackend::open(db_path) {
+        Ok(b) => b,
+        Err(e) => fatal(&format!("open rocksdb: {e}")),
+    };
+    let read = match backend.begin_read() {
+        Ok(r) => r,
+        Err(e) => fatal(&format!("begin read: {e}")),
+    };
+    match read.get(ACCOUNT_TRIE_NODES, &[]) {
+        Ok(Some(node_rlp)) => keccak(node_rlp),
+        Ok(None) => *EMPTY_TRIE_HASH,
+        Err(e) => fatal(&format!("read state trie root: {e}")),
+    }
+}
+
+/// Builds the decoder of a compressed workload. zstd frames may use the
+/// largest window `zstd --long` writes.
+fn decoder<'a>(
+    compression: Compression,
+    input: impl BufRead + 'a,
+) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(input)),
+        Compression::Zstd => {
+            let mut decoder = zstd::stream::read::Decoder::with_buffer(input)?;
+            decoder.window_log_max(31)?;
+            Box::new(decoder)
+        }
+        Compression::Auto | Compression::None => Box::new(input),
+    })
+}
+
+/// Runs every embedded fixture and example against a fresh temporary
+/// database, prints a pass/fail table and exits nonzero if any root
+/// differs. Of `cli`, only the flags that pick a code path without
+/// changing the root apply, so a flag such as `--max-ops` cannot fail a
+/// known root and no `--events-out` file is overwritten per fixture.
+fn self_test(cli: &Cli) -> ! {
+    let cli = &Cli {
+        preload_workload: cli.preload_workload,
+        two_stage_trie: cli.two_stage_trie,
+        pipelined: cli.pipelined,
+        pipeline_chunk_accounts: cli.pipeline_chunk_accounts,
+        max_trie_nodes_in_memory: cli.max_trie_nodes_in_memory,
+        ..Cli::parse_from(["ethrex-harness", "--self-test"])
+    };
+    let root_of = |name: &str, workload: &[u8]| {
+        let dir =
+            std::env::temp_dir().join(format!("statoor-ethrex-self-test-{}-{name}", process::id()));
+        let result = run(cli, &dir.to_string_lossy(), workload, &Progress::default());
+        let _ = std::fs::remove_dir_all(&dir);
+        result.state_root
+    };
+    let outcomes: Vec<Outcome> = FIXTURES
+        .iter()
+        .chain(BLOCK_FIXTURES)
+        .map(|fixture| Outcome::new(fixture, root_of(fixture.name, fixture.workload.as_bytes())))
+        .chain(
+            EXAMPLES.iter().map(|example| {
+                example.outcome(root_of(example.name, example.workload().as_bytes()))
+            }),
+        )
+        .collect();
+
+    print!("{}", render_table(&outcomes));
+    process::exit(i32::from(!outcomes.iter().all(Outcome::passed)));
+}
+
+/// Prints the health report of this binary and exits nonzero if any
+/// check failed.
+fn health_check() -> ! {
+    let report = health::run("ethrex");
+    match serde_json::to_string(&report) {
+        Ok(json) => println!("{json}"),
+        Err(e) => fatal(&format!("encode health report: {e}")),
+    }
+    process::exit(i32::from(!report.passed()));
+}
+
+/// Applies the workload read from `reader` to a fresh database at
+/// `db_path` and returns the result of its `compute_root` operation, or
+/// of the first `--max-ops` operations when that cap is reached first.
+/// The blocks of a `commit_block` workload are persisted as they end,
+/// and such a workload may stop at EOF. A `compute_root` with more
+/// operations after it ends a block too; its result is dropped.
+fn run(cli: &Cli, db_path: &str, reader: impl BufRead, progress: &Progress) -> BenchResult {
+    run_with_state(cli, db_path, reader, progress, &mut |_| {}).0
+}
+
+/// [`run`], passing the result of each `compute_root` that more
+/// operations follow to `checkpoint`, and also returning the merged
+/// per-address updates it applied, across every block of a
+/// `commit_block` workload.
+fn run_with_state(
+    cli: &Cli,
+    db_path: &str,
+    reader: impl BufRead,
+    progress: &Progress,
+    checkpoint: &mut dyn FnMut(&BenchResult),
+) -> (BenchResult, Vec<AccountUpdate>) {
+    let (mut input, preload) = match Input::open_as(
+        reader,
+        cli.workload_format,
+        cli.preload_workload.then_some(cli.preload_limit_mb),
+    ) {
+        Ok(opened) => opened,
+        Err(e) => fatal(&e.to_string()),
+    };
     let start = Instant::now();
+    let env_start = EnvSnapshot::capture();
 
     // Use in-memory store for trie operations (avoids disk I/O
     // during the trie computation phase).
-    let store = match Store::new(&cli.db, ethrex_storage::EngineType::InMemory) {
+    let store = match Store::new(db_path, ethrex_storage::EngineType::InMemory) {
         Ok(s) => s,
         Err(e) => fatal(&format!("open store: {e}")),
     };
@@ -74,204 +1266,2107 @@ fn main() {
     };
 
     // Open RocksDB backend separately for the DB write phase.
-    let db_backend: Arc<dyn StorageBackend> = match RocksDBBackend::open(&cli.db) {
+    let db_backend: Arc<dyn StorageBackend> = match RocksDBBackend::open(db_path) {
         Ok(b) => Arc::new(b),
         Err(e) => fatal(&format!("open rocksdb: {e}")),
     };
+    let guard = SpaceGuard::new(Path::new(db_path), cli.reserve_free_gb);
 
-    let mut accounts_created: usize = 0;
-    let mut contracts_created: usize = 0;
-    let mut storage_slots: usize = 0;
+    let mut counters: Counters<Address, H256> = if cli.unique_slots {
+        Counters::with_unique_slots()
+    } else {
+        Counters::default()
+    };
 
     // Accumulate updates per address so each address has one
-    // AccountUpdate with all its fields merged.
-    let mut updates: HashMap<Address, AccountUpdate> = HashMap::new();
-
-    let stdin = io::stdin();
-    for line_result in stdin.lock().lines() {
-        let line = match line_result {
-            Ok(l) => l,
-            Err(e) => fatal(&format!("read stdin: {e}")),
-        };
-        if line.is_empty() {
-            continue;
-        }
+    // AccountUpdate with all its fields merged. The map keeps std's
+    // randomly keyed SipHash on purpose: workloads can be fuzzed or
+    // crafted, and an unkeyed hasher such as FxHash degrades into long
+    // probe sequences on addresses that differ only in a few bytes.
+    let mut updates: HashMap<Address, AccountUpdate, RandomState> = HashMap::default();
+    let mut pending_code: u64 = 0;
+    let mut pending_code_bytes: usize = 0;
+    let mut blocks = Blocks::default();
+
+    let mut events = cli
+        .events_out
+        .as_deref()
+        .map(|path| match File::create(path) {
+            Ok(file) => EventLog::new(BufWriter::new(file), start),
+            Err(e) => fatal(&format!("create events file: {e}")),
+        });
+    let metrics_sink: Box<dyn Write> = match cli.metrics_out.as_deref().map(File::create) {
+        Some(Ok(file)) => Box::new(file),
+        Some(Err(e)) => fatal(&format!("create metrics file: {e}")),
+        None => Box::new(io::stderr()),
+    };
+    let mut metrics = MetricsLog::new(metrics_sink, start);
 
-        let op: Operation = match serde_json::from_str(&line) {
-            Ok(o) => o,
-            Err(e) => fatal(&format!("decode operation: {e}")),
+    let mut truncated_at_op = None;
+    let mut budget_triggered_at_op = None;
+    let mut input_bytes = InputBytes::default();
+    let mut decode = DecodeSampler::default();
+    let mut watchdog = ApplyWatchdog::default();
+    let mut root_requested = false;
+    let mut expected_root = None;
+
+    // Start of the current read or apply step, for the progress rates.
+    let mut mark = Instant::now();
+    let mut time_box = cli.duration_secs.map(|secs| TimeBox::new(mark, secs));
+    let mut sleeps = Sleeps::default();
+    let mut fingerprint = Fingerprint::default();
+    let mut reads = ReadStats::default();
+    let mut proofs = ProofStats::default();
+    let mut reserved = ReservedTouches::default();
+    let mut codes = CodeBook::default();
+    let mut tally = OpTally::default();
+    let mut header = WorkloadHeader::default();
+    let mut trailer = None;
+    let mut canaries = Canaries::default();
+    let mut assertions = Assertions::default();
+    let mut block_tags = BlockTags::default();
+    loop {
+        let (Entry { op, tenant, block }, len) = match input.next_entry(&mut decode) {
+            Ok(Some(next)) => next,
+            Ok(None) => break,
+            Err(e) => fatal(&e.to_string()),
         };
+        let now = Instant::now();
+        progress.read_op(now - mark);
+        mark = now;
+        let line = input.line_number();
+        input_bytes.record(op.name(), len);
+        fingerprint.record(&op);
+        tally.record(&op);
+        match header.record(&op, cli.max_ops) {
+            Ok(Some(ops)) => updates.reserve(ops),
+            Ok(None) => {}
+            Err(e) => fatal(&format!("line {line}: {e}")),
+        }
+        if let Some(kind) = reserved.record(&op)
+            && cli.reject_reserved_addresses
+        {
+            fatal(&format!("line {line}: {} touches {kind}", op.name()));
+        }
+        counters.record_tenant(tenant);
+        if let Some(log) = events.as_mut()
+            && let Err(e) = log.op(line, op.name())
+        {
+            fatal(&format!("write event: {e}"));
+        }
+        // A line of a later block commits the lines before it, as a
+        // commit_block between them would.
+        match block_tags.advance(block) {
+            Ok(Some(ended)) if !updates.is_empty() => {
+                blocks.commit(
+                    &store,
+                    &mut state_trie,
+                    std::mem::take(&mut updates),
+                    &db_backend,
+                    &guard,
+                    cli,
+                    &mut counters,
+                );
+                if let Some(block) = blocks.reports.last_mut() {
+                    block.block_number = Some(ended);
+                }
+                pending_code = 0;
+                pending_code_bytes = 0;
+                mark = Instant::now();
+            }
+            Ok(_) => {}
+            Err(e) => fatal(&format!("line {line}: {e}")),
+        }
 
-        match op.op.as_str() {
-            "create_account" => {
-                let addr = parse_address(&op.address);
-                let balance = parse_u256(&op.balance);
+        // The two deletions apply alike and are counted apart.
+        let selfdestruct = matches!(op, Op::Selfdestruct { .. });
+        match op {
+            Op::CreateAccount {
+                address,
+                balance,
+                nonce,
+                storage,
+            } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let balance = decode.hex(|| parse_u256(&balance, line));
                 let code_hash = *ethrex_common::constants::EMPTY_KECCACK_HASH;
 
                 let update = updates
                     .entry(addr)
                     .or_insert_with(|| AccountUpdate::new(addr));
+                update.removed = false;
                 update.info = Some(AccountInfo {
                     code_hash,
                     balance,
-                    nonce: op.nonce,
+                    nonce,
+                });
+                for (slot, value) in &storage {
+                    let slot = decode.hex(|| parse_h256(slot, line));
+                    update
+                        .added_storage
+                        .insert(slot, decode.hex(|| parse_u256(value, line)));
+                    counters.record_storage_write(addr, slot);
+                }
+                counters.record_account_created();
+            }
+            // The addresses are derived one at a time into `updates`,
+            // where each account needs its own entry for the trie anyway.
+            Op::CreateAccounts {
+                seed,
+                count,
+                balance,
+                nonce,
+            } => {
+                let seed = decode.hex(|| match derive::parse_seed(&seed) {
+                    Ok(seed) => seed,
+                    Err(e) => fatal(&format!("line {line}: {e}")),
                 });
-                accounts_created += 1;
+                let info = AccountInfo {
+                    code_hash: *ethrex_common::constants::EMPTY_KECCACK_HASH,
+                    balance: decode.hex(|| parse_u256(&balance, line)),
+                    nonce,
+                };
+                for addr in derive::addresses(&seed, count, |preimage| keccak(preimage).0) {
+                    let addr = Address::from(addr);
+                    let update = updates
+                        .entry(addr)
+                        .or_insert_with(|| AccountUpdate::new(addr));
+                    update.removed = false;
+                    update.info = Some(info.clone());
+                    counters.record_account_created();
+                }
             }
-            "set_code" => {
-                let addr = parse_address(&op.address);
-                let bytecode = hex_decode(&op.code);
+            Op::SetCode { address, code } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let bytecode = decode.hex(|| hex_decode(&code));
+                counters.record_set_code(addr, bytecode.len());
+                pending_code = pending_code.saturating_add(1);
+                pending_code_bytes = pending_code_bytes.saturating_add(bytecode.len());
                 let code = Code::from_bytecode(Bytes::from(bytecode));
-
-                let update = updates
-                    .entry(addr)
-                    .or_insert_with(|| AccountUpdate::new(addr));
-                if let Some(info) = &mut update.info {
-                    info.code_hash = code.hash;
-                } else {
-                    update.info = Some(AccountInfo {
-                        code_hash: code.hash,
-                        balance: U256::zero(),
-                        nonce: 0,
-                    });
+                set_code(&mut updates, &blocks, &state_trie, addr, code);
+            }
+            // Defining a code changes no state, so it is not an applied
+            // operation. Its hash is computed here, once.
+            Op::DefineCode { id, code } => {
+                let bytecode = decode.hex(|| hex_decode(&code));
+                if let Err(e) = codes.define(id, Code::from_bytecode(Bytes::from(bytecode))) {
+                    fatal(&format!("line {line}: {e}"));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::SetCodeRef { address, code_id } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let code = match codes.get_mut(&code_id) {
+                    Ok(code) => code.clone(),
+                    Err(e) => fatal(&format!("line {line}: {e}")),
+                };
+                counters.record_set_code(addr, code.bytecode.len());
+                pending_code = pending_code.saturating_add(1);
+                pending_code_bytes = pending_code_bytes.saturating_add(code.bytecode.len());
+                set_code(&mut updates, &blocks, &state_trie, addr, code);
+            }
+            Op::SetDelegation { address, target } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let target_addr = decode.hex(|| parse_address(&target));
+                if cli.require_delegation_targets
+                    && current_info(&updates, &blocks, &state_trie, target_addr).is_none()
+                {
+                    fatal(&format!(
+                        "line {line}: set_delegation target {target} is not an account"
+                    ));
                 }
-                update.code = Some(code);
-                contracts_created += 1;
+                let designation = delegation::designation(target_addr.0);
+                counters.record_delegation(&addr);
+                pending_code = pending_code.saturating_add(1);
+                pending_code_bytes = pending_code_bytes.saturating_add(designation.len());
+                let code = Code::from_bytecode(Bytes::from(designation.to_vec()));
+                set_code(&mut updates, &blocks, &state_trie, addr, code);
             }
-            "set_storage" => {
-                let addr = parse_address(&op.address);
-                let slot = parse_h256(&op.slot);
-                let value = parse_u256(&op.value);
+            Op::UpdateBalance {
+                address,
+                value,
+                sign,
+            } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let delta = decode.hex(|| parse_u256(&value, line));
+                update_balance(
+                    cli,
+                    &mut updates,
+                    &blocks,
+                    &state_trie,
+                    addr,
+                    delta,
+                    sign,
+                    line,
+                );
+                counters.record_balance_update();
+            }
+            Op::Transfer { from, to, value } => {
+                let from = decode.hex(|| parse_address(&from));
+                let to = decode.hex(|| parse_address(&to));
+                let delta = decode.hex(|| parse_u256(&value, line));
+                // The debit goes first, so a sender that cannot cover the
+                // transfer fails or clamps as its update_balance would.
+                update_balance(
+                    cli,
+                    &mut updates,
+                    &blocks,
+                    &state_trie,
+                    from,
+                    delta,
+                    Sign::Sub,
+                    line,
+                );
+                update_balance(
+                    cli,
+                    &mut updates,
+                    &blocks,
+                    &state_trie,
+                    to,
+                    delta,
+                    Sign::Add,
+                    line,
+                );
+                counters.record_transfer();
+            }
+            Op::IncrementNonce { address, count } => {
+                let addr = decode.hex(|| parse_address(&address));
+                increment_nonce(&mut updates, &blocks, &state_trie, addr, count, line);
+                counters.record_nonce_increment(count);
+            }
+            Op::TouchAccount { address } => {
+                let addr = decode.hex(|| parse_address(&address));
+                touch_account(&mut updates, &blocks, &state_trie, addr);
+                counters.record_account_touched();
+            }
+            Op::SetStorage {
+                address,
+                slot,
+                value,
+            } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let slot = decode.hex(|| parse_h256(&slot, line));
+                let value = decode.hex(|| parse_u256(&value, line));
 
                 let update = updates
                     .entry(addr)
                     .or_insert_with(|| AccountUpdate::new(addr));
                 update.added_storage.insert(slot, value);
-                storage_slots += 1;
+                counters.record_storage_write(addr, slot);
             }
-            "compute_root" => {
-                let update_list: Vec<AccountUpdate> = updates.into_values().collect();
-
-                emit_result(
-                    &store,
-                    &mut state_trie,
-                    &update_list,
-                    &db_backend,
-                    start,
-                    accounts_created,
-                    contracts_created,
-                    storage_slots,
+            // An empty batch stages nothing, so it cannot create the
+            // account the way a storage write to a missing one does.
+            Op::SetStorageBatch { address, entries } => {
+                let addr = decode.hex(|| parse_address(&address));
+                if !entries.is_empty() {
+                    let update = updates
+                        .entry(addr)
+                        .or_insert_with(|| AccountUpdate::new(addr));
+                    update
+                        .added_storage
+                        .extend(entries.iter().map(|(slot, value)| {
+                            let slot = decode.hex(|| parse_h256(slot, line));
+                            counters.record_storage_write(addr, slot);
+                            (slot, decode.hex(|| parse_u256(value, line)))
+                        }));
+                }
+            }
+            // Pre-Cancun selfdestruct leaves the same state as a deletion,
+            // so storage staged before it is dropped too.
+            Op::DeleteAccount { address } | Op::Selfdestruct { address } => {
+                let addr = decode.hex(|| parse_address(&address));
+                // Replaces whatever was staged, so the account's info,
+                // code and storage are dropped and the leaf is pruned.
+                updates.insert(
+                    addr,
+                    AccountUpdate {
+                        removed: true,
+                        ..AccountUpdate::new(addr)
+                    },
                 );
-                return;
+                blocks.deleted.insert(addr);
+                if selfdestruct {
+                    counters.record_selfdestruct(&addr);
+                } else {
+                    counters.record_account_deleted(&addr);
+                }
+            }
+            Op::ClearStorage { address } => {
+                let addr = decode.hex(|| parse_address(&address));
+                clear_storage(&mut updates, &blocks, &state_trie, addr, line);
+                counters.record_storage_cleared();
             }
-            other => fatal(&format!("unknown operation: {other}")),
+            // Reads look up the open tries, which hold the state as of the
+            // last commit_block. They are not applied operations.
+            Op::ReadAccount { address } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let key = keccak(addr).as_bytes().to_vec();
+                if let Err(e) = reads.time(|| state_trie.get(&key).map(|rlp| rlp.is_some())) {
+                    fatal(&format!("read account: {e}"));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::ReadStorage { address, slot } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let slot = decode.hex(|| parse_h256(&slot, line));
+                let slot_key = keccak(slot).as_bytes().to_vec();
+                let found = reads.time(|| {
+                    blocks.with_storage_trie(&state_trie, addr, |storage_trie| match storage_trie {
+                        Some(storage_trie) => storage_trie.get(&slot_key).map(|rlp| rlp.is_some()),
+                        None => Ok(false),
+                    })
+                });
+                if let Err(e) = found {
+                    fatal(&format!("read storage: {e}"));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::GetProof { address, slots } => {
+                if blocks.reports.is_empty() {
+                    fatal(&format!(
+                        "line {line}: get_proof before any committed root; end a block with \
+                         commit_block first"
+                    ));
+                }
+                let addr = decode.hex(|| parse_address(&address));
+                let slot_keys: Vec<Vec<u8>> = slots
+                    .iter()
+                    .map(|slot| {
+                        keccak(decode.hex(|| parse_h256(slot, line)))
+                            .as_bytes()
+                            .to_vec()
+                    })
+                    .collect();
+                let proved = proofs.time(|| {
+                    blocks.with_storage_trie(&state_trie, addr, |storage_trie| {
+                        prove(&state_trie, storage_trie, addr, &slot_keys)
+                    })
+                });
+                if let Err(e) = proved {
+                    fatal(&format!("get proof: {e}"));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            // Assertions read the state as the workload has written it,
+            // staged updates over the open tries. They are not applied
+            // operations either.
+            Op::AssertAccount {
+                address,
+                balance,
+                nonce,
+            } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let (actual_balance, actual_nonce) =
+                    current_info(&updates, &blocks, &state_trie, addr)
+                        .map_or((U256::zero(), 0), |info| (info.balance, info.nonce));
+                if let Some(balance) = balance {
+                    let expected = decode.hex(|| parse_u256(&balance, line));
+                    let checked =
+                        assertions.check(line, &address, "balance", expected, actual_balance);
+                    assertion_outcome(cli, checked);
+                }
+                if let Some(nonce) = nonce {
+                    let checked = assertions.check(line, &address, "nonce", nonce, actual_nonce);
+                    assertion_outcome(cli, checked);
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::AssertStorage {
+                address,
+                slot,
+                expected,
+            } => {
+                let addr = decode.hex(|| parse_address(&address));
+                let parsed_slot = decode.hex(|| parse_h256(&slot, line));
+                let expected = decode.hex(|| parse_u256(&expected, line));
+                let actual = current_slot(&updates, &blocks, &state_trie, addr, parsed_slot);
+                let checked =
+                    assertions.check(line, &address, &format!("slot {slot}"), expected, actual);
+                assertion_outcome(cli, checked);
+                mark = Instant::now();
+                continue;
+            }
+            // Taken in by `header.record` above.
+            Op::Header { .. } => {
+                mark = Instant::now();
+                continue;
+            }
+            // A checkpoint only reports; nothing is flushed or hashed.
+            Op::Checkpoint { label } => {
+                if let Err(e) = metrics.record(&label, line, &counters) {
+                    fatal(&format!("write metrics: {e}"));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            // A sleep only waits, and its time is left out of elapsed_ms
+            // and of the time box.
+            Op::Sleep { ms } => {
+                let slept = sleeps.sleep(ms);
+                if let Some(time_box) = time_box.as_mut() {
+                    time_box.extend(slept);
+                }
+                mark = Instant::now();
+                continue;
+            }
+            // A trailer only describes the lines before it.
+            Op::Trailer { counts, lines } => {
+                let report = tally.check(&counts, lines);
+                if cli.strict && !report.trailer_match {
+                    fatal(&format!(
+                        "line {line}: trailer does not match the workload: {report}"
+                    ));
+                }
+                trailer = Some(report);
+                mark = Instant::now();
+                continue;
+            }
+            // A canary checks how the value would be stored without storing it.
+            Op::Canary {
+                value,
+                expected_leaf_hash,
+                ..
+            } => {
+                let value = decode.hex(|| parse_u256(&value, line));
+                let leaf = hexfmt::fixed(keccak(value.encode_to_vec()));
+                if !canaries.check(line, &expected_leaf_hash, &leaf) && cli.strict {
+                    fatal(&format!(
+                        "line {line}: canary leaf hash {leaf} is not the expected {expected_leaf_hash}"
+                    ));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::CommitBlock { approx_gas } => {
+                blocks.commit(
+                    &store,
+                    &mut state_trie,
+                    std::mem::take(&mut updates),
+                    &db_backend,
+                    &guard,
+                    cli,
+                    &mut counters,
+                );
+                if let Some(block) = blocks.reports.last_mut() {
+                    block.approx_gas = approx_gas;
+                    block.block_number = block_tags.current();
+                }
+                pending_code = 0;
+                pending_code_bytes = 0;
+                mark = Instant::now();
+                continue;
+            }
+            Op::ComputeRoot {
+                expected_root: expected,
+            } => {
+                let more = match input.at_end() {
+                    Ok(at_end) => !at_end,
+                    Err(e) => fatal(&format!("read stdin: {e}")),
+                };
+                if more {
+                    // Right after commit_block the root is that block's,
+                    // so no empty block is committed to recompute it.
+                    if counters.ops_applied() > blocks.ops_committed || blocks.reports.is_empty() {
+                        blocks.commit(
+                            &store,
+                            &mut state_trie,
+                            std::mem::take(&mut updates),
+                            &db_backend,
+                            &guard,
+                            cli,
+                            &mut counters,
+                        );
+                        pending_code = 0;
+                        pending_code_bytes = 0;
+                    }
+                    let mut result = compute_result(
+                        TrieInput::Committed(blocks.phase_times()),
+                        &db_backend,
+                        &guard,
+                        cli,
+                        db_path,
+                        start,
+                        &sleeps,
+                        &counters,
+                        input.warnings(),
+                        progress,
+                    );
+                    progress.set_phase(Phase::Reading);
+                    result.measurement_model =
+                        Some(measurement_model(cli, true, false, blocks.spills > 0));
+                    result.root_match =
+                        expected.map(|expected| rootcheck::matches(&expected, &result.state_root));
+                    result.blocks = blocks.reports.kept().to_vec();
+                    result.fingerprint = Some(fingerprint.report());
+                    result.reads = reads.report();
+                    result.proofs = proofs.report();
+                    result.reserved = Some(reserved);
+                    result.preload.clone_from(&preload);
+                    result.trailer.clone_from(&trailer);
+                    result.canaries = canaries.report();
+                    result.assertions = assertions.report();
+                    (result.input_bytes_by_op, result.avg_bytes_per_op) =
+                        input_bytes.clone().into_parts();
+                    checkpoint(&result);
+                    mark = Instant::now();
+                    continue;
+                }
+                root_requested = true;
+                expected_root = expected;
+                break;
+            }
+        }
+
+        let applied = counters.record_applied();
+        let now = Instant::now();
+        progress.applied_op(now - mark);
+        if let Some(warning) = watchdog.applied(now - mark) {
+            eprintln!("ethrex-harness: {warning}");
+        }
+        mark = now;
+        progress.set_pending(Pending {
+            accounts: updates.len() as u64,
+            slots: counters.storage_slots(),
+            code: pending_code,
+            // Rough estimate: map entries plus storage entries and code.
+            bytes: (updates.capacity() * size_of::<(Address, AccountUpdate)>() + pending_code_bytes)
+                as u64
+                + counters.storage_slots() * size_of::<(H256, U256)>() as u64,
+        });
+        if let Some(budget) = cli.max_trie_nodes_in_memory
+            && blocks.spill_over(
+                budget,
+                &store,
+                &mut state_trie,
+                &mut updates,
+                &db_backend,
+                &guard,
+                cli,
+                &mut counters,
+            )
+        {
+            pending_code = 0;
+            pending_code_bytes = 0;
+            if budget_triggered_at_op.is_none() {
+                budget_triggered_at_op = Some(applied);
+                eprintln!(
+                    "ethrex-harness: trie node budget exceeded after {applied} operations; \
+                     the tries were written to RocksDB and reopened there"
+                );
+            }
+            mark = Instant::now();
+        }
+        if cli.max_ops.is_some_and(|max| applied >= max) {
+            truncated_at_op = Some(applied);
+            break;
+        }
+        if time_box
+            .as_mut()
+            .is_some_and(|time_box| time_box.applied(now))
+        {
+            break;
+        }
+    }
+
+    // A stream of blocks may end without compute_root.
+    let block_mode = !blocks.reports.is_empty();
+    let deadline_reached = time_box.as_ref().is_some_and(TimeBox::reached);
+    if !root_requested && truncated_at_op.is_none() && !deadline_reached && !block_mode {
+        fatal("no compute_root operation found");
+    }
+    let expected_root = expected_root
+        .or_else(|| header.expected_root(truncated_at_op.is_none() && !deadline_reached));
+
+    if let Some(log) = events.as_mut()
+        && let Err(e) = log.flush()
+    {
+        fatal(&format!("flush events: {e}"));
+    }
+    // Operations after the last commit_block form one more block.
+    if block_mode && !updates.is_empty() {
+        blocks.commit(
+            &store,
+            &mut state_trie,
+            std::mem::take(&mut updates),
+            &db_backend,
+            &guard,
+            cli,
+            &mut counters,
+        );
+        if let Some(block) = blocks.reports.last_mut() {
+            block.block_number = block_tags.current();
+        }
+    }
+    // A spilled run lands the rest of its updates on the reopened tries.
+    let spilled = !block_mode && blocks.spills > 0;
+    if spilled {
+        blocks.flush(
+            &store,
+            &mut state_trie,
+            std::mem::take(&mut updates),
+            &db_backend,
+            &guard,
+            cli,
+            &mut counters,
+            cli.prune_empty_accounts,
+        );
+        blocks.deleted.clear();
+    } else if !block_mode && cli.prune_empty_accounts {
+        counters.record_empty_pruned(blocks.prune_empty(&state_trie, &mut updates));
+    }
+    let update_list: Vec<AccountUpdate> = if block_mode {
+        std::mem::take(&mut blocks.committed)
+            .into_values()
+            .collect()
+    } else {
+        updates.into_values().collect()
+    };
+    let trie_input = if block_mode || spilled {
+        TrieInput::Committed(blocks.phase_times())
+    } else {
+        TrieInput::Compute {
+            store: &store,
+            state_trie: &mut state_trie,
+            account_updates: &update_list,
+            chunked: cli.pipelined,
+        }
+    };
+
+    let mut result = compute_result(
+        trie_input,
+        &db_backend,
+        &guard,
+        cli,
+        db_path,
+        start,
+        &sleeps,
+        &counters,
+        input.warnings(),
+        progress,
+    );
+    result.truncated_at_op = truncated_at_op;
+    result.root_match =
+        expected_root.map(|expected| rootcheck::matches(&expected, &result.state_root));
+    result.time_box = time_box.map(|time_box| time_box.report(counters.ops_applied()));
+    result.fingerprint = Some(fingerprint.report());
+    result.workload_name = header.name;
+    result.reads = reads.report();
+    result.proofs = proofs.report();
+    result.reserved = Some(reserved);
+    result.preload = preload;
+    result.trailer = trailer;
+    result.canaries = canaries.report();
+    result.assertions = assertions.report();
+    result.memory_budget_triggered_at_op = budget_triggered_at_op;
+    result.memory_budget_strategy = budget_triggered_at_op.map(|_| SPILL_TO_ROCKSDB);
+    result.memory_budget_spills = budget_triggered_at_op.map(|_| blocks.spills);
+    let chunked = !block_mode && !spilled && cli.pipelined;
+    result.measurement_model = Some(measurement_model(
+        cli,
+        block_mode,
+        chunked,
+        blocks.spills > 0,
+    ));
+    if block_mode {
+        result.warnings.extend(ignored_in_block_mode(cli));
+    }
+    (result.blocks, result.block_stats) = blocks::summarize(
+        blocks.reports,
+        cli.rolling_window,
+        cli.emit_per_block,
+        &blocks::sidecar_path(Path::new(db_path)),
+    )
+    .unwrap_or_else(|e| fatal(&e));
+    (result.input_bytes_by_op, result.avg_bytes_per_op) = input_bytes.into_parts();
+    result.decode_estimates = Some(decode.estimates());
+    result.op_rates = Some(progress.rates());
+    let apply_warning;
+    (result.apply_throughput_min_ops_per_sec, apply_warning) = watchdog.into_parts();
+    result.warnings.extend(apply_warning);
+    let environment = Environment {
+        start: env_start,
+        end: EnvSnapshot::capture(),
+    };
+    result.warnings.extend(environment.swap_warning());
+    result.environment = Some(environment);
+    (result, update_list)
+}
+
+/// Runs only the DB write phase, persisting the node set saved in the
+/// `--save-updates` file at `updates_path` to a fresh database.
+fn run_loaded(cli: &Cli, db_path: &str, updates_path: &str, progress: &Progress) -> BenchResult {
+    let start = Instant::now();
+    let env_start = EnvSnapshot::capture();
+
+    let db_backend: Arc<dyn StorageBackend> = match RocksDBBackend::open(db_path) {
+        Ok(b) => Arc::new(b),
+        Err(e) => fatal(&format!("open rocksdb: {e}")),
+    };
+    let guard = SpaceGuard::new(Path::new(db_path), cli.reserve_free_gb);
+
+    let file = match std::fs::read(updates_path) {
+        Ok(bytes) => bytes,
+        Err(e) => fatal(&format!("read updates file: {e}")),
+    };
+    let updates_list = match decode_updates(&file) {
+        Ok(list) => list,
+        Err(e) => fatal(&format!("decode updates file {updates_path}: {e}")),
+    };
+
+    let mut result = compute_result(
+        TrieInput::Loaded(updates_list),
+        &db_backend,
+        &guard,
+        cli,
+        db_path,
+        start,
+        &Sleeps::default(),
+        &Counters::default(),
+        Vec::new(),
+        progress,
+    );
+    result.skipped_phases = vec!["parse", "trie"];
+    let mut model = measurement_model(cli, false, false, false);
+    model.skip(APPLY);
+    model.skip(TRIE);
+    model.code_path("trie_source", "saved_updates");
+    result.measurement_model = Some(model);
+    result.updates_source = Some(UpdatesSource {
+        path: updates_path.to_string(),
+        keccak: hexfmt::fixed(keccak(&file)),
+    });
+    let environment = Environment {
+        start: env_start,
+        end: EnvSnapshot::capture(),
+    };
+    result.warnings.extend(environment.swap_warning());
+    result.environment = Some(environment);
+    result
+}
+
+/// Describes what the phases of a run measured. The trie is computed from
+/// the staged updates before anything is written, and the DB write phase
+/// persists its nodes; a `chunked` trie hands each finished chunk to a
+/// writer thread while it computes the next.
+fn measurement_model(
+    cli: &Cli,
+    block_mode: bool,
+    chunked: bool,
+    spilled: bool,
+) -> MeasurementModel {
+    let mut model = MeasurementModel::standard(cli.preload_workload, block_mode);
+    if chunked {
+        model.include(TRIE, "overlaps", DB_WRITE);
+        model.include(DB_WRITE, "flushes", "chunked");
+    }
+    // The trie is also hashed, and its nodes written, at every spill.
+    if spilled {
+        if !block_mode {
+            model.include(TRIE, "roots", "per_spill");
+        }
+        model.include(DB_WRITE, "flushes", "spilled");
+    }
+    model.code_path("phase_order", "trie,db_write");
+    model.code_path("trie_source", "account_updates");
+    model.code_path(
+        "trie",
+        if cli.two_stage_trie {
+            "two_stage"
+        } else {
+            "single_pass"
+        },
+    );
+    model.code_path(
+        "writes",
+        if chunked {
+            "writer_thread"
+        } else {
+            "after_trie"
+        },
+    );
+    model.code_path("trie_nodes", "written");
+    model
+}
+
+/// Applies the accumulated updates to the trie, or takes a loaded node
+/// set as is, persists the resulting nodes and assembles the benchmark
+/// result.
+#[allow(clippy::too_many_arguments)]
+fn compute_result(
+    trie_input: TrieInput,
+    db_backend: &Arc<dyn StorageBackend>,
+    guard: &SpaceGuard,
+    cli: &Cli,
+    db_path: &str,
+    start: Instant,
+    sleeps: &Sleeps,
+    counters: &Counters<Address, H256>,
+    mut warnings: Vec<String>,
+    progress: &Progress,
+) -> BenchResult {
+    #[cfg(feature = "profiling")]
+    let mut profiler = Profiler::new(&cli.profile);
+    #[cfg(not(feature = "profiling"))]
+    let mut profiler = Profiler::default();
+    let mut background = BackgroundCpu::default();
+    let mut system_metrics = BTreeMap::new();
+
+    let times = match trie_input {
+        TrieInput::Compute {
+            store,
+            state_trie,
+            account_updates,
+            chunked: true,
+        } => {
+            // The phases run at once, so they are profiled together, and
+            // the pipeline report splits CPU by thread instead of phase.
+            progress.set_phase(Phase::Trie);
+            profiler.phase("pipelined");
+            let times = pipelined_phases(
+                store,
+                state_trie,
+                account_updates,
+                cli.pipeline_chunk_accounts,
+                cli.fill_curve_points,
+                db_backend,
+                guard,
+                cli.inject_commit_latency_ms,
+                cli.keep_zero_slots,
+                start,
+                progress,
+            );
+            profiler.stop();
+            times
+        }
+        TrieInput::Committed(times) => times,
+        trie_input => sequential_phases(
+            trie_input,
+            db_backend,
+            guard,
+            cli,
+            start,
+            progress,
+            &mut profiler,
+            &mut background,
+            &mut system_metrics,
+        ),
+    };
+    let flush_cpu = CpuSample::now();
+    system_metrics.insert("after_db_write", SystemMetrics::capture());
+
+    // Phase 3: RocksDB's commit returns once the WAL is written, not
+    // synced; optionally make it durable and time that separately.
+    let post_commit_flush_ms = cli.flush_after_commit.then(|| {
+        let flush_start = Instant::now();
+        if let Err(e) = sync_tree(Path::new(db_path)) {
+            write_failed(guard, "sync db files", &e);
+        }
+        flush_start.elapsed().as_millis()
+    });
+    if post_commit_flush_ms.is_some() {
+        background.record("post_commit_flush", flush_cpu, CpuSample::now());
+    }
+
+    let (artifacts, profile_warnings) = profiler.into_parts();
+    warnings.extend(profile_warnings);
+    warnings.extend(counters.saturation_warning());
+
+    let end_metrics = SystemMetrics::capture();
+    let peak_memory = end_metrics.vm_peak_bytes.unwrap_or(0);
+    progress.set_phase(Phase::Done);
+    system_metrics.insert("end", end_metrics);
+    let (background_cpu_ms, cpu_warnings) = background.into_parts();
+    warnings.extend(cpu_warnings);
+
+    BenchResult {
+        client: "ethrex".to_string(),
+        simulated: times.injected_ms.is_some(),
+        state_root: hexfmt::fixed(times.state_root),
+        root_match: None,
+        counters: counters.totals(),
+        elapsed_ms: sleeps.elapsed_since(start).as_millis(),
+        sleep_ms: sleeps.sleep_ms(),
+        trie_time_ms: times.trie_ms,
+        db_write_time_ms: times.db_write_ms,
+        blocks: Vec::new(),
+        block_stats: None,
+        peak_memory_bytes: peak_memory,
+        db_logical_size_bytes: Some(sst_size(Path::new(db_path))).filter(|&size| size > 0),
+        warnings,
+        injected_latency_total_ms: times.injected_ms,
+        db_write_time_net_ms: times
+            .injected_ms
+            .map(|injected| times.db_write_ms.saturating_sub(injected)),
+        post_commit_flush_ms,
+        durability: if cli.flush_after_commit {
+            DURABLE
+        } else {
+            OS_BUFFERED
+        },
+        truncated_at_op: None,
+        time_box: None,
+        fingerprint: None,
+        workload_name: None,
+        reads: None,
+        proofs: None,
+        reserved: None,
+        preload: None,
+        compression: None,
+        trailer: None,
+        canaries: None,
+        assertions: None,
+        memory_budget_triggered_at_op: None,
+        memory_budget_strategy: None,
+        memory_budget_spills: None,
+        input_bytes_by_op: BTreeMap::new(),
+        avg_bytes_per_op: None,
+        decode_estimates: None,
+        op_rates: None,
+        apply_throughput_min_ops_per_sec: None,
+        background_cpu_ms,
+        system_metrics,
+        environment: None,
+        durability_probe: None,
+        code_read: None,
+        recompute: None,
+        storage_trie_time_ms: times.stage_ms.map(|(storage_ms, _)| storage_ms),
+        account_trie_time_ms: times.stage_ms.map(|(_, account_ms)| account_ms),
+        phases: times.phases,
+        pipeline: times.pipeline,
+        fill_curve: times.fill_curve,
+        skipped_phases: Vec::new(),
+        updates_source: None,
+        artifacts,
+        measurement_model: None,
+        invocation: None,
+    }
+}
+
+/// What the trie and DB write phases produced, however they ran.
+struct PhaseTimes {
+    state_root: H256,
+    trie_ms: u128,
+    db_write_ms: u128,
+    injected_ms: Option<u128>,
+    /// Storage and account trie stage times of a `--two-stage-trie` run.
+    stage_ms: Option<(u128, u128)>,
+    phases: BTreeMap<&'static str, PhaseInterval>,
+    pipeline: Option<PipelineReport>,
+    fill_curve: Vec<FillPoint>,
+}
+
+/// Computes the whole trie, then persists its nodes in one transaction.
+#[allow(clippy::too_many_arguments)]
+fn sequential_phases(
+    trie_input: TrieInput,
+    db_backend: &Arc<dyn StorageBackend>,
+    guard: &SpaceGuard,
+    cli: &Cli,
+    run_start: Instant,
+    progress: &Progress,
+    profiler: &mut Profiler,
+    background: &mut BackgroundCpu,
+    system_metrics: &mut BTreeMap<&'static str, SystemMetrics>,
+) -> PhaseTimes {
+    // Phase 1: Apply updates to the trie (trie time).
+    progress.set_phase(Phase::Trie);
+    profiler.phase("trie");
+    let trie_cpu = CpuSample::now();
+    let trie_start = Instant::now();
+    let (updates_list, stage_ms) = match trie_input {
+        TrieInput::Compute {
+            store,
+            state_trie,
+            account_updates,
+            ..
+        } if cli.two_stage_trie => {
+            let (list, storage_ms, account_ms) =
+                two_stage_trie(store, state_trie, account_updates, cli.keep_zero_slots);
+            (list, Some((storage_ms, account_ms)))
+        }
+        // ethrex's batch deletes every zero-valued slot, so kept ones go
+        // through the harness's own storage tries, untimed by stage.
+        TrieInput::Compute {
+            store,
+            state_trie,
+            account_updates,
+            ..
+        } if cli.keep_zero_slots => {
+            let (list, _, _) = two_stage_trie(store, state_trie, account_updates, true);
+            (list, None)
+        }
+        TrieInput::Compute {
+            store,
+            state_trie,
+            account_updates,
+            ..
+        } => match store.apply_account_updates_from_trie_batch(state_trie, account_updates) {
+            Ok(u) => (u, None),
+            Err(e) => fatal(&format!("apply account updates: {e}")),
+        },
+        TrieInput::Loaded(list) => (list, None),
+        TrieInput::Committed(_) => unreachable!("committed blocks have no trie phase left"),
+    };
+    let trie_end = Instant::now();
+    profiler.stop();
+    let write_cpu = CpuSample::now();
+    background.record("trie", trie_cpu, write_cpu);
+    system_metrics.insert("after_trie", SystemMetrics::capture());
+
+    // Saving is untimed and happens before any DB write, so a run that
+    // fails to write still leaves the file behind.
+    if let Some(path) = cli.save_updates.as_deref()
+        && let Err(e) = save_updates(path, &updates_list)
+    {
+        fatal(&format!("save updates to {path}: {e}"));
+    }
+
+    // Phase 2: Persist trie nodes to RocksDB (db write time).
+    progress.set_phase(Phase::DbWrite);
+    profiler.phase("db_write");
+    let db_start = Instant::now();
+    let injected_ms = write_updates_to_db(
+        db_backend,
+        &updates_list,
+        guard,
+        cli.inject_commit_latency_ms,
+    );
+    let db_end = Instant::now();
+    background.record("db_write", write_cpu, CpuSample::now());
+
+    PhaseTimes {
+        state_root: updates_list.state_trie_hash,
+        trie_ms: (trie_end - trie_start).as_millis(),
+        db_write_ms: (db_end - db_start).as_millis(),
+        injected_ms,
+        stage_ms,
+        phases: BTreeMap::from([
+            ("trie", PhaseInterval::new(run_start, trie_start, trie_end)),
+            ("db_write", PhaseInterval::new(run_start, db_start, db_end)),
+        ]),
+        pipeline: None,
+        fill_curve: Vec::new(),
+    }
+}
+
+/// Computes the trie `chunk_accounts` accounts at a time and hands each
+/// finished chunk to a writer thread, which persists it in its own
+/// transaction while the next chunk is computed. At most
+/// [`PIPELINE_QUEUE_DEPTH`] chunks wait for the writer.
+#[allow(clippy::too_many_arguments)]
+fn pipelined_phases(
+    store: &Store,
+    state_trie: &mut ethrex_trie::Trie,
+    account_updates: &[AccountUpdate],
+    chunk_accounts: u64,
+    fill_points: u64,
+    db_backend: &Arc<dyn StorageBackend>,
+    guard: &SpaceGuard,
+    latency_ms: u64,
+    keep_zero: bool,
+    run_start: Instant,
+    progress: &Progress,
+) -> PhaseTimes {
+    let cpu_start = CpuSample::now();
+    let trie_start = Instant::now();
+    let (sender, receiver) = mpsc::sync_channel::<AccountUpdatesList>(PIPELINE_QUEUE_DEPTH);
+    thread::scope(|scope| {
+        let writer = scope.spawn(move || {
+            let mut busy = Duration::ZERO;
+            let mut injected_ms: Option<u128> = None;
+            let mut interval: Option<(Instant, Instant)> = None;
+            for list in receiver {
+                let write_start = Instant::now();
+                if let Some(ms) = write_updates_to_db(db_backend, &list, guard, latency_ms) {
+                    injected_ms = Some(injected_ms.unwrap_or(0).saturating_add(ms));
+                }
+                let write_end = Instant::now();
+                busy += write_end - write_start;
+                interval = Some((interval.map_or(write_start, |(first, _)| first), write_end));
+            }
+            (busy, injected_ms, interval)
+        });
+
+        let mut blocked = Duration::ZERO;
+        let chunk_len = usize::try_from(chunk_accounts).unwrap_or(usize::MAX);
+        let chunked = trie_chunks(
+            store,
+            state_trie,
+            account_updates,
+            chunk_len,
+            fill_points,
+            keep_zero,
+            |list| {
+                let wait_start = Instant::now();
+                // The writer only hangs up by panicking, which the join
+                // below reports.
+                let _ = sender.send(list);
+                blocked += wait_start.elapsed();
+            },
+        );
+        drop(sender);
+        let trie_end = Instant::now();
+        let trie_cpu = CpuSample::now();
+        progress.set_phase(Phase::DbWrite);
+
+        let Ok((write_busy, injected_ms, write_interval)) = writer.join() else {
+            fatal("pipeline writer panicked");
+        };
+        let cpu = cpu_start
+            .zip(CpuSample::now())
+            .map(|(start, end)| end.since(start));
+        let mut phases =
+            BTreeMap::from([("trie", PhaseInterval::new(run_start, trie_start, trie_end))]);
+        if let Some((first, last)) = write_interval {
+            phases.insert("db_write", PhaseInterval::new(run_start, first, last));
+        }
+        PhaseTimes {
+            state_root: chunked.state_root,
+            trie_ms: chunked.busy.as_millis(),
+            db_write_ms: write_busy.as_millis(),
+            injected_ms,
+            stage_ms: None,
+            phases,
+            pipeline: Some(PipelineReport {
+                chunks: chunked.chunks,
+                chunk_accounts,
+                queue_depth: PIPELINE_QUEUE_DEPTH,
+                trie_blocked_ms: blocked.as_millis(),
+                trie_cpu_ms: cpu_start
+                    .zip(trie_cpu)
+                    .map(|(start, end)| end.since(start).0),
+                db_write_cpu_ms: cpu.map(|(_, other_ms)| other_ms),
+            }),
+            fill_curve: chunked.fill_curve,
+        }
+    })
+}
+
+/// The trie side of a `--pipelined` run.
+struct TrieChunks {
+    state_root: H256,
+    chunks: u64,
+    /// Time spent computing, excluding the time `each` took.
+    busy: Duration,
+    fill_curve: Vec<FillPoint>,
+}
+
+/// Applies `account_updates` to `state_trie` in chunks of
+/// `chunk_accounts` and passes each chunk's new nodes and code to `each`.
+/// Nodes a later chunk changes are passed again, so applying every chunk
+/// in order leaves the same node set as a single batch. The busy time is
+/// also split into `fill_points` fractions of the accounts.
+fn trie_chunks(
+    store: &Store,
+    state_trie: &mut ethrex_trie::Trie,
+    account_updates: &[AccountUpdate],
+    chunk_accounts: usize,
+    fill_points: u64,
+    keep_zero: bool,
+    mut each: impl FnMut(AccountUpdatesList),
+) -> TrieChunks {
+    let mut chunked = TrieChunks {
+        state_root: *EMPTY_TRIE_HASH,
+        chunks: 0,
+        busy: Duration::ZERO,
+        fill_curve: Vec::new(),
+    };
+    let mut curve = FillCurve::new(account_updates.len() as u64, fill_points);
+    // An empty workload still collects the empty trie once, as a single
+    // batch does.
+    let mut chunks: Vec<&[AccountUpdate]> = account_updates.chunks(chunk_accounts).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    let mut written_code = HashSet::new();
+    for chunk in chunks {
+        let chunk_start = Instant::now();
+        let (storage_roots, storage_updates) = storage_tries(store, chunk, keep_zero);
+        let code_updates = insert_accounts(state_trie, chunk, storage_roots, &mut written_code);
+        let (state_trie_hash, state_updates) = state_trie.collect_changes_since_last_hash();
+        let busy = chunk_start.elapsed();
+        chunked.busy += busy;
+        curve.record(chunk.len() as u64, busy);
+        chunked.state_root = state_trie_hash;
+        chunked.chunks += 1;
+        each(AccountUpdatesList {
+            state_trie_hash,
+            state_updates,
+            storage_updates,
+            code_updates,
+        });
+    }
+    chunked.fill_curve = curve.into_points();
+    chunked
+}
+
+/// Does the work of `apply_account_updates_from_trie_batch` in two timed
+/// stages: every account's storage trie on its own, then the account
+/// trie with the precomputed storage roots. Accounts are fresh, so each
+/// storage trie starts empty. Returns the updates and both stage times.
+fn two_stage_trie(
+    store: &Store,
+    state_trie: &mut ethrex_trie::Trie,
+    account_updates: &[AccountUpdate],
+    keep_zero: bool,
+) -> (AccountUpdatesList, u128, u128) {
+    let storage_start = Instant::now();
+    let (storage_roots, storage_updates) = storage_tries(store, account_updates, keep_zero);
+    let storage_ms = storage_start.elapsed().as_millis();
+
+    let account_start = Instant::now();
+    let code_updates = insert_accounts(
+        state_trie,
+        account_updates,
+        storage_roots,
+        &mut HashSet::new(),
+    );
+    let (state_trie_hash, state_updates) = state_trie.collect_changes_since_last_hash();
+    let account_ms = account_start.elapsed().as_millis();
+
+    let list = AccountUpdatesList {
+        state_trie_hash,
+        state_updates,
+        storage_updates,
+        code_updates,
+    };
+    (list, storage_ms, account_ms)
+}
+
+/// Builds each account's storage trie from empty, leaving out
+/// zero-valued slots unless `keep_zero` is set. Returns the storage
+/// roots in the order of `account_updates` and the new nodes per
+/// account hash.
+fn storage_tries(
+    store: &Store,
+    account_updates: &[AccountUpdate],
+    keep_zero: bool,
+) -> (Vec<H256>, Vec<(H256, Vec<(Nibbles, Vec<u8>)>)>) {
+    let mut storage_roots = Vec::with_capacity(account_updates.len());
+    let mut storage_updates = Vec::new();
+    for update in account_updates {
+        if update.added_storage.is_empty() {
+            storage_roots.push(*EMPTY_TRIE_HASH);
+            continue;
+        }
+        let account_hash = keccak(update.address);
+        let mut storage_trie =
+            match store.open_storage_trie(account_hash, *EMPTY_TRIE_HASH, *EMPTY_TRIE_HASH) {
+                Ok(t) => t,
+                Err(e) => fatal(&format!("open storage trie: {e}")),
+            };
+        for (slot, value) in &update.added_storage {
+            // A zero value deletes the slot, which is a no-op on an
+            // empty trie.
+            if value.is_zero() && !keep_zero {
+                continue;
+            }
+            if let Err(e) =
+                storage_trie.insert(keccak(slot).as_bytes().to_vec(), value.encode_to_vec())
+            {
+                fatal(&format!("insert storage slot: {e}"));
+            }
+        }
+        let (storage_root, nodes) = storage_trie.collect_changes_since_last_hash();
+        storage_roots.push(storage_root);
+        storage_updates.push((account_hash, nodes));
+    }
+    (storage_roots, storage_updates)
+}
+
+/// Inserts every account with its precomputed storage root into
+/// `state_trie`, removing deleted ones, and returns the code to persist:
+/// each code once, unless `written_code` already holds its hash.
+fn insert_accounts(
+    state_trie: &mut ethrex_trie::Trie,
+    account_updates: &[AccountUpdate],
+    storage_roots: Vec<H256>,
+    written_code: &mut HashSet<H256>,
+) -> Vec<(H256, Code)> {
+    let mut code_updates = Vec::new();
+    for (update, storage_root) in account_updates.iter().zip(storage_roots) {
+        let key = keccak(update.address).as_bytes().to_vec();
+        if update.removed {
+            if let Err(e) = state_trie.remove(&key) {
+                fatal(&format!("remove account: {e}"));
+            }
+            continue;
+        }
+        let mut account = AccountState {
+            storage_root,
+            ..AccountState::default()
+        };
+        if let Some(info) = &update.info {
+            account.nonce = info.nonce;
+            account.balance = info.balance;
+            account.code_hash = info.code_hash;
+            if let Some(code) = &update.code
+                && written_code.insert(info.code_hash)
+            {
+                code_updates.push((info.code_hash, code.clone()));
+            }
+        }
+        if let Err(e) = state_trie.insert(key, account.encode_to_vec()) {
+            fatal(&format!("insert account: {e}"));
+        }
+    }
+    code_updates
+}
+
+/// Magic and format version at the start of a `--save-updates` file.
+const UPDATES_MAGIC: &[u8; 16] = b"statoor-updates1";
+
+/// Writes `list` to `path`. After the magic come the state root, the
+/// state trie nodes, the storage trie nodes per account hash and the
+/// code per code hash. Every count and length is a little-endian u64;
+/// nodes are their nibble path and RLP, and code is its raw bytecode.
+fn save_updates(path: &str, list: &AccountUpdatesList) -> io::Result<()> {
+    fn put_bytes(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+        out.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        out.write_all(bytes)
+    }
+    fn put_nodes(out: &mut impl Write, nodes: &[(Nibbles, Vec<u8>)]) -> io::Result<()> {
+        out.write_all(&(nodes.len() as u64).to_le_bytes())?;
+        for (nibbles, node_rlp) in nodes {
+            put_bytes(out, nibbles.as_ref())?;
+            put_bytes(out, node_rlp)?;
+        }
+        Ok(())
+    }
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(UPDATES_MAGIC)?;
+    out.write_all(list.state_trie_hash.as_bytes())?;
+    put_nodes(&mut out, &list.state_updates)?;
+    out.write_all(&(list.storage_updates.len() as u64).to_le_bytes())?;
+    for (account_hash, nodes) in &list.storage_updates {
+        out.write_all(account_hash.as_bytes())?;
+        put_nodes(&mut out, nodes)?;
+    }
+    out.write_all(&(list.code_updates.len() as u64).to_le_bytes())?;
+    for (code_hash, code) in &list.code_updates {
+        out.write_all(code_hash.as_bytes())?;
+        put_bytes(&mut out, &code.bytecode)?;
+    }
+    out.flush()
+}
+
+/// Decodes a file written by [`save_updates`]. Code is rebuilt from its
+/// bytecode and must hash to the recorded code hash.
+fn decode_updates(file: &[u8]) -> Result<AccountUpdatesList, String> {
+    struct Cursor<'a>(&'a [u8]);
+
+    impl<'a> Cursor<'a> {
+        fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+            if self.0.len() < len {
+                return Err("truncated file".to_string());
+            }
+            let (head, rest) = self.0.split_at(len);
+            self.0 = rest;
+            Ok(head)
+        }
+        fn count(&mut self) -> Result<usize, String> {
+            let mut raw = [0; 8];
+            raw.copy_from_slice(self.take(8)?);
+            usize::try_from(u64::from_le_bytes(raw)).map_err(|e| e.to_string())
+        }
+        fn bytes(&mut self) -> Result<&'a [u8], String> {
+            let len = self.count()?;
+            self.take(len)
+        }
+        fn h256(&mut self) -> Result<H256, String> {
+            Ok(H256::from_slice(self.take(32)?))
+        }
+        fn nodes(&mut self) -> Result<Vec<(Nibbles, Vec<u8>)>, String> {
+            // Bound the preallocation by what the file can hold.
+            let count = self.count()?;
+            let mut nodes = Vec::with_capacity(count.min(self.0.len() / 16));
+            for _ in 0..count {
+                let nibbles = Nibbles::from_hex(self.bytes()?.to_vec());
+                nodes.push((nibbles, self.bytes()?.to_vec()));
+            }
+            Ok(nodes)
+        }
+    }
+
+    let mut cursor = Cursor(file);
+    if cursor.take(UPDATES_MAGIC.len()).ok() != Some(UPDATES_MAGIC.as_slice()) {
+        return Err("not a --save-updates file".to_string());
+    }
+    let state_trie_hash = cursor.h256()?;
+    let state_updates = cursor.nodes()?;
+    let mut storage_updates = Vec::new();
+    for _ in 0..cursor.count()? {
+        let account_hash = cursor.h256()?;
+        storage_updates.push((account_hash, cursor.nodes()?));
+    }
+    let mut code_updates = Vec::new();
+    for _ in 0..cursor.count()? {
+        let code_hash = cursor.h256()?;
+        let code = Code::from_bytecode(Bytes::copy_from_slice(cursor.bytes()?));
+        if code.hash != code_hash {
+            return Err(format!(
+                "code {} does not match its bytecode",
+                hexfmt::fixed(code_hash)
+            ));
+        }
+        code_updates.push((code_hash, code));
+    }
+    if !cursor.0.is_empty() {
+        return Err(format!("{} trailing bytes", cursor.0.len()));
+    }
+    Ok(AccountUpdatesList {
+        state_trie_hash,
+        state_updates,
+        storage_updates,
+        code_updates,
+    })
+}
+
+/// Prints the checked result line, spilling an oversized result to a
+/// sidecar next to the database, then the time the write took to stderr.
+fn emit_result(result: &impl Serialize, db_path: &str) {
+    let sidecar = output::sidecar_path(Path::new(db_path));
+    match output::emit(&mut io::stdout().lock(), "ethrex", result, &sidecar) {
+        Ok(emitted) => eprintln!("{emitted}"),
+        Err(e) => fatal(&e),
+    }
+}
+
+/// Persists the trie node and code updates in a single write transaction.
+/// When `latency_ms` is nonzero, sleeps that long after the commit and
+/// returns the time actually slept. Running out of disk space ends the
+/// run with a partial result.
+fn write_updates_to_db(
+    backend: &Arc<dyn StorageBackend>,
+    updates_list: &AccountUpdatesList,
+    guard: &SpaceGuard,
+    latency_ms: u64,
+) -> Option<u128> {
+    let mut tx = match backend.begin_write() {
+        Ok(tx) => tx,
+        Err(e) => write_failed(guard, "begin write", &e),
+    };
+
+    let mut written: u64 = 0;
+    let mut check_reserve = || {
+        if guard.reserve_exhausted(written) {
+            disk_full(&guard.report("ethrex", "reserve", None));
+        }
+        written += 1;
+    };
+
+    // Write state trie nodes
+    for (nibbles, node_rlp) in &updates_list.state_updates {
+        check_reserve();
+        let key = nibbles.as_ref();
+        if let Err(e) = tx.put(ACCOUNT_TRIE_NODES, key, node_rlp) {
+            write_failed(guard, "write state trie node", &e);
+        }
+    }
+
+    // Write storage trie nodes (prefixed by account hash)
+    for (account_hash, storage_nodes) in &updates_list.storage_updates {
+        for (nibbles, node_rlp) in storage_nodes {
+            check_reserve();
+            let prefixed = apply_prefix(Some(*account_hash), nibbles.clone());
+            let key = prefixed.into_vec();
+            if let Err(e) = tx.put(STORAGE_TRIE_NODES, &key, node_rlp) {
+                write_failed(guard, "write storage trie node", &e);
+            }
+        }
+    }
+
+    // Write contract code
+    for (code_hash, code) in &updates_list.code_updates {
+        check_reserve();
+        let key = code_hash.as_bytes();
+        let value = encode_code(code);
+        if let Err(e) = tx.put(ACCOUNT_CODES, key, &value) {
+            write_failed(guard, "write account code", &e);
+        }
+    }
+
+    if let Err(e) = tx.commit() {
+        write_failed(guard, "commit writes", &e);
+    }
+
+    (latency_ms > 0).then(|| inject_commit_latency(latency_ms))
+}
+
+/// Handles a failed DB write: out-of-space errors end the run with a
+/// partial result, anything else is fatal.
+fn write_failed(guard: &SpaceGuard, what: &str, err: &dyn std::fmt::Display) -> ! {
+    if is_disk_full(err) {
+        disk_full(&guard.report("ethrex", "enospc", Some(format!("{what}: {err}"))));
+    }
+    fatal(&format!("{what}: {err}"));
+}
+
+/// Prints the partial result of a run stopped for lack of disk space
+/// and exits with [`EXIT_DISK_FULL`].
+fn disk_full(report: &DiskFullReport) -> ! {
+    if let Err(e) = serde_json::to_writer(io::stdout(), report) {
+        fatal(&format!("encode result: {e}"));
+    }
+    println!();
+    process::exit(EXIT_DISK_FULL);
+}
+
+/// Encodes a code entry the way ethrex's `Store` does when it writes
+/// `ACCOUNT_CODES`: the RLP of the raw bytecode followed by the RLP of
+/// its precomputed jump targets. The store decodes both on read, so a
+/// bytecode-only value would make harness-built databases unreadable by
+/// ethrex itself.
+fn encode_code(code: &Code) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(code.bytecode.len() + 4 * code.jump_targets.len() + 12);
+    code.bytecode.encode(&mut buf);
+    code.jump_targets.encode(&mut buf);
+    buf
+}
+
+/// Sleeps for the artificial commit latency and returns the time
+/// actually slept in milliseconds.
+fn inject_commit_latency(latency_ms: u64) -> u128 {
+    let sleep_start = Instant::now();
+    std::thread::sleep(Duration::from_millis(latency_ms));
+    sleep_start.elapsed().as_millis()
+}
+
+/// Runs `--read-code` against the committed database. Reads go through
+/// `Store::get_account_code`, the path RPC serves `eth_getCode` from,
+/// rather than the raw backend the harness writes code with, so they see
+/// ethrex's code decoding and cache. Each pass opens its own Store; in
+/// the first, hashes the sample repeats may be served from that cache.
+/// The listing of the codes and the passes each get their own budget.
+fn read_code(
+    db_path: &str,
+    n: u64,
+    iterate_secs: Option<u64>,
+    read_bench_secs: Option<u64>,
+) -> CodeReadReport {
+    let (hashes, codes_truncated) = code_hashes(db_path, &Budget::start(iterate_secs));
+    let codes = hashes.len() as u64;
+    let sample = coderead::sample(hashes, n);
+    let budget = Budget::start(read_bench_secs);
+    let warm = read_code_pass(db_path, &sample, &budget);
+    let cold = coderead::cold_pass(&budget, || read_code_pass(db_path, &sample, &budget));
+    CodeReadReport::new(codes, codes_truncated, warm, cold)
+}
+
+/// Lists the hashes of the codes in `ACCOUNT_CODES`, all of them unless
+/// `budget` runs out first, which the flag reports. Store has no way to
+/// enumerate codes, so the keys are read from the backend.
+fn code_hashes(db_path: &str, budget: &Budget) -> (Vec<H256>, bool) {
+    let backend = match RocksDBBackend::open(db_path) {
+        Ok(b) => b,
+        Err(e) => fatal(&format!("open rocksdb: {e}")),
+    };
+    let read = match backend.begin_read() {
+        Ok(r) => r,
+        Err(e) => fatal(&format!("begin read: {e}")),
+    };
+    let codes = match read.prefix_iterator(ACCOUNT_CODES, &[]) {
+        Ok(codes) => codes,
+        Err(e) => fatal(&format!("iterate account codes: {e}")),
+    };
+    let mut hashes = Vec::new();
+    for entry in codes {
+        if budget.exhausted() {
+            return (hashes, true);
+        }
+        match entry {
+            Ok((key, _)) => hashes.push(H256::from_slice(&key)),
+            Err(e) => fatal(&format!("read account codes: {e}")),
+        }
+    }
+    (hashes, false)
+}
+
+/// Reads `sample` through a freshly opened Store, until `budget` runs
+/// out.
+fn read_code_pass(db_path: &str, sample: &[H256], budget: &Budget) -> CodeReadPass {
+    let store = match Store::new(db_path, ethrex_storage::EngineType::RocksDB) {
+        Ok(store) => store,
+        Err(e) => fatal(&format!("open store: {e}")),
+    };
+    let pass = coderead::measure(sample, budget, |hash| {
+        store
+            .get_account_code(*hash)
+            .map(|code| code.map(|code| code.bytecode.len()))
+            .map_err(|e| format!("read account code: {e}"))
+    });
+    match pass {
+        Ok(pass) => pass,
+        Err(e) => fatal(&e),
+    }
+}
+
+/// Runs `--recompute-root` against the committed database. Applying no
+/// updates would hand back the stored root hash untouched, so each
+/// computation instead walks every stored trie through the Store, which
+/// reads its nodes through the Store's cache, and hashes the leaves again.
+fn recompute_root(db_path: &str, passes: u64, expected: &str) -> RecomputeReport {
+    let state_root = H256::from_slice(&hex_decode(expected));
+    let root = |store: &Store| hexfmt::fixed(rehashed_root(store, state_root));
+    let report = recompute::measure(
+        passes,
+        expected,
+        || {
+            let store = match Store::new(db_path, ethrex_storage::EngineType::RocksDB) {
+                Ok(store) => store,
+                Err(e) => fatal(&format!("open store: {e}")),
+            };
+            let cold = root(&store);
+            (store, cold)
+        },
+        root,
+    );
+    match report {
+        Ok(report) => report,
+        Err(e) => fatal(&e),
+    }
+}
+
+/// Rebuilds the root of the state trie stored under `state_root` from its
+/// leaves, after checking each account's storage root the same way.
+fn rehashed_root(store: &Store, state_root: H256) -> H256 {
+    let state_trie = match store.open_state_trie(state_root) {
+        Ok(trie) => trie,
+        Err(e) => fatal(&format!("open state trie: {e}")),
+    };
+    let mut accounts = Vec::new();
+    for (path, rlp) in state_trie.into_iter().content() {
+        let account = match AccountState::decode(&rlp) {
+            Ok(account) => account,
+            Err(e) => fatal(&format!("decode account: {e}")),
+        };
+        if account.storage_root != *EMPTY_TRIE_HASH {
+            let account_hash = H256::from_slice(&path);
+            let storage_trie =
+                match store.open_storage_trie(account_hash, state_root, account.storage_root) {
+                    Ok(trie) => trie,
+                    Err(e) => fatal(&format!("open storage trie: {e}")),
+                };
+            let storage_root = ethrex_trie::Trie::compute_hash_from_unsorted_iter(
+                storage_trie.into_iter().content(),
+            );
+            if storage_root != account.storage_root {
+                fatal(&format!(
+                    "storage root of {} rehashed to {}, stored as {}",
+                    hexfmt::fixed(account_hash),
+                    hexfmt::fixed(storage_root),
+                    hexfmt::fixed(account.storage_root)
+                ));
+            }
+        }
+        accounts.push((path, rlp));
+    }
+    ethrex_trie::Trie::compute_hash_from_unsorted_iter(accounts.into_iter())
+}
+
+/// Generates the proof of `addr` and the slots hashed to `slot_keys` from
+/// the open tries, which hold the state as of the last block. A missing
+/// account or slot gets the path that proves it absent.
+fn prove(
+    state_trie: &ethrex_trie::Trie,
+    storage_trie: Option<&ethrex_trie::Trie>,
+    addr: Address,
+    slot_keys: &[Vec<u8>],
+) -> Result<proofs::Proof, ethrex_trie::TrieError> {
+    let key = keccak(addr).as_bytes().to_vec();
+    let mut bytes: usize = state_trie.get_proof(&key)?.iter().map(Vec::len).sum();
+    let found = state_trie.get(&key)?.is_some();
+    if let Some(storage_trie) = storage_trie {
+        for slot_key in slot_keys {
+            bytes += storage_trie
+                .get_proof(slot_key)?
+                .iter()
+                .map(Vec::len)
+                .sum::<usize>();
         }
     }
+    Ok(proofs::Proof {
+        bytes: bytes as u64,
+        found,
+    })
+}
 
-    fatal("no compute_root operation found");
+/// Answers `--stay-alive` queries from the rest of stdin. They are
+/// answered from the merged updates the run applied, which hold the
+/// final state of every touched address; the persisted trie nodes
+/// cannot be read back by key without ethrex's `Store`.
+fn serve_queries(updates: &[AccountUpdate]) {
+    let state: BTreeMap<Address, &AccountUpdate> = updates
+        .iter()
+        .map(|update| (update.address, update))
+        .collect();
+    if let Err(e) = query::serve(io::stdin().lock(), &mut io::stdout(), |query| {
+        answer_query(&state, query)
+    }) {
+        fatal(&e);
+    }
 }
 
-#[allow(clippy::too_many_arguments)]
-fn emit_result(
-    store: &Store,
-    state_trie: &mut ethrex_trie::Trie,
-    account_updates: &[AccountUpdate],
-    db_backend: &Arc<dyn StorageBackend>,
-    start: Instant,
-    accounts_created: usize,
-    contracts_created: usize,
-    storage_slots: usize,
-) {
-    // Phase 1: Apply updates to the trie (trie time).
-    let trie_start = Instant::now();
-    let updates_list =
-        match store.apply_account_updates_from_trie_batch(state_trie, account_updates) {
-            Ok(u) => u,
-            Err(e) => fatal(&format!("apply account updates: {e}")),
-        };
-    let trie_ms = trie_start.elapsed().as_millis();
+/// Answers `--query-db` queries from stdin by walking the tries persisted
+/// in `db_path` from their root, through a `Store` over the database.
+/// There is no run whose updates could list the state, so `dump_state`
+/// and `get_proof` are rejected.
+fn serve_persisted_queries(db_path: &str) {
+    // The backend that read the root is closed by now; RocksDB allows
+    // one open handle per directory.
+    let state_root = persisted_root(db_path);
+    let store = match Store::new(db_path, ethrex_storage::EngineType::RocksDB) {
+        Ok(store) => store,
+        Err(e) => fatal(&format!("open store: {e}")),
+    };
+    let state_trie = match store.open_state_trie(state_root) {
+        Ok(trie) => trie,
+        Err(e) => fatal(&format!("open state trie: {e}")),
+    };
+    let account = |address: &str| -> Result<Option<(H256, AccountState)>, String> {
+        let account_hash = keccak(Address::from_slice(&query_hex(address, 20)?));
+        let key = account_hash.as_bytes().to_vec();
+        match state_trie.get(&key) {
+            Ok(Some(rlp)) => AccountState::decode(&rlp)
+                .map(|account| Some((account_hash, account)))
+                .map_err(|e| format!("decode account: {e}")),
+            Ok(None) => Ok(None),
+            Err(e) => Err(format!("read account: {e}")),
+        }
+    };
+    let served = query::serve(io::stdin().lock(), &mut io::stdout(), |query| match query {
+        Query::ReadAccount { address } => {
+            Ok(account(address)?.map_or(Value::Null, |(_, account)| {
+                account_json(&AccountInfo {
+                    code_hash: account.code_hash,
+                    balance: account.balance,
+                    nonce: account.nonce,
+                })
+            }))
+        }
+        Query::ReadStorage { address, slot } => {
+            let slot_key = keccak(H256::from_slice(&query_hex(slot, 32)?))
+                .as_bytes()
+                .to_vec();
+            let Some((account_hash, account)) = account(address)? else {
+                return Ok(Value::String(hexfmt::quantity(U256::zero())));
+            };
+            let storage_trie = store
+                .open_storage_trie(account_hash, state_root, account.storage_root)
+                .map_err(|e| format!("open storage trie: {e}"))?;
+            let value = match storage_trie.get(&slot_key) {
+                Ok(Some(rlp)) => U256::decode(&rlp).map_err(|e| format!("decode slot: {e}"))?,
+                Ok(None) => U256::zero(),
+                Err(e) => return Err(format!("read storage: {e}")),
+            };
+            Ok(Value::String(hexfmt::quantity(value)))
+        }
+        Query::GetProof { .. } | Query::DumpState => Err(format!(
+            "{} is not supported by the ethrex harness with --query-db",
+            query.name()
+        )),
+    });
+    if let Err(e) = served {
+        fatal(&e);
+    }
+}
 
-    let state_root = updates_list.state_trie_hash;
+fn answer_query(state: &BTreeMap<Address, &AccountUpdate>, query: &Query) -> Result<Value, String> {
+    match query {
+        Query::ReadAccount { address } => {
+            let address = Address::from_slice(&query_hex(address, 20)?);
+            Ok(state
+                .get(&address)
+                .and_then(|update| update.info.as_ref())
+                .map_or(Value::Null, account_json))
+        }
+        Query::ReadStorage { address, slot } => {
+            let address = Address::from_slice(&query_hex(address, 20)?);
+            let slot = H256::from_slice(&query_hex(slot, 32)?);
+            let value = state
+                .get(&address)
+                .and_then(|update| update.added_storage.get(&slot))
+                .copied()
+                .unwrap_or_default();
+            Ok(Value::String(hexfmt::quantity(value)))
+        }
+        Query::GetProof { .. } => {
+            Err("get_proof is not supported by the ethrex harness".to_string())
+        }
+        Query::DumpState => Ok(Value::Array(
+            state
+                .values()
+                .filter_map(|update| {
+                    let mut account = account_json(update.info.as_ref()?);
+                    account["address"] = Value::String(hexfmt::fixed(update.address));
+                    account["storage"] = Value::Object(
+                        update
+                            .added_storage
+                            .iter()
+                            .map(|(slot, value)| {
+                                (hexfmt::fixed(slot), Value::String(hexfmt::quantity(value)))
+                            })
+                            .collect(),
+                    );
+                    Some(account)
+                })
+                .collect(),
+        )),
+    }
+}
 
-    // Phase 2: Persist trie nodes to RocksDB (db write time).
-    let db_start = Instant::now();
-    write_updates_to_db(db_backend, &updates_list);
-    let db_write_ms = db_start.elapsed().as_millis();
+fn account_json(info: &AccountInfo) -> Value {
+    serde_json::json!({
+        "nonce": info.nonce,
+        "balance": hexfmt::quantity(info.balance),
+        "code_hash": hexfmt::fixed(info.code_hash),
+    })
+}
 
-    let peak_memory = get_peak_memory_bytes();
+/// Decodes a query field of `len` bytes, failing only that query when it
+/// is malformed.
+fn query_hex(s: &str, len: usize) -> Result<Vec<u8>, String> {
+    let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+        .map_err(|e| format!("decode hex {s:?}: {e}"))?;
+    if bytes.len() != len {
+        return Err(format!(
+            "invalid {s:?}: expected {len} bytes, got {}",
+            bytes.len()
+        ));
+    }
+    Ok(bytes)
+}
 
-    let result = BenchResult {
-        client: "ethrex".to_string(),
-        state_root: format!("{state_root:#x}"),
-        accounts_created,
-        contracts_created,
-        storage_slots,
-        elapsed_ms: start.elapsed().as_millis(),
-        trie_time_ms: trie_ms,
-        db_write_time_ms: db_write_ms,
-        peak_memory_bytes: peak_memory,
+/// Applies a balance delta to the staged info of `addr`. With nothing
+/// staged, the delta applies to the account as of the last block, or to
+/// an empty account. Overflow is fatal; so is underflow unless
+/// `--clamp-balance-underflow` is set.
+#[allow(clippy::too_many_arguments)]
+fn update_balance(
+    cli: &Cli,
+    updates: &mut HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+    delta: U256,
+    sign: Sign,
+    line: u64,
+) {
+    let update = updates
+        .entry(addr)
+        .or_insert_with(|| AccountUpdate::new(addr));
+    update.removed = false;
+    let info = update.info.get_or_insert_with(|| {
+        blocks
+            .committed_info(state_trie, addr)
+            .unwrap_or(AccountInfo {
+                code_hash: *ethrex_common::constants::EMPTY_KECCACK_HASH,
+                balance: U256::zero(),
+                nonce: 0,
+            })
+    });
+    let balance = info.balance;
+    info.balance = match sign {
+        Sign::Add => match balance.checked_add(delta) {
+            Some(balance) => balance,
+            None => fatal(&format!(
+                "line {line}: update_balance: balance of {addr:?} overflows"
+            )),
+        },
+        Sign::Sub => match balance.checked_sub(delta) {
+            Some(balance) => balance,
+            None if cli.clamp_balance_underflow => U256::zero(),
+            None => fatal(&format!(
+                "line {line}: update_balance: {addr:?} holds {balance} and cannot pay {delta} \
+                 (--clamp-balance-underflow sets it to zero instead)"
+            )),
+        },
     };
+}
 
-    match serde_json::to_writer(io::stdout(), &result) {
-        Ok(()) => {
-            println!();
-        }
-        Err(e) => fatal(&format!("encode result: {e}")),
+/// Raises the nonce staged for `addr` by `count`. With no info staged,
+/// the account is read from the state trie, so one of an earlier block
+/// keeps its balance and code; an account that is not there is fatal
+/// rather than created empty, as is an overflowing nonce.
+fn increment_nonce(
+    updates: &mut HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+    count: u64,
+    line: u64,
+) {
+    let update = updates
+        .entry(addr)
+        .or_insert_with(|| AccountUpdate::new(addr));
+    if update.info.is_none() && !update.removed {
+        update.info = blocks.trie_info(state_trie, addr);
     }
+    let Some(info) = update.info.as_mut() else {
+        fatal(&format!(
+            "line {line}: increment_nonce: {addr:?} has no account"
+        ));
+    };
+    info.nonce = match info.nonce.checked_add(count) {
+        Some(nonce) => nonce,
+        None => fatal(&format!(
+            "line {line}: increment_nonce: nonce of {addr:?} overflows"
+        )),
+    };
 }
 
-fn write_updates_to_db(backend: &Arc<dyn StorageBackend>, updates_list: &AccountUpdatesList) {
-    let mut tx = match backend.begin_write() {
-        Ok(tx) => tx,
-        Err(e) => fatal(&format!("begin write: {e}")),
-    };
+/// Touches `addr`: an account staged or in the state trie keeps its info,
+/// and a missing one is staged empty, so the touch alone writes an empty
+/// leaf unless `--prune-empty-accounts` removes it.
+fn touch_account(
+    updates: &mut HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+) {
+    let update = updates
+        .entry(addr)
+        .or_insert_with(|| AccountUpdate::new(addr));
+    if update.info.is_none() && !update.removed {
+        update.info = blocks.trie_info(state_trie, addr);
+    }
+    update.removed = false;
+    update.info.get_or_insert_with(|| AccountInfo {
+        code_hash: *ethrex_common::constants::EMPTY_KECCACK_HASH,
+        balance: U256::zero(),
+        nonce: 0,
+    });
+}
 
-    // Write state trie nodes
-    for (nibbles, node_rlp) in &updates_list.state_updates {
-        let key = nibbles.as_ref();
-        if let Err(e) = tx.put(ACCOUNT_TRIE_NODES, key, node_rlp) {
-            fatal(&format!("write state trie node: {e}"));
-        }
+/// The info of `addr` as the workload has written it: the staged info,
+/// else that in the state trie as of the last block.
+fn current_info(
+    updates: &HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+) -> Option<AccountInfo> {
+    match updates.get(&addr) {
+        Some(update) if update.info.is_some() || update.removed => update.info.clone(),
+        _ => blocks.trie_info(state_trie, addr),
     }
+}
 
-    // Write storage trie nodes (prefixed by account hash)
-    for (account_hash, storage_nodes) in &updates_list.storage_updates {
-        for (nibbles, node_rlp) in storage_nodes {
-            let prefixed = apply_prefix(Some(*account_hash), nibbles.clone());
-            let key = prefixed.into_vec();
-            if let Err(e) = tx.put(STORAGE_TRIE_NODES, &key, node_rlp) {
-                fatal(&format!("write storage trie node: {e}"));
-            }
+/// The value of `slot` of `addr` as the workload has written it: the
+/// staged value, else zero if the account or its storage was wiped since
+/// the last block, else the value in its storage trie.
+fn current_slot(
+    updates: &HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+    slot: H256,
+) -> U256 {
+    if let Some(update) = updates.get(&addr) {
+        if let Some(value) = update.added_storage.get(&slot) {
+            return *value;
+        }
+        if update.removed || update.removed_storage {
+            return U256::zero();
         }
     }
+    if blocks.deleted.contains(&addr) {
+        return U256::zero();
+    }
+    blocks.with_storage_trie(state_trie, addr, |storage_trie| {
+        let Some(storage_trie) = storage_trie else {
+            return U256::zero();
+        };
+        match storage_trie.get(&keccak(slot).as_bytes().to_vec()) {
+            Ok(Some(rlp)) => match U256::decode(&rlp) {
+                Ok(value) => value,
+                Err(e) => fatal(&format!("decode slot: {e}")),
+            },
+            Ok(None) => U256::zero(),
+            Err(e) => fatal(&format!("read storage: {e}")),
+        }
+    })
+}
 
-    // Write contract code
-    for (code_hash, code) in &updates_list.code_updates {
-        let key = code_hash.as_bytes();
-        let value = code.bytecode.as_ref().encode_to_vec();
-        if let Err(e) = tx.put(ACCOUNT_CODES, key, &value) {
-            fatal(&format!("write account code: {e}"));
+/// Ends the run on a failed assertion, unless `--assertions warn` only
+/// has it printed.
+fn assertion_outcome(cli: &Cli, checked: Result<(), String>) {
+    if let Err(e) = checked {
+        if cli.assertions == AssertionMode::Warn {
+            eprintln!("ethrex-harness: assertion failed: {e}");
+        } else {
+            fatal(&format!("assertion failed: {e}"));
         }
     }
+}
 
-    if let Err(e) = tx.commit() {
-        fatal(&format!("commit writes: {e}"));
+/// Whether `info` is empty in the sense of EIP-161: zero nonce, zero
+/// balance and no code.
+fn is_empty_account(info: &AccountInfo) -> bool {
+    info.nonce == 0
+        && info.balance.is_zero()
+        && info.code_hash == *ethrex_common::constants::EMPTY_KECCACK_HASH
+}
+
+/// Deploys `code` to `addr`. An account of an earlier block keeps its
+/// balance and nonce. Each distinct code is persisted once however many
+/// accounts it is deployed to; see [`insert_accounts`].
+fn set_code(
+    updates: &mut HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+    code: Code,
+) {
+    let update = updates
+        .entry(addr)
+        .or_insert_with(|| AccountUpdate::new(addr));
+    update.removed = false;
+    if let Some(info) = &mut update.info {
+        info.code_hash = code.hash;
+    } else {
+        let (balance, nonce) = blocks
+            .committed_info(state_trie, addr)
+            .map_or((U256::zero(), 0), |info| (info.balance, info.nonce));
+        update.info = Some(AccountInfo {
+            code_hash: code.hash,
+            balance,
+            nonce,
+        });
     }
+    update.code = Some(code);
 }
 
-fn get_peak_memory_bytes() -> u64 {
-    // Read VmPeak from /proc/self/status on Linux
-    if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
-        for line in status.lines() {
-            if let Some(rest) = line.strip_prefix("VmPeak:") {
-                let trimmed = rest.trim().trim_end_matches(" kB").trim();
-                if let Ok(kb) = trimmed.parse::<u64>() {
-                    return kb * 1024;
-                }
-            }
-        }
+/// Drops the storage staged for `addr` and marks its committed storage
+/// for removal, keeping the account. Like [`increment_nonce`], it reads
+/// the account from the state trie when no info is staged, and an
+/// account that is not there is fatal.
+fn clear_storage(
+    updates: &mut HashMap<Address, AccountUpdate>,
+    blocks: &Blocks,
+    state_trie: &ethrex_trie::Trie,
+    addr: Address,
+    line: u64,
+) {
+    let update = updates
+        .entry(addr)
+        .or_insert_with(|| AccountUpdate::new(addr));
+    if update.info.is_none() && !update.removed {
+        update.info = blocks.trie_info(state_trie, addr);
+    }
+    if update.info.is_none() {
+        fatal(&format!(
+            "line {line}: clear_storage: {addr:?} has no account"
+        ));
     }
-    0
+    update.added_storage.clear();
+    update.removed_storage = true;
 }
 
 fn parse_address(s: &str) -> Address {
@@ -285,23 +3380,31 @@ fn parse_address(s: &str) -> Address {
     Address::from_slice(&bytes)
 }
 
-fn parse_h256(s: &str) -> H256 {
+/// Parses a storage slot: 32 bytes of `0x`-prefixed hex, or a decimal
+/// slot index.
+fn parse_h256(s: &str, line: u64) -> H256 {
+    if !s.starts_with("0x") {
+        return match quantity::parse(s) {
+            Ok(bytes) => H256::from(bytes),
+            Err(e) => fatal(&format!("line {line}: {e}")),
+        };
+    }
     let bytes = hex_decode(s);
     if bytes.len() != 32 {
         fatal(&format!(
-            "invalid H256: expected 32 bytes, got {}",
+            "line {line}: invalid H256 {s:?}: expected 32 bytes, got {}",
             bytes.len()
         ));
     }
     H256::from_slice(&bytes)
 }
 
-fn parse_u256(s: &str) -> U256 {
-    if s.is_empty() {
-        return U256::zero();
+/// Parses a balance or value, `0x`-prefixed hex or decimal.
+fn parse_u256(s: &str, line: u64) -> U256 {
+    match quantity::parse(s) {
+        Ok(bytes) => U256::from_big_endian(&bytes),
+        Err(e) => fatal(&format!("line {line}: {e}")),
     }
-    let bytes = hex_decode(s);
-    U256::from_big_endian(&bytes)
 }
 
 fn hex_decode(s: &str) -> Vec<u8> {
@@ -324,3 +3427,1128 @@ fn fatal(msg: &str) -> ! {
     eprintln!("ethrex-harness: {msg}");
     process::exit(1);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use clap::CommandFactory;
+    use statoor_common::fixtures::Fixture;
+
+    use super::*;
+
+    #[test]
+    fn two_stage_trie_matches_single_call() {
+        for fixture in FIXTURES {
+            let roots: Vec<String> = [&[][..], &["--two-stage-trie"]]
+                .into_iter()
+                .map(|flags| run_fixture(flags, fixture.workload).state_root)
+                .collect();
+            assert_eq!(roots[0], roots[1], "{}", fixture.name);
+            assert_eq!(roots[1], fixture.expected_root, "{}", fixture.name);
+        }
+    }
+
+    /// A database directory of its own under the temp dir, removed when
+    /// dropped, so a failing test leaves nothing behind.
+    struct TempDb(std::path::PathBuf);
+
+    impl TempDb {
+        fn new() -> Self {
+            static NEXT: AtomicU64 = AtomicU64::new(0);
+            let n = NEXT.fetch_add(1, Ordering::Relaxed);
+            TempDb(std::env::temp_dir().join(format!("statoor-ethrex-{}-{n}", process::id())))
+        }
+    }
+
+    impl std::ops::Deref for TempDb {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// The `Cli` of a run on `dir` with `flags`.
+    fn cli_on(dir: &Path, flags: &[&str]) -> Cli {
+        let db = dir.to_string_lossy();
+        Cli::parse_from(["ethrex-harness", "--db", &*db].iter().chain(flags))
+    }
+
+    /// Runs `workload` on a fresh database with `flags`.
+    fn run_fixture(flags: &[&str], workload: &str) -> BenchResult {
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, flags);
+        run(
+            &cli,
+            &dir.to_string_lossy(),
+            workload.as_bytes(),
+            &Progress::default(),
+        )
+    }
+
+    /// The self-test fixture called `name`.
+    fn fixture(name: &str) -> &'static Fixture {
+        FIXTURES
+            .iter()
+            .chain(BLOCK_FIXTURES)
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("no fixture {name}"))
+    }
+    /// The address of the `n`th account of a hand-written workload.
+    fn numbered(n: u8) -> String {
+        format!("0x10000000000000000000000000000000000000{n:02x}")
+    }
+
+    /// A `create_account` line for [`numbered`] account `n`.
+    fn create_numbered(n: u8, balance: &str, nonce: u8) -> String {
+        format!(
+            r#"{{"op":"create_account","address":"{}","balance":"{balance}","nonce":{nonce}}}"#,
+            numbered(n)
+        )
+    }
+
+    /// Every (table, key) a node set persists, with its final value when
+    /// the lists are written in order.
+    fn persisted(
+        lists: impl IntoIterator<Item = AccountUpdatesList>,
+    ) -> BTreeMap<(&'static str, Vec<u8>), Vec<u8>> {
+        let mut persisted = BTreeMap::new();
+        for list in lists {
+            for (nibbles, node_rlp) in list.state_updates {
+                persisted.insert(("account", nibbles.as_ref().to_vec()), node_rlp);
+            }
+            for (account_hash, nodes) in list.storage_updates {
+                for (nibbles, node_rlp) in nodes {
+                    let key = apply_prefix(Some(account_hash), nibbles).into_vec();
+                    persisted.insert(("storage", key), node_rlp);
+                }
+            }
+            for (code_hash, code) in list.code_updates {
+                persisted.insert(("code", code_hash.as_bytes().to_vec()), encode_code(&code));
+            }
+        }
+        persisted
+    }
+
+    #[test]
+    fn pipelined_matches_sequential() {
+        let mut workload = String::new();
+        for i in 0..300_u32 {
+            workload.push_str(&format!(
+                r#"{{"op":"create_account","address":"0x{i:040x}","balance":"0x01","nonce":0,"storage":{{"0x{:064x}":"0x{i:x}","0x{i:064x}":"0x02"}}}}"#,
+                u32::MAX
+            ));
+            workload.push('\n');
+            if i % 10 == 0 {
+                workload.push_str(&format!(
+                    r#"{{"op":"set_code","address":"0x{i:040x}","code":"0x60{:02x}"}}"#,
+                    i % 7
+                ));
+                workload.push('\n');
+            }
+        }
+        workload.push_str(r#"{"op":"compute_root"}"#);
+        let workloads = FIXTURES
+            .iter()
+            .map(|f| (f.name, f.workload, Some(f.expected_root)))
+            .chain([("generated", workload.as_str(), None)]);
+
+        for (name, workload, expected_root) in workloads {
+            let dir = TempDb::new();
+            let cli = cli_on(&dir, &[]);
+            let (sequential, updates) = run_with_state(
+                &cli,
+                &dir.to_string_lossy(),
+                workload.as_bytes(),
+                &Progress::default(),
+                &mut |_| {},
+            );
+            let pipelined =
+                run_fixture(&["--pipelined", "--pipeline-chunk-accounts", "7"], workload);
+            assert_eq!(pipelined.state_root, sequential.state_root, "{name}");
+            if let Some(expected_root) = expected_root {
+                assert_eq!(pipelined.state_root, expected_root, "{name}");
+            }
+            let chunks = pipelined.pipeline.map(|p| p.chunks);
+            assert_eq!(
+                chunks,
+                Some(updates.len().div_ceil(7).max(1) as u64),
+                "{name}"
+            );
+            assert!(pipelined.phases.contains_key("trie"), "{name}");
+
+            let trie_dir = TempDb::new();
+            let trie_dir = trie_dir.to_string_lossy().into_owned();
+            let fresh_trie = || {
+                let store = Store::new(&trie_dir, ethrex_storage::EngineType::InMemory)
+                    .unwrap_or_else(|e| panic!("open store: {e}"));
+                let trie = store
+                    .open_state_trie(*EMPTY_TRIE_HASH)
+                    .unwrap_or_else(|e| panic!("open state trie: {e}"));
+                (store, trie)
+            };
+            let (store, mut trie) = fresh_trie();
+            let batch = store
+                .apply_account_updates_from_trie_batch(&mut trie, &updates)
+                .unwrap_or_else(|e| panic!("apply account updates: {e}"));
+            let (store, mut trie) = fresh_trie();
+            let mut lists = Vec::new();
+            let chunked = trie_chunks(&store, &mut trie, &updates, 7, 10, false, |list| {
+                lists.push(list);
+            });
+            assert_eq!(chunked.state_root, batch.state_trie_hash, "{name}");
+            let curve_accounts: u64 = chunked.fill_curve.iter().map(|p| p.accounts).sum();
+            assert_eq!(curve_accounts, updates.len() as u64, "{name}");
+            assert_eq!(persisted(lists), persisted([batch]), "{name}");
+        }
+    }
+
+    #[test]
+    fn memory_budget_spills_to_rocksdb() {
+        // Every operation past the budget spills, so each one is applied
+        // to tries reopened over RocksDB.
+        for fixture in FIXTURES.iter().chain(BLOCK_FIXTURES) {
+            let result = run_fixture(&["--max-trie-nodes-in-memory", "1"], fixture.workload);
+            assert_eq!(result.state_root, fixture.expected_root, "{}", fixture.name);
+            if fixture.name == "account_with_storage" {
+                // One account, then its first storage slot, crosses the
+                // budget.
+                assert_eq!(result.memory_budget_triggered_at_op, Some(3));
+                assert_eq!(result.memory_budget_strategy, Some(SPILL_TO_ROCKSDB));
+                assert!(result.memory_budget_spills >= Some(1));
+            }
+        }
+    }
+
+    #[test]
+    fn kept_zero_slots_agree_on_every_trie_path() {
+        let fixture = fixture("zeroed_slot");
+        let as_block = fixture
+            .workload
+            .replace(r#"{"op":"compute_root"}"#, r#"{"op":"commit_block"}"#);
+        let runs: [(&[&str], &str); 5] = [
+            (&["--keep-zero-slots"], fixture.workload),
+            (&["--keep-zero-slots", "--two-stage-trie"], fixture.workload),
+            (&["--keep-zero-slots", "--pipelined"], fixture.workload),
+            (
+                &["--keep-zero-slots", "--max-trie-nodes-in-memory", "1"],
+                fixture.workload,
+            ),
+            (&["--keep-zero-slots"], &as_block),
+        ];
+        let roots: Vec<String> = runs
+            .into_iter()
+            .map(|(flags, workload)| run_fixture(flags, workload).state_root)
+            .collect();
+        assert_ne!(roots[0], fixture.expected_root);
+        assert!(roots.iter().all(|root| *root == roots[0]), "{roots:?}");
+    }
+
+    /// Generates `accounts` accounts, each with one storage slot, and a
+    /// `compute_root` as they are read.
+    struct LargeWorkload {
+        accounts: u64,
+        next: u64,
+        line: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Read for LargeWorkload {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pos == self.line.len() {
+                self.line.clear();
+                self.pos = 0;
+                match self.next.cmp(&self.accounts) {
+                    std::cmp::Ordering::Less => writeln!(
+                        self.line,
+                        r#"{{"op":"create_account","address":"0x{:040x}","balance":"0x01","nonce":0,"storage":{{"0x{:064x}":"0x01"}}}}"#,
+                        self.next + 1,
+                        self.next
+                    )?,
+                    std::cmp::Ordering::Equal => {
+                        self.line.extend_from_slice(b"{\"op\":\"compute_root\"}\n");
+                    }
+                    std::cmp::Ordering::Greater => return Ok(0),
+                }
+                self.next += 1;
+            }
+            let read = (&self.line[self.pos..]).read(buf)?;
+            self.pos += read;
+            Ok(read)
+        }
+    }
+
+    /// Runs a workload whose tries outgrow the memory of its cgroup,
+    /// which kills the run unless the budget bounds what it holds. The
+    /// test reruns itself in a scope of the user's systemd:
+    /// `cargo test memory_budget_bounds -- --ignored`.
+    #[test]
+    #[ignore = "needs systemd-run --user and takes minutes"]
+    fn memory_budget_bounds_a_large_run() {
+        const LIMITED: &str = "STATOOR_ETHREX_MEMORY_LIMITED";
+        if std::env::var_os(LIMITED).is_none() {
+            let exe = std::env::current_exe().unwrap_or_else(|e| panic!("test binary: {e}"));
+            let status = process::Command::new("systemd-run")
+                .args(["--user", "--scope", "--quiet"])
+                .args(["-p", "MemoryMax=512M", "-p", "MemorySwapMax=0"])
+                .arg(exe)
+                .args([
+                    "--ignored",
+                    "--exact",
+                    "tests::memory_budget_bounds_a_large_run",
+                ])
+                .env(LIMITED, "1")
+                .status()
+                .unwrap_or_else(|e| panic!("systemd-run: {e}"));
+            assert!(
+                status.success(),
+                "the budgeted run failed in 512 MiB: {status}"
+            );
+            return;
+        }
+        let workload = LargeWorkload {
+            accounts: 4_000_000,
+            next: 0,
+            line: Vec::new(),
+            pos: 0,
+        };
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &["--max-trie-nodes-in-memory", "100000"]);
+        let result = run(
+            &cli,
+            &dir.to_string_lossy(),
+            io::BufReader::new(workload),
+            &Progress::default(),
+        );
+        assert_eq!(result.counters.accounts_created, 4_000_000);
+        assert!(result.memory_budget_spills > Some(10));
+    }
+
+    #[test]
+    fn health_fixture_matches_its_root() {
+        let cli = Cli::parse_from(["ethrex-harness", "--health-check"]);
+        let dir = TempDb::new();
+        let result = run(
+            &cli,
+            &dir.to_string_lossy(),
+            HEALTH_FIXTURE.workload.as_bytes(),
+            &Progress::default(),
+        );
+        assert_eq!(result.state_root, HEALTH_FIXTURE.expected_root);
+    }
+
+    #[test]
+    fn invocation_records_every_flag() {
+        let cli = Cli::parse_from(["ethrex-harness", "--db", "/tmp/statoor-ethrex"]);
+        let mut fields: Vec<String> = serde_json::to_value(&cli)
+            .ok()
+            .and_then(|v| v.as_object().map(|o| o.keys().cloned().collect()))
+            .unwrap_or_default();
+        fields.sort();
+        let mut flags: Vec<String> = Cli::command()
+            .get_arguments()
+            .map(|arg| arg.get_id().to_string())
+            .filter(|id| id != "help")
+            .collect();
+        flags.sort();
+        assert_eq!(
+            fields, flags,
+            "every flag must be serialized in the invocation"
+        );
+
+        let invocation = invocation::resolved(&cli).unwrap_or_default();
+        assert_eq!(invocation["db"], "/tmp/statoor-ethrex");
+        assert_eq!(invocation["pipeline_chunk_accounts"], 4096);
+        assert_eq!(invocation["pipelined"], false);
+        assert!(invocation.get("max_ops").is_none());
+    }
+
+    #[test]
+    fn loaded_updates_reproduce_the_root() {
+        for fixture in FIXTURES {
+            let (saved_dir, loaded_dir) = (TempDb::new(), TempDb::new());
+            let updates = format!("{}.updates", saved_dir.to_string_lossy());
+            let saved = run(
+                &cli_on(&saved_dir, &["--save-updates", &updates]),
+                &saved_dir.to_string_lossy(),
+                fixture.workload.as_bytes(),
+                &Progress::default(),
+            );
+            let loaded = run_loaded(
+                &cli_on(&loaded_dir, &[]),
+                &loaded_dir.to_string_lossy(),
+                &updates,
+                &Progress::default(),
+            );
+            let _ = std::fs::remove_file(&updates);
+            assert_eq!(saved.state_root, fixture.expected_root, "{}", fixture.name);
+            assert_eq!(loaded.state_root, saved.state_root, "{}", fixture.name);
+            assert_eq!(loaded.skipped_phases, ["parse", "trie"]);
+            let phases = |result: &BenchResult| {
+                result
+                    .measurement_model
+                    .as_ref()
+                    .map(|model| model.phases.clone())
+            };
+            assert_eq!(phases(&saved), Some(vec![APPLY, TRIE, DB_WRITE]));
+            assert_eq!(phases(&loaded), Some(vec![DB_WRITE]));
+            let keccak = loaded.updates_source.map(|source| source.keccak);
+            assert!(keccak.is_some_and(|k| hexfmt::is_fixed(&k, 32)));
+        }
+        assert!(decode_updates(b"statoor-updates1").is_err());
+    }
+
+    #[test]
+    fn zero_padded_addresses_apply_at_a_steady_rate() {
+        let mut workload = String::new();
+        for i in 0..20_000_u32 {
+            // Each address is the index at a shifting offset in zeroes.
+            let offset = (i % 17) as usize;
+            let mut address = [0_u8; 20];
+            address[offset..offset + 4].copy_from_slice(&i.to_be_bytes());
+            let address: String = address.iter().map(|b| format!("{b:02x}")).collect();
+            workload.push_str(&format!(
+                r#"{{"op":"create_account","address":"0x{address}","balance":"0x01","nonce":0}}"#
+            ));
+            workload.push('\n');
+        }
+        workload.push_str(r#"{"op":"compute_root"}"#);
+
+        let result = run_fixture(&[], &workload);
+        assert!(result.apply_throughput_min_ops_per_sec.is_some());
+        assert!(
+            !result
+                .warnings
+                .iter()
+                .any(|w| w.contains("apply throughput")),
+            "{:?}",
+            result.warnings
+        );
+    }
+
+    #[test]
+    fn blocks_build_on_each_other() {
+        for fixture in BLOCK_FIXTURES {
+            let dir = TempDb::new();
+            let cli = cli_on(&dir, &[]);
+            let (result, updates) = run_with_state(
+                &cli,
+                &dir.to_string_lossy(),
+                fixture.workload.as_bytes(),
+                &Progress::default(),
+                &mut |_| {},
+            );
+            assert_eq!(result.state_root, fixture.expected_root, "{}", fixture.name);
+            assert_eq!(result.blocks.len(), 2, "{}", fixture.name);
+            assert_eq!(
+                result.blocks.last().map(|block| block.state_root.as_str()),
+                Some(fixture.expected_root),
+                "{}",
+                fixture.name
+            );
+            assert_eq!(
+                (result.trie_time_ms, result.db_write_time_ms),
+                blocks::totals(&result.blocks),
+                "{}",
+                fixture.name
+            );
+            // set_code in the second block keeps the nonce of the first.
+            if fixture.name == "two_blocks" {
+                let nonce = updates
+                    .iter()
+                    .find_map(|update| update.info.as_ref())
+                    .map(|info| info.nonce);
+                assert_eq!(nonce, Some(1));
+            }
+            // Reserved addresses are counted without the flag rejecting them.
+            if fixture.name == "reserved_across_blocks" {
+                let touches = result.reserved.unwrap_or_default();
+                assert_eq!(touches.zero_address_touches, 2);
+                assert_eq!(touches.precompile_touches, 5);
+                assert_eq!(touches.all_ones_address_touches, 2);
+            }
+        }
+    }
+
+    #[test]
+    fn queries_read_the_applied_state() {
+        let fixture = fixture("account_with_storage");
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let (result, updates) = run_with_state(
+            &cli,
+            &dir.to_string_lossy(),
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+            &mut |_| {},
+        );
+        assert!(hexfmt::is_fixed(&result.state_root, 32));
+        let state = updates.iter().map(|u| (u.address, u)).collect();
+
+        let address = "0x095e7baea6a6c7c4c2dfeb977efac326af552d87".to_string();
+        let account = answer_query(
+            &state,
+            &Query::ReadAccount {
+                address: address.clone(),
+            },
+        );
+        assert_eq!(account.map(|a| a["nonce"].clone()), Ok(Value::from(1)));
+        let slot = format!("0x{}", "0".repeat(63) + "1");
+        let value = answer_query(&state, &Query::ReadStorage { address, slot });
+        assert_eq!(value, Ok(Value::from("0x2a")));
+        let dump = answer_query(&state, &Query::DumpState).unwrap_or_default();
+        assert_eq!(dump.as_array().map(Vec::len), Some(1));
+        let account = &dump[0];
+        assert!(hexfmt::is_fixed(
+            account["address"].as_str().unwrap_or_default(),
+            20
+        ));
+        assert!(hexfmt::is_fixed(
+            account["code_hash"].as_str().unwrap_or_default(),
+            32
+        ));
+        assert!(hexfmt::is_quantity(
+            account["balance"].as_str().unwrap_or_default()
+        ));
+        let storage = account["storage"].as_object().cloned().unwrap_or_default();
+        assert_eq!(storage.len(), 2);
+        for (slot, value) in &storage {
+            assert!(hexfmt::is_fixed(slot, 32), "{slot}");
+            assert!(
+                hexfmt::is_quantity(value.as_str().unwrap_or_default()),
+                "{value}"
+            );
+        }
+        assert!(
+            answer_query(
+                &state,
+                &Query::GetProof {
+                    address: String::new(),
+                    slots: Vec::new()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn queries_miss_the_storage_of_a_recreated_account() {
+        let fixture = fixture("recreated_across_blocks");
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let (result, updates) = run_with_state(
+            &cli,
+            &dir.to_string_lossy(),
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+            &mut |_| {},
+        );
+        assert_eq!(result.state_root, fixture.expected_root);
+        let state = updates.iter().map(|u| (u.address, u)).collect();
+
+        let address = "0x095e7baea6a6c7c4c2dfeb977efac326af552d87".to_string();
+        let slot = |n: u8| format!("0x{n:064x}");
+        let read = |n| {
+            answer_query(
+                &state,
+                &Query::ReadStorage {
+                    address: address.clone(),
+                    slot: slot(n),
+                },
+            )
+        };
+        assert_eq!(read(0), Ok(Value::from("0x0")));
+        assert_eq!(read(1), Ok(Value::from("0x2a")));
+        let dump = answer_query(&state, &Query::DumpState).unwrap_or_default();
+        let storage = dump[0]["storage"].as_object().cloned().unwrap_or_default();
+        assert_eq!(storage.keys().collect::<Vec<_>>(), [&slot(1)]);
+    }
+
+    #[test]
+    fn reads_see_the_last_block() {
+        let fixture = fixture("account_with_storage");
+        let address = "0x095e7baea6a6c7c4c2dfeb977efac326af552d87";
+        let later = "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b";
+        let slot = |n: u8| format!("0x{n:064x}");
+        let block = fixture
+            .workload
+            .replace(r#"{"op":"compute_root"}"#, r#"{"op":"commit_block"}"#);
+        let reads = [
+            format!(r#"{{"op":"read_account","address":"{address}"}}"#),
+            format!(r#"{{"op":"read_account","address":"{later}"}}"#),
+            format!(
+                r#"{{"op":"read_storage","address":"{address}","slot":"{}"}}"#,
+                slot(1)
+            ),
+            format!(
+                r#"{{"op":"read_storage","address":"{address}","slot":"{}"}}"#,
+                slot(9)
+            ),
+            format!(r#"{{"op":"create_account","address":"{later}","balance":"0x1","nonce":0}}"#),
+            // Written in the open block, so not committed yet.
+            format!(r#"{{"op":"read_account","address":"{later}"}}"#),
+            r#"{"op":"compute_root"}"#.to_string(),
+        ];
+        let with_reads = format!("{block}{}\n", reads.join("\n"));
+        let without_reads: String = with_reads
+            .lines()
+            .filter(|line| !line.contains(r#""op":"read_"#))
+            .flat_map(|line| [line, "\n"])
+            .collect();
+
+        let results = [&with_reads, &without_reads].map(|workload| run_fixture(&[], workload));
+        let reported = results[0].reads.map(|r| (r.reads, r.read_misses));
+        assert_eq!(reported, Some((5, 3)));
+        assert_eq!(results[1].reads, None);
+        assert_eq!(results[0].state_root, results[1].state_root);
+        assert_eq!(
+            results[0].counters.accounts_created,
+            results[1].counters.accounts_created
+        );
+    }
+
+    #[test]
+    fn examples_reach_their_roots() {
+        for example in EXAMPLES {
+            let result = run_fixture(&[], &example.workload());
+            assert_eq!(
+                result.state_root,
+                example.expected_root(),
+                "{}",
+                example.name
+            );
+        }
+    }
+
+    #[test]
+    fn proofs_cover_present_and_absent_accounts() {
+        let fixture = fixture("account_with_storage");
+        let block = fixture
+            .workload
+            .replace(r#"{"op":"compute_root"}"#, r#"{"op":"commit_block"}"#);
+        let proofs = [
+            r#"{"op":"get_proof","address":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","slots":["0x01"]}"#,
+            r#"{"op":"get_proof","address":"0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b"}"#,
+            r#"{"op":"compute_root"}"#,
+        ];
+        let workload = format!("{block}{}\n", proofs.join("\n"));
+        let result = run_fixture(&[], &workload);
+        let Some(report) = result.proofs else {
+            panic!("get_proof lines must be reported");
+        };
+        assert_eq!((report.proofs_generated, report.proof_exclusions), (2, 1));
+        assert!(report.proof_bytes_total > 0);
+        assert_eq!(result.state_root, fixture.expected_root);
+    }
+
+    #[test]
+    fn empty_accounts_are_pruned_on_request() {
+        let fixture = fixture("single_account");
+        let workload = fixture.workload.replace(
+            r#"{"op":"compute_root"}"#,
+            concat!(
+                r#"{"op":"touch_account","address":"0x00000000000000000000000000000000000000aa"}"#,
+                "\n",
+                r#"{"op":"create_account","address":"0x00000000000000000000000000000000000000bb","balance":"0x0","nonce":0}"#,
+                "\n",
+                r#"{"op":"compute_root"}"#,
+            ),
+        );
+        let kept = run_fixture(&[], &workload);
+        assert_ne!(kept.state_root, fixture.expected_root);
+        assert_eq!(kept.counters.accounts_touched, 1);
+        assert_eq!(kept.counters.empty_accounts_pruned, 0);
+
+        let pruned = run_fixture(&["--prune-empty-accounts"], &workload);
+        assert_eq!(pruned.state_root, fixture.expected_root);
+        assert_eq!(pruned.counters.empty_accounts_pruned, 2);
+    }
+
+    #[test]
+    fn compute_root_with_more_operations_emits_a_checkpoint() {
+        let fixture = fixture("two_roots");
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let mut checkpoints = Vec::new();
+        let (result, _) = run_with_state(
+            &cli,
+            &dir.to_string_lossy(),
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+            &mut |checkpoint| {
+                checkpoints.push((
+                    checkpoint.state_root.clone(),
+                    checkpoint.counters.accounts_created,
+                ));
+            },
+        );
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(result.counters.accounts_created, 4);
+        let roots: Vec<&str> = result
+            .blocks
+            .iter()
+            .map(|b| b.state_root.as_str())
+            .collect();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(checkpoints, [(roots[0].to_string(), 2)]);
+    }
+
+    #[test]
+    fn block_tags_commit_each_block() {
+        let fixture = fixture("account_with_storage");
+        // The first two lines in block 5, the storage in block 6.
+        let workload: String = fixture
+            .workload
+            .lines()
+            .enumerate()
+            .map(|(i, line)| match line.strip_suffix('}') {
+                Some(op) if !line.contains("compute_root") => {
+                    format!("{op},\"block\":{}}}\n", if i < 2 { 5 } else { 6 })
+                }
+                _ => format!("{line}\n"),
+            })
+            .collect();
+        let result = run_fixture(&[], &workload);
+        let tagged: Vec<_> = result
+            .blocks
+            .iter()
+            .map(|block| (block.block_number, block.ops))
+            .collect();
+        assert_eq!(tagged, [(Some(5), 2), (Some(6), 2)]);
+        assert_eq!(result.state_root, fixture.expected_root);
+    }
+
+    #[test]
+    fn approx_gas_reaches_the_block_report() {
+        let workload = BLOCK_FIXTURES[0].workload.replace(
+            r#"{"op":"commit_block"}"#,
+            r#"{"op":"commit_block","approx_gas":121000}"#,
+        );
+        let result = run_fixture(&[], &workload);
+        let gas: Vec<Option<u64>> = result.blocks.iter().map(|b| b.approx_gas).collect();
+        // The block ended by compute_root has no estimate.
+        assert_eq!(gas, [Some(121_000), None]);
+    }
+
+    #[test]
+    fn preloaded_workload_reaches_the_same_root() {
+        let fixture = &BLOCK_FIXTURES[0];
+        let result = run_fixture(&["--preload-workload"], fixture.workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        let preload = result
+            .preload
+            .unwrap_or_else(|| panic!("no preload report"));
+        assert!(preload.preloaded);
+        assert_eq!(preload.preload_ops, fixture.workload.lines().count() as u64);
+    }
+
+    #[test]
+    fn derived_accounts_are_counted_as_created() {
+        let fixture = fixture("derived_accounts");
+        let result = run_fixture(&[], fixture.workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(result.counters.accounts_created, 3);
+    }
+
+    #[test]
+    fn delegations_are_counted_apart_from_contracts() {
+        let fixture = fixture("delegated_account");
+        let result = run_fixture(&["--require-delegation-targets"], fixture.workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(result.counters.contracts_created, 1);
+        assert_eq!(result.counters.delegations_created, 1);
+        assert_eq!(result.counters.code_bytes, 6 + 23);
+    }
+
+    #[test]
+    fn sleeps_are_reported_apart_from_elapsed() {
+        let fixture = fixture("single_account");
+        let workload = format!("{{\"op\":\"sleep\",\"ms\":50}}\n{}", fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert!(result.sleep_ms.is_some_and(|ms| ms >= 50));
+        assert_eq!(result.counters.accounts_created, 1);
+    }
+
+    #[test]
+    fn checkpoints_report_without_changing_the_root() {
+        let fixture = fixture("account_with_storage");
+        let dir = TempDb::new();
+        let metrics = dir.with_extension("metrics");
+        let metrics_arg = metrics.to_string_lossy().into_owned();
+        let cli = cli_on(&dir, &["--metrics-out", &metrics_arg]);
+        let workload: String = fixture
+            .workload
+            .lines()
+            .enumerate()
+            .map(|(i, line)| format!("{{\"op\":\"checkpoint\",\"label\":\"cp{i}\"}}\n{line}\n"))
+            .collect();
+        let result = run(
+            &cli,
+            &dir.to_string_lossy(),
+            workload.as_bytes(),
+            &Progress::default(),
+        );
+        let text = std::fs::read_to_string(&metrics).unwrap_or_default();
+        let _ = std::fs::remove_file(&metrics);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(text.lines().count(), fixture.workload.lines().count());
+        assert!(
+            text.starts_with(r#"{"checkpoint":"cp0","line":1,"ops_applied":0"#),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn headers_name_the_workload_and_check_its_root() {
+        let fixture = &FIXTURES[0];
+        let workload = format!(
+            "{{\"op\":\"header\",\"version\":1,\"name\":\"{}\",\"total_ops\":64,\"expected_root\":\"{}\"}}\n{}",
+            fixture.name, fixture.expected_root, fixture.workload
+        );
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(result.root_match, Some(true));
+        assert_eq!(result.workload_name.as_deref(), Some(fixture.name));
+    }
+
+    #[test]
+    fn canaries_check_the_value_encoding() {
+        let fixture = &FIXTURES[0];
+        let mut value = [0; 32];
+        value[30..].copy_from_slice(&[0x01, 0x00]);
+        let hash = hexfmt::fixed(statoor_common::canary::leaf_hash(&value));
+        let canary = |value: &str| {
+            format!(
+                "{{\"op\":\"canary\",\"address\":\"0x01\",\"slot\":\"0x01\",\"value\":\"{value}\",\"expected_leaf_hash\":\"{hash}\"}}\n"
+            )
+        };
+        // 256 in decimal is the value; 0x0001 is its bytes reversed.
+        let workload = canary("256") + &canary("0x0100") + &canary("0x0001") + fixture.workload;
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        let Some(report) = result.canaries else {
+            panic!("canaries must be reported");
+        };
+        assert_eq!((report.canaries_checked, report.canaries_failed), (3, 1));
+        assert_eq!(report.canary_failed_lines, [3]);
+    }
+
+    #[test]
+    fn assertions_read_your_writes() {
+        let workload = [
+            r#"{"op":"create_account","address":"0x00000000000000000000000000000000000000aa","balance":"16","nonce":1}"#,
+            r#"{"op":"set_storage","address":"0x00000000000000000000000000000000000000aa","slot":"0x01","value":"0x2a"}"#,
+            r#"{"op":"assert_account","address":"0x00000000000000000000000000000000000000aa","balance":"0x10","nonce":1}"#,
+            r#"{"op":"assert_storage","address":"0x00000000000000000000000000000000000000aa","slot":"0x01","expected":"42"}"#,
+            r#"{"op":"commit_block"}"#,
+            r#"{"op":"assert_storage","address":"0x00000000000000000000000000000000000000aa","slot":"0x01","expected":"0x2a"}"#,
+            r#"{"op":"set_storage","address":"0x00000000000000000000000000000000000000aa","slot":"0x01","value":"0x2b"}"#,
+            r#"{"op":"assert_storage","address":"0x00000000000000000000000000000000000000aa","slot":"0x01","expected":"0x2a"}"#,
+            r#"{"op":"assert_storage","address":"0x00000000000000000000000000000000000000aa","slot":"0x02","expected":"0"}"#,
+            r#"{"op":"assert_account","address":"0x00000000000000000000000000000000000000bb","nonce":0}"#,
+            r#"{"op":"compute_root"}"#,
+        ]
+        .join("\n");
+        let result = run_fixture(&["--assertions", "warn"], &workload);
+        let Some(report) = result.assertions else {
+            panic!("assertions must be reported");
+        };
+        // Only the check of the overwritten slot fails.
+        assert_eq!(
+            (report.assertions_checked, report.assertion_failures),
+            (7, 1)
+        );
+    }
+
+    #[test]
+    fn cbor_workloads_match_jsonl() {
+        for preload in [false, true] {
+            let flags: &[&str] = if preload {
+                &["--preload-workload"]
+            } else {
+                &[]
+            };
+            for fixture in FIXTURES {
+                let mut cbor = statoor_common::cbor::MAGIC.to_vec();
+                for line in fixture.workload.lines().filter(|l| !l.trim().is_empty()) {
+                    let Ok(entry) = serde_json::from_str::<Entry>(line) else {
+                        panic!("{}: fixture line must decode", fixture.name);
+                    };
+                    statoor_common::cbor::encode(&entry, true, &mut cbor);
+                }
+                let (json_dir, binary_dir) = (TempDb::new(), TempDb::new());
+                let json = run(
+                    &cli_on(&json_dir, flags),
+                    &json_dir.to_string_lossy(),
+                    fixture.workload.as_bytes(),
+                    &Progress::default(),
+                );
+                let binary = run(
+                    &cli_on(&binary_dir, flags),
+                    &binary_dir.to_string_lossy(),
+                    &cbor[..],
+                    &Progress::default(),
+                );
+                assert_eq!(binary.state_root, fixture.expected_root, "{}", fixture.name);
+                assert_eq!(
+                    serde_json::to_value(&binary.counters).ok(),
+                    serde_json::to_value(&json.counters).ok(),
+                    "{}",
+                    fixture.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn code_refs_match_set_code() {
+        let fixture = fixture("account_with_storage");
+        let address = "0x095e7baea6a6c7c4c2dfeb977efac326af552d87";
+        let set_code =
+            format!(r#"{{"op":"set_code","address":"{address}","code":"0x600160005500"}}"#);
+        let by_ref = format!(
+            r#"{{"op":"define_code","id":"store","code":"0x600160005500"}}
+{{"op":"set_code_ref","address":"{address}","code_id":"store"}}"#
+        );
+        let workload = fixture.workload.replace(&set_code, &by_ref);
+        assert_ne!(workload, fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(result.counters.contracts_created, 1);
+    }
+
+    #[test]
+    fn trailer_counts_are_checked() {
+        let fixture = fixture("account_with_storage");
+        let compute_root = r#"{"op":"compute_root"}"#;
+        let with_trailer = |set_storage: u64, lines: u64| {
+            let trailer = format!(
+                r#"{{"op":"trailer","counts":{{"create_account":1,"set_code":1,"set_storage":{set_storage}}},"lines":{lines}}}"#
+            );
+            fixture
+                .workload
+                .replace(compute_root, &format!("{trailer}\n{compute_root}"))
+        };
+        let run_trailer = |workload: String| {
+            let result = run_fixture(&[], &workload);
+            assert_eq!(result.state_root, fixture.expected_root);
+            result
+                .trailer
+                .unwrap_or_else(|| panic!("no trailer report"))
+        };
+
+        assert!(run_trailer(with_trailer(2, 4)).trailer_match);
+        let lost = run_trailer(with_trailer(3, 5));
+        assert!(!lost.trailer_match);
+        assert_eq!(lost.to_string(), "set_storage -1, lines -1");
+    }
+
+    #[test]
+    fn decimal_quantities_match_their_hex() {
+        let fixture = fixture("account_with_storage");
+        let slot_one = format!(r#""slot":"0x{:064x}""#, 1);
+        let workload = fixture
+            .workload
+            .replace(r#""balance":"0x0""#, r#""balance":"0""#)
+            .replace(&slot_one, r#""slot":"1""#)
+            .replace(r#""value":"0x2a""#, r#""value":"42""#);
+        assert_ne!(workload, fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+    }
+
+    #[test]
+    fn balance_deltas_reach_the_absolute_balances() {
+        let fixture = fixture("multiple_accounts");
+        let update = |n: u8, value: &str, sign: &str| {
+            format!(
+                r#"{{"op":"update_balance","address":"{}","value":"{value}","sign":"{sign}"}}"#,
+                numbered(n)
+            )
+        };
+        let transfer = format!(
+            r#"{{"op":"transfer","from":"{}","to":"{}","value":"0x2"}}"#,
+            numbered(1),
+            numbered(2)
+        );
+        // Account 1 pays 0x2 to account 2 and keeps 0x1; account 3 is
+        // credited in the block after its creation.
+        let workload = |debit: &[String]| {
+            let mut lines = vec![
+                create_numbered(1, "0x10", 0),
+                create_numbered(2, "0x0", 0),
+                create_numbered(3, "0x0", 7),
+                transfer.clone(),
+            ];
+            lines.extend_from_slice(debit);
+            lines.extend([
+                r#"{"op":"commit_block"}"#.to_string(),
+                update(3, "0x3", "add"),
+                create_numbered(4, "0x3635c9adc5dea00000", 255),
+                format!(
+                    r#"{{"op":"compute_root","expected_root":"{}"}}"#,
+                    fixture.expected_root
+                ),
+            ]);
+            lines.join("\n") + "\n"
+        };
+        let exact = workload(&[update(1, "0xd", "sub")]);
+        let clamped = workload(&[update(1, "0xffff", "sub"), update(1, "0x1", "add")]);
+        for (name, flags, workload) in [
+            ("exact", &[][..], exact),
+            ("clamped", &["--clamp-balance-underflow"][..], clamped),
+        ] {
+            let result = run_fixture(flags, &workload);
+            assert_eq!(result.root_match, Some(true), "{name}");
+            assert_eq!(result.counters.transfers, 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn nonce_bumps_reach_the_absolute_nonces() {
+        let fixture = fixture("multiple_accounts");
+        let bump = |n: u8, count: u8| {
+            format!(
+                r#"{{"op":"increment_nonce","address":"{}","count":{count}}}"#,
+                numbered(n)
+            )
+        };
+        // Account 3 is bumped in the block after its creation, so its
+        // info comes from the state trie.
+        let workload = [
+            create_numbered(1, "0x01", 0),
+            create_numbered(2, "0x02", 0),
+            create_numbered(3, "0x03", 4),
+            r#"{"op":"commit_block"}"#.to_string(),
+            bump(3, 2),
+            format!(r#"{{"op":"increment_nonce","address":"{}"}}"#, numbered(3)),
+            create_numbered(4, "0x3635c9adc5dea00000", 250),
+            bump(4, 5),
+            format!(
+                r#"{{"op":"compute_root","expected_root":"{}"}}"#,
+                fixture.expected_root
+            ),
+        ]
+        .join("\n");
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.root_match, Some(true));
+        assert_eq!(result.counters.nonce_increments, 8);
+    }
+
+    #[test]
+    fn code_reads_find_every_written_code() {
+        let fixture = fixture("ef_dogs_storage");
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let db_path = dir.to_string_lossy();
+        run(
+            &cli,
+            &db_path,
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+        );
+        let report = read_code(&db_path, 20, None, None);
+        // Budgets spent on arrival cut both phases short, and the
+        // report still says what was done.
+        let listing = read_code(&db_path, 20, Some(0), None);
+        let bench = read_code(&db_path, 20, None, Some(0));
+        assert_eq!(report.codes, 1);
+        assert_eq!((report.warm.reads, report.warm.misses), (20, 0));
+        // The fixture deploys the one-byte code 0x00.
+        assert_eq!(report.warm.bytes_read, 20);
+        assert_eq!(report.cold.is_some(), report.cold_skipped.is_none());
+        assert!(!report.truncated && !report.codes_truncated);
+        assert_eq!((listing.codes, listing.codes_truncated), (0, true));
+        assert_eq!(listing.warm.reads, 0);
+        assert!(bench.truncated && bench.warm.truncated);
+        assert_eq!((bench.codes, bench.warm.reads), (1, 0));
+        assert_eq!(bench.cold_skipped.as_deref(), Some(coderead::OUT_OF_BUDGET));
+    }
+
+    #[test]
+    fn roots_right_after_a_block_add_no_block() {
+        let fixture = fixture("two_blocks");
+        let workload = fixture.workload.replacen(
+            "{\"op\":\"commit_block\"}\n",
+            "{\"op\":\"commit_block\"}\n{\"op\":\"compute_root\"}\n",
+            1,
+        );
+        assert_ne!(workload, fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        let ops: Vec<u64> = result.blocks.iter().map(|block| block.ops).collect();
+        assert_eq!(ops, [3, 3]);
+    }
+
+    #[test]
+    fn recomputed_roots_match_the_run() {
+        let fixture = fixture("ef_dogs_storage");
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let db_path = dir.to_string_lossy();
+        let result = run(
+            &cli,
+            &db_path,
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+        );
+        let report = recompute_root(&db_path, 3, &result.state_root);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(report.recompute_times_ms.len(), 4);
+    }
+
+    #[test]
+    fn expected_root_is_checked() {
+        let fixture = &FIXTURES[0];
+        let digits = fixture
+            .expected_root
+            .trim_start_matches("0x")
+            .to_uppercase();
+        let mut fingerprints = Vec::new();
+        for (expected_root, root_match) in [
+            (String::new(), None),
+            (format!(r#","expected_root":"{digits}""#), Some(true)),
+            (r#","expected_root":"0x00""#.to_string(), Some(false)),
+        ] {
+            let workload = fixture.workload.replace(
+                r#"{"op":"compute_root"}"#,
+                &format!(r#"{{"op":"compute_root"{expected_root}}}"#),
+            );
+            let result = run_fixture(&[], &workload);
+            assert_eq!(result.root_match, root_match, "{expected_root}");
+            fingerprints.push(result.fingerprint);
+        }
+        // The expected root is not an operation of the workload.
+        assert!(fingerprints.windows(2).all(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    fn nan_metrics_fail_the_result_check() {
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let fixture = &FIXTURES[0];
+        let mut result = run(
+            &cli,
+            &dir.to_string_lossy(),
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+        );
+        let sidecar = output::sidecar_path(&dir);
+
+        let mut out = Vec::new();
+        assert!(output::emit(&mut out, "ethrex", &result, &sidecar).is_ok());
+        result.avg_bytes_per_op = result.avg_bytes_per_op.map(|avg| avg * f64::NAN);
+        let mut out = Vec::new();
+        let checked = output::emit(&mut out, "ethrex", &result, &sidecar);
+        assert!(checked.is_err_and(|e| e.contains("avg_bytes_per_op")));
+        assert!(String::from_utf8_lossy(&out).starts_with(r#"{"client":"ethrex","error""#));
+    }
+}
@@ -0,0 +1,216 @@
+//! Typed reading of the result lines the statoor harnesses print, for
+//! Rust programs that analyze them.
+//!
+//! A result carries a `schema_version`; one without it predates the
+//! field and is version 1. [`BenchResult::from_json`] upgrades every
+//! known version to the current struct, field by field, and keeps what
+//! it does not model in [`BenchResult::extra`], so a result written by a
+//! newer harness loses nothing when read and written back.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The version [`BenchResult`] models.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// The fields every result carries, and the ones the orchestrator's report
+/// reads. Everything else a harness reports lands in `extra`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub schema_version: u64,
+    pub client: String,
+    pub state_root: String,
+    pub accounts_created: u64,
+    pub contracts_created: u64,
+    pub storage_slots: u64,
+    pub elapsed_ms: u64,
+    pub trie_time_ms: u64,
+    pub db_write_time_ms: u64,
+    pub peak_memory_bytes: u64,
+    /// The fields of the line not named above, by name.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Why a line could not be read as a [`BenchResult`].
+#[derive(Debug)]
+pub enum ResultError {
+    /// The line is not JSON, or lacks a field of its version.
+    Decode(serde_json::Error),
+    /// The harness reported `error` instead of a result.
+    Harness { client: String, error: String },
+    /// The line is of a version newer than [`SCHEMA_VERSION`].
+    Unsupported(u64),
+}
+
+impl fmt::Display for ResultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "decode result: {e}"),
+            Self::Harness { client, error } => write!(f, "{client} harness failed: {error}"),
+            Self::Unsupported(version) => write!(
+                f,
+                "result schema_version {version} is newer than the supported {SCHEMA_VERSION}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResultError {}
+
+impl From<serde_json::Error> for ResultError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl BenchResult {
+    /// Reads one result line of any known version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line is not a result, is the error line of
+    /// a failed harness, or is of a newer version than this crate knows.
+    pub fn from_json(line: &str) -> Result<Self, ResultError> {
+        let mut value: Value = serde_json::from_str(line)?;
+        if let (Some(error), None) = (value.get("error"), value.get("state_root")) {
+            return Err(ResultError::Harness {
+                client: value
+                    .get("client")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                error: error
+                    .as_str()
+                    .map_or_else(|| error.to_string(), str::to_string),
+            });
+        }
+        let version = value
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .unwrap_or(1);
+        if version > SCHEMA_VERSION {
+            return Err(ResultError::Unsupported(version));
+        }
+        upgrade(&mut value, version);
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Writes the result as one line of the current version.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        // Every field is a string, a number or a JSON value already.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Path of the sidecar file holding the full result, when the harness
+    /// spilled it and printed only the required fields.
+    #[must_use]
+    pub fn sidecar(&self) -> Option<&str> {
+        self.extra.get("result_sidecar").and_then(Value::as_str)
+    }
+}
+
+/// Rewrites `value`, a result of `version`, into the current version.
+/// Each version past 1 adds a step from the one before it here, so old
+/// results run through every later step in turn.
+fn upgrade(value: &mut Value, version: u64) {
+    if let Some(fields) = value.as_object_mut()
+        && version < 2
+    {
+        // Version 1 is the unversioned result; only the tag is new.
+        fields.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Results of every schema version, as harnesses of that version
+    /// printed them.
+    const FIXTURES: &[(&str, &str)] = &[
+        ("v1-reth", include_str!("../fixtures/v1-reth.json")),
+        ("v1-ethrex", include_str!("../fixtures/v1-ethrex.json")),
+        ("v1-sidecar", include_str!("../fixtures/v1-sidecar.json")),
+    ];
+
+    #[test]
+    fn every_version_round_trips() {
+        for (name, line) in FIXTURES {
+            let result = match BenchResult::from_json(line) {
+                Ok(result) => result,
+                Err(e) => panic!("{name}: {e}"),
+            };
+            assert_eq!(result.schema_version, SCHEMA_VERSION, "{name}");
+            let Ok(again) = BenchResult::from_json(&result.to_json()) else {
+                panic!("{name}: written result must read back");
+            };
+            assert_eq!(again, result, "{name}");
+
+            // Nothing of the original line is dropped.
+            let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(line) else {
+                panic!("{name}: fixture must be a JSON object");
+            };
+            let Ok(Value::Object(written)) = serde_json::to_value(&result) else {
+                panic!("{name}: result must serialize to an object");
+            };
+            for (field, value) in fields {
+                assert_eq!(written.get(&field), Some(&value), "{name}: {field}");
+            }
+        }
+    }
+
+    #[test]
+    fn models_the_fields_every_harness_must_print() {
+        let Ok(Value::Object(fields)) = serde_json::to_value(BenchResult {
+            schema_version: SCHEMA_VERSION,
+            client: String::new(),
+            state_root: String::new(),
+            accounts_created: 0,
+            contracts_created: 0,
+            storage_slots: 0,
+            elapsed_ms: 0,
+            trie_time_ms: 0,
+            db_write_time_ms: 0,
+            peak_memory_bytes: 0,
+            extra: Map::new(),
+        }) else {
+            panic!("result must serialize to an object");
+        };
+        for field in statoor_common::output::REQUIRED_FIELDS {
+            assert!(fields.contains_key(*field), "{field}");
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_fields_and_the_sidecar() {
+        let Ok(result) = BenchResult::from_json(FIXTURES[2].1) else {
+            panic!("sidecar summary must read");
+        };
+        assert_eq!(result.sidecar(), Some("tmp/reth.result.json"));
+        let Ok(unknown) = BenchResult::from_json(&FIXTURES[0].1.replacen(
+            '{',
+            r#"{"schema_version":1,"future_metric":{"p99":7},"#,
+            1,
+        )) else {
+            panic!("unknown fields must not fail the read");
+        };
+        assert_eq!(unknown.extra["future_metric"]["p99"], 7);
+    }
+
+    #[test]
+    fn rejects_error_lines_and_newer_versions() {
+        let failed = BenchResult::from_json(r#"{"client":"reth","error":"encode result: NaN"}"#);
+        let Err(e) = failed else {
+            panic!("an error line is not a result");
+        };
+        assert_eq!(e.to_string(), "reth harness failed: encode result: NaN");
+        let Err(e) = BenchResult::from_json(r#"{"schema_version":2,"client":"reth"}"#) else {
+            panic!("a newer version must be rejected");
+        };
+        assert!(matches!(e, ResultError::Unsupported(2)), "{e}");
+    }
+}
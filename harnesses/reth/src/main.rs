@@ -1,212 +1,2465 @@
 /// Reth harness reads a JSONL workload from stdin, applies state
 /// operations using reth's native MDBX + trie layer, and outputs
 /// benchmark results as JSON to stdout.
-use std::collections::HashMap;
-use std::io::{self, BufRead};
-use std::path::PathBuf;
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use alloy_primitives::{Address, B256, U256, keccak256};
-use clap::Parser;
+use alloy_primitives::{Address, B256, Bytes, U256, keccak256};
+use alloy_rlp::Encodable;
+use clap::{Parser, ValueEnum};
 use reth_db::mdbx::DatabaseArguments;
-use reth_db::{DatabaseEnv, init_db, tables};
+use reth_db::{DatabaseEnv, init_db, open_db_read_only, tables};
+use reth_db_api::DatabaseError;
+use reth_db_api::cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO};
 use reth_db_api::database::Database;
 use reth_db_api::models::ClientVersion;
+use reth_db_api::table::{DupSort, Table};
 use reth_db_api::transaction::{DbTx, DbTxMut};
 use reth_primitives_traits::{Account, Bytecode, StorageEntry};
-use reth_trie::StateRoot;
-use reth_trie_db::DatabaseStateRoot;
-use serde::{Deserialize, Serialize};
+use reth_trie::prefix_set::{PrefixSetMut, TriePrefixSets, TriePrefixSetsMut};
+use reth_trie::proof::Proof;
+use reth_trie::updates::TrieUpdates;
+use reth_trie::{
+    HashBuilder, Nibbles, StateRoot, StorageRoot, StorageTrieEntry, StoredNibbles,
+    StoredNibblesSubKey,
+};
+use reth_trie_db::{DatabaseProof, DatabaseStateRoot, DatabaseStorageRoot};
+use serde::Serialize;
+use serde_json::Value;
+use statoor_common::assertions::{AssertionReport, Assertions};
+use statoor_common::blocks::{
+    self, BlockReport, BlockReports, BlockStats, BlockTags, DEFAULT_ROLLING_WINDOW,
+};
+use statoor_common::budget::Budget;
+use statoor_common::canary::{Canaries, CanaryReport};
+use statoor_common::cbor::WorkloadFormat;
+use statoor_common::codebook::CodeBook;
+use statoor_common::coderead::{self, CodeReadPass, CodeReadReport};
+use statoor_common::compression::{self, Compression, CompressionReport};
+use statoor_common::counters::{CounterTotals, Counters, InputBytes};
+use statoor_common::cpu::{BackgroundCpu, CpuSample};
+use statoor_common::delegation;
+use statoor_common::derive;
+use statoor_common::disk::{DURABLE, DiskFullReport, EXIT_DISK_FULL, SpaceGuard, is_disk_full};
+use statoor_common::events::EventLog;
+use statoor_common::examples::EXAMPLES;
+use statoor_common::fingerprint::{Fingerprint, FingerprintReport};
+use statoor_common::fixtures::{BLOCK_FIXTURES, FIXTURES, Outcome, render_table};
+use statoor_common::header::WorkloadHeader;
+use statoor_common::health;
+use statoor_common::hexfmt;
+use statoor_common::invocation;
+use statoor_common::lock::DbLock;
+use statoor_common::metrics::MetricsLog;
+use statoor_common::model::MeasurementModel;
+use statoor_common::multiroot::RootLines;
+use statoor_common::output;
+use statoor_common::preload::{DEFAULT_PRELOAD_LIMIT_MB, Input, PreloadReport};
+use statoor_common::probe::{self, ProbeReport};
+#[cfg(feature = "profiling")]
+use statoor_common::profile::ProfileArgs;
+use statoor_common::profile::Profiler;
+use statoor_common::proofs::{self, ProofReport, ProofStats};
+use statoor_common::protocol::{Entry, Op, Sign, capabilities_json, schema_json};
+use statoor_common::quantity;
+use statoor_common::query::{self, Query};
+use statoor_common::reads::{ReadReport, ReadStats};
+use statoor_common::recompute::{self, RecomputeReport};
+use statoor_common::reserved::ReservedTouches;
+use statoor_common::rootcheck::{self, EXIT_ROOT_MISMATCH};
+use statoor_common::sampling::{DecodeEstimates, DecodeSampler};
+use statoor_common::sleep::Sleeps;
+use statoor_common::status::{OpRates, Pending, Phase, Progress, spawn_reporter};
+use statoor_common::sysmetrics::{EnvSnapshot, Environment, SystemMetrics};
+use statoor_common::timebox::{TimeBox, TimeBoxReport};
+use statoor_common::trailer::{OpTally, TrailerReport};
+use statoor_common::watchdog::ApplyWatchdog;
 
-#[derive(Parser)]
+#[derive(Parser, Serialize)]
 struct Cli {
     /// Path to the MDBX database directory.
+    #[arg(
+        long,
+        required_unless_present_any = [
+            "self_test", "health_check", "print_schema", "capabilities", "probe_root_of",
+            "query_db"
+        ]
+    )]
+    db: Option<PathBuf>,
+
+    /// Run the embedded fixtures in temporary databases, print a
+    /// pass/fail table and exit. Only the flags that leave the root as it
+    /// is, such as --root-algorithm, apply to them.
+    #[arg(long)]
+    self_test: bool,
+
+    /// Run a three-operation workload end to end in a temporary
+    /// database, print a JSON health report and exit nonzero on failure.
+    #[arg(long)]
+    health_check: bool,
+
+    /// Print the JSON Schema of a workload line and exit.
+    #[arg(long)]
+    print_schema: bool,
+
+    /// Print the supported protocol version and operations as JSON and
+    /// exit.
+    #[arg(long)]
+    capabilities: bool,
+
+    /// Write an NDJSON event per applied operation to this file, usable
+    /// by the orchestrator's --replay-events.
+    #[arg(long)]
+    events_out: Option<PathBuf>,
+
+    /// Write the metric line of each checkpoint operation to this file
+    /// instead of stderr.
+    #[arg(long)]
+    metrics_out: Option<PathBuf>,
+
+    /// Artificial latency in milliseconds slept around each commit, to
+    /// simulate slower storage. Results are marked as simulated.
+    #[arg(long, default_value_t = 0)]
+    inject_commit_latency_ms: u64,
+
+    /// Stop the write phase with a partial result once free space on the
+    /// database filesystem drops below this many GiB. 0 disables the check.
+    #[arg(long, default_value_t = 0)]
+    reserve_free_gb: u64,
+
+    /// Stop after this many applied operations and compute the root over
+    /// that prefix, for quick smoke runs of huge workloads.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_ops: Option<u64>,
+
+    /// Stop applying operations this many seconds after starting to
+    /// read them, and compute the root over the prefix applied.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    duration_secs: Option<u64>,
+
+    /// Also write the preimage-keyed PlainAccountState and
+    /// PlainStorageState tables, as reth does in production, and report
+    /// the size of each table.
+    #[arg(long)]
+    full_tables: bool,
+
+    /// Store a slot set to zero as a zero-valued entry instead of
+    /// deleting it. The root then diverges from ethrex and from mainnet
+    /// semantics.
+    #[arg(long)]
+    keep_zero_slots: bool,
+
+    /// Set the balance to zero when an `update_balance` or `transfer`
+    /// subtracts more than it holds, instead of failing the run.
+    #[arg(long)]
+    clamp_balance_underflow: bool,
+
+    /// Remove accounts left with zero nonce, zero balance and no code
+    /// from the state at each commit, as EIP-158 does, instead of
+    /// storing them as empty leaves.
+    #[arg(long)]
+    prune_empty_accounts: bool,
+
+    /// Fail on the first operation touching the zero address, a
+    /// precompile or the all-ones address, which are counted either way.
+    #[arg(long)]
+    reject_reserved_addresses: bool,
+
+    /// Fail on a `set_delegation` whose target is in neither the workload
+    /// nor the state, which is more often a generator bug than intended.
+    #[arg(long)]
+    require_delegation_targets: bool,
+
+    /// Read and decode the whole workload before opening the database,
+    /// so the measured phases do no stdin I/O or JSON parsing.
+    #[arg(long)]
+    preload_workload: bool,
+
+    /// Workload encoding: `jsonl`, `cbor`, or `auto` to tell them apart
+    /// by the CBOR self-describe tag.
+    #[arg(long, default_value = "auto")]
+    workload_format: WorkloadFormat,
+
+    /// Codec stdin is compressed with: `gzip`, `zstd`, `none`, or `auto`
+    /// to go by its magic bytes.
+    #[arg(long, default_value = "auto")]
+    compression: Compression,
+
+    /// Refuse to preload a workload larger than this many MiB.
+    #[arg(long, default_value_t = DEFAULT_PRELOAD_LIMIT_MB)]
+    preload_limit_mb: u64,
+
+    /// Fail the run when a `trailer` line declares other operation
+    /// counts than were read, or a `canary` expects another leaf hash,
+    /// instead of only reporting the deltas.
+    #[arg(long)]
+    strict: bool,
+
+    /// What a failed `assert_account` or `assert_storage` does: `fail`
+    /// ends the run, `warn` prints it to stderr and goes on.
+    #[arg(long, value_enum, default_value_t = AssertionMode::Fail)]
+    assertions: AssertionMode,
+
+    /// Blocks per window of the `rolling_stats` of a `commit_block`
+    /// workload.
+    #[arg(long, default_value_t = DEFAULT_ROLLING_WINDOW)]
+    rolling_window: usize,
+
+    /// List every block in the result even past one rolling window,
+    /// instead of in a `<db>.blocks.json` sidecar.
+    #[arg(long)]
+    emit_per_block: bool,
+
+    /// State root implementation. `both` runs serial then parallel and
+    /// fails if the roots differ; the serial time is reported.
+    #[arg(long, value_enum, default_value_t = RootAlgorithm::Serial)]
+    root_algorithm: RootAlgorithm,
+
+    /// Worker threads for the parallel root (default: available cores).
+    #[arg(long)]
+    root_threads: Option<usize>,
+
+    /// After the run, reopen the database read-only in a child process
+    /// and check that it yields the reported root. `hard` also checks a
+    /// copy taken without lock files, approximating a crash snapshot.
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "soft")]
+    durability_probe: Option<ProbeMode>,
+
+    /// After the run, fetch this many code blobs by hash from the
+    /// Bytecodes table, sampled from the codes written, and report their
+    /// latencies. A second pass reopens the database after dropping the
+    /// page cache when the process may do so.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    read_code: Option<u64>,
+
+    /// Stop the `--read-code` passes after this many seconds, reporting
+    /// the reads made so far as `truncated`.
+    #[arg(long, requires = "read_code", value_parser = clap::value_parser!(u64).range(1..))]
+    read_bench_timeout_secs: Option<u64>,
+
+    /// Stop listing the codes `--read-code` samples from after this many
+    /// seconds, and sample from those listed so far.
+    #[arg(long, requires = "read_code", value_parser = clap::value_parser!(u64).range(1..))]
+    iterate_timeout_secs: Option<u64>,
+
+    /// After the run, compute the root again on a freshly opened
+    /// database, then this many more times on the same one, without
+    /// changing any state, and report the time of each. Every computation
+    /// must return the run's root.
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    recompute_root: Option<u64>,
+
+    /// Kill a `--durability-probe` child still running after this many
+    /// seconds, reporting the probe as `truncated`.
+    #[arg(long, requires = "durability_probe", value_parser = clap::value_parser!(u64).range(1..))]
+    verify_timeout_secs: Option<u64>,
+
+    /// Child side of --durability-probe and `statoor verify-db`: print
+    /// the state root of this database and exit.
+    #[arg(long, hide = true)]
+    probe_root_of: Option<PathBuf>,
+
+    /// Answer the `--stay-alive` queries on stdin against this database,
+    /// reopened read-only, without running a workload.
+    #[arg(long)]
+    query_db: Option<PathBuf>,
+
+    /// Start even if another live statoor process holds the lock file in
+    /// the database directory.
+    #[arg(long)]
+    force: bool,
+
+    /// Also report `unique_storage_slots`, the distinct (address, slot)
+    /// pairs written. Remembering them adds to the peak memory.
+    #[arg(long)]
+    unique_slots: bool,
+
+    /// After printing the result, keep answering `read_account`,
+    /// `read_storage`, `get_proof` and `dump_state` lines from stdin
+    /// until EOF, one JSON response per line.
+    #[arg(long)]
+    stay_alive: bool,
+
+    /// Number every result line with its `root_index`, for workloads
+    /// with several `compute_root` lines. Every line carries `final`
+    /// either way.
     #[arg(long)]
-    db: PathBuf,
-}
-
-#[derive(Deserialize)]
-struct Operation {
-    op: String,
-    #[serde(default)]
-    address: String,
-    #[serde(default)]
-    balance: String,
-    #[serde(default)]
-    nonce: u64,
-    #[serde(default)]
-    code: String,
-    #[serde(default)]
-    slot: String,
-    #[serde(default)]
-    value: String,
+    multi_root: bool,
+
+    #[cfg(feature = "profiling")]
+    #[command(flatten)]
+    #[serde(flatten)]
+    profile: ProfileArgs,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum AssertionMode {
+    Fail,
+    Warn,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ProbeMode {
+    /// Reopen the database in place.
+    Soft,
+    /// Also reopen a lock-free copy of the database directory.
+    Hard,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum RootAlgorithm {
+    /// reth's DB-backed `StateRoot`.
+    Serial,
+    /// Storage roots computed concurrently, then the account trie.
+    Parallel,
+    /// Both, asserting they agree.
+    Both,
 }
 
 #[derive(Serialize)]
 struct BenchResult {
     client: &'static str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    simulated: bool,
     state_root: String,
-    accounts_created: u64,
-    contracts_created: u64,
-    storage_slots: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_match: Option<bool>,
+    #[serde(flatten)]
+    counters: CounterTotals,
     elapsed_ms: u64,
+    /// Time spent in `sleep` lines, left out of `elapsed_ms`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sleep_ms: Option<u64>,
     trie_time_ms: u64,
     db_write_time_ms: u64,
+    /// Blocks committed with `commit_block`, in order. The trie and DB
+    /// write times above are their sums.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    blocks: Vec<BlockReport>,
+    #[serde(flatten)]
+    block_stats: Option<BlockStats>,
     peak_memory_bytes: u64,
+    /// Bytes in the pages of every table, without the free pages and
+    /// unused geometry of the data file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    db_logical_size_bytes: Option<u64>,
+    /// Staged writes left uncommitted when the root was computed: always
+    /// zero, since any other count aborts the run.
+    pending_at_root: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    injected_latency_total_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    db_write_time_net_ms: Option<u64>,
+    durability: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated_at_op: Option<u64>,
+    #[serde(flatten)]
+    time_box: Option<TimeBoxReport>,
+    #[serde(flatten)]
+    fingerprint: Option<FingerprintReport>,
+    /// The `name` of the workload's header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workload_name: Option<String>,
+    #[serde(flatten)]
+    reads: Option<ReadReport>,
+    #[serde(flatten)]
+    proofs: Option<ProofReport>,
+    #[serde(flatten)]
+    reserved: Option<ReservedTouches>,
+    #[serde(flatten)]
+    preload: Option<PreloadReport>,
+    #[serde(flatten)]
+    compression: Option<CompressionReport>,
+    #[serde(flatten)]
+    trailer: Option<TrailerReport>,
+    #[serde(flatten)]
+    canaries: Option<CanaryReport>,
+    #[serde(flatten)]
+    assertions: Option<AssertionReport>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    input_bytes_by_op: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_bytes_per_op: Option<f64>,
+    #[serde(flatten)]
+    decode_estimates: Option<DecodeEstimates>,
+    #[serde(flatten)]
+    op_rates: Option<OpRates>,
+    /// Slowest apply rate of any window of operations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apply_throughput_min_ops_per_sec: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    background_cpu_ms: BTreeMap<&'static str, u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    system_metrics: BTreeMap<&'static str, SystemMetrics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<Environment>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    table_size_bytes: BTreeMap<&'static str, u64>,
+    root_algorithm: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    root_threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parallel_trie_time_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    durability_probe: Option<ProbeReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_read: Option<CodeReadReport>,
+    #[serde(flatten)]
+    recompute: Option<RecomputeReport>,
+    /// Files written alongside the result, such as `--profile-out`
+    /// flamegraphs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<String>,
+    /// What each timed phase of this run included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    measurement_model: Option<MeasurementModel>,
+    /// Every flag of this run with its effective value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invocation: Option<Value>,
+}
+
+/// A `define_code` bytecode, hashed once for every `set_code_ref` to it.
+struct DefinedCode {
+    hash: B256,
+    len: usize,
+    /// The bytecode until the first `set_code_ref` stages it; later ones
+    /// only point accounts at the hash already written.
+    unwritten: Option<Bytecode>,
+}
+
+/// State collected from the workload, committed once before trie
+/// computation, or once per block of a `commit_block` workload.
+#[derive(Default)]
+struct PendingWrites {
+    accounts: Vec<(B256, Account)>,
+    bytecodes: Vec<(B256, Bytecode)>,
+    storage: Vec<(B256, StorageEntry)>,
+    /// Preimage-keyed copies, collected only with `--full-tables`.
+    plain_accounts: Vec<(Address, Account)>,
+    plain_storage: Vec<(Address, StorageEntry)>,
+    /// Total length of the pending bytecodes.
+    code_bytes: usize,
+    /// Deleted accounts by hashed address, with the staged writes made
+    /// before their last deletion.
+    deleted: HashMap<B256, Deletion>,
+    /// Accounts whose storage was cleared, as deletions of the storage
+    /// alone: the account lengths of their `staged` are zero, so no
+    /// account write is skipped.
+    storage_cleared: HashMap<B256, Deletion>,
+    /// Accounts written since the last commit, collected only with
+    /// `--prune-empty-accounts`.
+    touched: HashSet<Address>,
+}
+
+/// A `delete_account`: the account's entries are wiped from the tables,
+/// and its writes staged before the deletion are skipped.
+struct Deletion {
+    address: Address,
+    /// Lengths of `accounts`, `storage`, `plain_accounts` and
+    /// `plain_storage` when the account was deleted.
+    staged: [usize; 4],
+}
+
+impl PendingWrites {
+    /// Stages the whole account, as every account write does.
+    fn push_account(&mut self, cli: &Cli, address: Address, account: Account) {
+        self.accounts.push((keccak256(address), account));
+        if cli.full_tables {
+            self.plain_accounts.push((address, account));
+        }
+        if cli.prune_empty_accounts {
+            self.touched.insert(address);
+        }
+    }
+
+    /// Makes room for the `ops` operations a workload header announces.
+    /// Which of them are account writes is not known yet, so the account
+    /// and storage lists both get room for all of them.
+    fn reserve(&mut self, ops: usize) {
+        self.accounts.reserve(ops);
+        self.storage.reserve(ops);
+    }
+
+    /// Stages one storage slot, as `set_storage` does.
+    fn push_storage(&mut self, cli: &Cli, address: Address, slot: B256, value: U256) {
+        self.storage.push((
+            keccak256(address),
+            StorageEntry {
+                key: keccak256(slot),
+                value,
+            },
+        ));
+        if cli.full_tables {
+            self.plain_storage
+                .push((address, StorageEntry { key: slot, value }));
+        }
+    }
+
+    /// Stages the slots of a `set_storage_batch`, hashing the address
+    /// once for all of them.
+    fn push_storage_batch(&mut self, cli: &Cli, address: Address, entries: &[(B256, U256)]) {
+        let hashed_address = keccak256(address);
+        self.storage.reserve(entries.len());
+        for &(slot, value) in entries {
+            self.storage.push((
+                hashed_address,
+                StorageEntry {
+                    key: keccak256(slot),
+                    value,
+                },
+            ));
+        }
+        if cli.full_tables {
+            self.plain_storage.extend(
+                entries
+                    .iter()
+                    .map(|&(key, value)| (address, StorageEntry { key, value })),
+            );
+        }
+    }
+
+    /// Stages the deletion of `address`, dropping everything staged for
+    /// it so far.
+    fn delete_account(&mut self, address: Address) {
+        let staged = [
+            self.accounts.len(),
+            self.storage.len(),
+            self.plain_accounts.len(),
+            self.plain_storage.len(),
+        ];
+        self.deleted
+            .insert(keccak256(address), Deletion { address, staged });
+    }
+
+    /// Stages a `clear_storage` of `address`, dropping the storage
+    /// staged for it so far.
+    fn clear_storage(&mut self, address: Address) {
+        let staged = [0, self.storage.len(), 0, self.plain_storage.len()];
+        self.storage_cleared
+            .insert(keccak256(address), Deletion { address, staged });
+    }
+
+    /// Stages the deletion of every account written since the last commit
+    /// that is now empty, returning how many there were. Their storage
+    /// goes with them.
+    fn prune_empty(&mut self, account_map: &mut HashMap<Address, Account>) -> u64 {
+        let mut pruned = 0;
+        for address in std::mem::take(&mut self.touched) {
+            if account_map.get(&address).is_some_and(Account::is_empty) {
+                self.delete_account(address);
+                account_map.remove(&address);
+                pruned += 1;
+            }
+        }
+        pruned
+    }
+
+    /// Reports whether the write at `index` of staged list `list` was
+    /// made before a deletion of `hashed_address` or, for storage, before
+    /// a clear of it.
+    fn superseded(&self, hashed_address: &B256, list: usize, index: usize) -> bool {
+        [&self.deleted, &self.storage_cleared].iter().any(|wipes| {
+            wipes
+                .get(hashed_address)
+                .is_some_and(|deletion| index < deletion.staged[list])
+        })
+    }
+
+    /// Number of staged writes, deletions and clears not yet committed.
+    fn staged(&self) -> u64 {
+        (self.accounts.len()
+            + self.bytecodes.len()
+            + self.storage.len()
+            + self.plain_accounts.len()
+            + self.plain_storage.len()
+            + self.deleted.len()
+            + self.storage_cleared.len()) as u64
+    }
+
+    /// Sizes for status dumps, with the memory estimated from the vector
+    /// capacities plus the bytecode contents.
+    fn sizes(&self) -> Pending {
+        let bytes = self.accounts.capacity() * size_of::<(B256, Account)>()
+            + self.bytecodes.capacity() * size_of::<(B256, Bytecode)>()
+            + self.storage.capacity() * size_of::<(B256, StorageEntry)>()
+            + self.plain_accounts.capacity() * size_of::<(Address, Account)>()
+            + self.plain_storage.capacity() * size_of::<(Address, StorageEntry)>()
+            + self.code_bytes;
+        Pending {
+            accounts: self.accounts.len() as u64,
+            slots: self.storage.len() as u64,
+            code: self.bytecodes.len() as u64,
+            bytes: bytes as u64,
+        }
+    }
+}
+
+/// Outcome of the state root computation.
+struct RootTiming {
+    root: B256,
+    trie_ms: u64,
+    algorithm: &'static str,
+    threads: Option<usize>,
+    /// Parallel root time when both algorithms ran.
+    parallel_ms: Option<u64>,
+}
+
+/// Timings of the DB write phase.
+struct WriteTiming {
+    /// Total write phase time, including any injected latency.
+    db_write_ms: u64,
+    /// Injected commit latency, set only when latency injection is enabled.
+    injected_ms: Option<u64>,
+}
+
+/// Blocks committed with `commit_block`, with the sums of their timings
+/// the result reports as its own.
+#[derive(Default)]
+struct Blocks {
+    reports: BlockReports,
+    root: B256,
+    trie_ms: u64,
+    db_write_ms: u64,
+    injected_ms: Option<u64>,
+}
+
+impl Blocks {
+    /// Commits the writes staged since the previous block, leaving
+    /// `pending` empty, then updates the stored trie where they changed
+    /// it. The DB write time covers both commits. Returns how many empty
+    /// accounts `--prune-empty-accounts` removed.
+    fn commit(
+        &mut self,
+        db: &DatabaseEnv,
+        cli: &Cli,
+        pending: &mut PendingWrites,
+        account_map: &mut HashMap<Address, Account>,
+        guard: &SpaceGuard,
+        ops: u64,
+    ) -> u64 {
+        let pruned = if cli.prune_empty_accounts {
+            pending.prune_empty(account_map)
+        } else {
+            0
+        };
+        let prefix_sets = prefix_sets(pending, account_map);
+        let write = flush_writes(
+            db,
+            pending,
+            guard,
+            cli.inject_commit_latency_ms,
+            cli.keep_zero_slots,
+        );
+        let trie_start = Instant::now();
+        let (root, updates) = incremental_root(db, prefix_sets);
+        let trie_ms = trie_start.elapsed().as_millis() as u64;
+        let trie_write_start = Instant::now();
+        write_trie_updates(db, &updates, guard);
+        let db_write_ms = write.db_write_ms + trie_write_start.elapsed().as_millis() as u64;
+
+        self.reports.push(BlockReport {
+            state_root: hexfmt::fixed(root),
+            trie_time_ms: u128::from(trie_ms),
+            db_write_time_ms: u128::from(db_write_ms),
+            approx_gas: None,
+            block_number: None,
+            ops,
+        });
+        self.root = root;
+        self.trie_ms = self.trie_ms.saturating_add(trie_ms);
+        self.db_write_ms = self.db_write_ms.saturating_add(db_write_ms);
+        if let Some(ms) = write.injected_ms {
+            self.injected_ms = Some(self.injected_ms.unwrap_or(0).saturating_add(ms));
+        }
+        pruned
+    }
+
+    fn write_timing(&self) -> WriteTiming {
+        WriteTiming {
+            db_write_ms: self.db_write_ms,
+            injected_ms: self.injected_ms,
+        }
+    }
+
+    fn root_timing(&self) -> RootTiming {
+        RootTiming {
+            root: self.root,
+            trie_ms: self.trie_ms,
+            algorithm: "serial",
+            threads: None,
+            parallel_ms: None,
+        }
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
+    if cli.capabilities {
+        println!("{}", capabilities_json());
+        return;
+    }
+    if cli.print_schema {
+        print!("{}", schema_json());
+        return;
+    }
+    if cli.self_test {
+        self_test(&cli);
+    }
+    if cli.health_check {
+        health_check();
+    }
+    if let Some(path) = cli.probe_root_of.as_deref() {
+        let db = open_db_read_only(path, DatabaseArguments::new(ClientVersion::default()))
+            .unwrap_or_else(|e| fatal(&format!("open mdbx read-only: {e}")));
+        println!("{}", hexfmt::fixed(serial_state_root(&db)));
+        return;
+    }
+    if let Some(path) = cli.query_db.as_deref() {
+        serve_queries(path);
+        return;
+    }
+
+    let Some(db_path) = cli.db.as_deref() else {
+        fatal("--db is required");
+    };
+    // Held until the result is emitted; dropping it removes the lock file.
+    let _lock = DbLock::acquire(db_path, "reth", cli.force).unwrap_or_else(|e| fatal(&e));
+    let progress = Arc::new(Progress::default());
+    spawn_reporter(Arc::clone(&progress), io::stderr())
+        .unwrap_or_else(|e| fatal(&format!("start status reporter: {e}")));
+    let mut checkpoint_mismatch = false;
+    let mut roots = RootLines::new(cli.multi_root);
+    let (workload, decompressed) = compression::open(io::stdin().lock(), cli.compression, decoder)
+        .unwrap_or_else(|e| fatal(&format!("open workload: {e}")));
+    let mut result = run(&cli, db_path, workload, &progress, &mut |checkpoint| {
+        checkpoint_mismatch |= checkpoint.root_match == Some(false);
+        emit_result(&roots.line(checkpoint, false), db_path);
+    });
+    result.compression = decompressed.map(|d| d.report());
+    // `run` has closed the database, so the child sees only what was
+    // committed.
+    if let Some(mode) = cli.durability_probe {
+        result.durability_probe = Some(probe::run(
+            db_path,
+            &result.state_root,
+            mode == ProbeMode::Hard,
+            &Budget::start(cli.verify_timeout_secs),
+        ));
+    }
+    if let Some(n) = cli.read_code {
+        result.code_read = Some(read_code(
+            db_path,
+            n,
+            cli.iterate_timeout_secs,
+            cli.read_bench_timeout_secs,
+        ));
+    }
+    if let Some(passes) = cli.recompute_root {
+        result.recompute = Some(recompute_root(&cli, db_path, passes, &result.state_root));
+    }
+    result.invocation = Some(invocation::resolved(&cli).unwrap_or_else(|e| fatal(&e)));
+    emit_result(&roots.line(&result, true), db_path);
+    if cli.stay_alive {
+        serve_queries(db_path);
+    }
+    if result.root_match == Some(false) || checkpoint_mismatch {
+        eprintln!("reth-harness: state root does not match expected_root");
+        std::process::exit(EXIT_ROOT_MISMATCH);
+    }
+}
+
+/// Builds the decoder of a compressed workload. zstd frames may use the
+/// largest window `zstd --long` writes.
+fn decoder<'a>(
+    compression: Compression,
+    input: impl BufRead + 'a,
+) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(input)),
+        Compression::Zstd => {
+            let mut decoder = zstd::stream::read::Decoder::with_buffer(input)?;
+            decoder.window_log_max(31)?;
+            Box::new(decoder)
+        }
+        Compression::Auto | Compression::None => Box::new(input),
+    })
+}
+
+/// Runs every embedded fixture and example against a fresh temporary
+/// database, prints a pass/fail table and exits nonzero if any root
+/// differs. Of `cli`, only the flags that pick a code path without
+/// changing the root apply, so a flag such as `--max-ops` cannot fail a
+/// known root and no `--events-out` file is overwritten per fixture.
+fn self_test(cli: &Cli) -> ! {
+    let cli = &Cli {
+        full_tables: cli.full_tables,
+        preload_workload: cli.preload_workload,
+        root_algorithm: cli.root_algorithm,
+        root_threads: cli.root_threads,
+        ..Cli::parse_from(["reth-harness", "--self-test"])
+    };
+    let root_of = |name: &str, workload: &[u8]| {
+        let dir = std::env::temp_dir().join(format!(
+            "statoor-reth-self-test-{}-{name}",
+            std::process::id()
+        ));
+        let result = run(cli, &dir, workload, &Progress::default(), &mut |_| {});
+        let _ = std::fs::remove_dir_all(&dir);
+        result.state_root
+    };
+    let outcomes: Vec<Outcome> = FIXTURES
+        .iter()
+        .chain(BLOCK_FIXTURES)
+        .map(|fixture| Outcome::new(fixture, root_of(fixture.name, fixture.workload.as_bytes())))
+        .chain(
+            EXAMPLES.iter().map(|example| {
+                example.outcome(root_of(example.name, example.workload().as_bytes()))
+            }),
+        )
+        .collect();
+
+    print!("{}", render_table(&outcomes));
+    std::process::exit(i32::from(!outcomes.iter().all(Outcome::passed)));
+}
+
+/// Prints the health report of this binary and exits nonzero if any
+/// check failed.
+fn health_check() -> ! {
+    let report = health::run("reth");
+    let json = serde_json::to_string(&report)
+        .unwrap_or_else(|e| fatal(&format!("encode health report: {e}")));
+    println!("{json}");
+    std::process::exit(i32::from(!report.passed()));
+}
+
+/// Applies the workload read from `reader` to the MDBX database at
+/// `db_path` and returns the result of its `compute_root` operation, or
+/// of the first `--max-ops` operations when that cap is reached first.
+/// A workload split by `commit_block` is committed block by block, and
+/// may end without `compute_root`. A `compute_root` with more operations
+/// after it ends a block too, and its result goes to `checkpoint` before
+/// the run goes on.
+fn run(
+    cli: &Cli,
+    db_path: &Path,
+    reader: impl BufRead,
+    progress: &Progress,
+    checkpoint: &mut dyn FnMut(&BenchResult),
+) -> BenchResult {
+    let (mut input, preload) = Input::open_as(
+        reader,
+        cli.workload_format,
+        cli.preload_workload.then_some(cli.preload_limit_mb),
+    )
+    .unwrap_or_else(|e| fatal(&e.to_string()));
     let start = Instant::now();
+    let env_start = EnvSnapshot::capture();
 
-    let db = init_db(&cli.db, DatabaseArguments::new(ClientVersion::default()))
+    let db = init_db(db_path, DatabaseArguments::new(ClientVersion::default()))
         .unwrap_or_else(|e| fatal(&format!("open mdbx: {e}")));
+    let guard = SpaceGuard::new(db_path, cli.reserve_free_gb);
+    clear_stale_trie(&db, &guard);
 
-    let mut accounts: u64 = 0;
-    let mut contracts: u64 = 0;
-    let mut slots: u64 = 0;
+    let mut counters: Counters<Address, B256> = if cli.unique_slots {
+        Counters::with_unique_slots()
+    } else {
+        Counters::default()
+    };
 
     // Track per-address account state so set_code can update
     // the bytecode_hash after create_account.
     let mut account_map: HashMap<Address, Account> = HashMap::new();
 
-    // Collect all writes, commit once before trie computation.
-    let mut pending_accounts: Vec<(B256, Account)> = Vec::new();
-    let mut pending_bytecodes: Vec<(B256, Bytecode)> = Vec::new();
-    let mut pending_storage: Vec<(B256, StorageEntry)> = Vec::new();
-
-    let stdin = io::stdin().lock();
-    for line in stdin.lines() {
-        let line = line.unwrap_or_else(|e| fatal(&format!("read stdin: {e}")));
-        let op: Operation = serde_json::from_str(&line)
-            .unwrap_or_else(|e| fatal(&format!("decode operation: {e}")));
-
-        match op.op.as_str() {
-            "create_account" => {
-                let address = parse_address(&op.address);
-                let balance = parse_u256(&op.balance);
+    let mut pending = PendingWrites::default();
+    let mut blocks = Blocks::default();
+    // Operations applied since the last commit_block.
+    let mut block_ops: u64 = 0;
+
+    let mut events = cli.events_out.as_deref().map(|path| {
+        let file =
+            File::create(path).unwrap_or_else(|e| fatal(&format!("create events file: {e}")));
+        EventLog::new(BufWriter::new(file), start)
+    });
+    let metrics_sink: Box<dyn Write> = match cli.metrics_out.as_deref() {
+        Some(path) => Box::new(
+            File::create(path).unwrap_or_else(|e| fatal(&format!("create metrics file: {e}"))),
+        ),
+        None => Box::new(io::stderr()),
+    };
+    let mut metrics = MetricsLog::new(metrics_sink, start);
+
+    let mut truncated_at_op = None;
+    let mut input_bytes = InputBytes::default();
+    let mut decode = DecodeSampler::default();
+    let mut watchdog = ApplyWatchdog::default();
+    let mut root_requested = false;
+    let mut expected_root = None;
+
+    // Start of the current read or apply step, for the progress rates.
+    let mut mark = Instant::now();
+    let mut time_box = cli.duration_secs.map(|secs| TimeBox::new(mark, secs));
+    let mut sleeps = Sleeps::default();
+    let mut fingerprint = Fingerprint::default();
+    let mut reads = ReadStats::default();
+    let mut proofs = ProofStats::default();
+    let mut reserved = ReservedTouches::default();
+    let mut codes = CodeBook::default();
+    let mut tally = OpTally::default();
+    let mut header = WorkloadHeader::default();
+    let mut trailer = None;
+    let mut canaries = Canaries::default();
+    let mut assertions = Assertions::default();
+    let mut block_tags = BlockTags::default();
+    while let Some((Entry { op, tenant, block }, len)) = input
+        .next_entry(&mut decode)
+        .unwrap_or_else(|e| fatal(&e.to_string()))
+    {
+        let now = Instant::now();
+        progress.read_op(now - mark);
+        mark = now;
+        let line = input.line_number();
+        input_bytes.record(op.name(), len);
+        fingerprint.record(&op);
+        tally.record(&op);
+        let presize = header
+            .record(&op, cli.max_ops)
+            .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")));
+        if let Some(ops) = presize {
+            pending.reserve(ops);
+            account_map.reserve(ops);
+        }
+        if let Some(kind) = reserved.record(&op)
+            && cli.reject_reserved_addresses
+        {
+            fatal(&format!("line {line}: {} touches {kind}", op.name()));
+        }
+        counters.record_tenant(tenant);
+        if let Some(log) = events.as_mut() {
+            log.op(line, op.name())
+                .unwrap_or_else(|e| fatal(&format!("write event: {e}")));
+        }
+        // A line of a later block commits the lines before it, as a
+        // commit_block between them would.
+        let ended = block_tags
+            .advance(block)
+            .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")));
+        if let Some(ended) = ended
+            && block_ops > 0
+        {
+            let pruned = blocks.commit(&db, cli, &mut pending, &mut account_map, &guard, block_ops);
+            counters.record_empty_pruned(pruned);
+            if let Some(block) = blocks.reports.last_mut() {
+                block.block_number = Some(ended);
+            }
+            block_ops = 0;
+            progress.set_pending(pending.sizes());
+            mark = Instant::now();
+        }
+
+        // The two deletions apply alike and are counted apart.
+        let selfdestruct = matches!(op, Op::Selfdestruct { .. });
+        match op {
+            Op::CreateAccount {
+                address,
+                balance,
+                nonce,
+                storage,
+            } => {
+                let address = decode.hex(|| parse_address(&address));
+                let balance = decode.hex(|| parse_u256(&balance, line));
                 let account = Account {
-                    nonce: op.nonce,
+                    nonce,
                     balance,
                     bytecode_hash: None,
                 };
-                let hashed = keccak256(address);
-                pending_accounts.push((hashed, account));
+                pending.push_account(cli, address, account);
                 account_map.insert(address, account);
-                accounts += 1;
+                counters.record_account_created();
+                for (slot, value) in &storage {
+                    let slot = decode.hex(|| parse_b256(slot, line));
+                    let value = decode.hex(|| parse_u256(value, line));
+                    pending.push_storage(cli, address, slot, value);
+                    counters.record_storage_write(address, slot);
+                }
             }
-            "set_code" => {
-                let address = parse_address(&op.address);
-                let code_bytes = parse_hex(&op.code);
+            Op::CreateAccounts {
+                seed,
+                count,
+                balance,
+                nonce,
+            } => {
+                let seed = decode.hex(|| {
+                    derive::parse_seed(&seed)
+                        .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")))
+                });
+                let balance = decode.hex(|| parse_u256(&balance, line));
+                let account = Account {
+                    nonce,
+                    balance,
+                    bytecode_hash: None,
+                };
+                for address in derive::addresses(&seed, count, |preimage| keccak256(preimage).0) {
+                    let address = Address::from(address);
+                    pending.push_account(cli, address, account);
+                    account_map.insert(address, account);
+                    counters.record_account_created();
+                }
+            }
+            Op::SetCode { address, code } => {
+                let address = decode.hex(|| parse_address(&address));
+                let code_bytes = decode.hex(|| parse_hex(&code));
                 let code_hash = keccak256(&code_bytes);
+                counters.record_set_code(address, code_bytes.len());
+                pending.code_bytes += code_bytes.len();
                 let bytecode = Bytecode::new_raw(code_bytes.into());
-                pending_bytecodes.push((code_hash, bytecode));
+                pending.bytecodes.push((code_hash, bytecode));
 
                 let account = account_map.get(&address).copied().unwrap_or_default();
                 let updated = Account {
                     bytecode_hash: Some(code_hash),
                     ..account
                 };
-                let hashed = keccak256(address);
-                pending_accounts.push((hashed, updated));
+                pending.push_account(cli, address, updated);
                 account_map.insert(address, updated);
-                contracts += 1;
-            }
-            "set_storage" => {
-                let address = parse_address(&op.address);
-                let slot = parse_b256(&op.slot);
-                let value = parse_u256(&op.value);
-                let hashed_address = keccak256(address);
-                let hashed_slot = keccak256(slot);
-                pending_storage.push((
-                    hashed_address,
-                    StorageEntry {
-                        key: hashed_slot,
-                        value,
-                    },
-                ));
-                slots += 1;
             }
-            "compute_root" => {
-                let db_write_ms =
-                    flush_writes(&db, &pending_accounts, &pending_bytecodes, &pending_storage);
-                emit_result(&db, start, accounts, contracts, slots, db_write_ms);
-                return;
+            // Defining a code changes no state, so it is not an applied
+            // operation.
+            Op::DefineCode { id, code } => {
+                let code_bytes = decode.hex(|| parse_hex(&code));
+                let defined = DefinedCode {
+                    hash: keccak256(&code_bytes),
+                    len: code_bytes.len(),
+                    unwritten: Some(Bytecode::new_raw(code_bytes.into())),
+                };
+                codes
+                    .define(id, defined)
+                    .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")));
+                mark = Instant::now();
+                continue;
+            }
+            Op::SetCodeRef { address, code_id } => {
+                let address = decode.hex(|| parse_address(&address));
+                let defined = codes
+                    .get_mut(&code_id)
+                    .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")));
+                counters.record_set_code(address, defined.len);
+                if let Some(bytecode) = defined.unwritten.take() {
+                    pending.code_bytes += defined.len;
+                    pending.bytecodes.push((defined.hash, bytecode));
+                }
+
+                let account = account_map.get(&address).copied().unwrap_or_default();
+                let updated = Account {
+                    bytecode_hash: Some(defined.hash),
+                    ..account
+                };
+                pending.push_account(cli, address, updated);
+                account_map.insert(address, updated);
+            }
+            Op::SetDelegation {
+                address,
+                target: written,
+            } => {
+                let address = decode.hex(|| parse_address(&address));
+                let target = decode.hex(|| parse_address(&written));
+                if cli.require_delegation_targets
+                    && current_account(&db, &pending, &account_map, target).is_none()
+                {
+                    fatal(&format!(
+                        "line {line}: set_delegation target {written} is not an account"
+                    ));
+                }
+                let code = delegation::designation(target.into_array());
+                let code_hash = keccak256(code);
+                counters.record_delegation(&address);
+                pending.code_bytes += code.len();
+                let bytecode = Bytecode::new_raw(code.to_vec().into());
+                pending.bytecodes.push((code_hash, bytecode));
+
+                let account = account_map.get(&address).copied().unwrap_or_default();
+                let updated = Account {
+                    bytecode_hash: Some(code_hash),
+                    ..account
+                };
+                pending.push_account(cli, address, updated);
+                account_map.insert(address, updated);
+            }
+            Op::UpdateBalance {
+                address,
+                value,
+                sign,
+            } => {
+                let address = decode.hex(|| parse_address(&address));
+                let delta = decode.hex(|| parse_u256(&value, line));
+                let account =
+                    current_account(&db, &pending, &account_map, address).unwrap_or_default();
+                let balance = apply_delta(cli, address, account.balance, delta, sign, line);
+                let updated = Account { balance, ..account };
+                pending.push_account(cli, address, updated);
+                account_map.insert(address, updated);
+                counters.record_balance_update();
+            }
+            Op::Transfer { from, to, value } => {
+                let from = decode.hex(|| parse_address(&from));
+                let to = decode.hex(|| parse_address(&to));
+                let delta = decode.hex(|| parse_u256(&value, line));
+                // Debit first, so a transfer the sender cannot cover fails
+                // or clamps exactly as the update_balance would.
+                for (address, sign) in [(from, Sign::Sub), (to, Sign::Add)] {
+                    let account =
+                        current_account(&db, &pending, &account_map, address).unwrap_or_default();
+                    let balance = apply_delta(cli, address, account.balance, delta, sign, line);
+                    let updated = Account { balance, ..account };
+                    pending.push_account(cli, address, updated);
+                    account_map.insert(address, updated);
+                }
+                counters.record_transfer();
+            }
+            Op::IncrementNonce { address, count } => {
+                let address = decode.hex(|| parse_address(&address));
+                let Some(account) = current_account(&db, &pending, &account_map, address) else {
+                    fatal(&format!(
+                        "line {line}: increment_nonce: {address} has no account"
+                    ));
+                };
+                let nonce = account.nonce.checked_add(count).unwrap_or_else(|| {
+                    fatal(&format!(
+                        "line {line}: increment_nonce: nonce of {address} overflows"
+                    ))
+                });
+                let updated = Account { nonce, ..account };
+                pending.push_account(cli, address, updated);
+                account_map.insert(address, updated);
+                counters.record_nonce_increment(count);
+            }
+            // Rewriting an account unchanged leaves the root as it is, but
+            // makes it a candidate for `--prune-empty-accounts`.
+            Op::TouchAccount { address } => {
+                let address = decode.hex(|| parse_address(&address));
+                let account =
+                    current_account(&db, &pending, &account_map, address).unwrap_or_default();
+                pending.push_account(cli, address, account);
+                account_map.insert(address, account);
+                counters.record_account_touched();
+            }
+            Op::SetStorage {
+                address,
+                slot,
+                value,
+            } => {
+                let address = decode.hex(|| parse_address(&address));
+                let slot = decode.hex(|| parse_b256(&slot, line));
+                let value = decode.hex(|| parse_u256(&value, line));
+                pending.push_storage(cli, address, slot, value);
+                counters.record_storage_write(address, slot);
+            }
+            Op::SetStorageBatch { address, entries } => {
+                let address = decode.hex(|| parse_address(&address));
+                let entries: Vec<(B256, U256)> = entries
+                    .iter()
+                    .map(|(slot, value)| {
+                        let slot = decode.hex(|| parse_b256(slot, line));
+                        counters.record_storage_write(address, slot);
+                        (slot, decode.hex(|| parse_u256(value, line)))
+                    })
+                    .collect();
+                pending.push_storage_batch(cli, address, &entries);
+            }
+            // Pre-Cancun selfdestruct leaves the same state as a deletion.
+            Op::DeleteAccount { address } | Op::Selfdestruct { address } => {
+                let address = decode.hex(|| parse_address(&address));
+                pending.delete_account(address);
+                account_map.remove(&address);
+                if selfdestruct {
+                    counters.record_selfdestruct(&address);
+                } else {
+                    counters.record_account_deleted(&address);
+                }
+            }
+            // The account survives, so only its storage is staged for
+            // deletion; nothing about the account itself is rewritten.
+            Op::ClearStorage { address } => {
+                let address = decode.hex(|| parse_address(&address));
+                if current_account(&db, &pending, &account_map, address).is_none() {
+                    fatal(&format!(
+                        "line {line}: clear_storage: {address} has no account"
+                    ));
+                }
+                pending.clear_storage(address);
+                counters.record_storage_cleared();
+            }
+            // Reads look up the hashed tables, which hold the state as of
+            // the last commit_block, each in its own read transaction as
+            // an RPC request would. They are not applied operations.
+            Op::ReadAccount { address } => {
+                let hashed_address = keccak256(decode.hex(|| parse_address(&address)));
+                reads
+                    .time(|| read_committed_account(&db, hashed_address))
+                    .unwrap_or_else(|e| fatal(&format!("read account: {e}")));
+                mark = Instant::now();
+                continue;
+            }
+            Op::ReadStorage { address, slot } => {
+                let hashed_address = keccak256(decode.hex(|| parse_address(&address)));
+                let hashed_slot = keccak256(decode.hex(|| parse_b256(&slot, line)));
+                reads
+                    .time(|| read_committed_slot(&db, hashed_address, hashed_slot))
+                    .unwrap_or_else(|e| fatal(&format!("read storage: {e}")));
+                mark = Instant::now();
+                continue;
+            }
+            Op::GetProof { address, slots } => {
+                if blocks.reports.is_empty() {
+                    fatal(&format!(
+                        "line {line}: get_proof before any committed root; end a block with \
+                         commit_block first"
+                    ));
+                }
+                let address = decode.hex(|| parse_address(&address));
+                let slots: Vec<B256> = slots
+                    .iter()
+                    .map(|slot| decode.hex(|| parse_b256(slot, line)))
+                    .collect();
+                proofs
+                    .time(|| committed_proof(&db, address, &slots))
+                    .unwrap_or_else(|e| fatal(&format!("get proof: {e}")));
+                mark = Instant::now();
+                continue;
             }
-            other => fatal(&format!("unknown operation: {other}")),
+            // Assertions read the state as the workload has written it,
+            // pending writes over the tables. They are not applied
+            // operations either.
+            Op::AssertAccount {
+                address: written,
+                balance,
+                nonce,
+            } => {
+                let address = decode.hex(|| parse_address(&written));
+                let account =
+                    current_account(&db, &pending, &account_map, address).unwrap_or_default();
+                if let Some(balance) = balance {
+                    let expected = decode.hex(|| parse_u256(&balance, line));
+                    let checked =
+                        assertions.check(line, &written, "balance", expected, account.balance);
+                    assertion_outcome(cli, checked);
+                }
+                if let Some(nonce) = nonce {
+                    let checked = assertions.check(line, &written, "nonce", nonce, account.nonce);
+                    assertion_outcome(cli, checked);
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::AssertStorage {
+                address: written,
+                slot,
+                expected,
+            } => {
+                let address = decode.hex(|| parse_address(&written));
+                let parsed_slot = decode.hex(|| parse_b256(&slot, line));
+                let expected = decode.hex(|| parse_u256(&expected, line));
+                let actual = current_slot(&db, &pending, address, parsed_slot);
+                let checked =
+                    assertions.check(line, &written, &format!("slot {slot}"), expected, actual);
+                assertion_outcome(cli, checked);
+                mark = Instant::now();
+                continue;
+            }
+            // Taken in by `header.record` above.
+            Op::Header { .. } => {
+                mark = Instant::now();
+                continue;
+            }
+            // A checkpoint only reports; nothing is flushed or hashed.
+            Op::Checkpoint { label } => {
+                metrics
+                    .record(&label, line, &counters)
+                    .unwrap_or_else(|e| fatal(&format!("write metrics: {e}")));
+                mark = Instant::now();
+                continue;
+            }
+            // A sleep only waits, and its time is left out of elapsed_ms
+            // and of the time box.
+            Op::Sleep { ms } => {
+                let slept = sleeps.sleep(ms);
+                if let Some(time_box) = time_box.as_mut() {
+                    time_box.extend(slept);
+                }
+                mark = Instant::now();
+                continue;
+            }
+            // A trailer only describes the lines before it.
+            Op::Trailer { counts, lines } => {
+                let report = tally.check(&counts, lines);
+                if cli.strict && !report.trailer_match {
+                    fatal(&format!(
+                        "line {line}: trailer does not match the workload: {report}"
+                    ));
+                }
+                trailer = Some(report);
+                mark = Instant::now();
+                continue;
+            }
+            // A canary checks how the value would be stored without storing it.
+            Op::Canary {
+                value,
+                expected_leaf_hash,
+                ..
+            } => {
+                let value = decode.hex(|| parse_u256(&value, line));
+                let leaf = hexfmt::fixed(keccak256(alloy_rlp::encode(value)));
+                if !canaries.check(line, &expected_leaf_hash, &leaf) && cli.strict {
+                    fatal(&format!(
+                        "line {line}: canary leaf hash {leaf} is not the expected {expected_leaf_hash}"
+                    ));
+                }
+                mark = Instant::now();
+                continue;
+            }
+            Op::CommitBlock { approx_gas } => {
+                let pruned =
+                    blocks.commit(&db, cli, &mut pending, &mut account_map, &guard, block_ops);
+                counters.record_empty_pruned(pruned);
+                if let Some(block) = blocks.reports.last_mut() {
+                    block.approx_gas = approx_gas;
+                    block.block_number = block_tags.current();
+                }
+                block_ops = 0;
+                progress.set_pending(pending.sizes());
+                mark = Instant::now();
+                continue;
+            }
+            Op::ComputeRoot {
+                expected_root: expected,
+            } => {
+                let more = !input
+                    .at_end()
+                    .unwrap_or_else(|e| fatal(&format!("read stdin: {e}")));
+                if more {
+                    // Right after commit_block the root is that block's,
+                    // so no empty block is committed to recompute it.
+                    if block_ops > 0 || blocks.reports.is_empty() {
+                        let pruned = blocks.commit(
+                            &db,
+                            cli,
+                            &mut pending,
+                            &mut account_map,
+                            &guard,
+                            block_ops,
+                        );
+                        counters.record_empty_pruned(pruned);
+                        block_ops = 0;
+                        progress.set_pending(pending.sizes());
+                    }
+                    // The run-level measurements, such as system metrics,
+                    // are left to the final result.
+                    let mut result = compute_result(
+                        start,
+                        &sleeps,
+                        &counters,
+                        blocks.write_timing(),
+                        blocks.root_timing(),
+                        input.warnings(),
+                    );
+                    result.pending_at_root = assert_committed(&pending);
+                    result.measurement_model =
+                        Some(measurement_model(cli, result.root_algorithm, true));
+                    result.root_match =
+                        expected.map(|expected| rootcheck::matches(&expected, &result.state_root));
+                    result.blocks = blocks.reports.kept().to_vec();
+                    result.fingerprint = Some(fingerprint.report());
+                    result.reads = reads.report();
+                    result.proofs = proofs.report();
+                    result.reserved = Some(reserved);
+                    result.preload.clone_from(&preload);
+                    result.trailer.clone_from(&trailer);
+                    result.canaries = canaries.report();
+                    result.assertions = assertions.report();
+                    (result.input_bytes_by_op, result.avg_bytes_per_op) =
+                        input_bytes.clone().into_parts();
+                    checkpoint(&result);
+                    mark = Instant::now();
+                    continue;
+                }
+                root_requested = true;
+                expected_root = expected;
+                break;
+            }
+        }
+
+        let applied = counters.record_applied();
+        block_ops = block_ops.saturating_add(1);
+        let now = Instant::now();
+        progress.applied_op(now - mark);
+        if let Some(warning) = watchdog.applied(now - mark) {
+            eprintln!("reth-harness: {warning}");
+        }
+        mark = now;
+        progress.set_pending(pending.sizes());
+        if cli.max_ops.is_some_and(|max| applied >= max) {
+            truncated_at_op = Some(applied);
+            break;
+        }
+        if time_box
+            .as_mut()
+            .is_some_and(|time_box| time_box.applied(now))
+        {
+            break;
         }
     }
 
-    fatal("no compute_root operation found");
+    // A stream of blocks may end without compute_root.
+    let block_mode = !blocks.reports.is_empty();
+    let deadline_reached = time_box.as_ref().is_some_and(TimeBox::reached);
+    if !root_requested && truncated_at_op.is_none() && !deadline_reached && !block_mode {
+        fatal("no compute_root operation found");
+    }
+    let expected_root = expected_root
+        .or_else(|| header.expected_root(truncated_at_op.is_none() && !deadline_reached));
+
+    if let Some(log) = events.as_mut() {
+        log.flush()
+            .unwrap_or_else(|e| fatal(&format!("flush events: {e}")));
+    }
+    #[cfg(feature = "profiling")]
+    let mut profiler = Profiler::new(&cli.profile);
+    #[cfg(not(feature = "profiling"))]
+    let mut profiler = Profiler::default();
+    let mut background = BackgroundCpu::default();
+    let write_cpu = CpuSample::now();
+    progress.set_phase(Phase::DbWrite);
+    profiler.phase("db_write");
+    // Operations after the last commit_block form one more block.
+    let write = if block_mode {
+        if block_ops > 0 {
+            let pruned = blocks.commit(&db, cli, &mut pending, &mut account_map, &guard, block_ops);
+            counters.record_empty_pruned(pruned);
+            if let Some(block) = blocks.reports.last_mut() {
+                block.block_number = block_tags.current();
+            }
+        }
+        blocks.write_timing()
+    } else {
+        if cli.prune_empty_accounts {
+            counters.record_empty_pruned(pending.prune_empty(&mut account_map));
+        }
+        flush_writes(
+            &db,
+            &mut pending,
+            &guard,
+            cli.inject_commit_latency_ms,
+            cli.keep_zero_slots,
+        )
+    };
+    progress.set_phase(Phase::Trie);
+    profiler.phase("trie");
+    let trie_cpu = CpuSample::now();
+    let write_metrics = SystemMetrics::capture();
+    background.record("db_write", write_cpu, trie_cpu);
+    let pending_at_root = assert_committed(&pending);
+    let root = if block_mode {
+        blocks.root_timing()
+    } else {
+        compute_root(&db, cli)
+    };
+    profiler.stop();
+    let mut result = compute_result(start, &sleeps, &counters, write, root, input.warnings());
+    result.pending_at_root = pending_at_root;
+    result.measurement_model = Some(measurement_model(cli, result.root_algorithm, block_mode));
+    if block_mode && cli.root_algorithm != RootAlgorithm::Serial {
+        result.warnings.push(
+            "--root-algorithm ignored: commit_block roots are updated incrementally with the \
+             serial algorithm"
+                .to_string(),
+        );
+    }
+    (result.blocks, result.block_stats) = blocks::summarize(
+        blocks.reports,
+        cli.rolling_window,
+        cli.emit_per_block,
+        &blocks::sidecar_path(db_path),
+    )
+    .unwrap_or_else(|e| fatal(&e));
+    background.record("trie", trie_cpu, CpuSample::now());
+    progress.set_phase(Phase::Done);
+
+    let (background_cpu_ms, cpu_warnings) = background.into_parts();
+    result.truncated_at_op = truncated_at_op;
+    result.root_match =
+        expected_root.map(|expected| rootcheck::matches(&expected, &result.state_root));
+    result.time_box = time_box.map(|time_box| time_box.report(counters.ops_applied()));
+    result.fingerprint = Some(fingerprint.report());
+    result.workload_name = header.name;
+    result.reads = reads.report();
+    result.proofs = proofs.report();
+    result.reserved = Some(reserved);
+    result.preload = preload;
+    result.trailer = trailer;
+    result.canaries = canaries.report();
+    result.assertions = assertions.report();
+    (result.input_bytes_by_op, result.avg_bytes_per_op) = input_bytes.into_parts();
+    result.decode_estimates = Some(decode.estimates());
+    result.op_rates = Some(progress.rates());
+    let apply_warning;
+    (result.apply_throughput_min_ops_per_sec, apply_warning) = watchdog.into_parts();
+    result.warnings.extend(apply_warning);
+    result.background_cpu_ms = background_cpu_ms;
+    result
+        .system_metrics
+        .insert("after_db_write", write_metrics);
+    result.warnings.extend(cpu_warnings);
+    let profile_warnings;
+    (result.artifacts, profile_warnings) = profiler.into_parts();
+    result.warnings.extend(profile_warnings);
+    result.db_logical_size_bytes = Some(logical_size(&db));
+    if cli.full_tables {
+        result.table_size_bytes = table_sizes(&db);
+    }
+    let environment = Environment {
+        start: env_start,
+        end: EnvSnapshot::capture(),
+    };
+    result.warnings.extend(environment.swap_warning());
+    result.environment = Some(environment);
+    result
+}
+
+/// Describes what the phases of a run measured. Writes go to the hashed
+/// tables first and the root is computed over them, so the trie phase
+/// reads the database; outside block mode its nodes are never written.
+fn measurement_model(
+    cli: &Cli,
+    root_algorithm: &'static str,
+    block_mode: bool,
+) -> MeasurementModel {
+    let mut model = MeasurementModel::standard(cli.preload_workload, block_mode);
+    model.code_path("phase_order", "db_write,trie");
+    model.code_path("trie_source", "hashed_tables");
+    model.code_path(
+        "root",
+        if block_mode {
+            "incremental"
+        } else {
+            root_algorithm
+        },
+    );
+    model.code_path(
+        "writes",
+        if cli.full_tables {
+            "hashed_and_plain_tables"
+        } else {
+            "hashed_tables"
+        },
+    );
+    model.code_path(
+        "trie_nodes",
+        if block_mode { "written" } else { "not_written" },
+    );
+    model
 }
 
-/// Writes all pending state to MDBX in a single transaction.
-/// Returns the time spent writing in milliseconds.
+/// Checks that every staged write was committed before a root is
+/// computed, returning the count of those left over, zero, for the
+/// result. A root over the committed tables would otherwise silently
+/// miss them, so anything left is a harness bug and aborts the run.
+fn assert_committed(pending: &PendingWrites) -> u64 {
+    let staged = pending.staged();
+    if staged != 0 {
+        fatal(&format!(
+            "internal error: {staged} staged writes were not committed before computing the root"
+        ));
+    }
+    staged
+}
+
+/// Writes all pending state to MDBX in a single transaction, sleeping
+/// for `latency_ms` after the commit when latency injection is enabled.
+/// A later write of a slot replaces the earlier one, and a zero value
+/// deletes the slot unless `keep_zero_slots` is set. Running out of disk
+/// space ends the run with a partial result. `pending` is left empty, as
+/// its writes are committed once this returns.
 fn flush_writes(
     db: &DatabaseEnv,
-    accounts: &[(B256, Account)],
-    bytecodes: &[(B256, Bytecode)],
-    storage: &[(B256, StorageEntry)],
-) -> u64 {
+    pending: &mut PendingWrites,
+    guard: &SpaceGuard,
+    latency_ms: u64,
+    keep_zero_slots: bool,
+) -> WriteTiming {
+    let pending = std::mem::take(pending);
     let db_start = Instant::now();
 
     let tx = db
         .tx_mut()
-        .unwrap_or_else(|e| fatal(&format!("begin write tx: {e}")));
+        .unwrap_or_else(|e| write_failed(guard, "begin write tx", &e));
+
+    let mut written: u64 = 0;
+    let mut check_reserve = || {
+        if guard.reserve_exhausted(written) {
+            disk_full(&guard.report("reth", "reserve", None));
+        }
+        written += 1;
+    };
+
+    for (hashed_address, deletion) in &pending.deleted {
+        check_reserve();
+        tx.delete::<tables::HashedAccounts>(*hashed_address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete HashedAccounts", &e));
+        tx.delete::<tables::HashedStorages>(*hashed_address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete HashedStorages", &e));
+        tx.delete::<tables::PlainAccountState>(deletion.address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete PlainAccountState", &e));
+        tx.delete::<tables::PlainStorageState>(deletion.address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete PlainStorageState", &e));
+        tx.delete::<tables::StoragesTrie>(*hashed_address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete StoragesTrie", &e));
+    }
+
+    for (hashed_address, clear) in &pending.storage_cleared {
+        check_reserve();
+        tx.delete::<tables::HashedStorages>(*hashed_address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete HashedStorages", &e));
+        tx.delete::<tables::PlainStorageState>(clear.address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete PlainStorageState", &e));
+        tx.delete::<tables::StoragesTrie>(*hashed_address, None)
+            .unwrap_or_else(|e| write_failed(guard, "delete StoragesTrie", &e));
+    }
 
-    for (hashed_address, account) in accounts {
+    for (i, (hashed_address, account)) in pending.accounts.iter().enumerate() {
+        if pending.superseded(hashed_address, 0, i) {
+            continue;
+        }
+        check_reserve();
         tx.put::<tables::HashedAccounts>(*hashed_address, *account)
-            .unwrap_or_else(|e| fatal(&format!("put HashedAccounts: {e}")));
+            .unwrap_or_else(|e| write_failed(guard, "put HashedAccounts", &e));
     }
 
-    for (code_hash, bytecode) in bytecodes {
+    for (code_hash, bytecode) in &pending.bytecodes {
+        check_reserve();
         tx.put::<tables::Bytecodes>(*code_hash, bytecode.clone())
-            .unwrap_or_else(|e| fatal(&format!("put Bytecodes: {e}")));
+            .unwrap_or_else(|e| write_failed(guard, "put Bytecodes", &e));
+    }
+
+    let mut storage = tx
+        .cursor_dup_write::<tables::HashedStorages>()
+        .unwrap_or_else(|e| write_failed(guard, "open HashedStorages cursor", &e));
+    for (i, (hashed_address, entry)) in pending.storage.iter().enumerate() {
+        if pending.superseded(hashed_address, 1, i) {
+            continue;
+        }
+        check_reserve();
+        put_slot(&mut storage, *hashed_address, entry, keep_zero_slots)
+            .unwrap_or_else(|e| write_failed(guard, "put HashedStorages", &e));
     }
+    drop(storage);
 
-    for (hashed_address, entry) in storage {
-        tx.put::<tables::HashedStorages>(*hashed_address, *entry)
-            .unwrap_or_else(|e| fatal(&format!("put HashedStorages: {e}")));
+    for (i, (address, account)) in pending.plain_accounts.iter().enumerate() {
+        if !pending.deleted.is_empty() && pending.superseded(&keccak256(address), 2, i) {
+            continue;
+        }
+        check_reserve();
+        tx.put::<tables::PlainAccountState>(*address, *account)
+            .unwrap_or_else(|e| write_failed(guard, "put PlainAccountState", &e));
+    }
+
+    let mut plain_storage = tx
+        .cursor_dup_write::<tables::PlainStorageState>()
+        .unwrap_or_else(|e| write_failed(guard, "open PlainStorageState cursor", &e));
+    let wiped = !pending.deleted.is_empty() || !pending.storage_cleared.is_empty();
+    for (i, (address, entry)) in pending.plain_storage.iter().enumerate() {
+        if wiped && pending.superseded(&keccak256(address), 3, i) {
+            continue;
+        }
+        check_reserve();
+        put_slot(&mut plain_storage, *address, entry, keep_zero_slots)
+            .unwrap_or_else(|e| write_failed(guard, "put PlainStorageState", &e));
     }
+    drop(plain_storage);
 
     tx.commit()
-        .unwrap_or_else(|e| fatal(&format!("commit tx: {e}")));
+        .unwrap_or_else(|e| write_failed(guard, "commit tx", &e));
+    let injected_ms = (latency_ms > 0).then(|| inject_commit_latency(latency_ms));
 
-    db_start.elapsed().as_millis() as u64
+    WriteTiming {
+        db_write_ms: db_start.elapsed().as_millis() as u64,
+        injected_ms,
+    }
 }
 
-fn emit_result(
-    db: &DatabaseEnv,
-    start: Instant,
-    accounts: u64,
-    contracts: u64,
-    slots: u64,
-    db_write_ms: u64,
-) {
-    let trie_start = Instant::now();
+/// Writes one storage slot to a dup-sorted storage table, replacing the
+/// slot's earlier entry. A zero value only removes it, as reth does,
+/// unless `keep_zero` is set.
+fn put_slot<T, C>(
+    cursor: &mut C,
+    key: T::Key,
+    entry: &StorageEntry,
+    keep_zero: bool,
+) -> Result<(), DatabaseError>
+where
+    T: DupSort<SubKey = B256, Value = StorageEntry>,
+    C: DbDupCursorRO<T> + DbCursorRW<T>,
+{
+    if cursor
+        .seek_by_key_subkey(key.clone(), entry.key)?
+        .is_some_and(|existing| existing.key == entry.key)
+    {
+        cursor.delete_current()?;
+    }
+    if keep_zero || !entry.value.is_zero() {
+        cursor.upsert(key, entry)?;
+    }
+    Ok(())
+}
+
+/// Returns the on-disk size of each table the harness writes, from the
+/// MDBX page counts.
+fn table_sizes(db: &DatabaseEnv) -> BTreeMap<&'static str, u64> {
+    let tx = db
+        .tx()
+        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+    [
+        tables::HashedAccounts::NAME,
+        tables::HashedStorages::NAME,
+        tables::Bytecodes::NAME,
+        tables::PlainAccountState::NAME,
+        tables::PlainStorageState::NAME,
+    ]
+    .into_iter()
+    .map(|name| (name, table_bytes(&tx, name)))
+    .collect()
+}
+
+/// Returns the bytes in the pages of every table, the size MDBX reports
+/// in use. The data file itself grows in geometry steps and keeps freed
+/// pages, so it is usually larger.
+fn logical_size(db: &DatabaseEnv) -> u64 {
+    let tx = db
+        .tx()
+        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+    tables::Tables::ALL
+        .iter()
+        .map(|table| table_bytes(&tx, table.name()))
+        .sum()
+}
+
+/// Returns the bytes in the leaf, branch and overflow pages of `name`.
+fn table_bytes(tx: &<DatabaseEnv as Database>::TX, name: &str) -> u64 {
+    let table = tx
+        .inner
+        .open_db(Some(name))
+        .unwrap_or_else(|e| fatal(&format!("open table {name}: {e}")));
+    let stat = tx
+        .inner
+        .db_stat(&table)
+        .unwrap_or_else(|e| fatal(&format!("stat table {name}: {e}")));
+    let pages = stat.leaf_pages() + stat.branch_pages() + stat.overflow_pages();
+    pages as u64 * u64::from(stat.page_size())
+}
+
+/// Handles a failed DB write: out-of-space errors end the run with a
+/// partial result, anything else is fatal.
+fn write_failed(guard: &SpaceGuard, what: &str, err: &dyn std::fmt::Display) -> ! {
+    if is_disk_full(err) {
+        disk_full(&guard.report("reth", "enospc", Some(format!("{what}: {err}"))));
+    }
+    fatal(&format!("{what}: {err}"));
+}
+
+/// Prints the partial result of a run stopped for lack of disk space
+/// and exits with [`EXIT_DISK_FULL`].
+fn disk_full(report: &DiskFullReport) -> ! {
+    serde_json::to_writer(io::stdout(), report)
+        .unwrap_or_else(|e| fatal(&format!("encode result: {e}")));
+    println!();
+    std::process::exit(EXIT_DISK_FULL);
+}
+
+/// Sleeps for the artificial commit latency and returns the time
+/// actually slept in milliseconds.
+fn inject_commit_latency(latency_ms: u64) -> u64 {
+    let sleep_start = Instant::now();
+    std::thread::sleep(Duration::from_millis(latency_ms));
+    sleep_start.elapsed().as_millis() as u64
+}
+
+/// Computes the state root over the committed hashed tables with the
+/// selected algorithm.
+fn compute_root(db: &DatabaseEnv, cli: &Cli) -> RootTiming {
+    let threads = cli.root_threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+    });
+    let timed = |f: &dyn Fn() -> B256| {
+        let trie_start = Instant::now();
+        let root = f();
+        (root, trie_start.elapsed().as_millis() as u64)
+    };
+
+    match cli.root_algorithm {
+        RootAlgorithm::Serial => {
+            let (root, trie_ms) = timed(&|| serial_state_root(db));
+            RootTiming {
+                root,
+                trie_ms,
+                algorithm: "serial",
+                threads: None,
+                parallel_ms: None,
+            }
+        }
+        RootAlgorithm::Parallel => {
+            let (root, trie_ms) = timed(&|| parallel_state_root(db, threads));
+            RootTiming {
+                root,
+                trie_ms,
+                algorithm: "parallel",
+                threads: Some(threads),
+                parallel_ms: None,
+            }
+        }
+        RootAlgorithm::Both => {
+            let (root, trie_ms) = timed(&|| serial_state_root(db));
+            let (parallel_root, parallel_ms) = timed(&|| parallel_state_root(db, threads));
+            if parallel_root != root {
+                fatal(&format!(
+                    "state root mismatch: serial {}, parallel {}",
+                    hexfmt::fixed(root),
+                    hexfmt::fixed(parallel_root)
+                ));
+            }
+            RootTiming {
+                root,
+                trie_ms,
+                algorithm: "both",
+                threads: Some(threads),
+                parallel_ms: Some(parallel_ms),
+            }
+        }
+    }
+}
+
+fn serial_state_root(db: &DatabaseEnv) -> B256 {
     let tx = db
         .tx()
         .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
-    let root = StateRoot::from_tx(&tx)
+    StateRoot::from_tx(&tx)
         .root()
-        .unwrap_or_else(|e| fatal(&format!("compute state root: {e}")));
-    let trie_ms = trie_start.elapsed().as_millis() as u64;
+        .unwrap_or_else(|e| fatal(&format!("compute state root: {e}")))
+}
+
+/// Drops the trie nodes an earlier `commit_block` run stored, which a
+/// root computation would otherwise reuse as if they were current.
+fn clear_stale_trie(db: &DatabaseEnv, guard: &SpaceGuard) {
+    let tx = db
+        .tx_mut()
+        .unwrap_or_else(|e| write_failed(guard, "begin write tx", &e));
+    let stored = tx
+        .entries::<tables::AccountsTrie>()
+        .and_then(|accounts| Ok(accounts + tx.entries::<tables::StoragesTrie>()?))
+        .unwrap_or_else(|e| fatal(&format!("count trie entries: {e}")));
+    if stored == 0 {
+        return;
+    }
+    tx.clear::<tables::AccountsTrie>()
+        .unwrap_or_else(|e| write_failed(guard, "clear AccountsTrie", &e));
+    tx.clear::<tables::StoragesTrie>()
+        .unwrap_or_else(|e| write_failed(guard, "clear StoragesTrie", &e));
+    tx.commit()
+        .unwrap_or_else(|e| write_failed(guard, "commit tx", &e));
+}
+
+/// Marks what a block changed for the incremental root: every account
+/// written or deleted, every slot written, and all the storage of a
+/// deleted account or one whose storage was cleared.
+fn prefix_sets(pending: &PendingWrites, account_map: &HashMap<Address, Account>) -> TriePrefixSets {
+    let mut sets = TriePrefixSetsMut::default();
+    for (hashed_address, _) in &pending.accounts {
+        sets.account_prefix_set
+            .insert(Nibbles::unpack(hashed_address));
+    }
+    for (hashed_address, entry) in &pending.storage {
+        sets.account_prefix_set
+            .insert(Nibbles::unpack(hashed_address));
+        sets.storage_prefix_sets
+            .entry(*hashed_address)
+            .or_default()
+            .insert(Nibbles::unpack(entry.key));
+    }
+    for (hashed_address, deletion) in &pending.deleted {
+        sets.account_prefix_set
+            .insert(Nibbles::unpack(hashed_address));
+        sets.storage_prefix_sets
+            .insert(*hashed_address, PrefixSetMut::all());
+        if !account_map.contains_key(&deletion.address) {
+            sets.destroyed_accounts.insert(*hashed_address);
+        }
+    }
+    for hashed_address in pending.storage_cleared.keys() {
+        sets.account_prefix_set
+            .insert(Nibbles::unpack(hashed_address));
+        sets.storage_prefix_sets
+            .insert(*hashed_address, PrefixSetMut::all());
+    }
+    sets.freeze()
+}
+
+/// Computes the state root with reth's serial algorithm, reusing the
+/// stored trie outside `prefix_sets`, and returns the nodes it changed.
+fn incremental_root(db: &DatabaseEnv, prefix_sets: TriePrefixSets) -> (B256, TrieUpdates) {
+    let tx = db
+        .tx()
+        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+    StateRoot::from_tx(&tx)
+        .with_prefix_sets(prefix_sets)
+        .root_with_updates()
+        .unwrap_or_else(|e| fatal(&format!("compute state root: {e}")))
+}
 
-    let result = BenchResult {
+/// Stores the trie nodes a block changed, so the next block's root
+/// starts from them. Root nodes are never stored.
+fn write_trie_updates(db: &DatabaseEnv, updates: &TrieUpdates, guard: &SpaceGuard) {
+    let tx = db
+        .tx_mut()
+        .unwrap_or_else(|e| write_failed(guard, "begin write tx", &e));
+
+    let account_nodes = updates.account_nodes_ref();
+    for nibbles in updates.removed_nodes_ref() {
+        if !account_nodes.contains_key(nibbles) {
+            tx.delete::<tables::AccountsTrie>(StoredNibbles(*nibbles), None)
+                .unwrap_or_else(|e| write_failed(guard, "delete AccountsTrie", &e));
+        }
+    }
+    for (nibbles, node) in account_nodes {
+        if !nibbles.is_empty() {
+            tx.put::<tables::AccountsTrie>(StoredNibbles(*nibbles), node.clone())
+                .unwrap_or_else(|e| write_failed(guard, "put AccountsTrie", &e));
+        }
+    }
+
+    for (hashed_address, storage) in updates.storage_tries_ref() {
+        if storage.is_deleted() {
+            tx.delete::<tables::StoragesTrie>(*hashed_address, None)
+                .unwrap_or_else(|e| write_failed(guard, "delete StoragesTrie", &e));
+        }
+    }
+    let mut cursor = tx
+        .cursor_dup_write::<tables::StoragesTrie>()
+        .unwrap_or_else(|e| write_failed(guard, "open StoragesTrie cursor", &e));
+    for (hashed_address, storage) in updates.storage_tries_ref() {
+        let storage_nodes = storage.storage_nodes_ref();
+        let removed = storage
+            .removed_nodes_ref()
+            .iter()
+            .filter(|nibbles| !storage_nodes.contains_key(*nibbles))
+            .map(|nibbles| (*nibbles, None));
+        let changed = storage_nodes
+            .iter()
+            .filter(|(nibbles, _)| !nibbles.is_empty())
+            .map(|(nibbles, node)| (*nibbles, Some(node)));
+        for (nibbles, node) in removed.chain(changed) {
+            if cursor
+                .seek_by_key_subkey(*hashed_address, StoredNibblesSubKey(nibbles))
+                .unwrap_or_else(|e| write_failed(guard, "seek StoragesTrie", &e))
+                .is_some_and(|entry| entry.nibbles.0 == nibbles)
+            {
+                cursor
+                    .delete_current()
+                    .unwrap_or_else(|e| write_failed(guard, "delete StoragesTrie", &e));
+            }
+            if let Some(node) = node {
+                let entry = StorageTrieEntry {
+                    nibbles: StoredNibblesSubKey(nibbles),
+                    node: node.clone(),
+                };
+                cursor
+                    .upsert(*hashed_address, &entry)
+                    .unwrap_or_else(|e| write_failed(guard, "put StoragesTrie", &e));
+            }
+        }
+    }
+    drop(cursor);
+
+    tx.commit()
+        .unwrap_or_else(|e| write_failed(guard, "commit tx", &e));
+}
+
+/// Computes storage roots on `threads` workers, each with its own read
+/// transaction, then folds the account trie on the calling thread. This
+/// is the split reth's parallel state root uses.
+fn parallel_state_root(db: &DatabaseEnv, threads: usize) -> B256 {
+    let tx = db
+        .tx()
+        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+    let mut cursor = tx
+        .cursor_read::<tables::HashedAccounts>()
+        .unwrap_or_else(|e| fatal(&format!("open HashedAccounts cursor: {e}")));
+    let accounts: Vec<(B256, Account)> = cursor
+        .walk(None)
+        .unwrap_or_else(|e| fatal(&format!("walk HashedAccounts: {e}")))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|e| fatal(&format!("read HashedAccounts: {e}")));
+
+    let chunk = accounts.len().div_ceil(threads.max(1)).max(1);
+    let storage_roots: Vec<B256> = std::thread::scope(|scope| {
+        let workers: Vec<_> = accounts
+            .chunks(chunk)
+            .map(|part| {
+                scope.spawn(move || {
+                    let tx = db
+                        .tx()
+                        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+                    part.iter()
+                        .map(|(hashed_address, _)| {
+                            StorageRoot::from_tx_hashed(&tx, *hashed_address)
+                                .root()
+                                .unwrap_or_else(|e| fatal(&format!("compute storage root: {e}")))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|_| fatal("storage root worker panicked"))
+            })
+            .collect()
+    });
+
+    let mut builder = HashBuilder::default();
+    let mut buf = Vec::new();
+    for ((hashed_address, account), storage_root) in accounts.iter().zip(storage_roots) {
+        buf.clear();
+        account.into_trie_account(storage_root).encode(&mut buf);
+        builder.add_leaf(Nibbles::unpack(hashed_address), &buf);
+    }
+    builder.root()
+}
+
+/// Assembles the benchmark result from the phase timings.
+fn compute_result(
+    start: Instant,
+    sleeps: &Sleeps,
+    counters: &Counters<Address, B256>,
+    write: WriteTiming,
+    root: RootTiming,
+    mut warnings: Vec<String>,
+) -> BenchResult {
+    let end_metrics = SystemMetrics::capture();
+    warnings.extend(counters.saturation_warning());
+
+    BenchResult {
         client: "reth",
-        state_root: format!("{root:#x}"),
-        accounts_created: accounts,
-        contracts_created: contracts,
-        storage_slots: slots,
-        elapsed_ms: start.elapsed().as_millis() as u64,
-        trie_time_ms: trie_ms,
-        db_write_time_ms: db_write_ms,
-        peak_memory_bytes: peak_memory_bytes(),
+        simulated: write.injected_ms.is_some(),
+        state_root: hexfmt::fixed(root.root),
+        root_match: None,
+        counters: counters.totals(),
+        elapsed_ms: sleeps.elapsed_since(start).as_millis() as u64,
+        sleep_ms: sleeps.sleep_ms(),
+        trie_time_ms: root.trie_ms,
+        db_write_time_ms: write.db_write_ms,
+        blocks: Vec::new(),
+        block_stats: None,
+        peak_memory_bytes: end_metrics.vm_hwm_bytes.unwrap_or(0),
+        db_logical_size_bytes: None,
+        pending_at_root: 0,
+        warnings,
+        injected_latency_total_ms: write.injected_ms,
+        db_write_time_net_ms: write
+            .injected_ms
+            .map(|injected| write.db_write_ms.saturating_sub(injected)),
+        // MDBX opens in its default durable sync mode, so the commit
+        // returns only after the data is fsynced.
+        durability: DURABLE,
+        truncated_at_op: None,
+        time_box: None,
+        fingerprint: None,
+        workload_name: None,
+        reads: None,
+        proofs: None,
+        reserved: None,
+        preload: None,
+        compression: None,
+        trailer: None,
+        canaries: None,
+        assertions: None,
+        input_bytes_by_op: BTreeMap::new(),
+        avg_bytes_per_op: None,
+        decode_estimates: None,
+        op_rates: None,
+        apply_throughput_min_ops_per_sec: None,
+        background_cpu_ms: BTreeMap::new(),
+        system_metrics: BTreeMap::from([("end", end_metrics)]),
+        environment: None,
+        table_size_bytes: BTreeMap::new(),
+        root_algorithm: root.algorithm,
+        root_threads: root.threads,
+        parallel_trie_time_ms: root.parallel_ms,
+        durability_probe: None,
+        code_read: None,
+        recompute: None,
+        artifacts: Vec::new(),
+        measurement_model: None,
+        invocation: None,
+    }
+}
+
+/// Prints the checked result line, spilling an oversized result to a
+/// sidecar next to the database, then the time the write took to stderr.
+fn emit_result(result: &impl Serialize, db_path: &Path) {
+    let emitted = output::emit(
+        &mut io::stdout().lock(),
+        "reth",
+        result,
+        &output::sidecar_path(db_path),
+    )
+    .unwrap_or_else(|e| fatal(&e));
+    eprintln!("{emitted}");
+}
+
+/// Runs `--read-code` against the committed database: a pass over the
+/// sample on a fresh read-only environment, then the same sample again on
+/// another one after dropping the page cache. MDBX keeps no cache of its
+/// own, so the first pass is warm only by what the OS still holds. The
+/// listing of the codes and the passes each get their own budget.
+fn read_code(
+    db_path: &Path,
+    n: u64,
+    iterate_secs: Option<u64>,
+    read_bench_secs: Option<u64>,
+) -> CodeReadReport {
+    let open = || {
+        open_db_read_only(db_path, DatabaseArguments::new(ClientVersion::default()))
+            .unwrap_or_else(|e| fatal(&format!("open mdbx read-only: {e}")))
     };
+    let db = open();
+    let (hashes, codes_truncated) = code_hashes(&db, &Budget::start(iterate_secs));
+    let codes = hashes.len() as u64;
+    let sample = coderead::sample(hashes, n);
+    let budget = Budget::start(read_bench_secs);
+    let warm = read_code_pass(&db, &sample, &budget);
+    drop(db);
+    let cold = coderead::cold_pass(&budget, || read_code_pass(&open(), &sample, &budget));
+    CodeReadReport::new(codes, codes_truncated, warm, cold)
+}
 
-    serde_json::to_writer(io::stdout(), &result)
-        .unwrap_or_else(|e| fatal(&format!("encode result: {e}")));
-    println!();
+/// Runs `--recompute-root` against the committed database with the run's
+/// `--root-algorithm`. No trie updates are written, so every computation
+/// walks the same tables; MDBX keeps no cache of its own, and a warm one
+/// gains only what the OS holds of their pages.
+fn recompute_root(cli: &Cli, db_path: &Path, passes: u64, expected: &str) -> RecomputeReport {
+    let root = |db: &DatabaseEnv| hexfmt::fixed(compute_root(db, cli).root);
+    recompute::measure(
+        passes,
+        expected,
+        || {
+            let db = open_db_read_only(db_path, DatabaseArguments::new(ClientVersion::default()))
+                .unwrap_or_else(|e| fatal(&format!("open mdbx read-only: {e}")));
+            let cold = root(&db);
+            (db, cold)
+        },
+        root,
+    )
+    .unwrap_or_else(|e| fatal(&e))
+}
+
+/// Lists the hashes of the codes in the Bytecodes table, all of them
+/// unless `budget` runs out first, which the flag reports.
+fn code_hashes(db: &DatabaseEnv, budget: &Budget) -> (Vec<B256>, bool) {
+    let tx = db
+        .tx()
+        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+    let mut cursor = tx
+        .cursor_read::<tables::Bytecodes>()
+        .unwrap_or_else(|e| fatal(&format!("open Bytecodes cursor: {e}")));
+    let mut hashes = Vec::new();
+    for entry in cursor
+        .walk(None)
+        .unwrap_or_else(|e| fatal(&format!("walk Bytecodes: {e}")))
+    {
+        if budget.exhausted() {
+            return (hashes, true);
+        }
+        let (hash, _) = entry.unwrap_or_else(|e| fatal(&format!("read Bytecodes: {e}")));
+        hashes.push(hash);
+    }
+    (hashes, false)
+}
+
+/// Reads `sample` in one read transaction, as a provider serving
+/// `eth_getCode` would, until `budget` runs out.
+fn read_code_pass(db: &DatabaseEnv, sample: &[B256], budget: &Budget) -> CodeReadPass {
+    let tx = db
+        .tx()
+        .unwrap_or_else(|e| fatal(&format!("begin read tx: {e}")));
+    coderead::measure(sample, budget, |hash| {
+        tx.get::<tables::Bytecodes>(*hash)
+            .map(|code| code.map(|code| code.original_byte_slice().len()))
+            .map_err(|e| format!("read Bytecodes: {e}"))
+    })
+    .unwrap_or_else(|e| fatal(&e))
+}
+
+/// The account an update applies to: from `account_map`, which holds
+/// every account written during the run, else from `HashedAccounts` for
+/// one committed before it. An account deleted in the pending block is
+/// not read back, since its committed entry is only wiped at the next
+/// commit. A balance delta treats a missing account as empty, as in the
+/// EVM; a nonce bump refuses it.
+fn current_account(
+    db: &DatabaseEnv,
+    pending: &PendingWrites,
+    account_map: &HashMap<Address, Account>,
+    address: Address,
+) -> Option<Account> {
+    let hashed_address = keccak256(address);
+    match account_map.get(&address) {
+        Some(account) => Some(*account),
+        None if pending.deleted.contains_key(&hashed_address) => None,
+        None => db
+            .tx()
+            .and_then(|tx| tx.get::<tables::HashedAccounts>(hashed_address))
+            .unwrap_or_else(|e| fatal(&format!("read HashedAccounts: {e}"))),
+    }
+}
+
+/// The value of `slot` of `address` as the workload has written it: the
+/// last write staged since the account's storage was last wiped, else
+/// zero if it was, else the value in the tables.
+fn current_slot(db: &DatabaseEnv, pending: &PendingWrites, address: Address, slot: B256) -> U256 {
+    let hashed_address = keccak256(address);
+    let hashed_slot = keccak256(slot);
+    let wiped_at = [&pending.deleted, &pending.storage_cleared]
+        .into_iter()
+        .filter_map(|wiped| wiped.get(&hashed_address))
+        .map(|deletion| deletion.staged[1])
+        .max();
+    let staged = pending.storage[wiped_at.unwrap_or(0)..]
+        .iter()
+        .rev()
+        .find(|(staged, entry)| *staged == hashed_address && entry.key == hashed_slot);
+    match (staged, wiped_at) {
+        (Some((_, entry)), _) => entry.value,
+        (None, Some(_)) => U256::ZERO,
+        (None, None) => db
+            .tx()
+            .and_then(|tx| {
+                tx.cursor_dup_read::<tables::HashedStorages>()?
+                    .seek_by_key_subkey(hashed_address, hashed_slot)
+            })
+            .unwrap_or_else(|e| fatal(&format!("read HashedStorages: {e}")))
+            .filter(|entry| entry.key == hashed_slot)
+            .map_or(U256::ZERO, |entry| entry.value),
+    }
+}
+
+/// Ends the run on a failed assertion, unless `--assertions warn` only
+/// has it printed.
+fn assertion_outcome(cli: &Cli, checked: Result<(), String>) {
+    match checked {
+        Ok(()) => {}
+        Err(e) if cli.assertions == AssertionMode::Warn => {
+            eprintln!("reth-harness: assertion failed: {e}");
+        }
+        Err(e) => fatal(&format!("assertion failed: {e}")),
+    }
+}
+
+/// The balance after an `update_balance` of `delta`. Overflow is fatal;
+/// underflow too unless `--clamp-balance-underflow` is set.
+fn apply_delta(
+    cli: &Cli,
+    address: Address,
+    balance: U256,
+    delta: U256,
+    sign: Sign,
+    line: u64,
+) -> U256 {
+    match sign {
+        Sign::Add => balance.checked_add(delta).unwrap_or_else(|| {
+            fatal(&format!(
+                "line {line}: update_balance: balance of {address} overflows"
+            ))
+        }),
+        Sign::Sub => match balance.checked_sub(delta) {
+            Some(balance) => balance,
+            None if cli.clamp_balance_underflow => U256::ZERO,
+            None => fatal(&format!(
+                "line {line}: update_balance: {address} holds {balance} and cannot pay {delta} \
+                 (--clamp-balance-underflow sets it to zero instead)"
+            )),
+        },
+    }
+}
+
+/// Reports whether the hashed account exists in the committed state.
+fn read_committed_account(db: &DatabaseEnv, hashed_address: B256) -> Result<bool, DatabaseError> {
+    Ok(db
+        .tx()?
+        .get::<tables::HashedAccounts>(hashed_address)?
+        .is_some())
+}
+
+/// Reports whether the hashed slot is stored for the hashed account.
+fn read_committed_slot(
+    db: &DatabaseEnv,
+    hashed_address: B256,
+    hashed_slot: B256,
+) -> Result<bool, DatabaseError> {
+    let tx = db.tx()?;
+    let entry = tx
+        .cursor_dup_read::<tables::HashedStorages>()?
+        .seek_by_key_subkey(hashed_address, hashed_slot)?;
+    Ok(entry.is_some_and(|entry| entry.key == hashed_slot))
+}
+
+/// Generates the proof of `address` and `slots` from the committed trie
+/// tables, the way `eth_getProof` does.
+fn committed_proof(
+    db: &DatabaseEnv,
+    address: Address,
+    slots: &[B256],
+) -> Result<proofs::Proof, String> {
+    let tx = db.tx().map_err(|e| format!("begin read tx: {e}"))?;
+    let proof = Proof::from_tx(&tx)
+        .account_proof(address, slots)
+        .map_err(|e| format!("account proof: {e}"))?;
+    let bytes: usize = proof
+        .proof
+        .iter()
+        .chain(
+            proof
+                .storage_proofs
+                .iter()
+                .flat_map(|storage| &storage.proof),
+        )
+        .map(Bytes::len)
+        .sum();
+    Ok(proofs::Proof {
+        bytes: bytes as u64,
+        found: proof.info.is_some(),
+    })
+}
+
+/// Answers `--stay-alive` and `--query-db` queries from the rest of stdin
+/// against the committed database, reopened read-only.
+fn serve_queries(db_path: &Path) {
+    let db = open_db_read_only(db_path, DatabaseArguments::new(ClientVersion::default()))
+        .unwrap_or_else(|e| fatal(&format!("open mdbx read-only: {e}")));
+    query::serve(io::stdin().lock(), &mut io::stdout(), |query| {
+        answer_query(&db, query)
+    })
+    .unwrap_or_else(|e| fatal(&e));
+}
+
+/// Answers one query from the hashed tables. Addresses and slots are
+/// looked up by their keccak hashes, and `dump_state` lists them hashed,
+/// since the preimages are only stored with `--full-tables`.
+fn answer_query(db: &DatabaseEnv, query: &Query) -> Result<Value, String> {
+    let tx = db.tx().map_err(|e| format!("begin read tx: {e}"))?;
+    match query {
+        Query::ReadAccount { address } => {
+            let hashed_address = keccak256(query_arg::<Address>(address)?);
+            let account = tx
+                .get::<tables::HashedAccounts>(hashed_address)
+                .map_err(|e| format!("read HashedAccounts: {e}"))?;
+            Ok(account.as_ref().map_or(Value::Null, account_json))
+        }
+        Query::ReadStorage { address, slot } => {
+            let hashed_address = keccak256(query_arg::<Address>(address)?);
+            let hashed_slot = keccak256(query_arg::<B256>(slot)?);
+            let mut cursor = tx
+                .cursor_dup_read::<tables::HashedStorages>()
+                .map_err(|e| format!("open HashedStorages cursor: {e}"))?;
+            let value = cursor
+                .seek_by_key_subkey(hashed_address, hashed_slot)
+                .map_err(|e| format!("read HashedStorages: {e}"))?
+                .filter(|entry| entry.key == hashed_slot)
+                .map_or(U256::ZERO, |entry| entry.value);
+            Ok(Value::String(hexfmt::quantity(value)))
+        }
+        Query::GetProof { address, slots } => {
+            let address = query_arg::<Address>(address)?;
+            let slots = slots
+                .iter()
+                .map(|slot| query_arg::<B256>(slot))
+                .collect::<Result<Vec<_>, _>>()?;
+            let proof = Proof::from_tx(&tx)
+                .account_proof(address, &slots)
+                .map_err(|e| format!("account proof: {e}"))?;
+            let nodes =
+                |nodes: &[Bytes]| -> Vec<String> { nodes.iter().map(hexfmt::fixed).collect() };
+            Ok(serde_json::json!({
+                "address": hexfmt::fixed(address),
+                "account": proof.info.as_ref().map(account_json),
+                "storage_root": hexfmt::fixed(proof.storage_root),
+                "account_proof": nodes(&proof.proof),
+                "storage_proofs": proof.storage_proofs.iter().map(|storage| serde_json::json!({
+                    "slot": hexfmt::fixed(storage.key),
+                    "value": hexfmt::quantity(storage.value),
+                    "proof": nodes(&storage.proof),
+                })).collect::<Vec<_>>(),
+            }))
+        }
+        Query::DumpState => {
+            let mut accounts = tx
+                .cursor_read::<tables::HashedAccounts>()
+                .map_err(|e| format!("open HashedAccounts cursor: {e}"))?;
+            let mut storages = tx
+                .cursor_dup_read::<tables::HashedStorages>()
+                .map_err(|e| format!("open HashedStorages cursor: {e}"))?;
+            let mut dump = Vec::new();
+            for entry in accounts
+                .walk(None)
+                .map_err(|e| format!("walk HashedAccounts: {e}"))?
+            {
+                let (hashed_address, account) =
+                    entry.map_err(|e| format!("read HashedAccounts: {e}"))?;
+                let storage = storages
+                    .walk_dup(Some(hashed_address), None)
+                    .map_err(|e| format!("walk HashedStorages: {e}"))?
+                    .map(|entry| {
+                        entry.map(|(_, slot)| {
+                            (
+                                hexfmt::fixed(slot.key),
+                                Value::String(hexfmt::quantity(slot.value)),
+                            )
+                        })
+                    })
+                    .collect::<Result<serde_json::Map<_, _>, _>>()
+                    .map_err(|e| format!("read HashedStorages: {e}"))?;
+                let mut account = account_json(&account);
+                account["hashed_address"] = Value::String(hexfmt::fixed(hashed_address));
+                account["storage"] = Value::Object(storage);
+                dump.push(account);
+            }
+            Ok(Value::Array(dump))
+        }
+    }
+}
+
+fn account_json(account: &Account) -> Value {
+    serde_json::json!({
+        "nonce": account.nonce,
+        "balance": hexfmt::quantity(account.balance),
+        "code_hash": hexfmt::fixed(account.get_bytecode_hash()),
+    })
+}
+
+/// Parses a query field, failing only that query when it is malformed.
+fn query_arg<T: std::str::FromStr>(s: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    s.parse().map_err(|e| format!("parse {s:?}: {e}"))
 }
 
 fn parse_address(s: &str) -> Address {
@@ -214,17 +2467,23 @@ fn parse_address(s: &str) -> Address {
         .unwrap_or_else(|e| fatal(&format!("parse address {s:?}: {e}")))
 }
 
-fn parse_b256(s: &str) -> B256 {
+/// Parses a storage slot: 32 bytes of `0x`-prefixed hex, or a decimal
+/// slot index.
+fn parse_b256(s: &str, line: u64) -> B256 {
+    if !s.starts_with("0x") {
+        return quantity::parse(s)
+            .map(B256::from)
+            .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")));
+    }
     s.parse()
-        .unwrap_or_else(|e| fatal(&format!("parse B256 {s:?}: {e}")))
+        .unwrap_or_else(|e| fatal(&format!("line {line}: parse B256 {s:?}: {e}")))
 }
 
-fn parse_u256(s: &str) -> U256 {
-    if s.is_empty() {
-        return U256::ZERO;
-    }
-    let stripped = s.strip_prefix("0x").unwrap_or(s);
-    U256::from_be_slice(&hex_decode(stripped))
+/// Parses a balance or value, `0x`-prefixed hex or decimal.
+fn parse_u256(s: &str, line: u64) -> U256 {
+    quantity::parse(s)
+        .map(U256::from_be_bytes)
+        .unwrap_or_else(|e| fatal(&format!("line {line}: {e}")))
 }
 
 fn parse_hex(s: &str) -> Vec<u8> {
@@ -245,21 +2504,253 @@ fn hex_decode(s: &str) -> Vec<u8> {
         .collect()
 }
 
-fn peak_memory_bytes() -> u64 {
-    std::fs::read_to_string("/proc/self/status")
-        .ok()
-        .and_then(|status| {
-            status.lines().find_map(|line| {
-                line.strip_prefix("VmHWM:").map(|v| {
-                    let kb: u64 = v.trim().trim_end_matches(" kB").trim().parse().unwrap_or(0);
-                    kb * 1024
-                })
-            })
-        })
-        .unwrap_or(0)
-}
-
 fn fatal(msg: &str) -> ! {
     eprintln!("reth-harness: {msg}");
     std::process::exit(1);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use statoor_common::fixtures::Fixture;
+
+    use super::*;
+
+    /// A database directory of its own under the temp dir, removed when
+    /// dropped, so a failing test leaves nothing behind.
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new() -> Self {
+            static NEXT: AtomicU64 = AtomicU64::new(0);
+            let n = NEXT.fetch_add(1, Ordering::Relaxed);
+            TempDb(std::env::temp_dir().join(format!("statoor-reth-{}-{n}", std::process::id())))
+        }
+    }
+
+    impl std::ops::Deref for TempDb {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// The `Cli` of a run on `dir` with `flags`.
+    fn cli_on(dir: &Path, flags: &[&str]) -> Cli {
+        let db = dir.to_string_lossy();
+        Cli::parse_from(["reth-harness", "--db", &*db].iter().chain(flags))
+    }
+
+    /// Runs `workload` on a fresh database with `flags`.
+    fn run_fixture(flags: &[&str], workload: &str) -> BenchResult {
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, flags);
+        run(
+            &cli,
+            &dir,
+            workload.as_bytes(),
+            &Progress::default(),
+            &mut |_| {},
+        )
+    }
+
+    /// The self-test fixture called `name`.
+    fn fixture(name: &str) -> &'static Fixture {
+        FIXTURES
+            .iter()
+            .chain(BLOCK_FIXTURES)
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("no fixture {name}"))
+    }
+    /// The address of the `n`th account of a hand-written workload.
+    fn numbered(n: u8) -> String {
+        format!("0x10000000000000000000000000000000000000{n:02x}")
+    }
+
+    /// A `create_account` line for [`numbered`] account `n`.
+    fn create_numbered(n: u8, balance: &str, nonce: u8) -> String {
+        format!(
+            r#"{{"op":"create_account","address":"{}","balance":"{balance}","nonce":{nonce}}}"#,
+            numbered(n)
+        )
+    }
+
+    #[test]
+    fn every_mode_commits_before_the_root() {
+        let modes: [&[&str]; 5] = [
+            &[],
+            &["--full-tables"],
+            &["--root-algorithm", "both"],
+            &["--inject-commit-latency-ms", "1"],
+            &["--max-ops", "2"],
+        ];
+        for flags in modes {
+            for fixture in FIXTURES.iter().chain(BLOCK_FIXTURES) {
+                // A leading compute_root adds a checkpoint over the empty
+                // state without changing the final root.
+                let checkpointed = format!("{{\"op\":\"compute_root\"}}\n{}", fixture.workload);
+                for workload in [fixture.workload, checkpointed.as_str()] {
+                    let dir = TempDb::new();
+                    let cli = cli_on(&dir, flags);
+                    let mut pending_at_checkpoints = Vec::new();
+                    let result = run(
+                        &cli,
+                        &dir,
+                        workload.as_bytes(),
+                        &Progress::default(),
+                        &mut |checkpoint: &BenchResult| {
+                            pending_at_checkpoints.push(checkpoint.pending_at_root)
+                        },
+                    );
+                    assert_eq!(result.pending_at_root, 0, "{} {flags:?}", fixture.name);
+                    assert!(pending_at_checkpoints.iter().all(|&pending| pending == 0));
+                    if cli.max_ops.is_none() {
+                        assert_eq!(result.state_root, fixture.expected_root);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_quantities_match_their_hex() {
+        let fixture = fixture("account_with_storage");
+        let slot_one = format!(r#""slot":"0x{:064x}""#, 1);
+        let workload = fixture
+            .workload
+            .replace(r#""balance":"0x0""#, r#""balance":"0""#)
+            .replace(&slot_one, r#""slot":"1""#)
+            .replace(r#""value":"0x2a""#, r#""value":"42""#);
+        assert_ne!(workload, fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+    }
+
+    #[test]
+    fn balance_deltas_reach_the_absolute_balances() {
+        let fixture = fixture("multiple_accounts");
+        let update = |n: u8, value: &str, sign: &str| {
+            format!(
+                r#"{{"op":"update_balance","address":"{}","value":"{value}","sign":"{sign}"}}"#,
+                numbered(n)
+            )
+        };
+        let transfer = format!(
+            r#"{{"op":"transfer","from":"{}","to":"{}","value":"0x2"}}"#,
+            numbered(1),
+            numbered(2)
+        );
+        // Account 1 pays 0x2 to account 2 and keeps 0x1; account 3 is
+        // credited in the block after its creation.
+        let workload = |debit: &[String]| {
+            let mut lines = vec![
+                create_numbered(1, "0x10", 0),
+                create_numbered(2, "0x0", 0),
+                create_numbered(3, "0x0", 7),
+                transfer.clone(),
+            ];
+            lines.extend_from_slice(debit);
+            lines.extend([
+                r#"{"op":"commit_block"}"#.to_string(),
+                update(3, "0x3", "add"),
+                create_numbered(4, "0x3635c9adc5dea00000", 255),
+                format!(
+                    r#"{{"op":"compute_root","expected_root":"{}"}}"#,
+                    fixture.expected_root
+                ),
+            ]);
+            lines.join("\n") + "\n"
+        };
+        let exact = workload(&[update(1, "0xd", "sub")]);
+        let clamped = workload(&[update(1, "0xffff", "sub"), update(1, "0x1", "add")]);
+        for (name, flags, workload) in [
+            ("exact", &[][..], exact),
+            ("clamped", &["--clamp-balance-underflow"][..], clamped),
+        ] {
+            let result = run_fixture(flags, &workload);
+            assert_eq!(result.root_match, Some(true), "{name}");
+            assert_eq!(result.counters.transfers, 1, "{name}");
+        }
+    }
+
+    #[test]
+    fn nonce_bumps_reach_the_absolute_nonces() {
+        let fixture = fixture("multiple_accounts");
+        let bump = |n: u8, count: u8| {
+            format!(
+                r#"{{"op":"increment_nonce","address":"{}","count":{count}}}"#,
+                numbered(n)
+            )
+        };
+        // Account 3 is bumped in the block after its creation, so its
+        // info comes from the state trie.
+        let workload = [
+            create_numbered(1, "0x01", 0),
+            create_numbered(2, "0x02", 0),
+            create_numbered(3, "0x03", 4),
+            r#"{"op":"commit_block"}"#.to_string(),
+            bump(3, 2),
+            format!(r#"{{"op":"increment_nonce","address":"{}"}}"#, numbered(3)),
+            create_numbered(4, "0x3635c9adc5dea00000", 250),
+            bump(4, 5),
+            format!(
+                r#"{{"op":"compute_root","expected_root":"{}"}}"#,
+                fixture.expected_root
+            ),
+        ]
+        .join("\n");
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.root_match, Some(true));
+        assert_eq!(result.counters.nonce_increments, 8);
+    }
+
+    #[test]
+    fn sleeps_are_reported_apart_from_elapsed() {
+        let fixture = fixture("single_account");
+        let workload = format!("{{\"op\":\"sleep\",\"ms\":50}}\n{}", fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert!(result.sleep_ms.is_some_and(|ms| ms >= 50));
+        assert_eq!(result.counters.accounts_created, 1);
+    }
+
+    #[test]
+    fn roots_right_after_a_block_add_no_block() {
+        let fixture = fixture("two_blocks");
+        let workload = fixture.workload.replacen(
+            "{\"op\":\"commit_block\"}\n",
+            "{\"op\":\"commit_block\"}\n{\"op\":\"compute_root\"}\n",
+            1,
+        );
+        assert_ne!(workload, fixture.workload);
+        let result = run_fixture(&[], &workload);
+        assert_eq!(result.state_root, fixture.expected_root);
+        let ops: Vec<u64> = result.blocks.iter().map(|block| block.ops).collect();
+        assert_eq!(ops, [3, 3]);
+    }
+
+    #[test]
+    fn recomputed_roots_match_the_run() {
+        let fixture = fixture("ef_dogs_storage");
+        let dir = TempDb::new();
+        let cli = cli_on(&dir, &[]);
+        let result = run(
+            &cli,
+            &dir,
+            fixture.workload.as_bytes(),
+            &Progress::default(),
+            &mut |_| {},
+        );
+        let report = recompute_root(&cli, &dir, 3, &result.state_root);
+        assert_eq!(result.state_root, fixture.expected_root);
+        assert_eq!(report.recompute_times_ms.len(), 4);
+    }
+}
@@ -0,0 +1,94 @@
+package report
+
+import (
+	"fmt"
+	"io"
+
+	"github.com/weiihann/statoor/harness"
+)
+
+// NormalizedMetric is a result metric divided by a workload counter, so
+// runs over differently sized workloads can be compared.
+type NormalizedMetric struct {
+	// Name identifies the metric, e.g. "trie_us_per_slot".
+	Name string
+	// Header is the column title in the markdown report.
+	Header string
+	// Unit is "us" for times and "B" for sizes.
+	Unit  string
+	value func(harness.Result) (numerator, denominator float64)
+}
+
+// NormalizedMetrics lists the metrics Generate reports per client.
+var NormalizedMetrics = []NormalizedMetric{
+	{"elapsed_us_per_account", "Elapsed/Account", "us", func(r harness.Result) (float64, float64) {
+		return usFromMs(r.ElapsedMs), float64(r.AccountsCreated)
+	}},
+	{"trie_us_per_account", "Trie/Account", "us", func(r harness.Result) (float64, float64) {
+		return usFromMs(r.TrieTimeMs), float64(r.AccountsCreated)
+	}},
+	{"trie_us_per_slot", "Trie/Slot", "us", func(r harness.Result) (float64, float64) {
+		return usFromMs(r.TrieTimeMs), float64(r.StorageSlots)
+	}},
+	{"db_write_us_per_slot", "DB Write/Slot", "us", func(r harness.Result) (float64, float64) {
+		return usFromMs(r.DBWriteTimeMs), float64(r.StorageSlots)
+	}},
+	{"db_bytes_per_slot", "DB Size/Slot", "B", func(r harness.Result) (float64, float64) {
+		return float64(r.ComparableDBSize()), float64(r.StorageSlots)
+	}},
+}
+
+// Value computes the metric for r. It reports false when the workload
+// has none of the counted items (an account-only workload has no
+// per-slot metrics), since there is nothing to normalize by.
+func (m NormalizedMetric) Value(r harness.Result) (float64, bool) {
+	numerator, denominator := m.value(r)
+	if denominator == 0 {
+		return 0, false
+	}
+
+	return numerator / denominator, true
+}
+
+// writeNormalized prints the normalized metrics per client, with "-"
+// where the workload has nothing to normalize by.
+func writeNormalized(w io.Writer, results []harness.Result) {
+	fmt.Fprint(w, "| Client |")
+
+	for _, m := range NormalizedMetrics {
+		fmt.Fprintf(w, " %s |", m.Header)
+	}
+
+	fmt.Fprint(w, "\n|--------|")
+
+	for range NormalizedMetrics {
+		fmt.Fprint(w, "------|")
+	}
+
+	fmt.Fprintln(w)
+
+	for _, r := range results {
+		fmt.Fprintf(w, "| %s |", r.Client)
+
+		for _, m := range NormalizedMetrics {
+			fmt.Fprintf(w, " %s |", formatNormalized(m, r))
+		}
+
+		fmt.Fprintln(w)
+	}
+
+	fmt.Fprintln(w)
+}
+
+func formatNormalized(m NormalizedMetric, r harness.Result) string {
+	v, ok := m.Value(r)
+	if !ok {
+		return "-"
+	}
+
+	return fmt.Sprintf("%.2f %s", v, m.Unit)
+}
+
+func usFromMs(ms int64) float64 {
+	return float64(ms) * 1000
+}
@@ -6,17 +6,68 @@ import (
 	"fmt"
 	"io"
 	"math"
+	"slices"
+	"strconv"
 	"strings"
 
 	"github.com/weiihann/statoor/harness"
 )
 
+// Options adjust the checks Generate makes before comparing results.
+type Options struct {
+	// AllowModelMismatch compares results whose measurement models
+	// differ, flagging the differing keys instead of refusing.
+	AllowModelMismatch bool
+	// AllowSimulated compares simulated results with real ones, marking
+	// the simulated rows instead of refusing.
+	AllowSimulated bool
+}
+
 // Generate writes a markdown comparison table for the given results.
 func Generate(w io.Writer, results []harness.Result) error {
+	return GenerateWith(w, results, Options{})
+}
+
+// GenerateWith is Generate with the checks adjusted by opts.
+func GenerateWith(w io.Writer, results []harness.Result, opts Options) error {
 	if len(results) == 0 {
 		return fmt.Errorf("no results to report")
 	}
 
+	results, sorted := splitOrdering(results)
+
+	mixedSimulated := !sameSimulated(results)
+	if mixedSimulated && !opts.AllowSimulated {
+		return fmt.Errorf(
+			"refusing to compare simulated and non-simulated results (%s); pass --allow-simulated to compare them anyway",
+			strings.Join(simulatedClients(results), ", "),
+		)
+	}
+
+	if err := checkTimeBoxed(results); err != nil {
+		return err
+	}
+
+	if err := checkPreloaded(results); err != nil {
+		return err
+	}
+
+	if err := checkFingerprints(results); err != nil {
+		return err
+	}
+
+	if err := checkOrderings(results, sorted); err != nil {
+		return err
+	}
+
+	mismatches := modelMismatches(results)
+	if len(mismatches) > 0 && !opts.AllowModelMismatch {
+		return fmt.Errorf(
+			"refusing to compare results measured differently (%s); pass --allow-model-mismatch to compare them anyway",
+			strings.Join(mismatches, "; "),
+		)
+	}
+
 	rootMatch := checkStateRoots(results)
 	fastestMs := findFastest(results)
 
@@ -24,10 +75,89 @@ func Generate(w io.Writer, results []harness.Result) error {
 	fmt.Fprintln(w, "## Benchmark Results")
 	fmt.Fprintln(w)
 
+	switch {
+	case mixedSimulated:
+		fmt.Fprintf(w, "**SIMULATED**: timings of %s include injected commit latency, the others' do not\n",
+			strings.Join(simulatedClients(results), ", "))
+		fmt.Fprintln(w)
+	case results[0].Simulated:
+		fmt.Fprintln(w, "**SIMULATED**: timings include injected commit latency")
+		fmt.Fprintln(w)
+	}
+
+	if results[0].TimeBoxed {
+		writeTimeBox(w, results)
+	}
+
+	if results[0].Preloaded {
+		fmt.Fprintln(w, "**PRELOADED**: workloads were read into memory before timing")
+		fmt.Fprintln(w)
+	}
+
+	for _, mismatch := range mismatches {
+		fmt.Fprintf(w, "**MODEL MISMATCH**: %s\n", mismatch)
+		fmt.Fprintln(w)
+	}
+
+	for _, r := range results {
+		if len(r.Attempts) > 1 {
+			fmt.Fprintf(w, "**RETRIED**: %s succeeded on attempt %d after %s\n",
+				r.Client, len(r.Attempts), failureKinds(r.Attempts))
+			fmt.Fprintln(w)
+		}
+
+		if r.InputConsumptionOpsPerSec != nil && r.ApplyOpsPerSec != nil &&
+			*r.InputConsumptionOpsPerSec < *r.ApplyOpsPerSec {
+			fmt.Fprintf(w, "**INPUT-BOUND**: %s received %d ops/s but could apply %d ops/s; "+
+				"pre-materialize the workload or pass --preload-workload\n",
+				r.Client, *r.InputConsumptionOpsPerSec, *r.ApplyOpsPerSec)
+			fmt.Fprintln(w)
+		}
+
+		if r.RootMatch != nil && !*r.RootMatch {
+			fmt.Fprintf(w, "**EXPECTED ROOT MISMATCH**: %s root %s differs from the workload's expected_root\n",
+				r.Client, r.StateRoot)
+			fmt.Fprintln(w)
+		}
+
+		if r.TrailerMatch != nil && !*r.TrailerMatch {
+			fmt.Fprintf(w, "**TRAILER MISMATCH**: %s read other lines than the workload's trailer declares: %s\n",
+				r.Client, trailerDeltas(r))
+			fmt.Fprintln(w)
+		}
+
+		if r.CanariesFailed > 0 {
+			fmt.Fprintf(w, "**CANARY MISMATCH**: %s encoded %d of %d canary values to another leaf hash (lines %s)\n",
+				r.Client, r.CanariesFailed, r.CanariesChecked, canaryLines(r.CanaryFailedLines))
+			fmt.Fprintln(w)
+		}
+
+		if r.AssertionFailures > 0 {
+			fmt.Fprintf(w, "**ASSERTION FAILED**: %s read %d of %d asserted values differently than the workload expects\n",
+				r.Client, r.AssertionFailures, r.AssertionsChecked)
+			fmt.Fprintln(w)
+		}
+
+		if r.TruncatedAtOp != nil {
+			fmt.Fprintf(w, "**TRUNCATED**: %s stopped after %d operations\n",
+				r.Client, *r.TruncatedAtOp)
+			fmt.Fprintln(w)
+		}
+
+		if r.SparseDB() {
+			fmt.Fprintf(w, "**SPARSE FILES**: %s's database files are %s long but occupy %s on disk\n",
+				r.Client, formatBytes(r.DBApparentSizeBytes), formatBytes(r.DBAllocatedSizeBytes))
+			fmt.Fprintln(w)
+		}
+	}
+
 	// State root check.
-	if rootMatch {
+	switch {
+	case !samePrefix(results):
+		fmt.Fprintln(w, "State roots: not compared, the clients completed different prefixes")
+	case rootMatch:
 		fmt.Fprintln(w, "State roots: **all match**")
-	} else {
+	default:
 		fmt.Fprintln(w, "State roots: **MISMATCH**")
 
 		for _, r := range results {
@@ -43,25 +173,50 @@ func Generate(w io.Writer, results []harness.Result) error {
 	fmt.Fprintln(w, "|--------|---------|-----------|----------"+
 		"|----------|---------|---------|")
 
+	mostOps := mostOpsCompleted(results)
+
 	for _, r := range results {
 		speedup := 1.0
-		if fastestMs > 0 && r.ElapsedMs > 0 {
+
+		switch {
+		case r.TimeBoxed:
+			if r.OpsCompleted != nil && *r.OpsCompleted > 0 {
+				speedup = float64(mostOps) / float64(*r.OpsCompleted)
+			}
+		case fastestMs > 0 && r.ElapsedMs > 0:
 			speedup = float64(r.ElapsedMs) / float64(fastestMs)
 		}
 
+		client := r.Client
+		if mixedSimulated && r.Simulated {
+			client += " (simulated)"
+		}
+
 		fmt.Fprintf(w, "| %s | %s | %s | %s | %s | %s | %.2fx |\n",
-			r.Client,
+			client,
 			formatMs(r.ElapsedMs),
 			formatMs(r.TrieTimeMs),
 			formatMs(r.DBWriteTimeMs),
 			formatBytes(r.PeakMemoryBytes),
-			formatBytes(r.DBSizeBytes),
+			formatBytes(r.ComparableDBSize()),
 			speedup,
 		)
 	}
 
 	fmt.Fprintln(w)
 
+	writeNormalized(w, results)
+	writeDurability(w, results)
+	writeReads(w, results)
+	writeProofs(w, results)
+	writeGasThroughput(w, results)
+	writeRollingDrift(w, results)
+	writeFillCurves(w, results)
+	writeOrdering(w, results, sorted)
+	writeCodeReads(w, results)
+	writeRecomputes(w, results)
+	writeEnvironment(w, results)
+
 	// Detail rows.
 	fmt.Fprintln(w, "| Client | Accounts | Contracts | Storage Slots |")
 	fmt.Fprintln(w, "|--------|----------|-----------|---------------|")
@@ -86,6 +241,743 @@ func GenerateJSON(w io.Writer, results []harness.Result) error {
 	return enc.Encode(results)
 }
 
+// failureKinds lists the failure kinds of the failed attempts, in order.
+func failureKinds(attempts []harness.Attempt) string {
+	var kinds []string
+
+	for _, a := range attempts {
+		if a.Kind != "" {
+			kinds = append(kinds, string(a.Kind))
+		}
+	}
+
+	return strings.Join(kinds, ", ")
+}
+
+// trailerDeltas lists how many more lines of each operation r read than
+// its trailer declares, such as "set_storage -3, lines -3".
+func trailerDeltas(r harness.Result) string {
+	ops := make([]string, 0, len(r.TrailerDeltas))
+	for op := range r.TrailerDeltas {
+		ops = append(ops, op)
+	}
+
+	slices.Sort(ops)
+
+	deltas := make([]string, 0, len(ops)+1)
+	for _, op := range ops {
+		deltas = append(deltas, fmt.Sprintf("%s %+d", op, r.TrailerDeltas[op]))
+	}
+
+	if r.TrailerLinesDelta != nil {
+		deltas = append(deltas, fmt.Sprintf("lines %+d", *r.TrailerLinesDelta))
+	}
+
+	return strings.Join(deltas, ", ")
+}
+
+// writeDurability lists the durability level behind each client's DB
+// write time, since commits with different guarantees are not comparable.
+// canaryLines joins the failed canary lines for the report.
+func canaryLines(lines []int64) string {
+	parts := make([]string, len(lines))
+	for i, line := range lines {
+		parts[i] = strconv.FormatInt(line, 10)
+	}
+
+	return strings.Join(parts, ", ")
+}
+
+func writeDurability(w io.Writer, results []harness.Result) {
+	var lines []string
+
+	for _, r := range results {
+		if r.Durability == "" {
+			continue
+		}
+
+		line := fmt.Sprintf("  - %s: %s", r.Client, r.Durability)
+		if r.PostCommitFlushMs != nil {
+			line += fmt.Sprintf(" (post-commit flush %s)", formatMs(*r.PostCommitFlushMs))
+		}
+
+		if p := r.DurabilityProbe; p != nil {
+			line += fmt.Sprintf(", %s probe %s", p.Mode, p.Status)
+			if p.Truncated {
+				line += " (truncated)"
+			}
+
+			if len(p.Details) > 0 {
+				line += ": " + strings.Join(p.Details, "; ")
+			}
+		}
+
+		lines = append(lines, line)
+	}
+
+	if len(lines) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Durability:")
+
+	for _, line := range lines {
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// writeReads lists the read operations of each client's workload, which
+// the elapsed and write times above do not cover.
+func writeReads(w io.Writer, results []harness.Result) {
+	var lines []string
+
+	for _, r := range results {
+		if r.Reads == 0 {
+			continue
+		}
+
+		lines = append(lines, fmt.Sprintf("  - %s: %d reads in %s, %d misses",
+			r.Client, r.Reads, formatMs(r.ReadTimeMs), r.ReadMisses))
+	}
+
+	if len(lines) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Reads:")
+
+	for _, line := range lines {
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// GasThroughput divides the approx_gas of r's blocks by the time the
+// client took to commit them, their trie and DB write time, giving a
+// gas-per-second figure comparable with a chain's block limits. Only
+// blocks with an estimate count. It reports false when there are none or
+// they took no measurable time.
+func GasThroughput(r harness.Result) (gasPerSec float64, blocks int, ok bool) {
+	var gas uint64
+
+	var ms int64
+
+	for _, b := range r.Blocks {
+		if b.ApproxGas == nil {
+			continue
+		}
+
+		gas += *b.ApproxGas
+		ms += b.TrieTimeMs + b.DBWriteTimeMs
+		blocks++
+	}
+
+	if blocks == 0 || ms == 0 {
+		return 0, blocks, false
+	}
+
+	return float64(gas) / (float64(ms) / 1000), blocks, true
+}
+
+// writeGasThroughput lists each client's GasThroughput in Mgas/s.
+func writeGasThroughput(w io.Writer, results []harness.Result) {
+	var lines []string
+
+	for _, r := range results {
+		gasPerSec, blocks, ok := GasThroughput(r)
+		if !ok {
+			continue
+		}
+
+		lines = append(lines, fmt.Sprintf("  - %s: %.2f Mgas/s over %d blocks",
+			r.Client, gasPerSec/1e6, blocks))
+	}
+
+	if len(lines) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Gas throughput (approx_gas per second of block commit time):")
+
+	for _, line := range lines {
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// writeRollingDrift charts, per client, the rolling median of block
+// commit time (trie plus DB write) across the run's windows, so a client
+// slowing down as the state grows shows as a rising line.
+func writeRollingDrift(w io.Writer, results []harness.Result) {
+	const bars = "▁▂▃▄▅▆▇█"
+
+	levels := []rune(bars)
+
+	var lines []string
+
+	for _, r := range results {
+		if len(r.RollingStats) < 2 {
+			continue
+		}
+
+		medians := make([]int64, len(r.RollingStats))
+		lo, hi := int64(-1), int64(0)
+
+		for i, s := range r.RollingStats {
+			medians[i] = s.TrieTimeMs.Median + s.DBWriteTimeMs.Median
+			if lo < 0 || medians[i] < lo {
+				lo = medians[i]
+			}
+
+			hi = max(hi, medians[i])
+		}
+
+		var chart strings.Builder
+
+		for _, m := range medians {
+			level := 0
+			if hi > lo {
+				level = int((m - lo) * int64(len(levels)-1) / (hi - lo))
+			}
+
+			chart.WriteRune(levels[level])
+		}
+
+		lines = append(lines, fmt.Sprintf("  - %s: %s %s → %s per block over %d windows of %d",
+			r.Client, chart.String(), formatMs(medians[0]), formatMs(medians[len(medians)-1]),
+			len(medians), r.RollingWindow))
+	}
+
+	if len(lines) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Block commit time drift (rolling median of trie + DB write):")
+
+	for _, line := range lines {
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// writeFillCurves charts the fill curve of each client, the trie time per
+// thousand accounts as the trie fills. All curves share one scale, so
+// the bars of one client can be read against another's.
+func writeFillCurves(w io.Writer, results []harness.Result) {
+	const bars = "▁▂▃▄▅▆▇█"
+
+	levels := []rune(bars)
+
+	costs := make([][]float64, len(results))
+	lo, hi := -1.0, 0.0
+
+	for i, r := range results {
+		for _, p := range r.FillCurve {
+			if p.Accounts == 0 {
+				continue
+			}
+
+			cost := float64(p.TrieMs) * 1000 / float64(p.Accounts)
+			costs[i] = append(costs[i], cost)
+
+			if lo < 0 || cost < lo {
+				lo = cost
+			}
+
+			hi = max(hi, cost)
+		}
+	}
+
+	var lines []string
+
+	for i, r := range results {
+		if len(costs[i]) < 2 {
+			continue
+		}
+
+		var chart strings.Builder
+
+		for _, cost := range costs[i] {
+			level := 0
+			if hi > lo {
+				level = int((cost - lo) * float64(len(levels)-1) / (hi - lo))
+			}
+
+			chart.WriteRune(levels[level])
+		}
+
+		first, last := costs[i][0], costs[i][len(costs[i])-1]
+		lines = append(lines, fmt.Sprintf("  - %s: %s %.1f → %.1f ms per 1k accounts over %d points",
+			r.Client, chart.String(), first, last, len(costs[i])))
+	}
+
+	if len(lines) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Trie fill curve (trie time per 1k accounts as the trie fills, one scale):")
+
+	for _, line := range lines {
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// splitOrdering separates the results of a --compare-ordering run: those
+// on the workload in generation order, reported as usual, from those on
+// it sorted by hashed key, compared against them. Results that are all
+// of one ordering are reported as they are.
+func splitOrdering(results []harness.Result) (base, sorted []harness.Result) {
+	for _, r := range results {
+		if r.Ordering == harness.OrderingHashedKey {
+			sorted = append(sorted, r)
+		} else {
+			base = append(base, r)
+		}
+	}
+
+	if len(base) == 0 {
+		return sorted, nil
+	}
+
+	return base, sorted
+}
+
+// unsortedRun returns the run in generation order of the client of s.
+func unsortedRun(base []harness.Result, s harness.Result) (harness.Result, bool) {
+	i := slices.IndexFunc(base, func(r harness.Result) bool { return r.Client == s.Client })
+	if i < 0 {
+		return harness.Result{}, false
+	}
+
+	return base[i], true
+}
+
+// checkOrderings refuses to compare a sorted run with an unsorted one
+// that applied another number of operations. The fingerprint follows the
+// order of the operations, so the two runs of one workload differ in it
+// and are paired by client instead.
+func checkOrderings(base, sorted []harness.Result) error {
+	for _, s := range sorted {
+		r, ok := unsortedRun(base, s)
+		if ok && r.FingerprintOps != s.FingerprintOps {
+			return fmt.Errorf(
+				"refusing to compare orderings of different workloads (%s applied %d ops unsorted, %d sorted)",
+				r.Client, r.FingerprintOps, s.FingerprintOps,
+			)
+		}
+	}
+
+	return nil
+}
+
+// writeOrdering compares each client's sorted run with its run in
+// generation order. Both build the same state, so their roots must match.
+func writeOrdering(w io.Writer, base, sorted []harness.Result) {
+	if len(sorted) == 0 {
+		return
+	}
+
+	var mismatches []string
+
+	fmt.Fprintln(w, "Ordering effect (workload sorted by hashed key vs generation order):")
+	fmt.Fprintln(w)
+	fmt.Fprintln(w, "| Client | Elapsed | Sorted | Change | Trie Time | Sorted | Change |")
+	fmt.Fprintln(w, "|--------|---------|--------|--------|-----------|--------|--------|")
+
+	for _, s := range sorted {
+		r, ok := unsortedRun(base, s)
+		if !ok {
+			continue
+		}
+
+		if r.StateRoot != s.StateRoot {
+			mismatches = append(mismatches, fmt.Sprintf(
+				"**ORDERING ROOT MISMATCH**: %s computed %s sorted but %s unsorted",
+				r.Client, s.StateRoot, r.StateRoot))
+		}
+
+		fmt.Fprintf(w, "| %s | %s | %s | %s | %s | %s | %s |\n",
+			r.Client,
+			formatMs(r.ElapsedMs), formatMs(s.ElapsedMs), percentChange(r.ElapsedMs, s.ElapsedMs),
+			formatMs(r.TrieTimeMs), formatMs(s.TrieTimeMs), percentChange(r.TrieTimeMs, s.TrieTimeMs),
+		)
+	}
+
+	fmt.Fprintln(w)
+
+	for _, line := range mismatches {
+		fmt.Fprintln(w, line)
+		fmt.Fprintln(w)
+	}
+}
+
+// percentChange formats the change from before to after, or "-" when
+// there was nothing before.
+func percentChange(before, after int64) string {
+	if before == 0 {
+		return "-"
+	}
+
+	return fmt.Sprintf("%+.1f%%", float64(after-before)*100/float64(before))
+}
+
+// writeProofs lists the proofs each client generated for the workload's
+// get_proof operations, timed apart like reads.
+func writeProofs(w io.Writer, results []harness.Result) {
+	var lines []string
+
+	for _, r := range results {
+		if r.ProofsGenerated == 0 {
+			continue
+		}
+
+		lines = append(lines, fmt.Sprintf("  - %s: %d proofs in %s, %s, %d exclusions",
+			r.Client, r.ProofsGenerated, formatMs(r.ProofTimeMs),
+			formatBytes(uint64(r.ProofBytesTotal)), r.ProofExclusions))
+	}
+
+	if len(lines) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Proofs:")
+
+	for _, line := range lines {
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// writeRecomputes tabulates the --recompute-root times of each client,
+// every one of them rather than an average, so a single slow
+// recomputation shows as such.
+func writeRecomputes(w io.Writer, results []harness.Result) {
+	var rows, notes []string
+
+	for _, r := range results {
+		if len(r.RecomputeTimesMs) == 0 {
+			continue
+		}
+
+		ratio := "-"
+		if r.RecomputeColdWarmRatio != nil {
+			ratio = fmt.Sprintf("%.2fx", *r.RecomputeColdWarmRatio)
+		}
+
+		times := make([]string, len(r.RecomputeTimesMs))
+		for i, ms := range r.RecomputeTimesMs {
+			times[i] = strconv.FormatInt(ms, 10)
+		}
+
+		rows = append(rows, fmt.Sprintf("| %s | %s | %s | %s |",
+			r.Client, formatMs(r.RecomputeTimesMs[0]), ratio, strings.Join(times, ", ")))
+
+		if r.RecomputePageCacheKept != "" {
+			notes = append(notes, fmt.Sprintf("  - %s: page cache kept, %s",
+				r.Client, r.RecomputePageCacheKept))
+		}
+	}
+
+	if len(rows) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "| Client | Cold Root | Cold/Warm | Recompute Times (ms) |")
+	fmt.Fprintln(w, "|--------|-----------|-----------|----------------------|")
+
+	for _, row := range rows {
+		fmt.Fprintln(w, row)
+	}
+
+	fmt.Fprintln(w)
+
+	if len(notes) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Root recomputation:")
+
+	for _, note := range notes {
+		fmt.Fprintln(w, note)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// writeCodeReads tabulates the --read-code passes of each client. A
+// missing cold pass is noted with its reason, so a warm-only number is
+// not mistaken for a cold one.
+func writeCodeReads(w io.Writer, results []harness.Result) {
+	var rows, notes []string
+
+	row := func(client, pass string, p harness.CodeReadPass) string {
+		return fmt.Sprintf("| %s | %s | %d | %d | %s | %s | %dµs | %dµs | %dµs |",
+			client, pass, p.Reads, p.Misses, formatBytes(uint64(p.BytesRead)),
+			formatMs(p.CodeReadTimeMs), p.P50Us, p.P90Us, p.P99Us)
+	}
+
+	for _, r := range results {
+		c := r.CodeRead
+		if c == nil {
+			continue
+		}
+
+		rows = append(rows, row(r.Client, "warm", c.Warm))
+		if c.Cold != nil {
+			rows = append(rows, row(r.Client, "cold", *c.Cold))
+		}
+
+		if c.CodesTruncated {
+			notes = append(notes, fmt.Sprintf("  - %s: sampled from the first %d codes, the listing ran out of time",
+				r.Client, c.Codes))
+		}
+
+		if c.Truncated {
+			notes = append(notes, fmt.Sprintf("  - %s: truncated, the read bench ran out of time", r.Client))
+		}
+
+		if c.ColdSkipped != "" {
+			notes = append(notes, fmt.Sprintf("  - %s: no cold pass, %s", r.Client, c.ColdSkipped))
+		}
+	}
+
+	if len(rows) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "| Client | Pass | Code Reads | Misses | Bytes | Total | p50 | p90 | p99 |")
+	fmt.Fprintln(w, "|--------|------|------------|--------|-------|-------|-----|-----|-----|")
+
+	for _, row := range rows {
+		fmt.Fprintln(w, row)
+	}
+
+	fmt.Fprintln(w)
+
+	if len(notes) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Code reads:")
+
+	for _, note := range notes {
+		fmt.Fprintln(w, note)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// writeEnvironment flags host differences that commonly explain timing
+// gaps: differing transparent huge page modes and swap growth.
+func writeEnvironment(w io.Writer, results []harness.Result) {
+	var notes []string
+
+	modes := make(map[string]bool)
+
+	for _, r := range results {
+		if r.Environment != nil && r.Environment.Start.THPEnabled != "" {
+			modes[r.Environment.Start.THPEnabled] = true
+		}
+	}
+
+	if len(modes) > 1 {
+		for _, r := range results {
+			if r.Environment != nil && r.Environment.Start.THPEnabled != "" {
+				notes = append(notes, fmt.Sprintf("  - %s: transparent huge pages %s",
+					r.Client, r.Environment.Start.THPEnabled))
+			}
+		}
+	}
+
+	for _, r := range results {
+		if growth := r.Environment.SwapGrowth(); growth > 0 {
+			notes = append(notes, fmt.Sprintf("  - %s: swap grew by %s",
+				r.Client, formatBytes(growth)))
+		}
+	}
+
+	if len(notes) == 0 {
+		return
+	}
+
+	fmt.Fprintln(w, "Environment differences:")
+
+	for _, note := range notes {
+		fmt.Fprintln(w, note)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// sameSimulated reports whether the results are all simulated or all
+// real. The timings of one kind are not comparable with the other's.
+func sameSimulated(results []harness.Result) bool {
+	for _, r := range results[1:] {
+		if r.Simulated != results[0].Simulated {
+			return false
+		}
+	}
+
+	return true
+}
+
+// simulatedClients lists the clients of the simulated results, once each.
+func simulatedClients(results []harness.Result) []string {
+	var clients []string
+
+	for _, r := range results {
+		if r.Simulated && !slices.Contains(clients, r.Client) {
+			clients = append(clients, r.Client)
+		}
+	}
+
+	return clients
+}
+
+// checkTimeBoxed refuses to compare time-boxed runs against complete
+// ones: one is measured by operations completed, the other by time.
+func checkTimeBoxed(results []harness.Result) error {
+	for _, r := range results[1:] {
+		if r.TimeBoxed != results[0].TimeBoxed {
+			return fmt.Errorf(
+				"refusing to compare time-boxed and complete results (%s vs %s)",
+				results[0].Client, r.Client,
+			)
+		}
+	}
+
+	return nil
+}
+
+// checkPreloaded refuses to compare preloaded runs against streaming
+// ones: only the streaming timings include reading the workload.
+func checkPreloaded(results []harness.Result) error {
+	for _, r := range results[1:] {
+		if r.Preloaded != results[0].Preloaded {
+			return fmt.Errorf(
+				"refusing to compare preloaded and streaming results (%s vs %s)",
+				results[0].Client, r.Client,
+			)
+		}
+	}
+
+	return nil
+}
+
+// modelMismatches compares the measurement model of every result with
+// the first one's, as "reth vs ethrex: phases, included.trie.overlaps".
+// Results without a model, from harnesses that do not report one, are
+// not compared.
+func modelMismatches(results []harness.Result) []string {
+	first := results[0].MeasurementModel
+	if first == nil {
+		return nil
+	}
+
+	var mismatches []string
+
+	for _, r := range results[1:] {
+		if r.MeasurementModel == nil {
+			continue
+		}
+
+		if keys := first.Differences(r.MeasurementModel); len(keys) > 0 {
+			mismatches = append(mismatches, fmt.Sprintf("%s vs %s: %s",
+				results[0].Client, r.Client, strings.Join(keys, ", ")))
+		}
+	}
+
+	return mismatches
+}
+
+// checkFingerprints refuses to compare results that applied different
+// workloads. Results without a fingerprint, from older harnesses, and
+// time-boxed runs that stopped at different points are not checked.
+func checkFingerprints(results []harness.Result) error {
+	if !samePrefix(results) {
+		return nil
+	}
+
+	for _, r := range results[1:] {
+		a, b := results[0].WorkloadFingerprint, r.WorkloadFingerprint
+		if a != "" && b != "" && a != b {
+			return fmt.Errorf(
+				"refusing to compare results from different workloads (%s fingerprint %s over %d ops vs %s fingerprint %s over %d ops)",
+				results[0].Client, a, results[0].FingerprintOps, r.Client, b, r.FingerprintOps,
+			)
+		}
+	}
+
+	return nil
+}
+
+// writeTimeBox lists how far each time-boxed client got. Their speedup
+// is relative to the client that completed the most operations.
+func writeTimeBox(w io.Writer, results []harness.Result) {
+	fmt.Fprintln(w, "**TIME-BOXED**: speedup compares operations completed, not elapsed time")
+
+	for _, r := range results {
+		var ops int64
+		if r.OpsCompleted != nil {
+			ops = *r.OpsCompleted
+		}
+
+		line := fmt.Sprintf("  - %s: %d operations", r.Client, ops)
+		if !r.DeadlineReached {
+			line += " (the workload ended first)"
+		}
+
+		fmt.Fprintln(w, line)
+	}
+
+	fmt.Fprintln(w)
+}
+
+// samePrefix reports whether the results' roots cover the same
+// operations. Time-boxed runs may stop at different points, and so may
+// runs given different --max-ops.
+func samePrefix(results []harness.Result) bool {
+	for _, r := range results[1:] {
+		a, b := results[0].TruncatedAtOp, r.TruncatedAtOp
+		if (a == nil) != (b == nil) || (a != nil && *a != *b) {
+			return false
+		}
+	}
+
+	if !results[0].TimeBoxed {
+		return true
+	}
+
+	for _, r := range results[1:] {
+		if r.OpsCompleted == nil || results[0].OpsCompleted == nil ||
+			*r.OpsCompleted != *results[0].OpsCompleted {
+			return false
+		}
+	}
+
+	return true
+}
+
+func mostOpsCompleted(results []harness.Result) int64 {
+	var most int64
+
+	for _, r := range results {
+		if r.OpsCompleted != nil && *r.OpsCompleted > most {
+			most = *r.OpsCompleted
+		}
+	}
+
+	return most
+}
+
 func checkStateRoots(results []harness.Result) bool {
 	if len(results) < 2 {
 		return true
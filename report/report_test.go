@@ -82,6 +82,737 @@ func TestGenerateMismatchedRoots(t *testing.T) {
 	}
 }
 
+func TestGenerateFlagsExpectedRootMismatch(t *testing.T) {
+	match, mismatch := true, false
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", RootMatch: &match},
+		{Client: "ethrex", StateRoot: "0xdef", RootMatch: &mismatch},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "**EXPECTED ROOT MISMATCH**: ethrex root 0xdef") ||
+		strings.Contains(output, "MISMATCH**: reth") {
+		t.Errorf("only ethrex should be flagged:\n%s", output)
+	}
+}
+
+func TestGenerateFlagsTrailerMismatch(t *testing.T) {
+	match, mismatch := true, false
+	lines := int64(-3)
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", TrailerMatch: &match},
+		{
+			Client:            "ethrex",
+			StateRoot:         "0xabc",
+			TrailerMatch:      &mismatch,
+			TrailerDeltas:     map[string]int64{"set_storage": -3, "commit_block": 1},
+			TrailerLinesDelta: &lines,
+		},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	want := "**TRAILER MISMATCH**: ethrex read other lines than the workload's trailer declares: " +
+		"commit_block +1, set_storage -3, lines -3"
+	if output := buf.String(); !strings.Contains(output, want) || strings.Contains(output, "MISMATCH**: reth") {
+		t.Errorf("only ethrex should be flagged:\n%s", output)
+	}
+}
+
+func TestGenerateFlagsCanaryMismatch(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", CanariesChecked: 4},
+		{Client: "ethrex", StateRoot: "0xabc", CanariesChecked: 4, CanariesFailed: 2, CanaryFailedLines: []int64{7, 19}},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	want := "**CANARY MISMATCH**: ethrex encoded 2 of 4 canary values to another leaf hash (lines 7, 19)"
+	if output := buf.String(); !strings.Contains(output, want) || strings.Contains(output, "MISMATCH**: reth") {
+		t.Errorf("only ethrex should be flagged:\n%s", output)
+	}
+}
+
+func TestGenerateFlagsAssertionFailures(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", AssertionsChecked: 3},
+		{Client: "ethrex", StateRoot: "0xabc", AssertionsChecked: 3, AssertionFailures: 1},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	want := "**ASSERTION FAILED**: ethrex read 1 of 3 asserted values differently than the workload expects"
+	if output := buf.String(); !strings.Contains(output, want) || strings.Contains(output, "FAILED**: reth") {
+		t.Errorf("only ethrex should be flagged:\n%s", output)
+	}
+}
+
+func TestGenerateComparesOrderings(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", ElapsedMs: 2000, TrieTimeMs: 1000,
+			WorkloadFingerprint: "aa", FingerprintOps: 10},
+		{Client: "reth", StateRoot: "0xabc", ElapsedMs: 1500, TrieTimeMs: 600,
+			WorkloadFingerprint: "bb", FingerprintOps: 10, Ordering: harness.OrderingHashedKey},
+		{Client: "ethrex", StateRoot: "0xabc", ElapsedMs: 3000, TrieTimeMs: 1200,
+			WorkloadFingerprint: "aa", FingerprintOps: 10},
+		{Client: "ethrex", StateRoot: "0xdef", ElapsedMs: 3000, TrieTimeMs: 1500,
+			WorkloadFingerprint: "bb", FingerprintOps: 10, Ordering: harness.OrderingHashedKey},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	for _, want := range []string{
+		"| reth | 2.00s | 1.50s | -25.0% | 1.00s | 600ms | -40.0% |",
+		"| ethrex | 3.00s | 3.00s | +0.0% | 1.20s | 1.50s | +25.0% |",
+		"**ORDERING ROOT MISMATCH**: ethrex computed 0xdef sorted but 0xabc unsorted",
+		"State roots: **all match**",
+	} {
+		if !strings.Contains(output, want) {
+			t.Errorf("output lacks %q:\n%s", want, output)
+		}
+	}
+
+	results[1].FingerprintOps = 9
+	if err := Generate(&buf, results); err == nil || !strings.Contains(err.Error(), "orderings") {
+		t.Errorf("Generate = %v, want a refusal to compare different workloads", err)
+	}
+}
+
+func TestGenerateComparesAllocatedDBSize(t *testing.T) {
+	logical := uint64(3 << 20)
+	results := []harness.Result{
+		{
+			Client:               "reth",
+			StateRoot:            "0xabc",
+			DBSizeBytes:          1 << 30,
+			DBApparentSizeBytes:  1 << 30,
+			DBAllocatedSizeBytes: 4 << 20,
+			DBLogicalSizeBytes:   &logical,
+		},
+		{
+			Client:               "ethrex",
+			StateRoot:            "0xabc",
+			DBSizeBytes:          5 << 20,
+			DBApparentSizeBytes:  5 << 20,
+			DBAllocatedSizeBytes: 5 << 20,
+		},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "**SPARSE FILES**: reth's database files are 1 GB long but occupy 4 MB on disk") ||
+		strings.Contains(output, "ethrex's database files") {
+		t.Errorf("only reth should be flagged:\n%s", output)
+	}
+
+	if !strings.Contains(output, "| 3 MB |") || !strings.Contains(output, "| 5 MB |") ||
+		strings.Contains(output, "| 1 GB |") {
+		t.Errorf("DB Size should use the logical and allocated sizes:\n%s", output)
+	}
+}
+
+func TestGenerateRefusesMixedSimulated(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", ElapsedMs: 100, Simulated: true},
+		{Client: "ethrex", StateRoot: "0xabc", ElapsedMs: 200},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err == nil {
+		t.Error("expected error when mixing simulated and real results")
+	}
+
+	if err := GenerateWith(&buf, results, Options{AllowSimulated: true}); err != nil {
+		t.Fatalf("GenerateWith failed: %v", err)
+	}
+
+	out := buf.String()
+	for _, want := range []string{
+		"**SIMULATED**: timings of reth include injected commit latency, the others' do not",
+		"| reth (simulated) | 100ms |",
+		"| ethrex | 200ms |",
+	} {
+		if !strings.Contains(out, want) {
+			t.Errorf("report missing %q:\n%s", want, out)
+		}
+	}
+}
+
+func TestGenerateRefusesMixedPreloaded(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", ElapsedMs: 100, Preloaded: true},
+		{Client: "ethrex", StateRoot: "0xabc", ElapsedMs: 200, Preloaded: true},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate: %v", err)
+	}
+
+	if !strings.Contains(buf.String(), "**PRELOADED**") {
+		t.Errorf("preloaded results should be marked:\n%s", buf.String())
+	}
+
+	results[1].Preloaded = false
+	if err := Generate(&buf, results); err == nil {
+		t.Error("expected error when mixing preloaded and streaming results")
+	}
+}
+
+func TestGenerateRefusesDifferentMeasurementModels(t *testing.T) {
+	model := func(overlaps string) *harness.MeasurementModel {
+		return &harness.MeasurementModel{
+			Phases:    []string{"apply", "trie", "db_write"},
+			Included:  map[string]map[string]string{"trie": {"overlaps": overlaps}},
+			CodePaths: map[string]string{"phase_order": overlaps},
+		}
+	}
+
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", ElapsedMs: 100, MeasurementModel: model("none")},
+		{Client: "ethrex", StateRoot: "0xabc", ElapsedMs: 200, MeasurementModel: model("none")},
+		{Client: "geth", StateRoot: "0xabc", ElapsedMs: 300},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate: %v", err)
+	}
+
+	results[1].MeasurementModel = model("db_write")
+
+	err := Generate(&buf, results)
+	if err == nil || !strings.Contains(err.Error(), "reth vs ethrex: included.trie.overlaps") {
+		t.Fatalf("expected a refusal naming the differing key, got %v", err)
+	}
+
+	buf.Reset()
+
+	if err := GenerateWith(&buf, results, Options{AllowModelMismatch: true}); err != nil {
+		t.Fatalf("GenerateWith: %v", err)
+	}
+
+	if !strings.Contains(buf.String(), "**MODEL MISMATCH**: reth vs ethrex: included.trie.overlaps") {
+		t.Errorf("allowed mismatch should be flagged:\n%s", buf.String())
+	}
+}
+
+func TestGenerateComparesTimeBoxedRunsByOps(t *testing.T) {
+	ops := func(n int64) *int64 { return &n }
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", ElapsedMs: 60_000, TimeBoxed: true,
+			OpsCompleted: ops(3000), DeadlineReached: true},
+		{Client: "ethrex", StateRoot: "0xdef", ElapsedMs: 30_000, TimeBoxed: true,
+			OpsCompleted: ops(1000), DeadlineReached: true},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	for _, want := range []string{
+		"**TIME-BOXED**",
+		"  - ethrex: 1000 operations\n",
+		"State roots: not compared",
+		"| 1.00x |",
+		"| 3.00x |",
+	} {
+		if !strings.Contains(out, want) {
+			t.Errorf("report missing %q:\n%s", want, out)
+		}
+	}
+
+	results[1].OpsCompleted = ops(3000)
+	results[1].DeadlineReached = false
+
+	buf.Reset()
+
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	if !strings.Contains(buf.String(), "MISMATCH") ||
+		!strings.Contains(buf.String(), "3000 operations (the workload ended first)") {
+		t.Errorf("equal prefixes should be compared:\n%s", buf.String())
+	}
+
+	results[1].TimeBoxed = false
+	if err := Generate(&buf, results); err == nil {
+		t.Error("expected error when mixing time-boxed and complete results")
+	}
+}
+
+func TestGenerateSkipsRootsOfDifferentTruncations(t *testing.T) {
+	ops := func(n int64) *int64 { return &n }
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", TruncatedAtOp: ops(1000),
+			WorkloadFingerprint: "0x1111", FingerprintOps: 1000},
+		{Client: "ethrex", StateRoot: "0xdef",
+			WorkloadFingerprint: "0x2222", FingerprintOps: 5000},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	if !strings.Contains(buf.String(), "State roots: not compared") {
+		t.Errorf("a truncated and a complete run should not be compared:\n%s", buf.String())
+	}
+
+	results[1].TruncatedAtOp = ops(1000)
+	results[1].WorkloadFingerprint = "0x1111"
+	results[1].FingerprintOps = 1000
+
+	buf.Reset()
+
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	if !strings.Contains(buf.String(), "State roots: **MISMATCH**") {
+		t.Errorf("runs truncated at the same op should be compared:\n%s", buf.String())
+	}
+}
+
+func TestGenerateRefusesDifferentWorkloads(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", WorkloadFingerprint: "0x1111", FingerprintOps: 10},
+		{Client: "ethrex", StateRoot: "0xabc", WorkloadFingerprint: "0x1111", FingerprintOps: 10},
+		{Client: "reth", StateRoot: "0xabc"},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	results[1].WorkloadFingerprint = "0x2222"
+
+	err := Generate(&buf, results)
+	if err == nil || !strings.Contains(err.Error(), "different workloads") {
+		t.Errorf("err = %v, want a refusal to compare different workloads", err)
+	}
+}
+
+func TestGenerateListsReads(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", Reads: 1000, ReadTimeMs: 2500, ReadMisses: 10},
+		{Client: "ethrex", StateRoot: "0xabc"},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	if !strings.Contains(out, "Reads:\n  - reth: 1000 reads in 2.50s, 10 misses\n\n") {
+		t.Errorf("report missing reth's reads:\n%s", out)
+	}
+
+	if strings.Contains(out, "ethrex: 0 reads") {
+		t.Errorf("ethrex had no reads:\n%s", out)
+	}
+}
+
+func TestGenerateListsProofs(t *testing.T) {
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", ProofsGenerated: 40, ProofTimeMs: 12, ProofBytesTotal: 2048, ProofExclusions: 3},
+		{Client: "ethrex", StateRoot: "0xabc"},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	if !strings.Contains(out, "Proofs:\n  - reth: 40 proofs in 12ms, 2 KB, 3 exclusions\n\n") {
+		t.Errorf("report missing reth's proofs:\n%s", out)
+	}
+
+	if strings.Contains(out, "ethrex: 0 proofs") {
+		t.Errorf("ethrex had no proofs:\n%s", out)
+	}
+}
+
+func TestGenerateReportsGasThroughput(t *testing.T) {
+	gas := func(g uint64) *uint64 { return &g }
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", Blocks: []harness.Block{
+			{TrieTimeMs: 100, DBWriteTimeMs: 400, ApproxGas: gas(15_000_000)},
+			{TrieTimeMs: 200, DBWriteTimeMs: 300, ApproxGas: gas(15_000_000)},
+			// Ended by compute_root, so without an estimate.
+			{TrieTimeMs: 1000, DBWriteTimeMs: 1000},
+		}},
+		{Client: "ethrex", StateRoot: "0xabc", Blocks: []harness.Block{
+			{TrieTimeMs: 10, DBWriteTimeMs: 20},
+		}},
+	}
+
+	gasPerSec, blocks, ok := GasThroughput(results[0])
+	if !ok || blocks != 2 || gasPerSec != 30_000_000 {
+		t.Errorf("throughput = %v over %d blocks (%v), want 30M gas/s over 2", gasPerSec, blocks, ok)
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	if !strings.Contains(out, "  - reth: 30.00 Mgas/s over 2 blocks\n\n") {
+		t.Errorf("report missing reth's gas throughput:\n%s", out)
+	}
+
+	if strings.Count(out, "Mgas/s") != 1 {
+		t.Errorf("ethrex had no estimates:\n%s", out)
+	}
+}
+
+func TestGenerateChartsRollingDrift(t *testing.T) {
+	window := func(trie, dbWrite int64) harness.WindowStats {
+		return harness.WindowStats{
+			Blocks:        50,
+			TrieTimeMs:    harness.Spread{Median: trie},
+			DBWriteTimeMs: harness.Spread{Median: dbWrite},
+		}
+	}
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", RollingWindow: 50, RollingStats: []harness.WindowStats{
+			window(10, 10), window(20, 20), window(40, 40),
+		}},
+		{Client: "ethrex", StateRoot: "0xabc", RollingWindow: 50, RollingStats: []harness.WindowStats{
+			window(5, 5),
+		}},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	if !strings.Contains(out, "  - reth: ▁▃█ 20ms → 80ms per block over 3 windows of 50\n") {
+		t.Errorf("report missing reth's drift:\n%s", out)
+	}
+
+	if strings.Contains(out, "ethrex: ▁") {
+		t.Errorf("a single window has no drift to chart:\n%s", out)
+	}
+}
+
+func TestGenerateOverlaysFillCurves(t *testing.T) {
+	curve := func(trieMs ...int64) []harness.FillPoint {
+		points := make([]harness.FillPoint, len(trieMs))
+		for i, ms := range trieMs {
+			points[i] = harness.FillPoint{
+				Fraction: float64(i+1) / float64(len(trieMs)), Accounts: 1000, TrieMs: ms,
+			}
+		}
+
+		return points
+	}
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", FillCurve: curve(1, 2, 4)},
+		{Client: "ethrex", StateRoot: "0xabc", FillCurve: curve(2, 8)},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	for _, want := range []string{
+		"  - reth: ▁▂▄ 1.0 → 4.0 ms per 1k accounts over 3 points\n",
+		"  - ethrex: ▂█ 2.0 → 8.0 ms per 1k accounts over 2 points\n",
+	} {
+		if !strings.Contains(out, want) {
+			t.Errorf("report missing %q:\n%s", want, out)
+		}
+	}
+}
+
+func TestGenerateListsRecomputeTimes(t *testing.T) {
+	ratio := 4.5
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", RecomputeTimesMs: []int64{90, 20, 21, 64}, RecomputeColdWarmRatio: &ratio},
+		{Client: "ethrex", StateRoot: "0xabc", RecomputeTimesMs: []int64{3, 0}, RecomputePageCacheKept: "drop page cache: permission denied"},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	for _, want := range []string{
+		"| reth | 90ms | 4.50x | 90, 20, 21, 64 |",
+		"| ethrex | 3ms | - | 3, 0 |",
+		"  - ethrex: page cache kept, drop page cache: permission denied",
+	} {
+		if !strings.Contains(out, want) {
+			t.Errorf("report missing %q:\n%s", want, out)
+		}
+	}
+}
+
+func TestGenerateTabulatesCodeReads(t *testing.T) {
+	pass := func(p50 int64) harness.CodeReadPass {
+		return harness.CodeReadPass{Reads: 100, BytesRead: 2048, CodeReadTimeMs: 3, P50Us: p50, P90Us: 40, P99Us: 90}
+	}
+	cold := pass(700)
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", CodeRead: &harness.CodeRead{Codes: 10, Warm: pass(8), Cold: &cold}},
+		{Client: "ethrex", StateRoot: "0xabc", CodeRead: &harness.CodeRead{
+			Codes: 10, Warm: pass(9), ColdSkipped: "drop page cache: Permission denied (os error 13)",
+		}},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	for _, want := range []string{
+		"| reth | warm | 100 | 0 | 2 KB | 3ms | 8µs | 40µs | 90µs |",
+		"| reth | cold | 100 | 0 | 2 KB | 3ms | 700µs |",
+		"| ethrex | warm |",
+		"  - ethrex: no cold pass, drop page cache: Permission denied",
+	} {
+		if !strings.Contains(out, want) {
+			t.Errorf("report missing %q:\n%s", want, out)
+		}
+	}
+
+	if strings.Contains(out, "| ethrex | cold |") {
+		t.Errorf("ethrex has no cold pass:\n%s", out)
+	}
+}
+
+func TestGenerateNotesTruncatedPhases(t *testing.T) {
+	results := []harness.Result{
+		{
+			Client: "reth", StateRoot: "0xabc", Durability: "durable",
+			CodeRead: &harness.CodeRead{
+				Codes:          4,
+				CodesTruncated: true,
+				Warm:           harness.CodeReadPass{Reads: 3, Truncated: true},
+				ColdSkipped:    "the read bench budget ran out",
+				Truncated:      true,
+			},
+			DurabilityProbe: &harness.DurabilityProbe{
+				Status:    "skipped",
+				Mode:      "soft",
+				Details:   []string{"in place: not finished within the verify budget"},
+				Truncated: true,
+			},
+		},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	out := buf.String()
+	for _, want := range []string{
+		"| reth | warm | 3 |",
+		"  - reth: sampled from the first 4 codes, the listing ran out of time",
+		"  - reth: truncated, the read bench ran out of time",
+		"  - reth: durable, soft probe skipped (truncated): in place: not finished within the verify budget",
+	} {
+		if !strings.Contains(out, want) {
+			t.Errorf("report missing %q:\n%s", want, out)
+		}
+	}
+}
+
+func TestGenerateListsDurability(t *testing.T) {
+	flushMs := int64(1500)
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", Durability: "durable"},
+		{Client: "ethrex", StateRoot: "0xabc", Durability: "durable", PostCommitFlushMs: &flushMs},
+		{
+			Client: "reth", StateRoot: "0xabc", Durability: "durable",
+			DurabilityProbe: &harness.DurabilityProbe{
+				Status: "fail", Mode: "hard", Details: []string{"copy: root 0x1, expected 0xabc"},
+			},
+		},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "  - reth: durable\n") {
+		t.Errorf("expected reth durability line, got:\n%s", output)
+	}
+	if !strings.Contains(output, "  - ethrex: durable (post-commit flush 1.50s)") {
+		t.Errorf("expected ethrex flush time, got:\n%s", output)
+	}
+	if !strings.Contains(output, "  - reth: durable, hard probe fail: copy: root 0x1, expected 0xabc\n") {
+		t.Errorf("expected probe failure, got:\n%s", output)
+	}
+}
+
+func TestGenerateFlagsRetries(t *testing.T) {
+	results := []harness.Result{
+		{
+			Client: "reth", StateRoot: "0xabc",
+			Attempts: []harness.Attempt{
+				{Index: 1, Kind: harness.FailureInterrupted, Error: "signal: killed"},
+				{Index: 2},
+			},
+		},
+		{Client: "geth", StateRoot: "0xabc"},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "**RETRIED**: reth succeeded on attempt 2 after interrupted\n") {
+		t.Errorf("expected retry note, got:\n%s", output)
+	}
+	if strings.Contains(output, "geth succeeded") {
+		t.Errorf("unexpected retry note for geth:\n%s", output)
+	}
+}
+
+func TestGenerateFlagsInputBound(t *testing.T) {
+	slow, fast := int64(1000), int64(5000)
+	results := []harness.Result{
+		{Client: "reth", StateRoot: "0xabc", InputConsumptionOpsPerSec: &slow, ApplyOpsPerSec: &fast},
+		{Client: "ethrex", StateRoot: "0xabc", InputConsumptionOpsPerSec: &fast, ApplyOpsPerSec: &slow},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "**INPUT-BOUND**: reth received 1000 ops/s but could apply 5000 ops/s") {
+		t.Errorf("expected input-bound note, got:\n%s", output)
+	}
+	if strings.Contains(output, "ethrex received") {
+		t.Errorf("unexpected input-bound note for ethrex:\n%s", output)
+	}
+}
+
+func TestGenerateFlagsEnvironmentDifferences(t *testing.T) {
+	before, after := uint64(0), uint64(8*1024*1024)
+	results := []harness.Result{
+		{
+			Client:    "reth",
+			StateRoot: "0xabc",
+			Environment: &harness.Environment{
+				Start: harness.EnvSnapshot{THPEnabled: "always"},
+			},
+		},
+		{
+			Client:    "ethrex",
+			StateRoot: "0xabc",
+			Environment: &harness.Environment{
+				Start: harness.EnvSnapshot{THPEnabled: "madvise", SwapUsedBytes: &before},
+				End:   harness.EnvSnapshot{THPEnabled: "madvise", SwapUsedBytes: &after},
+			},
+		},
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, results); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "  - reth: transparent huge pages always") {
+		t.Errorf("expected THP mode for reth, got:\n%s", output)
+	}
+	if !strings.Contains(output, "  - ethrex: swap grew by 8 MB") {
+		t.Errorf("expected swap growth for ethrex, got:\n%s", output)
+	}
+}
+
+func TestNormalizedMetrics(t *testing.T) {
+	mixed := harness.Result{
+		Client:          "reth",
+		AccountsCreated: 200,
+		StorageSlots:    4000,
+		ElapsedMs:       1000,
+		TrieTimeMs:      600,
+		DBWriteTimeMs:   300,
+		DBSizeBytes:     512000,
+	}
+	accountsOnly := harness.Result{Client: "ethrex", AccountsCreated: 50, TrieTimeMs: 5}
+
+	want := map[string]float64{
+		"elapsed_us_per_account": 5000,
+		"trie_us_per_account":    3000,
+		"trie_us_per_slot":       150,
+		"db_write_us_per_slot":   75,
+		"db_bytes_per_slot":      128,
+	}
+
+	for _, m := range NormalizedMetrics {
+		got, ok := m.Value(mixed)
+		if !ok || got != want[m.Name] {
+			t.Errorf("%s = %v, %v; want %v", m.Name, got, ok, want[m.Name])
+		}
+
+		_, ok = m.Value(accountsOnly)
+		perAccount := strings.HasSuffix(m.Name, "_per_account")
+		if ok != perAccount {
+			t.Errorf("%s on an accounts-only result: ok = %v, want %v", m.Name, ok, perAccount)
+		}
+	}
+
+	var buf bytes.Buffer
+	if err := Generate(&buf, []harness.Result{mixed, accountsOnly}); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	output := buf.String()
+	if !strings.Contains(output, "| reth | 5000.00 us | 3000.00 us | 150.00 us | 75.00 us | 128.00 B |") {
+		t.Errorf("missing normalized row for reth:\n%s", output)
+	}
+	if !strings.Contains(output, "| ethrex | 0.00 us | 100.00 us | - | - | - |") {
+		t.Errorf("missing normalized row for ethrex:\n%s", output)
+	}
+}
+
 func TestGenerateEmpty(t *testing.T) {
 	var buf bytes.Buffer
 	err := Generate(&buf, nil)
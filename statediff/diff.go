@@ -0,0 +1,253 @@
+// Package statediff compares two state dumps account by account.
+package statediff
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"slices"
+	"strconv"
+	"strings"
+)
+
+// Diff kinds.
+const (
+	OnlyInA = "only_in_a"
+	OnlyInB = "only_in_b"
+	Field   = "field"
+	Slot    = "slot"
+)
+
+// zero is a storage value equal to an absent slot.
+const zero = "0x0"
+
+// Account is one line of a state dump, as listed by the dump_state
+// query: an account keyed by its address, or by its hashed address when
+// the dump came from hashed tables, with its storage.
+type Account struct {
+	Address       string            `json:"address,omitempty"`
+	HashedAddress string            `json:"hashed_address,omitempty"`
+	Nonce         uint64            `json:"nonce"`
+	Balance       string            `json:"balance"`
+	CodeHash      string            `json:"code_hash"`
+	Storage       map[string]string `json:"storage,omitempty"`
+}
+
+// Diff is one difference between two dumps. Field names the differing
+// account field (balance, nonce or code_hash), or the slot of a Slot
+// diff. A and B are the values in each dump, empty where absent.
+type Diff struct {
+	Kind    string `json:"kind"`
+	Account string `json:"account"`
+	Field   string `json:"field,omitempty"`
+	A       string `json:"a,omitempty"`
+	B       string `json:"b,omitempty"`
+}
+
+// Counts tallies the accounts of both dumps and every difference
+// between them.
+type Counts struct {
+	AccountsA int `json:"accounts_a"`
+	AccountsB int `json:"accounts_b"`
+	OnlyInA   int `json:"only_in_a"`
+	OnlyInB   int `json:"only_in_b"`
+	// DifferingAccounts counts accounts in both dumps with at least one
+	// differing field or slot.
+	DifferingAccounts int `json:"differing_accounts"`
+	DifferingSlots    int `json:"differing_slots"`
+}
+
+// Differ reports whether the dumps differ at all.
+func (c Counts) Differ() bool {
+	return c.OnlyInA > 0 || c.OnlyInB > 0 || c.DifferingAccounts > 0
+}
+
+// Compare merge-joins two dumps, each a JSON line per account sorted by
+// key, and calls emit for every difference in key order. Only the
+// current account of each dump is held in memory. Values are compared
+// as strings, since the harnesses emit canonical hex, and a zero slot
+// equals an absent one.
+func Compare(a, b io.Reader, emit func(Diff) error) (Counts, error) {
+	var counts Counts
+
+	ra := &dumpReader{name: "a", dec: json.NewDecoder(a)}
+	rb := &dumpReader{name: "b", dec: json.NewDecoder(b)}
+
+	x, err := ra.next()
+	if err != nil {
+		return counts, err
+	}
+
+	y, err := rb.next()
+	if err != nil {
+		return counts, err
+	}
+
+	if x != nil && y != nil && x.hashed != y.hashed {
+		return counts, errors.New("dumps key accounts differently: " +
+			"one by address, the other by hashed address")
+	}
+
+	for x != nil || y != nil {
+		switch {
+		case y == nil || (x != nil && x.key < y.key):
+			counts.OnlyInA++
+
+			if err := emit(Diff{Kind: OnlyInA, Account: x.key}); err != nil {
+				return counts, err
+			}
+
+			x, err = ra.next()
+		case x == nil || y.key < x.key:
+			counts.OnlyInB++
+
+			if err := emit(Diff{Kind: OnlyInB, Account: y.key}); err != nil {
+				return counts, err
+			}
+
+			y, err = rb.next()
+		default:
+			var slots int
+
+			slots, err = compareAccount(x, y, emit)
+			if err != nil {
+				return counts, err
+			}
+
+			counts.DifferingSlots += slots
+			if slots > 0 || !sameFields(&x.Account, &y.Account) {
+				counts.DifferingAccounts++
+			}
+
+			if x, err = ra.next(); err == nil {
+				y, err = rb.next()
+			}
+		}
+
+		if err != nil {
+			return counts, err
+		}
+	}
+
+	counts.AccountsA = ra.accounts
+	counts.AccountsB = rb.accounts
+
+	return counts, nil
+}
+
+// entry is a decoded account with its normalized key.
+type entry struct {
+	Account
+
+	key    string
+	hashed bool
+}
+
+// dumpReader decodes one dump and checks its order.
+type dumpReader struct {
+	name     string
+	dec      *json.Decoder
+	accounts int
+	last     string
+	hashed   bool
+}
+
+// next returns the dump's next account, or nil at its end.
+func (d *dumpReader) next() (*entry, error) {
+	var e entry
+	if err := d.dec.Decode(&e.Account); err != nil {
+		if errors.Is(err, io.EOF) {
+			return nil, nil
+		}
+
+		return nil, fmt.Errorf("decode account %d of dump %s: %w", d.accounts+1, d.name, err)
+	}
+
+	d.accounts++
+
+	switch {
+	case e.Address != "" && e.HashedAddress == "":
+		e.key = strings.ToLower(e.Address)
+	case e.Address == "" && e.HashedAddress != "":
+		e.key = strings.ToLower(e.HashedAddress)
+		e.hashed = true
+	default:
+		return nil, fmt.Errorf("account %d of dump %s needs exactly one of address and hashed_address",
+			d.accounts, d.name)
+	}
+
+	if d.accounts > 1 && e.hashed != d.hashed {
+		return nil, fmt.Errorf("dump %s mixes address and hashed_address keys", d.name)
+	}
+
+	if d.accounts > 1 && e.key <= d.last {
+		return nil, fmt.Errorf("dump %s is not sorted: %s follows %s", d.name, e.key, d.last)
+	}
+
+	d.last = e.key
+	d.hashed = e.hashed
+
+	return &e, nil
+}
+
+func sameFields(a, b *Account) bool {
+	return a.Balance == b.Balance && a.Nonce == b.Nonce && a.CodeHash == b.CodeHash
+}
+
+// compareAccount emits the field and slot diffs of an account present
+// in both dumps, slots in order, and returns how many slots differ.
+func compareAccount(x, y *entry, emit func(Diff) error) (int, error) {
+	fields := []struct{ name, a, b string }{
+		{"balance", x.Balance, y.Balance},
+		{"nonce", strconv.FormatUint(x.Nonce, 10), strconv.FormatUint(y.Nonce, 10)},
+		{"code_hash", x.CodeHash, y.CodeHash},
+	}
+
+	for _, f := range fields {
+		if f.a != f.b {
+			if err := emit(Diff{Kind: Field, Account: x.key, Field: f.name, A: f.a, B: f.b}); err != nil {
+				return 0, err
+			}
+		}
+	}
+
+	slots := make([]string, 0, len(x.Storage)+len(y.Storage))
+	for slot := range x.Storage {
+		slots = append(slots, slot)
+	}
+
+	for slot := range y.Storage {
+		if _, ok := x.Storage[slot]; !ok {
+			slots = append(slots, slot)
+		}
+	}
+
+	slices.Sort(slots)
+
+	var differing int
+
+	for _, slot := range slots {
+		a, b := slotValue(x.Storage, slot), slotValue(y.Storage, slot)
+		if a == b {
+			continue
+		}
+
+		differing++
+
+		if err := emit(Diff{Kind: Slot, Account: x.key, Field: slot, A: a, B: b}); err != nil {
+			return differing, err
+		}
+	}
+
+	return differing, nil
+}
+
+// slotValue returns a slot's value, empty when it is absent or zero.
+func slotValue(storage map[string]string, slot string) string {
+	if v := storage[slot]; v != zero {
+		return v
+	}
+
+	return ""
+}
@@ -0,0 +1,223 @@
+package statediff
+
+import (
+	"fmt"
+	"io"
+	"slices"
+	"strconv"
+	"strings"
+	"testing"
+)
+
+const (
+	addr1 = "0x0000000000000000000000000000000000000001"
+	addr2 = "0x0000000000000000000000000000000000000002"
+	addr3 = "0x0000000000000000000000000000000000000003"
+)
+
+func compareAll(t *testing.T, a, b string) (Counts, []Diff, error) {
+	t.Helper()
+
+	var diffs []Diff
+
+	counts, err := Compare(strings.NewReader(a), strings.NewReader(b), func(d Diff) error {
+		diffs = append(diffs, d)
+
+		return nil
+	})
+
+	return counts, diffs, err
+}
+
+func TestCompareClassifiesDifferences(t *testing.T) {
+	a := strings.Join([]string{
+		`{"address":"` + addr1 + `","nonce":1,"balance":"0x10","code_hash":"0xc1"}`,
+		`{"address":"` + addr2 + `","nonce":0,"balance":"0x0","code_hash":"0xc2",` +
+			`"storage":{"0x01":"0x2a","0x02":"0x0","0x03":"0x5"}}`,
+	}, "\n")
+	b := strings.Join([]string{
+		`{"address":"0x` + strings.ToUpper(addr2[2:]) + `","nonce":2,"balance":"0x0","code_hash":"0xc2",` +
+			`"storage":{"0x01":"0x2b","0x03":"0x5","0x04":"0x0"}}`,
+		`{"address":"` + addr3 + `","nonce":0,"balance":"0x1","code_hash":"0xc3"}`,
+	}, "\n")
+
+	counts, diffs, err := compareAll(t, a, b)
+	if err != nil {
+		t.Fatalf("compare: %v", err)
+	}
+
+	want := []Diff{
+		{Kind: OnlyInA, Account: addr1},
+		{Kind: Field, Account: addr2, Field: "nonce", A: "0", B: "2"},
+		{Kind: Slot, Account: addr2, Field: "0x01", A: "0x2a", B: "0x2b"},
+		{Kind: OnlyInB, Account: addr3},
+	}
+	if !slices.Equal(diffs, want) {
+		t.Errorf("diffs = %+v, want %+v", diffs, want)
+	}
+
+	wantCounts := Counts{
+		AccountsA: 2, AccountsB: 2, OnlyInA: 1, OnlyInB: 1,
+		DifferingAccounts: 1, DifferingSlots: 1,
+	}
+	if counts != wantCounts || !counts.Differ() {
+		t.Errorf("counts = %+v, want %+v", counts, wantCounts)
+	}
+}
+
+func TestCompareRejectsMalformedDumps(t *testing.T) {
+	line := func(key, value string) string {
+		return fmt.Sprintf(`{%q:%q,"nonce":0,"balance":"0x0","code_hash":"0x00"}`, key, value)
+	}
+
+	tests := []struct {
+		name string
+		a, b string
+		want string
+	}{
+		{
+			name: "unsorted",
+			a:    line("address", addr2) + "\n" + line("address", addr1),
+			want: "dump a is not sorted",
+		},
+		{
+			name: "duplicate",
+			b:    line("address", addr1) + "\n" + line("address", addr1),
+			want: "dump b is not sorted",
+		},
+		{
+			name: "different key kinds",
+			a:    line("address", addr1),
+			b:    line("hashed_address", addr1),
+			want: "dumps key accounts differently",
+		},
+		{
+			name: "mixed key kinds",
+			a:    line("address", addr1) + "\n" + line("hashed_address", addr2),
+			want: "dump a mixes address and hashed_address keys",
+		},
+		{
+			name: "no key",
+			a:    `{"nonce":0}`,
+			want: "needs exactly one of address and hashed_address",
+		},
+	}
+
+	for _, tc := range tests {
+		t.Run(tc.name, func(t *testing.T) {
+			_, _, err := compareAll(t, tc.a, tc.b)
+			if err == nil || !strings.Contains(err.Error(), tc.want) {
+				t.Errorf("err = %v, want it to mention %q", err, tc.want)
+			}
+		})
+	}
+}
+
+func TestRunTruncatesDiffsButNotCounts(t *testing.T) {
+	var a, b strings.Builder
+
+	for i := range 10 {
+		fmt.Fprintf(&a, `{"address":"0x%040x","nonce":0,"balance":"0x1","code_hash":"0x00"}`+"\n", i)
+		fmt.Fprintf(&b, `{"address":"0x%040x","nonce":0,"balance":"0x2","code_hash":"0x00"}`+"\n", i)
+	}
+
+	rep, err := Run("a", strings.NewReader(a.String()), "b", strings.NewReader(b.String()), 3)
+	if err != nil {
+		t.Fatalf("run: %v", err)
+	}
+
+	if len(rep.Diffs) != 3 || !rep.Truncated || rep.DifferingAccounts != 10 {
+		t.Errorf("kept %d diffs, truncated %v, %d differing accounts",
+			len(rep.Diffs), rep.Truncated, rep.DifferingAccounts)
+	}
+
+	var table strings.Builder
+
+	rep.WriteTable(&table)
+
+	if !strings.Contains(table.String(), "| 0x0000000000000000000000000000000000000002 | field | balance | 0x1 | 0x2 |") ||
+		!strings.Contains(table.String(), "Showing the first 3 differences.") {
+		t.Errorf("table:\n%s", table.String())
+	}
+}
+
+// generatedDump is a dump of n accounts produced one line at a time as it
+// is read, so a test can see how far ahead of the join its reader got.
+type generatedDump struct {
+	n, lines int
+	line     func(i int) string
+	pending  []byte
+}
+
+func (g *generatedDump) Read(p []byte) (int, error) {
+	if len(g.pending) == 0 {
+		if g.lines == g.n {
+			return 0, io.EOF
+		}
+
+		g.pending = []byte(g.line(g.lines))
+		g.lines++
+	}
+
+	n := copy(p, g.pending)
+	g.pending = g.pending[n:]
+
+	return n, nil
+}
+
+func TestCompareStreamsLargeDumps(t *testing.T) {
+	if testing.Short() {
+		t.Skip("generates two dumps of a million accounts")
+	}
+
+	const (
+		accounts = 1_000_000
+		// slack bounds how many lines either reader may run ahead of the
+		// account being compared: the decoder's read buffer, not the dump.
+		slack    = 1_000
+		every    = 50_000
+	)
+
+	account := func(i int, balance string) string {
+		return fmt.Sprintf(`{"address":"0x%040x","nonce":%d,"balance":%q,"code_hash":"0x00",`+
+			`"storage":{"0x01":"0x%x"}}`+"\n", i, i%7, balance, i)
+	}
+	a := &generatedDump{n: accounts, line: func(i int) string { return account(i, "0x1") }}
+	// b changes the balance of one account in every 50,000 and drops the
+	// last account.
+	b := &generatedDump{n: accounts - 1, line: func(i int) string {
+		if i%every == 0 {
+			return account(i, "0x2")
+		}
+
+		return account(i, "0x1")
+	}}
+
+	var emitted int
+
+	counts, err := Compare(a, b, func(d Diff) error {
+		emitted++
+
+		at, err := strconv.ParseUint(d.Account[2:], 16, 64)
+		if err != nil {
+			return err
+		}
+
+		if a.lines > int(at)+slack || b.lines > int(at)+slack {
+			return fmt.Errorf("at account %d the readers produced %d and %d lines", at, a.lines, b.lines)
+		}
+
+		return nil
+	})
+	if err != nil {
+		t.Fatalf("compare: %v", err)
+	}
+
+	want := Counts{
+		AccountsA: accounts, AccountsB: accounts - 1, OnlyInA: 1,
+		DifferingAccounts: accounts / every,
+	}
+	if counts != want || emitted != accounts/every+1 {
+		t.Errorf("counts = %+v with %d diffs, want %+v", counts, emitted, want)
+	}
+}
@@ -0,0 +1,77 @@
+package statediff
+
+import (
+	"fmt"
+	"io"
+)
+
+// Report is the JSON diff document of two dumps: their counts and the
+// first differences, in key order.
+type Report struct {
+	A string `json:"a"`
+	B string `json:"b"`
+	Counts
+	Diffs []Diff `json:"diffs"`
+	// Truncated is set when Diffs stopped at the limit before the last
+	// difference. Counts still cover every difference.
+	Truncated bool `json:"truncated"`
+}
+
+// Run compares dumps a and b and returns their report, keeping at most
+// maxDiffs differences. Zero keeps every difference.
+func Run(nameA string, a io.Reader, nameB string, b io.Reader, maxDiffs int) (Report, error) {
+	rep := Report{A: nameA, B: nameB, Diffs: []Diff{}}
+
+	counts, err := Compare(a, b, func(d Diff) error {
+		if maxDiffs > 0 && len(rep.Diffs) >= maxDiffs {
+			rep.Truncated = true
+
+			return nil
+		}
+
+		rep.Diffs = append(rep.Diffs, d)
+
+		return nil
+	})
+	rep.Counts = counts
+
+	return rep, err
+}
+
+// WriteTable writes the report as markdown: a summary of the counts,
+// then a row per kept difference.
+func (r Report) WriteTable(w io.Writer) {
+	fmt.Fprintf(w, "## State Diff\n\n")
+	fmt.Fprintf(w, "A: %s (%d accounts)\n", r.A, r.AccountsA)
+	fmt.Fprintf(w, "B: %s (%d accounts)\n\n", r.B, r.AccountsB)
+
+	if !r.Differ() {
+		fmt.Fprintln(w, "States: **identical**")
+
+		return
+	}
+
+	fmt.Fprintf(w, "States: **DIFFER**: %d only in A, %d only in B, "+
+		"%d differing accounts (%d slots)\n\n",
+		r.OnlyInA, r.OnlyInB, r.DifferingAccounts, r.DifferingSlots)
+
+	fmt.Fprintln(w, "| Account | Kind | Field | A | B |")
+	fmt.Fprintln(w, "|---------|------|-------|---|---|")
+
+	for _, d := range r.Diffs {
+		fmt.Fprintf(w, "| %s | %s | %s | %s | %s |\n",
+			d.Account, d.Kind, d.Field, orDash(d.A), orDash(d.B))
+	}
+
+	if r.Truncated {
+		fmt.Fprintf(w, "\nShowing the first %d differences.\n", len(r.Diffs))
+	}
+}
+
+func orDash(s string) string {
+	if s == "" {
+		return "-"
+	}
+
+	return s
+}
@@ -0,0 +1,73 @@
+package workload
+
+import (
+	"errors"
+	"fmt"
+)
+
+// ErrNoDivergence reports a bisection whose starting selection already
+// produces matching roots.
+var ErrNoDivergence = errors.New("roots do not diverge")
+
+// Probe runs the workload restricted to s and reports how many accounts
+// it kept and whether the clients' roots diverged.
+type Probe func(s Selector) (accounts int, diverged bool, err error)
+
+// Bisection is where a bisection stopped.
+type Bisection struct {
+	Selector Selector
+	Accounts int
+	// Probes counts the workloads run, including the starting one.
+	Probes int
+}
+
+// Bisect narrows a root divergence to at most maxAccounts accounts by
+// halving the selection and keeping a half that still diverges. It stops
+// early when neither half diverges on its own, since the divergence then
+// needs accounts from both.
+func Bisect(start Selector, maxAccounts int, probe Probe) (Bisection, error) {
+	if maxAccounts < 1 {
+		return Bisection{}, fmt.Errorf("max accounts must be positive, got %d", maxAccounts)
+	}
+
+	accounts, diverged, err := probe(start)
+	b := Bisection{Selector: start, Accounts: accounts, Probes: 1}
+
+	if err != nil {
+		return b, err
+	}
+
+	if !diverged {
+		return b, ErrNoDivergence
+	}
+
+	for b.Accounts > maxAccounts {
+		lo, hi, ok := b.Selector.Split()
+		if !ok {
+			return b, nil
+		}
+
+		narrowed := false
+
+		for _, half := range []Selector{lo, hi} {
+			accounts, diverged, err := probe(half)
+			b.Probes++
+
+			if err != nil {
+				return b, err
+			}
+
+			if diverged {
+				b.Selector, b.Accounts, narrowed = half, accounts, true
+
+				break
+			}
+		}
+
+		if !narrowed {
+			return b, nil
+		}
+	}
+
+	return b, nil
+}
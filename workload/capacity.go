@@ -0,0 +1,105 @@
+package workload
+
+import (
+	"fmt"
+	"math"
+)
+
+// ScaleProbe runs the workload generated at scale and reports whether
+// the run stayed within its budget.
+type ScaleProbe func(scale int) (fits bool, err error)
+
+// Capacity is where a capacity search stopped.
+type Capacity struct {
+	// Largest is the largest scale that fit, 0 when even the starting
+	// scale did not.
+	Largest int
+	// Exceeded is the smallest scale that did not fit, 0 when every
+	// scale up to the limit fit.
+	Exceeded int
+	// Probes counts the workloads run.
+	Probes int
+}
+
+// SearchCapacity finds the largest scale that fits, assuming every
+// scale below one that fits fits too. It doubles from start until a
+// scale does not fit or limit is reached (0 for no limit), then bisects
+// between the last fitting and the first exceeding scale until they are
+// within precision of the fitting one, or adjacent.
+func SearchCapacity(start, limit int, precision float64, probe ScaleProbe) (Capacity, error) {
+	if start < 1 {
+		return Capacity{}, fmt.Errorf("start scale must be positive, got %d", start)
+	}
+
+	if limit != 0 && limit < start {
+		return Capacity{}, fmt.Errorf("scale limit %d is below the start scale %d", limit, start)
+	}
+
+	if precision < 0 || precision >= 1 {
+		return Capacity{}, fmt.Errorf("precision must be in [0, 1), got %g", precision)
+	}
+
+	var c Capacity
+
+	try := func(scale int) (bool, error) {
+		c.Probes++
+
+		return probe(scale)
+	}
+
+	fits, err := try(start)
+	if err != nil {
+		return c, err
+	}
+
+	if !fits {
+		c.Exceeded = start
+
+		return c, nil
+	}
+
+	c.Largest = start
+
+	for c.Exceeded == 0 {
+		next := c.Largest * 2
+		if c.Largest > math.MaxInt/2 {
+			next = math.MaxInt
+		}
+
+		if limit != 0 {
+			next = min(next, limit)
+		}
+
+		if next == c.Largest {
+			return c, nil
+		}
+
+		fits, err := try(next)
+		if err != nil {
+			return c, err
+		}
+
+		if fits {
+			c.Largest = next
+		} else {
+			c.Exceeded = next
+		}
+	}
+
+	for c.Exceeded-c.Largest > max(1, int(float64(c.Largest)*precision)) {
+		mid := c.Largest + (c.Exceeded-c.Largest)/2
+
+		fits, err := try(mid)
+		if err != nil {
+			return c, err
+		}
+
+		if fits {
+			c.Largest = mid
+		} else {
+			c.Exceeded = mid
+		}
+	}
+
+	return c, nil
+}
@@ -0,0 +1,51 @@
+package workload
+
+import (
+	"errors"
+	"testing"
+)
+
+func TestSearchCapacityFindsTheThreshold(t *testing.T) {
+	below := func(threshold int) ScaleProbe {
+		return func(scale int) (bool, error) { return scale <= threshold, nil }
+	}
+
+	c, err := SearchCapacity(1, 0, 0, below(1000))
+	if err != nil {
+		t.Fatalf("search: %v", err)
+	}
+
+	if c.Largest != 1000 || c.Exceeded != 1001 {
+		t.Errorf("capacity = %+v, want 1000 fitting and 1001 exceeding", c)
+	}
+
+	// Doubling takes 11 runs to reach 1024, bisection 9 more.
+	if c.Probes != 20 {
+		t.Errorf("probes = %d, want 20", c.Probes)
+	}
+
+	coarse, err := SearchCapacity(1, 0, 0.05, below(1000))
+	if err != nil {
+		t.Fatalf("coarse search: %v", err)
+	}
+
+	if gap := coarse.Exceeded - coarse.Largest; coarse.Largest > 1000 || gap > 50 {
+		t.Errorf("coarse capacity = %+v, want within 5%% below 1000", coarse)
+	}
+
+	limited, err := SearchCapacity(3, 300, 0, below(1<<20))
+	if err != nil || limited.Largest != 300 || limited.Exceeded != 0 {
+		t.Errorf("limited capacity = %+v, %v; want 300 and nothing exceeding", limited, err)
+	}
+
+	none, err := SearchCapacity(10, 0, 0, below(5))
+	if err != nil || none.Largest != 0 || none.Exceeded != 10 || none.Probes != 1 {
+		t.Errorf("capacity = %+v, %v; want nothing fitting after one run", none, err)
+	}
+
+	failing := errors.New("generator failed")
+	_, err = SearchCapacity(1, 0, 0, func(int) (bool, error) { return false, failing })
+	if !errors.Is(err, failing) {
+		t.Errorf("err = %v, want the probe's error", err)
+	}
+}
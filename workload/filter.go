@@ -0,0 +1,217 @@
+package workload
+
+import (
+	"bufio"
+	"bytes"
+	"crypto/sha256"
+	"encoding/binary"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"strings"
+)
+
+// computeRootLine ends every filtered workload.
+const computeRootLine = `{"op":"compute_root"}`
+
+// maxModulus bounds Selector.Split, so the bucket arithmetic cannot
+// overflow.
+const maxModulus = 1 << 62
+
+// Selector picks the accounts a filtered workload keeps: those whose
+// address starts with Prefix and, when Modulus is set, whose address
+// hash falls in Bucket.
+type Selector struct {
+	// Prefix is a run of lowercase hex digits, without 0x.
+	Prefix string
+	// Modulus partitions addresses by the first 8 bytes of the SHA-256
+	// of their 20 bytes, big-endian. Zero disables partitioning.
+	Modulus uint64
+	Bucket  uint64
+}
+
+// Validate reports a selector that cannot match as intended.
+func (s Selector) Validate() error {
+	if strings.Trim(s.Prefix, "0123456789abcdef") != "" {
+		return fmt.Errorf("address prefix %q is not lowercase hex", s.Prefix)
+	}
+
+	if len(s.Prefix) > 40 {
+		return fmt.Errorf("address prefix %q is longer than an address", s.Prefix)
+	}
+
+	if s.Modulus == 0 && s.Bucket != 0 {
+		return errors.New("bucket requires a modulus")
+	}
+
+	if s.Modulus > 0 && s.Bucket >= s.Modulus {
+		return fmt.Errorf("bucket %d is not below modulus %d", s.Bucket, s.Modulus)
+	}
+
+	return nil
+}
+
+// Match reports whether s keeps the account at address. Addresses are
+// compared case-insensitively, with or without 0x.
+func (s Selector) Match(address string) bool {
+	digits := strings.ToLower(strings.TrimPrefix(address, "0x"))
+	if !strings.HasPrefix(digits, s.Prefix) {
+		return false
+	}
+
+	if s.Modulus == 0 {
+		return true
+	}
+
+	raw, err := hex.DecodeString(digits)
+	if err != nil {
+		raw = []byte(digits)
+	}
+
+	sum := sha256.Sum256(raw)
+
+	return binary.BigEndian.Uint64(sum[:8])%s.Modulus == s.Bucket
+}
+
+// Split divides the accounts s keeps into two disjoint halves, by
+// doubling the modulus. It reports false once the modulus cannot grow.
+func (s Selector) Split() (Selector, Selector, bool) {
+	modulus := max(s.Modulus, 1)
+	if modulus >= maxModulus {
+		return s, s, false
+	}
+
+	lo := Selector{Prefix: s.Prefix, Modulus: 2 * modulus, Bucket: s.Bucket}
+	hi := Selector{Prefix: s.Prefix, Modulus: 2 * modulus, Bucket: s.Bucket + modulus}
+
+	return lo, hi, true
+}
+
+// Args renders s as the flags of `statoor workload filter`.
+func (s Selector) Args() []string {
+	var args []string
+	if s.Prefix != "" {
+		args = append(args, "--address-prefix", "0x"+s.Prefix)
+	}
+
+	if s.Modulus > 0 {
+		args = append(args,
+			"--modulus", fmt.Sprint(s.Modulus), "--bucket", fmt.Sprint(s.Bucket))
+	}
+
+	return args
+}
+
+// FilterSummary describes a filtered workload.
+type FilterSummary struct {
+	// Operations counts the lines kept, including the final
+	// compute_root but not a trailer.
+	Operations int
+	// Addresses lists the kept accounts in order of first appearance.
+	Addresses []string
+}
+
+// Filter copies the operations of the workload in r that touch an
+// account s keeps to w, unchanged apart from surrounding whitespace and
+// in their original order. commit_block lines are always kept, so the
+// output has the same blocks, some of them possibly empty, and so are
+// define_code lines, which a kept set_code_ref may name, and checkpoint
+// lines, so the filtered run reports at the same points. A trailer
+// line is replaced by one declaring the kept lines, written before the
+// final compute_root.
+// Every other operation names its own account, so an account's lines
+// depend on nothing else; create_accounts, which derives its accounts,
+// and transfer, which moves a balance between two, are refused.
+// compute_root lines are dropped and a single one ends
+// the output, so it is always a valid workload. Lines are read one at a
+// time, so memory grows only with the number of kept accounts.
+func Filter(r io.Reader, w io.Writer, s Selector) (FilterSummary, error) {
+	var summary FilterSummary
+
+	in := bufio.NewReader(r)
+	out := bufio.NewWriter(w)
+	seen := make(map[string]struct{})
+
+	var (
+		tally      Tally
+		hasTrailer bool
+	)
+
+	for lineNo := 1; ; lineNo++ {
+		line, readErr := in.ReadBytes('\n')
+		if readErr != nil && readErr != io.EOF {
+			return summary, fmt.Errorf("read line %d: %w", lineNo, readErr)
+		}
+
+		if trimmed := bytes.TrimSpace(line); len(trimmed) > 0 {
+			var op struct {
+				Op      string `json:"op"`
+				Address string `json:"address"`
+			}
+
+			if err := json.Unmarshal(trimmed, &op); err != nil {
+				return summary, fmt.Errorf("decode line %d: %w", lineNo, err)
+			}
+
+			var keep bool
+
+			switch {
+			case op.Op == trailerOp:
+				hasTrailer = true
+			case op.Op == "header":
+				// Its total_ops and expected_root describe the unfiltered
+				// workload, so the header is dropped.
+			case op.Op == "commit_block", op.Op == "define_code", op.Op == "checkpoint":
+				keep = true
+			case op.Op == "create_accounts":
+				return summary, fmt.Errorf(
+					"line %d: create_accounts derives its addresses, so it cannot be filtered by account", lineNo)
+			case op.Op == "transfer":
+				return summary, fmt.Errorf(
+					"line %d: transfer involves two accounts, so it cannot be filtered by account", lineNo)
+			case op.Op != "compute_root" && s.Match(op.Address):
+				keep = true
+
+				address := strings.ToLower(op.Address)
+				if _, ok := seen[address]; !ok {
+					seen[address] = struct{}{}
+					summary.Addresses = append(summary.Addresses, address)
+				}
+			}
+
+			if keep {
+				if _, err := out.Write(append(trimmed, '\n')); err != nil {
+					return summary, fmt.Errorf("write line %d: %w", lineNo, err)
+				}
+
+				tally.Add(op.Op)
+				summary.Operations++
+			}
+		}
+
+		if readErr == io.EOF {
+			break
+		}
+	}
+
+	if hasTrailer {
+		trailer, err := json.Marshal(tally.Trailer())
+		if err != nil {
+			return summary, fmt.Errorf("encode trailer: %w", err)
+		}
+
+		if _, err := out.Write(append(trailer, '\n')); err != nil {
+			return summary, fmt.Errorf("write trailer: %w", err)
+		}
+	}
+
+	if _, err := out.WriteString(computeRootLine + "\n"); err != nil {
+		return summary, fmt.Errorf("write compute_root: %w", err)
+	}
+
+	summary.Operations++
+
+	return summary, out.Flush()
+}
@@ -0,0 +1,157 @@
+package workload
+
+import (
+	"bytes"
+	"errors"
+	"fmt"
+	"slices"
+	"strings"
+	"testing"
+)
+
+func TestFilterKeepsMatchingAccounts(t *testing.T) {
+	input := strings.Join([]string{
+		`{"op":"header","version":1,"name":"two","total_ops":7,"expected_root":"0x01"}`,
+		`{"op":"create_account","address":"0xAB00000000000000000000000000000000000001","balance":"0x1","nonce":0}`,
+		`{"op":"create_account","address":"0xcd00000000000000000000000000000000000002","balance":"0x1","nonce":0}`,
+		``,
+		`{"op":"commit_block"}`,
+		`{"op":"define_code","id":"c","code":"0x00"}`,
+		`{"op":"checkpoint","label":"half"}`,
+		`{"op":"set_storage","address":"0xcd00000000000000000000000000000000000002","slot":"0x01","value":"0x02"}`,
+		`{"op":"set_code","address":"0xab00000000000000000000000000000000000001","code":"0x00","tenant":1}`,
+		`{"op":"compute_root"}`,
+	}, "\r\n")
+
+	var out bytes.Buffer
+
+	summary, err := Filter(strings.NewReader(input), &out, Selector{Prefix: "ab"})
+	if err != nil {
+		t.Fatalf("filter: %v", err)
+	}
+
+	want := `{"op":"create_account","address":"0xAB00000000000000000000000000000000000001","balance":"0x1","nonce":0}
+{"op":"commit_block"}
+{"op":"define_code","id":"c","code":"0x00"}
+{"op":"checkpoint","label":"half"}
+{"op":"set_code","address":"0xab00000000000000000000000000000000000001","code":"0x00","tenant":1}
+{"op":"compute_root"}
+`
+	if out.String() != want {
+		t.Errorf("output:\n%s\nwant:\n%s", out.String(), want)
+	}
+
+	if summary.Operations != 5 ||
+		!slices.Equal(summary.Addresses, []string{"0xab00000000000000000000000000000000000001"}) {
+		t.Errorf("summary = %+v", summary)
+	}
+}
+
+func TestFilterRewritesTrailer(t *testing.T) {
+	input := strings.Join([]string{
+		`{"op":"create_account","address":"0xab00000000000000000000000000000000000001","balance":"0x1","nonce":0}`,
+		`{"op":"create_account","address":"0xcd00000000000000000000000000000000000002","balance":"0x1","nonce":0}`,
+		`{"op":"commit_block"}`,
+		`{"op":"trailer","counts":{"commit_block":1,"create_account":2},"lines":3}`,
+		`{"op":"compute_root"}`,
+	}, "\n")
+
+	var out bytes.Buffer
+	if _, err := Filter(strings.NewReader(input), &out, Selector{Prefix: "ab"}); err != nil {
+		t.Fatalf("filter: %v", err)
+	}
+
+	lines := strings.Split(strings.TrimSpace(out.String()), "\n")
+	want := `{"op":"trailer","counts":{"commit_block":1,"create_account":1},"lines":2}`
+	if len(lines) != 4 || lines[2] != want {
+		t.Errorf("output:\n%s\nwant trailer %s before compute_root", out.String(), want)
+	}
+
+	if check, err := VerifyTrailer(&out); err != nil || !check.Found || !check.Match() {
+		t.Errorf("filtered trailer check: found %v, deltas %q, error %v", check.Found, check, err)
+	}
+}
+
+func TestFilterRejectsDerivedAccounts(t *testing.T) {
+	input := `{"op":"create_accounts","seed":"0x5eed","count":3}` + "\n" + `{"op":"compute_root"}`
+
+	var out bytes.Buffer
+
+	_, err := Filter(strings.NewReader(input), &out, Selector{Prefix: "ab"})
+	if err == nil || !strings.Contains(err.Error(), "line 1: create_accounts") {
+		t.Errorf("err = %v, want create_accounts rejected", err)
+	}
+}
+
+func TestFilterRejectsTransfers(t *testing.T) {
+	input := `{"op":"create_account","address":"0xab01","balance":"0x10","nonce":0}` + "\n" +
+		`{"op":"transfer","from":"0xab01","to":"0xcd02","value":"0x1"}` + "\n" +
+		`{"op":"compute_root"}`
+
+	for _, s := range []Selector{{Prefix: "ab"}, {Modulus: 2, Bucket: 0}, {Modulus: 2, Bucket: 1}} {
+		var out bytes.Buffer
+
+		_, err := Filter(strings.NewReader(input), &out, s)
+		if err == nil || !strings.Contains(err.Error(), "line 2: transfer") {
+			t.Errorf("%+v: err = %v, want transfer rejected", s, err)
+		}
+	}
+}
+
+func TestSelectorSplitPartitions(t *testing.T) {
+	s := Selector{Modulus: 3, Bucket: 1}
+
+	lo, hi, ok := s.Split()
+	if !ok {
+		t.Fatal("split failed")
+	}
+
+	for i := range 200 {
+		address := fmt.Sprintf("0x%040x", i)
+		if s.Match(address) != (lo.Match(address) != hi.Match(address)) {
+			t.Errorf("%s: halves do not partition the selection", address)
+		}
+	}
+
+	if err := (Selector{Prefix: "0xab"}).Validate(); err == nil {
+		t.Error("prefix with 0x accepted")
+	}
+
+	if err := (Selector{Modulus: 2, Bucket: 2}).Validate(); err == nil {
+		t.Error("bucket beyond modulus accepted")
+	}
+}
+
+func TestBisectNarrowsToDivergingAccount(t *testing.T) {
+	universe := make([]string, 100)
+	for i := range universe {
+		universe[i] = fmt.Sprintf("0x%040x", i)
+	}
+
+	culprit := universe[37]
+	probe := func(s Selector) (int, bool, error) {
+		accounts := 0
+
+		for _, address := range universe {
+			if s.Match(address) {
+				accounts++
+			}
+		}
+
+		return accounts, s.Match(culprit), nil
+	}
+
+	b, err := Bisect(Selector{}, 1, probe)
+	if err != nil {
+		t.Fatalf("bisect: %v", err)
+	}
+
+	if b.Accounts != 1 || !b.Selector.Match(culprit) {
+		t.Errorf("bisection = %+v, want only %s", b, culprit)
+	}
+
+	agree := func(Selector) (int, bool, error) { return len(universe), false, nil }
+	if _, err := Bisect(Selector{}, 1, agree); !errors.Is(err, ErrNoDivergence) {
+		t.Errorf("err = %v, want ErrNoDivergence", err)
+	}
+}
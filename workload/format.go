@@ -0,0 +1,57 @@
+package workload
+
+import (
+	"bytes"
+	"errors"
+	"io"
+)
+
+// CBORMagic starts a workload in the binary format: the operations as a
+// CBOR sequence after the self-describe tag. The harnesses that list
+// "cbor" in their capabilities read it in place of JSONL.
+var CBORMagic = []byte{0xd9, 0xd9, 0xf7}
+
+// GzipMagic and ZstdMagic start compressed workloads, which harnesses
+// listing the codec in their capabilities decompress themselves.
+var (
+	GzipMagic = []byte{0x1f, 0x8b}
+	ZstdMagic = []byte{0x28, 0xb5, 0x2f, 0xfd}
+)
+
+// Compression names the codec of the workload read from r, "gzip" or
+// "zstd", or returns "" when it is not compressed.
+func Compression(r io.Reader) (string, error) {
+	head := make([]byte, len(ZstdMagic))
+
+	n, err := io.ReadFull(r, head)
+	if err != nil && !errors.Is(err, io.EOF) && !errors.Is(err, io.ErrUnexpectedEOF) {
+		return "", err
+	}
+
+	head = head[:n]
+
+	switch {
+	case bytes.HasPrefix(head, GzipMagic):
+		return "gzip", nil
+	case bytes.HasPrefix(head, ZstdMagic):
+		return "zstd", nil
+	default:
+		return "", nil
+	}
+}
+
+// IsCBOR reports whether the workload read from r starts with CBORMagic.
+func IsCBOR(r io.Reader) (bool, error) {
+	head := make([]byte, len(CBORMagic))
+
+	_, err := io.ReadFull(r, head)
+	if errors.Is(err, io.EOF) || errors.Is(err, io.ErrUnexpectedEOF) {
+		return false, nil
+	}
+
+	if err != nil {
+		return false, err
+	}
+
+	return bytes.Equal(head, CBORMagic), nil
+}
@@ -0,0 +1,135 @@
+package workload
+
+import (
+	"fmt"
+	"strconv"
+	"strings"
+)
+
+// GasCosts is a rough gas-equivalent price list for workload
+// operations, used to relate a generated block to a chain's gas limit.
+// It is not an EVM gas schedule: an operation stands for the cheapest
+// transaction that would cause the same state change, and anything
+// without a state change costs nothing.
+type GasCosts struct {
+	// AccountTouch prices create_account, update_balance, transfer,
+	// increment_nonce and touch_account: the 21000 base cost of the
+	// transaction behind each. A transfer is one transaction, not two
+	// touches.
+	AccountTouch uint64
+	// StorageSet prices a slot's first write in a block (SSTORE from
+	// zero), StorageUpdate a rewrite of a slot the block already wrote.
+	StorageSet    uint64
+	StorageUpdate uint64
+	// CodeByte prices each byte of set_code (the code deposit cost).
+	CodeByte uint64
+	// AccountDelete prices delete_account and selfdestruct.
+	AccountDelete uint64
+}
+
+// DefaultGasCosts returns the mainnet-derived prices.
+func DefaultGasCosts() GasCosts {
+	return GasCosts{
+		AccountTouch:  21000,
+		StorageSet:    20000,
+		StorageUpdate: 5000,
+		CodeByte:      200,
+		AccountDelete: 5000,
+	}
+}
+
+// gasCostNames maps the keys of ParseGasCosts to their fields.
+var gasCostNames = map[string]func(*GasCosts) *uint64{
+	"account_touch":  func(c *GasCosts) *uint64 { return &c.AccountTouch },
+	"storage_set":    func(c *GasCosts) *uint64 { return &c.StorageSet },
+	"storage_update": func(c *GasCosts) *uint64 { return &c.StorageUpdate },
+	"code_byte":      func(c *GasCosts) *uint64 { return &c.CodeByte },
+	"account_delete": func(c *GasCosts) *uint64 { return &c.AccountDelete },
+}
+
+// ParseGasCosts reads a comma-separated list of name=gas overrides, such
+// as "storage_set=22100,code_byte=0", on top of DefaultGasCosts. An
+// empty spec is the defaults.
+func ParseGasCosts(spec string) (GasCosts, error) {
+	costs := DefaultGasCosts()
+
+	for _, entry := range strings.Split(spec, ",") {
+		entry = strings.TrimSpace(entry)
+		if entry == "" {
+			continue
+		}
+
+		name, value, ok := strings.Cut(entry, "=")
+		if !ok {
+			return costs, fmt.Errorf("gas cost %q is not name=gas", entry)
+		}
+
+		field, known := gasCostNames[strings.TrimSpace(name)]
+		if !known {
+			return costs, fmt.Errorf("unknown gas cost %q", name)
+		}
+
+		gas, err := strconv.ParseUint(strings.TrimSpace(value), 10, 64)
+		if err != nil {
+			return costs, fmt.Errorf("gas cost %s: %w", name, err)
+		}
+
+		*field(&costs) = gas
+	}
+
+	return costs, nil
+}
+
+// GasMeter sums the cost of one block's operations. It remembers the
+// slots the block wrote, so memory grows with the block rather than the
+// workload; as a consequence, a slot written in an earlier block costs
+// StorageSet again.
+type GasMeter struct {
+	costs   GasCosts
+	gas     uint64
+	written map[string]struct{}
+}
+
+// NewGasMeter returns a meter pricing operations with costs.
+func NewGasMeter(costs GasCosts) *GasMeter {
+	return &GasMeter{costs: costs, written: make(map[string]struct{})}
+}
+
+// Add prices op into the current block.
+func (m *GasMeter) Add(op Operation) {
+	switch op.Op {
+	case "create_account", "update_balance", "transfer", "increment_nonce", "touch_account":
+		m.gas += m.costs.AccountTouch
+	case "set_code":
+		m.gas += m.costs.CodeByte * uint64(len(strings.TrimPrefix(op.Code, "0x"))/2)
+	case "set_storage":
+		m.addStorageWrite(op.Address, op.Slot)
+	case "set_storage_batch":
+		for _, entry := range op.Entries {
+			m.addStorageWrite(op.Address, entry[0])
+		}
+	case "delete_account", "selfdestruct":
+		m.gas += m.costs.AccountDelete
+	}
+}
+
+// addStorageWrite prices one slot write, a set the first time the block
+// writes the slot and an update after that.
+func (m *GasMeter) addStorageWrite(address, slot string) {
+	key := strings.ToLower(address + slot)
+	if _, again := m.written[key]; again {
+		m.gas += m.costs.StorageUpdate
+	} else {
+		m.written[key] = struct{}{}
+		m.gas += m.costs.StorageSet
+	}
+}
+
+// EndBlock returns the block's gas and starts the next block.
+func (m *GasMeter) EndBlock() uint64 {
+	gas := m.gas
+	m.gas = 0
+	clear(m.written)
+
+	return gas
+}
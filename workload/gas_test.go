@@ -0,0 +1,57 @@
+package workload
+
+import "testing"
+
+func TestGasMeterPricesABlock(t *testing.T) {
+	m := NewGasMeter(DefaultGasCosts())
+
+	for _, op := range []Operation{
+		{Op: "create_account", Address: "0x01"},
+		{Op: "set_code", Address: "0x01", Code: "0x600160005500"},
+		{Op: "set_storage", Address: "0x01", Slot: "0x0a", Value: "0x01"},
+		{Op: "set_storage", Address: "0x01", Slot: "0x0A", Value: "0x02"},
+		{Op: "set_storage", Address: "0x02", Slot: "0x0a", Value: "0x01"},
+		{Op: "set_storage_batch", Address: "0x02", Entries: [][2]string{{"0x0a", "0x02"}, {"0x0b", "0x01"}}},
+		{Op: "read_account", Address: "0x01"},
+	} {
+		m.Add(op)
+	}
+
+	// 21000 + 6 code bytes at 200 + three sets + two updates; reads are
+	// free.
+	if got, want := m.EndBlock(), uint64(21000+1200+20000+5000+20000+5000+20000); got != want {
+		t.Errorf("block gas = %d, want %d", got, want)
+	}
+
+	// A new block starts from zero and forgets the written slots.
+	m.Add(Operation{Op: "set_storage", Address: "0x01", Slot: "0x0a", Value: "0x03"})
+	m.Add(Operation{Op: "selfdestruct", Address: "0x01"})
+
+	if got := m.EndBlock(); got != 25000 {
+		t.Errorf("second block gas = %d, want 25000", got)
+	}
+}
+
+func TestParseGasCosts(t *testing.T) {
+	costs, err := ParseGasCosts(" storage_set=22100, code_byte=0 ")
+	if err != nil {
+		t.Fatalf("parse: %v", err)
+	}
+
+	want := DefaultGasCosts()
+	want.StorageSet, want.CodeByte = 22100, 0
+
+	if costs != want {
+		t.Errorf("costs = %+v, want %+v", costs, want)
+	}
+
+	if empty, err := ParseGasCosts(""); err != nil || empty != DefaultGasCosts() {
+		t.Errorf("empty spec = %+v, %v; want the defaults", empty, err)
+	}
+
+	for _, bad := range []string{"storage_set", "sstore=1", "code_byte=-1"} {
+		if _, err := ParseGasCosts(bad); err == nil {
+			t.Errorf("%q parsed, want an error", bad)
+		}
+	}
+}
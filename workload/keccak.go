@@ -0,0 +1,93 @@
+package workload
+
+import (
+	"encoding/binary"
+	"math/bits"
+)
+
+// keccakRate is the bytes of input Keccak-256 absorbs per permutation.
+const keccakRate = 136
+
+var keccakRoundConstants = [24]uint64{
+	0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+	0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+	0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+	0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+	0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+	0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+}
+
+// keccakRotations are the rho offsets of each lane, indexed x+5y.
+var keccakRotations = [25]int{
+	0, 1, 62, 28, 27,
+	36, 44, 6, 55, 20,
+	3, 10, 43, 25, 39,
+	41, 45, 15, 21, 8,
+	18, 2, 61, 56, 14,
+}
+
+// keccak256 returns the Keccak-256 hash the tries key accounts and slots
+// by. It is the original Keccak padding, not SHA3-256's, and is written
+// out here so the generator needs no dependency for it.
+func keccak256(data []byte) [32]byte {
+	var state [25]uint64
+
+	for len(data) >= keccakRate {
+		keccakAbsorb(&state, data[:keccakRate])
+		data = data[keccakRate:]
+	}
+
+	var last [keccakRate]byte
+	copy(last[:], data)
+	last[len(data)] ^= 0x01
+	last[keccakRate-1] ^= 0x80
+	keccakAbsorb(&state, last[:])
+
+	var sum [32]byte
+	for i := range 4 {
+		binary.LittleEndian.PutUint64(sum[i*8:], state[i])
+	}
+
+	return sum
+}
+
+// keccakAbsorb folds one block into the state and permutes it.
+func keccakAbsorb(state *[25]uint64, block []byte) {
+	for i := range keccakRate / 8 {
+		state[i] ^= binary.LittleEndian.Uint64(block[i*8:])
+	}
+
+	keccakF1600(state)
+}
+
+func keccakF1600(a *[25]uint64) {
+	var b [25]uint64
+
+	for round := range 24 {
+		// Theta.
+		var c [5]uint64
+		for x := range 5 {
+			c[x] = a[x] ^ a[x+5] ^ a[x+10] ^ a[x+15] ^ a[x+20]
+		}
+
+		for i := range 25 {
+			a[i] ^= c[(i+4)%5] ^ bits.RotateLeft64(c[(i+1)%5], 1)
+		}
+
+		// Rho and pi.
+		for x := range 5 {
+			for y := range 5 {
+				b[y+5*((2*x+3*y)%5)] = bits.RotateLeft64(a[x+5*y], keccakRotations[x+5*y])
+			}
+		}
+
+		// Chi and iota.
+		for y := range 5 {
+			for x := range 5 {
+				a[x+5*y] = b[x+5*y] ^ (^b[(x+1)%5+5*y] & b[(x+2)%5+5*y])
+			}
+		}
+
+		a[0] ^= keccakRoundConstants[round]
+	}
+}
@@ -0,0 +1,383 @@
+package workload
+
+import (
+	"bufio"
+	"bytes"
+	"cmp"
+	"container/heap"
+	"encoding/binary"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+)
+
+// DefaultSortBufferBytes is how much of a sorted workload Generate holds
+// in memory before it spills a sorted run to disk.
+const DefaultSortBufferBytes = 256 << 20
+
+// sortKeyLen is the length of a sort key: the keccak of the address, a
+// rank putting an account's create_account before its set_code and both
+// before its storage, and the keccak of the slot.
+const sortKeyLen = 32 + 1 + 32
+
+// sortRecordOverhead approximates what a buffered record takes in memory
+// besides its line, so the buffer bound holds for short lines too.
+const sortRecordOverhead = 128
+
+// maxMergeRuns caps the runs merged at once, and so the files open at
+// once. More runs are first merged into longer ones.
+const maxMergeRuns = 128
+
+// runReadBuffer is the read buffer of each run being merged.
+const runReadBuffer = 64 << 10
+
+// sortRecord is an operation waiting for its place in hashed-key order.
+// seq is its place in generation order, which breaks ties.
+type sortRecord struct {
+	key  [sortKeyLen]byte
+	seq  uint64
+	line []byte
+}
+
+func compareRecords(a, b *sortRecord) int {
+	if c := bytes.Compare(a.key[:], b.key[:]); c != 0 {
+		return c
+	}
+
+	return cmp.Compare(a.seq, b.seq)
+}
+
+// hashedKeySorter reorders the operations of Generate by the keys the
+// tries store them under. It buffers up to limit bytes of operations,
+// then sorts them and spills them as a run to a temporary directory;
+// Drain merges the runs back into one sorted stream. Memory stays near
+// limit, plus a read buffer per run while merging.
+type hashedKeySorter struct {
+	limit    int
+	buffered int
+	seq      uint64
+	records  []sortRecord
+	// dir holds the spilled runs. It is made on the first spill.
+	dir  string
+	runs []string
+	// made numbers the run files; spills counts the times the buffer
+	// was written out as one.
+	made   int
+	spills int
+}
+
+func newHashedKeySorter(limit int) *hashedKeySorter {
+	if limit == 0 {
+		limit = DefaultSortBufferBytes
+	}
+
+	return &hashedKeySorter{limit: limit}
+}
+
+// Encode buffers op, spilling the buffer once it reaches the limit.
+func (s *hashedKeySorter) Encode(op Operation) error {
+	line, err := json.Marshal(op)
+	if err != nil {
+		return err
+	}
+
+	s.records = append(s.records, sortRecord{key: sortKey(op), seq: s.seq, line: line})
+	s.seq++
+	s.buffered += len(line) + sortRecordOverhead
+
+	if s.buffered >= s.limit {
+		return s.spill()
+	}
+
+	return nil
+}
+
+// Drain passes every buffered operation to emit, in hashed-key order.
+func (s *hashedKeySorter) Drain(emit func(Operation) error) error {
+	decode := func(rec *sortRecord) error {
+		var op Operation
+		if err := json.Unmarshal(rec.line, &op); err != nil {
+			return fmt.Errorf("decode sorted operation: %w", err)
+		}
+
+		return emit(op)
+	}
+
+	if len(s.runs) == 0 {
+		slices.SortFunc(s.records, func(a, b sortRecord) int { return compareRecords(&a, &b) })
+
+		for i := range s.records {
+			if err := decode(&s.records[i]); err != nil {
+				return err
+			}
+		}
+
+		s.records = nil
+
+		return nil
+	}
+
+	if len(s.records) > 0 {
+		if err := s.spill(); err != nil {
+			return err
+		}
+	}
+
+	for len(s.runs) > maxMergeRuns {
+		if err := s.mergeRuns(maxMergeRuns); err != nil {
+			return err
+		}
+	}
+
+	return mergeRunFiles(s.runs, decode)
+}
+
+// Close removes the spilled runs.
+func (s *hashedKeySorter) Close() error {
+	if s.dir == "" {
+		return nil
+	}
+
+	return os.RemoveAll(s.dir)
+}
+
+// spill writes the buffered records, sorted, as a new run.
+func (s *hashedKeySorter) spill() error {
+	slices.SortFunc(s.records, func(a, b sortRecord) int { return compareRecords(&a, &b) })
+
+	err := s.writeRun(func(w *bufio.Writer) error {
+		for i := range s.records {
+			if err := writeRecord(w, &s.records[i]); err != nil {
+				return err
+			}
+		}
+
+		return nil
+	})
+	if err != nil {
+		return err
+	}
+
+	clear(s.records)
+	s.records = s.records[:0]
+	s.buffered = 0
+	s.spills++
+
+	return nil
+}
+
+// mergeRuns replaces the first n runs with one run merging them.
+func (s *hashedKeySorter) mergeRuns(n int) error {
+	merged := s.runs[:n]
+
+	err := s.writeRun(func(w *bufio.Writer) error {
+		return mergeRunFiles(merged, func(rec *sortRecord) error {
+			return writeRecord(w, rec)
+		})
+	})
+	if err != nil {
+		return err
+	}
+
+	for _, path := range merged {
+		os.Remove(path)
+	}
+
+	// writeRun appended the merged run after the others.
+	s.runs = s.runs[n:]
+
+	return nil
+}
+
+// writeRun creates the next run file, fills it with write and records it.
+func (s *hashedKeySorter) writeRun(write func(*bufio.Writer) error) error {
+	if s.dir == "" {
+		dir, err := os.MkdirTemp("", "statoor-sort-")
+		if err != nil {
+			return fmt.Errorf("create sort dir: %w", err)
+		}
+
+		s.dir = dir
+	}
+
+	path := filepath.Join(s.dir, fmt.Sprintf("run-%06d", s.made))
+	s.made++
+
+	f, err := os.Create(path)
+	if err != nil {
+		return fmt.Errorf("create sort run: %w", err)
+	}
+
+	w := bufio.NewWriter(f)
+	if err := write(w); err != nil {
+		f.Close()
+
+		return fmt.Errorf("write sort run %s: %w", path, err)
+	}
+
+	if err := w.Flush(); err != nil {
+		f.Close()
+
+		return fmt.Errorf("write sort run %s: %w", path, err)
+	}
+
+	if err := f.Close(); err != nil {
+		return fmt.Errorf("close sort run %s: %w", path, err)
+	}
+
+	s.runs = append(s.runs, path)
+
+	return nil
+}
+
+// writeRecord writes rec as it is stored in a run: the key, seq
+// big-endian, the line's length as a uvarint, then the line.
+func writeRecord(w *bufio.Writer, rec *sortRecord) error {
+	var head [sortKeyLen + 8 + binary.MaxVarintLen64]byte
+
+	n := copy(head[:], rec.key[:])
+	binary.BigEndian.PutUint64(head[n:], rec.seq)
+	n += 8
+	n += binary.PutUvarint(head[n:], uint64(len(rec.line)))
+
+	if _, err := w.Write(head[:n]); err != nil {
+		return err
+	}
+
+	_, err := w.Write(rec.line)
+
+	return err
+}
+
+// runReader reads back the records of one run, one at a time.
+type runReader struct {
+	r   *bufio.Reader
+	rec sortRecord
+}
+
+// next reads the following record into rr.rec, reporting false at the
+// end of the run.
+func (rr *runReader) next() (bool, error) {
+	if _, err := io.ReadFull(rr.r, rr.rec.key[:]); err != nil {
+		if errors.Is(err, io.EOF) {
+			return false, nil
+		}
+
+		return false, err
+	}
+
+	var seq [8]byte
+	if _, err := io.ReadFull(rr.r, seq[:]); err != nil {
+		return false, err
+	}
+
+	rr.rec.seq = binary.BigEndian.Uint64(seq[:])
+
+	size, err := binary.ReadUvarint(rr.r)
+	if err != nil {
+		return false, err
+	}
+
+	rr.rec.line = slices.Grow(rr.rec.line[:0], int(size))[:size]
+	if _, err := io.ReadFull(rr.r, rr.rec.line); err != nil {
+		return false, err
+	}
+
+	return true, nil
+}
+
+// runHeap orders runs by their next record.
+type runHeap []*runReader
+
+func (h runHeap) Len() int           { return len(h) }
+func (h runHeap) Less(i, j int) bool { return compareRecords(&h[i].rec, &h[j].rec) < 0 }
+func (h runHeap) Swap(i, j int)      { h[i], h[j] = h[j], h[i] }
+func (h *runHeap) Push(x any)        { *h = append(*h, x.(*runReader)) }
+
+func (h *runHeap) Pop() any {
+	old := *h
+	last := old[len(old)-1]
+	*h = old[:len(old)-1]
+
+	return last
+}
+
+// mergeRunFiles passes the records of the runs at paths to emit in
+// order. The record is only valid during the call.
+func mergeRunFiles(paths []string, emit func(*sortRecord) error) error {
+	h := make(runHeap, 0, len(paths))
+
+	for _, path := range paths {
+		f, err := os.Open(path)
+		if err != nil {
+			return fmt.Errorf("open sort run: %w", err)
+		}
+		defer f.Close()
+
+		rr := &runReader{r: bufio.NewReaderSize(f, runReadBuffer)}
+
+		ok, err := rr.next()
+		if err != nil {
+			return fmt.Errorf("read sort run %s: %w", path, err)
+		}
+
+		if ok {
+			h = append(h, rr)
+		}
+	}
+
+	heap.Init(&h)
+
+	for h.Len() > 0 {
+		top := h[0]
+		if err := emit(&top.rec); err != nil {
+			return err
+		}
+
+		ok, err := top.next()
+		if err != nil {
+			return fmt.Errorf("read sort run: %w", err)
+		}
+
+		if ok {
+			heap.Fix(&h, 0)
+		} else {
+			heap.Pop(&h)
+		}
+	}
+
+	return nil
+}
+
+// sortKey returns the key op is ordered by. Every operation Generate
+// emits touches one account, and only set_storage a slot.
+func sortKey(op Operation) [sortKeyLen]byte {
+	var key [sortKeyLen]byte
+
+	account := keccak256(hexBytes(op.Address))
+	copy(key[:32], account[:])
+
+	switch op.Op {
+	case "set_code":
+		key[32] = 1
+	case "set_storage":
+		key[32] = 2
+		slot := keccak256(hexBytes(op.Slot))
+		copy(key[33:], slot[:])
+	}
+
+	return key
+}
+
+// hexBytes decodes a 0x-prefixed hex string the generator wrote.
+func hexBytes(s string) []byte {
+	raw, _ := hex.DecodeString(strings.TrimPrefix(s, "0x"))
+
+	return raw
+}
@@ -0,0 +1,117 @@
+package workload
+
+import (
+	"bytes"
+	"encoding/hex"
+	"encoding/json"
+	"slices"
+	"strings"
+	"testing"
+)
+
+func TestKeccak256(t *testing.T) {
+	// The last input takes more than one block.
+	tests := []struct {
+		input string
+		want  string
+	}{
+		{"", "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"},
+		{"abc", "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"},
+		{strings.Repeat("a", 200), "96ea54061def936c4be90b518992fdc6f12f535068a256229aca54267b4d084d"},
+	}
+
+	for _, tt := range tests {
+		sum := keccak256([]byte(tt.input))
+		if got := hex.EncodeToString(sum[:]); got != tt.want {
+			t.Errorf("keccak256(%d bytes) = %s, want %s", len(tt.input), got, tt.want)
+		}
+	}
+}
+
+func sortedConfig(bufferBytes int) Config {
+	return Config{
+		NumAccounts:       150,
+		NumContracts:      6,
+		MaxSlots:          8,
+		MinSlots:          2,
+		Distribution:      "uniform",
+		Seed:              5,
+		CodeSize:          16,
+		SortedByHashedKey: true,
+		SortBufferBytes:   bufferBytes,
+	}
+}
+
+func TestGenerateSortedByHashedKey(t *testing.T) {
+	var plain, sorted bytes.Buffer
+
+	cfg := sortedConfig(0)
+	cfg.SortedByHashedKey = false
+
+	plainSummary, err := NewGenerator(cfg).Generate(&plain)
+	if err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	sortedSummary, err := NewGenerator(sortedConfig(0)).Generate(&sorted)
+	if err != nil {
+		t.Fatalf("sorted Generate failed: %v", err)
+	}
+
+	if sortedSummary != plainSummary {
+		t.Errorf("sorted summary = %+v, want %+v", sortedSummary, plainSummary)
+	}
+
+	plainLines := strings.Split(strings.TrimSpace(plain.String()), "\n")
+	sortedLines := strings.Split(strings.TrimSpace(sorted.String()), "\n")
+
+	if slices.Equal(plainLines, sortedLines) {
+		t.Fatal("sorting left the generation order")
+	}
+
+	var prev [sortKeyLen]byte
+
+	for i, line := range sortedLines[:len(sortedLines)-1] {
+		var op Operation
+		if err := json.Unmarshal([]byte(line), &op); err != nil {
+			t.Fatalf("line %d: %v", i+1, err)
+		}
+
+		key := sortKey(op)
+		if bytes.Compare(key[:], prev[:]) < 0 {
+			t.Fatalf("line %d is out of hashed-key order: %s", i+1, line)
+		}
+
+		prev = key
+	}
+
+	slices.Sort(plainLines)
+	slices.Sort(sortedLines)
+
+	if !slices.Equal(plainLines, sortedLines) {
+		t.Error("sorting changed the operations, not only their order")
+	}
+}
+
+func TestGenerateSortedSpillsToDisk(t *testing.T) {
+	var inMemory, spilled bytes.Buffer
+
+	if _, err := NewGenerator(sortedConfig(0)).Generate(&inMemory); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	// A one-byte buffer spills every operation as its own run, more
+	// than are merged at once.
+	summary, err := NewGenerator(sortedConfig(1)).Generate(&spilled)
+	if err != nil {
+		t.Fatalf("spilled Generate failed: %v", err)
+	}
+
+	if summary.SortRuns <= maxMergeRuns {
+		t.Errorf("sort runs = %d, want more than %d", summary.SortRuns, maxMergeRuns)
+	}
+
+	if spilled.String() != inMemory.String() {
+		t.Error("a spilled sort wrote another order than an in-memory one")
+	}
+}
@@ -0,0 +1,139 @@
+package workload
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"maps"
+	"slices"
+	"strings"
+)
+
+// trailerOp is the op of the line that declares a workload's counts.
+const trailerOp = "trailer"
+
+// Tally counts the lines of a workload by operation, for its trailer.
+type Tally struct {
+	Counts map[string]int
+	Lines  int
+}
+
+// Add counts one line of op. Trailer lines are not counted, since a
+// trailer declares only the lines before it.
+func (t *Tally) Add(op string) {
+	if op == trailerOp {
+		return
+	}
+
+	if t.Counts == nil {
+		t.Counts = make(map[string]int)
+	}
+
+	t.Counts[op]++
+	t.Lines++
+}
+
+// Trailer returns the trailer line declaring the lines added so far.
+func (t *Tally) Trailer() Operation {
+	lines := t.Lines
+
+	return Operation{Op: trailerOp, Counts: maps.Clone(t.Counts), Lines: &lines}
+}
+
+// Check compares the lines added so far with a trailer declaring counts
+// per operation and lines in all.
+func (t *Tally) Check(counts map[string]int, lines int) TrailerCheck {
+	check := TrailerCheck{Found: true, Deltas: make(map[string]int), LinesDelta: t.Lines - lines}
+
+	for op, seen := range t.Counts {
+		if delta := seen - counts[op]; delta != 0 {
+			check.Deltas[op] = delta
+		}
+	}
+
+	for op, declared := range counts {
+		if _, seen := t.Counts[op]; !seen && declared != 0 {
+			check.Deltas[op] = -declared
+		}
+	}
+
+	return check
+}
+
+// TrailerCheck is the outcome of checking a workload's trailer.
+type TrailerCheck struct {
+	// Found is set when the workload has a trailer line.
+	Found bool
+	// Deltas holds lines read minus lines declared, for the operations
+	// where they differ, and LinesDelta the same for the line total.
+	Deltas     map[string]int
+	LinesDelta int
+}
+
+// Match reports whether the trailer agrees with the lines before it.
+func (c TrailerCheck) Match() bool {
+	return len(c.Deltas) == 0 && c.LinesDelta == 0
+}
+
+// String lists the deltas, such as "set_storage -3, lines -3".
+func (c TrailerCheck) String() string {
+	ops := make([]string, 0, len(c.Deltas))
+	for op := range c.Deltas {
+		ops = append(ops, op)
+	}
+
+	slices.Sort(ops)
+
+	deltas := make([]string, 0, len(ops)+1)
+	for _, op := range ops {
+		deltas = append(deltas, fmt.Sprintf("%s %+d", op, c.Deltas[op]))
+	}
+
+	if c.LinesDelta != 0 {
+		deltas = append(deltas, fmt.Sprintf("lines %+d", c.LinesDelta))
+	}
+
+	return strings.Join(deltas, ", ")
+}
+
+// VerifyTrailer reads a JSONL workload from r and checks its trailer
+// against the lines before it. With several trailers, each is checked
+// and the first that disagrees is returned, or else the last. Lines are
+// decoded one at a time, so memory use does not grow with the workload.
+func VerifyTrailer(r io.Reader) (TrailerCheck, error) {
+	dec := json.NewDecoder(r)
+
+	var (
+		tally Tally
+		check TrailerCheck
+	)
+
+	for line := 1; ; line++ {
+		var op struct {
+			Op     string         `json:"op"`
+			Counts map[string]int `json:"counts"`
+			Lines  *int           `json:"lines"`
+		}
+
+		err := dec.Decode(&op)
+		if err == io.EOF {
+			return check, nil
+		}
+
+		if err != nil {
+			return check, fmt.Errorf("decode operation %d: %w", line, err)
+		}
+
+		if op.Op == trailerOp {
+			if op.Lines == nil {
+				return check, fmt.Errorf("trailer at operation %d has no lines", line)
+			}
+
+			if check = tally.Check(op.Counts, *op.Lines); !check.Match() {
+				return check, nil
+			}
+		}
+
+		tally.Add(op.Op)
+	}
+}
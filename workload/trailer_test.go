@@ -0,0 +1,73 @@
+package workload
+
+import (
+	"bytes"
+	"strings"
+	"testing"
+)
+
+func TestGeneratedTrailerMatches(t *testing.T) {
+	gen := NewGenerator(Config{
+		NumAccounts:  4,
+		NumContracts: 2,
+		MaxSlots:     5,
+		MinSlots:     2,
+		Distribution: "uniform",
+		Seed:         11,
+		CodeSize:     16,
+		BlockOps:     3,
+		Trailer:      true,
+	})
+
+	var buf bytes.Buffer
+
+	summary, err := gen.Generate(&buf)
+	if err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	lines := strings.Split(strings.TrimSpace(buf.String()), "\n")
+	if !strings.HasPrefix(lines[len(lines)-2], `{"op":"trailer","counts":{"commit_block":`) {
+		t.Errorf("final-but-one line = %s, want the trailer", lines[len(lines)-2])
+	}
+
+	check, err := VerifyTrailer(&buf)
+	if err != nil {
+		t.Fatalf("VerifyTrailer failed: %v", err)
+	}
+
+	if !check.Found || !check.Match() {
+		t.Errorf("trailer check: found %v, deltas %q", check.Found, check)
+	}
+
+	// The trailer is not an operation; the final compute_root is.
+	if summary.TotalOperations != len(lines)-1 {
+		t.Errorf("total operations = %d, want %d", summary.TotalOperations, len(lines)-1)
+	}
+}
+
+func TestVerifyTrailerReportsLostLines(t *testing.T) {
+	input := `{"op":"create_account","address":"0x01"}
+{"op":"set_storage","address":"0x01","slot":"0x01","value":"0x01"}
+{"op":"trailer","counts":{"create_account":1,"set_storage":3,"set_code":1},"lines":5}
+{"op":"compute_root"}
+`
+
+	check, err := VerifyTrailer(strings.NewReader(input))
+	if err != nil {
+		t.Fatalf("VerifyTrailer failed: %v", err)
+	}
+
+	if check.Match() || check.String() != "set_code -1, set_storage -2, lines -3" {
+		t.Errorf("trailer check = %q, want the lost lines", check)
+	}
+
+	check, err = VerifyTrailer(strings.NewReader(`{"op":"compute_root"}`))
+	if err != nil || check.Found {
+		t.Errorf("workload without trailer: found %v, error %v", check.Found, err)
+	}
+
+	if _, err := VerifyTrailer(strings.NewReader(`{"op":"trailer","counts":{}}`)); err == nil {
+		t.Error("trailer without lines accepted")
+	}
+}
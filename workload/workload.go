@@ -1,15 +1,27 @@
 // Package workload generates deterministic JSONL workloads for Ethereum
 // state benchmarking. Each workload consists of create_account, set_code,
-// set_storage, and compute_root operations.
+// set_storage, and compute_root operations, optionally split into blocks
+// by commit_block and declared by a trailer before the final
+// compute_root.
+//
+// Operations are written in generation order, account by account, unless
+// SortedByHashedKey orders them by the keys the tries store them under.
+//
+// A workload may be split between tenants, independent applications
+// sharing one state. Each tenant owns a disjoint address range and every
+// operation on its accounts carries its index in the tenant field.
 package workload
 
 import (
+	"bytes"
 	"encoding/hex"
 	"encoding/json"
 	"fmt"
 	"io"
 	"math"
 	mrand "math/rand"
+	"slices"
+	"strings"
 )
 
 // Operation represents a single state operation in the workload.
@@ -21,14 +33,36 @@ type Operation struct {
 	Code    string `json:"code,omitempty"`
 	Slot    string `json:"slot,omitempty"`
 	Value   string `json:"value,omitempty"`
+	// Entries are the slot and value pairs of a set_storage_batch.
+	Entries [][2]string `json:"entries,omitempty"`
+	// ApproxGas is the estimated cost of a commit_block's block.
+	ApproxGas *uint64 `json:"approx_gas,omitempty"`
+	// Counts and Lines are what a trailer declares about the lines
+	// before it.
+	Counts map[string]int `json:"counts,omitempty"`
+	Lines  *int           `json:"lines,omitempty"`
+	Tenant *int           `json:"tenant,omitempty"`
 }
 
-// Summary contains statistics about the generated workload.
+// Summary contains statistics about the generated workload. Counters
+// follow the harness definitions, so they can be cross-checked against
+// harness results: ContractsCreated counts addresses receiving
+// non-empty code for the first time, CodeUpdates later set_code
+// operations on such an address, and EmptyCodeSets zero-length code.
 type Summary struct {
 	TotalOperations  int
 	AccountsCreated  int
 	ContractsCreated int
+	CodeUpdates      int
+	EmptyCodeSets    int
 	StorageSlots     int
+	// Blocks counts the commit_block lines, and ApproxGas sums their
+	// approx_gas.
+	Blocks    int
+	ApproxGas uint64
+	// SortRuns counts the sorted runs a SortedByHashedKey workload was
+	// spilled to disk in, zero when it was sorted in memory.
+	SortRuns int
 }
 
 // Config controls workload generation parameters.
@@ -40,40 +74,188 @@ type Config struct {
 	Distribution string
 	Seed         int64
 	CodeSize     int
+	// Tenants splits the accounts and contracts between this many
+	// tenants, dealt out in turn. Zero leaves operations untagged.
+	Tenants int
+	// BlockOps ends a block with commit_block after this many
+	// operations, and after the last one, stamping each with the
+	// block's approx_gas. Zero writes a single batch.
+	BlockOps int
+	// GasCosts prices the operations of a block; nil means
+	// DefaultGasCosts.
+	GasCosts *GasCosts
+	// Trailer writes a trailer line before the final compute_root,
+	// declaring how many lines of each operation precede it.
+	Trailer bool
+	// SortedByHashedKey writes the same operations ordered by
+	// keccak(address), then by keccak(slot) within an account, whose
+	// create_account and set_code come before its storage. Blocks are
+	// cut from the sorted stream.
+	SortedByHashedKey bool
+	// SortBufferBytes bounds the operations a sorted workload holds in
+	// memory; beyond it they are spilled as sorted runs to a temporary
+	// directory and merged back. Zero means DefaultSortBufferBytes.
+	SortBufferBytes int
 }
 
+// tenantPrefixLen is the number of leading address bytes that identify
+// a tenant's range.
+const tenantPrefixLen = 4
+
 // Generator produces deterministic workloads from a Config.
 type Generator struct {
-	cfg Config
-	rng *mrand.Rand
+	cfg      Config
+	rng      *mrand.Rand
+	prefixes [][tenantPrefixLen]byte
 }
 
 // NewGenerator creates a Generator from the given Config.
 func NewGenerator(cfg Config) *Generator {
-	return &Generator{
+	g := &Generator{
 		cfg: cfg,
 		rng: mrand.New(mrand.NewSource(cfg.Seed)),
 	}
+
+	// Distinct random prefixes keep the tenants' ranges disjoint. They
+	// are drawn only for tenanted workloads, so untagged output does not
+	// change.
+	seen := make(map[[tenantPrefixLen]byte]struct{}, max(cfg.Tenants, 0))
+	for len(g.prefixes) < cfg.Tenants {
+		var prefix [tenantPrefixLen]byte
+		g.rng.Read(prefix[:])
+
+		if _, dup := seen[prefix]; dup {
+			continue
+		}
+
+		seen[prefix] = struct{}{}
+		g.prefixes = append(g.prefixes, prefix)
+	}
+
+	return g
+}
+
+// TenantOf returns the tenant whose range contains address, or false
+// when the workload is untenanted or no tenant owns it.
+func (g *Generator) TenantOf(address string) (int, bool) {
+	raw, err := hex.DecodeString(strings.TrimPrefix(address, "0x"))
+	if err != nil || len(raw) < tenantPrefixLen {
+		return 0, false
+	}
+
+	for tenant, prefix := range g.prefixes {
+		if bytes.Equal(raw[:tenantPrefixLen], prefix[:]) {
+			return tenant, true
+		}
+	}
+
+	return 0, false
+}
+
+// Ops returns the operation names Generate emits, so callers can check
+// harness support without scanning the output.
+func (g *Generator) Ops() []string {
+	ops := []string{"create_account", "set_code", "set_storage"}
+	if g.cfg.BlockOps > 0 {
+		ops = append(ops, "commit_block")
+	}
+
+	if g.cfg.Trailer {
+		ops = append(ops, trailerOp)
+	}
+
+	return append(ops, "compute_root")
+}
+
+// ScanOps reads a JSONL workload from r and returns the sorted set of
+// operation names it contains. Lines are decoded one at a time, so
+// memory use does not grow with the workload size.
+func ScanOps(r io.Reader) ([]string, error) {
+	dec := json.NewDecoder(r)
+	seen := make(map[string]struct{})
+
+	for line := 1; ; line++ {
+		var op struct {
+			Op string `json:"op"`
+		}
+
+		err := dec.Decode(&op)
+		if err == io.EOF {
+			break
+		}
+
+		if err != nil {
+			return nil, fmt.Errorf("scan operation %d: %w", line, err)
+		}
+
+		seen[op.Op] = struct{}{}
+	}
+
+	ops := make([]string, 0, len(seen))
+	for op := range seen {
+		ops = append(ops, op)
+	}
+
+	slices.Sort(ops)
+
+	return ops, nil
 }
 
 // Generate writes a JSONL workload to w and returns a Summary.
 func (g *Generator) Generate(w io.Writer) (Summary, error) {
-	enc := json.NewEncoder(w)
-	enc.SetEscapeHTML(false)
-
 	var summary Summary
 
+	if g.cfg.Tenants < 0 {
+		return summary, fmt.Errorf("tenants must not be negative, got %d", g.cfg.Tenants)
+	}
+
+	if g.cfg.BlockOps < 0 {
+		return summary, fmt.Errorf("block ops must not be negative, got %d", g.cfg.BlockOps)
+	}
+
+	if g.cfg.SortBufferBytes < 0 {
+		return summary, fmt.Errorf("sort buffer must not be negative, got %d", g.cfg.SortBufferBytes)
+	}
+
+	costs := DefaultGasCosts()
+	if g.cfg.GasCosts != nil {
+		costs = *g.cfg.GasCosts
+	}
+
+	lines := json.NewEncoder(w)
+	lines.SetEscapeHTML(false)
+
+	enc := &blockWriter{
+		enc:     lines,
+		size:    g.cfg.BlockOps,
+		meter:   NewGasMeter(costs),
+		summary: &summary,
+	}
+
+	// A sorted workload collects every operation before it writes one.
+	var ops opEncoder = enc
+
+	var sorter *hashedKeySorter
+	if g.cfg.SortedByHashedKey {
+		sorter = newHashedKeySorter(g.cfg.SortBufferBytes)
+		defer sorter.Close()
+
+		ops = sorter
+	}
+
 	// Generate EOAs.
 	for i := 0; i < g.cfg.NumAccounts; i++ {
-		addr := g.randomAddress()
+		tenant := g.tenant(i)
+		addr := g.randomAddress(tenant)
 		balance := g.randomBalance(1, 100)
 		nonce := uint64(g.rng.Intn(100))
 
-		if err := enc.Encode(Operation{
+		if err := ops.Encode(Operation{
 			Op:      "create_account",
 			Address: addr,
 			Balance: balance,
 			Nonce:   nonce,
+			Tenant:  tenant,
 		}); err != nil {
 			return summary, fmt.Errorf("encode create_account: %w", err)
 		}
@@ -86,26 +268,29 @@ func (g *Generator) Generate(w io.Writer) (Summary, error) {
 	slotDist := g.slotDistribution()
 
 	for i := 0; i < g.cfg.NumContracts; i++ {
-		addr := g.randomAddress()
+		tenant := g.tenant(i)
+		addr := g.randomAddress(tenant)
 		balance := g.randomBalance(0, 100)
 		nonce := uint64(g.rng.Intn(100))
 		code := g.randomCode()
 
-		if err := enc.Encode(Operation{
+		if err := ops.Encode(Operation{
 			Op:      "create_account",
 			Address: addr,
 			Balance: balance,
 			Nonce:   nonce,
+			Tenant:  tenant,
 		}); err != nil {
 			return summary, fmt.Errorf("encode create_account: %w", err)
 		}
 
 		summary.TotalOperations++
 
-		if err := enc.Encode(Operation{
+		if err := ops.Encode(Operation{
 			Op:      "set_code",
 			Address: addr,
 			Code:    code,
+			Tenant:  tenant,
 		}); err != nil {
 			return summary, fmt.Errorf("encode set_code: %w", err)
 		}
@@ -117,11 +302,12 @@ func (g *Generator) Generate(w io.Writer) (Summary, error) {
 			slot := g.randomHash()
 			value := g.randomNonZeroHash()
 
-			if err := enc.Encode(Operation{
+			if err := ops.Encode(Operation{
 				Op:      "set_storage",
 				Address: addr,
 				Slot:    slot,
 				Value:   value,
+				Tenant:  tenant,
 			}); err != nil {
 				return summary, fmt.Errorf("encode set_storage: %w", err)
 			}
@@ -133,8 +319,26 @@ func (g *Generator) Generate(w io.Writer) (Summary, error) {
 		summary.ContractsCreated++
 	}
 
+	if sorter != nil {
+		if err := sorter.Drain(enc.Encode); err != nil {
+			return summary, fmt.Errorf("sort by hashed key: %w", err)
+		}
+
+		summary.SortRuns = sorter.spills
+	}
+
+	if err := enc.commit(); err != nil {
+		return summary, err
+	}
+
+	if g.cfg.Trailer {
+		if err := lines.Encode(enc.tally.Trailer()); err != nil {
+			return summary, fmt.Errorf("encode trailer: %w", err)
+		}
+	}
+
 	// Final compute_root operation.
-	if err := enc.Encode(Operation{Op: "compute_root"}); err != nil {
+	if err := lines.Encode(Operation{Op: "compute_root"}); err != nil {
 		return summary, fmt.Errorf("encode compute_root: %w", err)
 	}
 
@@ -143,9 +347,90 @@ func (g *Generator) Generate(w io.Writer) (Summary, error) {
 	return summary, nil
 }
 
-func (g *Generator) randomAddress() string {
+// opEncoder takes the operations of Generate in generation order.
+type opEncoder interface {
+	Encode(op Operation) error
+}
+
+// blockWriter encodes the operations of Generate. With a block size
+// set, it ends every block of that many operations with a commit_block
+// carrying the block's approx_gas. It tallies every line it writes, for
+// the trailer.
+type blockWriter struct {
+	enc     *json.Encoder
+	size    int
+	meter   *GasMeter
+	pending int
+	summary *Summary
+	tally   Tally
+}
+
+// Encode writes op, then ends the block if op filled it.
+func (b *blockWriter) Encode(op Operation) error {
+	if err := b.enc.Encode(op); err != nil {
+		return err
+	}
+
+	b.tally.Add(op.Op)
+
+	if b.size == 0 {
+		return nil
+	}
+
+	b.meter.Add(op)
+	b.pending++
+
+	if b.pending == b.size {
+		return b.commit()
+	}
+
+	return nil
+}
+
+// commit ends the current block, unless it is empty.
+func (b *blockWriter) commit() error {
+	if b.pending == 0 {
+		return nil
+	}
+
+	gas := b.meter.EndBlock()
+	b.pending = 0
+
+	if err := b.enc.Encode(Operation{Op: "commit_block", ApproxGas: &gas}); err != nil {
+		return fmt.Errorf("encode commit_block: %w", err)
+	}
+
+	b.tally.Add("commit_block")
+
+	b.summary.Blocks++
+	b.summary.ApproxGas += gas
+	b.summary.TotalOperations++
+
+	return nil
+}
+
+// tenant returns the tenant the i-th account or contract is dealt to,
+// or nil for an untenanted workload.
+func (g *Generator) tenant(i int) *int {
+	if len(g.prefixes) == 0 {
+		return nil
+	}
+
+	tenant := i % len(g.prefixes)
+
+	return &tenant
+}
+
+// randomAddress returns a random address, inside the tenant's range
+// when tenant is set.
+func (g *Generator) randomAddress(tenant *int) string {
 	var buf [20]byte
-	g.rng.Read(buf[:])
+	if tenant == nil {
+		g.rng.Read(buf[:])
+	} else {
+		copy(buf[:], g.prefixes[*tenant][:])
+		g.rng.Read(buf[tenantPrefixLen:])
+	}
 
 	return "0x" + hex.EncodeToString(buf[:])
 }
@@ -4,6 +4,7 @@ import (
 	"bufio"
 	"bytes"
 	"encoding/json"
+	"slices"
 	"strings"
 	"testing"
 )
@@ -240,3 +241,229 @@ func TestDistributions(t *testing.T) {
 		})
 	}
 }
+
+func TestScanOps(t *testing.T) {
+	input := `{"op":"set_storage","address":"0x01"}
+{"op":"create_account"}
+{"op":"set_storage"}
+{"op":"compute_root"}
+`
+	ops, err := ScanOps(strings.NewReader(input))
+	if err != nil {
+		t.Fatalf("ScanOps failed: %v", err)
+	}
+
+	want := []string{"compute_root", "create_account", "set_storage"}
+	if strings.Join(ops, ",") != strings.Join(want, ",") {
+		t.Errorf("ops = %v, want %v", ops, want)
+	}
+}
+
+func TestIsCBOR(t *testing.T) {
+	tests := []struct {
+		input string
+		want  bool
+	}{
+		{"\xd9\xd9\xf7\xa1bopkcompute_root", true},
+		{`{"op":"compute_root"}`, false},
+		{"\xd9", false},
+		{"", false},
+	}
+
+	for _, tt := range tests {
+		got, err := IsCBOR(strings.NewReader(tt.input))
+		if err != nil || got != tt.want {
+			t.Errorf("IsCBOR(%q) = %t, %v, want %t", tt.input, got, err, tt.want)
+		}
+	}
+}
+
+func TestCompression(t *testing.T) {
+	tests := []struct {
+		input string
+		want  string
+	}{
+		{"\x1f\x8b\x08\x00", "gzip"},
+		{"\x28\xb5\x2f\xfd\x00", "zstd"},
+		{`{"op":"compute_root"}`, ""},
+		{"\x1f", ""},
+	}
+
+	for _, tt := range tests {
+		got, err := Compression(strings.NewReader(tt.input))
+		if err != nil || got != tt.want {
+			t.Errorf("Compression(%q) = %q, %v, want %q", tt.input, got, err, tt.want)
+		}
+	}
+}
+
+func TestGeneratorOpsCoverOutput(t *testing.T) {
+	gen := NewGenerator(Config{
+		NumAccounts:  3,
+		NumContracts: 2,
+		MaxSlots:     4,
+		MinSlots:     1,
+		Distribution: "uniform",
+		Seed:         1,
+		CodeSize:     16,
+		BlockOps:     3,
+		Trailer:      true,
+	})
+
+	var buf bytes.Buffer
+	if _, err := gen.Generate(&buf); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	ops, err := ScanOps(&buf)
+	if err != nil {
+		t.Fatalf("ScanOps failed: %v", err)
+	}
+
+	for _, op := range ops {
+		if !slices.Contains(gen.Ops(), op) {
+			t.Errorf("generated op %q not declared by Ops", op)
+		}
+	}
+}
+
+func TestGenerateBlocks(t *testing.T) {
+	cfg := Config{
+		NumAccounts:  5,
+		NumContracts: 2,
+		MaxSlots:     6,
+		MinSlots:     3,
+		Distribution: "uniform",
+		Seed:         3,
+		CodeSize:     16,
+		BlockOps:     4,
+	}
+
+	var buf bytes.Buffer
+
+	summary, err := NewGenerator(cfg).Generate(&buf)
+	if err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	lines := strings.Split(strings.TrimSpace(buf.String()), "\n")
+	meter := NewGasMeter(DefaultGasCosts())
+
+	var blocks, inBlock int
+
+	var gas uint64
+
+	for i, line := range lines {
+		var op Operation
+		if err := json.Unmarshal([]byte(line), &op); err != nil {
+			t.Fatalf("line %d: %v", i+1, err)
+		}
+
+		switch op.Op {
+		case "commit_block":
+			if op.ApproxGas == nil {
+				t.Fatalf("line %d: commit_block without approx_gas", i+1)
+			}
+
+			if want := meter.EndBlock(); *op.ApproxGas != want {
+				t.Errorf("block %d approx_gas = %d, want %d", blocks, *op.ApproxGas, want)
+			}
+
+			if inBlock == 0 || inBlock > cfg.BlockOps {
+				t.Errorf("block %d has %d operations", blocks, inBlock)
+			}
+
+			blocks++
+			gas += *op.ApproxGas
+			inBlock = 0
+		case "compute_root":
+			if inBlock != 0 {
+				t.Errorf("%d operations after the last commit_block", inBlock)
+			}
+		default:
+			meter.Add(op)
+			inBlock++
+		}
+	}
+
+	if summary.Blocks != blocks || summary.ApproxGas != gas || summary.TotalOperations != len(lines) {
+		t.Errorf("summary = %+v, want %d blocks, %d gas and %d operations",
+			summary, blocks, gas, len(lines))
+	}
+
+	// Only the last block may be short.
+	stateOps := len(lines) - blocks - 1
+	if want := (stateOps + cfg.BlockOps - 1) / cfg.BlockOps; blocks != want {
+		t.Errorf("%d blocks for %d operations, want %d", blocks, stateOps, want)
+	}
+}
+
+func TestGenerateTenants(t *testing.T) {
+	cfg := Config{
+		NumAccounts:  6,
+		NumContracts: 3,
+		MaxSlots:     4,
+		MinSlots:     1,
+		Distribution: "uniform",
+		Seed:         7,
+		CodeSize:     16,
+		Tenants:      3,
+	}
+
+	gen := NewGenerator(cfg)
+
+	var buf bytes.Buffer
+	if _, err := gen.Generate(&buf); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	perTenant := make(map[int]int)
+	owner := make(map[string]int)
+
+	scanner := bufio.NewScanner(&buf)
+	for scanner.Scan() {
+		var op Operation
+		if err := json.Unmarshal(scanner.Bytes(), &op); err != nil {
+			t.Fatalf("invalid JSON: %v", err)
+		}
+
+		if op.Op == "compute_root" {
+			if op.Tenant != nil {
+				t.Error("compute_root should not be tagged")
+			}
+
+			continue
+		}
+
+		if op.Tenant == nil {
+			t.Fatalf("untagged %s operation", op.Op)
+		}
+
+		tenant, ok := gen.TenantOf(op.Address)
+		if !ok || tenant != *op.Tenant {
+			t.Errorf("address %s tagged %d, range owner %d (%v)", op.Address, *op.Tenant, tenant, ok)
+		}
+
+		if prev, seen := owner[op.Address]; seen && prev != *op.Tenant {
+			t.Errorf("address %s tagged with tenants %d and %d", op.Address, prev, *op.Tenant)
+		}
+
+		owner[op.Address] = *op.Tenant
+		perTenant[*op.Tenant]++
+	}
+
+	if len(perTenant) != cfg.Tenants {
+		t.Errorf("ops spread over %d tenants, want %d", len(perTenant), cfg.Tenants)
+	}
+
+	var untagged bytes.Buffer
+
+	cfg.Tenants = 0
+	if _, err := NewGenerator(cfg).Generate(&untagged); err != nil {
+		t.Fatalf("Generate failed: %v", err)
+	}
+
+	if strings.Contains(untagged.String(), `"tenant"`) {
+		t.Error("untenanted workload should not carry tenant fields")
+	}
+}